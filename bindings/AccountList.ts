@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AccountProfile } from "./AccountProfile";
+
+/**
+ * 账号列表
+ */
+export type AccountList = { 
+/**
+ * 保存的和本机发现的账号
+ */
+accounts: Array<AccountProfile>, 
+/**
+ * 当前选择的配置名称
+ */
+selected: string | null, };
@@ -0,0 +1,26 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 账号配置
+ */
+export type AccountProfile = { 
+/**
+ * 配置名称
+ */
+name: string, 
+/**
+ * 微信ID
+ */
+wxid: string | null, 
+/**
+ * 数据目录
+ */
+data_dir: string, 
+/**
+ * 数据密钥
+ */
+data_key: string | null, 
+/**
+ * 输出目录
+ */
+output_dir: string, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 语音导出格式
+ */
+export type AudioFormat = "silk" | "wav" | "mp3";
@@ -0,0 +1,35 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DbCategory } from "./DbCategory";
+
+/**
+ * 备份请求，未提供的字段依次取账号配置和全局配置
+ */
+export type BackupRequest = { 
+/**
+ * 账号配置名称
+ */
+account?: string, 
+/**
+ * 微信数据目录
+ */
+input?: string, 
+/**
+ * 输出子目录，相对账号配置的输出目录（未指定账号时为工作目录），不能为绝对路径或包含 `..`
+ */
+output?: string, 
+/**
+ * 数据密钥（十六进制）
+ */
+key?: string, 
+/**
+ * 只备份这些类别的数据库
+ */
+only?: Array<DbCategory>, 
+/**
+ * 并发线程数
+ */
+threads?: number, 
+/**
+ * 写入输出目录下以时间命名的快照子目录，完成后按保留策略清理
+ */
+snapshot?: boolean, };
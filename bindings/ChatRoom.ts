@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ChatRoomMember } from "./ChatRoomMember";
+
+/**
+ * 群聊结构
+ */
+export type ChatRoom = { chatroom_name: string, display_name: string | null, member_count: number, 
+/**
+ * 群主
+ */
+owner: string | null, 
+/**
+ * 群成员
+ */
+members: Array<ChatRoomMember>, };
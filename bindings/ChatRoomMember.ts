@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 群成员
+ */
+export type ChatRoomMember = { username: string, 
+/**
+ * 群昵称
+ */
+display_name: string | null, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 联系人结构
+ */
+export type Contact = { username: string, nickname: string | null, remark: string | null, avatar: string | null, 
+/**
+ * 标签名称
+ */
+labels: Array<string>, };
@@ -0,0 +1,22 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 联系人查询参数
+ */
+export type ContactsParams = { 
+/**
+ * 联系人数据库，相对工作目录或会话目录
+ */
+db?: string, 
+/**
+ * 只返回带有该标签的联系人
+ */
+label?: string, 
+/**
+ * 上一页返回的 `next_cursor`
+ */
+cursor?: string, 
+/**
+ * 每页条数
+ */
+limit?: number, };
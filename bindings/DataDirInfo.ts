@@ -0,0 +1,26 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 数据目录信息
+ */
+export type DataDirInfo = { 
+/**
+ * 账号数据目录
+ */
+root: string, 
+/**
+ * 微信ID（不含目录后缀），目录名不符合账号前缀时为 `None`
+ */
+wxid: string | null, 
+/**
+ * 按目录布局推断的大版本（如 `4.x`），无法判断时为 `Unknown`
+ */
+version: string, 
+/**
+ * 数据库目录
+ */
+db_storage_path: string | null, 
+/**
+ * 数据库目录的总大小（字节）
+ */
+size: number, };
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 某天的消息数
+ */
+export type DayCount = { 
+/**
+ * 日期
+ */
+date: string, 
+/**
+ * 消息数
+ */
+count: number, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 数据库类别
+ */
+export type DbCategory = "message" | "contact" | "session" | "emoticon" | "hardlink" | "media" | "favorites" | "sns" | "fts" | "other";
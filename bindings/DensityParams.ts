@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 日历统计参数
+ */
+export type DensityParams = { 
+/**
+ * 时区偏移（分钟）
+ */
+tz?: number, };
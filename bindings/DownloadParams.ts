@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 下载参数
+ */
+export type DownloadParams = { 
+/**
+ * 要下载的会话，任务只导出了一个会话时可省略
+ */
+wxid?: string, };
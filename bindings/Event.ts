@@ -0,0 +1,80 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ExportSummary } from "./ExportSummary";
+import type { JobInfo } from "./JobInfo";
+
+/**
+ * 事件
+ */
+export type Event = { "type": "process_detected", 
+/**
+ * 进程PID
+ */
+pid: number, 
+/**
+ * 微信版本
+ */
+version: string, 
+/**
+ * 账号 wxid
+ */
+wxid: string | null, } | { "type": "key_extracted", 
+/**
+ * 密钥来源进程PID
+ */
+pid: number, 
+/**
+ * 密钥指纹，不包含密钥本身
+ */
+fingerprint: string, } | { "type": "decrypt_progress", 
+/**
+ * 刚完成的数据库
+ */
+file: string, 
+/**
+ * 已处理的数据库数（含失败的）
+ */
+processed: number, 
+/**
+ * 数据库总数
+ */
+total: number, } | { "type": "export_progress", 
+/**
+ * 会话
+ */
+wxid: string, 
+/**
+ * 已处理的消息数
+ */
+processed: number, 
+/**
+ * 会话消息总数
+ */
+total: number, } | { "type": "export_finished", 
+/**
+ * 会话
+ */
+wxid: string, 
+/**
+ * 导出结果
+ */
+summary: ExportSummary, } | { "type": "data_updated", 
+/**
+ * 刷新的数据库，相对数据目录的路径
+ */
+files: Array<string>, 
+/**
+ * 刷新时间
+ */
+at: string, } | { "type": "job_finished", 
+/**
+ * 任务最终信息
+ */
+job: JobInfo, } | { "type": "error", 
+/**
+ * 出错的流程，如 `refresh`
+ */
+source: string, 
+/**
+ * 错误信息
+ */
+message: string, };
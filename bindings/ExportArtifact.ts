@@ -0,0 +1,22 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 一个会话的导出文件
+ */
+export type ExportArtifact = { 
+/**
+ * 会话
+ */
+wxid: string, 
+/**
+ * 导出文件，相对工作目录
+ */
+file: string, 
+/**
+ * 写入的消息数
+ */
+messages: number, 
+/**
+ * 复制的媒体文件数
+ */
+media: number, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 导出格式
+ */
+export type ExportFormat = "json" | "txt" | "html" | "jsonl" | "csv";
@@ -0,0 +1,24 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ExportFormat } from "./ExportFormat";
+import type { JsonValue } from "./serde_json/JsonValue";
+
+/**
+ * 导出格式说明
+ */
+export type ExportFormatInfo = { 
+/**
+ * 格式
+ */
+format: ExportFormat, 
+/**
+ * 显示名称
+ */
+label: string, 
+/**
+ * 文件扩展名
+ */
+extension: string, 
+/**
+ * 导出选项的 JSON Schema
+ */
+options_schema: JsonValue, };
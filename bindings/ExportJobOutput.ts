@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ExportArtifact } from "./ExportArtifact";
+
+/**
+ * 导出任务结果
+ */
+export type ExportJobOutput = { 
+/**
+ * 导出目录，相对工作目录
+ */
+dir: string, 
+/**
+ * 导出文件
+ */
+artifacts: Array<ExportArtifact>, };
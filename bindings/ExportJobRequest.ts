@@ -0,0 +1,32 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AudioFormat } from "./AudioFormat";
+import type { ExportFormat } from "./ExportFormat";
+
+/**
+ * 导出请求
+ */
+export type ExportJobRequest = { 
+/**
+ * 要导出的会话（wxid 或群ID）
+ */
+wxids: Array<string>, 
+/**
+ * 导出格式
+ */
+format?: ExportFormat, 
+/**
+ * 同时复制媒体文件
+ */
+media?: boolean, 
+/**
+ * 以联系人备注或昵称命名导出文件
+ */
+name_by_contact?: boolean, 
+/**
+ * 导出语音消息的音频格式，省略时不导出语音
+ */
+voice_format?: AudioFormat, 
+/**
+ * 当前账号的 wxid
+ */
+self_wxid?: string, };
@@ -0,0 +1,31 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AudioFormat } from "./AudioFormat";
+
+/**
+ * 导出选项
+ */
+export type ExportOptions = { 
+/**
+ * 输出目录
+ */
+output_dir: string, 
+/**
+ * 复制消息引用的图片、视频和文件
+ */
+include_media: boolean, 
+/**
+ * 每次从数据库读取的消息数
+ */
+page_size: number, 
+/**
+ * 以联系人备注或昵称命名导出文件，默认使用 wxid
+ */
+name_by_contact: boolean, 
+/**
+ * 跳过已有有效完成标记的会话，未完成的会话重新导出
+ */
+resume: boolean, 
+/**
+ * 导出语音消息的音频格式，为空时不导出语音
+ */
+voice_format: AudioFormat | null, };
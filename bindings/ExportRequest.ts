@@ -0,0 +1,23 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ExportFormat } from "./ExportFormat";
+
+/**
+ * 会话导出请求
+ */
+export type ExportRequest = { 
+/**
+ * 会话的 wxid 或群ID
+ */
+wxid: string, 
+/**
+ * 导出格式
+ */
+format?: ExportFormat, 
+/**
+ * 同时复制媒体文件
+ */
+media?: boolean, 
+/**
+ * 当前账号的 wxid
+ */
+self_wxid?: string, };
@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 导出结果
+ */
+export type ExportSummary = { 
+/**
+ * 导出文件
+ */
+file: string, 
+/**
+ * 写入的消息数（不含被插件丢弃的）
+ */
+messages: number, 
+/**
+ * 复制的媒体文件数
+ */
+media: number, };
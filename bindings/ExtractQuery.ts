@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 提取参数
+ */
+export type ExtractQuery = { 
+/**
+ * 返回原始密钥
+ */
+reveal?: boolean, };
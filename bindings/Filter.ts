@@ -0,0 +1,20 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FilterOp } from "./FilterOp";
+import type { SqlValue } from "./SqlValue";
+
+/**
+ * 查询条件，多个条件之间为 AND 关系
+ */
+export type Filter = { 
+/**
+ * 列名
+ */
+column: string, 
+/**
+ * 运算符
+ */
+op: FilterOp, 
+/**
+ * 比较值，`is_null`/`is_not_null` 时忽略
+ */
+value: SqlValue, };
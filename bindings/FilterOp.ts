@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 比较运算符
+ */
+export type FilterOp = "eq" | "ne" | "lt" | "le" | "gt" | "ge" | "like" | "is_null" | "is_not_null";
@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TextKind } from "./TextKind";
+
+/**
+ * 已索引的文本
+ */
+export type IndexedText = { 
+/**
+ * 文本来源
+ */
+kind: TextKind, 
+/**
+ * 来源标识，图片为文件路径，语音为 `会话:消息序号`
+ */
+source: string, 
+/**
+ * 提取的文本
+ */
+content: string, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 任务创建响应
+ */
+export type JobCreated = { 
+/**
+ * 任务ID
+ */
+id: string, };
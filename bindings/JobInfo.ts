@@ -0,0 +1,36 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { JobStatus } from "./JobStatus";
+import type { JsonValue } from "./serde_json/JsonValue";
+
+/**
+ * 任务信息
+ */
+export type JobInfo = { 
+/**
+ * 任务ID
+ */
+id: string, 
+/**
+ * 任务类型，如 `backup`
+ */
+kind: string, 
+/**
+ * 当前状态
+ */
+status: JobStatus, 
+/**
+ * 创建时间
+ */
+created_at: string, 
+/**
+ * 结束时间
+ */
+finished_at: string | null, 
+/**
+ * 失败原因
+ */
+error: string | null, 
+/**
+ * 任务结果，如导出统计
+ */
+output?: JsonValue, };
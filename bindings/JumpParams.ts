@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 跳转参数
+ */
+export type JumpParams = { 
+/**
+ * 日期
+ */
+date: string, 
+/**
+ * 时区偏移（分钟）
+ */
+tz?: number, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 跳转目标
+ */
+export type JumpTarget = { 
+/**
+ * 锚点消息的 `seq`，当天及之后没有消息时为空
+ */
+seq: number | null, };
@@ -0,0 +1,26 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 提取结果
+ */
+export type KeyExtracted = { 
+/**
+ * 密钥指纹（SHA-256 前8字节），与备份记录中的指纹一致
+ */
+fingerprint: string, 
+/**
+ * 密钥来源进程PID
+ */
+pid: number, 
+/**
+ * 微信版本
+ */
+version: string, 
+/**
+ * 账号 wxid
+ */
+wxid: string | null, 
+/**
+ * 原始密钥（十六进制），仅 `reveal=true` 时返回
+ */
+key?: string, };
@@ -0,0 +1,27 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { MediaKind } from "./MediaKind";
+
+/**
+ * 会话中的媒体消息
+ */
+export type MediaItem = { 
+/**
+ * 消息的 `sort_seq`，作为下一页的游标
+ */
+seq: number, 
+/**
+ * 发送时间
+ */
+time: string, 
+/**
+ * 发送者
+ */
+sender: string, 
+/**
+ * 媒体类型
+ */
+kind: MediaKind, 
+/**
+ * 媒体 MD5，可经硬链接数据库定位文件
+ */
+md5: string, };
@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { MediaKind } from "./MediaKind";
+
+/**
+ * 媒体列表参数
+ */
+export type MediaListParams = { 
+/**
+ * 媒体类型
+ */
+kind: MediaKind, 
+/**
+ * 上一页返回的 `next_cursor`
+ */
+cursor?: string, 
+/**
+ * 每页条数
+ */
+limit?: number, };
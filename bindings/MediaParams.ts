@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 媒体查询参数
+ */
+export type MediaParams = { 
+/**
+ * 返回缩略图
+ */
+thumb?: boolean, };
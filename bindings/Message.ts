@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 消息结构
+ */
+export type Message = { seq: number, time: string, talker: string, talker_name: string | null, is_chatroom: boolean, sender: string, sender_name: string | null, is_self: boolean, msg_type: number, sub_type: number, content: string, 
+/**
+ * 插件附加的标签
+ */
+tags?: Array<string>, 
+/**
+ * 语音转写文本
+ */
+transcript?: string, };
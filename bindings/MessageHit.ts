@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Message } from "./Message";
+
+/**
+ * 命中的消息
+ */
+export type MessageHit = { 
+/**
+ * 消息
+ */
+message: Message, 
+/**
+ * 命中位置附近的内容片段，截断处以 `…` 表示
+ */
+snippet: string, 
+/**
+ * 片段中命中的字符区间 `[start, end)`，按 Unicode 字符计
+ */
+highlights: Array<[number, number]>, };
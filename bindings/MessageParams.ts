@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 发送消息的参数
+ */
+export type MessageParams = { 
+/**
+ * `endpoint` 事件给出的会话ID
+ */
+sessionId: string, };
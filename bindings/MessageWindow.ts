@@ -0,0 +1,23 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Message } from "./Message";
+
+/**
+ * 锚点附近的消息窗口
+ */
+export type MessageWindow = { 
+/**
+ * 按时间顺序排列的消息
+ */
+messages: Array<Message>, 
+/**
+ * 锚点消息在 `messages` 中的位置，锚点及之后没有消息时为 `None`
+ */
+anchor_index: number | null, 
+/**
+ * 窗口之前是否还有消息
+ */
+has_more_before: boolean, 
+/**
+ * 窗口之后是否还有消息
+ */
+has_more_after: boolean, };
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 排序
+ */
+export type OrderBy = { 
+/**
+ * 列名
+ */
+column: string, 
+/**
+ * 是否降序
+ */
+desc: boolean, };
@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 一页数据
+ */
+export type Page<T> = { 
+/**
+ * 本页数据
+ */
+items: Array<T>, 
+/**
+ * 下一页的游标，没有更多数据时为空
+ */
+next_cursor: string | null, 
+/**
+ * 总条数，统计代价较高的列表不提供
+ */
+total?: number, };
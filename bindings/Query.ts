@@ -0,0 +1,36 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Filter } from "./Filter";
+import type { OrderBy } from "./OrderBy";
+
+/**
+ * SELECT 查询
+ */
+export type Query = { 
+/**
+ * 表名
+ */
+table: string, 
+/**
+ * 查询的列，为空时查询全部列
+ */
+columns: Array<string>, 
+/**
+ * 查询条件
+ */
+filters: Array<Filter>, 
+/**
+ * 排序
+ */
+order_by: Array<OrderBy>, 
+/**
+ * 最多返回的行数
+ */
+limit: number | null, 
+/**
+ * 跳过的行数
+ */
+offset: number | null, 
+/**
+ * 只返回满足条件的行数（`count` 列），忽略查询的列
+ */
+count: boolean, };
@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Query } from "./Query";
+
+/**
+ * 查询请求，`query` 与 `sql` 二选一
+ */
+export type QueryRequest = { 
+/**
+ * 数据库路径，相对工作目录或会话目录
+ */
+db: string, 
+/**
+ * 结构化查询
+ */
+query?: Query, 
+/**
+ * 原始 SQL
+ */
+sql?: string, };
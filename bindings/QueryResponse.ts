@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { JsonValue } from "./serde_json/JsonValue";
+
+/**
+ * 查询结果
+ */
+export type QueryResponse = { 
+/**
+ * 每行一个以列名为键的对象
+ */
+rows: Array<JsonValue>, };
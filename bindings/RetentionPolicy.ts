@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 保留策略
+ */
+export type RetentionPolicy = { 
+/**
+ * 保留最近的快照数
+ */
+keep_last: number | null, 
+/**
+ * 保留最近若干周内每周最新的快照
+ */
+keep_weekly: number | null, };
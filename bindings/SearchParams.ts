@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 搜索参数
+ */
+export type SearchParams = { 
+/**
+ * 搜索文本
+ */
+q: string, 
+/**
+ * 每类最多返回的条数
+ */
+limit?: number, };
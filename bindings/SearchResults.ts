@@ -0,0 +1,25 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Contact } from "./Contact";
+import type { IndexedText } from "./IndexedText";
+import type { MessageHit } from "./MessageHit";
+
+/**
+ * 分组的搜索结果
+ */
+export type SearchResults = { 
+/**
+ * 用户名、昵称或备注匹配的联系人
+ */
+contacts: Array<Contact>, 
+/**
+ * 群名或群 ID 匹配的群聊
+ */
+chatrooms: Array<Contact>, 
+/**
+ * 内容匹配的文本消息，按时间倒序
+ */
+messages: Array<MessageHit>, 
+/**
+ * 媒体识别文本（OCR、语音转写）
+ */
+media: Array<IndexedText>, };
@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 会话结构
+ */
+export type Session = { username: string, last_message_time: string, unread_count: number, 
+/**
+ * 联系人备注或昵称
+ */
+display_name: string | null, 
+/**
+ * 最后一条消息的预览
+ */
+summary: string | null, 
+/**
+ * 群聊中最后一条消息的发送者名称
+ */
+last_sender: string | null, };
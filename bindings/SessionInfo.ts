@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DbCategory } from "./DbCategory";
+
+/**
+ * 会话信息
+ */
+export type SessionInfo = { 
+/**
+ * 解锁时间
+ */
+unlocked_at: string, 
+/**
+ * 已解密的数据库类别，为空表示全部
+ */
+categories: Array<DbCategory>, };
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 会话列表参数
+ */
+export type SessionListParams = { 
+/**
+ * 上一页返回的 `next_cursor`
+ */
+cursor?: string, 
+/**
+ * 每页条数
+ */
+limit?: number, };
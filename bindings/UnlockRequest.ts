@@ -0,0 +1,27 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DbCategory } from "./DbCategory";
+
+/**
+ * 解锁请求，未提供的字段依次取账号配置和全局配置
+ */
+export type UnlockRequest = { 
+/**
+ * 账号配置名称
+ */
+account?: string, 
+/**
+ * 微信数据目录
+ */
+input?: string, 
+/**
+ * 数据密钥（十六进制）
+ */
+key?: string, 
+/**
+ * 只解密这些类别的数据库
+ */
+only?: Array<DbCategory>, 
+/**
+ * 并发线程数
+ */
+threads?: number, };
@@ -0,0 +1,215 @@
+# MWXDump CLI English (US) resources
+
+## Help
+cli-after-help = Examples:
+    mwxdump key                         Extract the WeChat database key
+    mwxdump decrypt -o ./decrypted      Extract the key and decrypt the data directory
+    mwxdump --headless decrypt -o /out  Run headless (configuration from MWX_ variables)
+
+## Common
+log-level = Current log level: { $level }
+context-create-failed = Failed to create execution context: { $error }
+
+## Error hints
+exec-failed = Execution failed: { $error }
+error-cause = Caused by: { $cause }
+hint-process-not-found = Details: no WeChat process found, make sure WeChat is running
+hint-key-extraction-failed = Details: key extraction failed, possible reasons:
+hint-key-permission =   - Insufficient privileges, try running as administrator
+hint-key-unsupported-version =   - Unsupported WeChat version
+hint-key-search-algorithm =   - The memory search pattern needs updating
+hint-permission-denied = Details: insufficient privileges, try running as administrator
+hint-cancelled = Finished files were kept and unfinished outputs removed; rerun the same command to continue
+ctrl-c-received = ⏹️  Interrupt received, waiting for in-flight writes to finish... (press Ctrl-C again to force quit)
+ctrl-c-forced = Forced exit, .partial temporary files may be left behind
+ctrl-c-interrupted = ⏹️  Interrupt received, exiting
+
+## key command
+key-start = Starting WeChat key extraction...
+key-preset-detected = Preset key found in config file: { $prefix }...
+key-using-data-dir = Using configured WeChat data directory: { $dir }
+key-no-process = ❌ No supported WeChat main process found
+key-no-process-checklist = Please make sure that:
+key-check-running =    - WeChat is running
+key-check-version =    - The WeChat version supports key extraction
+key-check-permission =    - This program may read process information
+key-strategy-succeeded = ✅ Key strategy "{ $strategy }" succeeded
+key-strategy-failed = ⚠️  Key strategy "{ $strategy }" failed: { $error }
+key-strategy-module-relative = module-relative search
+key-strategy-heap-pattern-scan = heap pattern scan
+key-strategy-config-offsets = configured offsets
+key-from-dump = 🔍 Searching memory snapshot { $file } for keys, validating against databases in { $dir }
+key-accounts-found = 🔑 Extracted keys for { $count } accounts:
+key-column-account = Account
+key-column-key = Key
+key-candidates-found = 🔑 Found { $count } candidate keys (ranked by decryptable databases):
+key-candidate = { $index }. { $key } (PID: { $pid }, decrypts { $count } databases)
+key-validate-dir = 🔍 Validating the key against databases in { $dir }:
+key-validate-ok =    ✅ { $category }: { $path } ({ $version })
+key-validate-failed =    ❌ { $category }: { $path }
+key-validate-summary = The key decrypts databases in { $valid }/{ $total } categories
+key-validate-none = ⚠️  The key cannot decrypt any database in this directory; it may belong to another account
+
+## process command
+process-none = ✅ Process detection works, but no running WeChat process was found
+process-found = ✅ Found { $count } WeChat process(es):
+process-name = { $index }. Name: { $name }
+process-pid = PID: { $pid }
+process-is-main = Main process: { $value }
+process-path = Path: { $path }
+process-version = Version: { $version }
+process-data-dir = Data directory: { $dir }
+process-data-dir-missing = Data directory: not found
+process-wxid = WeChat ID: { $wxid }
+process-wxid-missing = not found
+process-detected-at = Detected at: { $time }
+process-done = Process detection finished!
+
+## info command
+info-root = Account directory: { $dir }
+info-wxid = WeChat ID: { $wxid }
+info-version = Layout version: { $version }
+info-db-storage = Database directory: { $dir }
+info-db-storage-missing = Database directory: not found
+info-size = Database size: { $size }
+info-categories = Databases by category:
+info-category = { $category }: { $count } file(s), { $size }
+
+## setup command
+setup-requires-terminal = The setup wizard needs an interactive terminal; in headless mode configure via MWX_ variables instead
+setup-welcome = Welcome to the MWXDump setup wizard. It will walk you through creating a config file.
+setup-step-detect = [1/5] Detecting WeChat processes
+setup-step-privilege = [2/5] Checking privileges
+setup-step-key = [3/5] Obtaining the data key
+setup-step-paths = [4/5] Choosing data and output directories
+setup-step-write = [5/5] Writing the config file
+setup-no-process = ⚠️  No running WeChat process found
+setup-confirm-manual = Continue by entering the data directory and key manually?
+setup-process-found = ✅ Found WeChat main process (PID { $pid })
+setup-select-process = Several WeChat processes were found, pick the account to configure
+setup-privilege-ok = ✅ Privilege check passed
+setup-privilege-missing = ⚠️  Not running as administrator, key extraction may fail
+setup-confirm-continue = Continue anyway?
+setup-key-ok = ✅ Key extracted
+setup-key-failed = ❌ Key extraction failed: { $error }
+setup-prompt-key = Enter the 64-character hex key (leave empty to skip)
+setup-invalid-key = The key must be 64 hexadecimal characters
+setup-select-account = Several WeChat accounts were found, pick the one to configure
+setup-account-not-running = ⚠️  The selected account is not the one currently logged in; the extracted key may not decrypt its data
+setup-prompt-data-dir = WeChat data directory
+setup-invalid-dir = Directory does not exist
+setup-prompt-output-dir = Decryption output directory
+setup-prompt-profile-name = Account profile name
+setup-confirm-merge = Config file { $path } already exists, merge the account profile into it?
+setup-cancelled = Cancelled, nothing was written
+setup-done = ✅ Configuration written to { $path }; run other commands with --config { $path }
+
+## server command
+server-listening = 🌐 HTTP server listening on http://{ $addr }; press Ctrl-C to stop
+server-auto-decrypt = 🔓 Automatic decryption started, job { $id }
+
+## mcp command
+mcp-sse-listening = 🤖 MCP server listening on http://{ $addr }/sse; press Ctrl-C to stop
+
+## sql command
+sql-row-count = ({ $count } rows)
+
+## contacts command
+contacts-count = ({ $count } contacts)
+
+## ocr command
+ocr-summary = OCR finished: { $indexed } indexed, { $skipped } skipped, { $failed } failed
+ocr-hit-count = ({ $count } results)
+
+## decrypt command
+decrypt-summary = Decrypted { $succeeded }/{ $files } files, { $failed } failed, peak memory { $peak } MB (limit { $max } MB)
+decrypt-unchanged = Skipped { $unchanged } unchanged files
+decrypt-started = 🔓 Starting decryption
+decrypt-progress = Decrypting
+decrypt-key-ready = ✅ Key ready: { $bytes } bytes
+decrypt-input-path = 📁 Input path: { $path }
+decrypt-pc-backup = 📦 Input is a WeChat backup; unpacking into the work directory
+decrypt-pc-backup-valid = ✅ Key is valid, backup database version: { $version }
+decrypt-ios-backup = 📱 Input is an iOS backup; importing into the work directory
+decrypt-ios-account = ✅ Found WeChat account { $account }
+decrypt-android-db = 🤖 Input is an Android WeChat database; importing into the work directory
+decrypt-android-valid = ✅ Password is valid, database cipher: SQLCipher { $profile }
+decrypt-snapshot-encrypted = 🔒 Encrypted { $files } files in the snapshot ({ $bytes } bytes)
+decrypt-manifest-signed = ✍️  Signed the snapshot manifest ({ $files } files, key fingerprint { $fingerprint })
+decrypt-record-failed = Failed to collect backup statistics: { $error }
+decrypt-snapshots-pruned = 🧹 Pruned { $pruned } expired snapshots, kept { $kept }
+decrypt-key-from-args = 🔑 Using the key given on the command line
+decrypt-key-from-config = 🔑 Using the preset key from the config file
+decrypt-key-extracting = 🔑 Extracting the key from the WeChat process...
+decrypt-target-process = 🎯 Target process: { $name } (PID: { $pid })
+decrypt-key-extracted = 🎉 Key extracted
+decrypt-input-from-args = 📂 Using the input path given on the command line
+decrypt-input-from-config = 📂 Using the data directory from the config file
+decrypt-input-detecting = 📂 Detecting the WeChat data directory...
+decrypt-input-detected = 🎉 Detected data directory: { $path }
+decrypt-account-info = 📂 Account: { $wxid }, database directory: { $db_dir }, size: { $size } bytes
+decrypt-unknown = unknown
+
+## dump-memory command
+dump-memory-done = Saved { $regions } memory regions ({ $size } MB) to { $path }
+
+## devtool command
+devtool-key-locations = Key found { $count } times in the snapshot:
+devtool-pointer = Depth { $depth } pointer at { $location } -> { $target } (field offset { $offset }):
+devtool-candidate-pattern = Candidate signature: pattern = "{ $pattern }", pointer_offset = { $offset }
+devtool-schema-written = Wrote { $count } JSON Schemas to { $dir }
+
+## config command
+config-valid = Configuration is valid: { $source }
+
+## export command
+export-summary = Exported { $messages } messages and { $media } media files to { $file }
+
+## backups command
+backups-count = ({ $count } backups)
+backups-no-policy = No retention policy set; add [backup.retention] to the config or pass --keep-last / --keep-weekly
+backups-prune-dry-run = Would prune { $pruned } snapshots and keep { $kept } (dry run)
+backups-pruned = Pruned { $pruned } snapshots, kept { $kept }
+backups-not-encrypted = { $snapshot } is not an encrypted snapshot
+backups-restored = Decrypted { $files } files to { $output }
+master-prompt = Master key passphrase
+master-prompt-new = New master key passphrase
+master-prompt-confirm = Repeat passphrase
+master-prompt-mismatch = Passphrases do not match
+master-created = Master key created: { $file } (fingerprint { $fingerprint }). Losing the passphrase makes encrypted snapshots unrecoverable
+master-passwd-changed = Master key passphrase changed; encrypted snapshots are unaffected
+master-missing = No master key under { $dir }; create one with keys master init
+master-status = Master key { $fingerprint } ({ $iterations } PBKDF2 iterations), { $encrypted } of { $snapshots } snapshots encrypted
+master-signing-key = Manifest signing public key { $public_key } (fingerprint { $fingerprint }); keep it elsewhere for verify --public-key
+verify-unsigned = Snapshot manifest is not signed
+verify-signature-valid = Signature valid, public key fingerprint { $fingerprint }
+verify-signature-untrusted = Signature only matches its embedded public key { $fingerprint } and no trusted key is available; pass --public-key to verify the signer
+verify-signature-invalid = Signature invalid: { $reason }
+verify-summary = { $files } files in manifest: { $missing } missing, { $modified } modified, { $extra } extra
+verify-failed = Snapshot { $snapshot } failed verification
+master-share-hint = Archive key for { $snapshot }; it decrypts only this snapshot (backups restore --archive-key)
+
+## media command
+media-decode-summary = Decoded { $decoded }/{ $files } images, skipped { $skipped } existing, { $failed } failed
+media-voices-summary = Exported { $converted }/{ $voices } voice messages, skipped { $skipped } existing, { $failed } failed
+
+## merge command
+merge-summary = Merged { $sources } sources: { $conversations } conversations, { $messages } messages, removed { $duplicates } duplicates, written to { $output }
+
+## service command
+service-installed = Scheduled backup registered: { $command }
+service-uninstalled = Scheduled backup { $name } removed
+service-cron-hint = Automatic registration is not supported on this platform; add this entry to crontab: { $line }
+service-unsupported = Scheduled backups are not supported on this platform ({ $os })
+service-config-required = Scheduled backups need a config file; run `mwxdump setup` first or pass --config
+service-config-missing = Scheduled backups run unattended and need { $key } in the config file; run `mwxdump setup` to create it
+service-data-dir-not-found = The configured wechat.data_dir does not exist or is not a directory: { $path }
+
+## desktop notifications
+notify-backup-succeeded = Backup finished
+notify-backup-succeeded-body = { $databases } databases, { $messages } messages in { $duration }
+notify-backup-failed = Backup failed
+notify-export-succeeded = Export finished: { $wxid }
+notify-export-succeeded-body = { $messages } messages, { $media } media files in { $duration }
+notify-export-failed = Export failed: { $wxid }
+notify-failed-body = { $error } (after { $duration })
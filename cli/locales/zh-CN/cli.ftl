@@ -0,0 +1,215 @@
+# MWXDump CLI 简体中文资源
+
+## 帮助信息
+cli-after-help = 示例:
+    mwxdump key                         提取微信数据库密钥
+    mwxdump decrypt -o ./decrypted      自动提取密钥并解密数据目录
+    mwxdump --headless decrypt -o /out  无头模式运行（配置来自 MWX_ 环境变量）
+
+## 通用
+log-level = 当前日志级别: { $level }
+context-create-failed = 创建执行上下文失败: { $error }
+
+## 错误提示
+exec-failed = 执行失败: { $error }
+error-cause = 错误原因: { $cause }
+hint-process-not-found = 详细信息: 未找到微信进程，请确保微信正在运行
+hint-key-extraction-failed = 详细信息: 密钥提取失败，可能原因:
+hint-key-permission =   - 权限不足，请尝试以管理员身份运行
+hint-key-unsupported-version =   - 微信版本不受支持
+hint-key-search-algorithm =   - 内存搜索算法需要优化
+hint-permission-denied = 详细信息: 权限不足，请尝试以管理员身份运行
+hint-cancelled = 已完成的文件已保留，未完成的输出已清理，重新运行同一命令即可继续
+ctrl-c-received = ⏹️  收到中断信号，正在等待进行中的写入完成...（再次按 Ctrl-C 强制退出）
+ctrl-c-forced = 强制退出，可能残留 .partial 临时文件
+ctrl-c-interrupted = ⏹️  收到中断信号，已退出
+
+## key 命令
+key-start = 开始微信密钥提取...
+key-preset-detected = 检测到配置文件中的预设密钥: { $prefix }...
+key-using-data-dir = 使用配置的微信数据目录: { $dir }
+key-no-process = ❌ 未发现有效版本的微信主进程
+key-no-process-checklist = 请确保：
+key-check-running =    - 微信正在运行
+key-check-version =    - 微信版本支持密钥提取
+key-check-permission =    - 程序有足够权限访问进程信息
+key-strategy-succeeded = ✅ 密钥提取策略「{ $strategy }」成功
+key-strategy-failed = ⚠️  密钥提取策略「{ $strategy }」失败: { $error }
+key-strategy-module-relative = 模块相对搜索
+key-strategy-heap-pattern-scan = 堆内存特征扫描
+key-strategy-config-offsets = 配置偏移
+key-from-dump = 🔍 在内存快照 { $file } 中搜索密钥，使用 { $dir } 中的数据库校验
+key-accounts-found = 🔑 提取到 { $count } 个账号的密钥:
+key-column-account = 账号
+key-column-key = 密钥
+key-candidates-found = 🔑 找到 { $count } 个候选密钥（按可解密的数据库数量排序）:
+key-candidate = { $index }. { $key }（PID: { $pid }，可解密 { $count } 个数据库）
+key-validate-dir = 🔍 使用 { $dir } 中的数据库验证密钥:
+key-validate-ok =    ✅ { $category }: { $path }（{ $version }）
+key-validate-failed =    ❌ { $category }: { $path }
+key-validate-summary = 密钥可解密 { $valid }/{ $total } 个类别的数据库
+key-validate-none = ⚠️  密钥无法解密该目录中的任何数据库，可能属于其他账号
+
+## process 命令
+process-none = ✅ 进程检测功能正常，但未发现运行中的微信进程
+process-found = ✅ 检测到 { $count } 个微信进程:
+process-name = { $index }. 进程名: { $name }
+process-pid = PID: { $pid }
+process-is-main = 是否主进程: { $value }
+process-path = 路径: { $path }
+process-version = 版本: { $version }
+process-data-dir = 数据目录: { $dir }
+process-data-dir-missing = 数据目录: 未找到
+process-wxid = 微信ID: { $wxid }
+process-wxid-missing = 未找到
+process-detected-at = 检测时间: { $time }
+process-done = 进程检测测试完成！
+
+## info command
+info-root = 账号目录: { $dir }
+info-wxid = 微信ID: { $wxid }
+info-version = 目录版本: { $version }
+info-db-storage = 数据库目录: { $dir }
+info-db-storage-missing = 数据库目录: 未找到
+info-size = 数据库大小: { $size }
+info-categories = 数据库分类:
+info-category = { $category }: { $count } 个, { $size }
+
+## setup command
+setup-requires-terminal = 配置向导需要交互式终端，无头模式下请改用 MWX_ 环境变量配置
+setup-welcome = 欢迎使用 MWXDump 配置向导，接下来将逐步生成配置文件。
+setup-step-detect = [1/5] 检测微信进程
+setup-step-privilege = [2/5] 检查运行权限
+setup-step-key = [3/5] 获取数据密钥
+setup-step-paths = [4/5] 选择数据与输出目录
+setup-step-write = [5/5] 写入配置文件
+setup-no-process = ⚠️  未发现运行中的微信进程
+setup-confirm-manual = 是否手动填写数据目录和密钥继续配置？
+setup-process-found = ✅ 发现微信主进程 (PID { $pid })
+setup-select-process = 检测到多个微信进程，请选择要配置的账号
+setup-privilege-ok = ✅ 权限检查通过
+setup-privilege-missing = ⚠️  当前未以管理员身份运行，密钥提取可能失败
+setup-confirm-continue = 是否仍然继续？
+setup-key-ok = ✅ 密钥提取成功
+setup-key-failed = ❌ 密钥提取失败: { $error }
+setup-prompt-key = 请输入64位十六进制密钥（留空跳过）
+setup-invalid-key = 密钥必须为64个十六进制字符
+setup-select-account = 发现多个微信账号，请选择要配置的账号
+setup-account-not-running = ⚠️  所选账号不是当前登录的账号，提取的密钥可能无法解密该账号的数据
+setup-prompt-data-dir = 微信数据目录
+setup-invalid-dir = 目录不存在
+setup-prompt-output-dir = 解密输出目录
+setup-prompt-profile-name = 账号配置名称
+setup-confirm-merge = 配置文件 { $path } 已存在，是否将账号配置合并写入？
+setup-cancelled = 已取消，未写入任何文件
+setup-done = ✅ 配置已写入 { $path }，之后可使用 --config { $path } 运行其他命令
+
+## server command
+server-listening = 🌐 HTTP 服务已启动: http://{ $addr }，按 Ctrl-C 停止
+server-auto-decrypt = 🔓 已启动自动解密，任务 { $id }
+
+## mcp command
+mcp-sse-listening = 🤖 MCP 服务已启动: http://{ $addr }/sse，按 Ctrl-C 停止
+
+## sql command
+sql-row-count = （{ $count } 行）
+
+## contacts command
+contacts-count = （共 { $count } 个联系人）
+
+## ocr command
+ocr-summary = 识别完成：写入索引 { $indexed } 张，跳过 { $skipped } 张，失败 { $failed } 张
+ocr-hit-count = （{ $count } 条结果）
+
+## decrypt command
+decrypt-summary = 已解密 { $succeeded }/{ $files } 个文件，失败 { $failed } 个，内存峰值 { $peak } MB（上限 { $max } MB）
+decrypt-unchanged = 跳过 { $unchanged } 个未变化的文件
+decrypt-started = 🔓 开始执行解密
+decrypt-progress = 解密中
+decrypt-key-ready = ✅ 密钥获取成功: { $bytes } 字节
+decrypt-input-path = 📁 输入路径: { $path }
+decrypt-pc-backup = 📦 输入目录为微信备份，解包到工作目录
+decrypt-pc-backup-valid = ✅ 密钥有效，备份数据库版本: { $version }
+decrypt-ios-backup = 📱 输入目录为 iOS 备份，导入到工作目录
+decrypt-ios-account = ✅ 找到微信账号 { $account }
+decrypt-android-db = 🤖 输入为安卓微信数据库，导入到工作目录
+decrypt-android-valid = ✅ 口令有效，数据库加密参数: SQLCipher { $profile }
+decrypt-snapshot-encrypted = 🔒 已加密快照中的 { $files } 个文件（{ $bytes } 字节）
+decrypt-manifest-signed = ✍️  已签名快照清单（{ $files } 个文件，公钥指纹 { $fingerprint }）
+decrypt-record-failed = 统计备份信息失败: { $error }
+decrypt-snapshots-pruned = 🧹 已清理 { $pruned } 个过期快照，保留 { $kept } 个
+decrypt-key-from-args = 🔑 使用用户提供的密钥
+decrypt-key-from-config = 🔑 使用配置文件中的预设密钥
+decrypt-key-extracting = 🔑 自动从微信进程提取密钥...
+decrypt-target-process = 🎯 目标进程: { $name } (PID: { $pid })
+decrypt-key-extracted = 🎉 自动提取密钥成功
+decrypt-input-from-args = 📂 使用用户提供的输入路径
+decrypt-input-from-config = 📂 使用配置文件中的数据目录
+decrypt-input-detecting = 📂 自动检测微信数据目录...
+decrypt-input-detected = 🎉 自动检测到数据目录: { $path }
+decrypt-account-info = 📂 账号: { $wxid }，数据库目录: { $db_dir }，大小: { $size } 字节
+decrypt-unknown = 未知
+
+## dump-memory command
+dump-memory-done = 已保存 { $regions } 个内存区域（{ $size } MB）到 { $path }
+
+## devtool command
+devtool-key-locations = 密钥在快照中出现 { $count } 次：
+devtool-pointer = 第 { $depth } 层指针 { $location } -> { $target }（字段偏移 { $offset }）：
+devtool-candidate-pattern = 候选特征：pattern = "{ $pattern }"，pointer_offset = { $offset }
+devtool-schema-written = 已写入 { $count } 个 JSON Schema 到 { $dir }
+
+## config command
+config-valid = 配置有效：{ $source }
+
+## export command
+export-summary = 已导出 { $messages } 条消息、{ $media } 个媒体文件到 { $file }
+
+## backups command
+backups-count = （共 { $count } 次备份）
+backups-no-policy = 未设置保留策略，请在配置中添加 [backup.retention] 或使用 --keep-last / --keep-weekly
+backups-prune-dry-run = 将删除 { $pruned } 个快照，保留 { $kept } 个（未实际删除）
+backups-pruned = 已删除 { $pruned } 个快照，保留 { $kept } 个
+backups-not-encrypted = { $snapshot } 不是加密的快照
+backups-restored = 已解密 { $files } 个文件到 { $output }
+master-prompt = 主密钥口令
+master-prompt-new = 新的主密钥口令
+master-prompt-confirm = 再次输入口令
+master-prompt-mismatch = 两次输入的口令不一致
+master-created = 已创建主密钥: { $file }（指纹 { $fingerprint }），遗失口令将无法解密已加密的快照
+master-passwd-changed = 已修改主密钥口令，已加密的快照不受影响
+master-missing = { $dir } 下没有主密钥，请先执行 keys master init
+master-status = 主密钥 { $fingerprint }（PBKDF2 迭代 { $iterations } 次），{ $snapshots } 个快照中 { $encrypted } 个已加密
+master-signing-key = 清单签名公钥 { $public_key }（指纹 { $fingerprint }），请另行保存，供 verify --public-key 使用
+verify-unsigned = 快照清单未签名
+verify-signature-valid = 签名有效，公钥指纹 { $fingerprint }
+verify-signature-untrusted = 签名只与签名文件自带的公钥 { $fingerprint } 相符，没有可信任的公钥，校验不通过；请用 --public-key 指定签名者的公钥
+verify-signature-invalid = 签名无效: { $reason }
+verify-summary = 清单中 { $files } 个文件：缺失 { $missing } 个，被修改 { $modified } 个，多出 { $extra } 个
+verify-failed = 快照 { $snapshot } 校验失败
+master-share-hint = { $snapshot } 的归档密钥，只能解密该快照（backups restore --archive-key）
+
+## media command
+media-decode-summary = 已解码 { $decoded }/{ $files } 个图片，跳过 { $skipped } 个已存在的，{ $failed } 个失败
+media-voices-summary = 已导出 { $converted }/{ $voices } 条语音，跳过 { $skipped } 条已存在的，{ $failed } 条失败
+
+## merge command
+merge-summary = 已合并 { $sources } 个来源：{ $conversations } 个会话、{ $messages } 条消息，去掉 { $duplicates } 条重复消息，输出到 { $output }
+
+## service command
+service-installed = 已注册定时备份：{ $command }
+service-uninstalled = 已删除定时备份 { $name }
+service-cron-hint = 当前平台不支持自动注册，可将以下条目添加到 crontab：{ $line }
+service-unsupported = 当前平台 ({ $os }) 不支持定时备份服务
+service-config-required = 定时备份需要配置文件，请先运行 `mwxdump setup` 或通过 --config 指定
+service-config-missing = 定时备份无人值守运行，需要在配置文件中设置 { $key }，可运行 `mwxdump setup` 生成
+service-data-dir-not-found = 配置的 wechat.data_dir 不存在或不是目录: { $path }
+
+## desktop notifications
+notify-backup-succeeded = 备份完成
+notify-backup-succeeded-body = { $databases } 个数据库，{ $messages } 条消息，用时 { $duration }
+notify-backup-failed = 备份失败
+notify-export-succeeded = 导出完成：{ $wxid }
+notify-export-succeeded-body = { $messages } 条消息，{ $media } 个媒体文件，用时 { $duration }
+notify-export-failed = 导出失败：{ $wxid }
+notify-failed-body = { $error }（用时 { $duration }）
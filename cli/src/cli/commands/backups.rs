@@ -0,0 +1,180 @@
+//! 备份快照管理命令
+//!
+//! - `backups list <DIR>`：显示备份根目录 `backups.json` 中记录的历史备份
+//! - `backups prune <DIR>`：按配置中的保留策略清理快照，`--dry-run` 时只列出将删除的快照
+//! - `backups restore <SNAPSHOT>`：解密加密的快照，使用主密钥口令或 `keys master share` 输出的归档密钥
+
+use clap::{Args, Subcommand};
+use serde_json::Value;
+use std::path::PathBuf;
+
+use super::master;
+use super::sql::{render_csv, render_table, OutputFormat};
+use crate::cli::context::ExecutionContext;
+use crate::i18n::{tr, tr_args};
+use mwxdump_core::backup::vault::{self, SecretKey};
+use mwxdump_core::backup::{BackupCatalog, BackupRecord, RetentionPolicy};
+use mwxdump_core::errors::{Result, WeChatError};
+use mwxdump_core::wechat::db::QueryRows;
+
+/// 快照管理参数
+#[derive(Args, Debug)]
+pub struct BackupsArgs {
+    #[command(subcommand)]
+    pub command: BackupsCommand,
+}
+
+/// 快照管理子命令
+#[derive(Subcommand, Debug)]
+pub enum BackupsCommand {
+    /// 显示历史备份
+    List {
+        /// 备份根目录
+        #[arg(value_name = "DIR")]
+        dir: PathBuf,
+
+        /// 输出格式
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+
+    /// 按保留策略清理旧快照
+    Prune {
+        /// 备份根目录（decrypt --snapshot 的输出目录）
+        #[arg(value_name = "DIR")]
+        dir: PathBuf,
+
+        /// 只显示将删除的快照，不实际删除
+        #[arg(long)]
+        dry_run: bool,
+
+        /// 保留最近的快照数（覆盖配置）
+        #[arg(long)]
+        keep_last: Option<usize>,
+
+        /// 保留最近若干周内每周最新的快照（覆盖配置）
+        #[arg(long)]
+        keep_weekly: Option<usize>,
+    },
+
+    /// 解密加密的快照
+    Restore {
+        /// 加密的快照目录
+        #[arg(value_name = "SNAPSHOT")]
+        snapshot: PathBuf,
+
+        /// 解密输出目录
+        #[arg(short, long, value_name = "DIR")]
+        output: PathBuf,
+
+        /// 快照的归档密钥（keys master share 的输出），未指定时用备份根目录的主密钥
+        #[arg(long, value_name = "HEX")]
+        archive_key: Option<String>,
+    },
+}
+
+/// 执行快照管理命令
+pub async fn execute(context: &ExecutionContext, args: BackupsArgs) -> Result<()> {
+    match args.command {
+        BackupsCommand::List { dir, format } => {
+            let catalog = BackupCatalog::load(&dir)?;
+            match format {
+                OutputFormat::Table => {
+                    print!("{}", render_table(&to_rows(&catalog.backups)));
+                    println!("{}", tr_args("backups-count", &[("count", catalog.backups.len().to_string())]));
+                }
+                OutputFormat::Csv => print!("{}", render_csv(&to_rows(&catalog.backups))),
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&catalog.backups)?),
+            }
+        }
+        BackupsCommand::Prune {
+            dir,
+            dry_run,
+            keep_last,
+            keep_weekly,
+        } => {
+            let configured = &context.config().backup.retention;
+            let policy = RetentionPolicy {
+                keep_last: keep_last.or(configured.keep_last),
+                keep_weekly: keep_weekly.or(configured.keep_weekly),
+            };
+            if !policy.is_enabled() {
+                println!("{}", tr("backups-no-policy"));
+                return Ok(());
+            }
+
+            let plan = policy.enforce(&dir, dry_run)?;
+            for snapshot in &plan.prune {
+                println!("- {}", snapshot.path.display());
+            }
+            let key = if dry_run { "backups-prune-dry-run" } else { "backups-pruned" };
+            println!(
+                "{}",
+                tr_args(
+                    key,
+                    &[
+                        ("pruned", plan.prune.len().to_string()),
+                        ("kept", plan.keep.len().to_string()),
+                    ],
+                )
+            );
+        }
+        BackupsCommand::Restore {
+            snapshot,
+            output,
+            archive_key,
+        } => {
+            if !vault::is_encrypted(&snapshot) {
+                return Err(WeChatError::DecryptionFailed(tr_args(
+                    "backups-not-encrypted",
+                    &[("snapshot", snapshot.display().to_string())],
+                ))
+                .into());
+            }
+            let key = match archive_key {
+                Some(hex) => SecretKey::from_hex(&hex)?,
+                None => {
+                    let master = master::unlock(context, &master::snapshot_root(&snapshot))?;
+                    vault::archive_key(&snapshot, &master)?
+                }
+            };
+            let report = vault::decrypt_archive(&snapshot, &output, &key)?;
+            println!(
+                "{}",
+                tr_args(
+                    "backups-restored",
+                    &[
+                        ("files", report.files.to_string()),
+                        ("output", output.display().to_string()),
+                    ],
+                )
+            );
+        }
+    }
+    Ok(())
+}
+
+/// 转换为表格行，时间显示为本地时间
+fn to_rows(backups: &[BackupRecord]) -> QueryRows {
+    QueryRows {
+        columns: ["name", "created_at", "wxid", "size", "databases", "messages", "key", "encrypted", "signed"]
+            .map(String::from)
+            .to_vec(),
+        rows: backups
+            .iter()
+            .map(|b| {
+                vec![
+                    Value::from(b.name.clone()),
+                    Value::from(b.created_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string()),
+                    b.wxid.clone().map_or(Value::Null, Value::from),
+                    Value::from(b.size),
+                    Value::from(b.databases),
+                    b.messages.map_or(Value::Null, Value::from),
+                    Value::from(b.key_fingerprint.clone()),
+                    Value::from(b.encrypted),
+                    Value::from(b.signed),
+                ]
+            })
+            .collect(),
+    }
+}
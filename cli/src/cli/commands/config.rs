@@ -0,0 +1,61 @@
+//! 配置文件命令
+//!
+//! `config validate` 一次列出配置文件中的所有问题（包括拼写错误的未知配置项），便于修改后逐项核对。
+
+use clap::{Args, Subcommand};
+use std::path::PathBuf;
+
+use crate::cli::context::ExecutionContext;
+use crate::config::{migration, unknown_key_issues, AppConfig};
+use crate::i18n::tr_args;
+use mwxdump_core::errors::{ConfigError, Result};
+
+/// 配置参数
+#[derive(Args, Debug)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub command: ConfigCommand,
+}
+
+/// 配置子命令
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// 检查配置文件，列出所有无效的配置项
+    Validate {
+        /// 配置文件路径，默认为 --config 指定或自动找到的 config.toml
+        file: Option<PathBuf>,
+    },
+}
+
+/// 执行配置命令
+pub async fn execute(context: &ExecutionContext, args: ConfigArgs) -> Result<()> {
+    match args.command {
+        ConfigCommand::Validate { file } => validate(context, file),
+    }
+}
+
+fn validate(context: &ExecutionContext, file: Option<PathBuf>) -> Result<()> {
+    let path = file.or_else(|| context.config_file().map(|p| p.to_path_buf()));
+
+    // 没有配置文件时检查当前生效的配置（如无头模式下来自环境变量的配置）
+    let (source, issues) = match &path {
+        Some(path) => {
+            let content = std::fs::read_to_string(path).map_err(|_| ConfigError::FileNotFound {
+                path: path.display().to_string(),
+            })?;
+            // 按迁移后的内容检查，但不改写文件
+            let content = migration::migrate(&content)?.map_or(content, |(_, upgraded)| upgraded);
+            let (config, unknown_keys) = AppConfig::parse(&content)?;
+            let mut issues = unknown_key_issues(&unknown_keys);
+            issues.extend(config.issues());
+            (path.display().to_string(), issues)
+        }
+        None => ("MWX_*".to_string(), context.config().issues()),
+    };
+
+    if !issues.is_empty() {
+        return Err(ConfigError::Invalid(issues).into());
+    }
+    println!("{}", tr_args("config-valid", &[("source", source)]));
+    Ok(())
+}
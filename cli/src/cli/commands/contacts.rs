@@ -0,0 +1,72 @@
+//! 联系人命令
+//!
+//! 列出解密后的联系人及其标签，可按标签筛选并导出为 CSV/JSON。
+
+use clap::Args;
+use serde_json::Value;
+use std::path::PathBuf;
+
+use super::sql::{render_csv, render_table, OutputFormat};
+use crate::cli::context::ExecutionContext;
+use crate::i18n::tr_args;
+use mwxdump_core::errors::Result;
+use mwxdump_core::models::Contact;
+use mwxdump_core::wechat::db::contacts::{self, CONTACT_DB_PATH};
+use mwxdump_core::wechat::db::{QueryRows, SqliteDataSource};
+
+/// 联系人参数
+#[derive(Args, Debug)]
+pub struct ContactsArgs {
+    /// 解密后的联系人数据库（默认为工作目录下的 db_storage/contact/decrypted_contact.db）
+    #[arg(long, value_name = "FILE")]
+    pub db: Option<PathBuf>,
+
+    /// 只显示带有该标签的联系人
+    #[arg(long)]
+    pub label: Option<String>,
+
+    /// 输出格式
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Table)]
+    pub format: OutputFormat,
+}
+
+/// 列出联系人
+pub async fn execute(context: &ExecutionContext, args: ContactsArgs) -> Result<()> {
+    let db = args
+        .db
+        .unwrap_or_else(|| context.database_config().work_dir.join(CONTACT_DB_PATH));
+    let source = SqliteDataSource::open(&db).await?;
+    let mut list = contacts::load_contacts(&source).await?;
+    if let Some(label) = &args.label {
+        list = contacts::filter_by_label(list, label);
+    }
+
+    match args.format {
+        OutputFormat::Table => {
+            print!("{}", render_table(&to_rows(&list)));
+            println!("{}", tr_args("contacts-count", &[("count", list.len().to_string())]));
+        }
+        OutputFormat::Csv => print!("{}", render_csv(&to_rows(&list))),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&list)?),
+    }
+    Ok(())
+}
+
+/// 转换为表格行，多个标签以逗号分隔
+fn to_rows(list: &[Contact]) -> QueryRows {
+    let text = |value: &Option<String>| value.clone().map_or(Value::Null, Value::from);
+    QueryRows {
+        columns: ["username", "nickname", "remark", "labels"].map(String::from).to_vec(),
+        rows: list
+            .iter()
+            .map(|c| {
+                vec![
+                    Value::from(c.username.clone()),
+                    text(&c.nickname),
+                    text(&c.remark),
+                    Value::from(c.labels.join(",")),
+                ]
+            })
+            .collect(),
+    }
+}
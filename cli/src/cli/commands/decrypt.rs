@@ -1,15 +1,61 @@
 //! 解密功能的命令
 
 use anyhow::Context;
-use clap::Args;
-use std::path::PathBuf;
-use tracing::info;
+use clap::{Args, ValueEnum};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tracing::{debug, info, warn};
 
+use super::master;
 use crate::cli::context::ExecutionContext;
-use mwxdump_core::errors::{Result, WeChatError};
-use mwxdump_core::wechat::decrypt::DecryptionProcessor;
-use mwxdump_core::wechat::key::key_extractor::{create_key_extractor, KeyExtractor};
-use mwxdump_core::wechat::process::{create_process_detector, ProcessDetector};
+use crate::cli::notify;
+use crate::i18n::{tr, tr_args};
+use mwxdump_core::backup::signing::{self, SigningKey};
+use mwxdump_core::backup::vault::{self, SecretKey};
+use mwxdump_core::backup::{self, BackupCatalog, BackupOutcome, BackupRecord};
+use mwxdump_core::errors::{ConfigError, Result, WeChatError};
+use mwxdump_core::events::Event;
+use mwxdump_core::wechat::android::{self, AndroidDatabase};
+use mwxdump_core::wechat::datadir::{self, DbCategory};
+use mwxdump_core::wechat::decrypt::{DecryptReport, DecryptVersion, DecryptionProcessor, ParallelOptions};
+use mwxdump_core::wechat::ios::{self, IosBackup};
+use mwxdump_core::wechat::key::key_extractor::{create_key_extractor_with_offsets, KeyExtractor};
+use mwxdump_core::wechat::pcbackup::{self, PcBackup};
+use mwxdump_core::wechat::process::detect_primary;
+
+/// 解密结束后输出摘要的格式
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SummaryFormat {
+    /// 一行文字摘要
+    #[default]
+    Text,
+    /// JSON，包含解密统计和备份记录
+    Json,
+}
+
+/// 数据库版本
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DbVersion {
+    /// 按密钥验证结果自动检测
+    #[default]
+    Auto,
+    /// 微信3.x（SQLCipher 3 参数）
+    V3,
+    /// 微信4.x
+    V4,
+}
+
+impl DbVersion {
+    /// 对应的解密版本，`Auto` 时为 `None`
+    pub fn decrypt_version(self) -> Option<DecryptVersion> {
+        match self {
+            DbVersion::Auto => None,
+            DbVersion::V3 => Some(DecryptVersion::V3),
+            DbVersion::V4 => Some(DecryptVersion::V4),
+        }
+    }
+}
 
 /// 自动或手动解密微信数据库文件
 #[derive(Args, Debug)]
@@ -39,6 +85,78 @@ pub struct DecryptArgs {
     /// 默认为系统的CPU核心数。
     #[arg(long, help = "设置并发解密的线程数", long_help = "指定用于并行解密文件的线程数量。如果留空或设为0，将自动使用您计算机的CPU核心数作为默认值，以实现最佳性能。")]
     pub threads: Option<usize>,
+
+    /// [可选] 强制启用单个文件内的页面并行解密。
+    #[arg(long, conflicts_with = "sequential", help = "强制启用文件内的页面并行解密（默认按文件大小和可用内存自动选择）")]
+    pub parallel: bool,
+
+    /// [可选] 禁用页面并行解密，逐页顺序解密。
+    #[arg(long, help = "禁用页面并行解密，逐页顺序解密", long_help = "逐页顺序解密每个文件，内存占用最低，适合低内存或单核设备。不能与 --parallel 及页面并行参数同时使用。")]
+    pub sequential: bool,
+
+    /// [可选] 单个文件内同时解密的页面数。
+    #[arg(long, conflicts_with = "sequential", value_name = "N", help = "单个文件内同时解密的页面数（默认按 CPU 核心数自动设置）")]
+    pub concurrent_pages: Option<usize>,
+
+    /// [可选] 每批读取和写入的页面数。
+    #[arg(long, conflicts_with = "sequential", value_name = "N", help = "页面并行解密时每批处理的页面数")]
+    pub batch_size: Option<usize>,
+
+    /// [可选] 页面并行解密的内存上限。
+    #[arg(long, conflicts_with = "sequential", value_name = "MB", help = "页面并行解密的内存上限（MB）")]
+    pub max_memory_mb: Option<usize>,
+
+    /// [可选] 只解密指定类别的数据库，多个类别用逗号分隔。
+    #[arg(long, value_delimiter = ',', value_name = "CATEGORY", help = "只解密指定类别的数据库，如 message,contact,session", long_help = "只解密指定类别的数据库，多个类别用逗号分隔。可选类别: message, contact, session, emoticon, hardlink, media, favorites, sns, fts, other。类别按 db_storage 下的一级目录判断，全文索引（*_fts.db）和 media_N.db 分别单独归为 fts 和 media。跳过体积较大的全文索引和媒体数据库可以显著缩短备份时间。仅对目录输入生效，单文件输入时给出警告并忽略。")]
+    pub only: Vec<DbCategory>,
+
+    /// [可选] 以快照方式备份到输出目录下以时间命名的子目录，完成后按保留策略清理旧快照。
+    #[arg(long, help = "备份到输出目录下以时间命名的快照子目录", long_help = "每次解密写入输出目录下以当前时间命名的子目录（如 20240501-093000），完成后按配置中的 [backup.retention] 保留策略删除过期快照。")]
+    pub snapshot: bool,
+
+    /// [可选] 全部完成后按路径排序输出每个文件的结果，并写入 failures.json。
+    #[arg(long, help = "全部完成后按路径排序输出每个文件的结果", long_help = "解密过程中不再按完成顺序报告失败，全部完成后按相对路径排序输出每个文件的结果，并把失败的文件按同样顺序写入输出目录下的 failures.json，便于比较多次运行的结果。仅对目录输入生效。")]
+    pub ordered: bool,
+
+    /// [可选] 增量解密，跳过自上次解密后未变化的文件。
+    #[arg(long, conflicts_with = "snapshot", help = "增量解密，跳过自上次解密后未变化的文件", long_help = "在输出目录下维护 decrypt_manifest.json，记录每个源文件的大小、修改时间和密文的 BLAKE3 哈希。再次解密时跳过未变化且输出仍存在的文件，只解密新增和变化的数据库。仅对目录输入生效，不能与 --snapshot 同时使用。")]
+    pub incremental: bool,
+
+    /// [可选] 增量模式下忽略清单，全部重新解密。
+    #[arg(long, requires = "incremental", help = "增量模式下忽略已有清单，全部重新解密并重写清单")]
+    pub force: bool,
+
+    /// [可选] 用备份根目录的主密钥加密快照。
+    #[arg(long, requires = "snapshot", help = "用主密钥加密快照（需先执行 keys master init）", long_help = "快照完成并记入 backups.json 后，以随机生成的归档密钥加密快照中的每个文件并删除明文，归档密钥由输出目录下 vault.json 中的主密钥加密保存。主密钥口令读取环境变量 MWX_MASTER_PASSPHRASE，未设置时在终端中输入。加密的快照用 backups restore 解密。")]
+    pub encrypt: bool,
+
+    /// [可选] 用 ed25519 签名快照的文件清单。
+    #[arg(long, requires = "snapshot", help = "用签名密钥签名快照的文件清单（需先执行 keys master init）", long_help = "快照完成后写入的 manifest.json 记录每个文件的大小和 BLAKE3 哈希，加上此参数后用 ed25519 私钥对清单签名，写入 manifest.sig，之后可用 verify --signature 证明快照未被改动。私钥首次使用时生成，由主密钥加密保存在输出目录的 signing.json 中，口令的读取方式与 --encrypt 相同。")]
+    pub sign: bool,
+
+    /// [可选] 指定数据库版本，默认自动检测。
+    #[arg(long, value_enum, default_value_t = DbVersion::Auto, help = "数据库版本（auto/v3/v4），默认自动检测", long_help = "微信3.x 的数据库为 v3（SHA1、64000 次迭代、1024 字节页面），微信4.x 为 v4。默认逐个文件按密钥验证结果自动检测；指定后只按该版本验证和解密，可省去对另一版本的尝试。")]
+    pub db_version: DbVersion,
+
+    /// [可选] 解密结束后输出摘要的格式。
+    #[arg(long, value_enum, default_value_t = SummaryFormat::Text, help = "解密结束后输出摘要的格式（text/json）", long_help = "解密结束后向标准输出打印摘要，包含文件数、成功和失败数以及页面并行解密的内存峰值和上限，可据此调整 --max-memory-mb。json 格式同时包含写入 backups.json 的备份记录，便于脚本处理。")]
+    pub format: SummaryFormat,
+
+    /// [可选] iOS 备份中包含多个微信账号时指定导入的账号。
+    #[arg(long, value_name = "WXID", help = "iOS 备份中要导入的账号（wxid 或其 MD5）", long_help = "输入为 iOS 备份（iTunes/访达备份目录）且其中有多个微信账号时，指定导入的账号，可以是 wxid 或备份中账号目录名（wxid 的 MD5）。只有一个账号时可省略。")]
+    pub account: Option<String>,
+
+    /// [可选] 安卓微信的 uin，用于计算 EnMicroMsg.db 的口令。
+    #[arg(long, value_name = "UIN", allow_hyphen_values = true, help = "安卓微信的 uin，用于计算 EnMicroMsg.db 的口令", long_help = "输入为安卓微信的 EnMicroMsg.db 时，口令为 md5(IMEI + uin) 的前7位。uin 见手机上 shared_prefs/system_config_prefs.xml 中的 default_uin（可能为负数）。也可以用 -k 直接提供7位口令。")]
+    pub uin: Option<String>,
+
+    /// [可选] 安卓设备的 IMEI，默认为微信在读取不到 IMEI 时使用的 1234567890ABCDEF。
+    #[arg(long, value_name = "IMEI", requires = "uin", help = "安卓设备的 IMEI（默认 1234567890ABCDEF）")]
+    pub imei: Option<String>,
+
+    /// [可选] 完成或失败后发送桌面通知（Windows）。
+    #[arg(long, help = "完成或失败后发送桌面通知（Windows）")]
+    pub notify: bool,
 }
 
 impl DecryptArgs {
@@ -53,7 +171,9 @@ impl DecryptArgs {
                 .into());
             }
         }
-        if let Some(key_str) = &self.key {
+        // 安卓数据库的 -k 为7位口令，不按十六进制密钥检查
+        let is_android = self.input.as_deref().is_some_and(android::is_android_db);
+        if let Some(key_str) = self.key.as_ref().filter(|_| !is_android) {
             if hex::decode(key_str)
                 .map_err(|e| WeChatError::DecryptionFailed(format!("密钥格式错误: {}", e)))?
                 .len()
@@ -65,92 +185,408 @@ impl DecryptArgs {
                 .into());
             }
         }
+        for (name, value) in [
+            ("concurrent-pages", self.concurrent_pages),
+            ("batch-size", self.batch_size),
+            ("max-memory-mb", self.max_memory_mb),
+        ] {
+            if value == Some(0) {
+                return Err(ConfigError::InvalidValue {
+                    key: name.to_string(),
+                    value: "0".to_string(),
+                }
+                .into());
+            }
+        }
         Ok(())
     }
+
+    /// 页面并行解密选项，未指定的参数使用解密器默认值
+    pub fn parallel_options(&self) -> ParallelOptions {
+        ParallelOptions {
+            enabled: match (self.parallel, self.sequential) {
+                (true, _) => Some(true),
+                (_, true) => Some(false),
+                _ => None,
+            },
+            concurrent_pages: self.concurrent_pages,
+            batch_size: self.batch_size,
+            max_memory_mb: self.max_memory_mb,
+            memory_monitor: None,
+        }
+    }
 }
 
 /// 执行解密命令
 pub async fn execute(context: &ExecutionContext, args: DecryptArgs) -> Result<()> {
-    info!("🔓 开始执行解密，参数: {:?}", args);
+    let (send_notification, validate_only, format) = (args.notify, args.validate_only, args.format);
+    let started = Instant::now();
+    let result = decrypt(context, args).await;
+    if send_notification && !validate_only {
+        let duration = ("duration", notify::format_duration(started.elapsed()));
+        match &result {
+            Ok(BackupOutcome { record, .. }) => notify::toast(
+                &tr("notify-backup-succeeded"),
+                &tr_args(
+                    "notify-backup-succeeded-body",
+                    &[
+                        ("databases", record.as_ref().map_or(0, |r| r.databases).to_string()),
+                        ("messages", record.as_ref().and_then(|r| r.messages).unwrap_or(0).to_string()),
+                        duration,
+                    ],
+                ),
+            ),
+            Err(e) => notify::toast(
+                &tr("notify-backup-failed"),
+                &tr_args("notify-failed-body", &[("error", e.to_string()), duration]),
+            ),
+        }
+    }
+    let outcome = result?;
+    match format {
+        SummaryFormat::Json => println!("{}", serde_json::to_string_pretty(&outcome)?),
+        SummaryFormat::Text if !validate_only => println!("{}", summary_line(&outcome.decrypt)),
+        SummaryFormat::Text => {}
+    }
+    Ok(())
+}
+
+/// 文字摘要，增量解密时附带跳过的文件数
+fn summary_line(report: &DecryptReport) -> String {
+    let summary = tr_args(
+        "decrypt-summary",
+        &[
+            ("succeeded", report.succeeded.to_string()),
+            ("files", report.files.to_string()),
+            ("failed", report.failed.to_string()),
+            ("peak", format!("{:.1}", report.peak_memory_mb())),
+            ("max", report.max_memory_mb.to_string()),
+        ],
+    );
+    match report.unchanged {
+        0 => summary,
+        unchanged => format!(
+            "{}\n{}",
+            summary,
+            tr_args("decrypt-unchanged", &[("unchanged", unchanged.to_string())])
+        ),
+    }
+}
+
+/// 解密并记录备份，返回解密统计和记入 `backups.json` 的记录
+async fn decrypt(context: &ExecutionContext, args: DecryptArgs) -> Result<BackupOutcome> {
+    info!("{}", tr("decrypt-started"));
+    debug!("解密参数: {:?}", args);
     args.validate()?;
+    let output = if args.snapshot {
+        backup::new_snapshot_path(&args.output)
+    } else {
+        args.output.clone()
+    };
+    // 解密前解开主密钥，口令错误时不必等待解密完成
+    let master = match (args.encrypt || args.sign) && !args.validate_only {
+        true => Some(master::unlock(context, &args.output)?),
+        false => None,
+    };
+    let master = master.as_ref();
+
+    // iOS 备份中的数据库没有加密，无需密钥
+    if let Some(input) = args.input.clone().filter(|input| ios::is_ios_backup(input)) {
+        return import_ios_backup(context, &args, input, output, master).await;
+    }
+    // 安卓数据库的口令由 uin 和 IMEI 计算，不使用电脑版的密钥
+    if let Some(input) = args.input.clone().filter(|input| android::is_android_db(input)) {
+        return import_android_db(context, &args, input, output, master).await;
+    }
 
     // 1. 获取密钥
     let key_bytes = get_key(context, &args).await?;
-    info!("✅ 密钥获取成功: {} 字节", key_bytes.len());
+    info!("{}", tr_args("decrypt-key-ready", &[("bytes", key_bytes.len().to_string())]));
 
     // 2. 获取输入路径
     let input_path = get_input_path(context, &args).await?;
-    info!("📁 输入路径确定: {:?}", input_path);
+    info!("{}", tr_args("decrypt-input-path", &[("path", input_path.display().to_string())]));
 
     // 3. 创建解密处理器并执行解密
+    if pcbackup::is_backup_dir(&input_path) {
+        return unpack_backup(context, &args, input_path, output, key_bytes, master).await;
+    }
+    let wxid = datadir::scan(&input_path).ok().and_then(|info| info.wxid);
+    let parallel = args.parallel_options();
     let processor = DecryptionProcessor::new(
         input_path,
-        args.output,
-        key_bytes,
+        output.clone(),
+        key_bytes.clone(),
         args.threads,
         args.validate_only,
+    )
+    .with_cancellation(context.cancellation_token())
+    .with_categories(args.only.clone())
+    .with_parallel_options(parallel)
+    .with_ordered_results(args.ordered)
+    .with_incremental(args.incremental)
+    .with_force(args.force)
+    .with_version(args.db_version.decrypt_version())
+    .with_events(context.events().clone());
+
+    // 进度条订阅事件总线上的解密进度
+    let bar = ProgressBar::new(0);
+    bar.set_style(
+        ProgressStyle::with_template("{prefix} {bar:40} {pos}/{len} {wide_msg}")
+            .unwrap_or_else(|_| ProgressStyle::default_bar()),
     );
+    bar.set_prefix(tr("decrypt-progress"));
+    let progress_bar = bar.clone();
+    let mut events = context.events().subscribe();
+    let progress = tokio::spawn(async move {
+        while let Some(event) = events.recv().await {
+            if let Event::DecryptProgress { file, processed, total } = event {
+                progress_bar.set_length(total);
+                progress_bar.set_position(processed);
+                progress_bar.set_message(file.display().to_string());
+            }
+        }
+    });
+    let decrypt = processor.execute().await;
+    progress.abort();
+    bar.finish_and_clear();
+    let decrypt = decrypt?;
+    if args.validate_only {
+        return Ok(BackupOutcome { record: None, decrypt });
+    }
+    record_backup(context, &args, &output, wxid, &key_bytes, decrypt, master).await
+}
+
+/// 解包微信电脑版的备份（`Backup.db`），之后与解密一样记录备份
+async fn unpack_backup(
+    context: &ExecutionContext,
+    args: &DecryptArgs,
+    input_path: PathBuf,
+    output: PathBuf,
+    key_bytes: Vec<u8>,
+    master: Option<&SecretKey>,
+) -> Result<BackupOutcome> {
+    info!("{}", tr("decrypt-pc-backup"));
+    let backup = PcBackup::new(input_path, key_bytes.clone()).with_cancellation(context.cancellation_token());
+    if args.validate_only {
+        let version = backup.detect_version().await?.ok_or_else(|| {
+            WeChatError::DecryptionFailed(format!("密钥无法解密备份 {:?}", backup.dir()))
+        })?;
+        info!("{}", tr_args("decrypt-pc-backup-valid", &[("version", version.as_str().to_string())]));
+        let decrypt = DecryptReport {
+            files: 1,
+            succeeded: 1,
+            ..Default::default()
+        };
+        return Ok(BackupOutcome { record: None, decrypt });
+    }
+    let decrypt = backup.unpack(&output).await?.into();
+    let wxid = backup.self_wxid().map(str::to_string);
+    record_backup(context, args, &output, wxid, &key_bytes, decrypt, master).await
+}
 
-    processor.execute().await
+/// 导入 iOS 备份中的微信账号，之后与解密一样记录备份
+async fn import_ios_backup(
+    context: &ExecutionContext,
+    args: &DecryptArgs,
+    input_path: PathBuf,
+    output: PathBuf,
+    master: Option<&SecretKey>,
+) -> Result<BackupOutcome> {
+    info!("{}", tr("decrypt-ios-backup"));
+    let backup = IosBackup::open(input_path).await?.with_cancellation(context.cancellation_token());
+    let account = backup.resolve_account(args.account.as_deref())?;
+    if args.validate_only {
+        info!("{}", tr_args("decrypt-ios-account", &[("account", account.to_string())]));
+        let decrypt = DecryptReport {
+            files: 1,
+            succeeded: 1,
+            ..Default::default()
+        };
+        return Ok(BackupOutcome { record: None, decrypt });
+    }
+    let report = backup.import(&account, &output).await?;
+    let decrypt = DecryptReport {
+        files: report.databases,
+        succeeded: report.databases,
+        elapsed_ms: report.elapsed_ms,
+        ..Default::default()
+    };
+    record_backup(context, args, &output, report.self_wxid, &[], decrypt, master).await
+}
+
+/// 解密并导入安卓微信的 `EnMicroMsg.db`，之后与解密一样记录备份
+async fn import_android_db(
+    context: &ExecutionContext,
+    args: &DecryptArgs,
+    input_path: PathBuf,
+    output: PathBuf,
+    master: Option<&SecretKey>,
+) -> Result<BackupOutcome> {
+    info!("{}", tr("decrypt-android-db"));
+    let password = match (&args.key, &args.uin) {
+        (Some(key), _) => key.clone(),
+        (None, Some(uin)) => android::derive_password(uin, args.imei.as_deref()),
+        (None, None) => {
+            return Err(ConfigError::MissingKey {
+                key: "uin".to_string(),
+            }
+            .into())
+        }
+    };
+    let database = AndroidDatabase::new(input_path, password.clone()).with_cancellation(context.cancellation_token());
+    if args.validate_only {
+        let profile = database.detect_profile()?.ok_or_else(|| {
+            WeChatError::DecryptionFailed(format!("口令无法解密 {:?}，请检查 uin 和 IMEI", database.path()))
+        })?;
+        info!("{}", tr_args("decrypt-android-valid", &[("profile", profile.as_str().to_string())]));
+        let decrypt = DecryptReport {
+            files: 1,
+            succeeded: 1,
+            ..Default::default()
+        };
+        return Ok(BackupOutcome { record: None, decrypt });
+    }
+    let report = database.import(&output).await?;
+    let decrypt = DecryptReport {
+        files: 1,
+        succeeded: 1,
+        elapsed_ms: report.elapsed_ms,
+        ..Default::default()
+    };
+    record_backup(context, args, &output, report.self_wxid, password.as_bytes(), decrypt, master).await
+}
+
+/// 记录到备份目录，需要时加密快照，快照备份完成后写入清单并清理过期快照
+async fn record_backup(
+    context: &ExecutionContext,
+    args: &DecryptArgs,
+    output: &Path,
+    wxid: Option<String>,
+    key_bytes: &[u8],
+    decrypt: DecryptReport,
+    master: Option<&SecretKey>,
+) -> Result<BackupOutcome> {
+    // 统计需要读取明文数据库，在加密之前完成
+    let captured = BackupRecord::capture(&args.output, output, wxid, key_bytes).await;
+    if let Some(master) = master.filter(|_| args.encrypt) {
+        let report = vault::encrypt_archive(output, master)?;
+        info!(
+            "{}",
+            tr_args(
+                "decrypt-snapshot-encrypted",
+                &[("files", report.files.to_string()), ("bytes", report.bytes.to_string())],
+            )
+        );
+    }
+    // 清单在加密之后生成，记录的是实际保存的文件
+    if args.snapshot {
+        let signer = match master.filter(|_| args.sign) {
+            Some(master) => Some(SigningKey::load_or_create(&args.output, master)?),
+            None => None,
+        };
+        let manifest = signing::write_manifest(output, signer.as_ref())?;
+        if let Some(signer) = &signer {
+            let fingerprint = signing::public_key_fingerprint(&signer.public_key());
+            info!(
+                "{}",
+                tr_args(
+                    "decrypt-manifest-signed",
+                    &[("files", manifest.files.len().to_string()), ("fingerprint", fingerprint)],
+                )
+            );
+        }
+    }
+    let record = match captured {
+        Ok(mut record) => {
+            record.encrypted = args.encrypt;
+            record.signed = args.sign;
+            BackupCatalog::append(&args.output, record.clone())?;
+            Some(record)
+        }
+        Err(e) => {
+            warn!("{}", tr_args("decrypt-record-failed", &[("error", e.to_string())]));
+            None
+        }
+    };
+    let retention = &context.config().backup.retention;
+    if args.snapshot && retention.is_enabled() {
+        let plan = retention.enforce(&args.output, false)?;
+        info!(
+            "{}",
+            tr_args(
+                "decrypt-snapshots-pruned",
+                &[("pruned", plan.prune.len().to_string()), ("kept", plan.keep.len().to_string())],
+            )
+        );
+    }
+    Ok(BackupOutcome { record, decrypt })
 }
 
 /// 获取密钥，如果用户未提供则自动提取
 async fn get_key(context: &ExecutionContext, args: &DecryptArgs) -> Result<Vec<u8>> {
     if let Some(key_str) = &args.key {
-        info!("🔑 使用用户提供的密钥");
+        info!("{}", tr("decrypt-key-from-args"));
         return Ok(hex::decode(key_str)?);
     }
 
     if let Some(preset_key) = context.wechat_data_key() {
-        info!("🔑 使用配置文件中的预设密钥");
+        info!("{}", tr("decrypt-key-from-config"));
         return Ok(hex::decode(preset_key)?);
     }
 
-    info!("🔑 自动从微信进程提取密钥...");
-    let detector = create_process_detector().context("创建进程检测器失败")?;
-    let processes = detector.detect_processes().await.context("检测微信进程失败")?;
-    if processes.is_empty() {
-        return Err(WeChatError::ProcessNotFound.into());
-    }
-
-    let process = &processes[0];
-    info!("🎯 目标进程: {} (PID: {})", process.name, process.pid);
+    info!("{}", tr("decrypt-key-extracting"));
+    let (process, _) = detect_primary(context.data_dir_validation(), context.supported_wechat_versions())
+        .await
+        .context("检测微信进程失败")?;
+    info!(
+        "{}",
+        tr_args("decrypt-target-process", &[("name", process.name.clone()), ("pid", process.pid.to_string())])
+    );
 
-    let key_extractor = create_key_extractor().context("创建密钥提取器失败")?;
-    let wechat_key = key_extractor.extract_key(process).await.context("提取密钥失败")?;
-    info!("🎉 自动提取密钥成功");
+    let key_extractor = create_key_extractor_with_offsets(context.key_offsets().to_vec())
+        .context("创建密钥提取器失败")?
+        .with_events(context.events().clone());
+    let wechat_key = key_extractor.extract_key(&process).await.context("提取密钥失败")?;
+    info!("{}", tr("decrypt-key-extracted"));
     Ok(wechat_key.key_data)
 }
 
 /// 获取输入路径，如果用户未提供则自动检测
 async fn get_input_path(context: &ExecutionContext, args: &DecryptArgs) -> Result<PathBuf> {
     if let Some(input_path) = &args.input {
-        info!("📂 使用用户提供的输入路径");
+        info!("{}", tr("decrypt-input-from-args"));
         return Ok(input_path.clone());
     }
 
     if let Some(data_dir) = context.wechat_data_dir() {
-        info!("📂 使用配置文件中的数据目录");
+        info!("{}", tr("decrypt-input-from-config"));
         return Ok(data_dir.to_path_buf());
     }
 
-    info!("📂 自动检测微信数据目录...");
-    let detector = create_process_detector()?;
-    let processes = detector.detect_processes().await?;
-    if processes.is_empty() {
-        return Err(WeChatError::ProcessNotFound.into());
-    }
-
-    let process = &processes[0];
-    if let Some(data_dir) = &process.data_dir {
-        info!("🎉 自动检测到数据目录: {:?}", data_dir);
-        Ok(data_dir.to_path_buf())
-    } else {
-        Err(WeChatError::DecryptionFailed(
-            "无法自动确定微信数据目录".to_string(),
+    info!("{}", tr("decrypt-input-detecting"));
+    let (process, dir_info) = detect_primary(context.data_dir_validation(), context.supported_wechat_versions()).await?;
+    let data_dir = process.data_dir.unwrap_or_else(|| dir_info.root.clone());
+    info!("{}", tr_args("decrypt-input-detected", &[("path", data_dir.display().to_string())]));
+    info!(
+        "{}",
+        tr_args(
+            "decrypt-account-info",
+            &[
+                ("wxid", dir_info.wxid.clone().unwrap_or_else(|| tr("decrypt-unknown"))),
+                (
+                    "db_dir",
+                    dir_info
+                        .db_storage_path
+                        .as_ref()
+                        .map(|path| path.display().to_string())
+                        .unwrap_or_else(|| tr("decrypt-unknown")),
+                ),
+                ("size", dir_info.size.to_string()),
+            ],
         )
-        .into())
-    }
+    );
+    Ok(data_dir)
 }
 
 
@@ -166,6 +602,24 @@ mod tests {
             key: Some("0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef".to_string()),
             validate_only: false,
             threads: Some(4),
+            parallel: false,
+            sequential: false,
+            concurrent_pages: None,
+            batch_size: None,
+            max_memory_mb: None,
+            only: Vec::new(),
+            snapshot: false,
+            ordered: false,
+            incremental: false,
+            force: false,
+            encrypt: false,
+            sign: false,
+            db_version: DbVersion::Auto,
+            format: SummaryFormat::Text,
+            account: None,
+            uin: None,
+            imei: None,
+            notify: false,
         };
         assert!(args.validate().is_ok());
 
@@ -175,4 +629,34 @@ mod tests {
         };
         assert!(bad_key_args.validate().is_err());
     }
+
+    #[test]
+    fn test_parallel_flags() {
+        use clap::Parser;
+
+        #[derive(Parser)]
+        struct Cli {
+            #[command(flatten)]
+            args: DecryptArgs,
+        }
+
+        let parse = |flags: &[&str]| Cli::try_parse_from([&["mwxdump", "-o", "out"], flags].concat());
+        let args = parse(&["--parallel", "--concurrent-pages", "8"]).unwrap().args;
+        assert_eq!(args.parallel_options().enabled, Some(true));
+        assert_eq!(args.parallel_options().concurrent_pages, Some(8));
+        assert_eq!(parse(&["--sequential"]).unwrap().args.parallel_options().enabled, Some(false));
+        assert_eq!(parse(&[]).unwrap().args.parallel_options(), ParallelOptions::default());
+        assert!(parse(&["--parallel", "--sequential"]).is_err());
+        assert!(parse(&["--sequential", "--batch-size", "16"]).is_err());
+        assert_eq!(parse(&["--uin", "-123456789"]).unwrap().args.uin.as_deref(), Some("-123456789"));
+        assert_eq!(parse(&["--db-version", "v3"]).unwrap().args.db_version.decrypt_version(), Some(DecryptVersion::V3));
+        assert_eq!(parse(&[]).unwrap().args.db_version, DbVersion::Auto);
+        assert!(parse(&["--max-memory-mb", "0"]).unwrap().args.validate().is_err());
+        assert!(parse(&["--incremental", "--force"]).unwrap().args.force);
+        assert!(parse(&["--force"]).is_err());
+        assert!(parse(&["--incremental", "--snapshot"]).is_err());
+        assert!(parse(&["--encrypt"]).is_err());
+        assert!(parse(&["--sign"]).is_err());
+        assert!(parse(&["--snapshot", "--encrypt"]).unwrap().args.encrypt);
+    }
 }
\ No newline at end of file
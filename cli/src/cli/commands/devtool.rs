@@ -0,0 +1,205 @@
+//! 开发辅助命令
+//!
+//! 面向维护者的工具，例如微信更新后用已知密钥在内存快照中反查新的密钥特征，
+//! 以及为前端和其他语言的客户端导出数据模型的 JSON Schema。
+
+use anyhow::Context;
+use clap::{Args, Subcommand};
+use std::path::PathBuf;
+
+use crate::cli::context::ExecutionContext;
+use crate::i18n::tr_args;
+use mwxdump_core::errors::{ConfigError, Result, WeChatError};
+use mwxdump_core::models::schema::model_schemas;
+use mwxdump_core::utils::memory_snapshot::MemorySnapshot;
+use mwxdump_core::utils::task;
+use mwxdump_core::wechat::key::pattern_finder::ByteContext;
+use mwxdump_core::wechat::key::{find_key_pattern, PatternSearchConfig};
+
+/// 候选特征的字节数，与内置特征长度一致
+const CANDIDATE_PATTERN_LEN: usize = 24;
+
+/// 开发辅助参数
+#[derive(Args, Debug)]
+pub struct DevtoolArgs {
+    #[command(subcommand)]
+    pub command: DevtoolCommand,
+}
+
+/// 开发辅助子命令
+#[derive(Subcommand, Debug)]
+pub enum DevtoolCommand {
+    /// 用已知密钥在内存快照中定位密钥及指针链，输出候选特征
+    FindPattern {
+        /// 已知正确的密钥（64位十六进制）
+        #[arg(long, value_name = "HEX")]
+        known_key: String,
+
+        /// dump-memory 生成的内存快照
+        #[arg(long, value_name = "FILE")]
+        dump: PathBuf,
+
+        /// 每处地址前后输出的字节数
+        #[arg(long, default_value_t = 32)]
+        context: usize,
+
+        /// 指针链的最大深度
+        #[arg(long, default_value_t = 2)]
+        depth: usize,
+    },
+
+    /// 输出联系人、群聊、会话和消息等模型的 JSON Schema
+    Schema {
+        /// 输出目录，每个模型写入 <名称>.schema.json；不指定时以单个 JSON 对象输出到标准输出
+        #[arg(long, value_name = "DIR")]
+        output: Option<PathBuf>,
+    },
+}
+
+/// 执行开发辅助命令
+pub async fn execute(_context: &ExecutionContext, args: DevtoolArgs) -> Result<()> {
+    match args.command {
+        DevtoolCommand::FindPattern {
+            known_key,
+            dump,
+            context,
+            depth,
+        } => find_pattern(&known_key, dump, context, depth).await,
+        DevtoolCommand::Schema { output } => schema(output),
+    }
+}
+
+fn schema(output: Option<PathBuf>) -> Result<()> {
+    let schemas = model_schemas();
+    let Some(dir) = output else {
+        let all: serde_json::Map<String, serde_json::Value> = schemas
+            .into_iter()
+            .map(|(name, schema)| (name.to_string(), schema.to_value()))
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&all)?);
+        return Ok(());
+    };
+
+    std::fs::create_dir_all(&dir)?;
+    for (name, schema) in &schemas {
+        std::fs::write(
+            dir.join(format!("{}.schema.json", name)),
+            serde_json::to_string_pretty(schema)? + "\n",
+        )?;
+    }
+    println!(
+        "{}",
+        tr_args(
+            "devtool-schema-written",
+            &[("count", schemas.len().to_string()), ("dir", dir.display().to_string())],
+        )
+    );
+    Ok(())
+}
+
+async fn find_pattern(known_key: &str, dump: PathBuf, context: usize, depth: usize) -> Result<()> {
+    let key = hex::decode(known_key.trim())
+        .ok()
+        .filter(|key| key.len() == 32)
+        .ok_or_else(|| ConfigError::InvalidValue {
+            key: "known-key".to_string(),
+            value: known_key.to_string(),
+        })?;
+    let config = PatternSearchConfig {
+        context,
+        max_depth: depth,
+        ..Default::default()
+    };
+
+    let report = task::spawn_blocking(
+        move || {
+            let snapshot = MemorySnapshot::open(&dump)?;
+            find_key_pattern(&snapshot, &key, &config)
+        },
+        WeChatError::KeyExtractionFailed,
+    )
+    .await
+    .context("搜索密钥特征失败")?;
+
+    println!(
+        "{}",
+        tr_args(
+            "devtool-key-locations",
+            &[("count", report.key_locations.len().to_string())],
+        )
+    );
+    for location in &report.key_locations {
+        print_context(location);
+    }
+
+    for link in &report.pointers {
+        println!(
+            "{}",
+            tr_args(
+                "devtool-pointer",
+                &[
+                    ("depth", link.depth.to_string()),
+                    ("location", format!("{:#x}", link.location)),
+                    ("target", format!("{:#x}", link.target)),
+                    ("offset", format!("{:#x}", link.field_offset())),
+                ],
+            )
+        );
+        print_context(&link.context);
+        if link.depth == 1 {
+            if let Some(pattern) = report.candidate_pattern(link, CANDIDATE_PATTERN_LEN) {
+                println!(
+                    "{}",
+                    tr_args(
+                        "devtool-candidate-pattern",
+                        &[
+                            ("pattern", hex::encode(pattern)),
+                            ("offset", (-(report.pointer_size as i64)).to_string()),
+                        ],
+                    )
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_context(context: &ByteContext) {
+    for line in hex_dump(context) {
+        println!("  {}", line);
+    }
+    println!();
+}
+
+/// 每行 16 字节的十六进制转储
+fn hex_dump(context: &ByteContext) -> Vec<String> {
+    context
+        .bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(index, chunk)| {
+            let bytes: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+            format!("{:016x}  {}", context.address + index * 16, bytes.join(" "))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_dump() {
+        let context = ByteContext {
+            address: 0x1000,
+            bytes: (0u8..20).collect(),
+        };
+        let lines = hex_dump(&context);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            "0000000000001000  00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f"
+        );
+        assert_eq!(lines[1], "0000000000001010  10 11 12 13");
+    }
+}
@@ -1,24 +1,61 @@
 //! 内存转储命令实现
 
+use anyhow::Context;
+use std::path::PathBuf;
+
 use crate::cli::context::ExecutionContext;
-use mwxdump_core::errors::Result;
+use crate::i18n::tr_args;
+use mwxdump_core::errors::{Result, WeChatError};
+use mwxdump_core::utils::memory_snapshot::{self, SnapshotRegions, SNAPSHOT_EXTENSION};
+use mwxdump_core::utils::task;
+use mwxdump_core::wechat::process::{create_process_detector_with_validation, ProcessDetector, WechatProcessInfo};
 
 /// 执行内存转储命令
-pub async fn execute(context: &ExecutionContext, pid: Option<u32>) -> Result<()> {
-    println!("正在执行内存转储...");
-    println!("当前日志级别: {}", context.log_level());
-    
-    if let Some(process_id) = pid {
-        println!("目标进程ID: {}", process_id);
-    } else {
-        println!("自动检测微信进程");
-    }
-    
-    // 显示配置信息
-    if let Some(data_dir) = context.wechat_data_dir() {
-        println!("配置的微信数据目录: {:?}", data_dir);
-    }
-    
-    // TODO: 实现内存转储逻辑
+///
+/// 把微信进程的内存写入快照文件，之后可在微信退出后离线分析。
+pub async fn execute(context: &ExecutionContext, pid: Option<u32>, output: Option<PathBuf>, all: bool) -> Result<()> {
+    let process = find_process(context, pid).await?;
+    let kind = if all { SnapshotRegions::Readable } else { SnapshotRegions::Private };
+    let output = output.unwrap_or_else(|| default_output(process.pid));
+    tracing::info!("开始转储进程 {} 的内存到 {:?}", process.pid, output);
+
+    let path = output.clone();
+    let index = task::spawn_blocking(
+        move || {
+            memory_snapshot::capture(&path, process.pid, &process.name, &process.version.to_string(), kind)
+        },
+        WeChatError::KeyExtractionFailed,
+    )
+    .await
+    .context("内存转储失败")?;
+
+    println!(
+        "{}",
+        tr_args(
+            "dump-memory-done",
+            &[
+                ("regions", index.regions.len().to_string()),
+                ("size", format!("{:.1}", index.total_bytes() as f64 / 1024.0 / 1024.0)),
+                ("path", output.display().to_string()),
+            ],
+        )
+    );
     Ok(())
-}
\ No newline at end of file
+}
+
+/// 按PID查找微信进程，未指定时使用主进程
+async fn find_process(context: &ExecutionContext, pid: Option<u32>) -> Result<WechatProcessInfo> {
+    let detector = create_process_detector_with_validation(context.data_dir_validation())?;
+    let processes = detector.detect_processes().await?;
+    let found = match pid {
+        Some(pid) => processes.into_iter().find(|p| p.pid == pid),
+        None => processes.into_iter().find(|p| p.is_main_process),
+    };
+    found.ok_or_else(|| WeChatError::ProcessNotFound.into())
+}
+
+/// 默认输出路径：当前目录下的 `wechat-<pid>-<时间>.mwxsnap`
+fn default_output(pid: u32) -> PathBuf {
+    let now = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    PathBuf::from(format!("wechat-{}-{}.{}", pid, now, SNAPSHOT_EXTENSION))
+}
@@ -0,0 +1,193 @@
+//! 导出命令
+//!
+//! 将单个会话的聊天记录导出为 JSON、文本或 HTML 文件，可同时复制图片、视频和文件，
+//! 以及转码为 WAV/MP3 的语音。
+//! `--pipeline` 时直接从加密的数据目录导出，解密数据只保存在临时目录中。
+
+use clap::Args;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::path::PathBuf;
+use std::time::Instant;
+
+use crate::cli::context::ExecutionContext;
+use crate::cli::notify;
+use crate::i18n::tr_args;
+use mwxdump_core::errors::{ConfigError, Result, WeChatError};
+use mwxdump_core::events::Event;
+use mwxdump_core::export::{ExportFormat, ExportOptions, ExportPipeline, ExportService};
+use mwxdump_core::plugins::PluginChain;
+use mwxdump_core::wechat::media::AudioFormat;
+use mwxdump_core::wechat::decrypt::DecryptedSession;
+
+/// 导出参数
+#[derive(Args, Debug)]
+pub struct ExportArgs {
+    /// 会话的 wxid 或群ID
+    #[arg(value_name = "WXID", required_unless_present = "talker")]
+    pub wxid: Option<String>,
+
+    /// 会话的 wxid 或群ID，与位置参数相同
+    #[arg(long, value_name = "WXID", conflicts_with = "wxid")]
+    pub talker: Option<String>,
+
+    /// 导出格式（json/txt/html/jsonl/csv）
+    #[arg(short, long, default_value = "json")]
+    pub format: ExportFormat,
+
+    /// 输出目录（默认为工作目录下的 export）
+    #[arg(short, long, value_name = "DIR")]
+    pub output: Option<PathBuf>,
+
+    /// 同时复制消息引用的媒体文件（需要配置微信数据目录）
+    #[arg(long)]
+    pub media: bool,
+
+    /// 导出语音消息并转码为指定格式（silk/wav/mp3），HTML 中可直接播放
+    #[arg(long, value_name = "FORMAT")]
+    pub voice: Option<AudioFormat>,
+
+    /// 以联系人备注或昵称命名导出文件（默认使用 wxid）
+    #[arg(long)]
+    pub name_by_contact: bool,
+
+    /// 断点续导：跳过已完成的导出，未完成的重新导出
+    #[arg(long)]
+    pub resume: bool,
+
+    /// 当前账号的 wxid，用于标记自己发送的消息
+    #[arg(long, value_name = "WXID")]
+    pub self_wxid: Option<String>,
+
+    /// 完成或失败后发送桌面通知（Windows）
+    #[arg(long)]
+    pub notify: bool,
+
+    /// 从加密的数据目录边解密边导出，不保留解密后的数据库
+    #[arg(long)]
+    pub pipeline: bool,
+
+    /// 加密的账号数据目录（默认使用配置中的微信数据目录）
+    #[arg(long, value_name = "DIR", requires = "pipeline")]
+    pub input: Option<PathBuf>,
+
+    /// 解密密钥（64位十六进制，默认使用配置中的密钥）
+    #[arg(long, value_name = "HEX", requires = "pipeline")]
+    pub key: Option<String>,
+}
+
+/// 导出会话
+pub async fn execute(context: &ExecutionContext, args: ExportArgs) -> Result<()> {
+    let wxid = args.wxid.clone().or_else(|| args.talker.clone()).unwrap_or_default();
+    let mut work_dir = context.database_config().work_dir.clone();
+    let options = ExportOptions {
+        output_dir: args.output.clone().unwrap_or_else(|| work_dir.join("export")),
+        include_media: args.media,
+        name_by_contact: args.name_by_contact,
+        resume: args.resume,
+        voice_format: args.voice,
+        ..Default::default()
+    };
+
+    // 导出结束后锁定会话，清除临时解密的数据
+    let session = match args.pipeline {
+        true => {
+            let session = pipeline(context, &args).await?;
+            work_dir = session.path().to_path_buf();
+            Some(session)
+        }
+        false => None,
+    };
+
+    let bar = ProgressBar::new(0);
+    bar.set_style(
+        ProgressStyle::with_template("{bar:40} {pos}/{len} {msg}")
+            .unwrap_or_else(|_| ProgressStyle::default_bar()),
+    );
+    let progress_bar = bar.clone();
+    let mut events = context.events().subscribe();
+    let progress = tokio::spawn(async move {
+        while let Some(event) = events.recv().await {
+            if let Event::ExportProgress { processed, total, .. } = event {
+                progress_bar.set_length(total);
+                progress_bar.set_position(processed);
+            }
+        }
+    });
+    let mut service = ExportService::new(work_dir)
+        .with_plugins(PluginChain::from_configs(&context.config().plugins)?)
+        .with_voice_config(context.config().voice.clone())
+        .with_cancellation(context.cancellation_token())
+        .with_events(context.events().clone());
+    if let Some(data_dir) = args.input.as_deref().or(context.wechat_data_dir()) {
+        service = service.with_data_root(data_dir);
+    }
+    if let Some(wxid) = args.self_wxid {
+        service = service.with_self_wxid(wxid);
+    }
+
+    let started = Instant::now();
+    let summary = service.export_conversation(&wxid, args.format, &options).await;
+    progress.abort();
+    bar.finish_and_clear();
+    if let Some(session) = session {
+        session.lock().await;
+    }
+    if args.notify {
+        let duration = ("duration", notify::format_duration(started.elapsed()));
+        match &summary {
+            Ok(summary) => notify::toast(
+                &tr_args("notify-export-succeeded", &[("wxid", wxid.clone())]),
+                &tr_args(
+                    "notify-export-succeeded-body",
+                    &[
+                        ("messages", summary.messages.to_string()),
+                        ("media", summary.media.to_string()),
+                        duration,
+                    ],
+                ),
+            ),
+            Err(e) => notify::toast(
+                &tr_args("notify-export-failed", &[("wxid", wxid.clone())]),
+                &tr_args("notify-failed-body", &[("error", e.to_string()), duration]),
+            ),
+        }
+    }
+    let summary = summary?;
+    println!(
+        "{}",
+        tr_args(
+            "export-summary",
+            &[
+                ("messages", summary.messages.to_string()),
+                ("media", summary.media.to_string()),
+                ("file", summary.file.display().to_string()),
+            ],
+        )
+    );
+    Ok(())
+}
+
+/// 把导出所需的数据库解密到临时会话
+async fn pipeline(context: &ExecutionContext, args: &ExportArgs) -> Result<DecryptedSession> {
+    let input = args
+        .input
+        .as_deref()
+        .or(context.wechat_data_dir())
+        .ok_or_else(|| WeChatError::DecryptionFailed("未指定微信数据目录，请使用 --input".to_string()))?;
+    let key_hex = args
+        .key
+        .as_deref()
+        .or(context.wechat_data_key())
+        .ok_or_else(|| WeChatError::DecryptionFailed("未指定解密密钥，请使用 --key".to_string()))?;
+    let key = hex::decode(key_hex.trim())
+        .ok()
+        .filter(|key| key.len() == 32)
+        .ok_or_else(|| ConfigError::InvalidValue {
+            key: "key".to_string(),
+            value: key_hex.to_string(),
+        })?;
+    ExportPipeline::new(input, key)
+        .with_cancellation(context.cancellation_token())
+        .unlock(args.media)
+        .await
+}
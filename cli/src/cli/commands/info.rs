@@ -0,0 +1,81 @@
+//! 数据目录信息命令
+
+use clap::Args;
+use std::path::PathBuf;
+
+use crate::cli::context::ExecutionContext;
+use crate::i18n::{tr, tr_args};
+use mwxdump_core::errors::Result;
+use mwxdump_core::wechat::datadir;
+use mwxdump_core::wechat::process::detect_primary;
+
+/// 数据目录信息参数
+#[derive(Args, Debug)]
+pub struct InfoArgs {
+    /// 微信数据目录（未指定时使用配置或自动检测）
+    #[arg(value_name = "PATH")]
+    pub path: Option<PathBuf>,
+}
+
+/// 显示数据目录信息
+pub async fn execute(context: &ExecutionContext, args: InfoArgs) -> Result<()> {
+    let path = match args.path {
+        Some(path) => path,
+        None => resolve_data_dir(context).await?,
+    };
+
+    let info = datadir::scan(&path)?;
+    println!("{}", tr_args("info-root", &[("dir", info.root.display().to_string())]));
+    let wxid = info.wxid.clone().unwrap_or_else(|| tr("process-wxid-missing"));
+    println!("{}", tr_args("info-wxid", &[("wxid", wxid)]));
+    println!("{}", tr_args("info-version", &[("version", info.version.to_string())]));
+    match &info.db_storage_path {
+        Some(dir) => println!("{}", tr_args("info-db-storage", &[("dir", dir.display().to_string())])),
+        None => println!("{}", tr("info-db-storage-missing")),
+    }
+    println!("{}", tr_args("info-size", &[("size", format_size(info.size))]));
+
+    if let Some(catalog) = info.catalog()? {
+        println!("{}", tr("info-categories"));
+        for (category, (count, size)) in catalog.summary() {
+            println!(
+                "  {}",
+                tr_args(
+                    "info-category",
+                    &[
+                        ("category", category.as_str().to_string()),
+                        ("count", count.to_string()),
+                        ("size", format_size(size)),
+                    ],
+                )
+            );
+        }
+    }
+    Ok(())
+}
+
+/// 依次使用配置的数据目录和检测到的进程数据目录
+async fn resolve_data_dir(context: &ExecutionContext) -> Result<PathBuf> {
+    if let Some(dir) = context.wechat_data_dir() {
+        return Ok(dir.to_path_buf());
+    }
+
+    let (process, info) = detect_primary(context.data_dir_validation(), context.supported_wechat_versions()).await?;
+    Ok(process.data_dir.unwrap_or(info.root))
+}
+
+/// 格式化字节数
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
@@ -1,57 +1,303 @@
 //! 测试密钥提取功能命令
 
+use clap::Args;
+use std::path::{Path, PathBuf};
+
 use crate::cli::context::ExecutionContext;
-use mwxdump_core::errors::Result;
-use mwxdump_core::wechat::key::{key_extractor, KeyExtractor, WeChatKey};
-use mwxdump_core::wechat::process::{ProcessDetector, create_process_detector};
+use crate::i18n::{tr, tr_args};
+use mwxdump_core::errors::{ConfigError, Result, WeChatError};
+use mwxdump_core::utils::task;
+use mwxdump_core::wechat::datadir;
+use mwxdump_core::wechat::key::{
+    key_extractor, rank_keys, validate_key_in_dir, KeyStrategyChain, SnapshotKeyExtractor, StrategyAttempt,
+    WeChatKey,
+};
+use mwxdump_core::wechat::process::{ProcessDetector, WechatProcessInfo, create_process_detector_with_validation};
+
+/// 从快照提取所有候选密钥时最多保留的数量
+const MAX_DUMP_CANDIDATES: usize = 16;
+
+/// 密钥命令参数
+#[derive(Args, Debug, Default)]
+pub struct KeyArgs {
+    /// 返回所有通过验证的候选密钥，并按能解密的数据库数量排序
+    #[arg(long)]
+    pub all: bool,
+
+    /// 提取后用该目录（db_storage 或账号目录）中每个类别的数据库验证密钥
+    #[arg(long, value_name = "DIR")]
+    pub validate_dir: Option<PathBuf>,
 
+    /// 从 dump-memory 保存的内存快照中提取密钥，无需运行微信，可在其他机器上执行
+    #[arg(long, value_name = "FILE")]
+    pub from_dump: Option<PathBuf>,
+
+    /// 快照所属账号的数据目录，用于校验候选密钥，默认使用配置中的 wechat.data_dir
+    #[arg(long, value_name = "DIR", requires = "from_dump")]
+    pub data_dir: Option<PathBuf>,
+}
 
 /// 执行密钥提取测试
-pub async fn execute(context: &ExecutionContext) -> Result<()> {
-    eprintln!("开始微信密钥提取...");
+pub async fn execute(context: &ExecutionContext, args: KeyArgs) -> Result<()> {
+    eprintln!("{}", tr("key-start"));
     
     // 显示当前配置信息
-    eprintln!("当前日志级别: {}", context.log_level());
+    eprintln!("{}", tr_args("log-level", &[("level", context.log_level().to_string())]));
     
     // 如果配置中有预设的数据密钥，显示提示
     if let Some(preset_key) = context.wechat_data_key() {
-        println!("检测到配置文件中的预设密钥: {}...", &preset_key[..8.min(preset_key.len())]);
+        let prefix = preset_key[..8.min(preset_key.len())].to_string();
+        println!("{}", tr_args("key-preset-detected", &[("prefix", prefix)]));
     }
     
     // 如果配置中有数据目录，优先使用
     if let Some(data_dir) = context.wechat_data_dir() {
-        println!("使用配置的微信数据目录: {:?}", data_dir);
+        println!("{}", tr_args("key-using-data-dir", &[("dir", format!("{:?}", data_dir))]));
     }
     
     // 设置更详细的日志级别，确保错误信息被捕获
     tracing::debug!("开始执行密钥提取，日志级别: {}", context.log_level());
+
+    if let Some(dump) = &args.from_dump {
+        return execute_from_dump(context, dump, &args).await;
+    }
     
     // 使用统一方法获取有效的主进程
-    let detector = create_process_detector()?;
+    let detector = create_process_detector_with_validation(context.data_dir_validation())?;
     
     let valid_main_processes = detector.detect_processes().await?;
     
     if valid_main_processes.is_empty() {
-        println!("❌ 未发现有效版本的微信主进程");
-        println!("   请确保：");
-        println!("   - 微信正在运行");
-        println!("   - 微信版本支持密钥提取");
-        println!("   - 程序有足够权限访问进程信息");
+        println!("{}", tr("key-no-process"));
+        println!("   {}", tr("key-no-process-checklist"));
+        println!("{}", tr("key-check-running"));
+        println!("{}", tr("key-check-version"));
+        println!("{}", tr("key-check-permission"));
         return Err(mwxdump_core::errors::WeChatError::ProcessNotFound.into());
     }
 
-    let key_extractor = key_extractor::create_key_extractor()?;
-    // tracing::info!("create key extractor: {}", );
+    let key_extractor = key_extractor::create_key_extractor_with_offsets(context.key_offsets().to_vec())?
+        .with_events(context.events().clone());
+
+    if args.all {
+        return execute_all(context, &key_extractor, &valid_main_processes, args.validate_dir.as_deref()).await;
+    }
 
+    // 多开时每个主进程对应一个账号，单个进程失败不影响其他账号
+    let mut keys = Vec::with_capacity(valid_main_processes.len());
+    let mut last_error = None;
     for process in valid_main_processes.iter() {
         tracing::info!("获取微信进程: {} 的加密密钥", process.pid);
-        let key = key_extractor.extract_key(process).await?;
-        tracing::info!("密钥获取成功：{}", key);
+        let (result, attempts) = key_extractor.extract_with_report(process).await;
+        
+        // 输出每个策略的尝试结果，便于微信更新后判断哪种特征需要维护
+        print_attempts(&attempts);
+        
+        match result {
+            Ok(key) => {
+                tracing::info!("密钥获取成功：{}", key);
+                keys.push(key);
+            }
+            Err(e) => {
+                tracing::warn!("进程 {} 密钥提取失败: {}", process.pid, e);
+                last_error = Some(e);
+            }
+        }
+    }
+    if let Some(e) = last_error.filter(|_| keys.is_empty()) {
+        return Err(e);
+    }
+
+    print_account_keys(&keys);
+
+    if let Some(dir) = &args.validate_dir {
+        for key in &keys {
+            validate_dir(key, dir).await?;
+        }
     }
     
     Ok(())
 }
 
+/// 在内存快照中搜索密钥，用账号数据目录中的数据库校验
+async fn execute_from_dump(context: &ExecutionContext, dump: &Path, args: &KeyArgs) -> Result<()> {
+    let data_dir = args
+        .data_dir
+        .clone()
+        .or_else(|| context.wechat_data_dir().map(PathBuf::from))
+        .ok_or_else(|| ConfigError::MissingKey {
+            key: "wechat.data_dir".to_string(),
+        })?;
+    println!(
+        "{}",
+        tr_args(
+            "key-from-dump",
+            &[("file", dump.display().to_string()), ("dir", data_dir.display().to_string())],
+        )
+    );
+
+    let limit = if args.all { MAX_DUMP_CANDIDATES } else { 1 };
+    let dump = dump.to_path_buf();
+    let dir = data_dir.clone();
+    let keys = task::spawn_blocking(
+        move || SnapshotKeyExtractor::open(&dump, &dir)?.extract_candidates(limit),
+        WeChatError::KeyExtractionFailed,
+    )
+    .await?;
+    if keys.is_empty() {
+        return Err(WeChatError::KeyExtractionFailed("快照中未找到有效密钥".to_string()).into());
+    }
+
+    let wxid = datadir::scan(&data_dir).ok().and_then(|info| info.wxid);
+    let keys: Vec<WeChatKey> = keys
+        .into_iter()
+        .map(|mut key| {
+            key.wxid = wxid.clone();
+            key.data_dir = Some(data_dir.clone());
+            key
+        })
+        .collect();
+    print_account_keys(&keys);
+
+    if let Some(dir) = &args.validate_dir {
+        for key in &keys {
+            validate_dir(key, dir).await?;
+        }
+    }
+    Ok(())
+}
+
+/// 以表格输出每个账号的密钥
+fn print_account_keys(keys: &[WeChatKey]) {
+    println!("{}", tr_args("key-accounts-found", &[("count", keys.len().to_string())]));
+    println!("{:<28} {:>8}  {}", tr("key-column-account"), "PID", tr("key-column-key"));
+    for key in keys {
+        println!("{:<28} {:>8}  {}", account_label(key), key.source_pid, key.to_hex());
+    }
+}
+
+/// 账号名称：优先使用 wxid，其次为数据目录
+fn account_label(key: &WeChatKey) -> String {
+    key.wxid
+        .clone()
+        .or_else(|| key.data_dir.as_ref().map(|dir| dir.display().to_string()))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// 收集所有进程中的候选密钥，按能解密的数据库数量排序输出
+async fn execute_all(
+    context: &ExecutionContext,
+    key_extractor: &KeyStrategyChain,
+    processes: &[WechatProcessInfo],
+    validate_dir: Option<&Path>,
+) -> Result<()> {
+    let mut candidates: Vec<WeChatKey> = Vec::new();
+    for process in processes {
+        tracing::info!("获取微信进程: {} 的所有候选密钥", process.pid);
+        let (keys, attempts) = key_extractor.extract_all_with_report(process).await;
+        print_attempts(&attempts);
+        candidates.extend(keys);
+    }
+
+    // 用配置的数据目录和各进程的数据目录试解密
+    let data_dirs = context
+        .wechat_data_dir()
+        .map(PathBuf::from)
+        .into_iter()
+        .chain(processes.iter().filter_map(|p| p.data_dir.clone()));
+    let mut db_storage_roots: Vec<PathBuf> = validate_dir.map(db_storage_dir).into_iter().collect();
+    for dir in data_dirs {
+        match datadir::scan(&dir) {
+            Ok(info) => db_storage_roots.extend(info.db_storage_path),
+            Err(e) => tracing::warn!("扫描数据目录 {:?} 失败: {}", dir, e),
+        }
+    }
+    db_storage_roots.sort();
+    db_storage_roots.dedup();
+
+    let ranked = rank_keys(candidates, &db_storage_roots).await?;
+    if ranked.is_empty() {
+        return Err(mwxdump_core::errors::WeChatError::KeyExtractionFailed("未找到候选密钥".to_string()).into());
+    }
+    println!("{}", tr_args("key-candidates-found", &[("count", ranked.len().to_string())]));
+    for (index, candidate) in ranked.iter().enumerate() {
+        println!(
+            "{}",
+            tr_args(
+                "key-candidate",
+                &[
+                    ("index", (index + 1).to_string()),
+                    ("key", candidate.key.to_hex()),
+                    ("pid", candidate.key.source_pid.to_string()),
+                    ("count", candidate.score().to_string()),
+                ],
+            )
+        );
+        for database in &candidate.databases {
+            println!("      {}", database.display());
+        }
+    }
+    Ok(())
+}
+
+/// 账号目录下有 db_storage 时使用该子目录
+fn db_storage_dir(dir: &Path) -> PathBuf {
+    let db_storage = dir.join("db_storage");
+    if db_storage.is_dir() {
+        db_storage
+    } else {
+        dir.to_path_buf()
+    }
+}
+
+/// 用目录中每个类别的数据库验证密钥，输出能否解密
+async fn validate_dir(key: &WeChatKey, dir: &Path) -> Result<()> {
+    let dir = db_storage_dir(dir);
+    println!("{}", tr_args("key-validate-dir", &[("dir", dir.display().to_string())]));
+    let results = validate_key_in_dir(&key.key_data, &dir).await?;
+    for result in &results {
+        let mut args = vec![
+            ("category", result.category.as_str().to_string()),
+            ("path", result.path.display().to_string()),
+        ];
+        match result.version {
+            Some(version) => {
+                args.push(("version", version.as_str().to_string()));
+                println!("{}", tr_args("key-validate-ok", &args));
+            }
+            None => println!("{}", tr_args("key-validate-failed", &args)),
+        }
+    }
+
+    let valid = results.iter().filter(|r| r.version.is_some()).count();
+    if valid == 0 {
+        println!("{}", tr("key-validate-none"));
+    } else {
+        println!(
+            "{}",
+            tr_args(
+                "key-validate-summary",
+                &[("valid", valid.to_string()), ("total", results.len().to_string())],
+            )
+        );
+    }
+    Ok(())
+}
+
+/// 输出每个策略的尝试结果
+fn print_attempts(attempts: &[StrategyAttempt]) {
+    for attempt in attempts {
+        let strategy = tr(&format!("key-strategy-{}", attempt.kind.as_str().replace('_', "-")));
+        match &attempt.error {
+            None => println!("{}", tr_args("key-strategy-succeeded", &[("strategy", strategy)])),
+            Some(error) => println!(
+                "{}",
+                tr_args("key-strategy-failed", &[("strategy", strategy), ("error", error.clone())])
+            ),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -63,7 +309,7 @@ mod tests {
         let context = ExecutionContext::with_defaults(Some("info".to_string()));
         
         // 这个测试在没有微信进程时应该正常完成
-        let result = execute(&context).await;
+        let result = execute(&context, KeyArgs::default()).await;
         // 注意：没有微信进程时会返回错误，这是预期的
         assert!(result.is_err());
     }
@@ -0,0 +1,198 @@
+//! 备份主密钥管理命令
+//!
+//! - `keys master init <DIR>`：在备份根目录创建主密钥，之后 `decrypt --snapshot --encrypt` 加密快照
+//! - `keys master passwd <DIR>`：修改主密钥口令，已加密的快照不受影响
+//! - `keys master status <DIR>`：显示主密钥指纹、已加密的快照数和清单签名公钥
+//! - `keys master share <SNAPSHOT>`：输出单个快照的归档密钥，凭此可用 `backups restore` 解密该快照
+//!
+//! 口令优先读取环境变量 `MWX_MASTER_PASSPHRASE`（修改口令时新口令读取 `MWX_NEW_MASTER_PASSPHRASE`），
+//! 未设置时在终端中输入。
+
+use clap::{Args, Subcommand};
+use dialoguer::Password;
+use std::path::{Path, PathBuf};
+
+use crate::cli::context::ExecutionContext;
+use crate::i18n::{tr, tr_args};
+use mwxdump_core::backup::signing::{self, SigningKeyFile};
+use mwxdump_core::backup::vault::{self, MasterVault, SecretKey, DEFAULT_KDF_ITERATIONS};
+use mwxdump_core::backup::{list_snapshots, BackupCatalog};
+use mwxdump_core::errors::{ConfigError, Result, VaultError};
+
+/// 主密钥口令的环境变量
+pub const PASSPHRASE_ENV: &str = "MWX_MASTER_PASSPHRASE";
+
+/// 修改口令时新口令的环境变量
+const NEW_PASSPHRASE_ENV: &str = "MWX_NEW_MASTER_PASSPHRASE";
+
+/// 备份密钥管理参数
+#[derive(Args, Debug)]
+pub struct KeysArgs {
+    #[command(subcommand)]
+    pub command: KeysCommand,
+}
+
+/// 备份密钥管理子命令
+#[derive(Subcommand, Debug)]
+pub enum KeysCommand {
+    /// 管理加密备份快照的主密钥
+    Master(MasterArgs),
+}
+
+/// 主密钥管理参数
+#[derive(Args, Debug)]
+pub struct MasterArgs {
+    #[command(subcommand)]
+    pub command: MasterCommand,
+}
+
+/// 主密钥管理子命令
+#[derive(Subcommand, Debug)]
+pub enum MasterCommand {
+    /// 在备份根目录创建主密钥
+    Init {
+        /// 备份根目录（decrypt --snapshot 的输出目录）
+        #[arg(value_name = "DIR")]
+        dir: PathBuf,
+    },
+
+    /// 修改主密钥口令
+    Passwd {
+        /// 备份根目录
+        #[arg(value_name = "DIR")]
+        dir: PathBuf,
+    },
+
+    /// 显示主密钥和加密快照的状态
+    Status {
+        /// 备份根目录
+        #[arg(value_name = "DIR")]
+        dir: PathBuf,
+    },
+
+    /// 输出单个快照的归档密钥，用于分享该快照
+    Share {
+        /// 加密的快照目录
+        #[arg(value_name = "SNAPSHOT")]
+        snapshot: PathBuf,
+    },
+}
+
+/// 执行备份密钥管理命令
+pub async fn execute(context: &ExecutionContext, args: KeysArgs) -> Result<()> {
+    let KeysCommand::Master(args) = args.command;
+    match args.command {
+        MasterCommand::Init { dir } => {
+            let passphrase = passphrase(context, PASSPHRASE_ENV, &tr("master-prompt-new"), true)?;
+            let (vault, _) = MasterVault::create(&dir, &passphrase, DEFAULT_KDF_ITERATIONS)?;
+            println!(
+                "{}",
+                tr_args(
+                    "master-created",
+                    &[
+                        ("file", dir.join(vault::VAULT_FILE).display().to_string()),
+                        ("fingerprint", vault.fingerprint),
+                    ],
+                )
+            );
+        }
+        MasterCommand::Passwd { dir } => {
+            let old = passphrase(context, PASSPHRASE_ENV, &tr("master-prompt"), false)?;
+            let new = passphrase(context, NEW_PASSPHRASE_ENV, &tr("master-prompt-new"), true)?;
+            MasterVault::change_passphrase(&dir, &old, &new)?;
+            println!("{}", tr("master-passwd-changed"));
+        }
+        MasterCommand::Status { dir } => {
+            let Some(vault) = MasterVault::load(&dir)? else {
+                println!("{}", tr_args("master-missing", &[("dir", dir.display().to_string())]));
+                return Ok(());
+            };
+            let snapshots = list_snapshots(&dir)?;
+            let encrypted = snapshots.iter().filter(|s| vault::is_encrypted(&s.path)).count();
+            let recorded = BackupCatalog::load(&dir)?.backups.iter().filter(|b| b.encrypted).count();
+            println!(
+                "{}",
+                tr_args(
+                    "master-status",
+                    &[
+                        ("fingerprint", vault.fingerprint),
+                        ("iterations", vault.iterations.to_string()),
+                        ("encrypted", encrypted.max(recorded).to_string()),
+                        ("snapshots", snapshots.len().to_string()),
+                    ],
+                )
+            );
+            // 公钥需另行保存，校验时用 verify --public-key 指定
+            if let Some(file) = SigningKeyFile::load(&dir)? {
+                let fingerprint = signing::public_key_fingerprint(&file.public_key);
+                println!(
+                    "{}",
+                    tr_args(
+                        "master-signing-key",
+                        &[("public_key", file.public_key), ("fingerprint", fingerprint)],
+                    )
+                );
+            }
+        }
+        MasterCommand::Share { snapshot } => {
+            let master = unlock(context, &snapshot_root(&snapshot))?;
+            let key = vault::archive_key(&snapshot, &master)?;
+            eprintln!("{}", tr_args("master-share-hint", &[("snapshot", snapshot.display().to_string())]));
+            println!("{}", key.to_hex());
+        }
+    }
+    Ok(())
+}
+
+/// 解开备份根目录的主密钥
+pub fn unlock(context: &ExecutionContext, root: &Path) -> Result<SecretKey> {
+    let vault = MasterVault::load(root)?.ok_or_else(|| VaultError::MasterNotFound {
+        dir: root.display().to_string(),
+    })?;
+    vault.unlock(&passphrase(context, PASSPHRASE_ENV, &tr("master-prompt"), false)?)
+}
+
+/// 快照所在的备份根目录
+pub fn snapshot_root(snapshot: &Path) -> PathBuf {
+    snapshot
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .map_or_else(|| PathBuf::from("."), Path::to_path_buf)
+}
+
+/// 读取口令：优先使用环境变量，否则在终端中输入
+fn passphrase(context: &ExecutionContext, env: &str, prompt: &str, confirm: bool) -> Result<String> {
+    let value = match std::env::var(env) {
+        Ok(value) => value,
+        Err(_) if !context.is_interactive() => {
+            return Err(ConfigError::MissingKey { key: env.to_string() }.into());
+        }
+        Err(_) => {
+            let mut input = Password::new().with_prompt(prompt);
+            if confirm {
+                input = input.with_confirmation(tr("master-prompt-confirm"), tr("master-prompt-mismatch"));
+            }
+            input.interact()?
+        }
+    };
+    // 空口令等于不加密，环境变量为空时同样拒绝
+    if value.is_empty() {
+        return Err(ConfigError::InvalidValue {
+            key: env.to_string(),
+            value: String::new(),
+        }
+        .into());
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_root() {
+        assert_eq!(snapshot_root(Path::new("backups/20240501-093000")), PathBuf::from("backups"));
+        assert_eq!(snapshot_root(Path::new("20240501-093000")), PathBuf::from("."));
+    }
+}
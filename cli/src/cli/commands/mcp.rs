@@ -0,0 +1,81 @@
+//! MCP 服务命令
+//!
+//! 让大模型客户端直接查询解密后的聊天数据。默认经标准输入输出通信，供客户端以子进程方式启动；
+//! `--transport sse` 时启动 HTTP 服务，供远程客户端连接。
+
+use chrono::{Local, Offset};
+use clap::{Args, ValueEnum};
+
+use crate::cli::context::ExecutionContext;
+use crate::i18n::tr_args;
+use crate::server::mcp::{self, McpState};
+use mwxdump_core::errors::Result;
+use mwxdump_core::mcp::{serve_stdio, McpServer, ResourceProvider};
+use mwxdump_core::scopes::{Scope, Scopes};
+
+/// 传输方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum McpTransport {
+    /// 标准输入输出，每行一条 JSON-RPC 消息
+    Stdio,
+    /// HTTP + SSE
+    Sse,
+}
+
+/// MCP 服务参数
+#[derive(Args, Debug)]
+pub struct McpArgs {
+    /// 传输方式
+    #[arg(long, value_enum, default_value_t = McpTransport::Stdio)]
+    pub transport: McpTransport,
+
+    /// 监听地址（仅 SSE，默认取配置 http.host）
+    #[arg(long)]
+    pub host: Option<String>,
+
+    /// 监听端口（仅 SSE，默认取配置 http.port）
+    #[arg(short, long)]
+    pub port: Option<u16>,
+
+    /// 限制标准输入输出会话的权限范围，如 read:messages,read:contacts（默认全部）；
+    /// SSE 会话的权限由访问令牌决定
+    #[arg(long = "scope", value_delimiter = ',', value_name = "SCOPE")]
+    pub scopes: Vec<Scope>,
+}
+
+impl McpArgs {
+    /// 是否经标准输出传输协议消息，此时日志只能写到标准错误
+    pub fn uses_stdout(&self) -> bool {
+        self.transport == McpTransport::Stdio
+    }
+}
+
+/// 启动 MCP 服务，输入结束或 Ctrl-C 后停止
+pub async fn execute(context: &ExecutionContext, args: McpArgs) -> Result<()> {
+    let work_dir = context.database_config().work_dir.clone();
+    // 按本机时区划分月份和日期
+    let offset = Local::now().offset().fix();
+    let shutdown = context.cancellation_token();
+
+    match args.transport {
+        McpTransport::Stdio => {
+            let scopes = if args.scopes.is_empty() { Scopes::all() } else { Scopes::new(args.scopes) };
+            let server = McpServer::new(
+                ResourceProvider::new(&work_dir)
+                    .with_scopes(scopes)
+                    .with_utc_offset(offset),
+            )
+            .with_events(context.events().clone());
+            tracing::info!("MCP 服务经标准输入输出提供，数据目录: {}", work_dir.display());
+            serve_stdio(&server, shutdown).await
+        }
+        McpTransport::Sse => {
+            let http = context.http_config();
+            let host = args.host.unwrap_or_else(|| http.host.clone());
+            let port = args.port.unwrap_or(http.port);
+            println!("{}", tr_args("mcp-sse-listening", &[("addr", format!("{}:{}", host, port))]));
+            let state = McpState::new(context.config().clone(), work_dir, offset).with_events(context.events().clone());
+            mcp::serve(state, &host, port, shutdown).await
+        }
+    }
+}
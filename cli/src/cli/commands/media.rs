@@ -0,0 +1,160 @@
+//! 媒体文件命令
+//!
+//! - `media decode`：把数据目录下的 `.dat` 图片缓存并行解码为 JPG/PNG/GIF 等图片
+//! - `media voices`：从解密后的媒体数据库导出语音，转码为 WAV/MP3
+
+use clap::{Args, Subcommand};
+use std::path::PathBuf;
+
+use crate::cli::context::ExecutionContext;
+use crate::i18n::tr_args;
+use mwxdump_core::errors::{ConfigError, Result};
+use mwxdump_core::wechat::db::voices::VoiceRepository;
+use mwxdump_core::wechat::media::{AudioFormat, DatDecoder, VoiceTranscoder};
+
+/// 媒体文件参数
+#[derive(Args, Debug)]
+pub struct MediaArgs {
+    #[command(subcommand)]
+    pub command: MediaCommand,
+}
+
+/// 媒体文件子命令
+#[derive(Subcommand, Debug)]
+pub enum MediaCommand {
+    /// 解码 `.dat` 图片缓存
+    Decode {
+        /// 输入目录，如账号数据目录、`msg/attach` 或 3.x 的 `FileStorage`（默认取配置 wechat.data_dir）
+        #[arg(short, long, value_name = "DIR")]
+        input: Option<PathBuf>,
+
+        /// 输出目录，图片保持原有的相对路径
+        #[arg(short, long, value_name = "DIR")]
+        output: PathBuf,
+
+        /// 4.x V2 格式图片的 AES 密钥（16个字符）
+        #[arg(long, value_name = "KEY", value_parser = parse_image_key)]
+        image_key: Option<[u8; 16]>,
+
+        /// 4.x 格式的异或字节（十六进制，如 0x37），默认由图片结尾推断
+        #[arg(long, value_name = "BYTE", value_parser = parse_xor_key)]
+        xor_key: Option<u8>,
+
+        /// 同时解码的文件数（默认为 CPU 核心数）
+        #[arg(long)]
+        threads: Option<usize>,
+    },
+
+    /// 导出语音消息
+    Voices {
+        /// 输出目录，语音按会话分目录保存
+        #[arg(short, long, value_name = "DIR")]
+        output: PathBuf,
+
+        /// 只导出指定会话（wxid 或群ID）
+        #[arg(long, value_name = "WXID")]
+        talker: Option<String>,
+
+        /// 音频格式（silk/wav/mp3）
+        #[arg(short, long, default_value = "wav")]
+        format: AudioFormat,
+    },
+}
+
+/// 解析图片 AES 密钥
+fn parse_image_key(value: &str) -> std::result::Result<[u8; 16], String> {
+    value
+        .as_bytes()
+        .try_into()
+        .map_err(|_| format!("图片密钥应为16个字符，实际为 {} 个", value.len()))
+}
+
+/// 解析十六进制的异或字节
+fn parse_xor_key(value: &str) -> std::result::Result<u8, String> {
+    let digits = value.trim_start_matches("0x").trim_start_matches("0X");
+    u8::from_str_radix(digits, 16).map_err(|e| format!("无效的异或字节 {}: {}", value, e))
+}
+
+/// 执行媒体文件命令
+pub async fn execute(context: &ExecutionContext, args: MediaArgs) -> Result<()> {
+    match args.command {
+        MediaCommand::Decode {
+            input,
+            output,
+            image_key,
+            xor_key,
+            threads,
+        } => {
+            let input = input
+                .or_else(|| context.wechat_data_dir().map(PathBuf::from))
+                .ok_or_else(|| ConfigError::MissingKey {
+                    key: "wechat.data_dir".to_string(),
+                })?;
+            let mut decoder = DatDecoder::new();
+            if let Some(key) = image_key {
+                decoder = decoder.with_aes_key(key);
+            }
+            if let Some(key) = xor_key {
+                decoder = decoder.with_xor_key(key);
+            }
+            let threads = threads
+                .filter(|&n| n > 0)
+                .unwrap_or_else(|| std::thread::available_parallelism().map_or(4, |n| n.get()));
+            tracing::info!("🖼️ 解码图片缓存 {:?} -> {:?}（{} 个并发）", input, output, threads);
+            let report = decoder
+                .decode_tree(&input, &output, threads, &context.cancellation_token())
+                .await?;
+            println!(
+                "{}",
+                tr_args(
+                    "media-decode-summary",
+                    &[
+                        ("decoded", report.decoded.to_string()),
+                        ("files", report.files.to_string()),
+                        ("skipped", report.skipped.to_string()),
+                        ("failed", report.failed.to_string()),
+                    ],
+                )
+            );
+            Ok(())
+        }
+        MediaCommand::Voices { output, talker, format } => {
+            let work_dir = &context.database_config().work_dir;
+            let repository = VoiceRepository::open(work_dir).await?;
+            if repository.shard_count() == 0 {
+                return Err(anyhow::anyhow!("工作目录 {:?} 中没有解密后的媒体数据库（media_*.db）", work_dir));
+            }
+            tracing::info!("🎙️ 导出语音 -> {:?}（{}）", output, format.extension());
+            let report = VoiceTranscoder::new(context.config().voice.clone())
+                .extract_all(&repository, talker.as_deref(), format, &output, &context.cancellation_token())
+                .await?;
+            println!(
+                "{}",
+                tr_args(
+                    "media-voices-summary",
+                    &[
+                        ("converted", report.converted.to_string()),
+                        ("voices", report.voices.to_string()),
+                        ("skipped", report.skipped.to_string()),
+                        ("failed", report.failed.to_string()),
+                    ],
+                )
+            );
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_keys() {
+        assert_eq!(parse_xor_key("0x37"), Ok(0x37));
+        assert_eq!(parse_xor_key("a1"), Ok(0xA1));
+        assert!(parse_xor_key("0x100").is_err());
+        assert_eq!(parse_image_key("0123456789abcdef").unwrap(), *b"0123456789abcdef");
+        assert!(parse_image_key("short").is_err());
+    }
+}
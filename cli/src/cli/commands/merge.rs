@@ -0,0 +1,61 @@
+//! 合并命令
+//!
+//! 把电脑版、iOS、安卓等来源的工作目录合并为一个工作目录，`--cross-device` 对齐不同设备上的同一条消息。
+
+use clap::Args;
+use std::path::PathBuf;
+
+use crate::cli::context::ExecutionContext;
+use crate::i18n::tr_args;
+use mwxdump_core::errors::Result;
+use mwxdump_core::wechat::merge::{ArchiveMerger, DEFAULT_TOLERANCE_SECS};
+
+/// 合并参数
+#[derive(Args, Debug)]
+pub struct MergeArgs {
+    /// 要合并的工作目录，重复的消息保留先列出的目录中的一条
+    #[arg(required = true, num_args = 1.., value_name = "DIR")]
+    pub sources: Vec<PathBuf>,
+
+    /// 输出的工作目录
+    #[arg(short, long, value_name = "DIR")]
+    pub output: PathBuf,
+
+    /// 跨设备对齐：按时间容差和内容去掉不同来源中的同一条消息
+    #[arg(long)]
+    pub cross_device: bool,
+
+    /// 跨设备对齐的时间容差（秒）
+    #[arg(long, default_value_t = DEFAULT_TOLERANCE_SECS, value_name = "SECS", requires = "cross_device")]
+    pub tolerance: i64,
+
+    /// 当前账号的 wxid，用于识别自己发送的消息
+    #[arg(long, value_name = "WXID")]
+    pub self_wxid: Option<String>,
+}
+
+/// 合并工作目录
+pub async fn execute(context: &ExecutionContext, args: MergeArgs) -> Result<()> {
+    let mut merger = ArchiveMerger::new(args.sources)
+        .with_cross_device(args.cross_device)
+        .with_tolerance(args.tolerance)
+        .with_cancellation(context.cancellation_token());
+    if let Some(wxid) = args.self_wxid {
+        merger = merger.with_self_wxid(wxid);
+    }
+    let report = merger.merge(&args.output).await?;
+    println!(
+        "{}",
+        tr_args(
+            "merge-summary",
+            &[
+                ("sources", report.sources.to_string()),
+                ("conversations", report.conversations.to_string()),
+                ("messages", report.messages.to_string()),
+                ("duplicates", report.duplicates.to_string()),
+                ("output", args.output.display().to_string()),
+            ],
+        )
+    );
+    Ok(())
+}
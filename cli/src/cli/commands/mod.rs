@@ -1,8 +1,25 @@
 //! CLI命令实现模块
 
 pub mod server;
+pub mod mcp;
 pub mod version;
 pub mod dump_memory;
+pub mod devtool;
+pub mod config;
 pub mod process;
 pub mod key;
-pub mod decrypt;
\ No newline at end of file
+pub mod master;
+pub mod decrypt;
+pub mod setup;
+pub mod info;
+pub mod contacts;
+pub mod sql;
+pub mod export;
+pub mod backups;
+pub mod service;
+pub mod media;
+pub mod merge;
+pub mod tail;
+pub mod verify;
+#[cfg(feature = "ocr")]
+pub mod ocr;
\ No newline at end of file
@@ -0,0 +1,65 @@
+//! 图片文字识别命令
+//!
+//! 对导出目录中的图片运行 OCR 并写入工作目录下的全文索引，或在索引中搜索图片文字。
+
+use clap::Args;
+use std::path::PathBuf;
+
+use crate::cli::context::ExecutionContext;
+use crate::i18n::tr_args;
+use mwxdump_core::errors::Result;
+use mwxdump_core::wechat::media::{CommandOcr, OcrIndexer};
+use mwxdump_core::wechat::search::{SearchIndex, SEARCH_INDEX_FILE};
+
+/// OCR 参数
+#[derive(Args, Debug)]
+#[command(group = clap::ArgGroup::new("mode").required(true).args(["dir", "search"]))]
+pub struct OcrArgs {
+    /// 需要识别的图片目录（递归扫描）
+    #[arg(value_name = "DIR")]
+    pub dir: Option<PathBuf>,
+
+    /// 在已识别的图片文字中搜索
+    #[arg(short, long, value_name = "TEXT")]
+    pub search: Option<String>,
+
+    /// 重新识别已索引的图片
+    #[arg(long)]
+    pub force: bool,
+
+    /// 最多显示的搜索结果数
+    #[arg(long, default_value_t = 20)]
+    pub limit: usize,
+}
+
+/// 识别图片或搜索图片文字
+pub async fn execute(context: &ExecutionContext, args: OcrArgs) -> Result<()> {
+    let index = SearchIndex::open(&context.database_config().work_dir.join(SEARCH_INDEX_FILE)).await?;
+
+    if let Some(text) = &args.search {
+        let hits = index.search(text, args.limit).await?;
+        for hit in &hits {
+            println!("{}\n    {}", hit.source, hit.content.replace('\n', " "));
+        }
+        println!("{}", tr_args("ocr-hit-count", &[("count", hits.len().to_string())]));
+        return Ok(());
+    }
+
+    let Some(dir) = args.dir else {
+        return Ok(());
+    };
+    let indexer = OcrIndexer::new(CommandOcr::new(context.config().ocr.clone()), index);
+    let summary = indexer.index_dir(&dir, args.force).await?;
+    println!(
+        "{}",
+        tr_args(
+            "ocr-summary",
+            &[
+                ("indexed", summary.indexed.to_string()),
+                ("skipped", summary.skipped.to_string()),
+                ("failed", summary.failed.to_string()),
+            ],
+        )
+    );
+    Ok(())
+}
@@ -3,8 +3,9 @@
 use anyhow::Context;
 
 use crate::cli::context::ExecutionContext;
+use crate::i18n::{tr, tr_args};
 use mwxdump_core::errors::Result;
-use mwxdump_core::wechat::process::{create_process_detector, ProcessDetector};
+use mwxdump_core::wechat::process::{create_process_detector_with_validation, ProcessDetector};
 /// 执行进程检测测试
 pub async fn execute(context: &ExecutionContext) -> Result<()> {
     tracing::info!("开始测试微信进程检测功能...");
@@ -14,7 +15,8 @@ pub async fn execute(context: &ExecutionContext) -> Result<()> {
         tracing::debug!("配置的微信数据目录: {:?}", data_dir);
     }
 
-    let detector = create_process_detector().context("初始化检测器失败")?;
+    let detector = create_process_detector_with_validation(context.data_dir_validation())
+        .context("初始化检测器失败")?;
 
     let processes = detector
         .detect_processes()
@@ -22,30 +24,34 @@ pub async fn execute(context: &ExecutionContext) -> Result<()> {
         .context("检测微信进程失败")?;
 
     if processes.is_empty() {
-        eprintln!("✅ 进程检测功能正常，但未发现运行中的微信进程");
+        eprintln!("{}", tr("process-none"));
     } else {
-        eprintln!("✅ 检测到 {} 个微信进程:", processes.len());
+        eprintln!("{}", tr_args("process-found", &[("count", processes.len().to_string())]));
         for (i, process) in processes.iter().enumerate() {
-            eprintln!("  {}. 进程名: {}", i + 1, process.name);
-            eprintln!("     PID: {}", process.pid);
-            eprintln!("     是否主进程: {}", process.is_main_process);
-            eprintln!("     路径: {:?}", process.path);
-            eprintln!("     版本: {:?}", process.version);
+            eprintln!(
+                "  {}",
+                tr_args("process-name", &[("index", (i + 1).to_string()), ("name", process.name.clone())])
+            );
+            eprintln!("     {}", tr_args("process-pid", &[("pid", process.pid.to_string())]));
+            eprintln!("     {}", tr_args("process-is-main", &[("value", process.is_main_process.to_string())]));
+            eprintln!("     {}", tr_args("process-path", &[("path", format!("{:?}", process.path))]));
+            eprintln!("     {}", tr_args("process-version", &[("version", process.version.to_string())]));
             
             if let Some(data_dir) = &process.data_dir {
-                eprintln!("     数据目录: {:?}", data_dir);
-                eprintln!("     微信ID: {}", process.get_current_wxid().unwrap_or("未找到".to_string()));
+                eprintln!("     {}", tr_args("process-data-dir", &[("dir", format!("{:?}", data_dir))]));
+                let wxid = process.get_current_wxid().unwrap_or_else(|| tr("process-wxid-missing"));
+                eprintln!("     {}", tr_args("process-wxid", &[("wxid", wxid)]));
             
             } else {
-                eprintln!("     数据目录: 未找到");
+                eprintln!("     {}", tr("process-data-dir-missing"));
             }
             eprintln!(
-                "     检测时间: {}",
-                process.detected_at.format("%Y-%m-%d %H:%M:%S")
+                "     {}",
+                tr_args("process-detected-at", &[("time", process.detected_at.format("%Y-%m-%d %H:%M:%S").to_string())])
             );
             eprintln!();
         }
     }
-    eprintln!("进程检测测试完成！");
+    eprintln!("{}", tr("process-done"));
     Ok(())
 }
@@ -1,14 +1,63 @@
 //! 服务器命令实现
 
+use clap::Args;
+
+use crate::cli::context::ExecutionContext;
+use crate::i18n::tr_args;
+use crate::server::{self, ServerState};
 use mwxdump_core::errors::Result;
 
-/// 执行服务器命令
-pub async fn execute(host: String, port: u16, daemon: bool) -> Result<()> {
-    println!("正在启动HTTP服务器...");
-    println!("监听地址: {}:{}", host, port);
-    if daemon {
-        println!("后台运行模式");
+/// HTTP服务参数
+#[derive(Args, Debug)]
+pub struct ServerArgs {
+    /// 监听地址（默认取配置 http.host）
+    #[arg(long)]
+    pub host: Option<String>,
+
+    /// 监听端口（默认取配置 http.port）
+    #[arg(short, long)]
+    pub port: Option<u16>,
+
+    /// 临时会话模式：不写入明文工作目录，解锁后数据只保存在临时目录并在退出时清除
+    #[arg(long)]
+    pub ephemeral: bool,
+
+    /// 监控微信数据目录，变化的数据库自动重新解密并推送 data_updated 事件
+    #[arg(long)]
+    pub watch: bool,
+
+    /// 允许查询接口执行原始 SQL（连接仍为只读）
+    #[arg(long)]
+    pub allow_raw_sql: bool,
+}
+
+/// 执行服务器命令，Ctrl-C 后停止
+pub async fn execute(context: &ExecutionContext, args: ServerArgs) -> Result<()> {
+    let http = context.http_config();
+    let host = args.host.unwrap_or_else(|| http.host.clone());
+    let port = args.port.unwrap_or(http.port);
+
+    println!("{}", tr_args("server-listening", &[("addr", format!("{}:{}", host, port))]));
+    let shutdown = context.cancellation_token();
+    let state = ServerState::new(context.config().clone())
+        .with_ephemeral(args.ephemeral)
+        .with_raw_sql(args.allow_raw_sql)
+        .with_shutdown(shutdown.clone());
+    if context.is_auto_decrypt_enabled() {
+        if args.ephemeral {
+            tracing::warn!("临时会话模式下不自动解密，请通过 /api/v1/session/unlock 解锁");
+        } else {
+            let id = server::jobs::spawn_auto_decrypt(&state);
+            println!("{}", tr_args("server-auto-decrypt", &[("id", id.to_string())]));
+        }
+    }
+    if args.watch {
+        let refresh = server::refresh::run(state.clone(), shutdown.clone());
+        tokio::spawn(async move {
+            if let Err(e) = refresh.await {
+                tracing::error!("自动刷新已停止: {}", e);
+            }
+        });
     }
-    // TODO: 实现HTTP服务器逻辑
-    Ok(())
-}
\ No newline at end of file
+    server::serve(state, &host, port, shutdown).await
+}
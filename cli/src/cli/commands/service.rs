@@ -0,0 +1,332 @@
+//! 定时备份服务命令
+//!
+//! 按计划以当前配置执行 `decrypt --snapshot` 快照备份：Windows 上注册计划任务（schtasks），
+//! macOS 上安装 launchd 用户代理；其他平台输出可添加到 crontab 的命令行。
+//!
+//! 定时备份无人值守运行，无法交互输入，也不能假定微信正在运行，因此注册前要求配置文件中
+//! 给出 `wechat.data_dir` 和 `wechat.data_key`。
+
+use clap::{Args, Subcommand, ValueEnum};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::cli::context::ExecutionContext;
+use crate::i18n::{tr, tr_args};
+use mwxdump_core::errors::{ConfigError, Result};
+
+/// 计划任务名称（Windows）
+pub const TASK_NAME: &str = "MwXdump Backup";
+
+/// launchd 代理标签（macOS）
+pub const LAUNCHD_LABEL: &str = "com.mwxdump.backup";
+
+/// 备份频率
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Schedule {
+    /// 每小时
+    Hourly,
+    /// 每天
+    Daily,
+    /// 每周日
+    Weekly,
+}
+
+/// 服务参数
+#[derive(Args, Debug)]
+pub struct ServiceArgs {
+    #[command(subcommand)]
+    pub command: ServiceCommand,
+}
+
+/// 服务子命令
+#[derive(Subcommand, Debug)]
+pub enum ServiceCommand {
+    /// 注册定时备份
+    Install {
+        /// 备份频率
+        #[arg(long, value_enum, default_value_t = Schedule::Daily)]
+        schedule: Schedule,
+
+        /// 执行时间（HH:MM，每小时执行时只使用分钟）
+        #[arg(long, default_value = "03:00")]
+        time: String,
+
+        /// 备份根目录（默认为工作目录下的 backups）
+        #[arg(short, long, value_name = "DIR")]
+        output: Option<PathBuf>,
+    },
+    /// 删除定时备份
+    Uninstall,
+    /// 查看定时备份状态
+    Status,
+}
+
+/// 执行服务命令
+pub async fn execute(context: &ExecutionContext, args: ServiceArgs) -> Result<()> {
+    match args.command {
+        ServiceCommand::Install { schedule, time, output } => {
+            let (hour, minute) = parse_time(&time)?;
+            check_backup_config(context.config_path(), context.wechat_data_dir(), context.wechat_data_key())?;
+            let exe = std::env::current_exe()?;
+            let config = context.config_path().map(std::path::absolute).transpose()?;
+            let output = std::path::absolute(
+                output.unwrap_or_else(|| context.database_config().work_dir.join("backups")),
+            )?;
+            let command = backup_command(&exe, config.as_deref(), &output);
+            install(&command, schedule, hour, minute)?;
+            println!("{}", tr_args("service-installed", &[("command", command.join(" "))]));
+        }
+        ServiceCommand::Uninstall => {
+            uninstall()?;
+            println!("{}", tr_args("service-uninstalled", &[("name", TASK_NAME.to_string())]));
+        }
+        ServiceCommand::Status => status()?,
+    }
+    Ok(())
+}
+
+/// 解析 `HH:MM`
+fn parse_time(time: &str) -> Result<(u32, u32)> {
+    let invalid = || ConfigError::InvalidValue {
+        key: "time".to_string(),
+        value: time.to_string(),
+    };
+    let (hour, minute) = time.split_once(':').ok_or_else(invalid)?;
+    let hour: u32 = hour.parse().map_err(|_| invalid())?;
+    let minute: u32 = minute.parse().map_err(|_| invalid())?;
+    if hour > 23 || minute > 59 {
+        return Err(invalid().into());
+    }
+    Ok((hour, minute))
+}
+
+/// 检查定时备份所需的配置，缺少时给出需要补充的配置项
+fn check_backup_config(config: Option<&Path>, data_dir: Option<&Path>, data_key: Option<&str>) -> Result<()> {
+    if config.is_none() {
+        anyhow::bail!(tr("service-config-required"));
+    }
+    let Some(data_dir) = data_dir else {
+        anyhow::bail!(tr_args("service-config-missing", &[("key", "wechat.data_dir".to_string())]));
+    };
+    if !data_dir.is_dir() {
+        anyhow::bail!(tr_args("service-data-dir-not-found", &[("path", data_dir.display().to_string())]));
+    }
+    if data_key.is_none_or(|key| key.trim().is_empty()) {
+        anyhow::bail!(tr_args("service-config-missing", &[("key", "wechat.data_key".to_string())]));
+    }
+    Ok(())
+}
+
+/// 定时执行的备份命令行
+fn backup_command(exe: &Path, config: Option<&Path>, output: &Path) -> Vec<String> {
+    let mut command = vec![exe.display().to_string()];
+    if let Some(config) = config {
+        command.extend(["--config".to_string(), config.display().to_string()]);
+    }
+    command.extend(["decrypt", "--snapshot", "-o"].map(String::from));
+    command.push(output.display().to_string());
+    command
+}
+
+/// 运行系统命令，失败时带上错误输出
+fn run(program: &str, args: &[String]) -> Result<String> {
+    let output = Command::new(program).args(args).output()?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "{} 执行失败: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// schtasks 的 `/TR` 参数，含空格的参数加引号
+fn windows_command_line(command: &[String]) -> String {
+    command
+        .iter()
+        .map(|arg| if arg.contains(' ') { format!("\"{}\"", arg) } else { arg.clone() })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 创建计划任务的 schtasks 参数
+fn schtasks_create_args(command: &[String], schedule: Schedule, hour: u32, minute: u32) -> Vec<String> {
+    let mut args = vec![
+        "/Create".to_string(),
+        "/TN".to_string(),
+        TASK_NAME.to_string(),
+        "/TR".to_string(),
+        windows_command_line(command),
+        "/SC".to_string(),
+    ];
+    match schedule {
+        Schedule::Hourly => args.push("HOURLY".to_string()),
+        Schedule::Daily => args.push("DAILY".to_string()),
+        Schedule::Weekly => args.extend(["WEEKLY", "/D", "SUN"].map(String::from)),
+    }
+    args.extend(["/ST".to_string(), format!("{:02}:{:02}", hour, minute), "/F".to_string()]);
+    args
+}
+
+/// launchd 代理的 plist 内容
+fn launchd_plist(command: &[String], schedule: Schedule, hour: u32, minute: u32, log: &Path) -> String {
+    let escape = |s: &str| s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+    let arguments: String = command
+        .iter()
+        .map(|arg| format!("        <string>{}</string>\n", escape(arg)))
+        .collect();
+    let mut interval = format!("        <key>Minute</key><integer>{}</integer>\n", minute);
+    if schedule != Schedule::Hourly {
+        interval.push_str(&format!("        <key>Hour</key><integer>{}</integer>\n", hour));
+    }
+    if schedule == Schedule::Weekly {
+        interval.push_str("        <key>Weekday</key><integer>0</integer>\n");
+    }
+    let log = escape(&log.display().to_string());
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{LAUNCHD_LABEL}</string>
+    <key>ProgramArguments</key>
+    <array>
+{arguments}    </array>
+    <key>StartCalendarInterval</key>
+    <dict>
+{interval}    </dict>
+    <key>StandardOutPath</key>
+    <string>{log}</string>
+    <key>StandardErrorPath</key>
+    <string>{log}</string>
+</dict>
+</plist>
+"#
+    )
+}
+
+/// crontab 条目
+fn cron_line(command: &[String], schedule: Schedule, hour: u32, minute: u32) -> String {
+    let timing = match schedule {
+        Schedule::Hourly => format!("{} * * * *", minute),
+        Schedule::Daily => format!("{} {} * * *", minute, hour),
+        Schedule::Weekly => format!("{} {} * * 0", minute, hour),
+    };
+    let command: Vec<String> = command
+        .iter()
+        .map(|arg| if arg.contains(' ') { format!("'{}'", arg) } else { arg.clone() })
+        .collect();
+    format!("{} {}", timing, command.join(" "))
+}
+
+/// launchd 代理文件路径
+fn launchd_plist_path() -> Result<PathBuf> {
+    let home = std::env::var_os("HOME").ok_or_else(|| ConfigError::MissingKey { key: "HOME".to_string() })?;
+    Ok(PathBuf::from(home)
+        .join("Library/LaunchAgents")
+        .join(format!("{}.plist", LAUNCHD_LABEL)))
+}
+
+fn install(command: &[String], schedule: Schedule, hour: u32, minute: u32) -> Result<()> {
+    if cfg!(windows) {
+        run("schtasks", &schtasks_create_args(command, schedule, hour, minute))?;
+    } else if cfg!(target_os = "macos") {
+        let path = launchd_plist_path()?;
+        let log = path
+            .ancestors()
+            .nth(2)
+            .unwrap_or(Path::new("/tmp"))
+            .join("Logs/mwxdump-backup.log");
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, launchd_plist(command, schedule, hour, minute, &log))?;
+        let _ = run("launchctl", &["unload".to_string(), path.display().to_string()]);
+        run("launchctl", &["load".to_string(), "-w".to_string(), path.display().to_string()])?;
+    } else {
+        println!("{}", tr_args("service-cron-hint", &[("line", cron_line(command, schedule, hour, minute))]));
+    }
+    Ok(())
+}
+
+fn uninstall() -> Result<()> {
+    if cfg!(windows) {
+        run("schtasks", &["/Delete", "/TN", TASK_NAME, "/F"].map(String::from))?;
+    } else if cfg!(target_os = "macos") {
+        let path = launchd_plist_path()?;
+        let _ = run("launchctl", &["unload".to_string(), path.display().to_string()]);
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+    } else {
+        println!("{}", tr_args("service-unsupported", &[("os", std::env::consts::OS.to_string())]));
+    }
+    Ok(())
+}
+
+fn status() -> Result<()> {
+    if cfg!(windows) {
+        print!("{}", run("schtasks", &["/Query", "/TN", TASK_NAME, "/FO", "LIST", "/V"].map(String::from))?);
+    } else if cfg!(target_os = "macos") {
+        print!("{}", run("launchctl", &["list".to_string(), LAUNCHD_LABEL.to_string()])?);
+    } else {
+        println!("{}", tr_args("service-unsupported", &[("os", std::env::consts::OS.to_string())]));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_install_requires_data_dir_and_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Some(Path::new("config.toml"));
+        let key = "00".repeat(32);
+        assert!(check_backup_config(config, Some(dir.path()), Some(&key)).is_ok());
+
+        assert!(check_backup_config(None, Some(dir.path()), Some(&key)).is_err());
+        let err = check_backup_config(config, None, Some(&key)).unwrap_err();
+        assert!(err.to_string().contains("wechat.data_dir"));
+        let err = check_backup_config(config, Some(dir.path()), None).unwrap_err();
+        assert!(err.to_string().contains("wechat.data_key"));
+        let missing = dir.path().join("missing");
+        let err = check_backup_config(config, Some(&missing), Some(&key)).unwrap_err();
+        assert!(err.to_string().contains("missing"));
+    }
+
+    #[test]
+    fn test_schedule_definitions() {
+        assert_eq!(parse_time("03:30").unwrap(), (3, 30));
+        assert!(parse_time("24:00").is_err());
+        assert!(parse_time("3pm").is_err());
+
+        let command = backup_command(
+            Path::new("C:/Program Files/mwxdump/mwxdump.exe"),
+            Some(Path::new("C:/mwxdump/config.toml")),
+            Path::new("D:/backups"),
+        );
+        assert_eq!(
+            command[1..],
+            ["--config", "C:/mwxdump/config.toml", "decrypt", "--snapshot", "-o", "D:/backups"]
+        );
+
+        let args = schtasks_create_args(&command, Schedule::Weekly, 3, 0);
+        assert_eq!(
+            args[4],
+            "\"C:/Program Files/mwxdump/mwxdump.exe\" --config C:/mwxdump/config.toml decrypt --snapshot -o D:/backups"
+        );
+        assert_eq!(args[5..], ["/SC", "WEEKLY", "/D", "SUN", "/ST", "03:00", "/F"]);
+
+        let plist = launchd_plist(&command, Schedule::Daily, 3, 5, Path::new("/tmp/backup.log"));
+        assert!(plist.contains("<key>Hour</key><integer>3</integer>"));
+        assert!(plist.contains("<key>Minute</key><integer>5</integer>"));
+        assert!(!plist.contains("Weekday"));
+        assert!(plist.contains("<string>--snapshot</string>"));
+
+        assert!(cron_line(&command, Schedule::Hourly, 3, 15).starts_with("15 * * * * 'C:/Program Files/"));
+    }
+}
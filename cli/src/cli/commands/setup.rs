@@ -0,0 +1,235 @@
+//! 首次运行配置向导
+//!
+//! 依次完成进程检测、权限检查、密钥提取、输出目录选择，并写入带账号配置的配置文件。
+
+use anyhow::bail;
+use clap::Args;
+use dialoguer::{Confirm, Input, Select};
+use std::path::PathBuf;
+
+use crate::cli::context::ExecutionContext;
+use crate::config::{AccountProfile, AppConfig};
+use crate::i18n::{tr, tr_args};
+use mwxdump_core::errors::{Result, WeChatError};
+use mwxdump_core::wechat::key::key_extractor::{create_key_extractor_with_offsets, KeyExtractor};
+use mwxdump_core::wechat::key::KeyOffset;
+use mwxdump_core::wechat::process::{
+    create_process_detector_with_validation, ProcessDetector, WeChatAccount, WechatProcessInfo,
+};
+
+/// 配置向导参数
+#[derive(Args, Debug)]
+pub struct SetupArgs {
+    /// 配置文件写入路径
+    #[arg(short, long, value_name = "FILE", default_value = "config.toml")]
+    pub output: PathBuf,
+}
+
+/// 执行配置向导
+pub async fn execute(context: &ExecutionContext, args: SetupArgs) -> Result<()> {
+    if !context.is_interactive() {
+        bail!(tr("setup-requires-terminal"));
+    }
+
+    println!("{}", tr("setup-welcome"));
+
+    // 1. 进程检测
+    println!("\n{}", tr("setup-step-detect"));
+    let detector = create_process_detector_with_validation(context.data_dir_validation())?;
+    let processes = detector.detect_processes().await?;
+    let process = select_process(&processes)?;
+
+    // 2. 权限检查
+    println!("\n{}", tr("setup-step-privilege"));
+    check_privilege()?;
+
+    // 3. 密钥提取
+    println!("\n{}", tr("setup-step-key"));
+    let data_key = obtain_key(process, context.key_offsets()).await?;
+
+    // 4. 数据目录与输出目录
+    println!("\n{}", tr("setup-step-paths"));
+    let accounts = detector.enumerate_accounts().await.unwrap_or_default();
+    let account = select_account(&accounts, process)?;
+    let mut data_dir_prompt = Input::<String>::new().with_prompt(tr("setup-prompt-data-dir"));
+    if let Some(dir) = account
+        .map(|a| &a.data_dir)
+        .or_else(|| process.and_then(|p| p.data_dir.as_ref()))
+    {
+        data_dir_prompt = data_dir_prompt.default(dir.display().to_string());
+    }
+    let data_dir: String = data_dir_prompt
+        .validate_with(|input: &String| -> std::result::Result<(), String> {
+            if PathBuf::from(input).is_dir() {
+                Ok(())
+            } else {
+                Err(tr("setup-invalid-dir"))
+            }
+        })
+        .interact_text()?;
+
+    let output_dir: String = Input::new()
+        .with_prompt(tr("setup-prompt-output-dir"))
+        .default(context.database_config().work_dir.display().to_string())
+        .interact_text()?;
+
+    // 5. 账号配置
+    let wxid = account
+        .map(|a| a.wxid.clone())
+        .or_else(|| process.and_then(|p| p.get_current_wxid()));
+    let name: String = Input::new()
+        .with_prompt(tr("setup-prompt-profile-name"))
+        .default(wxid.clone().unwrap_or_else(|| "default".to_string()))
+        .interact_text()?;
+
+    let profile = AccountProfile {
+        name,
+        wxid,
+        data_dir: PathBuf::from(data_dir),
+        data_key,
+        output_dir: PathBuf::from(output_dir),
+    };
+
+    // 6. 写入配置文件
+    println!("\n{}", tr("setup-step-write"));
+    let mut config = if args.output.exists() {
+        let overwrite = Confirm::new()
+            .with_prompt(tr_args("setup-confirm-merge", &[("path", args.output.display().to_string())]))
+            .default(true)
+            .interact()?;
+        if !overwrite {
+            println!("{}", tr("setup-cancelled"));
+            return Ok(());
+        }
+        AppConfig::from_file(&args.output)?
+    } else {
+        context.config().clone()
+    };
+
+    config.wechat.data_dir = Some(profile.data_dir.clone());
+    config.wechat.data_key = profile.data_key.clone();
+    config.database.work_dir = profile.output_dir.clone();
+    config.upsert_account(profile);
+    config.validate()?;
+    config.save_to_file(&args.output)?;
+
+    println!("{}", tr_args("setup-done", &[("path", args.output.display().to_string())]));
+    Ok(())
+}
+
+/// 选择要配置的微信进程，未检测到进程时询问是否继续手动配置
+fn select_process(processes: &[WechatProcessInfo]) -> Result<Option<&WechatProcessInfo>> {
+    match processes {
+        [] => {
+            println!("{}", tr("setup-no-process"));
+            let proceed = Confirm::new()
+                .with_prompt(tr("setup-confirm-manual"))
+                .default(true)
+                .interact()?;
+            if !proceed {
+                return Err(WeChatError::ProcessNotFound.into());
+            }
+            Ok(None)
+        }
+        [only] => {
+            println!("{}", tr_args("setup-process-found", &[("pid", only.pid.to_string())]));
+            Ok(Some(only))
+        }
+        _ => {
+            let items: Vec<String> = processes
+                .iter()
+                .map(|p| format!("{} (PID {}, {})", p.name, p.pid, p.version))
+                .collect();
+            let index = Select::new()
+                .with_prompt(tr("setup-select-process"))
+                .items(&items)
+                .default(0)
+                .interact()?;
+            Ok(Some(&processes[index]))
+        }
+    }
+}
+
+/// 本机有多个微信账号时选择要配置的账号，默认选中当前登录的账号
+fn select_account<'a>(
+    accounts: &'a [WeChatAccount],
+    process: Option<&WechatProcessInfo>,
+) -> Result<Option<&'a WeChatAccount>> {
+    let running_dir = process.and_then(|p| p.data_dir.as_ref());
+    if accounts.len() <= 1 {
+        return Ok(accounts.first());
+    }
+
+    let items: Vec<String> = accounts
+        .iter()
+        .map(|a| format!("{} ({})", a.wxid, a.data_dir.display()))
+        .collect();
+    let default = accounts
+        .iter()
+        .position(|a| Some(&a.data_dir) == running_dir)
+        .unwrap_or(0);
+    let index = Select::new()
+        .with_prompt(tr("setup-select-account"))
+        .items(&items)
+        .default(default)
+        .interact()?;
+
+    let account = &accounts[index];
+    if running_dir.is_some_and(|dir| dir != &account.data_dir) {
+        println!("{}", tr("setup-account-not-running"));
+    }
+    Ok(Some(account))
+}
+
+/// 检查当前是否具备读取进程内存的权限
+fn check_privilege() -> Result<()> {
+    #[cfg(target_os = "windows")]
+    let elevated = mwxdump_core::utils::windows::process::is_elevated();
+    #[cfg(not(target_os = "windows"))]
+    let elevated = true;
+
+    if elevated {
+        println!("{}", tr("setup-privilege-ok"));
+        return Ok(());
+    }
+
+    println!("{}", tr("setup-privilege-missing"));
+    let proceed = Confirm::new()
+        .with_prompt(tr("setup-confirm-continue"))
+        .default(false)
+        .interact()?;
+    if !proceed {
+        return Err(WeChatError::PermissionDenied(tr("setup-privilege-missing")).into());
+    }
+    Ok(())
+}
+
+/// 从进程提取密钥，失败或无进程时允许手动输入
+async fn obtain_key(process: Option<&WechatProcessInfo>, offsets: &[KeyOffset]) -> Result<Option<String>> {
+    if let Some(process) = process {
+        let extractor = create_key_extractor_with_offsets(offsets.to_vec())?;
+        match extractor.extract_key(process).await {
+            Ok(key) => {
+                println!("{}", tr("setup-key-ok"));
+                return Ok(Some(key.to_hex()));
+            }
+            Err(e) => {
+                println!("{}", tr_args("setup-key-failed", &[("error", e.to_string())]));
+            }
+        }
+    }
+
+    let key: String = Input::new()
+        .with_prompt(tr("setup-prompt-key"))
+        .allow_empty(true)
+        .validate_with(|input: &String| -> std::result::Result<(), String> {
+            if input.is_empty() || hex::decode(input).map(|k| k.len() == 32).unwrap_or(false) {
+                Ok(())
+            } else {
+                Err(tr("setup-invalid-key"))
+            }
+        })
+        .interact_text()?;
+
+    Ok((!key.is_empty()).then_some(key))
+}
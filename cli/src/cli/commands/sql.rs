@@ -0,0 +1,152 @@
+//! SQL 查询命令
+//!
+//! 对解密后的数据库执行只读的原始 SQL，无需安装 sqlite3。
+
+use clap::{Args, ValueEnum};
+use serde_json::Value;
+use std::path::PathBuf;
+
+use crate::cli::context::ExecutionContext;
+use crate::i18n::tr_args;
+use mwxdump_core::errors::Result;
+use mwxdump_core::wechat::db::{DataSource, QueryRows, SqlValue, SqliteDataSource};
+
+/// 输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// 对齐的文本表格
+    Table,
+    /// CSV
+    Csv,
+    /// JSON 数组
+    Json,
+}
+
+/// SQL 查询参数
+#[derive(Args, Debug)]
+pub struct SqlArgs {
+    /// 解密后的数据库文件
+    #[arg(long, value_name = "FILE")]
+    pub db: PathBuf,
+
+    /// SQL 语句，可使用 `?` 占位符
+    #[arg(value_name = "SQL")]
+    pub sql: String,
+
+    /// 按顺序绑定到 `?` 占位符的参数，可重复指定
+    #[arg(short, long = "param", value_name = "VALUE")]
+    pub params: Vec<SqlValue>,
+
+    /// 输出格式
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Table)]
+    pub format: OutputFormat,
+}
+
+/// 执行 SQL 查询，连接以只读方式打开
+pub async fn execute(_context: &ExecutionContext, args: SqlArgs) -> Result<()> {
+    let source = SqliteDataSource::open(&args.db).await?.with_raw_sql(true);
+    let result = source.fetch_raw(&args.sql, &args.params).await?;
+
+    match args.format {
+        OutputFormat::Table => {
+            print!("{}", render_table(&result));
+            println!("{}", tr_args("sql-row-count", &[("count", result.rows.len().to_string())]));
+        }
+        OutputFormat::Csv => print!("{}", render_csv(&result)),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&result.into_objects())?),
+    }
+    Ok(())
+}
+
+/// 单元格文本，`null` 在表格中显示为 `NULL`
+fn cell_text(value: &Value, null: &str) -> String {
+    match value {
+        Value::Null => null.to_string(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// 渲染为按显示宽度对齐的表格
+pub(crate) fn render_table(result: &QueryRows) -> String {
+    if result.columns.is_empty() {
+        return String::new();
+    }
+    let cells: Vec<Vec<String>> = result
+        .rows
+        .iter()
+        .map(|row| row.iter().map(|v| cell_text(v, "NULL")).collect())
+        .collect();
+    let widths: Vec<usize> = result
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            cells
+                .iter()
+                .map(|row| console::measure_text_width(&row[i]))
+                .chain(std::iter::once(console::measure_text_width(column)))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let format_row = |row: &[String]| {
+        let padded: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{}{}", cell, " ".repeat(width - console::measure_text_width(cell))))
+            .collect();
+        format!("{}\n", padded.join(" | ").trim_end())
+    };
+
+    let mut output = format_row(&result.columns);
+    let separator: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+    output.push_str(&format!("{}\n", separator.join("-+-")));
+    for row in &cells {
+        output.push_str(&format_row(row));
+    }
+    output
+}
+
+/// 渲染为 CSV，必要时对字段加引号
+pub(crate) fn render_csv(result: &QueryRows) -> String {
+    let escape = |field: &str| {
+        if field.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    };
+    let mut output = String::new();
+    let header: Vec<String> = result.columns.iter().map(|c| escape(c)).collect();
+    output.push_str(&format!("{}\n", header.join(",")));
+    for row in &result.rows {
+        let fields: Vec<String> = row.iter().map(|v| escape(&cell_text(v, ""))).collect();
+        output.push_str(&format!("{}\n", fields.join(",")));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_table_and_csv() {
+        let result = QueryRows {
+            columns: vec!["username".to_string(), "remark".to_string()],
+            rows: vec![
+                vec![json!("wxid_a"), json!("同事, 项目组")],
+                vec![json!("wxid_b"), Value::Null],
+            ],
+        };
+
+        assert_eq!(
+            render_table(&result),
+            "username | remark\n---------+-------------\nwxid_a   | 同事, 项目组\nwxid_b   | NULL\n"
+        );
+        assert_eq!(render_csv(&result), "username,remark\nwxid_a,\"同事, 项目组\"\nwxid_b,\n");
+    }
+}
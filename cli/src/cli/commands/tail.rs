@@ -0,0 +1,134 @@
+//! 跟踪会话命令
+//!
+//! 类似 `tail -f`：先输出会话最近的消息，之后监控微信数据目录，消息分片变化时快照并重新解密到工作目录，
+//! 输出新到达的消息。
+
+use clap::Args;
+use std::path::{Path, PathBuf};
+
+use crate::cli::context::ExecutionContext;
+use mwxdump_core::errors::{ConfigError, Result, WeChatError};
+use mwxdump_core::export::{ExportFormat, ExportRecord};
+use mwxdump_core::models::Message;
+use mwxdump_core::wechat::datadir::{self, watch, DbStorageWatcher};
+use mwxdump_core::wechat::db::contacts::ContactRepository;
+use mwxdump_core::wechat::db::tail::ConversationTail;
+
+/// 跟踪参数
+#[derive(Args, Debug)]
+pub struct TailArgs {
+    /// 会话的 wxid 或群ID
+    #[arg(long, value_name = "WXID")]
+    pub talker: String,
+
+    /// 开始时输出的最近消息数
+    #[arg(short = 'n', long, default_value_t = 10)]
+    pub lines: u32,
+
+    /// 以 JSON Lines 输出，每行一条消息
+    #[arg(long)]
+    pub json: bool,
+
+    /// 当前账号的 wxid，用于标记自己发送的消息
+    #[arg(long, value_name = "WXID")]
+    pub self_wxid: Option<String>,
+
+    /// 加密的账号数据目录（默认使用配置中的微信数据目录）
+    #[arg(long, value_name = "DIR")]
+    pub input: Option<PathBuf>,
+
+    /// 解密密钥（64位十六进制，默认使用配置中的密钥）
+    #[arg(long, value_name = "HEX")]
+    pub key: Option<String>,
+}
+
+/// 跟踪会话的新消息，直到 Ctrl-C
+pub async fn execute(context: &ExecutionContext, args: TailArgs) -> Result<()> {
+    let input = args
+        .input
+        .as_deref()
+        .or(context.wechat_data_dir())
+        .ok_or_else(|| WeChatError::DecryptionFailed("未指定微信数据目录，请使用 --input".to_string()))?;
+    let key_hex = args
+        .key
+        .as_deref()
+        .or(context.wechat_data_key())
+        .ok_or_else(|| WeChatError::DecryptionFailed("未指定解密密钥，请使用 --key".to_string()))?;
+    let key = hex::decode(key_hex.trim())
+        .ok()
+        .filter(|key| key.len() == 32)
+        .ok_or_else(|| ConfigError::InvalidValue {
+            key: "key".to_string(),
+            value: key_hex.to_string(),
+        })?;
+    let info = datadir::scan(input)?;
+    let db_storage = info.db_storage_path.ok_or_else(|| WeChatError::DataDirNotFound {
+        path: input.display().to_string(),
+    })?;
+
+    let work_dir = context.database_config().work_dir.clone();
+    let format = if args.json { ExportFormat::Jsonl } else { ExportFormat::Txt };
+    let mut exporter = format.exporter(std::io::stdout(), &work_dir);
+    let mut tail = ConversationTail::new(&work_dir, args.talker.clone());
+    if let Some(wxid) = args.self_wxid {
+        tail = tail.with_self_wxid(wxid);
+    }
+    let mut contacts = ContactRepository::open(&work_dir).await.unwrap_or_default();
+    let mut print = |contacts: &ContactRepository, messages: Vec<Message>| -> Result<()> {
+        for mut message in messages {
+            message.sender_name = contacts.display_name(&message.sender).map(str::to_string);
+            exporter.write(&ExportRecord { message, media: None })?;
+        }
+        Ok(())
+    };
+    print(&contacts, tail.backlog(args.lines).await?)?;
+
+    let mut batches = DbStorageWatcher::new(db_storage).start(context.cancellation_token())?;
+    while let Some(changed) = batches.recv().await {
+        // 只重新解密消息分片和联系人，其他数据库的变化与会话无关
+        let changed: Vec<PathBuf> = changed.into_iter().filter(|path| is_tail_source(path)).collect();
+        match watch::refresh_changed(&info.root, &changed, &work_dir, &key, &[], None).await {
+            Ok(files) if files.is_empty() => continue,
+            Ok(files) => {
+                if files.iter().any(|file| file.starts_with("db_storage/contact")) {
+                    contacts = ContactRepository::open(&work_dir).await.unwrap_or_default();
+                }
+            }
+            Err(e) => {
+                tracing::warn!("刷新失败: {}", e);
+                continue;
+            }
+        }
+        print(&contacts, tail.poll().await?)?;
+    }
+    Ok(())
+}
+
+/// 是否为消息分片或联系人数据库
+fn is_tail_source(path: &Path) -> bool {
+    let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    let dir = path.parent().and_then(|dir| dir.file_name()).and_then(|dir| dir.to_str());
+    match dir {
+        Some("message") => name
+            .strip_prefix("message_")
+            .and_then(|rest| rest.strip_suffix(".db"))
+            .is_some_and(|index| index.parse::<u32>().is_ok()),
+        Some("contact") => name == "contact.db",
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tail_sources() {
+        assert!(is_tail_source(Path::new("/d/db_storage/message/message_0.db")));
+        assert!(is_tail_source(Path::new("/d/db_storage/contact/contact.db")));
+        assert!(!is_tail_source(Path::new("/d/db_storage/message/message_fts.db")));
+        assert!(!is_tail_source(Path::new("/d/db_storage/message/message_resource.db")));
+        assert!(!is_tail_source(Path::new("/d/db_storage/message/media_0.db")));
+        assert!(!is_tail_source(Path::new("/d/db_storage/session/session.db")));
+    }
+}
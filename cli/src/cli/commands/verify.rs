@@ -0,0 +1,98 @@
+//! 快照校验命令
+//!
+//! 按快照中的 `manifest.json` 重新计算每个文件的哈希，报告缺失、被修改和多出的文件；
+//! `--signature` 时同时校验 `manifest.sig`。信任的公钥优先使用 `--public-key`，
+//! 否则使用备份根目录 `signing.json` 中的公钥，该公钥需先用主密钥认证（读取主密钥口令），
+//! 防止改动快照的人连同公钥一起替换；两者都没有时签名校验不通过。
+
+use anyhow::bail;
+use clap::Args;
+use std::path::{Path, PathBuf};
+
+use super::master::{self, snapshot_root};
+use crate::cli::context::ExecutionContext;
+use crate::i18n::{tr, tr_args};
+use mwxdump_core::backup::signing::{self, SignatureStatus, SigningKeyFile};
+use mwxdump_core::errors::Result;
+
+/// 校验参数
+#[derive(Args, Debug)]
+pub struct VerifyArgs {
+    /// 快照目录
+    #[arg(value_name = "SNAPSHOT")]
+    pub snapshot: PathBuf,
+
+    /// 同时校验清单签名，快照未签名时视为失败
+    #[arg(long)]
+    pub signature: bool,
+
+    /// 信任的签名公钥（十六进制），默认使用备份根目录 signing.json 中经主密钥认证的公钥
+    #[arg(long, value_name = "HEX", requires = "signature")]
+    pub public_key: Option<String>,
+
+    /// 以 JSON 输出校验结果
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// 校验快照
+pub async fn execute(context: &ExecutionContext, args: VerifyArgs) -> Result<()> {
+    let trusted = match (&args.public_key, args.signature) {
+        (Some(key), _) => Some(key.clone()),
+        (None, true) => authenticated_public_key(context, &snapshot_root(&args.snapshot))?,
+        (None, false) => None,
+    };
+    let snapshot = args.snapshot.clone();
+    let signature = args.signature;
+    let report =
+        tokio::task::spawn_blocking(move || signing::verify_snapshot(&snapshot, signature, trusted.as_deref()))
+            .await??;
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        for (label, files) in [("-", &report.missing), ("*", &report.modified), ("+", &report.extra)] {
+            for file in files {
+                println!("{} {}", label, file);
+            }
+        }
+        match &report.signature {
+            SignatureStatus::NotChecked => {}
+            SignatureStatus::Unsigned => println!("{}", tr("verify-unsigned")),
+            SignatureStatus::Valid { fingerprint, trusted } => {
+                let key = if *trusted { "verify-signature-valid" } else { "verify-signature-untrusted" };
+                println!("{}", tr_args(key, &[("fingerprint", fingerprint.clone())]));
+            }
+            SignatureStatus::Invalid { reason } => {
+                println!("{}", tr_args("verify-signature-invalid", &[("reason", reason.clone())]));
+            }
+        }
+        println!(
+            "{}",
+            tr_args(
+                "verify-summary",
+                &[
+                    ("files", report.files.to_string()),
+                    ("missing", report.missing.len().to_string()),
+                    ("modified", report.modified.len().to_string()),
+                    ("extra", report.extra.len().to_string()),
+                ],
+            )
+        );
+    }
+    if !report.is_ok() {
+        bail!(tr_args("verify-failed", &[("snapshot", args.snapshot.display().to_string())]));
+    }
+    Ok(())
+}
+
+/// 用主密钥认证备份根目录 `signing.json` 中的公钥，没有该文件时返回 `None`
+///
+/// `signing.json` 与快照放在一起，未经认证的公钥可能已被替换，认证失败时返回错误。
+fn authenticated_public_key(context: &ExecutionContext, root: &Path) -> Result<Option<String>> {
+    let Some(file) = SigningKeyFile::load(root)? else {
+        return Ok(None);
+    };
+    let master = master::unlock(context, root)?;
+    Ok(Some(file.trusted_public_key(&master)?))
+}
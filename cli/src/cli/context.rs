@@ -1,29 +1,52 @@
 //! CLI执行上下文
 
-use crate::config::{AppConfig, ConfigService};
+use crate::config::{AppConfig, ConfigMode, ConfigService};
 use mwxdump_core::errors::Result;
-use std::path::Path;
+use mwxdump_core::events::EventBus;
+use std::path::{Path, PathBuf};
+use tokio_util::sync::CancellationToken;
 
 /// CLI执行上下文
 #[derive(Debug)]
 pub struct ExecutionContext {
     /// 配置服务
     config_service: Option<ConfigService>,
+    /// 指定或自动找到的配置文件，加载失败时也保留
+    config_file: Option<PathBuf>,
     /// 日志级别
     log_level: String,
     /// 默认配置
     default_config: AppConfig,
+    /// 是否为无头模式
+    headless: bool,
+    /// 取消令牌（Ctrl-C 时触发）
+    cancel_token: CancellationToken,
+    /// 事件总线，进度条等订阅命令执行中的事件
+    events: EventBus,
 }
 
 impl ExecutionContext {
     /// 创建新的执行上下文
-    pub fn new(config_path: Option<String>, cli_log_level: Option<String>) -> Result<Self> {
+    ///
+    /// 宽松模式下配置文件加载失败时提示后使用默认配置，严格模式下直接返回错误。
+    pub fn new(config_path: Option<String>, cli_log_level: Option<String>, mode: ConfigMode) -> Result<Self> {
+        let config_path = config_path.or_else(|| {
+            let path = ConfigService::find_default_config()?;
+            eprintln!("ℹ️  未指定 --config，使用默认位置的配置文件: {}", path.display());
+            Some(path.display().to_string())
+        });
+        let config_file = config_path.as_ref().map(PathBuf::from);
         let config_service = if let Some(path) = config_path {
-            match ConfigService::load_from_file(&path) {
+            match ConfigService::load_from_file(&path, mode) {
                 Ok(service) => {
-                    println!("✅ 成功加载配置文件: {}", path);
+                    // 标准输出可能用于传输协议消息（如 mcp 命令），提示写到标准错误
+                    eprintln!("✅ 成功加载配置文件: {}", path);
+                    for key in service.unknown_keys() {
+                        eprintln!("⚠️  忽略未知的配置项: {}（请检查是否拼写错误）", key);
+                    }
                     Some(service)
                 }
+                Err(e) if mode == ConfigMode::Strict => return Err(e),
                 Err(e) => {
                     eprintln!("⚠️  配置文件加载失败: {}", e);
                     eprintln!("   使用默认配置继续执行...");
@@ -48,8 +71,32 @@ impl ExecutionContext {
         
         Ok(Self {
             config_service,
+            config_file,
             log_level,
             default_config: AppConfig::default(),
+            headless: false,
+            cancel_token: CancellationToken::new(),
+            events: EventBus::new(),
+        })
+    }
+
+    /// 创建无头模式的执行上下文
+    ///
+    /// 配置只从环境变量读取，不加载配置文件，也不向终端输出提示信息。
+    pub fn headless(cli_log_level: Option<String>) -> Result<Self> {
+        let config_service = ConfigService::load_from_env()?;
+
+        let log_level = cli_log_level
+            .unwrap_or_else(|| config_service.config().logging.level.clone());
+
+        Ok(Self {
+            config_service: Some(config_service),
+            config_file: None,
+            log_level,
+            default_config: AppConfig::default(),
+            headless: true,
+            cancel_token: CancellationToken::new(),
+            events: EventBus::new(),
         })
     }
     
@@ -58,8 +105,12 @@ impl ExecutionContext {
         let log_level = cli_log_level.unwrap_or_else(|| "info".to_string());
         Self {
             config_service: None,
+            config_file: None,
             log_level,
             default_config: AppConfig::default(),
+            headless: false,
+            cancel_token: CancellationToken::new(),
+            events: EventBus::new(),
         }
     }
     
@@ -71,6 +122,62 @@ impl ExecutionContext {
             .unwrap_or(&self.default_config)
     }
     
+    /// 已加载的配置文件路径
+    pub fn config_path(&self) -> Option<&Path> {
+        self.config_service.as_ref().and_then(|cs| cs.config_path())
+    }
+    
+    /// 配置中 `[command.<name>]` 给出的子命令默认参数
+    pub fn command_defaults(&self, name: &str) -> Vec<String> {
+        self.config().command_args(name)
+    }
+    
+    /// 指定或自动找到的配置文件路径，不论是否加载成功
+    pub fn config_file(&self) -> Option<&Path> {
+        self.config_file.as_deref()
+    }
+    
+    /// 是否为无头模式
+    pub fn is_headless(&self) -> bool {
+        self.headless
+    }
+
+    /// 是否允许交互式提示
+    ///
+    /// 无头模式或标准输入不是终端时均不允许交互。
+    pub fn is_interactive(&self) -> bool {
+        !self.headless && console::user_attended()
+    }
+
+    /// 获取取消令牌
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancel_token.clone()
+    }
+
+    /// 获取事件总线
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    /// 按配置切换界面语言
+    pub fn apply_language(&self) {
+        if let Some(language) = &self.config().general.language {
+            crate::i18n::set_language_tag(language);
+        }
+    }
+
+    /// 加载用户版本特征文件到全局特征库
+    ///
+    /// 未配置特征文件时使用内置特征，无需调用。
+    pub fn load_signatures(&self) -> Result<()> {
+        use mwxdump_core::wechat::signatures::SignatureDb;
+        
+        if let Some(path) = &self.config().wechat.signatures_file {
+            SignatureDb::install_global(SignatureDb::load_with_overrides(Some(path))?)?;
+        }
+        Ok(())
+    }
+
     /// 获取日志级别
     pub fn log_level(&self) -> &str {
         &self.log_level
@@ -86,6 +193,16 @@ impl ExecutionContext {
         self.config().wechat.data_key.as_deref()
     }
     
+    /// 获取配置的密钥指针偏移
+    pub fn key_offsets(&self) -> &[mwxdump_core::wechat::key::KeyOffset] {
+        &self.config().wechat.key_offsets
+    }
+    
+    /// 获取数据目录验证方式
+    pub fn data_dir_validation(&self) -> mwxdump_core::wechat::process::DataDirValidation {
+        self.config().wechat.data_dir_validation
+    }
+    
     /// 获取HTTP服务配置
     pub fn http_config(&self) -> &crate::config::HttpConfig {
         &self.config().http
@@ -2,68 +2,264 @@
 //! 
 //! 处理所有命令行相关的功能
 
-use clap::{CommandFactory, Parser, Subcommand};
-use mwxdump_core::errors::Result;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use std::ffi::OsString;
+use mwxdump_core::errors::{ConfigError, Result};
 
 pub mod commands;
 pub mod context;
+pub mod notify;
 
 use context::ExecutionContext;
+use crate::config::{AppConfig, ConfigMode, ConfigService};
 
 /// MwXdump-rs 命令行应用
 #[derive(Parser)]
 #[command(name = "mwx-cli")]
 #[command(about = "微信聊天记录管理工具")]
 #[command(version = env!("CARGO_PKG_VERSION"))]
+#[command(args_override_self = true)]
 pub struct Cli {
-    /// 配置文件路径
-    #[arg(short, long, value_name = "FILE")]
+    /// 配置文件路径，未指定时使用可执行文件所在目录下的 config.toml（存在时）
+    #[arg(short, long, value_name = "FILE", env = "MWX_CONFIG")]
     pub config: Option<String>,
     
     /// 日志级别
     #[arg(short, long)]
     pub log_level: Option<String>,
+
+    /// 无头模式：无颜色、JSON日志、无交互提示，配置仅从 MWX_ 环境变量读取
+    #[arg(long, global = true, env = "MWX_HEADLESS")]
+    pub headless: bool,
+
+    /// 严格解析配置文件：出现未知配置项时报错，而不是忽略后继续使用默认值
+    #[arg(long, global = true, env = "MWX_STRICT_CONFIG")]
+    pub strict_config: bool,
     
     /// 子命令
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
 
+/// 合并了配置默认参数的命令行
+struct MergedArgs {
+    /// 子命令名
+    name: String,
+    /// 插入的默认参数
+    defaults: Vec<String>,
+    /// 合并后的完整参数
+    args: Vec<OsString>,
+}
+
 /// 支持的命令
 #[derive(Subcommand)]
 pub enum Commands {
     /// 获取微信数据密钥
-    Key,
+    Key(commands::key::KeyArgs),
+
+    /// 管理备份密钥（keys master：加密快照的主密钥）
+    Keys(commands::master::KeysArgs),
 
     /// 测试进程检测功能
     Process,
 
     /// 解密数据文件
     Decrypt(commands::decrypt::DecryptArgs),
+
+    /// 首次运行配置向导
+    Setup(commands::setup::SetupArgs),
+
+    /// 显示微信数据目录信息
+    Info(commands::info::InfoArgs),
+
+    /// 列出联系人及标签
+    Contacts(commands::contacts::ContactsArgs),
+
+    /// 对解密后的数据库执行只读SQL查询
+    Sql(commands::sql::SqlArgs),
+
+    /// 导出单个会话的聊天记录
+    Export(commands::export::ExportArgs),
+
+    /// 查看历史备份、清理快照
+    Backups(commands::backups::BackupsArgs),
+
+    /// 注册、删除或查看定时备份（Windows 计划任务 / macOS launchd）
+    Service(commands::service::ServiceArgs),
+
+    /// 解码图片缓存等媒体文件
+    Media(commands::media::MediaArgs),
+
+    /// 合并多个来源的工作目录，可跨设备对齐去重
+    Merge(commands::merge::MergeArgs),
+
+    /// 跟踪会话，自动刷新并输出新到达的消息（类似 tail -f）
+    Tail(commands::tail::TailArgs),
+
+    /// 按文件清单校验快照，可同时校验清单签名
+    Verify(commands::verify::VerifyArgs),
+
+    /// 识别图片文字并写入全文索引，或搜索图片文字
+    #[cfg(feature = "ocr")]
+    Ocr(commands::ocr::OcrArgs),
     /// 启动HTTP服务器
-    // Server,
+    Server(commands::server::ServerArgs),
+
+    /// 启动 MCP 服务，供大模型客户端查询聊天数据
+    Mcp(commands::mcp::McpArgs),
     
     /// 显示版本信息
     Version,
     
-    /// 内存转储（调试用）
+    /// 把微信进程内存保存为快照文件，供离线分析
     DumpMemory {
-        /// 进程ID
+        /// 进程ID，默认使用检测到的微信主进程
         #[arg(short, long)]
         pid: Option<u32>,
-    }
+        /// 快照文件路径，默认为当前目录下的 wechat-<pid>-<时间>.mwxsnap
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+        /// 保存所有可读区域（包括模块映像），默认只保存私有可写区域（堆）
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// 开发辅助工具，如在内存快照中反查密钥特征
+    Devtool(commands::devtool::DevtoolArgs),
+
+    /// 检查配置文件
+    Config(commands::config::ConfigArgs),
 }
 
 impl Cli {
     /// 执行命令
     pub async fn execute(self) -> Result<()> {
-        // 解构 self 以避免部分移动问题
-        let Cli { config, log_level, command } = self;
-        
         // 创建执行上下文
-        let context = ExecutionContext::new(config, log_level)?;
+        let context = if self.headless {
+            ExecutionContext::headless(self.log_level.clone())?
+        } else {
+            ExecutionContext::new(
+                self.config.clone(),
+                self.log_level.clone(),
+                ConfigMode::from_strict(self.strict_config),
+            )?
+        };
+        context.apply_language();
+        context.load_signatures()?;
+        
+        let cli = self.with_command_defaults(&context)?;
+        Self::execute_command_with_context(cli.command, &context).await
+    }
+    
+    /// 命令是否经标准输出传输协议消息或消息流，此时日志必须输出到标准错误
+    pub fn uses_stdout_protocol(&self) -> bool {
+        match &self.command {
+            Some(Commands::Mcp(args)) => args.uses_stdout(),
+            Some(Commands::Tail(_)) => true,
+            _ => false,
+        }
+    }
+    
+    /// 命令是否响应取消令牌：收到 Ctrl-C 后等待进行中的写入完成并清理未完成的输出
+    ///
+    /// 未指定子命令时按配置的默认命令判断。
+    pub fn honours_cancellation(&self, context: &ExecutionContext) -> bool {
+        let default = match &self.command {
+            Some(_) => None,
+            None => Self::resolve_default_command(context).ok().flatten(),
+        };
+        matches!(
+            self.command.as_ref().or(default.as_ref()),
+            Some(
+                Commands::Decrypt(_)
+                    | Commands::Export(_)
+                    | Commands::Media(_)
+                    | Commands::Merge(_)
+                    | Commands::Tail(_)
+                    | Commands::Server(_)
+                    | Commands::Mcp(_)
+            )
+        )
+    }
+    
+    /// 构建带本地化帮助尾注的命令定义
+    pub fn localized_command() -> clap::Command {
+        Self::command().after_help(crate::i18n::tr("cli-after-help"))
+    }
+    
+    /// 解析命令行参数（帮助信息使用当前语言）
+    ///
+    /// 解析前先读取配置中的 `general.language`，使 `--help` 和参数错误提示也使用配置的语言。
+    pub fn parse_localized() -> Self {
+        let args: Vec<OsString> = std::env::args_os().collect();
+        if let Some(language) = Self::config_language(&args) {
+            crate::i18n::set_language_tag(&language);
+        }
+        let matches = Self::localized_command().get_matches_from(args);
+        Self::from_arg_matches(&matches).unwrap_or_else(|e| e.exit())
+    }
+
+    /// 在解析命令行之前查找配置的界面语言
+    ///
+    /// 按 `--config`、`MWX_CONFIG` 或默认位置找到配置文件，只读取语言设置；无头模式读取 `MWX_GENERAL__LANGUAGE`。
+    /// 配置文件的错误留给随后创建执行上下文时报告。
+    fn config_language(args: &[OsString]) -> Option<String> {
+        let args: Vec<&str> = args.iter().skip(1).filter_map(|arg| arg.to_str()).collect();
+        let headless = args.contains(&"--headless")
+            || std::env::var("MWX_HEADLESS").is_ok_and(|value| matches!(value.as_str(), "1" | "true"));
+        if headless {
+            return std::env::var("MWX_GENERAL__LANGUAGE").ok();
+        }
+        let explicit = args
+            .iter()
+            .enumerate()
+            .find_map(|(i, arg)| match *arg {
+                "-c" | "--config" => args.get(i + 1).map(std::path::PathBuf::from),
+                _ => arg.strip_prefix("--config=").map(std::path::PathBuf::from),
+            })
+            .or_else(|| std::env::var_os("MWX_CONFIG").map(std::path::PathBuf::from));
+        let path = explicit.or_else(ConfigService::find_default_config)?;
+        let content = std::fs::read_to_string(path).ok()?;
+        AppConfig::parse(&content).ok()?.0.general.language
+    }
+    
+    /// 合并配置中 `[command.<子命令>]` 的默认参数后重新解析命令行
+    ///
+    /// 默认参数插入在子命令名之后，命令行中随后给出的同名参数会覆盖它们。
+    /// 默认参数只能补充可选参数，必填参数仍需在命令行中给出。
+    pub fn with_command_defaults(self, context: &ExecutionContext) -> Result<Self> {
+        let args: Vec<OsString> = std::env::args_os().collect();
+        match Self::merge_command_defaults(&args, context) {
+            Some(merged) => Self::parse_merged(merged),
+            None => Ok(self),
+        }
+    }
+    
+    /// 在子命令名之后插入配置的默认参数，没有可合并的默认参数时返回 `None`
+    fn merge_command_defaults(args: &[OsString], context: &ExecutionContext) -> Option<MergedArgs> {
+        let matches = Self::command().try_get_matches_from(args).ok()?;
+        let name = matches.subcommand_name()?.to_string();
+        let defaults = context.command_defaults(&name);
+        if defaults.is_empty() {
+            return None;
+        }
+        let position = args.iter().skip(1).position(|arg| arg.to_str() == Some(name.as_str()))? + 1;
         
-        Self::execute_command_with_context(command, &context).await
+        let mut merged = args[..=position].to_vec();
+        merged.extend(defaults.iter().map(OsString::from));
+        merged.extend_from_slice(&args[position + 1..]);
+        Some(MergedArgs { name, defaults, args: merged })
+    }
+    
+    /// 解析合并后的参数，失败时指出出错的配置段
+    fn parse_merged(merged: MergedArgs) -> Result<Self> {
+        Self::try_parse_from(&merged.args).map_err(|e| {
+            ConfigError::InvalidValue {
+                key: format!("command.{}", merged.name),
+                value: format!("{} ({})", merged.defaults.join(" "), e.kind()),
+            }
+            .into()
+        })
     }
     
     /// 使用已有上下文执行命令
@@ -71,30 +267,254 @@ impl Cli {
         Self::execute_command_with_context(self.command, &context).await
     }
     
+    /// 解析配置中的默认命令
+    ///
+    /// 按 shell 规则拆分参数，含空格的路径可以加引号。未配置 `general.default_command` 时返回 `None`。
+    fn resolve_default_command(context: &ExecutionContext) -> Result<Option<Commands>> {
+        let Some(line) = context.config().general.default_command.as_deref() else {
+            return Ok(None);
+        };
+        
+        let words = shell_words::split(line).map_err(|e| ConfigError::InvalidValue {
+            key: "general.default_command".to_string(),
+            value: format!("{} ({})", line, e),
+        })?;
+        let args: Vec<OsString> = std::iter::once("mwx-cli".to_string())
+            .chain(words)
+            .map(OsString::from)
+            .collect();
+        let cli = match Self::merge_command_defaults(&args, context) {
+            Some(merged) => Self::parse_merged(merged)?,
+            None => Self::try_parse_from(&args).map_err(|e| ConfigError::InvalidValue {
+                key: "general.default_command".to_string(),
+                value: format!("{} ({})", line, e.kind()),
+            })?,
+        };
+        Ok(cli.command)
+    }
+    
     /// 内部方法：使用上下文执行具体命令
     async fn execute_command_with_context(command: Option<Commands>, context: &ExecutionContext) -> Result<()> {
+        let command = match command {
+            Some(command) => Some(command),
+            None => Self::resolve_default_command(context)?,
+        };
+        
         match command {
-            Some(Commands::Key) => {
-                commands::key::execute(context).await
+            Some(Commands::Key(args)) => {
+                commands::key::execute(context, args).await
+            }
+            Some(Commands::Keys(args)) => {
+                commands::master::execute(context, args).await
             }
 
             Some(Commands::Decrypt(args)) => {
                 commands::decrypt::execute(context, args).await
             }
+            Some(Commands::Setup(args)) => {
+                commands::setup::execute(context, args).await
+            }
+            Some(Commands::Info(args)) => {
+                commands::info::execute(context, args).await
+            }
+            Some(Commands::Contacts(args)) => {
+                commands::contacts::execute(context, args).await
+            }
+            Some(Commands::Sql(args)) => {
+                commands::sql::execute(context, args).await
+            }
+            Some(Commands::Export(args)) => {
+                commands::export::execute(context, args).await
+            }
+            Some(Commands::Backups(args)) => {
+                commands::backups::execute(context, args).await
+            }
+            Some(Commands::Service(args)) => {
+                commands::service::execute(context, args).await
+            }
+            Some(Commands::Media(args)) => {
+                commands::media::execute(context, args).await
+            }
+            Some(Commands::Merge(args)) => {
+                commands::merge::execute(context, args).await
+            }
+            Some(Commands::Tail(args)) => {
+                commands::tail::execute(context, args).await
+            }
+            Some(Commands::Verify(args)) => {
+                commands::verify::execute(context, args).await
+            }
+            #[cfg(feature = "ocr")]
+            Some(Commands::Ocr(args)) => {
+                commands::ocr::execute(context, args).await
+            }
+            Some(Commands::Server(args)) => {
+                commands::server::execute(context, args).await
+            }
+            Some(Commands::Mcp(args)) => {
+                commands::mcp::execute(context, args).await
+            }
             Some(Commands::Version) => {
                 commands::version::execute(context).await
             }
-            Some(Commands::DumpMemory { pid }) => {
-                commands::dump_memory::execute(context, pid).await
+            Some(Commands::DumpMemory { pid, output, all }) => {
+                commands::dump_memory::execute(context, pid, output, all).await
+            }
+            Some(Commands::Devtool(args)) => {
+                commands::devtool::execute(context, args).await
+            }
+            Some(Commands::Config(args)) => {
+                commands::config::execute(context, args).await
             }
             Some(Commands::Process) => {
                 commands::process::execute(context).await
             }
             None => {
-                // 没有子命令时显示帮助
-                println!("{}", Self::command().render_help());
+                // 没有子命令且未配置默认命令时显示帮助
+                println!("{}", Self::localized_command().render_help());
                 Ok(())
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use mwxdump_core::wechat::datadir::DbCategory;
+
+    fn context_with_default(default_command: &str) -> ExecutionContext {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let mut config = AppConfig::default();
+        config.general.default_command = Some(default_command.to_string());
+        config.save_to_file(&path).unwrap();
+        ExecutionContext::new(Some(path.display().to_string()), None, ConfigMode::Lenient).unwrap()
+    }
+
+    #[test]
+    fn test_resolve_default_command() {
+        let context = context_with_default("decrypt -o ./out --threads 2");
+        match Cli::resolve_default_command(&context).unwrap() {
+            Some(Commands::Decrypt(args)) => {
+                assert_eq!(args.output, std::path::PathBuf::from("./out"));
+                assert_eq!(args.threads, Some(2));
+            }
+            _ => panic!("默认命令解析错误"),
+        }
+
+        let context = context_with_default("decrypt -o ./out --only message,contact");
+        match Cli::resolve_default_command(&context).unwrap() {
+            Some(Commands::Decrypt(args)) => {
+                assert_eq!(args.only, [DbCategory::Message, DbCategory::Contact]);
+            }
+            _ => panic!("默认命令解析错误"),
+        }
+
+        let context = context_with_default(r#"decrypt -o "./My Backups/out""#);
+        match Cli::resolve_default_command(&context).unwrap() {
+            Some(Commands::Decrypt(args)) => {
+                assert_eq!(args.output, std::path::PathBuf::from("./My Backups/out"));
+            }
+            _ => panic!("默认命令解析错误"),
+        }
+
+        let context = context_with_default("no-such-command");
+        assert!(Cli::resolve_default_command(&context).is_err());
+        let context = context_with_default(r#"decrypt -o "./unterminated"#);
+        assert!(Cli::resolve_default_command(&context).is_err());
+
+        let context = ExecutionContext::with_defaults(None);
+        assert!(Cli::resolve_default_command(&context).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_command_defaults_beneath_cli_flags() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let mut config = AppConfig::default();
+        config.command.insert(
+            "decrypt".to_string(),
+            toml::toml! {
+                threads = 8
+                ordered = true
+                snapshot = false
+                only = ["message", "contact"]
+            },
+        );
+        config.save_to_file(&path).unwrap();
+        let context = ExecutionContext::new(Some(path.display().to_string()), None, ConfigMode::Lenient).unwrap();
+
+        let parse = |line: &str| {
+            let args: Vec<OsString> = line.split_whitespace().map(OsString::from).collect();
+            let merged = Cli::merge_command_defaults(&args, &context).unwrap();
+            match Cli::parse_merged(merged).unwrap().command {
+                Some(Commands::Decrypt(args)) => args,
+                _ => panic!("命令解析错误"),
+            }
+        };
+
+        let args = parse("mwx-cli decrypt -o ./out");
+        assert_eq!(args.threads, Some(8));
+        assert!(args.ordered);
+        assert!(!args.snapshot);
+        assert_eq!(args.only, [DbCategory::Message, DbCategory::Contact]);
+
+        let args = parse("mwx-cli -l debug decrypt -o ./out --threads 2");
+        assert_eq!(args.threads, Some(2));
+
+        let args: Vec<OsString> = ["mwx-cli", "version"].map(OsString::from).to_vec();
+        assert!(Cli::merge_command_defaults(&args, &context).is_none());
+    }
+
+    #[test]
+    fn test_config_language_before_parsing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let mut config = AppConfig::default();
+        config.general.language = Some("en-US".to_string());
+        config.save_to_file(&path).unwrap();
+
+        let path = path.display().to_string();
+        let inline = format!("--config={}", path);
+        for args in [
+            vec!["mwx-cli", "-c", path.as_str(), "decrypt", "--help"],
+            vec!["mwx-cli", inline.as_str(), "--help"],
+        ] {
+            let args: Vec<OsString> = args.into_iter().map(OsString::from).collect();
+            assert_eq!(Cli::config_language(&args).as_deref(), Some("en-US"));
+        }
+        let missing: Vec<OsString> = ["mwx-cli", "-c", "no-such-config.toml"].map(OsString::from).to_vec();
+        assert_eq!(Cli::config_language(&missing), None);
+    }
+
+    #[test]
+    fn test_honours_cancellation() {
+        let context = ExecutionContext::with_defaults(None);
+        let parse = |args: &[&str]| Cli::try_parse_from(args).unwrap_or_else(|e| panic!("{}", e));
+        assert!(parse(&["mwx-cli", "decrypt", "-o", "./out"]).honours_cancellation(&context));
+        assert!(!parse(&["mwx-cli", "key"]).honours_cancellation(&context));
+        assert!(!parse(&["mwx-cli"]).honours_cancellation(&context));
+        assert!(parse(&["mwx-cli"]).honours_cancellation(&context_with_default("decrypt -o ./out")));
+    }
+
+    #[test]
+    fn test_usage_error_exit_code_is_reserved() {
+        use mwxdump_core::errors::exit_code;
+
+        let Err(error) = Cli::try_parse_from(["mwx-cli", "--no-such-flag"]) else {
+            panic!("未知参数应解析失败");
+        };
+        assert_eq!(error.exit_code(), exit_code::USAGE);
+        for code in [
+            exit_code::PROCESS_NOT_FOUND,
+            exit_code::PERMISSION_DENIED,
+            exit_code::KEY_EXTRACTION_FAILED,
+            exit_code::DECRYPTION_FAILED,
+            exit_code::CONFIG_ERROR,
+        ] {
+            assert_ne!(code, exit_code::USAGE);
+        }
+    }
+}
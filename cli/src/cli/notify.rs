@@ -0,0 +1,59 @@
+//! 桌面通知
+//!
+//! 耗时命令加 `--notify` 时，结束后通过 PowerShell 弹出 Windows 系统通知；其他平台只写日志。
+
+use std::time::Duration;
+
+/// PowerShell 的应用标识，未注册的标识在 Windows 10 上不会显示通知
+#[cfg(windows)]
+const TOAST_APP_ID: &str = r"{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\WindowsPowerShell\v1.0\powershell.exe";
+
+/// 标题和正文通过环境变量传入，避免转义问题
+#[cfg(windows)]
+const TOAST_SCRIPT: &str = r#"
+[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] | Out-Null
+$xml = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02)
+$text = $xml.GetElementsByTagName('text')
+$text.Item(0).AppendChild($xml.CreateTextNode($env:MWX_TOAST_TITLE)) | Out-Null
+$text.Item(1).AppendChild($xml.CreateTextNode($env:MWX_TOAST_BODY)) | Out-Null
+[Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier($env:MWX_TOAST_APP).Show([Windows.UI.Notifications.ToastNotification]::new($xml))
+"#;
+
+/// 发送系统通知，失败时只记录日志
+pub fn toast(title: &str, body: &str) {
+    tracing::info!("{}: {}", title, body);
+    #[cfg(windows)]
+    {
+        let status = std::process::Command::new("powershell")
+            .args(["-NoProfile", "-NonInteractive", "-Command", TOAST_SCRIPT])
+            .env("MWX_TOAST_TITLE", title)
+            .env("MWX_TOAST_BODY", body)
+            .env("MWX_TOAST_APP", TOAST_APP_ID)
+            .status();
+        if let Err(e) = status {
+            tracing::warn!("发送系统通知失败: {}", e);
+        }
+    }
+}
+
+/// 耗时的简短表示，如 `1h 02m`、`3m 05s`、`12s`
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m {:02}s", secs / 60, secs % 60),
+        _ => format!("{}h {:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(12_400)), "12s");
+        assert_eq!(format_duration(Duration::from_secs(185)), "3m 05s");
+        assert_eq!(format_duration(Duration::from_secs(3720)), "1h 02m");
+    }
+}
@@ -0,0 +1,101 @@
+//! 配置文件格式版本与迁移
+//!
+//! 配置文件通过顶层的 `version` 记录格式版本，缺少时视为版本 0。加载旧版本的配置时依次执行
+//! 迁移函数（重命名或移动配置项、清理旧的占位值等），在原文件旁保留备份后原地升级。
+//! 迁移基于 `toml_edit`，保留文件中的注释和排版。
+
+use std::path::{Path, PathBuf};
+use toml_edit::DocumentMut;
+
+use mwxdump_core::errors::{ConfigError, Result};
+
+/// 当前配置文件格式版本
+pub const CONFIG_VERSION: u32 = 1;
+
+/// 迁移函数，把配置从下标对应的版本升级到下一版本
+type Migration = fn(&mut DocumentMut);
+
+/// 按版本排列的迁移函数，第 `n` 项把版本 `n` 升级到 `n + 1`
+const MIGRATIONS: [Migration; CONFIG_VERSION as usize] = [drop_placeholder_key];
+
+/// 迁移配置内容
+///
+/// 已是当前版本时返回 `None`，否则返回原版本号和升级后的内容。
+/// 配置版本高于当前程序支持的版本时报错，避免旧程序改写新格式的配置。
+pub fn migrate(content: &str) -> Result<Option<(u32, String)>> {
+    let mut document: DocumentMut = content
+        .parse()
+        .map_err(|e: toml_edit::TomlError| ConfigError::ParseError(e.to_string()))?;
+
+    let version = match document.get("version") {
+        None => 0,
+        Some(item) => item
+            .as_integer()
+            .and_then(|v| u32::try_from(v).ok())
+            .filter(|&v| v <= CONFIG_VERSION)
+            .ok_or_else(|| ConfigError::InvalidValue {
+                key: "version".to_string(),
+                value: item.to_string().trim().to_string(),
+            })?,
+    };
+    if version == CONFIG_VERSION {
+        return Ok(None);
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(&mut document);
+    }
+    document["version"] = toml_edit::value(CONFIG_VERSION as i64);
+    Ok(Some((version, document.to_string())))
+}
+
+/// 备份原配置文件后写入升级后的内容，返回备份文件路径
+pub fn upgrade_file(path: &Path, from_version: u32, content: &str) -> Result<PathBuf> {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".v{}.bak", from_version));
+    let backup = PathBuf::from(backup);
+
+    std::fs::copy(path, &backup)?;
+    std::fs::write(path, content)?;
+    Ok(backup)
+}
+
+/// 0 → 1：删除从示例配置复制来的占位密钥，否则会被当作格式错误的密钥
+fn drop_placeholder_key(document: &mut DocumentMut) {
+    if let Some(wechat) = document.get_mut("wechat").and_then(|item| item.as_table_like_mut()) {
+        if wechat.get("data_key").and_then(|item| item.as_str()) == Some("your_preset_key_here") {
+            wechat.remove("data_key");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_legacy_config() {
+        let legacy = "# 示例\n[wechat]\ndata_key = \"your_preset_key_here\"\n# 自动解密\nauto_decrypt = false\n";
+        let (from, upgraded) = migrate(legacy).unwrap().unwrap();
+        assert_eq!(from, 0);
+        assert!(upgraded.starts_with("version = 1\n"));
+        assert!(upgraded.contains("# 自动解密\nauto_decrypt = false"));
+        assert!(!upgraded.contains("data_key"));
+
+        assert!(migrate(&upgraded).unwrap().is_none());
+        assert!(migrate("version = 99\n").is_err());
+    }
+
+    #[test]
+    fn test_upgrade_file_keeps_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[logging]\nlevel = \"info\"\n").unwrap();
+
+        let (from, upgraded) = migrate(&std::fs::read_to_string(&path).unwrap()).unwrap().unwrap();
+        let backup = upgrade_file(&path, from, &upgraded).unwrap();
+        assert_eq!(backup, dir.path().join("config.toml.v0.bak"));
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), "[logging]\nlevel = \"info\"\n");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), upgraded);
+    }
+}
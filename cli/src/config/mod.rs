@@ -3,13 +3,45 @@
 //! 负责应用配置的加载、验证和管理
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
-use mwxdump_core::errors::{ConfigError, Result};
+use mwxdump_core::errors::{ConfigError, ConfigIssue, Result};
+use mwxdump_core::backup::RetentionPolicy;
+use mwxdump_core::plugins::PluginConfig;
+#[cfg(feature = "ocr")]
+use mwxdump_core::wechat::media::OcrConfig;
+#[cfg(feature = "transcription")]
+use mwxdump_core::wechat::media::TranscriptionConfig;
+use mwxdump_core::wechat::media::VoiceConfig;
+use mwxdump_core::webhook::WebhookSettings;
+use mwxdump_core::wechat::db::QueryCacheConfig;
+use mwxdump_core::wechat::key::KeyOffset;
+use mwxdump_core::wechat::process::DataDirValidation;
+use mwxdump_core::scopes::Scope;
+pub use mwxdump_core::wechat::process::AccountProfile;
 use toml::toml;
 
+pub mod migration;
+
+pub use migration::CONFIG_VERSION;
+
+/// 环境变量配置前缀
+pub const ENV_PREFIX: &str = "MWX";
+
+/// 默认配置文件名
+pub const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
 /// 应用主配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
+    /// 配置文件格式版本，缺少时为 0，加载时自动迁移到 [`CONFIG_VERSION`]
+    #[serde(default)]
+    pub version: u32,
+    
+    /// 通用配置
+    #[serde(default)]
+    pub general: GeneralConfig,
+    
     /// HTTP服务配置
     pub http: HttpConfig,
     
@@ -21,6 +53,52 @@ pub struct AppConfig {
     
     /// 日志配置
     pub logging: LoggingConfig,
+    
+    /// 备份配置
+    #[serde(default)]
+    pub backup: BackupConfig,
+    
+    /// 消息处理插件
+    #[serde(default)]
+    pub plugins: Vec<PluginConfig>,
+    
+    /// 账号配置
+    #[serde(default)]
+    pub accounts: Vec<AccountProfile>,
+    
+    /// 各子命令的默认参数，如 `[command.decrypt]` 下的 `threads = 8`，命令行参数优先
+    #[serde(default)]
+    pub command: BTreeMap<String, toml::Table>,
+    
+    /// 图片文字识别
+    #[cfg(feature = "ocr")]
+    #[serde(default)]
+    pub ocr: OcrConfig,
+    
+    /// 语音消息转写（导出时使用）
+    #[cfg(feature = "transcription")]
+    #[serde(default)]
+    pub transcription: TranscriptionConfig,
+    
+    /// 语音转码（media voices 和导出语音时使用）
+    #[serde(default)]
+    pub voice: VoiceConfig,
+    
+    /// 自动刷新发现新消息时推送的 Webhook
+    #[serde(default)]
+    pub webhook: WebhookSettings,
+}
+
+/// 通用配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GeneralConfig {
+    /// 界面语言（zh-CN / en-US），未设置时根据 LANG 环境变量选择
+    pub language: Option<String>,
+    
+    /// 未指定子命令时执行的默认命令（含参数），例如 `"decrypt -o ./decrypted"`
+    ///
+    /// 参数按 shell 规则拆分，含空格的路径需加引号。未设置时显示帮助信息。
+    pub default_command: Option<String>,
 }
 
 /// HTTP服务配置
@@ -35,8 +113,55 @@ pub struct HttpConfig {
     /// 是否启用CORS
     pub enable_cors: bool,
     
+    /// 允许跨域访问的来源，默认只包含桌面端 webview 和开发服务器
+    #[serde(default = "default_cors_origins")]
+    pub cors_origins: Vec<String>,
+    
     /// 静态文件目录
     pub static_dir: Option<PathBuf>,
+    
+    /// 接口访问令牌，设置后请求需携带 `Authorization: Bearer <token>`，拥有全部权限
+    #[serde(default)]
+    pub auth_token: Option<String>,
+    
+    /// 限定权限范围的访问令牌
+    #[serde(default)]
+    pub tokens: Vec<ApiToken>,
+    
+    /// 是否开启密钥提取接口，开启后还要求设置 `auth_token`
+    #[serde(default)]
+    pub allow_key_extraction: bool,
+    
+    /// 密钥提取接口是否允许 `?reveal=true` 返回原始密钥，否则只返回指纹
+    #[serde(default)]
+    pub allow_key_reveal: bool,
+}
+
+/// 限定权限范围的访问令牌
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiToken {
+    /// 名称，用于日志
+    pub name: String,
+    /// 令牌
+    pub token: String,
+    /// 授予的权限，如 `read:messages`
+    #[serde(default)]
+    pub scopes: Vec<Scope>,
+}
+
+impl HttpConfig {
+    /// 是否要求访问令牌
+    pub fn auth_enabled(&self) -> bool {
+        self.auth_token.is_some() || !self.tokens.is_empty()
+    }
+}
+
+/// 默认允许的跨域来源：Tauri webview（macOS/Linux 与 Windows）和前端开发服务器
+pub fn default_cors_origins() -> Vec<String> {
+    ["tauri://localhost", "http://tauri.localhost", "https://tauri.localhost", "http://localhost:1420"]
+        .into_iter()
+        .map(String::from)
+        .collect()
 }
 
 /// 数据库配置
@@ -50,6 +175,10 @@ pub struct DatabaseConfig {
     
     /// 连接超时时间（秒）
     pub connection_timeout: u64,
+    
+    /// 查询结果缓存
+    #[serde(default)]
+    pub cache: QueryCacheConfig,
 }
 
 /// 微信配置
@@ -61,11 +190,31 @@ pub struct WeChatConfig {
     /// 数据密钥
     pub data_key: Option<String>,
     
-    /// 是否启用自动解密
+    /// 启动 HTTP 服务时自动解密检测到的数据到工作目录
     pub auto_decrypt: bool,
     
-    /// 支持的微信版本
+    /// 支持的微信版本，如 `4.0` 或 `4.x`；检测到的进程不在其中时拒绝提取密钥，为空时不限制
     pub supported_versions: Vec<String>,
+    
+    /// 密钥指针偏移（其他策略失败时使用）
+    #[serde(default)]
+    pub key_offsets: Vec<KeyOffset>,
+    
+    /// 用户版本特征文件，条目优先于内置特征
+    #[serde(default)]
+    pub signatures_file: Option<PathBuf>,
+    
+    /// 数据目录验证方式：filesystem（默认）或 memory（严格模式）
+    #[serde(default)]
+    pub data_dir_validation: DataDirValidation,
+}
+
+/// 备份配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BackupConfig {
+    /// 快照保留策略，以快照方式备份后自动清理
+    #[serde(default)]
+    pub retention: RetentionPolicy,
 }
 
 /// 日志配置
@@ -84,16 +233,24 @@ pub struct LoggingConfig {
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
+            version: CONFIG_VERSION,
+            general: GeneralConfig::default(),
             http: HttpConfig {
                 host: "127.0.0.1".to_string(),
                 port: 5030,
                 enable_cors: true,
+                cors_origins: default_cors_origins(),
                 static_dir: None,
+                auth_token: None,
+                tokens: Vec::new(),
+                allow_key_extraction: false,
+                allow_key_reveal: false,
             },
             database: DatabaseConfig {
                 work_dir: PathBuf::from("./work"),
                 pool_size: 10,
                 connection_timeout: 30,
+                cache: QueryCacheConfig::default(),
             },
             wechat: WeChatConfig {
                 data_dir: None,
@@ -101,21 +258,80 @@ impl Default for AppConfig {
                 auto_decrypt: false,
                 supported_versions: vec![
                     "3.x".to_string(),
-                    "4.0".to_string(),
+                    "4.x".to_string(),
                 ],
+                key_offsets: Vec::new(),
+                signatures_file: None,
+                data_dir_validation: DataDirValidation::default(),
             },
             logging: LoggingConfig {
                 level: "info".to_string(),
                 file: None,
                 console: true,
             },
+            backup: BackupConfig::default(),
+            plugins: Vec::new(),
+            accounts: Vec::new(),
+            command: BTreeMap::new(),
+            #[cfg(feature = "ocr")]
+            ocr: OcrConfig::default(),
+            #[cfg(feature = "transcription")]
+            transcription: TranscriptionConfig::default(),
+            voice: VoiceConfig::default(),
+            webhook: WebhookSettings::default(),
         }
     }
 }
 
+/// 配置文件解析模式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConfigMode {
+    /// 忽略未知配置项，仅给出警告
+    #[default]
+    Lenient,
+    /// 出现未知配置项（如把 `[http]` 拼成 `[htttp]`）时拒绝加载
+    Strict,
+}
+
+impl ConfigMode {
+    /// 由 `--strict-config` 开关确定模式
+    pub fn from_strict(strict: bool) -> Self {
+        if strict {
+            Self::Strict
+        } else {
+            Self::Lenient
+        }
+    }
+}
+
+/// 数据密钥是否为 32 字节的十六进制
+fn is_valid_key(key: &str) -> bool {
+    hex::decode(key.trim()).is_ok_and(|bytes| bytes.len() == 32)
+}
+
+/// 配置值的命令行形式，字符串不带引号
+fn arg_value(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// 未知配置项对应的问题
+pub fn unknown_key_issues(keys: &[String]) -> Vec<ConfigIssue> {
+    keys.iter()
+        .map(|key| ConfigIssue::new(key, "", "未知的配置项，请检查是否拼写错误"))
+        .collect()
+}
+
 impl AppConfig {
-    /// 从文件加载配置
+    /// 从文件加载配置，忽略未知配置项
     pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        Self::from_file_with_mode(path, ConfigMode::Lenient).map(|(config, _)| config)
+    }
+    
+    /// 按指定模式从文件加载配置，同时返回未知配置项的路径
+    pub fn from_file_with_mode<P: AsRef<std::path::Path>>(path: P, mode: ConfigMode) -> Result<(Self, Vec<String>)> {
         let path = path.as_ref();
         
         if !path.exists() {
@@ -127,13 +343,75 @@ impl AppConfig {
         let content = std::fs::read_to_string(path)
             .map_err(|e| ConfigError::ParseError(e.to_string()))?;
         
-        let config: AppConfig = toml::from_str(&content)
-            .map_err(|e| ConfigError::ParseError(e.to_string()))?;
+        // 旧版本配置先迁移，备份后原地升级；无法写入时仅在内存中使用升级后的配置
+        let content = match migration::migrate(&content)? {
+            Some((from_version, upgraded)) => {
+                match migration::upgrade_file(path, from_version, &upgraded) {
+                    Ok(backup) => tracing::info!(
+                        "配置文件已从版本 {} 升级到 {}，原文件备份为 {}",
+                        from_version,
+                        CONFIG_VERSION,
+                        backup.display()
+                    ),
+                    Err(e) => tracing::warn!("无法升级配置文件 {}: {}", path.display(), e),
+                }
+                upgraded
+            }
+            None => content,
+        };
         
+        let (config, unknown_keys) = Self::parse(&content)?;
+        
+        let mut issues = match mode {
+            ConfigMode::Strict => unknown_key_issues(&unknown_keys),
+            ConfigMode::Lenient => Vec::new(),
+        };
+        issues.extend(config.issues());
+        if !issues.is_empty() {
+            return Err(ConfigError::Invalid(issues).into());
+        }
+        Ok((config, unknown_keys))
+    }
+    
+    /// 解析 TOML 配置内容，返回配置和按路径排序的未知配置项（如 `htttp`、`http.prot`）
+    pub fn parse(content: &str) -> Result<(Self, Vec<String>)> {
+        let deserializer = toml::Deserializer::parse(content)
+            .map_err(|e| ConfigError::ParseError(e.to_string()))?;
+        let mut unknown_keys = Vec::new();
+        let config = serde_ignored::deserialize(deserializer, |path| unknown_keys.push(path.to_string()))
+            .map_err(|e| ConfigError::ParseError(e.to_string()))?;
+        unknown_keys.sort();
+        Ok((config, unknown_keys))
+    }
+    
+    /// 仅从环境变量加载配置（无头/容器模式）
+    ///
+    /// 在默认配置之上叠加 `MWX_` 前缀的环境变量，层级之间使用 `__` 分隔，
+    /// 例如 `MWX_HTTP__PORT=8080`、`MWX_WECHAT__DATA_DIR=/data`。
+    /// 列表项使用逗号分隔，例如 `MWX_WECHAT__SUPPORTED_VERSIONS=3.x,4.0`。
+    pub fn from_env() -> Result<Self> {
+        let defaults = config::Config::try_from(&AppConfig::default())
+            .map_err(|e| ConfigError::ParseError(e.to_string()))?;
+
+        let config: AppConfig = config::Config::builder()
+            .add_source(defaults)
+            .add_source(
+                config::Environment::with_prefix(ENV_PREFIX)
+                    .prefix_separator("_")
+                    .separator("__")
+                    .list_separator(",")
+                    .with_list_parse_key("wechat.supported_versions")
+                    .with_list_parse_key("http.cors_origins")
+                    .try_parsing(true),
+            )
+            .build()
+            .and_then(|c| c.try_deserialize())
+            .map_err(|e| ConfigError::ParseError(e.to_string()))?;
+
         config.validate()?;
         Ok(config)
     }
-    
+
     /// 保存配置到文件
     pub fn save_to_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
         let content = toml::to_string_pretty(self)
@@ -145,33 +423,143 @@ impl AppConfig {
         Ok(())
     }
     
-    /// 验证配置
+    /// 验证配置，存在问题时返回列出所有问题的 [`ConfigError::Invalid`]
     pub fn validate(&self) -> Result<()> {
+        let issues = self.issues();
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(issues).into())
+        }
+    }
+    
+    /// 检查所有配置项，返回发现的全部问题
+    pub fn issues(&self) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+        
         // 验证端口范围
         if self.http.port == 0 {
-            return Err(ConfigError::InvalidValue {
-                key: "http.port".to_string(),
-                value: self.http.port.to_string(),
-            }.into());
+            issues.push(ConfigIssue::new("http.port", self.http.port.to_string(), "端口不能为 0"));
+        }
+        
+        // 验证跨域来源，必须是完整的 `scheme://host[:port]`，不接受通配符
+        for origin in &self.http.cors_origins {
+            let valid = origin
+                .split_once("://")
+                .is_some_and(|(scheme, host)| !scheme.is_empty() && !host.is_empty() && !host.contains('/'))
+                && origin.is_ascii()
+                && !origin.contains(|c: char| c.is_ascii_whitespace() || c.is_ascii_control() || c == '*');
+            if !valid {
+                issues.push(ConfigIssue::new("http.cors_origins", origin, "应为完整的 scheme://host[:port]，不支持通配符"));
+            }
+        }
+        
+        // 验证访问令牌：不能为空或重复
+        let mut seen = std::collections::HashSet::new();
+        for token in &self.http.tokens {
+            if token.token.is_empty() || self.http.auth_token.as_ref() == Some(&token.token) || !seen.insert(&token.token) {
+                issues.push(ConfigIssue::new(format!("http.tokens.{}", token.name), "<token>", "令牌为空或与其他令牌重复"));
+            }
+        }
+        
+        // 验证插件脚本
+        for plugin in self.plugins.iter().filter(|p| p.enabled) {
+            if !plugin.script.is_file() {
+                issues.push(ConfigIssue::new(
+                    format!("plugins.{}.script", plugin.name),
+                    plugin.script.display().to_string(),
+                    "脚本文件不存在",
+                ));
+            }
+        }
+        
+        // 验证 Webhook 地址
+        for hook in self.webhook.hooks.iter().filter(|h| h.enabled) {
+            let valid = hook
+                .url
+                .split_once("://")
+                .is_some_and(|(scheme, rest)| matches!(scheme, "http" | "https") && !rest.is_empty());
+            if !valid {
+                issues.push(ConfigIssue::new(format!("webhook.hooks.{}.url", hook.name), &hook.url, "应为 http:// 或 https:// 开头的地址"));
+            }
+        }
+        
+        // 验证数据目录和密钥
+        if let Some(path) = &self.wechat.data_dir {
+            if !path.is_dir() {
+                issues.push(ConfigIssue::new("wechat.data_dir", path.display().to_string(), "目录不存在"));
+            }
+        }
+        if let Some(key) = &self.wechat.data_key {
+            if !is_valid_key(key) {
+                issues.push(ConfigIssue::new("wechat.data_key", "<key>", "应为 64 位十六进制"));
+            }
         }
         
-        // 验证工作目录
-        if !self.database.work_dir.is_absolute() {
-            // 如果是相对路径，转换为绝对路径
+        // 验证版本特征文件
+        if let Some(path) = &self.wechat.signatures_file {
+            if !path.is_file() {
+                issues.push(ConfigIssue::new("wechat.signatures_file", path.display().to_string(), "文件不存在"));
+            }
+        }
+        
+        // 验证账号配置
+        for account in &self.accounts {
+            if !account.data_dir.is_dir() {
+                issues.push(ConfigIssue::new(
+                    format!("accounts.{}.data_dir", account.name),
+                    account.data_dir.display().to_string(),
+                    "目录不存在",
+                ));
+            }
+            if account.data_key.as_deref().is_some_and(|key| !is_valid_key(key)) {
+                issues.push(ConfigIssue::new(format!("accounts.{}.data_key", account.name), "<key>", "应为 64 位十六进制"));
+            }
         }
         
         // 验证日志级别
         match self.logging.level.as_str() {
             "trace" | "debug" | "info" | "warn" | "error" => {}
             _ => {
-                return Err(ConfigError::InvalidValue {
-                    key: "logging.level".to_string(),
-                    value: self.logging.level.clone(),
-                }.into());
+                issues.push(ConfigIssue::new(
+                    "logging.level",
+                    &self.logging.level,
+                    "未知的日志级别，可选 trace、debug、info、warn、error",
+                ));
             }
         }
         
-        Ok(())
+        issues
+    }
+    
+    /// 子命令在配置中的默认参数，转换为 `--key=value` 形式的命令行参数
+    ///
+    /// 键名中的 `_` 转换为 `-`；布尔值为 `true` 时只输出开关，数组的每一项各输出一次。
+    pub fn command_args(&self, name: &str) -> Vec<String> {
+        let Some(table) = self.command.get(name) else {
+            return Vec::new();
+        };
+        let mut args = Vec::new();
+        for (key, value) in table {
+            let flag = format!("--{}", key.replace('_', "-"));
+            match value {
+                toml::Value::Boolean(true) => args.push(flag),
+                toml::Value::Boolean(false) => {}
+                toml::Value::Array(items) => {
+                    args.extend(items.iter().map(|item| format!("{}={}", flag, arg_value(item))));
+                }
+                other => args.push(format!("{}={}", flag, arg_value(other))),
+            }
+        }
+        args
+    }
+    
+    /// 添加或替换同名账号配置
+    pub fn upsert_account(&mut self, profile: AccountProfile) {
+        match self.accounts.iter_mut().find(|a| a.name == profile.name) {
+            Some(existing) => *existing = profile,
+            None => self.accounts.push(profile),
+        }
     }
     
     /// 获取HTTP服务地址
@@ -185,6 +573,8 @@ impl AppConfig {
 pub struct ConfigService {
     config: AppConfig,
     config_path: Option<PathBuf>,
+    /// 加载时忽略的未知配置项
+    unknown_keys: Vec<String>,
 }
 
 impl ConfigService {
@@ -193,25 +583,56 @@ impl ConfigService {
         Self {
             config: AppConfig::default(),
             config_path: None,
+            unknown_keys: Vec::new(),
         }
     }
     
-    /// 从文件加载配置
-    pub fn load_from_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+    /// 按指定模式从文件加载配置
+    pub fn load_from_file<P: AsRef<std::path::Path>>(path: P, mode: ConfigMode) -> Result<Self> {
         let path = path.as_ref().to_path_buf();
-        let config = AppConfig::from_file(&path)?;
+        let (config, unknown_keys) = AppConfig::from_file_with_mode(&path, mode)?;
         
         Ok(Self {
             config,
             config_path: Some(path),
+            unknown_keys,
         })
     }
     
+    /// 查找默认位置的配置文件
+    ///
+    /// 未通过 `--config` 或 `MWX_CONFIG` 指定时，只查找可执行文件所在目录下的 `config.toml`，
+    /// 便于直接双击运行或由计划任务无参数调用。不查找当前目录，避免在任意目录下运行时
+    /// 加载其中的配置（包括 `general.default_command`）。
+    pub fn find_default_config() -> Option<PathBuf> {
+        let exe = std::env::current_exe().ok()?;
+        Some(exe.parent()?.join(DEFAULT_CONFIG_FILE)).filter(|path| path.is_file())
+    }
+    
+    /// 从环境变量加载配置
+    pub fn load_from_env() -> Result<Self> {
+        Ok(Self {
+            config: AppConfig::from_env()?,
+            config_path: None,
+            unknown_keys: Vec::new(),
+        })
+    }
+    
+    /// 加载时忽略的未知配置项
+    pub fn unknown_keys(&self) -> &[String] {
+        &self.unknown_keys
+    }
+
     /// 获取配置
     pub fn config(&self) -> &AppConfig {
         &self.config
     }
     
+    /// 配置文件路径，从环境变量加载时为 `None`
+    pub fn config_path(&self) -> Option<&std::path::Path> {
+        self.config_path.as_deref()
+    }
+    
     /// 更新配置
     pub fn update_config<F>(&mut self, f: F) -> Result<()>
     where
@@ -236,4 +657,105 @@ impl ConfigService {
             Err(ConfigError::ParseError("No config file path set".to_string()).into())
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+
+    #[test]
+    #[serial]
+    fn test_from_env_overrides_defaults() {
+        let data_dir = tempfile::tempdir().unwrap();
+        std::env::set_var("MWX_HTTP__PORT", "8080");
+        std::env::set_var("MWX_WECHAT__DATA_DIR", data_dir.path());
+        std::env::set_var("MWX_WECHAT__SUPPORTED_VERSIONS", "4.0,4.1");
+
+        let config = AppConfig::from_env().unwrap();
+
+        std::env::remove_var("MWX_HTTP__PORT");
+        std::env::remove_var("MWX_WECHAT__DATA_DIR");
+        std::env::remove_var("MWX_WECHAT__SUPPORTED_VERSIONS");
+
+        assert_eq!(config.http.port, 8080);
+        assert_eq!(config.http.host, "127.0.0.1");
+        assert_eq!(config.wechat.data_dir.as_deref(), Some(data_dir.path()));
+        assert_eq!(config.wechat.supported_versions, vec!["4.0", "4.1"]);
+    }
+
+    #[test]
+    fn test_upsert_account_replaces_by_name() {
+        let profile = |key: &str| AccountProfile {
+            name: "main".to_string(),
+            wxid: Some("wxid_test".to_string()),
+            data_dir: PathBuf::from("/data"),
+            data_key: Some(key.to_string()),
+            output_dir: PathBuf::from("./work"),
+        };
+
+        let mut config = AppConfig::default();
+        config.upsert_account(profile("aa"));
+        config.upsert_account(profile("bb"));
+
+        assert_eq!(config.accounts.len(), 1);
+        assert_eq!(config.accounts[0].data_key.as_deref(), Some("bb"));
+    }
+
+    #[test]
+    #[serial]
+    fn test_from_env_without_variables() {
+        let config = AppConfig::from_env().unwrap();
+        assert_eq!(config.http.port, 5030);
+        assert_eq!(config.logging.level, "info");
+    }
+
+    #[test]
+    fn test_validate_cors_origins() {
+        let mut config = AppConfig::default();
+        assert!(config.validate().is_ok());
+        for origin in ["*", "localhost:1420", "https://nas.local/", "https://*.example.com"] {
+            config.http.cors_origins = vec![origin.to_string()];
+            assert!(config.validate().is_err(), "{}", origin);
+        }
+    }
+
+    #[test]
+    fn test_validate_collects_all_issues() {
+        let mut config = AppConfig::default();
+        config.http.port = 0;
+        config.logging.level = "verbose".to_string();
+        config.wechat.data_dir = Some(PathBuf::from("/nonexistent/wechat"));
+        config.wechat.data_key = Some("abcd".to_string());
+
+        let keys: Vec<_> = config.issues().into_iter().map(|issue| issue.key).collect();
+        assert_eq!(keys, ["http.port", "wechat.data_dir", "wechat.data_key", "logging.level"]);
+
+        let err = config.validate().unwrap_err();
+        match err.downcast_ref::<ConfigError>() {
+            Some(ConfigError::Invalid(issues)) => assert_eq!(issues.len(), 4),
+            other => panic!("unexpected error: {:?}", other),
+        }
+        assert!(err.to_string().contains("logging.level = verbose"));
+    }
+
+    #[test]
+    fn test_unknown_keys_in_strict_and_lenient_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let defaults = toml::to_string(&AppConfig::default()).unwrap();
+        std::fs::write(&path, format!("verbose = true\n\n[htttp]\nport = 8080\n\n{}", defaults)).unwrap();
+
+        let (config, unknown_keys) = AppConfig::from_file_with_mode(&path, ConfigMode::Lenient).unwrap();
+        assert_eq!(config.http.port, 5030);
+        assert_eq!(unknown_keys, ["htttp", "verbose"]);
+
+        let err = AppConfig::from_file_with_mode(&path, ConfigMode::Strict).unwrap_err();
+        match err.downcast_ref::<ConfigError>() {
+            Some(ConfigError::Invalid(issues)) => {
+                let keys: Vec<_> = issues.iter().map(|issue| issue.key.as_str()).collect();
+                assert_eq!(keys, ["htttp", "verbose"]);
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,211 @@
+//! 国际化模块
+//!
+//! 基于 Fluent 的轻量级本地化层，内置 zh-CN 与 en-US 两套资源。
+//! 语言选择顺序：配置文件 `general.language` > `LC_ALL`/`LC_MESSAGES`/`LANG` 环境变量 > zh-CN。
+//! 目标语言缺失的条目会回退到 zh-CN。
+
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use once_cell::sync::Lazy;
+use std::sync::{Arc, RwLock};
+use unic_langid::LanguageIdentifier;
+
+const ZH_CN_RESOURCE: &str = include_str!("../../locales/zh-CN/cli.ftl");
+const EN_US_RESOURCE: &str = include_str!("../../locales/en-US/cli.ftl");
+
+/// 支持的界面语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    /// 简体中文
+    #[default]
+    ZhCn,
+    /// 美式英语
+    EnUs,
+}
+
+impl Language {
+    /// 解析语言标签，支持 `en`、`en-US`、`en_US.UTF-8`、`zh_CN` 等形式
+    pub fn parse(tag: &str) -> Option<Self> {
+        let tag = tag.trim().to_ascii_lowercase();
+        let primary = tag
+            .split(['-', '_', '.'])
+            .next()
+            .unwrap_or_default();
+        match primary {
+            "zh" => Some(Language::ZhCn),
+            "en" => Some(Language::EnUs),
+            _ => None,
+        }
+    }
+
+    /// 从环境变量推断语言
+    pub fn from_env() -> Option<Self> {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Self::parse(&value))
+    }
+
+    /// 语言标签
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Language::ZhCn => "zh-CN",
+            Language::EnUs => "en-US",
+        }
+    }
+
+    fn resource(&self) -> &'static str {
+        match self {
+            Language::ZhCn => ZH_CN_RESOURCE,
+            Language::EnUs => EN_US_RESOURCE,
+        }
+    }
+}
+
+/// 单一语言的资源包
+struct Localizer {
+    language: Language,
+    bundle: FluentBundle<FluentResource>,
+    fallback: Option<FluentBundle<FluentResource>>,
+}
+
+impl Localizer {
+    fn new(language: Language) -> Self {
+        let fallback = if language == Language::ZhCn {
+            None
+        } else {
+            Some(build_bundle(Language::ZhCn))
+        };
+        Self {
+            language,
+            bundle: build_bundle(language),
+            fallback,
+        }
+    }
+
+    fn format(&self, key: &str, args: Option<&FluentArgs>) -> String {
+        format_in(&self.bundle, key, args)
+            .or_else(|| self.fallback.as_ref().and_then(|b| format_in(b, key, args)))
+            .unwrap_or_else(|| key.to_string())
+    }
+}
+
+fn build_bundle(language: Language) -> FluentBundle<FluentResource> {
+    let langid: LanguageIdentifier = language
+        .as_str()
+        .parse()
+        .expect("内置语言标签必须合法");
+    let resource = FluentResource::try_new(language.resource().to_string())
+        .expect("内置 Fluent 资源必须合法");
+
+    let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+    // 终端输出不需要 Unicode 方向隔离符
+    bundle.set_use_isolating(false);
+    bundle
+        .add_resource(resource)
+        .expect("内置 Fluent 资源不允许重复条目");
+    bundle
+}
+
+fn format_in(
+    bundle: &FluentBundle<FluentResource>,
+    key: &str,
+    args: Option<&FluentArgs>,
+) -> Option<String> {
+    let pattern = bundle.get_message(key)?.value()?;
+    let mut errors = Vec::new();
+    let text = bundle.format_pattern(pattern, args, &mut errors).into_owned();
+    if !errors.is_empty() {
+        tracing::debug!("格式化本地化条目 {} 出错: {:?}", key, errors);
+    }
+    Some(text)
+}
+
+static CURRENT: Lazy<RwLock<Arc<Localizer>>> = Lazy::new(|| {
+    RwLock::new(Arc::new(Localizer::new(
+        Language::from_env().unwrap_or_default(),
+    )))
+});
+
+fn current() -> Arc<Localizer> {
+    CURRENT.read().expect("i18n 锁已损坏").clone()
+}
+
+/// 切换当前语言
+pub fn set_language(language: Language) {
+    if current().language == language {
+        return;
+    }
+    *CURRENT.write().expect("i18n 锁已损坏") = Arc::new(Localizer::new(language));
+}
+
+/// 根据语言标签切换语言，无法识别的标签将被忽略
+pub fn set_language_tag(tag: &str) {
+    match Language::parse(tag) {
+        Some(language) => set_language(language),
+        None => tracing::warn!("不支持的界面语言: {}，继续使用 {}", tag, language().as_str()),
+    }
+}
+
+/// 当前语言
+pub fn language() -> Language {
+    current().language
+}
+
+/// 获取本地化文本
+pub fn tr(key: &str) -> String {
+    current().format(key, None)
+}
+
+/// 获取带参数的本地化文本
+pub fn tr_args(key: &str, args: &[(&str, String)]) -> String {
+    let mut fluent_args = FluentArgs::new();
+    for (name, value) in args {
+        fluent_args.set(*name, value.clone());
+    }
+    current().format(key, Some(&fluent_args))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_language() {
+        assert_eq!(Language::parse("en_US.UTF-8"), Some(Language::EnUs));
+        assert_eq!(Language::parse("en"), Some(Language::EnUs));
+        assert_eq!(Language::parse("zh-CN"), Some(Language::ZhCn));
+        assert_eq!(Language::parse("C"), None);
+    }
+
+    #[test]
+    fn test_format_with_args() {
+        let localizer = Localizer::new(Language::EnUs);
+        let mut args = FluentArgs::new();
+        args.set("count", 2);
+        assert_eq!(
+            localizer.format("process-found", Some(&args)),
+            "✅ Found 2 WeChat process(es):"
+        );
+    }
+
+    #[test]
+    fn test_bundles_have_same_keys() {
+        let ids = |source: &str| {
+            source
+                .lines()
+                .filter(|line| line.starts_with(|c: char| c.is_ascii_lowercase()))
+                .filter_map(|line| line.split(" =").next())
+                .map(str::to_string)
+                .collect::<std::collections::BTreeSet<_>>()
+        };
+        assert_eq!(ids(ZH_CN_RESOURCE), ids(EN_US_RESOURCE));
+    }
+
+    #[test]
+    fn test_missing_key_returns_key() {
+        let localizer = Localizer::new(Language::ZhCn);
+        assert_eq!(localizer.format("no-such-key", None), "no-such-key");
+    }
+}
@@ -9,6 +9,8 @@ pub use mwxdump_core::*;
 pub mod app;
 pub mod cli;
 pub mod config;
+pub mod i18n;
+pub mod server;
 
 // 为 HTTP 响应添加错误转换
 use axum::response::IntoResponse;
@@ -1,67 +1,164 @@
-use clap::Parser;
 use tracing::{info, error};
-use mwxdump_core::errors::Result;
+use mwxdump_core::errors::{error_code, exit_code, Result};
 mod app;
 mod cli;
 mod config;
+mod i18n;
+mod server;
 
 use cli::Cli;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     // 解析命令行参数
-    let cli = Cli::parse();
+    let cli = Cli::parse_localized();
+    
+    // 无头模式下关闭终端颜色
+    if cli.headless {
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+    }
     
     // 创建执行上下文以确定最终的日志级别
-    let context = match cli::context::ExecutionContext::new(cli.config.clone(), cli.log_level.clone()) {
+    let context_result = if cli.headless {
+        cli::context::ExecutionContext::headless(cli.log_level.clone())
+    } else {
+        cli::context::ExecutionContext::new(
+            cli.config.clone(),
+            cli.log_level.clone(),
+            config::ConfigMode::from_strict(cli.strict_config),
+        )
+    };
+    let context = match context_result {
         Ok(ctx) => ctx,
         Err(e) => {
-            eprintln!("创建执行上下文失败: {}", e);
-            std::process::exit(1);
+            eprintln!("{}", i18n::tr_args("context-create-failed", &[("error", e.to_string())]));
+            std::process::exit(error_code(&e));
         }
     };
     
+    context.apply_language();
+    
     // 根据配置初始化日志系统
-    init_tracing(&context)?;
+    init_tracing(&context, cli.uses_stdout_protocol())?;
     
     info!("MwXdump 启动，日志级别: {}", context.log_level());
+    if let Some(path) = context.config_file() {
+        info!("配置文件: {}", path.display());
+    }
+    
+    install_ctrl_c_handler(context.cancellation_token(), cli.honours_cancellation(&context));
+    
+    if let Err(e) = context.load_signatures() {
+        error!("加载版本特征失败: {}", e);
+        eprintln!("{}", i18n::tr_args("exec-failed", &[("error", e.to_string())]));
+        std::process::exit(error_code(&e));
+    }
+    
+    // 合并配置中的子命令默认参数，命令行参数优先
+    let cli = match cli.with_command_defaults(&context) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("{}", i18n::tr_args("exec-failed", &[("error", e.to_string())]));
+            std::process::exit(error_code(&e));
+        }
+    };
     
     // 执行命令，传递已创建的上下文
     if let Err(e) = cli.execute_with_context(context).await {
         error!("执行失败: {}", e);
         
         // 打印更详细的错误信息到控制台
-        eprintln!("\n执行失败: {}", e);
+        eprintln!("\n{}", i18n::tr_args("exec-failed", &[("error", e.to_string())]));
         
         // 将错误转换为anyhow::Error以便获取更多信息
         let err_any = anyhow::anyhow!("{}", e);
         
         // 检查错误源
         if let Some(source) = err_any.source() {
-            eprintln!("错误原因: {}", source);
+            eprintln!("{}", i18n::tr_args("error-cause", &[("cause", source.to_string())]));
         }
         
-        // 如果是微信相关错误，提供更详细的错误信息和解决方案
-        if e.to_string().contains("微信进程未找到") {
-            eprintln!("详细信息: 未找到微信进程，请确保微信正在运行");
-        } else if e.to_string().contains("密钥提取失败") {
-            eprintln!("详细信息: 密钥提取失败，可能原因:");
-            eprintln!("  - 权限不足，请尝试以管理员身份运行");
-            eprintln!("  - 微信版本不受支持");
-            eprintln!("  - 内存搜索算法需要优化");
-        } else if e.to_string().contains("权限不足") {
-            eprintln!("详细信息: 权限不足，请尝试以管理员身份运行");
+        // 根据退出码提供更详细的错误信息和解决方案
+        let code = error_code(&e);
+        match code {
+            exit_code::PROCESS_NOT_FOUND => {
+                eprintln!("{}", i18n::tr("hint-process-not-found"));
+            }
+            exit_code::KEY_EXTRACTION_FAILED => {
+                eprintln!("{}", i18n::tr("hint-key-extraction-failed"));
+                eprintln!("{}", i18n::tr("hint-key-permission"));
+                eprintln!("{}", i18n::tr("hint-key-unsupported-version"));
+                eprintln!("{}", i18n::tr("hint-key-search-algorithm"));
+            }
+            exit_code::PERMISSION_DENIED => {
+                eprintln!("{}", i18n::tr("hint-permission-denied"));
+            }
+            exit_code::CANCELLED => {
+                eprintln!("{}", i18n::tr("hint-cancelled"));
+            }
+            _ => {}
         }
         
-        std::process::exit(1);
+        std::process::exit(code);
     }
     
     Ok(())
 }
 
-fn init_tracing(context: &cli::context::ExecutionContext) -> Result<()> {
+/// 安装 Ctrl-C 处理器
+///
+/// 命令响应取消令牌时，第一次 Ctrl-C 触发取消令牌，等待进行中的写入完成并清理未完成的输出，
+/// 第二次 Ctrl-C 立即退出；其他命令收到 Ctrl-C 后直接退出。
+fn install_ctrl_c_handler(token: tokio_util::sync::CancellationToken, graceful: bool) {
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_err() {
+            return;
+        }
+        if !graceful {
+            eprintln!("\n{}", i18n::tr("ctrl-c-interrupted"));
+            std::process::exit(exit_code::CANCELLED);
+        }
+        eprintln!("\n{}", i18n::tr("ctrl-c-received"));
+        token.cancel();
+        
+        if tokio::signal::ctrl_c().await.is_ok() {
+            eprintln!("{}", i18n::tr("ctrl-c-forced"));
+            std::process::exit(exit_code::CANCELLED);
+        }
+    });
+}
+
+fn init_tracing(context: &cli::context::ExecutionContext, stdout_protocol: bool) -> Result<()> {
     use mwxdump_core::logs::{LogConfig, LogLevel, LogOutput, init_tracing_with_config};
     
+    // 无头模式：JSON 格式日志输出到标准错误，便于容器日志采集
+    if context.is_headless() {
+        let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+            .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(context.log_level()));
+        tracing_subscriber::fmt()
+            .json()
+            .with_ansi(false)
+            .with_env_filter(filter)
+            .with_writer(std::io::stderr)
+            .try_init()
+            .map_err(|e| anyhow::anyhow!("初始化JSON日志失败: {}", e))?;
+        return Ok(());
+    }
+    
+    // 标准输出用于传输协议消息（如 mcp 命令）：日志输出到标准错误
+    if stdout_protocol {
+        let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+            .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(context.log_level()));
+        tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_env_filter(filter)
+            .with_writer(std::io::stderr)
+            .try_init()
+            .map_err(|e| anyhow::anyhow!("初始化日志失败: {}", e))?;
+        return Ok(());
+    }
+    
     // 根据执行上下文创建日志配置
     let log_level = match context.log_level().to_lowercase().as_str() {
         "error" => LogLevel::Error,
@@ -0,0 +1,56 @@
+//! 联系人接口
+//!
+//! `GET /api/v1/contacts?label=同事&limit=200`：分页列出联系人及其标签，可按标签筛选。
+
+use axum::extract::{Query, State};
+use axum::Json;
+use serde::Deserialize;
+use ts_rs::TS;
+
+use super::files::{allowed_roots, resolve_file};
+use super::{parse_cursor, ApiError, ServerState};
+use mwxdump_core::models::{Contact, Page};
+use mwxdump_core::wechat::db::contacts::{self, CONTACT_DB_PATH};
+use mwxdump_core::wechat::db::SqliteDataSource;
+
+/// 联系人查询参数
+#[derive(Debug, Default, Deserialize, TS)]
+#[ts(export, optional_fields)]
+pub struct ContactsParams {
+    /// 联系人数据库，相对工作目录或会话目录
+    pub db: Option<String>,
+    /// 只返回带有该标签的联系人
+    pub label: Option<String>,
+    /// 上一页返回的 `next_cursor`
+    pub cursor: Option<String>,
+    /// 每页条数
+    pub limit: Option<usize>,
+}
+
+/// 联系人列表默认每页条数
+pub const DEFAULT_CONTACT_PAGE: usize = 200;
+
+/// 联系人列表每页最多条数
+pub const MAX_CONTACT_PAGE: usize = 1000;
+
+/// 列出联系人
+pub async fn list_contacts(
+    State(state): State<ServerState>,
+    Query(params): Query<ContactsParams>,
+) -> Result<Json<Page<Contact>>, ApiError> {
+    let cursor = parse_cursor(params.cursor.as_deref())?;
+    let limit = params.limit.unwrap_or(DEFAULT_CONTACT_PAGE).clamp(1, MAX_CONTACT_PAGE);
+    let db = params.db.as_deref().unwrap_or(CONTACT_DB_PATH);
+    let db = resolve_file(&allowed_roots(&state).await, db)?;
+    let source = SqliteDataSource::open(&db)
+        .await
+        .map_err(|e| ApiError::bad_request(e.to_string()))?;
+    let mut list = contacts::load_contacts(&source)
+        .await
+        .map_err(|e| ApiError::bad_request(e.to_string()))?;
+    if let Some(label) = &params.label {
+        list = contacts::filter_by_label(list, label);
+    }
+    let page = Page::from_offset(list, cursor, limit).map_err(|e| ApiError::bad_request(e.to_string()))?;
+    Ok(Json(page))
+}
@@ -0,0 +1,59 @@
+//! 服务端事件推送
+//!
+//! `GET /api/v1/events`：以 SSE 转发事件总线上的事件，事件名与 JSON 中的 `type` 一致，
+//! 如客户端收到 `data_updated` 后重新加载对应数据，收到 `job_finished` 后刷新任务状态。
+
+use axum::extract::State;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use futures::stream::{self, Stream};
+use std::convert::Infallible;
+
+use super::ServerState;
+
+/// 订阅事件流
+pub async fn subscribe(State(state): State<ServerState>) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let receiver = state.events.subscribe();
+    let events = stream::unfold(receiver, |mut receiver| async move {
+        let event = receiver.recv().await?;
+        let sse = Event::default()
+            .event(event.name())
+            .json_data(&event)
+            .unwrap_or_default();
+        Some((Ok(sse), receiver))
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use chrono::Utc;
+    use std::path::PathBuf;
+    use crate::server::router;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use futures::StreamExt;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_data_updated_event_stream() {
+        let state = ServerState::new(AppConfig::default());
+        let response = router(state.clone())
+            .oneshot(Request::get("/api/v1/events").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        state.events.publish(mwxdump_core::events::Event::DataUpdated {
+            files: vec![PathBuf::from("db_storage/message/message_0.db")],
+            at: Utc::now(),
+        });
+
+        let mut body = response.into_body().into_data_stream();
+        let chunk = body.next().await.unwrap().unwrap();
+        let text = String::from_utf8_lossy(&chunk);
+        assert!(text.starts_with("event: data_updated"));
+        assert!(text.contains("message_0.db"));
+    }
+}
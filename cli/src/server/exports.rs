@@ -0,0 +1,252 @@
+//! 导出任务队列
+//!
+//! - `POST /api/v1/exports`：把一个或多个会话的导出加入队列，返回任务ID
+//! - `GET /api/v1/exports/{id}`：查询导出状态，完成后 `output.artifacts` 列出导出文件
+//! - `GET /api/v1/exports/{id}/download?wxid=<wxid>`：下载导出文件，只有一个会话时可省略 `wxid`
+//!
+//! 导出按提交顺序逐个执行，结果写入工作目录下的 `export/{批次ID}`，各批次互不覆盖。
+
+use axum::extract::{Path, Query, Request, State};
+use axum::http::StatusCode;
+use axum::response::Response;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use ts_rs::TS;
+
+use super::files::serve_file;
+use super::jobs::JobCreated;
+use super::{ApiError, ServerState};
+use mwxdump_core::errors::MwxDumpError;
+use mwxdump_core::export::{ExportFormat, ExportOptions, ExportService};
+use mwxdump_core::jobs::{JobId, JobInfo, JobStatus};
+use mwxdump_core::plugins::PluginChain;
+use mwxdump_core::wechat::media::AudioFormat;
+
+/// 导出任务的类型
+pub const EXPORT_JOB_KIND: &str = "exports";
+
+/// 导出请求
+#[derive(Debug, Deserialize, TS)]
+#[ts(export, optional_fields)]
+pub struct ExportJobRequest {
+    /// 要导出的会话（wxid 或群ID）
+    pub wxids: Vec<String>,
+    /// 导出格式
+    #[serde(default = "default_format")]
+    #[ts(optional, as = "Option<_>")]
+    pub format: ExportFormat,
+    /// 同时复制媒体文件
+    #[serde(default)]
+    #[ts(optional, as = "Option<_>")]
+    pub media: bool,
+    /// 以联系人备注或昵称命名导出文件
+    #[serde(default)]
+    #[ts(optional, as = "Option<_>")]
+    pub name_by_contact: bool,
+    /// 导出语音消息的音频格式，省略时不导出语音
+    pub voice_format: Option<AudioFormat>,
+    /// 当前账号的 wxid
+    pub self_wxid: Option<String>,
+}
+
+fn default_format() -> ExportFormat {
+    ExportFormat::Json
+}
+
+/// 下载参数
+#[derive(Debug, Default, Deserialize, TS)]
+#[ts(export, optional_fields)]
+pub struct DownloadParams {
+    /// 要下载的会话，任务只导出了一个会话时可省略
+    pub wxid: Option<String>,
+}
+
+/// 导出任务结果
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ExportJobOutput {
+    /// 导出目录，相对工作目录
+    pub dir: PathBuf,
+    /// 导出文件
+    pub artifacts: Vec<ExportArtifact>,
+}
+
+/// 一个会话的导出文件
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ExportArtifact {
+    /// 会话
+    pub wxid: String,
+    /// 导出文件，相对工作目录
+    pub file: PathBuf,
+    /// 写入的消息数
+    #[ts(type = "number")]
+    pub messages: u64,
+    /// 复制的媒体文件数
+    #[ts(type = "number")]
+    pub media: u64,
+}
+
+/// 加入导出队列
+pub async fn create(
+    State(state): State<ServerState>,
+    Json(request): Json<ExportJobRequest>,
+) -> Result<(StatusCode, Json<JobCreated>), ApiError> {
+    if state.ephemeral {
+        return Err(ApiError::bad_request("临时会话模式下不支持导出到磁盘"));
+    }
+    if request.wxids.is_empty() {
+        return Err(ApiError::bad_request("wxids 不能为空"));
+    }
+    if let Some(wxid) = request.wxids.iter().find(|wxid| !is_plain_name(wxid)) {
+        return Err(ApiError::bad_request(format!("非法的会话ID: {}", wxid)));
+    }
+
+    let work_dir = state.config.database.work_dir.clone();
+    let dir = PathBuf::from("export").join(JobId::new_v4().to_string());
+    let options = ExportOptions {
+        output_dir: work_dir.join(&dir),
+        include_media: request.media,
+        name_by_contact: request.name_by_contact,
+        voice_format: request.voice_format,
+        ..Default::default()
+    };
+    let plugins = PluginChain::from_configs(&state.config.plugins).map_err(|e| ApiError::bad_request(e.to_string()))?;
+    let mut service = ExportService::new(&work_dir)
+        .with_plugins(plugins)
+        .with_voice_config(state.config.voice.clone())
+        .with_events(state.events.clone());
+    if let Some(data_dir) = &state.config.wechat.data_dir {
+        service = service.with_data_root(data_dir);
+    }
+    if let Some(wxid) = request.self_wxid {
+        service = service.with_self_wxid(wxid);
+    }
+    tracing::info!("导出请求入队: {} 个会话 ({:?})", request.wxids.len(), request.format);
+
+    let queue = state.export_queue.clone();
+    let id = state.jobs.spawn_with_output(EXPORT_JOB_KIND, move |token| async move {
+        // 等待前面的导出完成，排队期间可取消
+        let _permit = tokio::select! {
+            permit = queue.acquire_owned() => permit?,
+            _ = token.cancelled() => return Err(MwxDumpError::Cancelled.into()),
+        };
+        let service = service.with_cancellation(token);
+        let mut artifacts = Vec::with_capacity(request.wxids.len());
+        for wxid in request.wxids {
+            let summary = service.export_conversation(&wxid, request.format, &options).await?;
+            let file = summary.file.strip_prefix(&work_dir).unwrap_or(&summary.file).to_path_buf();
+            artifacts.push(ExportArtifact {
+                wxid,
+                file,
+                messages: summary.messages,
+                media: summary.media,
+            });
+        }
+        Ok(ExportJobOutput { dir, artifacts })
+    });
+    Ok((StatusCode::ACCEPTED, Json(JobCreated { id })))
+}
+
+/// 查询导出状态
+pub async fn get(State(state): State<ServerState>, Path(id): Path<JobId>) -> Result<Json<JobInfo>, ApiError> {
+    export_job(&state, &id).map(Json)
+}
+
+/// 下载导出文件
+pub async fn download(
+    State(state): State<ServerState>,
+    Path(id): Path<JobId>,
+    Query(params): Query<DownloadParams>,
+    request: Request,
+) -> Result<Response, ApiError> {
+    let job = export_job(&state, &id)?;
+    if job.status != JobStatus::Succeeded {
+        return Err(ApiError::conflict(format!("导出任务尚未成功完成: {:?}", job.status)));
+    }
+    let output: ExportJobOutput = job
+        .output
+        .and_then(|output| serde_json::from_value(output).ok())
+        .ok_or_else(|| ApiError::not_found(format!("export {}", id)))?;
+    let artifact = match params.wxid {
+        Some(wxid) => output.artifacts.into_iter().find(|a| a.wxid == wxid),
+        None if output.artifacts.len() == 1 => output.artifacts.into_iter().next(),
+        None => return Err(ApiError::bad_request("任务包含多个会话，请用 wxid 参数指定")),
+    }
+    .ok_or_else(|| ApiError::not_found(format!("export {} artifact", id)))?;
+
+    let file = state.config.database.work_dir.join(&artifact.file);
+    tracing::info!("下载导出文件: {:?}", file);
+    Ok(serve_file(file, request).await)
+}
+
+/// 查找导出任务，其他类型的任务视为不存在
+fn export_job(state: &ServerState, id: &JobId) -> Result<JobInfo, ApiError> {
+    state
+        .jobs
+        .get(id)
+        .filter(|job| job.kind == EXPORT_JOB_KIND)
+        .ok_or_else(|| ApiError::not_found(format!("export {}", id)))
+}
+
+/// 会话ID会用作文件名，不能包含路径分隔符或指向上级目录
+fn is_plain_name(wxid: &str) -> bool {
+    !wxid.is_empty() && wxid != "." && wxid != ".." && !wxid.contains(['/', '\\', ':', '\0'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::server::router;
+    use axum::body::Body;
+    use mwxdump_core::wechat::db::messages::MESSAGE_DB_DIR;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_export_queue_and_download() {
+        let work = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(work.path().join(MESSAGE_DB_DIR)).unwrap();
+        let mut config = AppConfig::default();
+        config.database.work_dir = work.path().to_path_buf();
+        let state = ServerState::new(config);
+        let post = |body: &str| {
+            axum::http::Request::post("/api/v1/exports")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let get = |uri: String| axum::http::Request::get(uri).body(Body::empty()).unwrap();
+
+        for body in [r#"{"wxids": []}"#, r#"{"wxids": ["../config"]}"#] {
+            let response = router(state.clone()).oneshot(post(body)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+
+        let response = router(state.clone()).oneshot(post(r#"{"wxids": ["wxid_a", "wxid_b"]}"#)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let created: JobCreated = serde_json::from_slice(&body).unwrap();
+        let job = state.jobs.wait(&created.id).await.unwrap();
+        assert_eq!(job.status, JobStatus::Succeeded, "{:?}", job.error);
+
+        let response = router(state.clone()).oneshot(get(format!("/api/v1/exports/{}", created.id))).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let info: JobInfo = serde_json::from_slice(&body).unwrap();
+        let output: ExportJobOutput = serde_json::from_value(info.output.unwrap()).unwrap();
+        assert_eq!(output.artifacts.len(), 2);
+        assert!(work.path().join(&output.artifacts[1].file).is_file());
+
+        let uri = format!("/api/v1/exports/{}/download", created.id);
+        let response = router(state.clone()).oneshot(get(uri.clone())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = router(state.clone()).oneshot(get(format!("{}?wxid=wxid_b", uri))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // 其他类型的任务不能通过导出接口查询
+        let backup = state.jobs.spawn("backup", |_| async { Ok(()) });
+        let response = router(state).oneshot(get(format!("/api/v1/exports/{}", backup))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,132 @@
+//! 文件下载接口
+//!
+//! `GET /api/v1/files/{path}`：下载工作目录或账号输出目录下的解密数据库和导出文件，支持 Range 请求断点续传。
+//! 路径相对于允许的根目录，依次在当前会话的临时目录、工作目录和各账号输出目录中查找，不允许访问根目录之外的文件。
+//! 临时会话模式下只提供会话内的解密数据。
+
+use axum::body::Body;
+use axum::extract::{Path, Request, State};
+use axum::response::Response;
+use std::path::{Component, PathBuf};
+use tower::ServiceExt;
+use tower_http::services::ServeFile;
+
+use super::{ApiError, ServerState};
+use crate::config::AppConfig;
+
+/// 下载文件
+pub async fn download(
+    State(state): State<ServerState>,
+    Path(path): Path<String>,
+    request: Request,
+) -> Result<Response, ApiError> {
+    let file = resolve_file(&allowed_roots(&state).await, &path)?;
+    tracing::info!("下载文件: {:?}", file);
+    Ok(serve_file(file, request).await)
+}
+
+/// 发送文件，支持 Range 请求
+pub(super) async fn serve_file(file: PathBuf, request: Request) -> Response {
+    let response = ServeFile::new(file)
+        .oneshot(request)
+        .await
+        .unwrap_or_else(|never| match never {});
+    response.map(Body::new)
+}
+
+/// 允许访问的根目录：当前会话目录，非临时会话模式下还包括持久化输出目录
+pub(super) async fn allowed_roots(state: &ServerState) -> Vec<PathBuf> {
+    let session_dir = state.session.read().await.as_ref().map(|s| s.path().to_path_buf());
+    let mut roots: Vec<PathBuf> = session_dir.into_iter().collect();
+    if !state.ephemeral {
+        roots.extend(persistent_roots(&state.config));
+    }
+    roots
+}
+
+/// 持久化输出的根目录
+fn persistent_roots(config: &AppConfig) -> Vec<PathBuf> {
+    std::iter::once(config.database.work_dir.clone())
+        .chain(config.accounts.iter().map(|p| p.output_dir.clone()))
+        .collect()
+}
+
+/// 在允许的根目录中查找文件
+///
+/// 拒绝绝对路径和 `..`，并在解析符号链接后再次确认文件仍位于根目录内。
+pub(super) fn resolve_file(roots: &[PathBuf], path: &str) -> Result<PathBuf, ApiError> {
+    let relative = PathBuf::from(path);
+    if relative
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(ApiError::bad_request(format!("非法路径: {}", path)));
+    }
+
+    for root in roots {
+        let Ok(root) = root.canonicalize() else {
+            continue;
+        };
+        let Ok(candidate) = root.join(&relative).canonicalize() else {
+            continue;
+        };
+        if candidate.starts_with(&root) && candidate.is_file() {
+            return Ok(candidate);
+        }
+    }
+    Err(ApiError::not_found(format!("file {}", path)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::AppConfig;
+    use crate::server::{router, ServerState};
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_download_with_range_and_auth() {
+        let work = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(work.path().join("db_storage")).unwrap();
+        std::fs::write(work.path().join("db_storage").join("session.db"), b"0123456789").unwrap();
+
+        let mut config = AppConfig::default();
+        config.database.work_dir = work.path().to_path_buf();
+        config.http.auth_token = Some("s3cret".to_string());
+        let state = ServerState::new(config);
+
+        let get = |uri: &str, range: Option<&str>, token: Option<&str>| {
+            let mut builder = Request::get(uri);
+            if let Some(range) = range {
+                builder = builder.header(header::RANGE, range);
+            }
+            if let Some(token) = token {
+                builder = builder.header(header::AUTHORIZATION, format!("Bearer {}", token));
+            }
+            builder.body(Body::empty()).unwrap()
+        };
+
+        let uri = "/api/v1/files/db_storage/session.db";
+        let response = router(state.clone()).oneshot(get(uri, None, None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = router(state.clone()).oneshot(get(uri, None, Some("s3cret"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"0123456789");
+
+        let response = router(state.clone()).oneshot(get(uri, Some("bytes=2-5"), Some("s3cret"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"2345");
+
+        let uri = "/api/v1/files/..%2Fconfig.toml";
+        let response = router(state.clone()).oneshot(get(uri, None, Some("s3cret"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let uri = "/api/v1/files/db_storage/missing.db";
+        let response = router(state).oneshot(get(uri, None, Some("s3cret"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
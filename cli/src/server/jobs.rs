@@ -0,0 +1,358 @@
+//! 任务接口
+//!
+//! - `POST /api/v1/jobs/backup`：按账号配置或请求参数启动一次解密备份，返回任务ID
+//! - `POST /api/v1/jobs/export`：导出单个会话到工作目录的 `export` 下，返回任务ID
+//! - `GET /api/v1/jobs/{id}`：查询任务状态，成功的任务在 `output` 中附带备份记录或导出统计
+//!
+//! 配置 `wechat.auto_decrypt = true` 时，服务启动后以 `auto_decrypt` 任务解密一次到工作目录。
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use ts_rs::TS;
+
+use super::{ApiError, ServerState};
+use crate::config::AppConfig;
+use mwxdump_core::backup::BackupTask;
+use mwxdump_core::errors::{ConfigError, Result as CoreResult};
+use mwxdump_core::events::EventBus;
+use mwxdump_core::export::{ExportFormat, ExportOptions, ExportService};
+use mwxdump_core::jobs::{JobId, JobInfo};
+use mwxdump_core::plugins::PluginChain;
+use mwxdump_core::wechat::datadir::DbCategory;
+use mwxdump_core::wechat::key::key_extractor::{create_key_extractor_with_offsets, KeyExtractor};
+use mwxdump_core::wechat::process::detect_primary;
+
+/// 备份请求，未提供的字段依次取账号配置和全局配置
+#[derive(Debug, Default, Deserialize, TS)]
+#[ts(export, optional_fields)]
+pub struct BackupRequest {
+    /// 账号配置名称
+    pub account: Option<String>,
+    /// 微信数据目录
+    pub input: Option<PathBuf>,
+    /// 输出子目录，相对账号配置的输出目录（未指定账号时为工作目录），不能为绝对路径或包含 `..`
+    pub output: Option<PathBuf>,
+    /// 数据密钥（十六进制）
+    pub key: Option<String>,
+    /// 只备份这些类别的数据库
+    #[serde(default)]
+    #[ts(optional, as = "Option<_>")]
+    pub only: Vec<DbCategory>,
+    /// 并发线程数
+    pub threads: Option<usize>,
+    /// 写入输出目录下以时间命名的快照子目录，完成后按保留策略清理
+    #[serde(default)]
+    #[ts(optional, as = "Option<_>")]
+    pub snapshot: bool,
+}
+
+/// 会话导出请求
+#[derive(Debug, Deserialize, TS)]
+#[ts(export, optional_fields)]
+pub struct ExportRequest {
+    /// 会话的 wxid 或群ID
+    pub wxid: String,
+    /// 导出格式
+    #[serde(default = "default_export_format")]
+    #[ts(optional, as = "Option<_>")]
+    pub format: ExportFormat,
+    /// 同时复制媒体文件
+    #[serde(default)]
+    #[ts(optional, as = "Option<_>")]
+    pub media: bool,
+    /// 当前账号的 wxid
+    pub self_wxid: Option<String>,
+}
+
+fn default_export_format() -> ExportFormat {
+    ExportFormat::Json
+}
+
+/// 任务创建响应
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct JobCreated {
+    /// 任务ID
+    pub id: JobId,
+}
+
+/// 解析完成的备份参数
+#[derive(Debug)]
+pub(super) struct BackupPlan {
+    pub(super) input: PathBuf,
+    pub(super) output: PathBuf,
+    pub(super) key: Vec<u8>,
+    pub(super) only: Vec<DbCategory>,
+    pub(super) threads: Option<usize>,
+    pub(super) snapshot: bool,
+}
+
+/// 启动备份任务
+pub async fn create_backup(
+    State(state): State<ServerState>,
+    Json(request): Json<BackupRequest>,
+) -> Result<(StatusCode, Json<JobCreated>), ApiError> {
+    if state.ephemeral {
+        return Err(ApiError::bad_request("临时会话模式下不支持持久化备份，请使用 /api/v1/session/unlock"));
+    }
+    let plan = resolve_backup(&state.config, request)?;
+    tracing::info!("收到备份请求: {:?} -> {:?}", plan.input, plan.output);
+
+    let task = BackupTask {
+        input: plan.input,
+        output: plan.output,
+        key: plan.key,
+        categories: plan.only,
+        threads: plan.threads,
+        snapshot: plan.snapshot,
+        retention: state.config.backup.retention.clone(),
+    };
+    let events = state.events.clone();
+    let id = state.jobs.spawn_with_output("backup", move |token| async move { task.run(token, events).await });
+    Ok((StatusCode::ACCEPTED, Json(JobCreated { id })))
+}
+
+/// 启动会话导出任务
+pub async fn create_export(
+    State(state): State<ServerState>,
+    Json(request): Json<ExportRequest>,
+) -> Result<(StatusCode, Json<JobCreated>), ApiError> {
+    if state.ephemeral {
+        return Err(ApiError::bad_request("临时会话模式下不支持导出到磁盘"));
+    }
+    let work_dir = state.config.database.work_dir.clone();
+    let options = ExportOptions {
+        output_dir: work_dir.join("export"),
+        include_media: request.media,
+        ..Default::default()
+    };
+    let mut service = ExportService::new(work_dir)
+        .with_plugins(PluginChain::from_configs(&state.config.plugins).map_err(|e| ApiError::bad_request(e.to_string()))?)
+        .with_events(state.events.clone());
+    if let Some(data_dir) = &state.config.wechat.data_dir {
+        service = service.with_data_root(data_dir);
+    }
+    if let Some(wxid) = request.self_wxid {
+        service = service.with_self_wxid(wxid);
+    }
+    tracing::info!("收到导出请求: {} ({:?})", request.wxid, request.format);
+
+    let id = state.jobs.spawn_with_output("export", move |token| async move {
+        service
+            .with_cancellation(token)
+            .export_conversation(&request.wxid, request.format, &options)
+            .await
+    });
+    Ok((StatusCode::ACCEPTED, Json(JobCreated { id })))
+}
+
+/// 启动自动解密任务，解密到工作目录
+pub fn spawn_auto_decrypt(state: &ServerState) -> JobId {
+    let config = state.config.clone();
+    let events = state.events.clone();
+    state.jobs.spawn_with_output("auto_decrypt", move |token| async move {
+        let task = auto_decrypt_task(&config, &events).await?;
+        tracing::info!("自动解密: {:?} -> {:?}", task.input, task.output);
+        task.run(token, events).await
+    })
+}
+
+/// 自动解密的参数：配置了数据目录和密钥时直接使用，否则检测运行中的微信并提取密钥
+async fn auto_decrypt_task(config: &AppConfig, events: &EventBus) -> CoreResult<BackupTask> {
+    let wechat = &config.wechat;
+    let (input, key) = match (&wechat.data_dir, &wechat.data_key) {
+        (Some(data_dir), Some(key_hex)) => {
+            let key = hex::decode(key_hex)
+                .ok()
+                .filter(|k| k.len() == 32)
+                .ok_or_else(|| ConfigError::InvalidValue {
+                    key: "wechat.data_key".to_string(),
+                    value: key_hex.clone(),
+                })?;
+            (data_dir.clone(), key)
+        }
+        _ => {
+            let (process, info) = detect_primary(wechat.data_dir_validation, &wechat.supported_versions).await?;
+            let extractor = create_key_extractor_with_offsets(wechat.key_offsets.clone())?.with_events(events.clone());
+            let key = extractor.extract_key(&process).await?;
+            (wechat.data_dir.clone().unwrap_or(info.root), key.key_data)
+        }
+    };
+    Ok(BackupTask {
+        input,
+        output: config.database.work_dir.clone(),
+        key,
+        categories: Vec::new(),
+        threads: None,
+        snapshot: false,
+        retention: config.backup.retention.clone(),
+    })
+}
+
+/// 查询任务状态
+pub async fn get_job(
+    State(state): State<ServerState>,
+    Path(id): Path<JobId>,
+) -> Result<Json<JobInfo>, ApiError> {
+    state
+        .jobs
+        .get(&id)
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found(format!("job {}", id)))
+}
+
+/// 合并请求参数、账号配置和全局配置
+pub(super) fn resolve_backup(config: &AppConfig, request: BackupRequest) -> Result<BackupPlan, ApiError> {
+    let profile = match &request.account {
+        Some(name) => Some(
+            config
+                .accounts
+                .iter()
+                .find(|p| &p.name == name)
+                .ok_or_else(|| ApiError::not_found(format!("account {}", name)))?,
+        ),
+        None => None,
+    };
+
+    let input = request
+        .input
+        .or_else(|| profile.map(|p| p.data_dir.clone()))
+        .or_else(|| config.wechat.data_dir.clone())
+        .ok_or_else(|| ApiError::bad_request("缺少微信数据目录 (input)"))?;
+    if !input.exists() {
+        return Err(ApiError::bad_request(format!("输入路径不存在: {}", input.display())));
+    }
+
+    // 只能写入配置的输出目录之下，防止请求把数据库解密到任意位置
+    let base = profile.map_or_else(|| config.database.work_dir.clone(), |p| p.output_dir.clone());
+    let output = match request.output {
+        Some(relative) => base.join(confined_path(&relative)?),
+        None => base,
+    };
+
+    let key_hex = request
+        .key
+        .or_else(|| profile.and_then(|p| p.data_key.clone()))
+        .or_else(|| config.wechat.data_key.clone())
+        .ok_or_else(|| ApiError::bad_request("缺少数据密钥 (key)"))?;
+    let key = hex::decode(&key_hex)
+        .ok()
+        .filter(|k| k.len() == 32)
+        .ok_or_else(|| ApiError::bad_request("密钥必须为64个十六进制字符"))?;
+
+    Ok(BackupPlan {
+        input,
+        output,
+        key,
+        only: request.only,
+        threads: request.threads,
+        snapshot: request.snapshot,
+    })
+}
+
+/// 检查请求中的相对路径，拒绝绝对路径和 `..`
+fn confined_path(path: &std::path::Path) -> Result<&std::path::Path, ApiError> {
+    use std::path::Component;
+    let escapes = path
+        .components()
+        .any(|c| matches!(c, Component::Prefix(_) | Component::RootDir | Component::ParentDir));
+    if escapes || path.as_os_str().is_empty() {
+        return Err(ApiError::bad_request(format!(
+            "输出路径必须是输出目录下的相对路径: {}",
+            path.display()
+        )));
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AccountProfile;
+    use crate::server::router;
+    use axum::body::Body;
+    use axum::http::Request;
+    use mwxdump_core::jobs::JobStatus;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_backup_job_endpoints() {
+        let input = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        let mut config = AppConfig::default();
+        config.accounts.push(AccountProfile {
+            name: "home".to_string(),
+            wxid: None,
+            data_dir: input.path().to_path_buf(),
+            data_key: Some("00".repeat(32)),
+            output_dir: output.path().to_path_buf(),
+        });
+        let state = ServerState::new(config);
+
+        let post = |body: &str| {
+            Request::post("/api/v1/jobs/backup")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let response = router(state.clone()).oneshot(post("{}")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = router(state.clone()).oneshot(post(r#"{"account":"office"}"#)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // 输出只能位于账号输出目录之下
+        for output in ["/tmp/elsewhere", "../elsewhere", "nested/../../elsewhere"] {
+            let body = serde_json::json!({"account": "home", "output": output}).to_string();
+            let response = router(state.clone()).oneshot(post(&body)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", output);
+        }
+        let plan = resolve_backup(
+            &state.config,
+            BackupRequest {
+                account: Some("home".to_string()),
+                output: Some(PathBuf::from("nightly")),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(plan.output, output.path().join("nightly"));
+
+        let response = router(state.clone()).oneshot(post(r#"{"account":"home"}"#)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let created: JobCreated = serde_json::from_slice(&body).unwrap();
+        let job = state.jobs.wait(&created.id).await.unwrap();
+        assert_eq!(job.status, JobStatus::Succeeded);
+        let output = job.output.unwrap();
+        assert_eq!(output["record"]["databases"], 0);
+        assert_eq!(output["decrypt"]["files"], 0);
+        assert!(output["decrypt"]["max_memory_mb"].as_u64().unwrap() > 0);
+
+        let uri = format!("/api/v1/jobs/{}", created.id);
+        let response = router(state.clone())
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let export = Request::post("/api/v1/jobs/export")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"wxid":"wxid_friend","format":"txt"}"#))
+            .unwrap();
+        let response = router(state.clone()).oneshot(export).await.unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let created: JobCreated = serde_json::from_slice(&body).unwrap();
+        // 工作目录中没有消息数据库，任务失败
+        assert_eq!(state.jobs.wait(&created.id).await.unwrap().status, JobStatus::Failed);
+
+        let uri = "/api/v1/jobs/00000000-0000-0000-0000-000000000000";
+        let response = router(state).oneshot(Request::get(uri).body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
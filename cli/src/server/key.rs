@@ -0,0 +1,123 @@
+//! 密钥提取接口
+//!
+//! `POST /api/v1/key/extract`：在服务端从当前登录的微信主进程提取数据密钥。
+//! 需要同时配置访问令牌和 `allow_key_extraction`，令牌还需拥有 `run:decrypt` 权限；默认只返回密钥指纹，
+//! `?reveal=true` 返回原始密钥，还需开启 `allow_key_reveal`。
+
+use axum::extract::{Query, State};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use super::{ApiError, ServerState};
+use mwxdump_core::backup::catalog::key_fingerprint;
+use mwxdump_core::errors::{error_code, exit_code};
+use mwxdump_core::wechat::key::key_extractor::{create_key_extractor_with_offsets, KeyExtractor};
+use mwxdump_core::wechat::process::detect_primary;
+
+/// 提取参数
+#[derive(Debug, Default, Deserialize, TS)]
+#[ts(export, optional_fields)]
+pub struct ExtractQuery {
+    /// 返回原始密钥
+    #[serde(default)]
+    #[ts(optional, as = "Option<_>")]
+    pub reveal: bool,
+}
+
+/// 提取结果
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct KeyExtracted {
+    /// 密钥指纹（SHA-256 前8字节），与备份记录中的指纹一致
+    pub fingerprint: String,
+    /// 密钥来源进程PID
+    pub pid: u32,
+    /// 微信版本
+    pub version: String,
+    /// 账号 wxid
+    pub wxid: Option<String>,
+    /// 原始密钥（十六进制），仅 `reveal=true` 时返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub key: Option<String>,
+}
+
+/// 提取密钥
+pub async fn extract(
+    State(state): State<ServerState>,
+    Query(query): Query<ExtractQuery>,
+) -> Result<Json<KeyExtracted>, ApiError> {
+    let http = &state.config.http;
+    if !http.allow_key_extraction {
+        return Err(ApiError::forbidden("未开启密钥提取接口，请在 [http] 中设置 allow_key_extraction = true"));
+    }
+    if !http.auth_enabled() {
+        return Err(ApiError::forbidden("密钥提取接口需要先在 [http] 中设置 auth_token 或 tokens"));
+    }
+    if query.reveal && !http.allow_key_reveal {
+        return Err(ApiError::forbidden("未开启原始密钥返回，请在 [http] 中设置 allow_key_reveal = true"));
+    }
+
+    let wechat = &state.config.wechat;
+    let (process, info) = detect_primary(wechat.data_dir_validation, &wechat.supported_versions).await.map_err(extraction_error)?;
+    let extractor = create_key_extractor_with_offsets(wechat.key_offsets.clone())
+        .map_err(extraction_error)?
+        .with_events(state.events.clone());
+    let key = extractor.extract_key(&process).await.map_err(extraction_error)?;
+
+    let fingerprint = key_fingerprint(&key.key_data);
+    if query.reveal {
+        tracing::warn!("通过接口返回原始密钥: PID {}, 指纹 {}", process.pid, fingerprint);
+    } else {
+        tracing::info!("通过接口提取密钥: PID {}, 指纹 {}", process.pid, fingerprint);
+    }
+    Ok(Json(KeyExtracted {
+        fingerprint,
+        pid: process.pid,
+        version: process.version.to_string(),
+        wxid: info.wxid,
+        key: query.reveal.then(|| key.to_hex()),
+    }))
+}
+
+/// 未找到微信进程时返回 404，其他失败返回 500
+fn extraction_error(error: anyhow::Error) -> ApiError {
+    if error_code(&error) == exit_code::PROCESS_NOT_FOUND {
+        ApiError::not_found("WeChat process")
+    } else {
+        ApiError::internal(error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::AppConfig;
+    use crate::server::{router, ServerState};
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+    use tower::ServiceExt;
+
+    async fn post(config: AppConfig, uri: &str) -> StatusCode {
+        let request = Request::post(uri)
+            .header(header::AUTHORIZATION, "Bearer s3cret")
+            .body(Body::empty())
+            .unwrap();
+        router(ServerState::new(config)).oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_extract_requires_opt_in() {
+        let uri = "/api/v1/key/extract";
+        let mut config = AppConfig::default();
+        assert_eq!(post(config.clone(), uri).await, StatusCode::FORBIDDEN);
+
+        // 开启后仍要求配置访问令牌
+        config.http.allow_key_extraction = true;
+        assert_eq!(post(config.clone(), uri).await, StatusCode::FORBIDDEN);
+
+        config.http.auth_token = Some("s3cret".to_string());
+        assert_eq!(post(config.clone(), &format!("{}?reveal=true", uri)).await, StatusCode::FORBIDDEN);
+        assert_ne!(post(config, uri).await, StatusCode::FORBIDDEN);
+    }
+}
@@ -0,0 +1,295 @@
+//! MCP 的 SSE 传输
+//!
+//! - `GET /sse`：建立事件流，第一条 `endpoint` 事件给出发送消息的地址 `/message?sessionId=<id>`
+//! - `POST /message?sessionId=<id>`：发送 JSON-RPC 消息，返回 202，响应以 `message` 事件经事件流推送
+//!
+//! 与 HTTP 接口使用同一套访问令牌，令牌的权限范围决定会话可调用的工具和可读取的资源。
+//! 事件流断开后会话随之删除。事件总线上的事件按 [`notification`] 转换后推送给所有会话。
+
+use axum::extract::{Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::Router;
+use chrono::FixedOffset;
+use futures::stream::{self, Stream, StreamExt};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use ts_rs::TS;
+
+use super::{check_bind, granted_scopes, ApiError};
+use crate::config::AppConfig;
+use mwxdump_core::errors::{HttpError, Result};
+use mwxdump_core::events::EventBus;
+use mwxdump_core::mcp::{notification, McpServer, ResourceProvider};
+use mwxdump_core::scopes::Scopes;
+
+/// 每个会话待推送的响应数，超出时发送消息的请求等待
+const SESSION_BUFFER: usize = 32;
+
+/// 一个 SSE 会话
+struct McpSession {
+    server: Arc<McpServer>,
+    sender: mpsc::Sender<String>,
+}
+
+/// SSE 传输的共享状态
+#[derive(Clone)]
+pub struct McpState {
+    config: Arc<AppConfig>,
+    work_dir: PathBuf,
+    offset: FixedOffset,
+    events: EventBus,
+    sessions: Arc<Mutex<HashMap<String, McpSession>>>,
+}
+
+impl McpState {
+    /// 为解密输出目录 `work_dir` 提供服务，月份和日期按 `offset` 时区划分
+    pub fn new(config: AppConfig, work_dir: PathBuf, offset: FixedOffset) -> Self {
+        Self {
+            config: Arc::new(config),
+            work_dir,
+            offset,
+            events: EventBus::default(),
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// 订阅共享的事件总线，默认使用独立的总线
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// 当前连接的会话数
+    pub fn session_count(&self) -> usize {
+        self.sessions.lock().expect("MCP 会话表锁").len()
+    }
+
+    /// 校验访问令牌，返回授予的权限
+    fn authorize(&self, headers: &HeaderMap) -> std::result::Result<Scopes, ApiError> {
+        let provided = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        granted_scopes(&self.config.http, provided).ok_or_else(ApiError::unauthorized)
+    }
+}
+
+/// 事件流结束时删除会话
+struct SessionGuard {
+    id: String,
+    sessions: Arc<Mutex<HashMap<String, McpSession>>>,
+}
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        self.sessions.lock().expect("MCP 会话表锁").remove(&self.id);
+        tracing::debug!("MCP 会话已断开: {}", self.id);
+    }
+}
+
+/// 发送消息的参数
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct MessageParams {
+    /// `endpoint` 事件给出的会话ID
+    #[serde(rename = "sessionId")]
+    pub session_id: String,
+}
+
+/// 构建路由
+pub fn router(state: McpState) -> Router {
+    Router::new()
+        .route("/sse", get(connect))
+        .route("/message", post(message))
+        .with_state(state)
+}
+
+/// 建立事件流并创建会话
+async fn connect(State(state): State<McpState>, headers: HeaderMap) -> Response {
+    let scopes = match state.authorize(&headers) {
+        Ok(scopes) => scopes,
+        Err(e) => return e.into_response(),
+    };
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    let (sender, receiver) = mpsc::channel(SESSION_BUFFER);
+    let server = McpServer::new(
+        ResourceProvider::new(&state.work_dir)
+            .with_scopes(scopes)
+            .with_utc_offset(state.offset),
+    );
+    let mut notifications = state.events.subscribe();
+    let forward = sender.clone();
+    tokio::spawn(async move {
+        // 事件流断开后转发随之结束
+        loop {
+            let event = tokio::select! {
+                _ = forward.closed() => break,
+                event = notifications.recv() => event,
+            };
+            let Some(event) = event else { break };
+            if let Some(message) = notification(&event) {
+                if forward.send(message.to_string()).await.is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    state.sessions.lock().expect("MCP 会话表锁").insert(
+        id.clone(),
+        McpSession {
+            server: Arc::new(server),
+            sender,
+        },
+    );
+    tracing::debug!("MCP 会话已连接: {}，当前 {} 个会话", id, state.session_count());
+
+    let endpoint = Event::default().event("endpoint").data(format!("/message?sessionId={}", id));
+    let guard = SessionGuard {
+        id,
+        sessions: state.sessions.clone(),
+    };
+    Sse::new(events(endpoint, receiver, guard))
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+/// 先推送 `endpoint` 事件，再逐条推送响应；事件流被丢弃时 `guard` 删除会话
+fn events(
+    endpoint: Event,
+    receiver: mpsc::Receiver<String>,
+    guard: SessionGuard,
+) -> impl Stream<Item = std::result::Result<Event, Infallible>> {
+    let responses = stream::unfold((receiver, guard), |(mut receiver, guard)| async move {
+        let response = receiver.recv().await?;
+        Some((Ok(Event::default().event("message").data(response)), (receiver, guard)))
+    });
+    stream::once(async move { Ok(endpoint) }).chain(responses)
+}
+
+/// 接收客户端消息，处理结果经事件流返回
+async fn message(
+    State(state): State<McpState>,
+    Query(params): Query<MessageParams>,
+    headers: HeaderMap,
+    body: String,
+) -> std::result::Result<StatusCode, ApiError> {
+    state.authorize(&headers)?;
+    let session = state
+        .sessions
+        .lock()
+        .expect("MCP 会话表锁")
+        .get(&params.session_id)
+        .map(|session| (session.server.clone(), session.sender.clone()));
+    let Some((server, sender)) = session else {
+        return Err(ApiError::not_found(format!("MCP 会话 {}", params.session_id)));
+    };
+    tokio::spawn(async move {
+        if let Some(response) = server.handle_text(&body).await {
+            // 事件流已断开时丢弃响应
+            let _ = sender.send(response).await;
+        }
+    });
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// 启动 SSE 服务，取消令牌触发后停止
+pub async fn serve(state: McpState, host: &str, port: u16, shutdown: CancellationToken) -> Result<()> {
+    check_bind(&state.config.http, host)?;
+    let listener = tokio::net::TcpListener::bind((host, port)).await.map_err(|e| {
+        if e.kind() == std::io::ErrorKind::AddrInUse {
+            HttpError::PortInUse { port }
+        } else {
+            HttpError::ServerStartFailed(e.to_string())
+        }
+    })?;
+    tracing::info!("MCP SSE 服务监听于 {}:{}", host, port);
+    axum::serve(listener, router(state))
+        .with_graceful_shutdown(async move { shutdown.cancelled().await })
+        .await
+        .map_err(|e| HttpError::ServerStartFailed(e.to_string()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_sse_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = AppConfig::default();
+        config.http.auth_token = Some("s3cret".to_string());
+        let events = EventBus::new();
+        let state =
+            McpState::new(config, dir.path().to_path_buf(), FixedOffset::east_opt(0).unwrap()).with_events(events.clone());
+        let app = router(state.clone());
+
+        let response = app
+            .clone()
+            .oneshot(Request::get("/sse").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::get("/sse")
+                    .header(header::AUTHORIZATION, "Bearer s3cret")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let mut body = response.into_body().into_data_stream();
+        let first = String::from_utf8(body.next().await.unwrap().unwrap().to_vec()).unwrap();
+        assert!(first.starts_with("event: endpoint"));
+        let endpoint = first.lines().find_map(|line| line.strip_prefix("data: ")).unwrap().to_string();
+        assert_eq!(state.session_count(), 1);
+
+        let post = |uri: &str, body: &str| {
+            Request::post(uri)
+                .header(header::AUTHORIZATION, "Bearer s3cret")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let response = app
+            .clone()
+            .oneshot(post(&endpoint, r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let event = String::from_utf8(body.next().await.unwrap().unwrap().to_vec()).unwrap();
+        assert!(event.starts_with("event: message"));
+        assert!(event.contains(r#""id":1"#));
+
+        events.publish(mwxdump_core::events::Event::DataUpdated {
+            files: Vec::new(),
+            at: chrono::Utc::now(),
+        });
+        let event = String::from_utf8(body.next().await.unwrap().unwrap().to_vec()).unwrap();
+        assert!(event.contains("notifications/resources/list_changed"));
+
+        let response = app
+            .clone()
+            .oneshot(post("/message?sessionId=nope", "{}"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        drop(body);
+        assert_eq!(state.session_count(), 0);
+    }
+}
@@ -0,0 +1,135 @@
+//! 媒体接口
+//!
+//! `GET /api/v1/media/{kind}/{md5}`：经硬链接数据库定位图片、视频或文件并返回原始内容
+//! （图片为未解码的 `.dat`），`kind` 为 `image`/`video`/`file`。
+//! 加上 `?thumb=true` 时返回缩略图（保存在工作目录的 `.thumbs` 下），无法生成时返回 404。
+
+use axum::extract::{Path, Query, Request, State};
+use axum::response::Response;
+use serde::Deserialize;
+use std::path::PathBuf;
+use ts_rs::TS;
+
+use super::files::{allowed_roots, resolve_file, serve_file};
+use super::{ApiError, ServerState};
+use mwxdump_core::wechat::db::hardlink::{MediaKind, HARDLINK_DB_PATH};
+use mwxdump_core::wechat::db::SqliteDataSource;
+use mwxdump_core::wechat::media::MediaResolver;
+
+/// 媒体查询参数
+#[derive(Debug, Default, Deserialize, TS)]
+#[ts(export, optional_fields)]
+pub struct MediaParams {
+    /// 返回缩略图
+    #[serde(default)]
+    #[ts(optional, as = "Option<_>")]
+    pub thumb: bool,
+}
+
+/// 获取媒体文件
+pub async fn get_media(
+    State(state): State<ServerState>,
+    Path((kind, md5)): Path<(MediaKind, String)>,
+    Query(params): Query<MediaParams>,
+    request: Request,
+) -> Result<Response, ApiError> {
+    let data_root: PathBuf = state
+        .config
+        .wechat
+        .data_dir
+        .clone()
+        .ok_or_else(|| ApiError::bad_request("未配置微信数据目录 (wechat.data_dir)"))?;
+
+    let hardlink_db = resolve_file(&allowed_roots(&state).await, HARDLINK_DB_PATH)?;
+    let hardlink = SqliteDataSource::open(&hardlink_db)
+        .await
+        .map_err(|e| ApiError::bad_request(e.to_string()))?;
+    let file = MediaResolver::new(data_root, hardlink)
+        .resolve_md5(kind, &md5)
+        .await
+        .map_err(|e| ApiError::bad_request(e.to_string()))?
+        .filter(|path| path.is_file())
+        .ok_or_else(|| ApiError::not_found(format!("media {}", md5)))?;
+
+    let file = if params.thumb { thumbnail(&state, file, &md5).await? } else { file };
+    Ok(serve_file(file, request).await)
+}
+
+/// 生成或复用缩略图
+#[cfg(feature = "thumbnails")]
+async fn thumbnail(state: &ServerState, file: PathBuf, md5: &str) -> Result<PathBuf, ApiError> {
+    use mwxdump_core::wechat::media::Thumbnailer;
+
+    let thumbnailer = Thumbnailer::default().with_store_dir(state.config.database.work_dir.clone());
+    tokio::task::spawn_blocking(move || thumbnailer.generate(&file))
+        .await
+        .map_err(|e| ApiError::bad_request(e.to_string()))?
+        .map_err(|e| ApiError::bad_request(e.to_string()))?
+        .ok_or_else(|| ApiError::not_found(format!("thumbnail {}", md5)))
+}
+
+/// 未启用 `thumbnails` 特性时不提供缩略图
+#[cfg(not(feature = "thumbnails"))]
+async fn thumbnail(_state: &ServerState, _file: PathBuf, md5: &str) -> Result<PathBuf, ApiError> {
+    Err(ApiError::not_found(format!("thumbnail {}", md5)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::AppConfig;
+    use crate::server::{router, ServerState};
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use sqlx::sqlite::SqliteConnectOptions;
+    use sqlx::ConnectOptions;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_get_media_by_md5() {
+        let work = tempfile::tempdir().unwrap();
+        let data = tempfile::tempdir().unwrap();
+        let db = work.path().join("db_storage/hardlink/decrypted_hardlink.db");
+        std::fs::create_dir_all(db.parent().unwrap()).unwrap();
+        let mut conn = SqliteConnectOptions::new()
+            .filename(&db)
+            .create_if_missing(true)
+            .connect()
+            .await
+            .unwrap();
+        for sql in [
+            "CREATE TABLE dir2id (username TEXT)",
+            "INSERT INTO dir2id (rowid, username) VALUES (1, 'abcd'), (2, '2024-05')",
+            "CREATE TABLE image_hardlink_info_v3 (md5 TEXT, file_name TEXT, file_size INTEGER, modify_time INTEGER, dir1 INTEGER, dir2 INTEGER)",
+            "INSERT INTO image_hardlink_info_v3 VALUES ('d41d8cd98f00b204e9800998ecf8427e', 'x.dat', 3, 1, 1, 2)",
+        ] {
+            sqlx::query(sql).execute(&mut conn).await.unwrap();
+        }
+        drop(conn);
+        let image_dir = data.path().join("msg/attach/abcd/2024-05/Img");
+        std::fs::create_dir_all(&image_dir).unwrap();
+        std::fs::write(image_dir.join("x.dat"), b"dat").unwrap();
+
+        let mut config = AppConfig::default();
+        config.database.work_dir = work.path().to_path_buf();
+        config.wechat.data_dir = Some(data.path().to_path_buf());
+        let state = ServerState::new(config);
+        let get = |uri: &str| Request::get(uri).body(Body::empty()).unwrap();
+
+        let response = router(state.clone())
+            .oneshot(get("/api/v1/media/image/d41d8cd98f00b204e9800998ecf8427e"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"dat");
+
+        let response = router(state.clone())
+            .oneshot(get("/api/v1/media/image/00000000000000000000000000000000"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = router(state).oneshot(get("/api/v1/media/voice/00")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,279 @@
+//! 消息接口
+//!
+//! - `GET /api/v1/messages/{talker}?limit=100`：按时间顺序分页读取会话消息，附带消息总数；
+//!   第一页经查询缓存保存，自动刷新后失效
+//! - `GET /api/v1/messages/{talker}/density?tz=480`：按天统计会话的消息数，供日历热力图使用
+//! - `GET /api/v1/messages/{talker}/jump?date=2024-05-01&tz=480`：当天或之后第一条消息的 `seq`
+//! - `GET /api/v1/messages/{talker}/media?kind=image&limit=50`：按时间倒序列出媒体消息，
+//!   文件内容经 `/api/v1/media/{kind}/{md5}` 获取
+//!
+//! 列表接口返回 [`Page`]，以 `next_cursor` 作为下一次请求的 `cursor` 翻页。
+//! `tz` 为时区偏移（分钟，默认 0），决定日期的划分。
+
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use chrono::{FixedOffset, NaiveDate};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use super::files::allowed_roots;
+use super::{cached, parse_cursor, ApiError, ServerState};
+use mwxdump_core::models::{Message, Page};
+use mwxdump_core::wechat::db::hardlink::MediaKind;
+use mwxdump_core::wechat::db::messages::{DayCount, MediaItem, MessageRepository, MESSAGE_DB_DIR};
+use mwxdump_core::wechat::db::QueryKey;
+
+/// 消息列表默认每页条数
+pub const DEFAULT_MESSAGE_PAGE: u32 = 100;
+
+/// 消息列表每页最多条数
+pub const MAX_MESSAGE_PAGE: u32 = 1000;
+
+/// 媒体列表默认每页条数
+pub const DEFAULT_MEDIA_PAGE: u32 = 50;
+
+/// 媒体列表每页最多条数
+pub const MAX_MEDIA_PAGE: u32 = 500;
+
+/// 消息列表参数
+#[derive(Debug, Default, Deserialize, TS)]
+#[ts(export, optional_fields)]
+pub struct MessageListParams {
+    /// 上一页返回的 `next_cursor`
+    pub cursor: Option<String>,
+    /// 每页条数
+    pub limit: Option<u32>,
+}
+
+/// 日历统计参数
+#[derive(Debug, Default, Deserialize, TS)]
+#[ts(export, optional_fields)]
+pub struct DensityParams {
+    /// 时区偏移（分钟）
+    #[serde(default)]
+    #[ts(optional, as = "Option<_>")]
+    pub tz: i32,
+}
+
+/// 跳转参数
+#[derive(Debug, Deserialize, TS)]
+#[ts(export, optional_fields)]
+pub struct JumpParams {
+    /// 日期
+    pub date: NaiveDate,
+    /// 时区偏移（分钟）
+    #[serde(default)]
+    #[ts(optional, as = "Option<_>")]
+    pub tz: i32,
+}
+
+/// 跳转目标
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct JumpTarget {
+    /// 锚点消息的 `seq`，当天及之后没有消息时为空
+    #[ts(type = "number | null")]
+    pub seq: Option<i64>,
+}
+
+/// 媒体列表参数
+#[derive(Debug, Deserialize, TS)]
+#[ts(export, optional_fields)]
+pub struct MediaListParams {
+    /// 媒体类型
+    pub kind: MediaKind,
+    /// 上一页返回的 `next_cursor`
+    pub cursor: Option<String>,
+    /// 每页条数
+    pub limit: Option<u32>,
+}
+
+/// 按时间顺序分页读取会话消息
+pub async fn list_messages(
+    State(state): State<ServerState>,
+    Path(talker): Path<String>,
+    Query(params): Query<MessageListParams>,
+) -> Result<Json<Page<Message>>, ApiError> {
+    let after = seq_cursor(params.cursor.as_deref())?;
+    let limit = params.limit.unwrap_or(DEFAULT_MESSAGE_PAGE).clamp(1, MAX_MESSAGE_PAGE);
+    // 只缓存第一页，即最近的消息
+    if after.is_none() {
+        let key = QueryKey::RecentMessages {
+            talker: talker.clone(),
+            limit: limit as usize,
+        };
+        return cached(&state, key, || load_page(&state, &talker, None, limit)).await.map(Json);
+    }
+    load_page(&state, &talker, after, limit).await.map(Json)
+}
+
+async fn load_page(
+    state: &ServerState,
+    talker: &str,
+    after: Option<i64>,
+    limit: u32,
+) -> Result<Page<Message>, ApiError> {
+    let repo = open_repository(state).await?;
+    let messages = repo
+        .page(talker, after, limit)
+        .await
+        .map_err(|e| ApiError::bad_request(e.to_string()))?;
+    let total = repo.count(talker).await.map_err(|e| ApiError::bad_request(e.to_string()))?;
+    Ok(Page::from_seq(messages, limit as usize, |m| m.seq).with_total(total))
+}
+
+/// 按天统计会话的消息数
+pub async fn density(
+    State(state): State<ServerState>,
+    Path(talker): Path<String>,
+    Query(params): Query<DensityParams>,
+) -> Result<Json<Vec<DayCount>>, ApiError> {
+    let offset = utc_offset(params.tz)?;
+    let repo = open_repository(&state).await?;
+    let days = repo
+        .density(&talker, offset)
+        .await
+        .map_err(|e| ApiError::bad_request(e.to_string()))?;
+    Ok(Json(days))
+}
+
+/// 查找指定日期的第一条消息
+pub async fn jump(
+    State(state): State<ServerState>,
+    Path(talker): Path<String>,
+    Query(params): Query<JumpParams>,
+) -> Result<Json<JumpTarget>, ApiError> {
+    let offset = utc_offset(params.tz)?;
+    let repo = open_repository(&state).await?;
+    let seq = repo
+        .seq_at_date(&talker, params.date, offset)
+        .await
+        .map_err(|e| ApiError::bad_request(e.to_string()))?;
+    Ok(Json(JumpTarget { seq }))
+}
+
+/// 列出会话中的媒体消息
+pub async fn list_media(
+    State(state): State<ServerState>,
+    Path(talker): Path<String>,
+    Query(params): Query<MediaListParams>,
+) -> Result<Json<Page<MediaItem>>, ApiError> {
+    let before = seq_cursor(params.cursor.as_deref())?;
+    let limit = params.limit.unwrap_or(DEFAULT_MEDIA_PAGE).clamp(1, MAX_MEDIA_PAGE);
+    let repo = open_repository(&state).await?;
+    let items = repo
+        .list_media(&talker, params.kind, before, limit)
+        .await
+        .map_err(|e| ApiError::bad_request(e.to_string()))?;
+    Ok(Json(Page::from_seq(items, limit as usize, |item| item.seq)))
+}
+
+/// 解析按 `sort_seq` 翻页的游标
+fn seq_cursor(cursor: Option<&str>) -> Result<Option<i64>, ApiError> {
+    parse_cursor(cursor)?
+        .map(|cursor| cursor.seq().map_err(|e| ApiError::bad_request(e.to_string())))
+        .transpose()
+}
+
+fn utc_offset(minutes: i32) -> Result<FixedOffset, ApiError> {
+    minutes
+        .checked_mul(60)
+        .and_then(FixedOffset::east_opt)
+        .ok_or_else(|| ApiError::bad_request(format!("无效的时区偏移: {}", minutes)))
+}
+
+/// 打开第一个包含消息数据库的允许目录
+async fn open_repository(state: &ServerState) -> Result<MessageRepository, ApiError> {
+    let root = allowed_roots(state)
+        .await
+        .into_iter()
+        .find(|root| root.join(MESSAGE_DB_DIR).is_dir())
+        .ok_or_else(|| ApiError::not_found(MESSAGE_DB_DIR))?;
+    MessageRepository::open(&root)
+        .await
+        .map_err(|e| ApiError::bad_request(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::server::router;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use mwxdump_core::wechat::db::messages::message_table;
+    use sqlx::sqlite::SqliteConnectOptions;
+    use sqlx::ConnectOptions;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_density_and_jump() {
+        let work = tempfile::tempdir().unwrap();
+        let mut config = AppConfig::default();
+        config.database.work_dir = work.path().to_path_buf();
+        let state = ServerState::new(config);
+        let get = |uri: &str| Request::get(uri).body(Body::empty()).unwrap();
+
+        let response = router(state.clone()).oneshot(get("/api/v1/messages/wxid_friend/density")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let dir = work.path().join(MESSAGE_DB_DIR);
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut conn = SqliteConnectOptions::new()
+            .filename(dir.join("decrypted_message_0.db"))
+            .create_if_missing(true)
+            .connect()
+            .await
+            .unwrap();
+        let table = message_table("wxid_friend");
+        for sql in [
+            format!("CREATE TABLE {} (sort_seq INTEGER, create_time INTEGER)", table),
+            // 2024-05-01 23:30 与 2024-05-02 01:00（UTC）
+            format!("INSERT INTO {} VALUES (1, 1714606200), (2, 1714611600)", table),
+        ] {
+            sqlx::query(&sql).execute(&mut conn).await.unwrap();
+        }
+        drop(conn);
+
+        let response = router(state.clone()).oneshot(get("/api/v1/messages/wxid_friend/density")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let days: Vec<DayCount> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(days.iter().map(|d| d.count).collect::<Vec<_>>(), [1, 1]);
+
+        let response = router(state.clone())
+            .oneshot(get("/api/v1/messages/wxid_friend/density?tz=480"))
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let days: Vec<DayCount> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(days.len(), 1);
+        assert_eq!(days[0].date, NaiveDate::from_ymd_opt(2024, 5, 2).unwrap());
+
+        let response = router(state.clone())
+            .oneshot(get("/api/v1/messages/wxid_friend/jump?date=2024-05-02"))
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let target: JumpTarget = serde_json::from_slice(&body).unwrap();
+        assert_eq!(target.seq, Some(2));
+
+        let response = router(state.clone())
+            .oneshot(get("/api/v1/messages/wxid_friend/media?kind=image&cursor=bad"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = router(state.clone())
+            .oneshot(get("/api/v1/messages/wxid_friend/media?kind=voice"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = router(state)
+            .oneshot(get("/api/v1/messages/wxid_friend/density?tz=100000"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
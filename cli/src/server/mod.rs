@@ -0,0 +1,593 @@
+//! HTTP 服务
+//!
+//! 提供远程触发备份、查询任务状态等接口，便于 NAS、家庭自动化系统按计划调用。
+
+pub mod contacts;
+pub mod events;
+pub mod exports;
+pub mod files;
+pub mod jobs;
+pub mod key;
+pub mod mcp;
+pub mod media;
+pub mod messages;
+pub mod query;
+pub mod refresh;
+pub mod search;
+pub mod session;
+pub mod sessions;
+
+use axum::extract::{Request, State};
+use axum::http::{header, Extensions, HeaderMap, HeaderName, HeaderValue, Method, StatusCode, Version};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chrono::Utc;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::json;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+use tokio::sync::{RwLock, Semaphore};
+use tokio_util::sync::CancellationToken;
+use tower_http::compression::predicate::{NotForContentType, Predicate, SizeAbove};
+use tower_http::compression::CompressionLayer;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, RequestId, SetRequestIdLayer};
+use tower_http::trace::{DefaultOnResponse, TraceLayer};
+use tower_http::LatencyUnit;
+use tracing::Level;
+
+use crate::config::{AppConfig, HttpConfig};
+use mwxdump_core::errors::{HttpError, Result};
+use mwxdump_core::events::{Event, EventBus};
+use mwxdump_core::jobs::JobManager;
+use mwxdump_core::models::Cursor;
+use mwxdump_core::scopes::{Scope, Scopes};
+use mwxdump_core::wechat::db::{QueryCache, QueryKey};
+use mwxdump_core::wechat::decrypt::DecryptedSession;
+
+/// 小于该字节数的响应不压缩
+const COMPRESSION_MIN_SIZE: u16 = 1024;
+
+/// 服务共享状态
+#[derive(Clone)]
+pub struct ServerState {
+    /// 应用配置
+    pub config: Arc<AppConfig>,
+    /// 后台任务
+    pub jobs: JobManager,
+    /// 当前解锁的临时解密会话
+    pub session: Arc<RwLock<Option<DecryptedSession>>>,
+    /// 临时会话模式：不写入持久化的明文工作目录，只提供会话内的解密数据
+    pub ephemeral: bool,
+    /// 事件总线，事件流订阅者收到其中的全部事件
+    pub events: EventBus,
+    /// 高频查询的结果缓存，自动刷新后失效
+    pub cache: QueryCache<QueryKey, serde_json::Value>,
+    /// 是否允许查询接口执行原始 SQL
+    pub allow_raw_sql: bool,
+    /// 导出队列，同一时间只运行一个导出任务
+    pub export_queue: Arc<Semaphore>,
+    /// 服务退出令牌，会话解锁等长时间操作随服务退出而取消
+    pub shutdown: CancellationToken,
+}
+
+impl ServerState {
+    /// 使用配置创建服务状态
+    pub fn new(config: AppConfig) -> Self {
+        let cache = QueryCache::new(&config.database.cache);
+        let events = EventBus::new();
+        Self {
+            config: Arc::new(config),
+            jobs: JobManager::new().with_events(events.clone()),
+            session: Arc::new(RwLock::new(None)),
+            ephemeral: false,
+            events,
+            cache,
+            allow_raw_sql: false,
+            export_queue: Arc::new(Semaphore::new(1)),
+            shutdown: CancellationToken::new(),
+        }
+    }
+
+    /// 设置是否启用临时会话模式
+    pub fn with_ephemeral(mut self, ephemeral: bool) -> Self {
+        self.ephemeral = ephemeral;
+        self
+    }
+
+    /// 设置服务退出令牌
+    pub fn with_shutdown(mut self, shutdown: CancellationToken) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// 设置是否允许原始 SQL 查询
+    pub fn with_raw_sql(mut self, allow: bool) -> Self {
+        self.allow_raw_sql = allow;
+        self
+    }
+
+    /// 数据库重新解密后使缓存失效，并通知事件流订阅者
+    pub fn data_updated(&self, files: Vec<PathBuf>) {
+        self.cache.invalidate_all();
+        self.events.publish(Event::DataUpdated { files, at: Utc::now() });
+    }
+}
+
+/// 命中缓存时直接返回，否则执行查询并缓存结果
+pub(crate) async fn cached<T, F, Fut>(state: &ServerState, key: QueryKey, query: F) -> std::result::Result<T, ApiError>
+where
+    T: Serialize + DeserializeOwned,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = std::result::Result<T, ApiError>>,
+{
+    if let Some(value) = state.cache.get(&key).and_then(|value| serde_json::from_value(value).ok()) {
+        return Ok(value);
+    }
+    let value = query().await?;
+    if let Ok(json) = serde_json::to_value(&value) {
+        state.cache.insert(key, json);
+    }
+    Ok(value)
+}
+
+/// 构建路由
+pub fn router(state: ServerState) -> Router {
+    let cors = cors_layer(&state.config.http);
+    let decrypt = Router::new()
+        .route("/api/v1/jobs/backup", post(jobs::create_backup))
+        .route("/api/v1/key/extract", post(key::extract))
+        .route("/api/v1/session/unlock", post(session::unlock))
+        .route("/api/v1/session/lock", post(session::lock));
+    let messages = Router::new()
+        .route("/api/v1/jobs/export", post(jobs::create_export))
+        .route("/api/v1/exports", post(exports::create))
+        .route("/api/v1/exports/{id}", get(exports::get))
+        .route("/api/v1/exports/{id}/download", get(exports::download))
+        .route("/api/v1/sessions", get(sessions::list_sessions))
+        .route("/api/v1/messages/{talker}", get(messages::list_messages))
+        .route("/api/v1/messages/{talker}/density", get(messages::density))
+        .route("/api/v1/messages/{talker}/jump", get(messages::jump))
+        .route("/api/v1/messages/{talker}/media", get(messages::list_media));
+    // 原始文件和查询可以读取任意解密数据库，搜索同时返回联系人
+    let databases = Router::new()
+        .route("/api/v1/files/{*path}", get(files::download))
+        .route("/api/v1/query", post(query::query))
+        .route("/api/v1/search", get(search::search));
+    // 任务结果、会话状态和事件流会透露账号和新消息
+    let status = Router::new()
+        .route("/api/v1/jobs/{id}", get(jobs::get_job))
+        .route("/api/v1/session", get(session::get_session))
+        .route("/api/v1/events", get(events::subscribe));
+    let router = Router::new()
+        .merge(scoped(decrypt, &[Scope::RunDecrypt]))
+        .merge(scoped(messages, &[Scope::ReadMessages]))
+        .merge(scoped(databases, &[Scope::ReadMessages, Scope::ReadContacts]))
+        .merge(scoped(
+            Router::new().route("/api/v1/contacts", get(contacts::list_contacts)),
+            &[Scope::ReadContacts],
+        ))
+        .merge(scoped(
+            Router::new().route("/api/v1/media/{kind}/{md5}", get(media::get_media)),
+            &[Scope::ReadMedia],
+        ))
+        .merge(scoped(status, &[Scope::ReadMessages]))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_auth))
+        .with_state(state)
+        .layer(compression_layer());
+    let router = match cors {
+        Some(cors) => router.layer(cors),
+        None => router,
+    };
+    with_request_id(router)
+}
+
+/// 为每个请求分配 `x-request-id`（客户端已提供时沿用），写入响应头、访问日志和错误响应
+///
+/// 访问日志只记录路径，不记录查询参数，避免搜索文本等内容进入日志。
+fn with_request_id(router: Router) -> Router {
+    router
+        .layer(middleware::from_fn(scope_request_id))
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(|request: &Request| {
+                    tracing::info_span!(
+                        "http",
+                        request_id = request_id(request).unwrap_or_default(),
+                        method = %request.method(),
+                        path = request.uri().path(),
+                    )
+                })
+                .on_request(())
+                .on_response(DefaultOnResponse::new().level(Level::INFO).latency_unit(LatencyUnit::Millis)),
+        )
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+}
+
+tokio::task_local! {
+    /// 当前请求的ID，供错误响应使用
+    static REQUEST_ID: String;
+}
+
+/// 读取 [`SetRequestIdLayer`] 分配的请求ID
+fn request_id(request: &Request) -> Option<&str> {
+    request
+        .extensions()
+        .get::<RequestId>()
+        .and_then(|id| id.header_value().to_str().ok())
+}
+
+/// 在请求ID的作用域内处理请求
+async fn scope_request_id(request: Request, next: Next) -> Response {
+    let id = request_id(&request).unwrap_or_default().to_string();
+    REQUEST_ID.scope(id, next.run(request)).await
+}
+
+/// 构建响应压缩层，按客户端的 `Accept-Encoding` 选择 gzip 或 deflate
+///
+/// 只压缩 JSON 和文本等可压缩的内容；数据库、媒体文件本身已压缩或需要支持 `Range`，
+/// 事件流需要逐条推送，都不压缩。
+fn compression_layer() -> CompressionLayer<impl Predicate> {
+    CompressionLayer::new().compress_when(
+        SizeAbove::new(COMPRESSION_MIN_SIZE)
+            .and(NotForContentType::SSE)
+            .and(compressible_content_type),
+    )
+}
+
+/// 响应内容是否为可压缩的文本类型
+fn compressible_content_type(_: StatusCode, _: Version, headers: &HeaderMap, _: &Extensions) -> bool {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let essence = content_type.split(';').next().unwrap_or_default().trim();
+    essence.starts_with("text/")
+        || essence == "application/json"
+        || essence == "application/javascript"
+        || essence.ends_with("+json")
+        || essence.ends_with("+xml")
+}
+
+/// 按配置的来源白名单构建 CORS 层，未启用时返回 `None`
+///
+/// CORS 层放在认证之外，浏览器的预检请求不携带令牌也能通过。
+fn cors_layer(config: &HttpConfig) -> Option<CorsLayer> {
+    if !config.enable_cors {
+        return None;
+    }
+    let origins: Vec<HeaderValue> = config
+        .cors_origins
+        .iter()
+        .filter_map(|origin| match HeaderValue::from_str(origin) {
+            Ok(value) => Some(value),
+            Err(_) => {
+                tracing::warn!("忽略无效的跨域来源: {}", origin);
+                None
+            }
+        })
+        .collect();
+    Some(
+        CorsLayer::new()
+            .allow_origin(AllowOrigin::list(origins))
+            .allow_methods([Method::GET, Method::POST])
+            .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE, header::RANGE])
+            .expose_headers([
+                header::CONTENT_RANGE,
+                header::CONTENT_LENGTH,
+                header::ACCEPT_RANGES,
+                HeaderName::from_static("x-request-id"),
+            ]),
+    )
+}
+
+/// 校验访问令牌并记录授予的权限，未配置令牌时放行所有请求并授予全部权限
+async fn require_auth(State(state): State<ServerState>, mut request: Request, next: Next) -> Response {
+    let provided = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match granted_scopes(&state.config.http, provided) {
+        Some(scopes) => {
+            request.extensions_mut().insert(scopes);
+            next.run(request).await
+        }
+        None => {
+            tracing::warn!("拒绝未授权请求: {}", request.uri().path());
+            ApiError::unauthorized().into_response()
+        }
+    }
+}
+
+/// 未配置访问令牌时拒绝监听本机以外的地址，避免局域网内任何人都能读取聊天记录
+pub(crate) fn check_bind(config: &HttpConfig, host: &str) -> Result<()> {
+    let loopback = host.eq_ignore_ascii_case("localhost")
+        || host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback());
+    if !loopback && !config.auth_enabled() {
+        return Err(HttpError::InsecureBind { host: host.to_string() }.into());
+    }
+    Ok(())
+}
+
+/// 令牌对应的权限，令牌无效时返回 `None`
+pub(crate) fn granted_scopes(config: &HttpConfig, provided: Option<&str>) -> Option<Scopes> {
+    if !config.auth_enabled() {
+        return Some(Scopes::all());
+    }
+    let provided = provided?;
+    if config.auth_token.as_deref().is_some_and(|token| token_eq(token, provided)) {
+        return Some(Scopes::all());
+    }
+    config
+        .tokens
+        .iter()
+        .find(|token| token_eq(&token.token, provided))
+        .map(|token| Scopes::new(token.scopes.iter().copied()))
+}
+
+/// 以常量时间比较令牌，避免通过响应耗时逐字节猜出令牌
+fn token_eq(expected: &str, provided: &str) -> bool {
+    expected.as_bytes().ct_eq(provided.as_bytes()).into()
+}
+
+/// 为一组路由要求权限
+fn scoped(router: Router<ServerState>, required: &'static [Scope]) -> Router<ServerState> {
+    router.route_layer(middleware::from_fn_with_state(required, require_scopes))
+}
+
+/// 检查 [`require_auth`] 记录的权限
+async fn require_scopes(State(required): State<&'static [Scope]>, request: Request, next: Next) -> Response {
+    let missing = match request.extensions().get::<Scopes>() {
+        Some(granted) => granted.missing(required),
+        None => required.first().copied(),
+    };
+    match missing {
+        Some(scope) => {
+            tracing::warn!("令牌缺少权限 {}: {}", scope, request.uri().path());
+            ApiError::forbidden(format!("令牌缺少权限: {}", scope)).into_response()
+        }
+        None => next.run(request).await,
+    }
+}
+
+/// 启动 HTTP 服务，取消令牌触发后停止接受新请求并退出
+pub async fn serve(state: ServerState, host: &str, port: u16, shutdown: CancellationToken) -> Result<()> {
+    check_bind(&state.config.http, host)?;
+    let listener = tokio::net::TcpListener::bind((host, port)).await.map_err(|e| {
+        if e.kind() == std::io::ErrorKind::AddrInUse {
+            HttpError::PortInUse { port }
+        } else {
+            HttpError::ServerStartFailed(e.to_string())
+        }
+    })?;
+    tracing::info!("HTTP 服务监听于 {}:{}", host, port);
+
+    let session = state.session.clone();
+    let result = axum::serve(listener, router(state))
+        .with_graceful_shutdown(async move { shutdown.cancelled().await })
+        .await;
+    // 退出前清除临时解密数据
+    if let Some(session) = session.write().await.take() {
+        session.lock().await;
+    }
+    result.map_err(|e| HttpError::ServerStartFailed(e.to_string()))?;
+    Ok(())
+}
+
+/// 解析列表接口的 `cursor` 参数
+pub fn parse_cursor(cursor: Option<&str>) -> std::result::Result<Option<Cursor>, ApiError> {
+    Cursor::parse(cursor).map_err(|e| ApiError::bad_request(e.to_string()))
+}
+
+/// 接口错误响应
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    /// 请求参数错误
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            message: message.into(),
+        }
+    }
+
+    /// 资源不存在
+    pub fn not_found(resource: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::NOT_FOUND,
+            message: HttpError::ResourceNotFound { resource: resource.into() }.to_string(),
+        }
+    }
+
+    /// 操作未被允许
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::FORBIDDEN,
+            message: message.into(),
+        }
+    }
+
+    /// 资源当前状态不允许该操作
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::CONFLICT,
+            message: message.into(),
+        }
+    }
+
+    /// 服务端处理失败
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            message: message.into(),
+        }
+    }
+
+    /// 缺少或错误的访问令牌
+    pub fn unauthorized() -> Self {
+        Self {
+            status: StatusCode::UNAUTHORIZED,
+            message: HttpError::AuthenticationFailed.to_string(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let mut body = json!({
+            "error": self.message,
+            "code": self.status.as_u16()
+        });
+        if let Ok(id) = REQUEST_ID.try_with(Clone::clone) {
+            body["request_id"] = json!(id);
+        }
+        let body = Json(body);
+        (self.status, body).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use mwxdump_core::jobs::JobId;
+    use tower::ServiceExt;
+
+    async fn preflight(config: AppConfig, origin: &str) -> Option<HeaderValue> {
+        let request = Request::options("/api/v1/contacts")
+            .header(header::ORIGIN, origin)
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+            .body(Body::empty())
+            .unwrap();
+        let response = router(ServerState::new(config)).oneshot(request).await.unwrap();
+        response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).cloned()
+    }
+
+    #[tokio::test]
+    async fn test_cors_allowlist() {
+        let mut config = AppConfig::default();
+        config.http.auth_token = Some("s3cret".to_string());
+        assert_eq!(
+            preflight(config.clone(), "tauri://localhost").await.unwrap(),
+            "tauri://localhost"
+        );
+        assert!(preflight(config.clone(), "https://evil.example").await.is_none());
+
+        config.http.cors_origins.push("https://nas.local:8443".to_string());
+        assert!(preflight(config.clone(), "https://nas.local:8443").await.is_some());
+
+        config.http.enable_cors = false;
+        assert!(preflight(config, "tauri://localhost").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_token_scopes() {
+        let mut config = AppConfig::default();
+        config.http.auth_token = Some("admin".to_string());
+        config.http.tokens.push(crate::config::ApiToken {
+            name: "llm".to_string(),
+            token: "t1".to_string(),
+            scopes: vec![Scope::ReadMessages],
+        });
+        config.http.tokens.push(crate::config::ApiToken {
+            name: "gallery".to_string(),
+            token: "t2".to_string(),
+            scopes: vec![Scope::ReadMedia],
+        });
+        let state = ServerState::new(config);
+        let send = |method: &str, uri: &str, token: &str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(header::AUTHORIZATION, format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap()
+        };
+        let status = |request: Request<Body>| {
+            let state = state.clone();
+            async move { router(state).oneshot(request).await.unwrap().status() }
+        };
+
+        assert_eq!(status(send("GET", "/api/v1/contacts", "t1")).await, StatusCode::FORBIDDEN);
+        assert_eq!(status(send("POST", "/api/v1/session/lock", "t1")).await, StatusCode::FORBIDDEN);
+        assert_eq!(status(send("GET", "/api/v1/search?q=a", "t1")).await, StatusCode::FORBIDDEN);
+        assert_eq!(status(send("GET", "/api/v1/session", "nope")).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(send("GET", "/api/v1/session", "t1")).await, StatusCode::NOT_FOUND);
+        assert_eq!(status(send("GET", "/api/v1/session", "t2")).await, StatusCode::FORBIDDEN);
+        assert_eq!(status(send("GET", "/api/v1/events", "t2")).await, StatusCode::FORBIDDEN);
+        assert_eq!(status(send("POST", "/api/v1/session/lock", "admin")).await, StatusCode::NO_CONTENT);
+        assert_eq!(status(send("GET", "/api/v1/contacts", "nope")).await, StatusCode::UNAUTHORIZED);
+        // 权限满足后交给处理函数，数据库不存在时返回 404 而不是 403
+        let allowed = status(send("GET", "/api/v1/messages/wxid_a/density", "t1")).await;
+        assert!(allowed != StatusCode::FORBIDDEN && allowed != StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn test_check_bind() {
+        let mut config = AppConfig::default();
+        for host in ["127.0.0.1", "localhost", "::1", "[::1]"] {
+            assert!(check_bind(&config.http, host).is_ok(), "{}", host);
+        }
+        assert!(check_bind(&config.http, "0.0.0.0").is_err());
+        assert!(check_bind(&config.http, "192.168.1.2").is_err());
+        config.http.auth_token = Some("s3cret".to_string());
+        assert!(check_bind(&config.http, "0.0.0.0").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_request_id_in_header_and_error() {
+        let state = ServerState::new(AppConfig::default());
+        let uri = format!("/api/v1/jobs/{}", JobId::new_v4());
+        let request = Request::get(&uri).header("x-request-id", "abc-123").body(Body::empty()).unwrap();
+        let response = router(state.clone()).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.headers().get("x-request-id").unwrap(), "abc-123");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["request_id"], "abc-123");
+
+        // 未提供时生成新的ID
+        let request = Request::get(&uri).body(Body::empty()).unwrap();
+        let response = router(state).oneshot(request).await.unwrap();
+        let id = response.headers().get("x-request-id").unwrap().to_str().unwrap().to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["request_id"], id.as_str());
+        assert!(JobId::parse_str(&id).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_compresses_json_only() {
+        let app = Router::new()
+            .route("/json", get(|| async { Json(vec!["message"; 1000]) }))
+            .route("/db", get(|| async { ([(header::CONTENT_TYPE, "application/octet-stream")], vec![0u8; 4096]) }))
+            .layer(compression_layer());
+        let get = |uri: &str| {
+            Request::get(uri)
+                .header(header::ACCEPT_ENCODING, "gzip")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(get("/json")).await.unwrap();
+        assert_eq!(response.headers().get(header::CONTENT_ENCODING).unwrap(), "gzip");
+        let response = app.oneshot(get("/db")).await.unwrap();
+        assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+    }
+}
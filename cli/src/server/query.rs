@@ -0,0 +1,127 @@
+//! 查询接口
+//!
+//! `POST /api/v1/query`：对工作目录（或当前会话）中的解密数据库执行只读查询。
+//! 默认只接受结构化查询，原始 SQL 需要以 `--allow-raw-sql` 启动服务。
+
+use axum::extract::State;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use ts_rs::TS;
+
+use super::files::{allowed_roots, resolve_file};
+use super::{ApiError, ServerState};
+use mwxdump_core::wechat::db::{DataSource, Query, SqliteDataSource};
+
+/// 未指定 `limit` 时最多返回的行数
+pub const DEFAULT_QUERY_LIMIT: u32 = 1000;
+
+/// 查询请求，`query` 与 `sql` 二选一
+#[derive(Debug, Deserialize, TS)]
+#[ts(export, optional_fields)]
+pub struct QueryRequest {
+    /// 数据库路径，相对工作目录或会话目录
+    pub db: String,
+    /// 结构化查询
+    pub query: Option<Query>,
+    /// 原始 SQL
+    pub sql: Option<String>,
+}
+
+/// 查询结果
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct QueryResponse {
+    /// 每行一个以列名为键的对象
+    pub rows: Vec<Value>,
+}
+
+/// 执行查询
+pub async fn query(
+    State(state): State<ServerState>,
+    Json(request): Json<QueryRequest>,
+) -> Result<Json<QueryResponse>, ApiError> {
+    let db = resolve_file(&allowed_roots(&state).await, &request.db)?;
+    let source = SqliteDataSource::open(&db)
+        .await
+        .map_err(|e| ApiError::bad_request(e.to_string()))?
+        .with_raw_sql(state.allow_raw_sql);
+
+    let rows = match (request.query, request.sql) {
+        (Some(mut query), None) => {
+            query.limit.get_or_insert(DEFAULT_QUERY_LIMIT);
+            source.fetch(&query).await
+        }
+        (None, Some(sql)) => {
+            if !state.allow_raw_sql {
+                return Err(ApiError::forbidden("未开启原始 SQL 查询，请以 --allow-raw-sql 启动服务"));
+            }
+            source.fetch_raw(&sql, &[]).await.map(|rows| rows.into_objects())
+        }
+        _ => return Err(ApiError::bad_request("query 与 sql 必须且只能提供一个")),
+    }
+    .map_err(|e| ApiError::bad_request(e.to_string()))?;
+    Ok(Json(QueryResponse { rows }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::server::router;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use sqlx::sqlite::SqliteConnectOptions;
+    use sqlx::ConnectOptions;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_structured_and_raw_queries() {
+        let work = tempfile::tempdir().unwrap();
+        let mut conn = SqliteConnectOptions::new()
+            .filename(work.path().join("session.db"))
+            .create_if_missing(true)
+            .connect()
+            .await
+            .unwrap();
+        sqlx::query("CREATE TABLE SessionTable (username TEXT, unread_count INTEGER)")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO SessionTable VALUES ('wxid_a', 3), ('wxid_b', 0)")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        drop(conn);
+
+        let mut config = AppConfig::default();
+        config.database.work_dir = work.path().to_path_buf();
+        let state = ServerState::new(config);
+
+        let post = |body: &str| {
+            Request::post("/api/v1/query")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let body = r#"{"db":"session.db","query":{"table":"SessionTable","filters":[{"column":"unread_count","op":"gt","value":0}]}}"#;
+        let response = router(state.clone()).oneshot(post(body)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let result: QueryResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(result.rows, [serde_json::json!({"username": "wxid_a", "unread_count": 3})]);
+
+        let raw = r#"{"db":"session.db","sql":"SELECT * FROM SessionTable"}"#;
+        let response = router(state.clone()).oneshot(post(raw)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let state = state.with_raw_sql(true);
+        let response = router(state.clone()).oneshot(post(raw)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let write = r#"{"db":"session.db","sql":"DELETE FROM SessionTable"}"#;
+        let response = router(state).oneshot(post(write)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,125 @@
+//! 自动刷新
+//!
+//! 监控微信数据库目录，变化的分片快照后重新解密到工作目录（临时会话模式下为当前会话目录），
+//! 并通过事件流通知客户端，实现近实时的聊天记录访问；配置了 Webhook 时推送匹配的新消息。
+//!
+//! 临时会话模式使用解锁时的密钥，并只刷新会话解密的类别；否则使用配置中的 `wechat.data_key`。
+//! 密钥缺失或无效时通过事件流报告错误，监控继续运行，配置或解锁后即可恢复刷新。
+
+use std::path::PathBuf;
+use tokio_util::sync::CancellationToken;
+use zeroize::Zeroizing;
+
+use super::ServerState;
+use mwxdump_core::errors::{ConfigError, Result, WeChatError};
+use mwxdump_core::events::Event;
+use mwxdump_core::wechat::datadir::{self, watch, DbCategory, DbStorageWatcher};
+use mwxdump_core::wechat::db::tail::NewMessageScanner;
+use mwxdump_core::webhook::WebhookDispatcher;
+
+/// 运行自动刷新，直到取消令牌触发
+pub async fn run(state: ServerState, shutdown: CancellationToken) -> Result<()> {
+    let config = &state.config;
+    let data_dir = config.wechat.data_dir.clone().ok_or_else(|| ConfigError::MissingKey {
+        key: "wechat.data_dir".to_string(),
+    })?;
+
+    let info = datadir::scan(&data_dir)?;
+    let db_storage = info.db_storage_path.ok_or_else(|| WeChatError::DataDirNotFound {
+        path: data_dir.display().to_string(),
+    })?;
+
+    let webhooks = WebhookDispatcher::new(config.webhook.clone());
+    let mut scanner: Option<NewMessageScanner> = None;
+    let mut batches = DbStorageWatcher::new(db_storage).start(shutdown.clone())?;
+    while let Some(changed) = batches.recv().await {
+        let target = match refresh_target(&state).await {
+            Ok(Some(target)) => target,
+            Ok(None) => {
+                tracing::debug!("会话未解锁，忽略 {} 个数据库变化", changed.len());
+                continue;
+            }
+            Err(e) => {
+                tracing::warn!("无法刷新 {} 个变化的数据库: {}", changed.len(), e);
+                state.events.publish(Event::Error {
+                    source: "refresh".to_string(),
+                    message: e.to_string(),
+                });
+                continue;
+            }
+        };
+        let output = target.output;
+        // 刷新前记录各会话的最后一条消息，Webhook 只推送此后的消息
+        if !webhooks.is_empty() && scanner.as_ref().map(|s| s.work_dir()) != Some(output.as_path()) {
+            let mut fresh = NewMessageScanner::new(&output);
+            if let Some(wxid) = &info.wxid {
+                fresh = fresh.with_self_wxid(wxid.clone());
+            }
+            scanner = match fresh.prime().await {
+                Ok(()) => Some(fresh),
+                Err(e) => {
+                    tracing::warn!("读取会话列表失败，暂不推送 Webhook: {}", e);
+                    None
+                }
+            };
+        }
+        match watch::refresh_changed(&info.root, &changed, &output, &target.key, &target.categories, None).await {
+            Ok(files) if files.is_empty() => {}
+            Ok(files) => {
+                state.data_updated(files);
+                if let Some(scanner) = scanner.as_mut() {
+                    match scanner.scan().await {
+                        Ok(messages) if !messages.is_empty() => {
+                            let webhooks = webhooks.clone();
+                            tokio::spawn(async move { webhooks.dispatch(&messages).await });
+                        }
+                        Ok(_) => {}
+                        Err(e) => tracing::warn!("读取新消息失败: {}", e),
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::warn!("自动刷新失败: {}", e);
+                state.events.publish(Event::Error {
+                    source: "refresh".to_string(),
+                    message: e.to_string(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 一次刷新的输出目录、密钥和类别
+struct RefreshTarget {
+    output: PathBuf,
+    key: Zeroizing<Vec<u8>>,
+    categories: Vec<DbCategory>,
+}
+
+/// 当前的刷新目标，临时会话模式下会话未解锁时返回 `None`
+async fn refresh_target(state: &ServerState) -> Result<Option<RefreshTarget>> {
+    if state.ephemeral {
+        return Ok(state.session.read().await.as_ref().map(|session| RefreshTarget {
+            output: session.path().to_path_buf(),
+            key: Zeroizing::new(session.key().to_vec()),
+            categories: session.categories().to_vec(),
+        }));
+    }
+    let key_hex = state.config.wechat.data_key.clone().ok_or_else(|| ConfigError::MissingKey {
+        key: "wechat.data_key".to_string(),
+    })?;
+    // 错误会推送到事件流，不回显密钥内容
+    let key = hex::decode(&key_hex)
+        .ok()
+        .filter(|key| key.len() == 32)
+        .ok_or_else(|| ConfigError::InvalidValue {
+            key: "wechat.data_key".to_string(),
+            value: "<已隐藏>".to_string(),
+        })?;
+    Ok(Some(RefreshTarget {
+        output: state.config.database.work_dir.clone(),
+        key: Zeroizing::new(key),
+        categories: Vec::new(),
+    }))
+}
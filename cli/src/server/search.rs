@@ -0,0 +1,83 @@
+//! 搜索接口
+//!
+//! `GET /api/v1/search?q=周五&limit=20`：在工作目录（或当前会话）中同时搜索联系人、群聊、
+//! 文本消息和媒体识别文本，按类别分组返回，每类最多 `limit` 条。
+
+use axum::extract::{Query, State};
+use axum::Json;
+use serde::Deserialize;
+use ts_rs::TS;
+
+use super::files::allowed_roots;
+use super::{ApiError, ServerState};
+use mwxdump_core::wechat::db::contacts::CONTACT_DB_PATH;
+use mwxdump_core::wechat::db::messages::MESSAGE_DB_DIR;
+use mwxdump_core::wechat::search::{CombinedSearch, SearchResults};
+
+/// 每类默认返回的条数
+pub const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+/// 每类最多返回的条数
+pub const MAX_SEARCH_LIMIT: usize = 200;
+
+/// 搜索参数
+#[derive(Debug, Deserialize, TS)]
+#[ts(export, optional_fields)]
+pub struct SearchParams {
+    /// 搜索文本
+    pub q: String,
+    /// 每类最多返回的条数
+    pub limit: Option<usize>,
+}
+
+/// 综合搜索
+pub async fn search(
+    State(state): State<ServerState>,
+    Query(params): Query<SearchParams>,
+) -> Result<Json<SearchResults>, ApiError> {
+    let root = allowed_roots(&state)
+        .await
+        .into_iter()
+        .find(|root| root.join(CONTACT_DB_PATH).is_file() || root.join(MESSAGE_DB_DIR).is_dir())
+        .ok_or_else(|| ApiError::not_found(MESSAGE_DB_DIR))?;
+    let limit = params.limit.unwrap_or(DEFAULT_SEARCH_LIMIT).clamp(1, MAX_SEARCH_LIMIT);
+    let results = CombinedSearch::open(&root)
+        .await
+        .map_err(|e| ApiError::bad_request(e.to_string()))?
+        .search(&params.q, limit)
+        .await
+        .map_err(|e| ApiError::bad_request(e.to_string()))?;
+    Ok(Json(results))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::server::router;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_search_endpoint() {
+        let work = tempfile::tempdir().unwrap();
+        let mut config = AppConfig::default();
+        config.database.work_dir = work.path().to_path_buf();
+        let state = ServerState::new(config);
+        let get = |uri: &str| Request::get(uri).body(Body::empty()).unwrap();
+
+        let response = router(state.clone()).oneshot(get("/api/v1/search?q=hi")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        std::fs::create_dir_all(work.path().join(MESSAGE_DB_DIR)).unwrap();
+        let response = router(state.clone()).oneshot(get("/api/v1/search?q=hi")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let results: SearchResults = serde_json::from_slice(&body).unwrap();
+        assert!(results.messages.is_empty() && results.contacts.is_empty());
+
+        let response = router(state).oneshot(get("/api/v1/search")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,171 @@
+//! 临时解密会话接口
+//!
+//! - `POST /api/v1/session/unlock`：使用密钥解锁，将选定的数据库解密到临时目录
+//! - `GET /api/v1/session`：查询当前会话
+//! - `POST /api/v1/session/lock`：锁定会话并清除临时数据
+//!
+//! 服务退出时会话自动锁定。
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use ts_rs::TS;
+
+use super::jobs::{resolve_backup, BackupRequest};
+use super::{ApiError, ServerState};
+use mwxdump_core::wechat::datadir::DbCategory;
+use mwxdump_core::wechat::decrypt::DecryptedSession;
+
+/// 解锁请求，未提供的字段依次取账号配置和全局配置
+#[derive(Debug, Default, Deserialize, TS)]
+#[ts(export, optional_fields)]
+pub struct UnlockRequest {
+    /// 账号配置名称
+    pub account: Option<String>,
+    /// 微信数据目录
+    pub input: Option<PathBuf>,
+    /// 数据密钥（十六进制）
+    pub key: Option<String>,
+    /// 只解密这些类别的数据库
+    #[serde(default)]
+    #[ts(optional, as = "Option<_>")]
+    pub only: Vec<DbCategory>,
+    /// 并发线程数
+    pub threads: Option<usize>,
+}
+
+/// 会话信息
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SessionInfo {
+    /// 解锁时间
+    pub unlocked_at: DateTime<Utc>,
+    /// 已解密的数据库类别，为空表示全部
+    pub categories: Vec<DbCategory>,
+}
+
+impl From<&DecryptedSession> for SessionInfo {
+    fn from(session: &DecryptedSession) -> Self {
+        Self {
+            unlocked_at: session.unlocked_at(),
+            categories: session.categories().to_vec(),
+        }
+    }
+}
+
+/// 解锁会话，新会话解密完成后替换旧会话并清除旧数据
+///
+/// 解密期间不持有会话锁，其他接口仍可读取旧会话；服务退出时解密随之取消。
+pub async fn unlock(
+    State(state): State<ServerState>,
+    Json(request): Json<UnlockRequest>,
+) -> Result<Json<SessionInfo>, ApiError> {
+    let plan = resolve_backup(
+        &state.config,
+        BackupRequest {
+            account: request.account,
+            input: request.input,
+            output: None,
+            key: request.key,
+            only: request.only,
+            threads: request.threads,
+            snapshot: false,
+        },
+    )?;
+
+    let session = DecryptedSession::unlock(&plan.input, plan.key, plan.only, plan.threads, state.shutdown.clone())
+        .await
+        .map_err(|e| ApiError::bad_request(format!("解锁失败: {}", e)))?;
+    let info = SessionInfo::from(&session);
+    let previous = state.session.write().await.replace(session);
+    state.cache.invalidate_all();
+    if let Some(previous) = previous {
+        previous.lock().await;
+    }
+    Ok(Json(info))
+}
+
+/// 查询当前会话
+pub async fn get_session(State(state): State<ServerState>) -> Result<Json<SessionInfo>, ApiError> {
+    state
+        .session
+        .read()
+        .await
+        .as_ref()
+        .map(|session| Json(SessionInfo::from(session)))
+        .ok_or_else(|| ApiError::not_found("session"))
+}
+
+/// 锁定会话并清除临时数据
+pub async fn lock(State(state): State<ServerState>) -> StatusCode {
+    let session = state.session.write().await.take();
+    state.cache.invalidate_all();
+    if let Some(session) = session {
+        session.lock().await;
+    }
+    StatusCode::NO_CONTENT
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::AppConfig;
+    use crate::server::{router, ServerState};
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_ephemeral_session_lifecycle() {
+        let input = tempfile::tempdir().unwrap();
+        let work = tempfile::tempdir().unwrap();
+        std::fs::write(work.path().join("persistent.db"), b"plain").unwrap();
+
+        let mut config = AppConfig::default();
+        config.database.work_dir = work.path().to_path_buf();
+        config.wechat.data_dir = Some(input.path().to_path_buf());
+        config.wechat.data_key = Some("00".repeat(32));
+        let state = ServerState::new(config).with_ephemeral(true);
+
+        let send = |method: &str, uri: &str, body: &str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let response = router(state.clone()).oneshot(send("GET", "/api/v1/session", "")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = router(state.clone()).oneshot(send("POST", "/api/v1/jobs/backup", "{}")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = router(state.clone())
+            .oneshot(send("POST", "/api/v1/session/unlock", r#"{"only":["contact"]}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let session_dir = state.session.read().await.as_ref().unwrap().path().to_path_buf();
+        std::fs::write(session_dir.join("decrypted_contact.db"), b"contact").unwrap();
+
+        let response = router(state.clone())
+            .oneshot(send("GET", "/api/v1/files/decrypted_contact.db", ""))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = router(state.clone()).oneshot(send("GET", "/api/v1/files/persistent.db", "")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = router(state.clone()).oneshot(send("POST", "/api/v1/session/lock", "")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(!session_dir.exists());
+
+        let response = router(state).oneshot(send("GET", "/api/v1/session", "")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
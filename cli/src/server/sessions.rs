@@ -0,0 +1,114 @@
+//! 会话列表接口
+//!
+//! `GET /api/v1/sessions?limit=50`：按最后消息时间从新到旧分页列出会话，附带联系人名称。
+//! 完整的会话列表经查询缓存保存，前端轮询时不必每次读取数据库。
+
+use axum::extract::{Query, State};
+use axum::Json;
+use serde::Deserialize;
+use ts_rs::TS;
+
+use super::files::allowed_roots;
+use super::{cached, parse_cursor, ApiError, ServerState};
+use mwxdump_core::models::{Page, Session};
+use mwxdump_core::wechat::db::contacts::ContactRepository;
+use mwxdump_core::wechat::db::sessions::{SessionRepository, SESSION_DB_PATH};
+use mwxdump_core::wechat::db::QueryKey;
+
+/// 会话列表默认每页条数
+pub const DEFAULT_SESSION_PAGE: usize = 50;
+
+/// 会话列表每页最多条数
+pub const MAX_SESSION_PAGE: usize = 500;
+
+/// 会话列表参数
+#[derive(Debug, Default, Deserialize, TS)]
+#[ts(export, optional_fields)]
+pub struct SessionListParams {
+    /// 上一页返回的 `next_cursor`
+    pub cursor: Option<String>,
+    /// 每页条数
+    pub limit: Option<usize>,
+}
+
+/// 列出最近会话
+pub async fn list_sessions(
+    State(state): State<ServerState>,
+    Query(params): Query<SessionListParams>,
+) -> Result<Json<Page<Session>>, ApiError> {
+    let cursor = parse_cursor(params.cursor.as_deref())?;
+    let limit = params.limit.unwrap_or(DEFAULT_SESSION_PAGE).clamp(1, MAX_SESSION_PAGE);
+    let sessions: Vec<Session> = cached(&state, QueryKey::SessionList, || load_sessions(&state)).await?;
+    let page = Page::from_offset(sessions, cursor, limit).map_err(|e| ApiError::bad_request(e.to_string()))?;
+    Ok(Json(page))
+}
+
+/// 从第一个包含会话数据库的允许目录读取会话，并用联系人名称补全
+async fn load_sessions(state: &ServerState) -> Result<Vec<Session>, ApiError> {
+    let root = allowed_roots(state)
+        .await
+        .into_iter()
+        .find(|root| root.join(SESSION_DB_PATH).is_file())
+        .ok_or_else(|| ApiError::not_found(SESSION_DB_PATH))?;
+    let mut sessions = SessionRepository::open(&root)
+        .await
+        .map_err(|e| ApiError::bad_request(e.to_string()))?;
+    // 联系人数据库可能未解密，此时只返回用户名
+    if let Ok(contacts) = ContactRepository::open(&root).await {
+        sessions.fill_names(&contacts);
+    }
+    Ok(sessions.into_sessions())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::server::router;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use sqlx::sqlite::SqliteConnectOptions;
+    use sqlx::ConnectOptions;
+    use tower::ServiceExt;
+
+    async fn execute(path: &std::path::Path, sql: &str) {
+        let mut conn = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true)
+            .connect()
+            .await
+            .unwrap();
+        sqlx::query(sql).execute(&mut conn).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_session_list_is_cached_until_refresh() {
+        let work = tempfile::tempdir().unwrap();
+        let mut config = AppConfig::default();
+        config.database.work_dir = work.path().to_path_buf();
+        let state = ServerState::new(config);
+        let list = || async {
+            let request = Request::get("/api/v1/sessions").body(Body::empty()).unwrap();
+            let response = router(state.clone()).oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let page: Page<Session> = serde_json::from_slice(&body).unwrap();
+            page.items.into_iter().map(|s| s.username).collect::<Vec<_>>()
+        };
+
+        let db = work.path().join(SESSION_DB_PATH);
+        std::fs::create_dir_all(db.parent().unwrap()).unwrap();
+        execute(&db, "CREATE TABLE SessionTable (username TEXT, unread_count INTEGER, sort_timestamp INTEGER)").await;
+        execute(&db, "INSERT INTO SessionTable VALUES ('wxid_a', 0, 1700000000)").await;
+        assert_eq!(list().await, ["wxid_a"]);
+
+        // 第二次请求命中缓存，不会读到新写入的会话
+        execute(&db, "INSERT INTO SessionTable VALUES ('wxid_b', 1, 1700000009)").await;
+        assert_eq!(list().await, ["wxid_a"]);
+        assert_eq!(state.cache.stats(), (1, 1));
+
+        // 自动刷新后缓存失效
+        state.data_updated(vec![SESSION_DB_PATH.into()]);
+        assert_eq!(list().await, ["wxid_b", "wxid_a"]);
+    }
+}
@@ -0,0 +1,154 @@
+//! 备份目录
+//!
+//! 备份根目录下的 `backups.json` 记录每次备份的时间、账号、大小、消息数和密钥指纹，
+//! 供历史查看、差异对比和保留策略使用。密钥只保存 SHA-256 指纹，不保存密钥本身。
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+use crate::errors::Result;
+use crate::wechat::datadir::DbCatalog;
+use crate::wechat::db::messages::{MessageRepository, MESSAGE_DB_DIR};
+
+/// 备份根目录中的目录文件名
+pub const CATALOG_FILE: &str = "backups.json";
+
+/// 单次备份记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupRecord {
+    /// 相对备份根目录的路径，直接写入根目录时为 `.`
+    pub name: String,
+    /// 备份完成时间
+    pub created_at: DateTime<Utc>,
+    /// 微信ID
+    pub wxid: Option<String>,
+    /// 数据库总大小（字节）
+    pub size: u64,
+    /// 数据库文件数
+    pub databases: usize,
+    /// 消息总数，未备份消息数据库时为 `None`
+    pub messages: Option<u64>,
+    /// 密钥指纹（SHA-256 前 8 字节）
+    pub key_fingerprint: String,
+    /// 快照是否已用主密钥加密
+    #[serde(default)]
+    pub encrypted: bool,
+    /// 快照清单是否已签名
+    #[serde(default)]
+    pub signed: bool,
+}
+
+impl BackupRecord {
+    /// 统计备份目录的大小和消息数
+    pub async fn capture(root: &Path, backup: &Path, wxid: Option<String>, key: &[u8]) -> Result<Self> {
+        let catalog = DbCatalog::scan(backup)?;
+        let messages = match backup.join(MESSAGE_DB_DIR).is_dir() {
+            true => Some(MessageRepository::open(backup).await?.count_all().await?),
+            false => None,
+        };
+        let name = backup.strip_prefix(root).unwrap_or(backup).to_string_lossy().replace('\\', "/");
+        Ok(Self {
+            name: if name.is_empty() { ".".to_string() } else { name },
+            created_at: Utc::now(),
+            wxid,
+            size: catalog.total_size(),
+            databases: catalog.entries.len(),
+            messages,
+            key_fingerprint: key_fingerprint(key),
+            encrypted: false,
+            signed: false,
+        })
+    }
+
+    /// 备份所在路径
+    pub fn path(&self, root: &Path) -> PathBuf {
+        root.join(&self.name)
+    }
+}
+
+/// 备份目录
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupCatalog {
+    /// 备份记录，按时间从旧到新
+    pub backups: Vec<BackupRecord>,
+}
+
+impl BackupCatalog {
+    /// 读取备份根目录的目录文件，不存在时返回空目录
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = root.join(CATALOG_FILE);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// 写入目录文件，先写临时文件再替换
+    pub fn save(&self, root: &Path) -> Result<()> {
+        std::fs::create_dir_all(root)?;
+        let temp = root.join(format!("{}.tmp", CATALOG_FILE));
+        std::fs::write(&temp, serde_json::to_vec_pretty(self)?)?;
+        std::fs::rename(temp, root.join(CATALOG_FILE))?;
+        Ok(())
+    }
+
+    /// 追加一条记录并保存
+    pub fn append(root: &Path, record: BackupRecord) -> Result<()> {
+        let mut catalog = Self::load(root)?;
+        catalog.backups.push(record);
+        catalog.save(root)
+    }
+
+    /// 移除备份目录已不存在的记录并保存，返回移除的条数
+    pub fn forget_missing(root: &Path) -> Result<usize> {
+        let mut catalog = Self::load(root)?;
+        let before = catalog.backups.len();
+        catalog.backups.retain(|record| record.path(root).exists());
+        let removed = before - catalog.backups.len();
+        if removed > 0 {
+            catalog.save(root)?;
+        }
+        Ok(removed)
+    }
+}
+
+/// 密钥指纹，用于区分不同密钥产生的备份
+pub fn key_fingerprint(key: &[u8]) -> String {
+    hex::encode(&Sha256::digest(key)[..8])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wechat::db::messages::tests::create_message_dbs;
+
+    #[tokio::test]
+    async fn test_record_and_forget_backups() {
+        let root = tempfile::tempdir().unwrap();
+        let snapshot = root.path().join("20240501-093000");
+        create_message_dbs(&snapshot).await;
+        let key = [7u8; 32];
+
+        let record = BackupRecord::capture(root.path(), &snapshot, Some("wxid_self".to_string()), &key)
+            .await
+            .unwrap();
+        assert_eq!(record.name, "20240501-093000");
+        assert_eq!(record.databases, 2);
+        assert_eq!(record.messages, Some(4));
+        assert_eq!(record.key_fingerprint, key_fingerprint(&key));
+        assert_eq!(record.key_fingerprint.len(), 16);
+        BackupCatalog::append(root.path(), record).unwrap();
+
+        let flat = BackupRecord::capture(root.path(), root.path(), None, &key).await.unwrap();
+        assert_eq!(flat.name, ".");
+        assert_eq!(flat.messages, None);
+
+        assert_eq!(BackupCatalog::load(root.path()).unwrap().backups.len(), 1);
+        assert_eq!(BackupCatalog::forget_missing(root.path()).unwrap(), 0);
+        std::fs::remove_dir_all(&snapshot).unwrap();
+        assert_eq!(BackupCatalog::forget_missing(root.path()).unwrap(), 1);
+        assert!(BackupCatalog::load(root.path()).unwrap().backups.is_empty());
+    }
+}
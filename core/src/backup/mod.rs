@@ -0,0 +1,85 @@
+//! 备份快照
+//!
+//! 以快照方式备份时，每次备份写入备份根目录下以时间命名的子目录（如 `20240501-093000`），
+//! 归档文件同样以时间开头（如 `20240501-093000.zip`）。保留策略据此识别历史快照并清理，
+//! 每次备份的统计信息记录在根目录的 `backups.json` 中。快照可用主密钥加密，见 [`vault`]；
+//! 快照的文件清单可用 ed25519 签名，见 [`signing`]。
+
+pub mod catalog;
+pub mod retention;
+pub mod signing;
+pub mod task;
+pub mod vault;
+
+pub use catalog::{BackupCatalog, BackupRecord};
+pub use retention::{RetentionPlan, RetentionPolicy};
+pub use task::{BackupOutcome, BackupTask};
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::errors::Result;
+
+/// 快照名称中的时间格式
+pub const SNAPSHOT_NAME_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// 快照名称中时间部分的长度
+const SNAPSHOT_NAME_LEN: usize = 15;
+
+/// 历史快照（目录或归档文件）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// 快照路径
+    pub path: PathBuf,
+    /// 快照时间（本地时间）
+    pub created: NaiveDateTime,
+}
+
+/// 指定时间的快照名称
+pub fn snapshot_name(at: NaiveDateTime) -> String {
+    at.format(SNAPSHOT_NAME_FORMAT).to_string()
+}
+
+/// 在备份根目录下为当前时间创建快照路径（不创建目录）
+pub fn new_snapshot_path(root: &Path) -> PathBuf {
+    root.join(snapshot_name(chrono::Local::now().naive_local()))
+}
+
+/// 列出备份根目录下的快照，按时间从新到旧排序；名称不以时间开头的条目忽略
+pub fn list_snapshots(root: &Path) -> Result<Vec<Snapshot>> {
+    let mut snapshots = Vec::new();
+    for entry in std::fs::read_dir(root)? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let Some(created) = name
+            .get(..SNAPSHOT_NAME_LEN)
+            .and_then(|prefix| NaiveDateTime::parse_from_str(prefix, SNAPSHOT_NAME_FORMAT).ok())
+        else {
+            continue;
+        };
+        snapshots.push(Snapshot { path, created });
+    }
+    snapshots.sort_by_key(|s| std::cmp::Reverse(s.created));
+    Ok(snapshots)
+}
+
+/// 递归列出目录中的文件，按路径排序
+pub(crate) fn collect_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in std::fs::read_dir(&current)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
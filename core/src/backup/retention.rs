@@ -0,0 +1,143 @@
+//! 快照保留策略
+//!
+//! 保留最近 N 个快照，以及最近 M 周内每周最新的一个快照，其余快照删除。
+//! 两项都未设置时不清理；最新的快照始终保留。
+
+use chrono::{Datelike, Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+use ts_rs::TS;
+
+use super::{list_snapshots, BackupCatalog, Snapshot};
+use crate::errors::Result;
+
+/// 保留策略
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    /// 保留最近的快照数
+    pub keep_last: Option<usize>,
+    /// 保留最近若干周内每周最新的快照
+    pub keep_weekly: Option<usize>,
+}
+
+/// 清理计划
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPlan {
+    /// 保留的快照
+    pub keep: Vec<Snapshot>,
+    /// 将删除的快照
+    pub prune: Vec<Snapshot>,
+}
+
+impl RetentionPolicy {
+    /// 是否设置了任何保留规则
+    pub fn is_enabled(&self) -> bool {
+        self.keep_last.is_some() || self.keep_weekly.is_some()
+    }
+
+    /// 计算清理计划，`snapshots` 需按时间从新到旧排序
+    pub fn plan(&self, snapshots: Vec<Snapshot>, now: NaiveDateTime) -> RetentionPlan {
+        if !self.is_enabled() {
+            return RetentionPlan {
+                keep: snapshots,
+                prune: Vec::new(),
+            };
+        }
+
+        let weekly_since = self.keep_weekly.map(|weeks| now - Duration::weeks(weeks as i64));
+        let mut weeks_seen = HashSet::new();
+        let mut plan = RetentionPlan::default();
+        for (index, snapshot) in snapshots.into_iter().enumerate() {
+            let by_last = index == 0 || self.keep_last.is_some_and(|n| index < n);
+            let week = snapshot.created.iso_week();
+            let by_week = weekly_since.is_some_and(|since| snapshot.created >= since)
+                && weeks_seen.insert((week.year(), week.week()));
+            if by_last || by_week {
+                plan.keep.push(snapshot);
+            } else {
+                plan.prune.push(snapshot);
+            }
+        }
+        plan
+    }
+
+    /// 按策略清理备份根目录，`dry_run` 时只返回计划
+    pub fn enforce(&self, root: &Path, dry_run: bool) -> Result<RetentionPlan> {
+        let plan = self.plan(list_snapshots(root)?, chrono::Local::now().naive_local());
+        if !dry_run {
+            plan.apply()?;
+            BackupCatalog::forget_missing(root)?;
+        }
+        Ok(plan)
+    }
+}
+
+impl RetentionPlan {
+    /// 删除计划中的快照
+    pub fn apply(&self) -> Result<()> {
+        for snapshot in &self.prune {
+            if snapshot.path.is_dir() {
+                std::fs::remove_dir_all(&snapshot.path)?;
+            } else {
+                std::fs::remove_file(&snapshot.path)?;
+            }
+            tracing::info!("已删除过期快照: {:?}", snapshot.path);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::snapshot_name;
+    use chrono::NaiveDate;
+
+    fn at(day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 5, day).unwrap().and_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_plan_keeps_last_and_weekly() {
+        let root = tempfile::tempdir().unwrap();
+        // 2024-05-01 至 05-29 每周三两次，外加一个归档和一个无关目录
+        for day in [1, 8, 15, 22, 29] {
+            for hour in [9, 21] {
+                std::fs::create_dir(root.path().join(snapshot_name(at(day, hour)))).unwrap();
+            }
+        }
+        std::fs::write(root.path().join(format!("{}.zip", snapshot_name(at(2, 0)))), b"zip").unwrap();
+        std::fs::create_dir(root.path().join("notes")).unwrap();
+
+        let snapshots = list_snapshots(root.path()).unwrap();
+        assert_eq!(snapshots.len(), 11);
+        assert_eq!(snapshots[0].created, at(29, 21));
+
+        let now = at(30, 0);
+        let policy = RetentionPolicy {
+            keep_last: Some(3),
+            keep_weekly: Some(3),
+        };
+        let plan = policy.plan(snapshots.clone(), now);
+        let kept: Vec<_> = plan.keep.iter().map(|s| s.created).collect();
+        // 最近 3 个，加上 05-15 所在周最新的快照（05-08 及更早超出 3 周）
+        assert_eq!(kept, [at(29, 21), at(29, 9), at(22, 21), at(15, 21)]);
+        assert_eq!(plan.prune.len(), 7);
+
+        let plan = RetentionPolicy::default().plan(snapshots, now);
+        assert!(plan.prune.is_empty());
+
+        let plan = RetentionPolicy {
+            keep_last: Some(0),
+            keep_weekly: None,
+        }
+        .enforce(root.path(), false)
+        .unwrap();
+        assert_eq!(plan.keep.len(), 1);
+        assert_eq!(list_snapshots(root.path()).unwrap().len(), 1);
+        assert!(root.path().join("notes").exists());
+    }
+}
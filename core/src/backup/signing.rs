@@ -0,0 +1,462 @@
+//! 快照清单与签名
+//!
+//! 快照完成后在快照目录写入 `manifest.json`，记录每个文件的大小和 BLAKE3 哈希；启用签名时再用
+//! ed25519 私钥对清单原文签名，写入 `manifest.sig`。私钥由主密钥加密保存在备份根目录的
+//! `signing.json` 中，公钥以明文保存并附主密钥计算的 HMAC。
+//!
+//! 校验时先验证签名，再逐个比较文件；签名只证明清单出自持有私钥的人，校验时应使用事先记录的公钥
+//! （`--public-key`），或用主密钥认证过的 `signing.json` 中的公钥（[`SigningKeyFile::trusted_public_key`]），
+//! 而不是签名文件自带的公钥。`signing.json` 与快照放在一起，能改动快照的人也能替换其中的公钥，
+//! 未经认证时不可信。
+
+use chrono::{DateTime, Utc};
+use hmac::Mac;
+use ring::rand::SystemRandom;
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use zeroize::Zeroize;
+
+use super::catalog::key_fingerprint;
+use super::collect_files;
+use super::vault::{self, SecretKey};
+use crate::errors::{Result, VaultError};
+use crate::wechat::decrypt::manifest::hash_file;
+
+/// 快照目录中的清单文件
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// 快照目录中的签名文件
+pub const SIGNATURE_FILE: &str = "manifest.sig";
+
+/// 备份根目录中的签名密钥文件
+pub const SIGNING_KEY_FILE: &str = "signing.json";
+
+/// 清单和签名文件的格式版本
+const MANIFEST_VERSION: u32 = 1;
+
+/// 认证签名公钥的子密钥标签
+const PUBLIC_KEY_MAC_LABEL: &[u8] = b"mwxdump-signing-public";
+
+/// 快照清单
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    /// 格式版本
+    pub version: u32,
+    /// 生成时间
+    pub created_at: DateTime<Utc>,
+    /// 相对快照目录的路径（以 `/` 分隔）到文件信息
+    pub files: BTreeMap<String, ManifestEntry>,
+}
+
+/// 清单中的文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// 文件大小（字节）
+    pub size: u64,
+    /// BLAKE3 哈希（十六进制）
+    pub blake3: String,
+}
+
+impl SnapshotManifest {
+    /// 统计快照目录中的文件，清单和签名文件除外
+    pub fn build(dir: &Path) -> Result<Self> {
+        let mut files = BTreeMap::new();
+        for path in collect_files(dir)? {
+            let Some(relative) = manifest_key(dir, &path) else {
+                continue;
+            };
+            let entry = ManifestEntry {
+                size: std::fs::metadata(&path)?.len(),
+                blake3: hash_file(&path)?,
+            };
+            files.insert(relative, entry);
+        }
+        Ok(Self {
+            version: MANIFEST_VERSION,
+            created_at: Utc::now(),
+            files,
+        })
+    }
+}
+
+/// 清单签名
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestSignature {
+    /// 格式版本
+    pub version: u32,
+    /// 签名公钥（十六进制）
+    pub public_key: String,
+    /// 对 `manifest.json` 原文的 ed25519 签名（十六进制）
+    pub signature: String,
+}
+
+/// 备份根目录的签名密钥文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SigningKeyFile {
+    /// 格式版本
+    pub version: u32,
+    /// 公钥（十六进制）
+    pub public_key: String,
+    /// 由主密钥加密的 PKCS#8 私钥（十六进制）
+    pub wrapped_key: String,
+    /// 主密钥对公钥的 HMAC（十六进制），旧版本生成的文件没有，下次签名时补上
+    #[serde(default)]
+    pub public_key_mac: String,
+}
+
+impl SigningKeyFile {
+    /// 读取备份根目录的签名密钥文件，不存在时返回 `None`
+    pub fn load(root: &Path) -> Result<Option<Self>> {
+        let path = root.join(SIGNING_KEY_FILE);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&std::fs::read_to_string(path)?)?))
+    }
+
+    /// 用主密钥认证公钥，通过后返回公钥（十六进制）
+    ///
+    /// 公钥被替换或 HMAC 缺失时返回 `VaultError::UntrustedSigningKey`。
+    pub fn trusted_public_key(&self, master: &SecretKey) -> Result<String> {
+        let untrusted = || VaultError::UntrustedSigningKey(SIGNING_KEY_FILE.to_string());
+        let (Ok(public_key), Ok(mac)) = (hex::decode(&self.public_key), hex::decode(&self.public_key_mac)) else {
+            return Err(untrusted().into());
+        };
+        vault::authenticate(master, PUBLIC_KEY_MAC_LABEL, &public_key)
+            .verify_slice(&mac)
+            .map_err(|_| untrusted())?;
+        Ok(self.public_key.clone())
+    }
+
+    fn save(&self, root: &Path) -> Result<()> {
+        std::fs::create_dir_all(root)?;
+        std::fs::write(root.join(SIGNING_KEY_FILE), serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// ed25519 签名密钥
+pub struct SigningKey {
+    pair: Ed25519KeyPair,
+}
+
+impl SigningKey {
+    /// 用主密钥解开备份根目录的签名密钥，不存在时生成并保存
+    ///
+    /// 明文公钥与私钥不符时拒绝使用；公钥缺少 HMAC（旧版本生成的文件）时补上。
+    pub fn load_or_create(root: &Path, master: &SecretKey) -> Result<Self> {
+        if let Some(mut file) = SigningKeyFile::load(root)? {
+            let mut pkcs8 = vault::open(master, b"signing", &hex::decode(&file.wrapped_key)?)
+                .map_err(|_| VaultError::Corrupted(format!("{} 不是由当前主密钥加密的", SIGNING_KEY_FILE)))?;
+            let pair = Ed25519KeyPair::from_pkcs8(&pkcs8);
+            pkcs8.zeroize();
+            let pair = pair.map_err(|e| VaultError::Corrupted(format!("{}: {}", SIGNING_KEY_FILE, e)))?;
+            let key = Self { pair };
+            if !file.public_key.eq_ignore_ascii_case(&key.public_key()) {
+                return Err(VaultError::UntrustedSigningKey(SIGNING_KEY_FILE.to_string()).into());
+            }
+            if file.trusted_public_key(master).is_err() {
+                file.public_key_mac = public_key_mac(master, &key.pair);
+                file.save(root)?;
+            }
+            return Ok(key);
+        }
+
+        let document = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+            .map_err(|_| VaultError::EncryptionFailed("无法生成签名密钥".to_string()))?;
+        let pair = Ed25519KeyPair::from_pkcs8(document.as_ref())
+            .map_err(|e| VaultError::EncryptionFailed(format!("无法生成签名密钥: {}", e)))?;
+        let file = SigningKeyFile {
+            version: MANIFEST_VERSION,
+            public_key: hex::encode(pair.public_key().as_ref()),
+            wrapped_key: hex::encode(vault::seal(master, b"signing", document.as_ref())?),
+            public_key_mac: public_key_mac(master, &pair),
+        };
+        file.save(root)?;
+        Ok(Self { pair })
+    }
+
+    /// 公钥（十六进制）
+    pub fn public_key(&self) -> String {
+        hex::encode(self.pair.public_key().as_ref())
+    }
+
+    /// 签名
+    pub fn sign(&self, message: &[u8]) -> ManifestSignature {
+        ManifestSignature {
+            version: MANIFEST_VERSION,
+            public_key: self.public_key(),
+            signature: hex::encode(self.pair.sign(message).as_ref()),
+        }
+    }
+}
+
+/// 主密钥对签名公钥的 HMAC（十六进制）
+fn public_key_mac(master: &SecretKey, pair: &Ed25519KeyPair) -> String {
+    let mac = vault::authenticate(master, PUBLIC_KEY_MAC_LABEL, pair.public_key().as_ref());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// 公钥指纹，与密钥指纹的算法相同
+pub fn public_key_fingerprint(public_key: &str) -> String {
+    key_fingerprint(&hex::decode(public_key).unwrap_or_default())
+}
+
+/// 写入快照清单，提供签名密钥时同时写入签名
+pub fn write_manifest(dir: &Path, signer: Option<&SigningKey>) -> Result<SnapshotManifest> {
+    let manifest = SnapshotManifest::build(dir)?;
+    let bytes = serde_json::to_vec_pretty(&manifest)?;
+    std::fs::write(dir.join(MANIFEST_FILE), &bytes)?;
+    if let Some(signer) = signer {
+        std::fs::write(dir.join(SIGNATURE_FILE), serde_json::to_vec_pretty(&signer.sign(&bytes))?)?;
+    }
+    Ok(manifest)
+}
+
+/// 签名校验结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SignatureStatus {
+    /// 未要求校验签名
+    NotChecked,
+    /// 快照没有签名
+    Unsigned,
+    /// 签名有效；`trusted` 为 `false` 时只能说明清单与签名文件自带的公钥相符，校验不通过
+    Valid { fingerprint: String, trusted: bool },
+    /// 签名无效或公钥与信任的公钥不符
+    Invalid { reason: String },
+}
+
+/// 快照校验结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifyReport {
+    /// 清单中的文件数
+    pub files: usize,
+    /// 清单中有、快照中缺失的文件
+    pub missing: Vec<String>,
+    /// 大小或哈希与清单不符的文件
+    pub modified: Vec<String>,
+    /// 快照中有、清单中没有的文件
+    pub extra: Vec<String>,
+    /// 签名校验结果
+    pub signature: SignatureStatus,
+}
+
+impl VerifyReport {
+    /// 文件与清单一致，且要求校验签名时签名有效并来自信任的公钥
+    ///
+    /// 只与签名文件自带的公钥相符的签名不算通过：改动快照的人可以用自己的密钥重新签名。
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty()
+            && self.modified.is_empty()
+            && self.extra.is_empty()
+            && matches!(
+                self.signature,
+                SignatureStatus::NotChecked | SignatureStatus::Valid { trusted: true, .. }
+            )
+    }
+}
+
+/// 按清单校验快照；`check_signature` 时同时校验签名，`trusted_key` 为信任的公钥（十六进制）
+pub fn verify_snapshot(dir: &Path, check_signature: bool, trusted_key: Option<&str>) -> Result<VerifyReport> {
+    let path = dir.join(MANIFEST_FILE);
+    if !path.is_file() {
+        return Err(VaultError::Corrupted(format!("快照没有清单: {}", path.display())).into());
+    }
+    let bytes = std::fs::read(&path)?;
+    let signature = match check_signature {
+        true => check(dir, &bytes, trusted_key)?,
+        false => SignatureStatus::NotChecked,
+    };
+    let manifest: SnapshotManifest = serde_json::from_slice(&bytes)?;
+    let current = SnapshotManifest::build(dir)?;
+
+    let mut report = VerifyReport {
+        files: manifest.files.len(),
+        missing: Vec::new(),
+        modified: Vec::new(),
+        extra: Vec::new(),
+        signature,
+    };
+    for (name, entry) in &manifest.files {
+        match current.files.get(name) {
+            None => report.missing.push(name.clone()),
+            Some(actual) if actual != entry => report.modified.push(name.clone()),
+            Some(_) => {}
+        }
+    }
+    report.extra = current
+        .files
+        .keys()
+        .filter(|name| !manifest.files.contains_key(*name))
+        .cloned()
+        .collect();
+    Ok(report)
+}
+
+/// 校验清单原文的签名
+fn check(dir: &Path, manifest: &[u8], trusted_key: Option<&str>) -> Result<SignatureStatus> {
+    let path = dir.join(SIGNATURE_FILE);
+    if !path.is_file() {
+        return Ok(SignatureStatus::Unsigned);
+    }
+    let signature: ManifestSignature = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    let invalid = |reason: &str| SignatureStatus::Invalid {
+        reason: reason.to_string(),
+    };
+    if trusted_key.is_some_and(|key| !key.trim().eq_ignore_ascii_case(&signature.public_key)) {
+        return Ok(invalid("签名公钥与信任的公钥不符"));
+    }
+    let (Ok(public_key), Ok(bytes)) = (hex::decode(&signature.public_key), hex::decode(&signature.signature)) else {
+        return Ok(invalid("签名文件格式错误"));
+    };
+    if UnparsedPublicKey::new(&ED25519, &public_key).verify(manifest, &bytes).is_err() {
+        return Ok(invalid("清单已被修改或签名无效"));
+    }
+    Ok(SignatureStatus::Valid {
+        fingerprint: public_key_fingerprint(&signature.public_key),
+        trusted: trusted_key.is_some(),
+    })
+}
+
+/// 清单中的路径键，清单和签名文件返回 `None`
+fn manifest_key(dir: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(dir).ok()?;
+    let key = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    (key != MANIFEST_FILE && key != SIGNATURE_FILE).then_some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signed_manifest_detects_tampering() {
+        let root = tempfile::tempdir().unwrap();
+        let snapshot = root.path().join("20240501-093000");
+        std::fs::create_dir_all(snapshot.join("db_storage")).unwrap();
+        std::fs::write(snapshot.join("db_storage/contact.db"), b"contacts").unwrap();
+        std::fs::write(snapshot.join("backup.log"), b"log").unwrap();
+
+        let master = SecretKey::generate().unwrap();
+        let signer = SigningKey::load_or_create(root.path(), &master).unwrap();
+        let public_key = signer.public_key();
+        assert_eq!(SigningKey::load_or_create(root.path(), &master).unwrap().public_key(), public_key);
+        assert!(SigningKey::load_or_create(root.path(), &SecretKey::generate().unwrap()).is_err());
+
+        let manifest = write_manifest(&snapshot, Some(&signer)).unwrap();
+        assert_eq!(manifest.files.keys().collect::<Vec<_>>(), ["backup.log", "db_storage/contact.db"]);
+        let report = verify_snapshot(&snapshot, true, Some(&public_key)).unwrap();
+        assert!(report.is_ok());
+        assert_eq!(
+            report.signature,
+            SignatureStatus::Valid {
+                fingerprint: public_key_fingerprint(&public_key),
+                trusted: true
+            }
+        );
+
+        // 修改文件
+        std::fs::write(snapshot.join("db_storage/contact.db"), b"CONTACTS").unwrap();
+        std::fs::write(snapshot.join("extra.db"), b"").unwrap();
+        let report = verify_snapshot(&snapshot, false, None).unwrap();
+        assert_eq!((report.modified.len(), report.extra.len()), (1, 1));
+        assert_eq!(report.signature, SignatureStatus::NotChecked);
+        assert!(!report.is_ok());
+
+        // 修改文件后重写清单，签名失效
+        let rewritten = SnapshotManifest::build(&snapshot).unwrap();
+        std::fs::write(snapshot.join(MANIFEST_FILE), serde_json::to_vec_pretty(&rewritten).unwrap()).unwrap();
+        let report = verify_snapshot(&snapshot, true, None).unwrap();
+        assert!(report.modified.is_empty() && matches!(report.signature, SignatureStatus::Invalid { .. }));
+
+        // 用另一个密钥重新签名，与信任的公钥不符
+        let other = SigningKey::load_or_create(&root.path().join("other"), &master).unwrap();
+        write_manifest(&snapshot, Some(&other)).unwrap();
+        assert!(matches!(
+            verify_snapshot(&snapshot, true, None).unwrap().signature,
+            SignatureStatus::Valid { trusted: false, .. }
+        ));
+        assert!(!verify_snapshot(&snapshot, true, Some(&public_key)).unwrap().is_ok());
+
+        std::fs::remove_file(snapshot.join(SIGNATURE_FILE)).unwrap();
+        assert_eq!(verify_snapshot(&snapshot, true, None).unwrap().signature, SignatureStatus::Unsigned);
+    }
+
+    #[test]
+    fn test_swapped_signing_key_is_rejected() {
+        let root = tempfile::tempdir().unwrap();
+        let snapshot = root.path().join("20240501-093000");
+        std::fs::create_dir_all(&snapshot).unwrap();
+        std::fs::write(snapshot.join("contact.db"), b"contacts").unwrap();
+        let master = SecretKey::generate().unwrap();
+        let owner = SigningKey::load_or_create(root.path(), &master).unwrap();
+        write_manifest(&snapshot, Some(&owner)).unwrap();
+        let original = SigningKeyFile::load(root.path()).unwrap().unwrap();
+        assert_eq!(original.trusted_public_key(&master).unwrap(), owner.public_key());
+
+        // 篡改文件，换上自己的 signing.json 并重新签名
+        std::fs::write(snapshot.join("contact.db"), b"forged").unwrap();
+        let attacker = tempfile::tempdir().unwrap();
+        let forged = SigningKey::load_or_create(attacker.path(), &SecretKey::generate().unwrap()).unwrap();
+        std::fs::copy(attacker.path().join(SIGNING_KEY_FILE), root.path().join(SIGNING_KEY_FILE)).unwrap();
+        write_manifest(&snapshot, Some(&forged)).unwrap();
+
+        // 备份根目录中的公钥未经主密钥认证，不能作为信任的公钥
+        let swapped = SigningKeyFile::load(root.path()).unwrap().unwrap();
+        let err = swapped.trusted_public_key(&master).unwrap_err();
+        assert!(matches!(err.downcast_ref::<VaultError>(), Some(VaultError::UntrustedSigningKey(_))));
+        let report = verify_snapshot(&snapshot, true, Some(&owner.public_key())).unwrap();
+        assert!(!report.is_ok());
+
+        // 只替换明文公钥：认证失败，签名时也拒绝使用
+        let mut edited = original.clone();
+        edited.public_key = forged.public_key();
+        edited.save(root.path()).unwrap();
+        assert!(edited.trusted_public_key(&master).is_err());
+        assert!(SigningKey::load_or_create(root.path(), &master).is_err());
+
+        // 旧版本没有 HMAC 的文件不可信，下次签名时补上
+        let legacy = SigningKeyFile {
+            public_key_mac: String::new(),
+            ..original
+        };
+        legacy.save(root.path()).unwrap();
+        assert!(legacy.trusted_public_key(&master).is_err());
+        SigningKey::load_or_create(root.path(), &master).unwrap();
+        let upgraded = SigningKeyFile::load(root.path()).unwrap().unwrap();
+        assert_eq!(upgraded.trusted_public_key(&master).unwrap(), owner.public_key());
+    }
+
+    #[test]
+    fn test_foreign_signature_is_rejected() {
+        let root = tempfile::tempdir().unwrap();
+        let snapshot = root.path().join("20240501-093000");
+        std::fs::create_dir_all(&snapshot).unwrap();
+        std::fs::write(snapshot.join("contact.db"), b"contacts").unwrap();
+        let owner = SigningKey::load_or_create(root.path(), &SecretKey::generate().unwrap()).unwrap();
+        write_manifest(&snapshot, Some(&owner)).unwrap();
+
+        // 篡改文件后用自己的密钥重写清单并重新签名
+        std::fs::write(snapshot.join("contact.db"), b"forged").unwrap();
+        let attacker = tempfile::tempdir().unwrap();
+        let forged = SigningKey::load_or_create(attacker.path(), &SecretKey::generate().unwrap()).unwrap();
+        write_manifest(&snapshot, Some(&forged)).unwrap();
+
+        // 没有信任的公钥时签名只与自带公钥相符，不算通过
+        let report = verify_snapshot(&snapshot, true, None).unwrap();
+        assert!(report.modified.is_empty());
+        assert!(matches!(report.signature, SignatureStatus::Valid { trusted: false, .. }));
+        assert!(!report.is_ok());
+
+        let report = verify_snapshot(&snapshot, true, Some(&owner.public_key())).unwrap();
+        assert!(matches!(report.signature, SignatureStatus::Invalid { .. }));
+        assert!(!report.is_ok());
+    }
+}
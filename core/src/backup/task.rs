@@ -0,0 +1,82 @@
+//! 备份任务
+//!
+//! 一次完整的备份：解密到输出目录（快照模式下为新的时间子目录），记录到 `backups.json`，
+//! 快照备份完成后写入文件清单并按保留策略清理。HTTP 备份任务和 UI 的定时备份共用。
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tokio_util::sync::CancellationToken;
+
+use super::{new_snapshot_path, signing, BackupCatalog, BackupRecord, RetentionPolicy};
+use crate::errors::Result;
+use crate::events::EventBus;
+use crate::wechat::datadir::{self, DbCategory};
+use crate::wechat::decrypt::{DecryptReport, DecryptionProcessor};
+
+/// 备份参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupTask {
+    /// 微信数据目录
+    pub input: PathBuf,
+    /// 备份根目录
+    pub output: PathBuf,
+    /// 数据密钥
+    pub key: Vec<u8>,
+    /// 只备份这些类别的数据库，为空时全部备份
+    #[serde(default)]
+    pub categories: Vec<DbCategory>,
+    /// 并发线程数
+    pub threads: Option<usize>,
+    /// 写入以时间命名的快照子目录
+    #[serde(default)]
+    pub snapshot: bool,
+    /// 快照保留策略
+    #[serde(default)]
+    pub retention: RetentionPolicy,
+}
+
+/// 备份结果，作为备份任务的输出
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupOutcome {
+    /// 记入 `backups.json` 的记录，统计失败时为 `None`
+    pub record: Option<BackupRecord>,
+    /// 解密统计，含内存峰值，可据此调整 `max_memory_mb`
+    pub decrypt: DecryptReport,
+}
+
+impl BackupTask {
+    /// 执行备份，解密进度发布到 `events`
+    pub async fn run(&self, token: CancellationToken, events: EventBus) -> Result<BackupOutcome> {
+        let output = match self.snapshot {
+            true => new_snapshot_path(&self.output),
+            false => self.output.clone(),
+        };
+        let wxid = datadir::scan(&self.input).ok().and_then(|info| info.wxid);
+        let decrypt = DecryptionProcessor::new(self.input.clone(), output.clone(), self.key.clone(), self.threads, false)
+            .with_cancellation(token)
+            .with_categories(self.categories.clone())
+            .with_events(events)
+            .execute()
+            .await?;
+        let record = match BackupRecord::capture(&self.output, &output, wxid, &self.key).await {
+            Ok(record) => {
+                BackupCatalog::append(&self.output, record.clone())?;
+                Some(record)
+            }
+            Err(e) => {
+                tracing::warn!("统计备份信息失败: {}", e);
+                None
+            }
+        };
+        // 后台任务没有主密钥口令，清单不签名
+        if self.snapshot {
+            if let Err(e) = signing::write_manifest(&output, None) {
+                tracing::warn!("写入快照清单失败: {}", e);
+            }
+        }
+        if self.snapshot && self.retention.is_enabled() {
+            self.retention.enforce(&self.output, false)?;
+        }
+        Ok(BackupOutcome { record, decrypt })
+    }
+}
@@ -0,0 +1,570 @@
+//! 备份加密
+//!
+//! 密钥分两层：备份根目录的 `vault.json` 保存随机生成的主密钥，主密钥由口令经 PBKDF2-HMAC-SHA512
+//! 派生的密钥加密；每个加密的快照使用各自随机生成的归档密钥，由主密钥加密后保存在快照目录的
+//! `archive.key.json` 中。修改口令只需重新加密主密钥；把单个快照的归档密钥交给他人即可让其解密
+//! 该快照，而不暴露口令和主密钥。
+//!
+//! 快照中的每个文件加密为同名的 `.mwxenc` 文件：文件头为魔数和16字节随机数，之后按 1MB 分块，
+//! 每块以 AES-256-CBC 加密并附 HMAC-SHA256。认证数据包含文件随机数、块序号和是否为最后一块，
+//! 块被替换、重排或截断时解密失败。
+
+use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use hmac::{Hmac, Mac};
+use pbkdf2::pbkdf2_hmac;
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Sha512};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use zeroize::Zeroize;
+
+use super::catalog::key_fingerprint;
+use super::collect_files;
+use crate::errors::{Result, VaultError};
+
+/// 备份根目录中的主密钥文件
+pub const VAULT_FILE: &str = "vault.json";
+
+/// 加密快照中的归档密钥文件
+pub const ARCHIVE_KEY_FILE: &str = "archive.key.json";
+
+/// 加密文件的扩展名
+pub const ENCRYPTED_EXTENSION: &str = "mwxenc";
+
+/// 口令派生的默认迭代次数
+pub const DEFAULT_KDF_ITERATIONS: u32 = 600_000;
+
+/// 加密文件的魔数
+const MAGIC: &[u8; 8] = b"MWXENC01";
+
+/// 密钥文件格式版本
+const VAULT_VERSION: u32 = 1;
+
+/// 明文分块大小
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+const KEY_SIZE: usize = 32;
+const IV_SIZE: usize = 16;
+const TAG_SIZE: usize = 32;
+const NONCE_SIZE: usize = 16;
+const SALT_SIZE: usize = 16;
+
+/// 256 位密钥，释放时清零
+#[derive(Clone, PartialEq, Eq)]
+pub struct SecretKey([u8; KEY_SIZE]);
+
+impl SecretKey {
+    /// 随机生成
+    pub fn generate() -> Result<Self> {
+        let mut key = [0u8; KEY_SIZE];
+        random(&mut key)?;
+        Ok(Self(key))
+    }
+
+    /// 从64位十六进制解析
+    pub fn from_hex(text: &str) -> Result<Self> {
+        let mut bytes = hex::decode(text.trim()).map_err(|_| VaultError::InvalidKey)?;
+        let key = <[u8; KEY_SIZE]>::try_from(bytes.as_slice()).map_err(|_| VaultError::InvalidKey);
+        bytes.zeroize();
+        Ok(Self(key?))
+    }
+
+    /// 十六进制表示
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+
+    /// 密钥指纹，与备份记录中的密钥指纹算法相同
+    pub fn fingerprint(&self) -> String {
+        key_fingerprint(&self.0)
+    }
+
+    /// 由口令派生
+    fn derive(passphrase: &str, salt: &[u8], iterations: u32) -> Self {
+        let mut key = [0u8; KEY_SIZE];
+        pbkdf2_hmac::<Sha512>(passphrase.as_bytes(), salt, iterations, &mut key);
+        Self(key)
+    }
+
+    /// 派生加密或认证用的子密钥
+    fn subkey(&self, label: &[u8]) -> [u8; KEY_SIZE] {
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&self.0).expect("HMAC 接受任意长度的密钥");
+        mac.update(label);
+        mac.finalize().into_bytes().into()
+    }
+}
+
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl std::fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SecretKey({})", self.fingerprint())
+    }
+}
+
+/// 备份根目录的主密钥
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MasterVault {
+    /// 格式版本
+    pub version: u32,
+    /// 口令派生的迭代次数
+    pub iterations: u32,
+    /// 口令派生的盐（十六进制）
+    pub salt: String,
+    /// 由口令派生密钥加密的主密钥（十六进制）
+    pub wrapped_key: String,
+    /// 主密钥指纹
+    pub fingerprint: String,
+}
+
+impl MasterVault {
+    /// 在备份根目录创建主密钥，已存在时报错
+    pub fn create(root: &Path, passphrase: &str, iterations: u32) -> Result<(Self, SecretKey)> {
+        let path = root.join(VAULT_FILE);
+        if path.exists() {
+            return Err(VaultError::AlreadyExists {
+                path: path.display().to_string(),
+            }
+            .into());
+        }
+        let master = SecretKey::generate()?;
+        let vault = Self::wrap(&master, passphrase, iterations)?;
+        std::fs::create_dir_all(root)?;
+        vault.save(root)?;
+        Ok((vault, master))
+    }
+
+    /// 读取备份根目录的主密钥，不存在时返回 `None`
+    pub fn load(root: &Path) -> Result<Option<Self>> {
+        let path = root.join(VAULT_FILE);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&std::fs::read_to_string(path)?)?))
+    }
+
+    /// 用口令解开主密钥
+    pub fn unlock(&self, passphrase: &str) -> Result<SecretKey> {
+        let salt = hex::decode(&self.salt)?;
+        let wrapping = SecretKey::derive(passphrase, &salt, self.iterations);
+        let mut key = open(&wrapping, b"master", &hex::decode(&self.wrapped_key)?)
+            .map_err(|_| VaultError::WrongPassphrase)?;
+        let master = <[u8; KEY_SIZE]>::try_from(key.as_slice())
+            .map(SecretKey)
+            .map_err(|_| VaultError::Corrupted(VAULT_FILE.to_string()));
+        key.zeroize();
+        master.map_err(Into::into)
+    }
+
+    /// 修改口令，主密钥和已加密的快照不变
+    pub fn change_passphrase(root: &Path, old: &str, new: &str) -> Result<Self> {
+        let vault = Self::load(root)?.ok_or_else(|| VaultError::MasterNotFound {
+            dir: root.display().to_string(),
+        })?;
+        let master = vault.unlock(old)?;
+        let changed = Self::wrap(&master, new, vault.iterations)?;
+        changed.save(root)?;
+        Ok(changed)
+    }
+
+    fn wrap(master: &SecretKey, passphrase: &str, iterations: u32) -> Result<Self> {
+        let mut salt = [0u8; SALT_SIZE];
+        random(&mut salt)?;
+        let wrapping = SecretKey::derive(passphrase, &salt, iterations);
+        Ok(Self {
+            version: VAULT_VERSION,
+            iterations,
+            salt: hex::encode(salt),
+            wrapped_key: hex::encode(seal(&wrapping, b"master", &master.0)?),
+            fingerprint: master.fingerprint(),
+        })
+    }
+
+    /// 先写临时文件再替换
+    fn save(&self, root: &Path) -> Result<()> {
+        let temp = root.join(format!("{}.tmp", VAULT_FILE));
+        std::fs::write(&temp, serde_json::to_vec_pretty(self)?)?;
+        std::fs::rename(temp, root.join(VAULT_FILE))?;
+        Ok(())
+    }
+}
+
+/// 快照的归档密钥文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveKeyFile {
+    /// 格式版本
+    pub version: u32,
+    /// 加密归档密钥的主密钥指纹
+    pub master_fingerprint: String,
+    /// 由主密钥加密的归档密钥（十六进制）
+    pub wrapped_key: String,
+}
+
+/// 加密或解密的统计
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveReport {
+    /// 处理的文件数
+    pub files: usize,
+    /// 明文总大小（字节）
+    pub bytes: u64,
+}
+
+/// 目录是否为加密的快照
+pub fn is_encrypted(dir: &Path) -> bool {
+    dir.join(ARCHIVE_KEY_FILE).is_file()
+}
+
+/// 以新的归档密钥加密快照目录中的所有文件，加密后删除明文
+///
+/// 归档密钥先写入 `archive.key.json.pending`；全部密文落盘后才改名为 `archive.key.json` 并删除明文。
+/// 中途失败时撤销已写入的密文，明文保持不变。
+pub fn encrypt_archive(dir: &Path, master: &SecretKey) -> Result<ArchiveReport> {
+    if is_encrypted(dir) {
+        return Err(VaultError::AlreadyEncrypted {
+            path: dir.display().to_string(),
+        }
+        .into());
+    }
+    let key = SecretKey::generate()?;
+    let key_file = ArchiveKeyFile {
+        version: VAULT_VERSION,
+        master_fingerprint: master.fingerprint(),
+        wrapped_key: hex::encode(seal(master, b"archive", &key.0)?),
+    };
+    // 归档密钥先写入待定文件并落盘，任何明文删除之前密钥已可恢复
+    let pending = dir.join(format!("{}.pending", ARCHIVE_KEY_FILE));
+    write_durable(&pending, &serde_json::to_vec_pretty(&key_file)?)?;
+
+    let plaintexts: Vec<PathBuf> = collect_files(dir)?
+        .into_iter()
+        .filter(|file| !is_encrypted_file(file) && *file != pending)
+        .collect();
+    let mut report = ArchiveReport::default();
+    let mut written = Vec::with_capacity(plaintexts.len());
+    for file in &plaintexts {
+        let target = encrypted_path(file);
+        match encrypt_file(&key, file, &target) {
+            Ok(bytes) => {
+                report.bytes += bytes;
+                report.files += 1;
+                written.push(target);
+            }
+            Err(e) => {
+                // 明文仍完整，撤销已写入的密文，快照保持未加密
+                let _ = std::fs::remove_file(partial_path(&encrypted_path(file)));
+                for target in &written {
+                    let _ = std::fs::remove_file(target);
+                }
+                let _ = std::fs::remove_file(&pending);
+                return Err(e);
+            }
+        }
+    }
+    // 全部密文落盘后启用归档密钥，之后才删除明文
+    std::fs::rename(&pending, dir.join(ARCHIVE_KEY_FILE))?;
+    sync_dir(dir);
+    for file in &plaintexts {
+        std::fs::remove_file(file)?;
+    }
+    Ok(report)
+}
+
+/// 用主密钥解开快照的归档密钥
+pub fn archive_key(dir: &Path, master: &SecretKey) -> Result<SecretKey> {
+    let key_file: ArchiveKeyFile = serde_json::from_str(&std::fs::read_to_string(dir.join(ARCHIVE_KEY_FILE))?)?;
+    if key_file.master_fingerprint != master.fingerprint() {
+        return Err(VaultError::ForeignMaster {
+            fingerprint: key_file.master_fingerprint,
+        }
+        .into());
+    }
+    let mut bytes = open(master, b"archive", &hex::decode(&key_file.wrapped_key)?)?;
+    let key = <[u8; KEY_SIZE]>::try_from(bytes.as_slice())
+        .map(SecretKey)
+        .map_err(|_| VaultError::Corrupted(ARCHIVE_KEY_FILE.to_string()));
+    bytes.zeroize();
+    key.map_err(Into::into)
+}
+
+/// 用归档密钥把加密的快照解密到 `output`，保留目录结构
+pub fn decrypt_archive(dir: &Path, output: &Path, key: &SecretKey) -> Result<ArchiveReport> {
+    let mut report = ArchiveReport::default();
+    for file in collect_files(dir)? {
+        if !is_encrypted_file(&file) {
+            continue;
+        }
+        let relative = file.strip_prefix(dir).unwrap_or(&file).with_extension("");
+        let target = output.join(relative);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        report.bytes += decrypt_file(key, &file, &target)?;
+        report.files += 1;
+    }
+    Ok(report)
+}
+
+/// 加密单个文件，返回明文大小
+pub fn encrypt_file(key: &SecretKey, source: &Path, target: &Path) -> Result<u64> {
+    let mut reader = BufReader::new(File::open(source)?);
+    let partial = partial_path(target);
+    let mut writer = BufWriter::new(File::create(&partial)?);
+    let mut nonce = [0u8; NONCE_SIZE];
+    random(&mut nonce)?;
+    writer.write_all(MAGIC)?;
+    writer.write_all(&nonce)?;
+
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut total = 0u64;
+    for index in 0u64.. {
+        let read = read_full(&mut reader, &mut buffer)?;
+        let last = read < CHUNK_SIZE;
+        let sealed = seal(key, &chunk_aad(&nonce, index, last), &buffer[..read])?;
+        writer.write_all(&(sealed.len() as u32).to_le_bytes())?;
+        writer.write_all(&sealed)?;
+        total += read as u64;
+        if last {
+            break;
+        }
+    }
+    writer.flush()?;
+    writer.get_ref().sync_all()?;
+    drop(writer);
+    std::fs::rename(&partial, target)?;
+    Ok(total)
+}
+
+/// 解密单个文件，返回明文大小；校验失败时不留下输出
+pub fn decrypt_file(key: &SecretKey, source: &Path, target: &Path) -> Result<u64> {
+    let partial = partial_path(target);
+    let result = decrypt_to(key, source, &partial);
+    match result {
+        Ok(total) => {
+            std::fs::rename(&partial, target)?;
+            Ok(total)
+        }
+        Err(e) => {
+            let _ = std::fs::remove_file(&partial);
+            Err(e)
+        }
+    }
+}
+
+fn decrypt_to(key: &SecretKey, source: &Path, target: &Path) -> Result<u64> {
+    let corrupted = || VaultError::Corrupted(source.display().to_string());
+    let mut reader = BufReader::new(File::open(source)?);
+    let mut header = [0u8; MAGIC.len() + NONCE_SIZE];
+    reader.read_exact(&mut header).map_err(|_| corrupted())?;
+    if &header[..MAGIC.len()] != MAGIC {
+        return Err(corrupted().into());
+    }
+    let nonce = &header[MAGIC.len()..];
+
+    let mut writer = BufWriter::new(File::create(target)?);
+    let mut total = 0u64;
+    for index in 0u64.. {
+        let mut len = [0u8; 4];
+        reader.read_exact(&mut len).map_err(|_| corrupted())?;
+        let len = u32::from_le_bytes(len) as usize;
+        if len > CHUNK_SIZE + IV_SIZE * 2 + TAG_SIZE {
+            return Err(corrupted().into());
+        }
+        let mut sealed = vec![0u8; len];
+        reader.read_exact(&mut sealed).map_err(|_| corrupted())?;
+        let last = reader.fill_buf()?.is_empty();
+        let plain = open(key, &chunk_aad(nonce, index, last), &sealed).map_err(|_| corrupted())?;
+        writer.write_all(&plain)?;
+        total += plain.len() as u64;
+        if last {
+            break;
+        }
+    }
+    writer.flush()?;
+    Ok(total)
+}
+
+/// 加密并认证：`IV | 密文 | HMAC(aad | IV | 密文)`
+pub(super) fn seal(key: &SecretKey, aad: &[u8], plain: &[u8]) -> Result<Vec<u8>> {
+    let mut iv = [0u8; IV_SIZE];
+    random(&mut iv)?;
+    let enc_key = key.subkey(b"mwxdump-vault-enc");
+    let mut buffer = plain.to_vec();
+    buffer.resize(plain.len() + IV_SIZE - plain.len() % IV_SIZE, 0);
+    let encrypted = cbc::Encryptor::<aes::Aes256>::new(&enc_key.into(), &iv.into())
+        .encrypt_padded_mut::<Pkcs7>(&mut buffer, plain.len())
+        .map_err(|e| VaultError::EncryptionFailed(e.to_string()))?;
+    let mut sealed = [iv.as_slice(), encrypted].concat();
+    let tag = authenticator(key, aad, &sealed).finalize().into_bytes();
+    sealed.extend_from_slice(&tag);
+    buffer.zeroize();
+    Ok(sealed)
+}
+
+/// 校验并解密 [`seal`] 的输出
+pub(super) fn open(key: &SecretKey, aad: &[u8], sealed: &[u8]) -> Result<Vec<u8>> {
+    let invalid = || VaultError::Corrupted("数据校验失败".to_string());
+    if sealed.len() < IV_SIZE + IV_SIZE + TAG_SIZE {
+        return Err(invalid().into());
+    }
+    let (body, expected) = sealed.split_at(sealed.len() - TAG_SIZE);
+    authenticator(key, aad, body).verify_slice(expected).map_err(|_| invalid())?;
+
+    let (iv, encrypted) = body.split_at(IV_SIZE);
+    let enc_key = key.subkey(b"mwxdump-vault-enc");
+    let mut buffer = encrypted.to_vec();
+    let plain = cbc::Decryptor::<aes::Aes256>::new(&enc_key.into(), iv.into())
+        .decrypt_padded_mut::<Pkcs7>(&mut buffer)
+        .map_err(|_| invalid())?
+        .to_vec();
+    buffer.zeroize();
+    Ok(plain)
+}
+
+/// 以 `label` 派生的子密钥计算 `data` 的 HMAC，用于认证明文保存的数据
+pub(super) fn authenticate(key: &SecretKey, label: &[u8], data: &[u8]) -> Hmac<Sha256> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&key.subkey(label)).expect("HMAC 接受任意长度的密钥");
+    mac.update(data);
+    mac
+}
+
+/// 以认证子密钥计算 `aad | body` 的 HMAC
+fn authenticator(key: &SecretKey, aad: &[u8], body: &[u8]) -> Hmac<Sha256> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&key.subkey(b"mwxdump-vault-mac")).expect("HMAC 接受任意长度的密钥");
+    mac.update(aad);
+    mac.update(body);
+    mac
+}
+
+/// 分块的认证数据：文件随机数、块序号和是否为最后一块
+fn chunk_aad(nonce: &[u8], index: u64, last: bool) -> Vec<u8> {
+    [nonce, &index.to_le_bytes(), &[last as u8]].concat()
+}
+
+fn random(buffer: &mut [u8]) -> Result<()> {
+    getrandom::fill(buffer).map_err(|e| VaultError::EncryptionFailed(format!("无法获取随机数: {}", e)).into())
+}
+
+/// 尽量读满缓冲区，返回读取的字节数，小于缓冲区大小时已到文件末尾
+fn read_full(reader: &mut impl Read, buffer: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..])? {
+            0 => break,
+            read => filled += read,
+        }
+    }
+    Ok(filled)
+}
+
+/// 写入文件并落盘
+fn write_durable(path: &Path, data: &[u8]) -> Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// 目录项落盘，使重命名持久化；Windows 上无法打开目录，忽略
+fn sync_dir(dir: &Path) {
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+}
+
+fn is_encrypted_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == ENCRYPTED_EXTENSION)
+}
+
+fn encrypted_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".");
+    name.push(ENCRYPTED_EXTENSION);
+    PathBuf::from(name)
+}
+
+fn partial_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".partial");
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_and_share_archive() {
+        let root = tempfile::tempdir().unwrap();
+        let snapshot = root.path().join("20240501-093000");
+        std::fs::create_dir_all(snapshot.join("db_storage/message")).unwrap();
+        let large: Vec<u8> = (0..CHUNK_SIZE * 2 + 7).map(|i| (i % 251) as u8).collect();
+        std::fs::write(snapshot.join("db_storage/message/decrypted_message_0.db"), &large).unwrap();
+        std::fs::write(snapshot.join("empty.db"), b"").unwrap();
+
+        let (vault, master) = MasterVault::create(root.path(), "口令", 1000).unwrap();
+        assert!(MasterVault::create(root.path(), "口令", 1000).is_err());
+        let report = encrypt_archive(&snapshot, &master).unwrap();
+        assert_eq!((report.files, report.bytes), (2, large.len() as u64));
+        assert!(is_encrypted(&snapshot));
+        assert!(!snapshot.join("empty.db").exists());
+        assert!(encrypt_archive(&snapshot, &master).is_err());
+
+        // 口令错误时无法解开主密钥；修改口令后快照仍可解密
+        assert!(vault.unlock("wrong").is_err());
+        MasterVault::change_passphrase(root.path(), "口令", "新口令").unwrap();
+        let reloaded = MasterVault::load(root.path()).unwrap().unwrap();
+        assert_eq!(reloaded.fingerprint, vault.fingerprint);
+        let master = reloaded.unlock("新口令").unwrap();
+
+        // 只凭分享的归档密钥即可解密
+        let shared = SecretKey::from_hex(&archive_key(&snapshot, &master).unwrap().to_hex()).unwrap();
+        let restored = root.path().join("restored");
+        let report = decrypt_archive(&snapshot, &restored, &shared).unwrap();
+        assert_eq!(report.files, 2);
+        assert_eq!(std::fs::read(restored.join("db_storage/message/decrypted_message_0.db")).unwrap(), large);
+        assert!(std::fs::read(restored.join("empty.db")).unwrap().is_empty());
+
+        let other = SecretKey::generate().unwrap();
+        assert!(archive_key(&snapshot, &other).is_err());
+        assert!(decrypt_archive(&snapshot, &root.path().join("bad"), &other).is_err());
+        assert!(!root.path().join("bad/empty.db").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_failed_encryption_keeps_plaintext() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.db"), b"plain").unwrap();
+        // 无法打开的文件排在后面，使加密在处理完 a.db 之后失败
+        std::os::unix::fs::symlink(dir.path().join("missing"), dir.path().join("z.db")).unwrap();
+
+        let master = SecretKey::generate().unwrap();
+        assert!(encrypt_archive(dir.path(), &master).is_err());
+        assert_eq!(std::fs::read(dir.path().join("a.db")).unwrap(), b"plain");
+        assert!(!dir.path().join("a.db.mwxenc").exists());
+        assert!(!is_encrypted(dir.path()));
+        assert!(!dir.path().join("archive.key.json.pending").exists());
+    }
+
+    #[test]
+    fn test_detects_truncated_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = SecretKey::generate().unwrap();
+        let (plain, sealed, restored) = (dir.path().join("a"), dir.path().join("a.mwxenc"), dir.path().join("b"));
+        std::fs::write(&plain, vec![1u8; CHUNK_SIZE + 10]).unwrap();
+        encrypt_file(&key, &plain, &sealed).unwrap();
+        assert_eq!(decrypt_file(&key, &sealed, &restored).unwrap(), CHUNK_SIZE as u64 + 10);
+
+        // 去掉最后一块后，剩下的块不是以“最后一块”加密的
+        let data = std::fs::read(&sealed).unwrap();
+        let first = MAGIC.len() + NONCE_SIZE + 4 + u32::from_le_bytes(data[24..28].try_into().unwrap()) as usize;
+        std::fs::write(&sealed, &data[..first]).unwrap();
+        assert!(decrypt_file(&key, &sealed, &restored.with_extension("x")).is_err());
+        assert!(!restored.with_extension("x").exists());
+    }
+}
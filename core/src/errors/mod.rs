@@ -6,6 +6,68 @@ use thiserror::Error;
 
 pub type Result<T> = anyhow::Result<T>;
 
+/// 进程退出码
+///
+/// 供脚本根据退出码区分失败原因，无需解析标准错误输出。
+///
+/// | 退出码 | 含义 |
+/// |--------|------|
+/// | 0 | 成功 |
+/// | 1 | 未分类的错误 |
+/// | 2 | 命令行参数错误，由 clap 返回，本模块不使用 |
+/// | 10 | 未找到微信进程 |
+/// | 11 | 权限不足 |
+/// | 12 | 密钥提取失败 |
+/// | 13 | 解密失败 |
+/// | 14 | 配置错误 |
+/// | 130 | 用户中断 |
+///
+/// 具体失败原因从 10 开始编号，避开 clap 和 shell 的保留值。
+pub mod exit_code {
+    /// 成功
+    pub const SUCCESS: i32 = 0;
+    /// 未分类的错误
+    pub const GENERAL: i32 = 1;
+    /// 命令行参数错误，由 clap 在解析失败时返回
+    pub const USAGE: i32 = 2;
+    /// 未找到微信进程
+    pub const PROCESS_NOT_FOUND: i32 = 10;
+    /// 权限不足
+    pub const PERMISSION_DENIED: i32 = 11;
+    /// 密钥提取失败
+    pub const KEY_EXTRACTION_FAILED: i32 = 12;
+    /// 解密失败
+    pub const DECRYPTION_FAILED: i32 = 13;
+    /// 配置错误
+    pub const CONFIG_ERROR: i32 = 14;
+    /// 用户中断（Ctrl-C），与 shell 的 128 + SIGINT 约定一致
+    pub const CANCELLED: i32 = 130;
+}
+
+/// 获取错误对应的进程退出码
+///
+/// 沿错误链查找第一个可识别的错误类型，找不到时返回 [`exit_code::GENERAL`]。
+pub fn error_code(err: &anyhow::Error) -> i32 {
+    err.chain()
+        .find_map(|cause| {
+            if let Some(e) = cause.downcast_ref::<MwxDumpError>() {
+                Some(e.error_code())
+            } else if let Some(e) = cause.downcast_ref::<WeChatError>() {
+                Some(e.error_code())
+            } else if cause.is::<ConfigError>() {
+                Some(exit_code::CONFIG_ERROR)
+            } else if cause.is::<VaultError>() {
+                Some(exit_code::DECRYPTION_FAILED)
+            } else if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+                (e.kind() == std::io::ErrorKind::PermissionDenied)
+                    .then_some(exit_code::PERMISSION_DENIED)
+            } else {
+                None
+            }
+        })
+        .unwrap_or(exit_code::GENERAL)
+}
+
 /// 应用主要错误类型
 #[derive(Error, Debug)] // Clone, PartialEq, Eq are useful for testing
 pub enum MwxDumpError {
@@ -33,16 +95,42 @@ pub enum MwxDumpError {
     #[error("序列化错误: {0}")]
     Serialization(#[from] serde_json::Error),
 
+    #[error("插件错误: {0}")]
+    Plugin(#[from] PluginError),
+
+    #[error("备份加密错误: {0}")]
+    Vault(#[from] VaultError),
+
     #[error("系统错误: '{0}'")]
     System(#[from] SystemError),
   
     #[error("无效或无法解析的版本字符串: '{0}'")]
     InvalidVersion(String),
     
+    #[error("操作已取消")]
+    Cancelled,
+    
     #[error("其他错误: {0}")]
     Other(#[from] anyhow::Error),
 }
 
+impl MwxDumpError {
+    /// 获取对应的进程退出码
+    pub fn error_code(&self) -> i32 {
+        match self {
+            MwxDumpError::Config(_) => exit_code::CONFIG_ERROR,
+            MwxDumpError::WeChat(e) => e.error_code(),
+            MwxDumpError::Vault(_) => exit_code::DECRYPTION_FAILED,
+            MwxDumpError::Io(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+                exit_code::PERMISSION_DENIED
+            }
+            MwxDumpError::Cancelled => exit_code::CANCELLED,
+            MwxDumpError::Other(e) => error_code(e),
+            _ => exit_code::GENERAL,
+        }
+    }
+}
+
 /// 配置相关错误
 #[derive(Error, Debug)]
 pub enum ConfigError {
@@ -57,6 +145,45 @@ pub enum ConfigError {
     
     #[error("配置项值无效: {key} = {value}")]
     InvalidValue { key: String, value: String },
+    
+    #[error("配置存在 {} 处问题:{}", .0.len(), format_issues(.0))]
+    Invalid(Vec<ConfigIssue>),
+}
+
+/// 单个配置问题
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigIssue {
+    /// 配置项路径，如 `http.port`、`plugins.notify.script`
+    pub key: String,
+    /// 出错的值
+    pub value: String,
+    /// 问题说明
+    pub reason: String,
+}
+
+impl ConfigIssue {
+    pub fn new(key: impl Into<String>, value: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            value: value.into(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.value.is_empty() {
+            write!(f, "{}: {}", self.key, self.reason)
+        } else {
+            write!(f, "{} = {}: {}", self.key, self.value, self.reason)
+        }
+    }
+}
+
+/// 每个问题单独一行
+fn format_issues(issues: &[ConfigIssue]) -> String {
+    issues.iter().map(|issue| format!("\n  - {}", issue)).collect()
 }
 
 #[derive(Error, Debug)]
@@ -70,6 +197,9 @@ pub enum SystemError {
     
     #[error("进程路径缺失")]
     MissingPath,
+    
+    #[error("目录 {path} 正被另一个 MWXDump 进程使用 (PID: {holder})，请等待其完成后再试")]
+    InstanceLocked { path: String, holder: String },
 }
 
 
@@ -90,6 +220,18 @@ pub enum DatabaseError {
     
     #[error("数据迁移失败: {0}")]
     MigrationFailed(String),
+    
+    #[error("非法的表名或列名: {0}")]
+    InvalidIdentifier(String),
+    
+    #[error("未开启原始 SQL 查询")]
+    RawSqlDisabled,
+    
+    #[error("原始 SQL 中不允许使用 {0}")]
+    ForbiddenSql(String),
+    
+    #[error("无效的分页游标: {0}")]
+    InvalidCursor(String),
 }
 
 /// 微信相关错误
@@ -104,14 +246,38 @@ pub enum WeChatError {
     #[error("数据解密失败: {0}")]
     DecryptionFailed(String),
     
-    #[error("不支持的微信版本: {version}， 请升级到4.0+版本")]
-    UnsupportedVersion { version: String },
+    #[error("不支持的微信版本: {version}，支持的版本: {supported}")]
+    UnsupportedVersion { version: String, supported: String },
     
     #[error("权限不足: {0}")]
     PermissionDenied(String),
     
     #[error("数据文件损坏: {path}")]
     CorruptedFile { path: String },
+    
+    #[error("无法识别的微信数据目录: {path}")]
+    DataDirNotFound { path: String },
+
+    #[error("输出路径 {output} 位于微信数据目录 {data_dir} 中，为避免破坏原始数据已拒绝写入")]
+    OutputInsideDataDir { output: String, data_dir: String },
+}
+
+impl WeChatError {
+    /// 获取对应的进程退出码
+    pub fn error_code(&self) -> i32 {
+        match self {
+            WeChatError::ProcessNotFound => exit_code::PROCESS_NOT_FOUND,
+            WeChatError::PermissionDenied(_) => exit_code::PERMISSION_DENIED,
+            WeChatError::KeyExtractionFailed(_) | WeChatError::UnsupportedVersion { .. } => {
+                exit_code::KEY_EXTRACTION_FAILED
+            }
+            WeChatError::DecryptionFailed(_) | WeChatError::CorruptedFile { .. } => {
+                exit_code::DECRYPTION_FAILED
+            }
+            WeChatError::DataDirNotFound { .. } => exit_code::GENERAL,
+            WeChatError::OutputInsideDataDir { .. } => exit_code::CONFIG_ERROR,
+        }
+    }
 }
 
 /// HTTP服务相关错误
@@ -123,6 +289,9 @@ pub enum HttpError {
     #[error("端口被占用: {port}")]
     PortInUse { port: u16 },
     
+    #[error("未设置访问令牌时只能监听本机地址: {host}")]
+    InsecureBind { host: String },
+    
     #[error("请求处理失败: {0}")]
     RequestFailed(String),
     
@@ -147,6 +316,50 @@ pub enum McpError {
     
     #[error("资源访问失败: {resource}")]
     ResourceAccessFailed { resource: String },
+    
+    #[error("缺少权限: {scope}")]
+    ScopeDenied { scope: String },
+}
+
+/// 备份加密和签名相关错误
+#[derive(Error, Debug)]
+pub enum VaultError {
+    #[error("主密钥已存在: {path}")]
+    AlreadyExists { path: String },
+
+    #[error("{dir} 下没有主密钥，请先执行 keys master init")]
+    MasterNotFound { dir: String },
+
+    #[error("主密钥口令错误")]
+    WrongPassphrase,
+
+    #[error("快照由另一个主密钥（{fingerprint}）加密")]
+    ForeignMaster { fingerprint: String },
+
+    #[error("快照已加密: {path}")]
+    AlreadyEncrypted { path: String },
+
+    #[error("密钥应为64位十六进制")]
+    InvalidKey,
+
+    #[error("加密数据已损坏或密钥错误: {0}")]
+    Corrupted(String),
+
+    #[error("{0} 中的签名公钥未经当前主密钥认证，可能已被替换")]
+    UntrustedSigningKey(String),
+
+    #[error("加密失败: {0}")]
+    EncryptionFailed(String),
+}
+
+/// 插件相关错误
+#[derive(Error, Debug)]
+pub enum PluginError {
+    #[error("插件加载失败: {name} - {reason}")]
+    LoadFailed { name: String, reason: String },
+    
+    #[error("插件执行失败: {name} - {reason}")]
+    ExecutionFailed { name: String, reason: String },
 }
 
 /// UI相关错误
@@ -170,4 +383,26 @@ impl From<windows::core::Error> for MwxDumpError {
     fn from(err: windows::core::Error) -> Self {
         MwxDumpError::WeChat(WeChatError::ProcessNotFound)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_error_code_mapping() {
+        let err: anyhow::Error = WeChatError::ProcessNotFound.into();
+        assert_eq!(error_code(&err), exit_code::PROCESS_NOT_FOUND);
+
+        let err: anyhow::Error = MwxDumpError::from(ConfigError::MissingKey { key: "k".into() }).into();
+        assert_eq!(error_code(&err), exit_code::CONFIG_ERROR);
+
+        let err = Err::<(), _>(WeChatError::DecryptionFailed("x".into()))
+            .context("解密目录失败")
+            .unwrap_err();
+        assert_eq!(error_code(&err), exit_code::DECRYPTION_FAILED);
+
+        assert_eq!(error_code(&anyhow::anyhow!("未知")), exit_code::GENERAL);
+    }
+}
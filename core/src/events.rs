@@ -0,0 +1,204 @@
+//! 事件总线
+//!
+//! 进程检测、密钥提取、解密和导出进度、任务结束等事件统一发布到 [`EventBus`]，
+//! CLI 进度条、HTTP 事件流、MCP 通知和桌面端各自订阅，不再为每个功能单独建通道。
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tokio::sync::broadcast;
+use ts_rs::TS;
+
+use crate::export::ExportSummary;
+use crate::jobs::JobInfo;
+
+/// 事件通道容量，落后过多的订阅者会丢弃旧事件
+pub const EVENT_CAPACITY: usize = 256;
+
+/// 事件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// 检测到微信进程
+    ProcessDetected {
+        /// 进程PID
+        pid: u32,
+        /// 微信版本
+        version: String,
+        /// 账号 wxid
+        wxid: Option<String>,
+    },
+    /// 已提取密钥
+    KeyExtracted {
+        /// 密钥来源进程PID
+        pid: u32,
+        /// 密钥指纹，不包含密钥本身
+        fingerprint: String,
+    },
+    /// 解密完成一个数据库
+    DecryptProgress {
+        /// 刚完成的数据库
+        file: PathBuf,
+        /// 已处理的数据库数（含失败的）
+        #[ts(type = "number")]
+        processed: u64,
+        /// 数据库总数
+        #[ts(type = "number")]
+        total: u64,
+    },
+    /// 导出处理完一页消息
+    ExportProgress {
+        /// 会话
+        wxid: String,
+        /// 已处理的消息数
+        #[ts(type = "number")]
+        processed: u64,
+        /// 会话消息总数
+        #[ts(type = "number")]
+        total: u64,
+    },
+    /// 导出完成一个会话
+    ExportFinished {
+        /// 会话
+        wxid: String,
+        /// 导出结果
+        summary: ExportSummary,
+    },
+    /// 数据库已重新解密
+    DataUpdated {
+        /// 刷新的数据库，相对数据目录的路径
+        files: Vec<PathBuf>,
+        /// 刷新时间
+        at: DateTime<Utc>,
+    },
+    /// 后台任务结束
+    JobFinished {
+        /// 任务最终信息
+        job: JobInfo,
+    },
+    /// 后台流程出错，调用方无法直接拿到错误时发布
+    Error {
+        /// 出错的流程，如 `refresh`
+        source: String,
+        /// 错误信息
+        message: String,
+    },
+}
+
+impl Event {
+    /// 事件名，与序列化后的 `type` 字段一致
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::ProcessDetected { .. } => "process_detected",
+            Event::KeyExtracted { .. } => "key_extracted",
+            Event::DecryptProgress { .. } => "decrypt_progress",
+            Event::ExportProgress { .. } => "export_progress",
+            Event::ExportFinished { .. } => "export_finished",
+            Event::DataUpdated { .. } => "data_updated",
+            Event::JobFinished { .. } => "job_finished",
+            Event::Error { .. } => "error",
+        }
+    }
+}
+
+/// 事件总线
+///
+/// 克隆后共享同一个通道；没有订阅者时发布的事件直接丢弃。
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<Event>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::channel(EVENT_CAPACITY).0,
+        }
+    }
+}
+
+impl EventBus {
+    /// 创建事件总线
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 发布事件
+    pub fn publish(&self, event: Event) {
+        // 没有订阅者时发送失败，可以忽略
+        let _ = self.sender.send(event);
+    }
+
+    /// 订阅之后发布的事件
+    pub fn subscribe(&self) -> EventReceiver {
+        EventReceiver {
+            receiver: self.sender.subscribe(),
+        }
+    }
+}
+
+/// 事件订阅者
+#[derive(Debug)]
+pub struct EventReceiver {
+    receiver: broadcast::Receiver<Event>,
+}
+
+impl EventReceiver {
+    /// 等待下一个事件，落后时跳过丢弃的事件，总线关闭后返回 `None`
+    pub async fn recv(&mut self) -> Option<Event> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) => return Some(event),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("事件订阅者落后，丢弃 {} 条事件", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// 取出已到达的下一个事件，没有时立即返回 `None`
+    pub fn try_recv(&mut self) -> Option<Event> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(event) => return Some(event),
+                Err(broadcast::error::TryRecvError::Lagged(skipped)) => {
+                    tracing::warn!("事件订阅者落后，丢弃 {} 条事件", skipped);
+                }
+                Err(_) => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_publish_and_subscribe() {
+        let bus = EventBus::new();
+        // 没有订阅者时发布不报错
+        bus.publish(Event::KeyExtracted {
+            pid: 1,
+            fingerprint: "dropped".to_string(),
+        });
+
+        let mut first = bus.subscribe();
+        let mut second = bus.clone().subscribe();
+        let event = Event::ProcessDetected {
+            pid: 42,
+            version: "4.0.3.22".to_string(),
+            wxid: Some("wxid_self".to_string()),
+        };
+        bus.publish(event.clone());
+        assert_eq!(first.recv().await, Some(event.clone()));
+        assert_eq!(second.try_recv(), Some(event.clone()));
+        assert_eq!(second.try_recv(), None);
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], event.name());
+        assert_eq!(serde_json::from_value::<Event>(json).unwrap(), event);
+    }
+}
@@ -0,0 +1,117 @@
+//! 导出断点
+//!
+//! 每个会话导出完成后在输出目录的 `.checkpoints` 下写入完成标记，记录导出格式、导出结果
+//! 以及文件的大小和 SHA-256。续导时标记有效且文件未被改动的会话直接跳过；没有标记
+//! （导出中途中断，文件只写了一部分）或校验不一致的会话从头重新导出。
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use super::filename::sanitize_filename;
+use super::{ExportFormat, ExportSummary};
+use crate::errors::Result;
+
+/// 完成标记所在的目录，相对输出目录
+pub const CHECKPOINT_DIR: &str = ".checkpoints";
+
+/// 会话的导出完成标记
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// 会话
+    pub wxid: String,
+    /// 导出格式
+    pub format: ExportFormat,
+    /// 导出结果
+    pub summary: ExportSummary,
+    /// 导出文件大小
+    pub size: u64,
+    /// 导出文件的 SHA-256
+    pub sha256: String,
+    /// 完成时间
+    pub finished_at: DateTime<Utc>,
+}
+
+impl Checkpoint {
+    /// 为已写完的导出文件生成完成标记
+    pub fn new(wxid: &str, format: ExportFormat, summary: &ExportSummary) -> Result<Self> {
+        let (size, sha256) = file_digest(&summary.file)?;
+        Ok(Self {
+            wxid: wxid.to_string(),
+            format,
+            summary: summary.clone(),
+            size,
+            sha256,
+            finished_at: Utc::now(),
+        })
+    }
+
+    /// 读取会话的完成标记，不存在或无法解析时返回 `None`
+    pub fn load(output_dir: &Path, wxid: &str) -> Option<Self> {
+        let content = std::fs::read_to_string(marker_path(output_dir, wxid)).ok()?;
+        match serde_json::from_str::<Self>(&content) {
+            Ok(checkpoint) if checkpoint.wxid == wxid => Some(checkpoint),
+            Ok(_) => None,
+            Err(e) => {
+                tracing::warn!("会话 {} 的完成标记无法解析，忽略: {}", wxid, e);
+                None
+            }
+        }
+    }
+
+    /// 写入输出目录
+    pub fn save(&self, output_dir: &Path) -> Result<()> {
+        let path = marker_path(output_dir, &self.wxid);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// 删除会话的完成标记，开始重新导出前调用
+    pub fn clear(output_dir: &Path, wxid: &str) -> Result<()> {
+        match std::fs::remove_file(marker_path(output_dir, wxid)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// 格式一致且导出文件的大小和哈希与标记相同
+    pub fn verify(&self, format: ExportFormat) -> bool {
+        if self.format != format {
+            return false;
+        }
+        match file_digest(&self.summary.file) {
+            Ok((size, sha256)) => size == self.size && sha256 == self.sha256,
+            Err(_) => false,
+        }
+    }
+}
+
+/// 会话完成标记的路径
+fn marker_path(output_dir: &Path, wxid: &str) -> PathBuf {
+    output_dir
+        .join(CHECKPOINT_DIR)
+        .join(format!("{}.json", sanitize_filename(wxid)))
+}
+
+/// 文件大小和 SHA-256
+fn file_digest(path: &Path) -> Result<(u64, String)> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    let mut size = 0;
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        size += read as u64;
+    }
+    Ok((size, hex::encode(hasher.finalize())))
+}
@@ -0,0 +1,195 @@
+//! 导出文件名
+//!
+//! 联系人备注等显示名称可能包含 Windows 和 exFAT 不允许的字符。[`sanitize_filename`]
+//! 把这些字符换成外观相近的全角字符，保留中文等非 ASCII 字符；[`FileNameMap`] 为每个会话
+//! 分配不重复的文件名（不区分大小写，重名时追加 ` (2)` 等后缀），并在输出目录下的
+//! `.checkpoints/.filenames.json` 中记录会话与文件名的对应关系，重复导出时沿用同一个文件名。
+//! 记录放在导出文件之外，不会与名为 `filenames` 的会话的导出文件冲突。
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use super::checkpoint::CHECKPOINT_DIR;
+use crate::errors::Result;
+
+/// 会话与文件名对应关系的文件名，位于 [`CHECKPOINT_DIR`] 下
+pub const FILENAME_MAP_FILE: &str = ".filenames.json";
+
+/// 旧版本写在输出目录下的对应关系文件
+const LEGACY_FILENAME_MAP_FILE: &str = "filenames.json";
+
+/// 文件名主体的最大字节数，为扩展名和 `_files` 等后缀留出余量
+pub const MAX_FILENAME_BYTES: usize = 180;
+
+/// Windows 保留的设备名，带扩展名时同样不可用
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "LPT1",
+    "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// 把名称转为可在 Windows、exFAT 和 Unix 上使用的文件名主体
+///
+/// 非法字符替换为对应的全角字符，控制字符删除，去掉首尾空白和结尾的点；
+/// 结果为空、是保留设备名或超长时分别替换为 `_`、追加 `_`、按字符边界截断。
+pub fn sanitize_filename(name: &str) -> String {
+    let replaced: String = name
+        .chars()
+        .filter(|c| !c.is_control())
+        .map(|c| match c {
+            '<' => '＜',
+            '>' => '＞',
+            ':' => '：',
+            '"' => '＂',
+            '/' => '／',
+            '\\' => '＼',
+            '|' => '｜',
+            '?' => '？',
+            '*' => '＊',
+            other => other,
+        })
+        .collect();
+
+    let mut sanitized = truncate_bytes(replaced.trim(), MAX_FILENAME_BYTES)
+        .trim_end_matches(['.', ' '])
+        .to_string();
+    if sanitized.is_empty() {
+        return "_".to_string();
+    }
+    let stem = sanitized.split('.').next().unwrap_or_default();
+    if RESERVED_NAMES.iter().any(|reserved| reserved.eq_ignore_ascii_case(stem)) {
+        sanitized.insert(stem.len(), '_');
+    }
+    sanitized
+}
+
+/// 截断到不超过 `max` 字节的字符边界
+fn truncate_bytes(s: &str, max: usize) -> &str {
+    if s.len() <= max {
+        return s;
+    }
+    let end = (0..=max).rev().find(|&i| s.is_char_boundary(i)).unwrap_or(0);
+    &s[..end]
+}
+
+/// 输出目录中对应关系文件的路径
+fn map_path(dir: &Path) -> PathBuf {
+    dir.join(CHECKPOINT_DIR).join(FILENAME_MAP_FILE)
+}
+
+/// 会话到导出文件名主体的映射
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct FileNameMap {
+    names: BTreeMap<String, String>,
+}
+
+impl FileNameMap {
+    /// 读取输出目录中已有的映射，不存在时为空
+    ///
+    /// 没有新位置的记录时沿用旧版本输出目录下的 `filenames.json`；该文件也可能是名为
+    /// `filenames` 的会话的导出结果，无法解析为映射时忽略。
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = map_path(dir);
+        if path.exists() {
+            return Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?);
+        }
+        let legacy = std::fs::read_to_string(dir.join(LEGACY_FILENAME_MAP_FILE)).ok();
+        Ok(legacy.and_then(|content| serde_json::from_str(&content).ok()).unwrap_or_default())
+    }
+
+    /// 写入输出目录
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        let path = map_path(dir);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// 会话已分配的文件名主体
+    pub fn get(&self, wxid: &str) -> Option<&str> {
+        self.names.get(wxid).map(String::as_str)
+    }
+
+    /// 为会话分配文件名主体：清理 `name`，与其他会话重名时追加序号；
+    /// 名称未变时沿用之前分配的文件名
+    pub fn assign(&mut self, wxid: &str, name: &str) -> String {
+        let base = sanitize_filename(name);
+        if let Some(existing) = self.names.get(wxid) {
+            let suffixed = existing.strip_prefix(base.as_str()).is_some_and(|rest| rest.starts_with(" ("));
+            if *existing == base || suffixed {
+                return existing.clone();
+            }
+            self.names.remove(wxid);
+        }
+        let taken = |candidate: &str| self.names.values().any(|n| n.to_lowercase() == candidate.to_lowercase());
+        let mut candidate = base.clone();
+        let mut index = 2;
+        while taken(&candidate) {
+            candidate = format!("{} ({})", base, index);
+            index += 1;
+        }
+        self.names.insert(wxid.to_string(), candidate.clone());
+        candidate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_filename() {
+        assert_eq!(sanitize_filename("张三 (同事)"), "张三 (同事)");
+        assert_eq!(sanitize_filename("a/b:c*?"), "a／b：c＊？");
+        assert_eq!(sanitize_filename(" 项目群...  "), "项目群");
+        assert_eq!(sanitize_filename("tab\there\n"), "tabhere");
+        assert_eq!(sanitize_filename("con"), "con_");
+        assert_eq!(sanitize_filename("LPT1.notes"), "LPT1_.notes");
+        assert_eq!(sanitize_filename("..."), "_");
+        let long = sanitize_filename(&"群".repeat(100));
+        assert!(long.len() <= MAX_FILENAME_BYTES);
+        assert!(long.chars().all(|c| c == '群'));
+    }
+
+    #[test]
+    fn test_assign_avoids_collisions() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut map = FileNameMap::default();
+        assert_eq!(map.assign("wxid_a", "Alice"), "Alice");
+        assert_eq!(map.assign("wxid_b", "alice"), "alice (2)");
+        assert_eq!(map.assign("wxid_c", "Alice"), "Alice (3)");
+        assert_eq!(map.assign("wxid_b", "alice"), "alice (2)");
+        assert_eq!(map.assign("wxid_a", "改名了"), "改名了");
+        assert_eq!(map.assign("wxid_d", "Alice"), "Alice");
+        map.save(dir.path()).unwrap();
+
+        let loaded = FileNameMap::load(dir.path()).unwrap();
+        assert_eq!(loaded, map);
+        assert_eq!(loaded.get("wxid_b"), Some("alice (2)"));
+    }
+
+    #[test]
+    fn test_map_does_not_collide_with_exports() {
+        let dir = tempfile::tempdir().unwrap();
+        // 名为 filenames 的会话导出为 filenames.json，不是对应关系
+        std::fs::write(dir.path().join("filenames.json"), r#"[{"content": "hi"}]"#).unwrap();
+        assert_eq!(FileNameMap::load(dir.path()).unwrap(), FileNameMap::default());
+
+        let mut map = FileNameMap::default();
+        map.assign("filenames", "filenames");
+        map.save(dir.path()).unwrap();
+        assert!(dir.path().join(CHECKPOINT_DIR).join(FILENAME_MAP_FILE).is_file());
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("filenames.json")).unwrap(),
+            r#"[{"content": "hi"}]"#
+        );
+
+        // 旧版本的对应关系仍可读取
+        let legacy = tempfile::tempdir().unwrap();
+        std::fs::write(legacy.path().join("filenames.json"), r#"{"wxid_a": "Alice"}"#).unwrap();
+        assert_eq!(FileNameMap::load(legacy.path()).unwrap().get("wxid_a"), Some("Alice"));
+    }
+}
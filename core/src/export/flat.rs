@@ -0,0 +1,210 @@
+//! JSON Lines 和 CSV 导出
+//!
+//! 两种格式使用同一套固定字段，便于导入数据库或表格工具，字段只增不改：
+//!
+//! | 字段 | 说明 |
+//! |------|------|
+//! | `id` | 消息在会话内的序号 |
+//! | `timestamp` | 发送时间，RFC 3339 格式的 UTC 时间 |
+//! | `talker` | 会话的 wxid 或群聊ID |
+//! | `sender` | 发送者 wxid |
+//! | `type` | 消息类型 |
+//! | `content` | 消息内容 |
+//! | `media` | 媒体文件相对导出目录的路径，没有时为空 |
+//!
+//! 每条消息写完即交给底层写入器，内存占用与会话大小无关。
+
+use chrono::SecondsFormat;
+use serde::Serialize;
+use std::io::Write;
+
+use super::format::{ExportRecord, Exporter};
+use crate::errors::Result;
+
+/// 导出字段，按此顺序写入 CSV 的表头
+pub const FLAT_COLUMNS: [&str; 7] = ["id", "timestamp", "talker", "sender", "type", "content", "media"];
+
+/// 按固定字段展开的消息
+#[derive(Debug, Serialize)]
+struct FlatRecord<'a> {
+    id: i64,
+    timestamp: String,
+    talker: &'a str,
+    sender: &'a str,
+    #[serde(rename = "type")]
+    msg_type: i64,
+    content: &'a str,
+    media: Option<&'a str>,
+}
+
+impl<'a> From<&'a ExportRecord> for FlatRecord<'a> {
+    fn from(record: &'a ExportRecord) -> Self {
+        let message = &record.message;
+        Self {
+            id: message.seq,
+            timestamp: message.time.to_rfc3339_opts(SecondsFormat::Secs, true),
+            talker: &message.talker,
+            sender: &message.sender,
+            msg_type: message.msg_type,
+            content: &message.content,
+            media: record.media.as_deref(),
+        }
+    }
+}
+
+/// JSON Lines 导出器，每行一个 JSON 对象
+pub struct JsonLinesExporter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> JsonLinesExporter<W> {
+    /// 写入 `writer`
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+}
+
+impl<W: Write + Send> Exporter for JsonLinesExporter<W> {
+    fn write(&mut self, record: &ExportRecord) -> Result<()> {
+        serde_json::to_writer(&mut self.writer, &FlatRecord::from(record))?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// CSV 导出器
+///
+/// 按 RFC 4180 以 CRLF 换行，含逗号、引号或换行的字段加双引号；
+/// 文件以 UTF-8 BOM 开头，Excel 可以直接识别中文。
+pub struct CsvExporter<W: Write> {
+    writer: W,
+    header_written: bool,
+}
+
+impl<W: Write> CsvExporter<W> {
+    /// 写入 `writer`
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            header_written: false,
+        }
+    }
+
+    fn write_header(&mut self) -> Result<()> {
+        self.writer.write_all("\u{feff}".as_bytes())?;
+        self.write_row(&FLAT_COLUMNS)?;
+        self.header_written = true;
+        Ok(())
+    }
+
+    fn write_row(&mut self, fields: &[&str]) -> Result<()> {
+        for (i, field) in fields.iter().enumerate() {
+            if i > 0 {
+                self.writer.write_all(b",")?;
+            }
+            if field.contains([',', '"', '\r', '\n']) {
+                write!(self.writer, "\"{}\"", field.replace('"', "\"\""))?;
+            } else {
+                self.writer.write_all(field.as_bytes())?;
+            }
+        }
+        self.writer.write_all(b"\r\n")?;
+        Ok(())
+    }
+}
+
+impl<W: Write + Send> Exporter for CsvExporter<W> {
+    fn write(&mut self, record: &ExportRecord) -> Result<()> {
+        if !self.header_written {
+            self.write_header()?;
+        }
+        let record = FlatRecord::from(record);
+        self.write_row(&[
+            &record.id.to_string(),
+            &record.timestamp,
+            record.talker,
+            record.sender,
+            &record.msg_type.to_string(),
+            record.content,
+            record.media.unwrap_or_default(),
+        ])
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        // 没有消息时也写入表头
+        if !self.header_written {
+            self.write_header()?;
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Message;
+    use chrono::TimeZone;
+
+    fn record(seq: i64, content: &str, media: Option<&str>) -> ExportRecord {
+        let mut message = Message::new();
+        message.seq = seq;
+        message.time = chrono::Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        message.talker = "123@chatroom".to_string();
+        message.sender = "wxid_b".to_string();
+        message.content = content.to_string();
+        ExportRecord {
+            message,
+            media: media.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_jsonl_export() {
+        let mut output = Vec::new();
+        let mut exporter = Box::new(JsonLinesExporter::new(&mut output));
+        exporter.write(&record(1, "你好\n世界", None)).unwrap();
+        exporter.write(&record(2, "", Some("123@chatroom_files/a.jpg"))).unwrap();
+        exporter.finish().unwrap();
+
+        let lines: Vec<serde_json::Value> = std::str::from_utf8(&output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        let keys: Vec<&str> = lines[0].as_object().unwrap().keys().map(String::as_str).collect();
+        assert_eq!(keys.len(), FLAT_COLUMNS.len());
+        assert!(FLAT_COLUMNS.iter().all(|column| keys.contains(column)));
+        assert_eq!(lines[0]["timestamp"], "2023-11-14T22:13:20Z");
+        assert_eq!(lines[0]["content"], "你好\n世界");
+        assert_eq!(lines[0]["media"], serde_json::Value::Null);
+        assert_eq!(lines[1]["media"], "123@chatroom_files/a.jpg");
+    }
+
+    #[test]
+    fn test_csv_export() {
+        let mut output = Vec::new();
+        let mut exporter = Box::new(CsvExporter::new(&mut output));
+        exporter.write(&record(1, "他说\"好的, 明天见\"", None)).unwrap();
+        exporter.write(&record(2, "第一行\n第二行", Some("a.jpg"))).unwrap();
+        exporter.finish().unwrap();
+
+        let csv = String::from_utf8(output).unwrap();
+        assert_eq!(
+            csv,
+            "\u{feff}id,timestamp,talker,sender,type,content,media\r\n\
+             1,2023-11-14T22:13:20Z,123@chatroom,wxid_b,1,\"他说\"\"好的, 明天见\"\"\",\r\n\
+             2,2023-11-14T22:13:20Z,123@chatroom,wxid_b,1,\"第一行\n第二行\",a.jpg\r\n"
+        );
+
+        let mut empty = Vec::new();
+        Box::new(CsvExporter::new(&mut empty)).finish().unwrap();
+        assert_eq!(String::from_utf8(empty).unwrap(), "\u{feff}id,timestamp,talker,sender,type,content,media\r\n");
+    }
+}
@@ -0,0 +1,210 @@
+//! 导出格式
+//!
+//! 每种格式实现 [`Exporter`]，按时间顺序逐条写入消息，结束时补全文件尾部。
+//! [`available_formats`] 列出所有格式及其选项的 JSON Schema，界面据此生成导出对话框。
+
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+use ts_rs::TS;
+
+use super::flat::{CsvExporter, JsonLinesExporter};
+use super::html::HtmlExporter;
+use super::ExportOptions;
+use crate::errors::{MwxDumpError, Result};
+use crate::models::Message;
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// JSON 数组
+    Json,
+    /// 纯文本聊天记录
+    Txt,
+    /// 可离线打开的 HTML 页面，图片内嵌
+    Html,
+    /// JSON Lines，每行一条消息，字段见 [`super::flat`]
+    Jsonl,
+    /// CSV 表格，字段见 [`super::flat`]
+    Csv,
+}
+
+impl ExportFormat {
+    /// 所有导出格式
+    pub const ALL: [ExportFormat; 5] = [
+        ExportFormat::Json,
+        ExportFormat::Txt,
+        ExportFormat::Html,
+        ExportFormat::Jsonl,
+        ExportFormat::Csv,
+    ];
+
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "JSON",
+            ExportFormat::Txt => "纯文本",
+            ExportFormat::Html => "网页",
+            ExportFormat::Jsonl => "JSON Lines",
+            ExportFormat::Csv => "CSV 表格",
+        }
+    }
+
+    /// 格式说明和选项的 JSON Schema
+    ///
+    /// 新格式有专属选项时在通用选项的 `properties` 中补充。
+    pub fn info(&self) -> ExportFormatInfo {
+        ExportFormatInfo {
+            format: *self,
+            label: self.label(),
+            extension: self.extension(),
+            options_schema: ExportOptions::json_schema(),
+        }
+    }
+
+    /// 文件扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Txt => "txt",
+            ExportFormat::Html => "html",
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::Csv => "csv",
+        }
+    }
+
+    /// 创建写入 `writer` 的导出器，`output_dir` 为导出目录，媒体路径相对该目录
+    pub fn exporter<W: Write + Send + 'static>(&self, writer: W, output_dir: &Path) -> Box<dyn Exporter> {
+        match self {
+            ExportFormat::Json => Box::new(JsonExporter::new(writer)),
+            ExportFormat::Txt => Box::new(TextExporter::new(writer)),
+            ExportFormat::Html => Box::new(HtmlExporter::new(writer, output_dir)),
+            ExportFormat::Jsonl => Box::new(JsonLinesExporter::new(writer)),
+            ExportFormat::Csv => Box::new(CsvExporter::new(writer)),
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = MwxDumpError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(ExportFormat::Json),
+            "txt" | "text" => Ok(ExportFormat::Txt),
+            "html" | "htm" => Ok(ExportFormat::Html),
+            "jsonl" | "ndjson" => Ok(ExportFormat::Jsonl),
+            "csv" => Ok(ExportFormat::Csv),
+            _ => Err(MwxDumpError::Other(anyhow::anyhow!("不支持的导出格式: {}", s))),
+        }
+    }
+}
+
+/// 导出格式说明
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export)]
+pub struct ExportFormatInfo {
+    /// 格式
+    pub format: ExportFormat,
+    /// 显示名称
+    pub label: &'static str,
+    /// 文件扩展名
+    pub extension: &'static str,
+    /// 导出选项的 JSON Schema
+    pub options_schema: serde_json::Value,
+}
+
+/// 所有可用的导出格式
+pub fn available_formats() -> Vec<ExportFormatInfo> {
+    ExportFormat::ALL.iter().map(ExportFormat::info).collect()
+}
+
+/// 导出的单条消息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportRecord {
+    /// 消息
+    #[serde(flatten)]
+    pub message: Message,
+    /// 媒体文件相对导出目录的路径
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media: Option<String>,
+}
+
+/// 导出器
+pub trait Exporter: Send {
+    /// 写入一条消息
+    fn write(&mut self, record: &ExportRecord) -> Result<()>;
+
+    /// 写入文件尾部并刷新
+    fn finish(self: Box<Self>) -> Result<()>;
+}
+
+/// JSON 数组导出器
+pub struct JsonExporter<W: Write> {
+    writer: W,
+    count: u64,
+}
+
+impl<W: Write> JsonExporter<W> {
+    /// 写入 `writer`
+    pub fn new(writer: W) -> Self {
+        Self { writer, count: 0 }
+    }
+}
+
+impl<W: Write + Send> Exporter for JsonExporter<W> {
+    fn write(&mut self, record: &ExportRecord) -> Result<()> {
+        self.writer.write_all(if self.count == 0 { b"[\n" } else { b",\n" })?;
+        serde_json::to_writer(&mut self.writer, record)?;
+        self.count += 1;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        self.writer.write_all(if self.count == 0 { b"[]\n" } else { b"\n]\n" })?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// 纯文本导出器，每条消息一行：`时间 发送者: 内容`
+pub struct TextExporter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> TextExporter<W> {
+    /// 写入 `writer`
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+}
+
+impl<W: Write + Send> Exporter for TextExporter<W> {
+    fn write(&mut self, record: &ExportRecord) -> Result<()> {
+        let message = &record.message;
+        let sender = message.sender_name.as_deref().unwrap_or(&message.sender);
+        write!(
+            self.writer,
+            "{} {}: {}",
+            message.time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S"),
+            sender,
+            message.content
+        )?;
+        if let Some(media) = &record.media {
+            write!(self.writer, " [{}]", media)?;
+        }
+        if let Some(transcript) = &message.transcript {
+            write!(self.writer, " ({})", transcript)?;
+        }
+        writeln!(self.writer)?;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
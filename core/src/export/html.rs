@@ -0,0 +1,240 @@
+//! HTML 导出
+//!
+//! 把会话渲染为单个可离线打开的 HTML 文件：样式内联，消息按气泡排列，自己发送的靠右，
+//! 日期变化处插入分隔；图片以 data URI 嵌入，语音以 `<audio>` 播放，视频、文件和过大的图片以相对路径链接。
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{Local, NaiveDate};
+use std::io::Write;
+use std::path::PathBuf;
+
+use super::format::{ExportRecord, Exporter};
+use crate::errors::Result;
+use crate::models::Message;
+
+/// 超过该大小的图片不嵌入，改为链接
+pub const MAX_INLINE_IMAGE_BYTES: u64 = 5 * 1024 * 1024;
+
+const MSG_TYPE_IMAGE: i64 = 3;
+
+const MSG_TYPE_VOICE: i64 = 34;
+
+const MSG_TYPE_SYSTEM: i64 = 10000;
+
+const STYLE: &str = "\
+body{margin:0;background:#ededed;font:14px/1.5 -apple-system,'PingFang SC','Microsoft YaHei',sans-serif;color:#111}\
+header{position:sticky;top:0;background:#f7f7f7;border-bottom:1px solid #ddd;padding:12px 16px;font-size:16px;font-weight:600;text-align:center}\
+main{max-width:760px;margin:0 auto;padding:16px}\
+.day,.system{text-align:center;color:#999;font-size:12px;margin:16px 0}\
+.msg{display:flex;flex-direction:column;align-items:flex-start;margin:8px 0}\
+.msg.self{align-items:flex-end}\
+.meta{color:#888;font-size:12px;margin:0 4px 2px}\
+.bubble{max-width:70%;background:#fff;border-radius:6px;padding:8px 12px;white-space:pre-wrap;word-break:break-word}\
+.self .bubble{background:#95ec69}\
+.bubble img{display:block;max-width:100%;border-radius:4px}\
+.bubble audio{display:block;max-width:100%}\
+.transcript{color:#666;font-size:12px;margin-top:4px}\
+footer{text-align:center;color:#aaa;font-size:12px;padding:16px}";
+
+/// HTML 导出器
+pub struct HtmlExporter<W: Write> {
+    writer: W,
+    /// 导出目录，媒体路径相对该目录
+    output_dir: PathBuf,
+    header_written: bool,
+    last_day: Option<NaiveDate>,
+    count: u64,
+}
+
+impl<W: Write> HtmlExporter<W> {
+    /// 写入 `writer`，`output_dir` 用于读取需要嵌入的图片
+    pub fn new(writer: W, output_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            writer,
+            output_dir: output_dir.into(),
+            header_written: false,
+            last_day: None,
+            count: 0,
+        }
+    }
+
+    /// 写入文件头，标题取自第一条消息的会话名称
+    fn write_header(&mut self, title: &str) -> Result<()> {
+        write!(
+            self.writer,
+            "<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+             <title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n<header>{title}</header>\n<main>\n",
+            title = escape(title),
+        )?;
+        self.header_written = true;
+        Ok(())
+    }
+
+    /// 消息主体：图片嵌入，语音可播放，其他媒体链接，文本转义
+    fn body(&self, record: &ExportRecord) -> String {
+        let message = &record.message;
+        let Some(media) = &record.media else {
+            return escape(&message.content);
+        };
+        let link = format!("<a href=\"{0}\">{0}</a>", escape(media));
+        if message.msg_type == MSG_TYPE_VOICE {
+            return format!("<audio controls preload=\"none\" src=\"{}\"></audio>", escape(media));
+        }
+        if message.msg_type != MSG_TYPE_IMAGE {
+            return link;
+        }
+        match self.inline_image(media) {
+            Some(src) => format!("<img src=\"{}\" alt=\"图片\" loading=\"lazy\">", src),
+            None => link,
+        }
+    }
+
+    /// 读取图片并编码为 data URI，文件过大或不是可识别的图片格式时返回 `None`
+    fn inline_image(&self, media: &str) -> Option<String> {
+        let path = self.output_dir.join(media);
+        let size = std::fs::metadata(&path).ok()?.len();
+        if size > MAX_INLINE_IMAGE_BYTES {
+            return None;
+        }
+        let data = std::fs::read(&path).ok()?;
+        let mime = image_mime(&data)?;
+        Some(format!("data:{};base64,{}", mime, STANDARD.encode(&data)))
+    }
+}
+
+impl<W: Write + Send> Exporter for HtmlExporter<W> {
+    fn write(&mut self, record: &ExportRecord) -> Result<()> {
+        let message = &record.message;
+        if !self.header_written {
+            self.write_header(message.talker_name.as_deref().unwrap_or(&message.talker))?;
+        }
+        let time = message.time.with_timezone(&Local);
+        if self.last_day != Some(time.date_naive()) {
+            self.last_day = Some(time.date_naive());
+            writeln!(self.writer, "<div class=\"day\">{}</div>", time.format("%Y-%m-%d"))?;
+        }
+        self.count += 1;
+
+        if message.msg_type == MSG_TYPE_SYSTEM {
+            writeln!(self.writer, "<div class=\"system\">{}</div>", escape(&message.content))?;
+            return Ok(());
+        }
+        let class = if message.is_self { "msg self" } else { "msg" };
+        write!(
+            self.writer,
+            "<div class=\"{}\"><div class=\"meta\">{} <time datetime=\"{}\">{}</time></div><div class=\"bubble\">{}",
+            class,
+            escape(sender_name(message)),
+            message.time.to_rfc3339(),
+            time.format("%H:%M:%S"),
+            self.body(record),
+        )?;
+        if let Some(transcript) = &message.transcript {
+            write!(self.writer, "<div class=\"transcript\">{}</div>", escape(transcript))?;
+        }
+        writeln!(self.writer, "</div></div>")?;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        if !self.header_written {
+            self.write_header("")?;
+        }
+        write!(
+            self.writer,
+            "</main>\n<footer>共 {} 条消息 · 由 mwxdump 导出于 {}</footer>\n</body>\n</html>\n",
+            self.count,
+            Local::now().format("%Y-%m-%d %H:%M"),
+        )?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// 发送者名称，没有解析到名称时使用 wxid
+fn sender_name(message: &Message) -> &str {
+    message
+        .sender_name
+        .as_deref()
+        .filter(|name| !name.is_empty())
+        .unwrap_or(&message.sender)
+}
+
+/// 转义 HTML 特殊字符
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// 按文件头识别浏览器可直接显示的图片格式
+fn image_mime(data: &[u8]) -> Option<&'static str> {
+    match data {
+        [0xFF, 0xD8, 0xFF, ..] => Some("image/jpeg"),
+        [0x89, b'P', b'N', b'G', ..] => Some("image/png"),
+        [b'G', b'I', b'F', b'8', ..] => Some("image/gif"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+        [b'B', b'M', ..] => Some("image/bmp"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_export() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("a_files")).unwrap();
+        std::fs::write(dir.path().join("a_files/1.jpg"), [0xFF, 0xD8, 0xFF, 0xE0, 0, 0]).unwrap();
+        std::fs::write(dir.path().join("a_files/2.dat"), [0x12, 0x34]).unwrap();
+
+        let record = |msg_type: i64, content: &str, media: Option<&str>, is_self: bool| {
+            let mut message = Message::new();
+            message.talker = "wxid_a".to_string();
+            message.talker_name = Some("老友".to_string());
+            message.sender = if is_self { "wxid_self" } else { "wxid_a" }.to_string();
+            message.is_self = is_self;
+            message.msg_type = msg_type;
+            message.content = content.to_string();
+            ExportRecord {
+                message,
+                media: media.map(str::to_string),
+            }
+        };
+
+        let mut output = Vec::new();
+        let mut exporter = Box::new(HtmlExporter::new(&mut output, dir.path()));
+        exporter.write(&record(1, "<b>你好</b>", None, false)).unwrap();
+        exporter.write(&record(3, "", Some("a_files/1.jpg"), true)).unwrap();
+        exporter.write(&record(3, "", Some("a_files/2.dat"), false)).unwrap();
+        exporter.write(&record(34, "", Some("a_files/voice_7.mp3"), false)).unwrap();
+        exporter.write(&record(10000, "你撤回了一条消息", None, true)).unwrap();
+        exporter.finish().unwrap();
+
+        let html = String::from_utf8(output).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>老友</title>"));
+        assert!(html.contains("&lt;b&gt;你好&lt;/b&gt;"));
+        assert!(html.contains("<img src=\"data:image/jpeg;base64,/9j/4AAA\""));
+        assert!(html.contains("<a href=\"a_files/2.dat\">"));
+        assert!(html.contains("<audio controls preload=\"none\" src=\"a_files/voice_7.mp3\"></audio>"));
+        assert!(html.contains("<div class=\"system\">你撤回了一条消息</div>"));
+        assert_eq!(html.matches("class=\"msg self\"").count(), 1);
+        assert_eq!(html.matches("class=\"day\"").count(), 1);
+        assert!(html.contains("共 5 条消息"));
+        assert!(html.trim_end().ends_with("</html>"));
+    }
+}
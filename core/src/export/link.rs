@@ -0,0 +1,47 @@
+//! 应用内链接
+//!
+//! `mwxdump://conversation/<wxid>` 由桌面 UI 注册为协议处理程序，导出的文件可据此链接回应用中的会话。
+
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+/// 协议名
+pub const LINK_SCHEME: &str = "mwxdump";
+
+/// 会话链接前缀
+const CONVERSATION_PREFIX: &str = "mwxdump://conversation/";
+
+/// wxid 中保留不编码的字符
+const WXID_SAFE: &AsciiSet = &NON_ALPHANUMERIC.remove(b'_').remove(b'-').remove(b'.').remove(b'@');
+
+/// 会话链接
+pub fn conversation_link(wxid: &str) -> String {
+    format!("{}{}", CONVERSATION_PREFIX, utf8_percent_encode(wxid, WXID_SAFE))
+}
+
+/// 从会话链接中取出 wxid，不是会话链接时返回 `None`
+pub fn parse_conversation_link(link: &str) -> Option<String> {
+    let rest = link.strip_prefix(CONVERSATION_PREFIX)?;
+    let encoded = rest.split(['?', '#']).next().unwrap_or_default().trim_end_matches('/');
+    if encoded.is_empty() || encoded.contains('/') {
+        return None;
+    }
+    Some(percent_decode_str(encoded).decode_utf8().ok()?.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversation_link_round_trip() {
+        assert_eq!(conversation_link("wxid_friend"), "mwxdump://conversation/wxid_friend");
+        assert_eq!(conversation_link("123@chatroom"), "mwxdump://conversation/123@chatroom");
+        assert_eq!(conversation_link("a b/c"), "mwxdump://conversation/a%20b%2Fc");
+        assert_eq!(parse_conversation_link(&conversation_link("a b/c")).as_deref(), Some("a b/c"));
+
+        assert_eq!(parse_conversation_link("mwxdump://conversation/123%40chatroom/?from=html").as_deref(), Some("123@chatroom"));
+        assert_eq!(parse_conversation_link("mwxdump://conversation/"), None);
+        assert_eq!(parse_conversation_link("mwxdump://conversation/a/b"), None);
+        assert_eq!(parse_conversation_link("https://example.com/conversation/wxid"), None);
+    }
+}
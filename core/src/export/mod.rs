@@ -0,0 +1,635 @@
+//! 聊天记录导出
+//!
+//! [`ExportService`] 是 CLI、HTTP 服务和 UI 共用的高层导出接口：按会话分页读取消息、
+//! 补全联系人名称、经插件链处理、定位并复制媒体文件，再交给对应格式的导出器写入文件，
+//! 过程中向事件总线发布进度。每个会话完成后写入完成标记，续导时跳过已完成的会话。
+//! 指定语音格式时，语音消息从媒体数据库中取出并转码后与其他媒体放在一起，HTML 中可直接播放。
+//! [`ExportPipeline`] 可以先把所需数据库解密到临时目录再导出，不保留解密副本。
+
+pub mod checkpoint;
+pub mod filename;
+pub mod flat;
+pub mod format;
+pub mod html;
+pub mod link;
+pub mod pipeline;
+
+pub use checkpoint::Checkpoint;
+pub use filename::{sanitize_filename, FileNameMap};
+pub use flat::{CsvExporter, JsonLinesExporter, FLAT_COLUMNS};
+pub use format::{available_formats, ExportFormat, ExportFormatInfo, ExportRecord, Exporter};
+pub use html::HtmlExporter;
+pub use link::{conversation_link, parse_conversation_link};
+pub use pipeline::ExportPipeline;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use tokio_util::sync::CancellationToken;
+use ts_rs::TS;
+
+use crate::errors::{MwxDumpError, Result};
+use crate::events::{Event, EventBus};
+use crate::models::Message;
+use crate::plugins::PluginChain;
+use crate::wechat::datadir::SourceGuard;
+use crate::wechat::db::chatrooms::ChatRoomRepository;
+use crate::wechat::db::contacts::ContactRepository;
+use crate::wechat::db::hardlink::HARDLINK_DB_PATH;
+use crate::wechat::db::messages::MessageRepository;
+use crate::wechat::db::voices::VoiceRepository;
+use crate::wechat::db::SqliteDataSource;
+use crate::wechat::media::voice::MSG_TYPE_VOICE;
+use crate::wechat::media::{AudioFormat, MediaResolver, VoiceConfig, VoiceTranscoder};
+
+/// 导出选项
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct ExportOptions {
+    /// 输出目录
+    pub output_dir: PathBuf,
+    /// 复制消息引用的图片、视频和文件
+    pub include_media: bool,
+    /// 每次从数据库读取的消息数
+    pub page_size: u32,
+    /// 以联系人备注或昵称命名导出文件，默认使用 wxid
+    pub name_by_contact: bool,
+    /// 跳过已有有效完成标记的会话，未完成的会话重新导出
+    pub resume: bool,
+    /// 导出语音消息的音频格式，为空时不导出语音
+    pub voice_format: Option<AudioFormat>,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            output_dir: PathBuf::from("export"),
+            include_media: false,
+            page_size: 1000,
+            name_by_contact: false,
+            resume: false,
+            voice_format: None,
+        }
+    }
+}
+
+impl ExportOptions {
+    /// 选项的 JSON Schema，默认值取自 [`ExportOptions::default`]
+    pub fn json_schema() -> serde_json::Value {
+        let defaults = Self::default();
+        serde_json::json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": "ExportOptions",
+            "type": "object",
+            "properties": {
+                "output_dir": {
+                    "type": "string",
+                    "title": "输出目录",
+                    "default": defaults.output_dir,
+                },
+                "include_media": {
+                    "type": "boolean",
+                    "title": "复制媒体文件",
+                    "description": "复制消息引用的图片、视频和文件",
+                    "default": defaults.include_media,
+                },
+                "page_size": {
+                    "type": "integer",
+                    "title": "分页大小",
+                    "description": "每次从数据库读取的消息数",
+                    "minimum": 1,
+                    "default": defaults.page_size,
+                },
+                "name_by_contact": {
+                    "type": "boolean",
+                    "title": "按联系人命名",
+                    "description": "以联系人备注或昵称命名导出文件，默认使用 wxid",
+                    "default": defaults.name_by_contact,
+                },
+                "resume": {
+                    "type": "boolean",
+                    "title": "断点续导",
+                    "description": "跳过已完成的会话，未完成的会话重新导出",
+                    "default": defaults.resume,
+                },
+                "voice_format": {
+                    "type": ["string", "null"],
+                    "title": "语音格式",
+                    "description": "导出语音消息的音频格式，为空时不导出语音",
+                    "enum": ["silk", "wav", "mp3", null],
+                    "default": defaults.voice_format,
+                },
+            },
+            "additionalProperties": false,
+        })
+    }
+}
+
+/// 导出结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ExportSummary {
+    /// 导出文件
+    pub file: PathBuf,
+    /// 写入的消息数（不含被插件丢弃的）
+    #[ts(type = "number")]
+    pub messages: u64,
+    /// 复制的媒体文件数
+    #[ts(type = "number")]
+    pub media: u64,
+}
+
+/// 导出服务
+pub struct ExportService {
+    work_dir: PathBuf,
+    data_root: Option<PathBuf>,
+    self_wxid: Option<String>,
+    plugins: PluginChain,
+    events: EventBus,
+    voice: VoiceTranscoder,
+    cancel_token: CancellationToken,
+}
+
+impl ExportService {
+    /// `work_dir` 为解密输出目录
+    pub fn new(work_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            work_dir: work_dir.into(),
+            data_root: None,
+            self_wxid: None,
+            plugins: PluginChain::new(),
+            events: EventBus::new(),
+            voice: VoiceTranscoder::default(),
+            cancel_token: CancellationToken::new(),
+        }
+    }
+
+    /// 账号数据目录，导出媒体时需要
+    pub fn with_data_root(mut self, data_root: impl Into<PathBuf>) -> Self {
+        self.data_root = Some(data_root.into());
+        self
+    }
+
+    /// 当前账号的 wxid，用于标记自己发送的消息
+    pub fn with_self_wxid(mut self, wxid: impl Into<String>) -> Self {
+        self.self_wxid = Some(wxid.into());
+        self
+    }
+
+    /// 导出前对每条消息执行的插件
+    pub fn with_plugins(mut self, plugins: PluginChain) -> Self {
+        self.plugins = plugins;
+        self
+    }
+
+    /// 每处理完一页消息发布 [`Event::ExportProgress`]，每个会话完成后发布 [`Event::ExportFinished`]
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// 语音转码命令，导出语音时使用
+    pub fn with_voice_config(mut self, config: VoiceConfig) -> Self {
+        self.voice = VoiceTranscoder::new(config);
+        self
+    }
+
+    /// 设置取消令牌，取消后在下一页开始前返回 `MwxDumpError::Cancelled`
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel_token = token;
+        self
+    }
+
+    /// 导出一个会话到 `options.output_dir/{文件名}.{ext}`
+    ///
+    /// 文件名默认为 wxid，`name_by_contact` 时为联系人备注或昵称，均经 [`sanitize_filename`]
+    /// 清理并在输出目录的 `.checkpoints/.filenames.json` 中记录。完成后写入 [`Checkpoint`]，`options.resume`
+    /// 时已完成且文件校验一致的会话直接返回上次的结果。
+    pub async fn export_conversation(
+        &self,
+        wxid: &str,
+        format: ExportFormat,
+        options: &ExportOptions,
+    ) -> Result<ExportSummary> {
+        if let Some(data_root) = &self.data_root {
+            SourceGuard::new().with_root(data_root).check_output(&options.output_dir)?;
+        }
+        let mut repository = MessageRepository::open(&self.work_dir).await?;
+        if let Some(self_wxid) = &self.self_wxid {
+            repository = repository.with_self_wxid(self_wxid.clone());
+        }
+        let total = repository.count(wxid).await?;
+        let contacts = self.load_contacts().await;
+        let members = match wxid.ends_with("@chatroom") {
+            true => self.load_member_names(wxid).await,
+            false => HashMap::new(),
+        };
+        let resolver = match options.include_media {
+            true => self.media_resolver().await,
+            false => None,
+        };
+        let voices = match options.voice_format {
+            Some(format) => self.voice_repository().await.map(|voices| (voices, format)),
+            None => None,
+        };
+
+        std::fs::create_dir_all(&options.output_dir)?;
+        let display = match options.name_by_contact {
+            true => contacts.display_name(wxid).unwrap_or(wxid),
+            false => wxid,
+        };
+        let mut file_names = FileNameMap::load(&options.output_dir)?;
+        let base = file_names.assign(wxid, display);
+        file_names.save(&options.output_dir)?;
+        let media_dir = options.output_dir.join(format!("{}_files", base));
+        let file = options.output_dir.join(format!("{}.{}", base, format.extension()));
+        if options.resume {
+            match Checkpoint::load(&options.output_dir, wxid) {
+                Some(checkpoint) if checkpoint.summary.file == file && checkpoint.verify(format) => {
+                    tracing::info!("会话 {} 已导出，跳过", wxid);
+                    self.events.publish(Event::ExportFinished {
+                        wxid: wxid.to_string(),
+                        summary: checkpoint.summary.clone(),
+                    });
+                    return Ok(checkpoint.summary);
+                }
+                Some(_) => tracing::warn!("会话 {} 的导出文件与完成标记不一致，重新导出", wxid),
+                None if file.exists() => tracing::warn!("会话 {} 上次未导出完成，重新导出", wxid),
+                None => {}
+            }
+        }
+        Checkpoint::clear(&options.output_dir, wxid)?;
+        let mut exporter = format.exporter(BufWriter::new(File::create(&file)?), &options.output_dir);
+        tracing::info!("开始导出会话 {} ({} 条消息) -> {:?}", wxid, total, file);
+
+        let mut summary = ExportSummary {
+            file,
+            messages: 0,
+            media: 0,
+        };
+        let mut processed = 0;
+        let mut cursor = None;
+        loop {
+            if self.cancel_token.is_cancelled() {
+                return Err(MwxDumpError::Cancelled.into());
+            }
+            let page = repository.page(wxid, cursor, options.page_size.max(1)).await?;
+            let Some(last) = page.last() else {
+                break;
+            };
+            cursor = Some(last.seq);
+            processed += page.len() as u64;
+
+            for mut message in page {
+                fill_member_name(&mut message, &contacts, &members);
+                contacts.fill_names(&mut message);
+                let Some(message) = self.plugins.process(message) else {
+                    continue;
+                };
+                let media = match (&resolver, &voices) {
+                    (_, Some((voices, format))) if message.msg_type == MSG_TYPE_VOICE => {
+                        self.export_voice(voices, *format, &message, &media_dir, &options.output_dir)
+                            .await
+                    }
+                    (Some(resolver), _) => copy_media(resolver, &message, &media_dir, &options.output_dir).await,
+                    _ => None,
+                };
+                summary.media += media.is_some() as u64;
+                exporter.write(&ExportRecord { message, media })?;
+                summary.messages += 1;
+            }
+            self.events.publish(Event::ExportProgress {
+                wxid: wxid.to_string(),
+                processed,
+                total,
+            });
+        }
+
+        exporter.finish()?;
+        Checkpoint::new(wxid, format, &summary)?.save(&options.output_dir)?;
+        tracing::info!("会话 {} 导出完成: {} 条消息，{} 个媒体文件", wxid, summary.messages, summary.media);
+        self.events.publish(Event::ExportFinished {
+            wxid: wxid.to_string(),
+            summary: summary.clone(),
+        });
+        Ok(summary)
+    }
+
+    /// 用于解析显示名称的联系人，联系人数据库不可用时为空
+    async fn load_contacts(&self) -> ContactRepository {
+        ContactRepository::open(&self.work_dir).await.unwrap_or_default()
+    }
+
+    /// 群成员的群昵称，群聊数据不可用时为空
+    async fn load_member_names(&self, chatroom: &str) -> HashMap<String, String> {
+        match ChatRoomRepository::open(&self.work_dir).await {
+            Ok(chatrooms) => chatrooms.member_names(chatroom),
+            Err(e) => {
+                tracing::debug!("无法读取群聊 {} 的成员: {}", chatroom, e);
+                HashMap::new()
+            }
+        }
+    }
+
+    /// 创建媒体定位器，缺少数据目录或硬链接数据库时不导出媒体
+    async fn media_resolver(&self) -> Option<MediaResolver<SqliteDataSource>> {
+        let Some(data_root) = &self.data_root else {
+            tracing::warn!("未设置微信数据目录，跳过媒体文件");
+            return None;
+        };
+        match SqliteDataSource::open(&self.work_dir.join(HARDLINK_DB_PATH)).await {
+            Ok(hardlink) => Some(MediaResolver::new(data_root, hardlink)),
+            Err(e) => {
+                tracing::warn!("无法打开硬链接数据库，跳过媒体文件: {}", e);
+                None
+            }
+        }
+    }
+
+    /// 打开语音仓库，没有媒体数据库时不导出语音
+    async fn voice_repository(&self) -> Option<VoiceRepository> {
+        match VoiceRepository::open(&self.work_dir).await {
+            Ok(voices) if voices.shard_count() > 0 => Some(voices),
+            Ok(_) => {
+                tracing::warn!("工作目录中没有媒体数据库，跳过语音");
+                None
+            }
+            Err(e) => {
+                tracing::warn!("无法打开媒体数据库，跳过语音: {}", e);
+                None
+            }
+        }
+    }
+
+    /// 转码语音消息到媒体目录，返回相对导出目录的路径；失败时只记录日志
+    async fn export_voice(
+        &self,
+        voices: &VoiceRepository,
+        format: AudioFormat,
+        message: &Message,
+        media_dir: &Path,
+        output_dir: &Path,
+    ) -> Option<String> {
+        let data = match voices.find(&message.talker, message.time.timestamp()).await {
+            Ok(Some(data)) => data,
+            Ok(None) => return None,
+            Err(e) => {
+                tracing::warn!("读取语音 {} 失败: {}", message.seq, e);
+                return None;
+            }
+        };
+        let target = media_dir.join(format!("voice_{}.{}", message.seq, format.extension()));
+        if let Err(e) = self.voice.transcode(&data, format, &target).await {
+            tracing::warn!("转码语音 {} 失败: {}", message.seq, e);
+            return None;
+        }
+        let relative = target.strip_prefix(output_dir).unwrap_or(&target);
+        Some(relative.to_string_lossy().replace('\\', "/"))
+    }
+}
+
+/// 群消息发送者的名称：联系人备注优先，其次群昵称，都没有时留给联系人昵称
+fn fill_member_name(message: &mut Message, contacts: &ContactRepository, members: &HashMap<String, String>) {
+    if message.sender_name.is_some() {
+        return;
+    }
+    let remark = contacts
+        .get(&message.sender)
+        .and_then(|contact| contact.remark.clone())
+        .filter(|remark| !remark.is_empty());
+    message.sender_name = remark.or_else(|| members.get(&message.sender).cloned());
+}
+
+/// 复制消息引用的媒体文件，返回相对导出目录的路径；找不到文件时只记录日志
+async fn copy_media(
+    resolver: &MediaResolver<SqliteDataSource>,
+    message: &Message,
+    media_dir: &Path,
+    output_dir: &Path,
+) -> Option<String> {
+    let source = match resolver.resolve(message).await {
+        Ok(Some(path)) if path.is_file() => path,
+        Ok(_) => return None,
+        Err(e) => {
+            tracing::warn!("定位消息 {} 的媒体失败: {}", message.seq, e);
+            return None;
+        }
+    };
+    let target = media_dir.join(source.file_name()?);
+    let copied = std::fs::create_dir_all(media_dir).and_then(|_| std::fs::copy(&source, &target));
+    if let Err(e) = copied {
+        tracing::warn!("复制媒体文件失败: {:?} - {}", source, e);
+        return None;
+    }
+    let relative = target.strip_prefix(output_dir).unwrap_or(&target);
+    Some(relative.to_string_lossy().replace('\\', "/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wechat::db::messages::tests::create_message_dbs;
+
+    #[test]
+    fn test_format_schemas_match_options() {
+        let formats = available_formats();
+        assert_eq!(formats.len(), ExportFormat::ALL.len());
+        assert_eq!(formats[0].extension, "json");
+
+        // Schema 的属性和默认值与选项的序列化结果一致
+        let defaults = serde_json::to_value(ExportOptions::default()).unwrap();
+        for info in &formats {
+            let properties = info.options_schema["properties"].as_object().unwrap();
+            assert_eq!(properties.len(), defaults.as_object().unwrap().len());
+            for (key, value) in defaults.as_object().unwrap() {
+                assert_eq!(&properties[key]["default"], value, "{}", key);
+            }
+        }
+    }
+
+    #[test]
+    fn test_member_name_prefers_remark() {
+        use crate::models::Contact;
+
+        let contacts = ContactRepository::from_contacts(vec![Contact {
+            remark: Some("王哥".to_string()),
+            ..Contact::new("wxid_a".to_string())
+        }]);
+        let members = HashMap::from([
+            ("wxid_a".to_string(), "班长".to_string()),
+            ("wxid_b".to_string(), "学委".to_string()),
+        ]);
+        let name = |sender: &str| {
+            let mut message = Message::new();
+            message.sender = sender.to_string();
+            fill_member_name(&mut message, &contacts, &members);
+            message.sender_name
+        };
+        assert_eq!(name("wxid_a").as_deref(), Some("王哥"));
+        assert_eq!(name("wxid_b").as_deref(), Some("学委"));
+        assert_eq!(name("wxid_c"), None);
+    }
+
+    #[tokio::test]
+    async fn test_export_conversation() {
+        let work = tempfile::tempdir().unwrap();
+        create_message_dbs(work.path()).await;
+        let out = tempfile::tempdir().unwrap();
+        let options = ExportOptions {
+            output_dir: out.path().to_path_buf(),
+            page_size: 2,
+            ..Default::default()
+        };
+
+        let events = EventBus::new();
+        let mut receiver = events.subscribe();
+        let service = ExportService::new(work.path())
+            .with_self_wxid("wxid_self")
+            .with_events(events);
+        let summary = service
+            .export_conversation("wxid_friend", ExportFormat::Json, &options)
+            .await
+            .unwrap();
+        assert_eq!(summary.messages, 3);
+        let mut last = 0;
+        loop {
+            match receiver.try_recv() {
+                Some(Event::ExportProgress { processed, total, .. }) => {
+                    assert_eq!(total, 3);
+                    last = processed;
+                }
+                Some(Event::ExportFinished { summary: finished, .. }) => {
+                    assert_eq!(finished, summary);
+                    break;
+                }
+                other => panic!("意外的事件: {:?}", other),
+            }
+        }
+        assert_eq!(last, 3);
+        let json: Vec<serde_json::Value> =
+            serde_json::from_str(&std::fs::read_to_string(&summary.file).unwrap()).unwrap();
+        assert_eq!(json[2]["content"], "压缩的消息");
+        assert_eq!(json[0]["is_self"], true);
+
+        let summary = ExportService::new(work.path())
+            .export_conversation("nobody", ExportFormat::Json, &options)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(summary.file).unwrap(), "[]\n");
+
+        let summary = ExportService::new(work.path())
+            .export_conversation("123@chatroom", ExportFormat::Txt, &options)
+            .await
+            .unwrap();
+        assert!(std::fs::read_to_string(summary.file).unwrap().ends_with("wxid_b: hi all\n"));
+
+        let summary = ExportService::new(work.path())
+            .export_conversation("wxid_friend", ExportFormat::Html, &options)
+            .await
+            .unwrap();
+        assert_eq!(summary.file.extension().unwrap(), "html");
+        let html = std::fs::read_to_string(summary.file).unwrap();
+        assert!(html.contains("在吗"));
+        assert!(html.contains("共 3 条消息"));
+
+        let summary = ExportService::new(work.path())
+            .export_conversation("wxid_friend", ExportFormat::Jsonl, &options)
+            .await
+            .unwrap();
+        assert_eq!(summary.file.extension().unwrap(), "jsonl");
+        assert_eq!(std::fs::read_to_string(summary.file).unwrap().lines().count(), 3);
+
+        let summary = ExportService::new(work.path())
+            .export_conversation("wxid_friend", ExportFormat::Csv, &options)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(summary.file).unwrap().lines().count(), 4);
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let cancelled = ExportService::new(work.path())
+            .with_cancellation(token)
+            .export_conversation("wxid_friend", ExportFormat::Txt, &options)
+            .await;
+        assert!(matches!(
+            cancelled.unwrap_err().downcast_ref::<MwxDumpError>(),
+            Some(MwxDumpError::Cancelled)
+        ));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_export_voice_messages() {
+        use crate::wechat::db::voices::tests::{create_media_db, VOICE_DATA};
+
+        let work = tempfile::tempdir().unwrap();
+        create_message_dbs(work.path()).await;
+        create_media_db(work.path()).await;
+        let out = tempfile::tempdir().unwrap();
+        let options = ExportOptions {
+            output_dir: out.path().to_path_buf(),
+            voice_format: Some(AudioFormat::Wav),
+            ..Default::default()
+        };
+        let copy = vec!["{input}".to_string(), "{output}".to_string()];
+        let service = ExportService::new(work.path()).with_voice_config(VoiceConfig {
+            decoder: "cp".to_string(),
+            decoder_args: copy.clone(),
+            encoder: "cp".to_string(),
+            encoder_args: copy,
+        });
+        let summary = service
+            .export_conversation("wxid_friend", ExportFormat::Html, &options)
+            .await
+            .unwrap();
+        assert_eq!(summary.media, 1);
+        let html = std::fs::read_to_string(&summary.file).unwrap();
+        assert!(html.contains("<audio controls preload=\"none\" src=\"wxid_friend_files/voice_3000.wav\"></audio>"));
+        let wav = std::fs::read(out.path().join("wxid_friend_files/voice_3000.wav")).unwrap();
+        assert_eq!(&wav[44..], VOICE_DATA);
+    }
+
+    #[tokio::test]
+    async fn test_resume_skips_finished_conversations() {
+        let work = tempfile::tempdir().unwrap();
+        create_message_dbs(work.path()).await;
+        let out = tempfile::tempdir().unwrap();
+        let options = ExportOptions {
+            output_dir: out.path().to_path_buf(),
+            resume: true,
+            ..Default::default()
+        };
+        let events = EventBus::new();
+        let service = ExportService::new(work.path()).with_events(events.clone());
+        let summary = service
+            .export_conversation("wxid_friend", ExportFormat::Json, &options)
+            .await
+            .unwrap();
+        let checkpoint = Checkpoint::load(out.path(), "wxid_friend").unwrap();
+        assert!(checkpoint.verify(ExportFormat::Json));
+        assert!(!checkpoint.verify(ExportFormat::Txt));
+
+        // 已完成的会话直接返回上次的结果，不再读取消息
+        let mut receiver = events.subscribe();
+        let resumed = service
+            .export_conversation("wxid_friend", ExportFormat::Json, &options)
+            .await
+            .unwrap();
+        assert_eq!(resumed, summary);
+        assert!(matches!(receiver.try_recv(), Some(Event::ExportFinished { .. })));
+
+        // 文件只写了一部分时重新导出
+        let content = std::fs::read_to_string(&summary.file).unwrap();
+        std::fs::write(&summary.file, &content[..content.len() / 2]).unwrap();
+        let mut receiver = events.subscribe();
+        service
+            .export_conversation("wxid_friend", ExportFormat::Json, &options)
+            .await
+            .unwrap();
+        assert!(matches!(receiver.try_recv(), Some(Event::ExportProgress { .. })));
+        assert_eq!(std::fs::read_to_string(&summary.file).unwrap(), content);
+    }
+}
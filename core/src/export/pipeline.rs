@@ -0,0 +1,90 @@
+//! 边解密边导出
+//!
+//! 只需要导出结果时，把导出用到的数据库解密到临时会话目录（见 [`DecryptedSession`]），
+//! 导出器直接读取，结束后清除，磁盘上不保留完整的解密副本。
+
+use std::path::PathBuf;
+use tokio_util::sync::CancellationToken;
+
+use crate::errors::Result;
+use crate::wechat::datadir::DbCategory;
+use crate::wechat::decrypt::DecryptedSession;
+
+/// 导出用到的数据库类别；导出媒体时还需要硬链接索引
+pub fn required_categories(include_media: bool) -> Vec<DbCategory> {
+    let mut categories = vec![DbCategory::Message, DbCategory::Contact];
+    if include_media {
+        categories.push(DbCategory::Hardlink);
+    }
+    categories
+}
+
+/// 解密导出流水线
+pub struct ExportPipeline {
+    input: PathBuf,
+    key: Vec<u8>,
+    threads: Option<usize>,
+    cancel_token: CancellationToken,
+}
+
+impl ExportPipeline {
+    /// `input` 为加密的账号数据目录
+    pub fn new(input: impl Into<PathBuf>, key: Vec<u8>) -> Self {
+        Self {
+            input: input.into(),
+            key,
+            threads: None,
+            cancel_token: CancellationToken::new(),
+        }
+    }
+
+    /// 解密线程数，默认为 CPU 核心数
+    pub fn with_threads(mut self, threads: Option<usize>) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// 设置取消令牌，取消后停止解密
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel_token = token;
+        self
+    }
+
+    /// 解密导出所需的数据库，返回的会话目录可作为 [`ExportService`](super::ExportService) 的工作目录，
+    /// 会话释放时清除解密数据
+    pub async fn unlock(&self, include_media: bool) -> Result<DecryptedSession> {
+        let categories = required_categories(include_media);
+        tracing::info!("流水线导出，临时解密 {} 类数据库", categories.len());
+        DecryptedSession::unlock(
+            &self.input,
+            self.key.clone(),
+            categories,
+            self.threads,
+            self.cancel_token.clone(),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_pipeline_unlocks_required_categories() {
+        assert!(!required_categories(false).contains(&DbCategory::Hardlink));
+
+        let input = tempfile::tempdir().unwrap();
+        let session = ExportPipeline::new(input.path(), vec![0u8; 32])
+            .with_threads(Some(1))
+            .unlock(true)
+            .await
+            .unwrap();
+        assert_eq!(session.categories(), required_categories(true));
+        let path = session.path().to_path_buf();
+        assert!(path.is_dir());
+
+        session.lock().await;
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,334 @@
+//! 后台任务管理
+//!
+//! 备份等耗时操作以任务形式在后台运行，调用方拿到任务ID后轮询状态，
+//! HTTP 服务和 UI 共用同一套任务记录；定时触发见 [`scheduler`]。任务结束时向事件总线发布
+//! [`Event::JobFinished`]，用于桌面通知等。已结束的任务最多保留 [`MAX_FINISHED_JOBS`] 个、
+//! [`FINISHED_JOB_TTL_HOURS`] 小时，超出后从旧到新清除，运行中的任务不受影响。
+
+pub mod scheduler;
+
+pub use scheduler::Scheduler;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::errors::{MwxDumpError, Result};
+use crate::events::{Event, EventBus};
+
+/// 任务ID
+pub type JobId = Uuid;
+
+/// 默认保留的已结束任务数
+pub const MAX_FINISHED_JOBS: usize = 200;
+
+/// 已结束任务的保留时间（小时）
+pub const FINISHED_JOB_TTL_HOURS: i64 = 24;
+
+/// 任务状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    /// 运行中
+    Running,
+    /// 成功完成
+    Succeeded,
+    /// 执行失败
+    Failed,
+    /// 已取消
+    Cancelled,
+}
+
+impl JobStatus {
+    /// 任务是否已结束
+    pub fn is_finished(&self) -> bool {
+        !matches!(self, JobStatus::Running)
+    }
+}
+
+/// 任务信息
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct JobInfo {
+    /// 任务ID
+    pub id: JobId,
+    /// 任务类型，如 `backup`
+    pub kind: String,
+    /// 当前状态
+    pub status: JobStatus,
+    /// 创建时间
+    pub created_at: DateTime<Utc>,
+    /// 结束时间
+    pub finished_at: Option<DateTime<Utc>>,
+    /// 失败原因
+    pub error: Option<String>,
+    /// 任务结果，如导出统计
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub output: Option<serde_json::Value>,
+}
+
+impl JobInfo {
+    /// 任务耗时，未结束时为 `None`
+    pub fn duration(&self) -> Option<chrono::Duration> {
+        self.finished_at.map(|finished| finished - self.created_at)
+    }
+}
+
+struct JobEntry {
+    info: JobInfo,
+    cancel_token: CancellationToken,
+    status_rx: watch::Receiver<JobStatus>,
+}
+
+/// 任务管理器
+///
+/// 克隆后共享同一份任务记录。
+#[derive(Clone)]
+pub struct JobManager {
+    jobs: Arc<RwLock<HashMap<JobId, JobEntry>>>,
+    events: EventBus,
+    max_finished: usize,
+}
+
+impl Default for JobManager {
+    fn default() -> Self {
+        Self {
+            jobs: Arc::default(),
+            events: EventBus::default(),
+            max_finished: MAX_FINISHED_JOBS,
+        }
+    }
+}
+
+impl JobManager {
+    /// 创建任务管理器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 保留的已结束任务数
+    pub fn with_max_finished(mut self, max_finished: usize) -> Self {
+        self.max_finished = max_finished;
+        self
+    }
+
+    /// 向共享的事件总线发布任务结束事件，默认使用独立的总线
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// 任务结束事件所在的事件总线
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    /// 在后台启动任务
+    ///
+    /// `task` 收到任务专属的取消令牌；返回 `MwxDumpError::Cancelled` 时任务记为已取消。
+    pub fn spawn<F, Fut>(&self, kind: &str, task: F) -> JobId
+    where
+        F: FnOnce(CancellationToken) -> Fut,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.spawn_with_output(kind, task)
+    }
+
+    /// 在后台启动有结果的任务，成功时结果序列化后记入 [`JobInfo::output`]
+    pub fn spawn_with_output<F, Fut, T>(&self, kind: &str, task: F) -> JobId
+    where
+        F: FnOnce(CancellationToken) -> Fut,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+        T: Serialize,
+    {
+        let id = Uuid::new_v4();
+        let cancel_token = CancellationToken::new();
+        let (status_tx, status_rx) = watch::channel(JobStatus::Running);
+        let info = JobInfo {
+            id,
+            kind: kind.to_string(),
+            status: JobStatus::Running,
+            created_at: Utc::now(),
+            finished_at: None,
+            error: None,
+            output: None,
+        };
+        let mut jobs = self.jobs.write().unwrap();
+        prune_finished(&mut jobs, self.max_finished);
+        jobs.insert(
+            id,
+            JobEntry {
+                info,
+                cancel_token: cancel_token.clone(),
+                status_rx,
+            },
+        );
+        drop(jobs);
+        tracing::info!("任务 {} ({}) 已启动", id, kind);
+
+        let future = task(cancel_token);
+        let jobs = self.jobs.clone();
+        let events = self.events.clone();
+        let max_finished = self.max_finished;
+        tokio::spawn(async move {
+            let result = future.await;
+            let (status, error, output) = match result {
+                Ok(value) => (
+                    JobStatus::Succeeded,
+                    None,
+                    serde_json::to_value(value).ok().filter(|v| !v.is_null()),
+                ),
+                Err(e) if matches!(e.downcast_ref::<MwxDumpError>(), Some(MwxDumpError::Cancelled)) => {
+                    (JobStatus::Cancelled, None, None)
+                }
+                Err(e) => (JobStatus::Failed, Some(e.to_string()), None),
+            };
+            match &error {
+                Some(e) => tracing::warn!("任务 {} 失败: {}", id, e),
+                None => tracing::info!("任务 {} 结束: {:?}", id, status),
+            }
+            let info = {
+                let mut jobs = jobs.write().unwrap();
+                let info = jobs.get_mut(&id).map(|entry| {
+                    entry.info.status = status;
+                    entry.info.finished_at = Some(Utc::now());
+                    entry.info.error = error;
+                    entry.info.output = output;
+                    entry.info.clone()
+                });
+                prune_finished(&mut jobs, max_finished);
+                info
+            };
+            let _ = status_tx.send(status);
+            if let Some(job) = info {
+                events.publish(Event::JobFinished { job });
+            }
+        });
+
+        id
+    }
+
+    /// 获取任务信息
+    pub fn get(&self, id: &JobId) -> Option<JobInfo> {
+        self.jobs.read().unwrap().get(id).map(|entry| entry.info.clone())
+    }
+
+    /// 所有任务，按创建时间排序
+    pub fn list(&self) -> Vec<JobInfo> {
+        let mut jobs: Vec<JobInfo> = self
+            .jobs
+            .read()
+            .unwrap()
+            .values()
+            .map(|entry| entry.info.clone())
+            .collect();
+        jobs.sort_by_key(|job| job.created_at);
+        jobs
+    }
+
+    /// 请求取消任务，任务不存在或已结束时返回 `false`
+    pub fn cancel(&self, id: &JobId) -> bool {
+        match self.jobs.read().unwrap().get(id) {
+            Some(entry) if !entry.info.status.is_finished() => {
+                entry.cancel_token.cancel();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 等待任务结束并返回最终信息
+    pub async fn wait(&self, id: &JobId) -> Option<JobInfo> {
+        let mut status_rx = self.jobs.read().unwrap().get(id)?.status_rx.clone();
+        let _ = status_rx.wait_for(|status| status.is_finished()).await;
+        self.get(id)
+    }
+}
+
+/// 清除过期的已结束任务，并只保留最近结束的 `max_finished` 个
+fn prune_finished(jobs: &mut HashMap<JobId, JobEntry>, max_finished: usize) {
+    let expired = Utc::now() - chrono::Duration::hours(FINISHED_JOB_TTL_HOURS);
+    jobs.retain(|_, entry| entry.info.finished_at.is_none_or(|finished| finished > expired));
+    let mut finished: Vec<(DateTime<Utc>, JobId)> = jobs
+        .values()
+        .filter_map(|entry| entry.info.finished_at.map(|at| (at, entry.info.id)))
+        .collect();
+    if finished.len() <= max_finished {
+        return;
+    }
+    finished.sort();
+    for (_, id) in &finished[..finished.len() - max_finished] {
+        jobs.remove(id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::WeChatError;
+
+    #[tokio::test]
+    async fn test_job_lifecycle() {
+        let manager = JobManager::new();
+        let mut finished = manager.events().subscribe();
+
+        let ok = manager.spawn("backup", |_| async { Ok(()) });
+        assert_eq!(manager.wait(&ok).await.unwrap().status, JobStatus::Succeeded);
+        let Some(Event::JobFinished { job: event }) = finished.recv().await else {
+            panic!("应发布任务结束事件");
+        };
+        assert_eq!(event.id, ok);
+        assert!(event.duration().is_some());
+        assert!(event.output.is_none());
+
+        let counted = manager.spawn_with_output("export", |_| async { Ok(serde_json::json!({"messages": 3})) });
+        let info = manager.wait(&counted).await.unwrap();
+        assert_eq!(info.output.unwrap()["messages"], 3);
+
+        let failed = manager.spawn("backup", |_| async {
+            Err(WeChatError::DecryptionFailed("bad key".to_string()).into())
+        });
+        let info = manager.wait(&failed).await.unwrap();
+        assert_eq!(info.status, JobStatus::Failed);
+        assert!(info.error.unwrap().contains("bad key"));
+
+        let cancelled = manager.spawn("backup", |token| async move {
+            token.cancelled().await;
+            Err(MwxDumpError::Cancelled.into())
+        });
+        assert!(manager.cancel(&cancelled));
+        assert_eq!(manager.wait(&cancelled).await.unwrap().status, JobStatus::Cancelled);
+        assert!(!manager.cancel(&cancelled));
+
+        assert_eq!(manager.list().len(), 4);
+        assert!(manager.get(&Uuid::new_v4()).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_finished_jobs_are_evicted() {
+        let manager = JobManager::new().with_max_finished(2);
+        let running = manager.spawn("backup", |token| async move {
+            token.cancelled().await;
+            Ok(())
+        });
+        let mut finished = Vec::new();
+        for _ in 0..4 {
+            let id = manager.spawn("export", |_| async { Ok(()) });
+            manager.wait(&id).await.unwrap();
+            finished.push(id);
+        }
+        // 只保留最近结束的两个任务，运行中的任务不受影响
+        assert!(manager.get(&finished[0]).is_none() && manager.get(&finished[1]).is_none());
+        assert!(manager.get(&finished[2]).is_some() && manager.get(&finished[3]).is_some());
+        assert_eq!(manager.get(&running).unwrap().status, JobStatus::Running);
+        manager.cancel(&running);
+    }
+}
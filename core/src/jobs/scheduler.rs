@@ -0,0 +1,163 @@
+//! 定时任务
+//!
+//! 按固定间隔通过 [`JobManager`] 启动同一种任务，可暂停、恢复和立即触发。
+//! 上一次任务仍在运行时跳过本次触发，避免同一份备份并发执行。
+
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use serde::Serialize;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use super::{JobId, JobManager};
+use crate::errors::Result;
+
+type TaskFactory = Arc<dyn Fn(CancellationToken) -> BoxFuture<'static, Result<serde_json::Value>> + Send + Sync>;
+
+/// 定时任务
+///
+/// 克隆后共享同一份状态。
+#[derive(Clone)]
+pub struct Scheduler {
+    manager: JobManager,
+    kind: String,
+    task: TaskFactory,
+    paused: Arc<AtomicBool>,
+    current: Arc<Mutex<Option<JobId>>>,
+    stop_token: CancellationToken,
+}
+
+impl Scheduler {
+    /// 创建定时任务，`task` 每次触发时调用一次，结果记入 [`JobInfo::output`](super::JobInfo::output)
+    pub fn new<F, Fut, T>(manager: JobManager, kind: &str, task: F) -> Self
+    where
+        F: Fn(CancellationToken) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+        T: Serialize,
+    {
+        Self {
+            manager,
+            kind: kind.to_string(),
+            task: Arc::new(move |token| {
+                task(token)
+                    .map(|result| result.map(|value| serde_json::to_value(value).unwrap_or_default()))
+                    .boxed()
+            }),
+            paused: Arc::new(AtomicBool::new(false)),
+            current: Arc::new(Mutex::new(None)),
+            stop_token: CancellationToken::new(),
+        }
+    }
+
+    /// 在后台按 `every` 间隔触发，首次触发在一个间隔之后
+    pub fn start(&self, every: Duration) {
+        let scheduler = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + every, every);
+            loop {
+                tokio::select! {
+                    _ = scheduler.stop_token.cancelled() => break,
+                    _ = ticker.tick() => {
+                        if !scheduler.is_paused() {
+                            scheduler.trigger();
+                        }
+                    }
+                }
+            }
+            tracing::debug!("定时任务 {} 已停止", scheduler.kind);
+        });
+        tracing::info!("定时任务 {} 已启动，间隔 {:?}", self.kind, every);
+    }
+
+    /// 立即启动一次任务，上一次任务仍在运行时返回 `None`
+    pub fn trigger(&self) -> Option<JobId> {
+        let mut current = self.current.lock().unwrap();
+        let running = current
+            .and_then(|id| self.manager.get(&id))
+            .is_some_and(|job| !job.status.is_finished());
+        if running {
+            tracing::info!("定时任务 {} 仍在运行，跳过本次触发", self.kind);
+            return None;
+        }
+        let id = self.manager.spawn_with_output(&self.kind, |token| (self.task)(token));
+        *current = Some(id);
+        Some(id)
+    }
+
+    /// 最近一次启动的任务
+    pub fn last_job(&self) -> Option<JobId> {
+        *self.current.lock().unwrap()
+    }
+
+    /// 暂停定时触发，不影响正在运行的任务和手动触发
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    /// 恢复定时触发
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+    }
+
+    /// 是否已暂停
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// 停止后台触发
+    pub fn stop(&self) {
+        self.stop_token.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jobs::JobStatus;
+    use std::sync::atomic::AtomicUsize;
+    use tokio::sync::Notify;
+
+    #[tokio::test]
+    async fn test_scheduler_trigger_and_pause() {
+        let manager = JobManager::new();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let release = Arc::new(Notify::new());
+        let scheduler = {
+            let runs = runs.clone();
+            let release = release.clone();
+            Scheduler::new(manager.clone(), "backup", move |_| {
+                let runs = runs.clone();
+                let release = release.clone();
+                async move {
+                    runs.fetch_add(1, Ordering::SeqCst);
+                    release.notified().await;
+                    Ok(())
+                }
+            })
+        };
+
+        let first = scheduler.trigger().unwrap();
+        assert!(scheduler.trigger().is_none());
+        release.notify_one();
+        assert_eq!(manager.wait(&first).await.unwrap().status, JobStatus::Succeeded);
+
+        scheduler.pause();
+        scheduler.start(Duration::from_millis(10));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        scheduler.resume();
+        while scheduler.last_job() == Some(first) {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        scheduler.stop();
+        release.notify_one();
+        let second = scheduler.last_job().unwrap();
+        assert_eq!(manager.wait(&second).await.unwrap().status, JobStatus::Succeeded);
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+        assert_eq!(manager.list().len(), 2);
+    }
+}
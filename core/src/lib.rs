@@ -3,9 +3,17 @@
 //! 这是一个共享的核心库，提供微信数据处理的核心功能，
 //! 可以被 CLI 和 GUI 应用程序共同使用。
 
+pub mod backup;
 pub mod errors;
+pub mod events;
+pub mod export;
+pub mod jobs;
 pub mod logs;
+pub mod mcp;
 pub mod models;
+pub mod plugins;
+pub mod scopes;
+pub mod webhook;
 pub mod wechat;
 pub mod utils;
 
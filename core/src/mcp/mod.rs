@@ -0,0 +1,19 @@
+//! MCP（Model Context Protocol）数据层
+//!
+//! 与传输方式无关：这里定义 MCP 客户端可浏览的资源及其 URI 模板和预置的提示词，
+//! MCP 服务把它们映射到 `resources/list`、`resources/templates/list` 和 `resources/read`；
+//! 提示词对应 `prompts/list` 和 `prompts/get`；查询工具对应 `tools/list` 和 `tools/call`。
+//! [`McpServer`] 处理 JSON-RPC 消息，可经标准输入输出或 SSE 传输。
+
+pub mod prompts;
+pub mod resources;
+pub mod server;
+pub mod tools;
+
+pub use prompts::{Prompt, PromptLibrary, PromptResult};
+
+pub use resources::{Resource, ResourceContents, ResourceProvider, ResourceTemplate, ResourceUri};
+
+pub use server::{notification, serve_stdio, McpServer};
+
+pub use tools::{Tool, ToolBox, ToolResult};
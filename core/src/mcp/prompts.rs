@@ -0,0 +1,312 @@
+//! MCP 提示词
+//!
+//! 预置常用分析的提示词，生成时按参数读取对应数据并作为资源嵌入，
+//! 客户端选中提示词、填入联系人等参数即可直接得到可用的上下文。
+//! [`McpServer`](super::McpServer) 把 `prompts/list` 和 `prompts/get` 请求分派到 [`PromptLibrary`]。
+
+use chrono::{Datelike, Months, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+
+use super::resources::{display_name, ResourceContents, ResourceProvider, ResourceUri};
+use crate::errors::{McpError, Result};
+use crate::scopes::Scope;
+use crate::wechat::db::hardlink::MediaKind;
+use crate::wechat::db::messages::MessageRepository;
+use crate::wechat::search::CombinedSearch;
+
+/// 查找文件时最多列出的文件数
+pub const MAX_PROMPT_FILES: usize = 500;
+
+/// 搜索话题时每类最多返回的条数
+pub const PROMPT_SEARCH_LIMIT: usize = 50;
+
+/// 提示词
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Prompt {
+    /// 名称
+    pub name: String,
+    /// 说明
+    pub description: String,
+    /// 参数
+    pub arguments: Vec<PromptArgument>,
+}
+
+/// 提示词参数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptArgument {
+    /// 参数名
+    pub name: String,
+    /// 说明
+    pub description: String,
+    /// 是否必填
+    pub required: bool,
+}
+
+/// 生成的提示词
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptResult {
+    /// 说明
+    pub description: String,
+    /// 消息
+    pub messages: Vec<PromptMessage>,
+}
+
+/// 提示词中的一条消息
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptMessage {
+    /// 角色，固定为 `user`
+    pub role: String,
+    /// 内容
+    pub content: PromptContent,
+}
+
+/// 消息内容
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PromptContent {
+    /// 文本
+    Text { text: String },
+    /// 嵌入的资源
+    Resource { resource: ResourceContents },
+}
+
+impl PromptMessage {
+    fn text(text: String) -> Self {
+        Self {
+            role: "user".to_string(),
+            content: PromptContent::Text { text },
+        }
+    }
+
+    fn resource(resource: ResourceContents) -> Self {
+        Self {
+            role: "user".to_string(),
+            content: PromptContent::Resource { resource },
+        }
+    }
+}
+
+/// 提示词库
+pub struct PromptLibrary {
+    resources: ResourceProvider,
+}
+
+impl PromptLibrary {
+    /// 使用资源提供者读取数据
+    pub fn new(resources: ResourceProvider) -> Self {
+        Self { resources }
+    }
+
+    /// 所有提示词
+    pub fn prompts() -> Vec<Prompt> {
+        let argument = |name: &str, description: &str, required: bool| PromptArgument {
+            name: name.to_string(),
+            description: description.to_string(),
+            required,
+        };
+        vec![
+            Prompt {
+                name: "summarize_month".to_string(),
+                description: "总结与某个联系人或群在某个月的聊天".to_string(),
+                arguments: vec![
+                    argument("wxid", "联系人的 wxid 或群ID", true),
+                    argument("month", "月份（yyyy-mm），默认为上个月", false),
+                ],
+            },
+            Prompt {
+                name: "find_files".to_string(),
+                description: "列出会话中发送的文件，可只看某个人发送的".to_string(),
+                arguments: vec![
+                    argument("wxid", "联系人的 wxid 或群ID", true),
+                    argument("sender", "只列出该 wxid 发送的文件", false),
+                ],
+            },
+            Prompt {
+                name: "search_topic".to_string(),
+                description: "在所有聊天中查找某个话题的讨论".to_string(),
+                arguments: vec![argument("keyword", "关键词", true)],
+            },
+        ]
+    }
+
+    /// 生成提示词需要的权限
+    pub fn required_scopes(name: &str) -> &'static [Scope] {
+        match name {
+            "find_files" => &[Scope::ReadMessages, Scope::ReadMedia],
+            "search_topic" => &[Scope::ReadMessages, Scope::ReadContacts],
+            _ => &[Scope::ReadMessages],
+        }
+    }
+
+    /// 按名称和参数生成提示词
+    pub async fn get(&self, name: &str, arguments: &HashMap<String, String>) -> Result<PromptResult> {
+        self.resources.require(Self::required_scopes(name))?;
+        match name {
+            "summarize_month" => self.summarize_month(arguments).await,
+            "find_files" => self.find_files(arguments).await,
+            "search_topic" => self.search_topic(arguments).await,
+            _ => Err(McpError::ProtocolError(format!("未知的提示词: {}", name)).into()),
+        }
+    }
+
+    async fn summarize_month(&self, arguments: &HashMap<String, String>) -> Result<PromptResult> {
+        let wxid = required(arguments, "wxid")?;
+        let month = match arguments.get("month").filter(|m| !m.is_empty()) {
+            Some(month) => NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d")
+                .map_err(|_| McpError::ProtocolError(format!("无效的月份: {}", month)))?,
+            None => {
+                let today = Utc::now().with_timezone(&self.resources.offset()).date_naive();
+                let this_month = today.with_day(1).unwrap_or(today);
+                this_month.checked_sub_months(Months::new(1)).unwrap_or(this_month)
+            }
+        };
+        let name = self.name_of(wxid).await;
+        let uri = ResourceUri::Messages { wxid: wxid.to_string(), month }.to_string();
+        let messages = self.resources.read(&uri).await?;
+        let month = month.format("%Y-%m");
+        Ok(PromptResult {
+            description: format!("总结与{}在{}的聊天", name, month),
+            messages: vec![
+                PromptMessage::text(format!(
+                    "下面是我与「{}」在 {} 的聊天记录（JSON，按时间顺序，is_self 为 true 的是我发送的）。\
+                     请按话题总结主要内容，列出做出的决定、约定的时间和待办事项，并注明对应日期。\
+                     如果 truncated 为 true，说明记录不完整，请在总结中提示。",
+                    name, month
+                )),
+                PromptMessage::resource(messages),
+            ],
+        })
+    }
+
+    async fn find_files(&self, arguments: &HashMap<String, String>) -> Result<PromptResult> {
+        let wxid = required(arguments, "wxid")?;
+        let sender = arguments.get("sender").filter(|s| !s.is_empty());
+        let repository = MessageRepository::open(self.resources.work_dir()).await?;
+
+        let mut files = Vec::new();
+        let mut before = None;
+        while files.len() < MAX_PROMPT_FILES {
+            let page = repository.list_media(wxid, MediaKind::File, before, 200).await?;
+            let Some(last) = page.last() else {
+                break;
+            };
+            before = Some(last.seq);
+            files.extend(page.into_iter().filter(|item| sender.is_none_or(|s| &item.sender == s)));
+        }
+        files.truncate(MAX_PROMPT_FILES);
+
+        let name = self.name_of(wxid).await;
+        let scope = match sender {
+            Some(sender) => format!("「{}」在与「{}」的会话中", self.name_of(sender).await, name),
+            None => format!("与「{}」的会话中", name),
+        };
+        let uri = format!("wechat://files/{}", wxid);
+        let resource = ResourceContents {
+            uri,
+            mime_type: "application/json".to_string(),
+            text: serde_json::to_string_pretty(&json!({ "wxid": wxid, "sender": sender, "files": files }))?,
+        };
+        Ok(PromptResult {
+            description: format!("{}发送的文件", scope),
+            messages: vec![
+                PromptMessage::text(format!(
+                    "下面是{}发送的文件（JSON，按时间倒序，md5 可用于下载）。\
+                     请按时间列出这些文件，注明发送者和日期；没有文件时直接说明。",
+                    scope
+                )),
+                PromptMessage::resource(resource),
+            ],
+        })
+    }
+
+    async fn search_topic(&self, arguments: &HashMap<String, String>) -> Result<PromptResult> {
+        let keyword = required(arguments, "keyword")?;
+        let results = CombinedSearch::open(self.resources.work_dir())
+            .await?
+            .with_contacts(self.resources.contacts().await.unwrap_or_default())
+            .search(keyword, PROMPT_SEARCH_LIMIT)
+            .await?;
+        let resource = ResourceContents {
+            uri: format!("wechat://search/{}", keyword),
+            mime_type: "application/json".to_string(),
+            text: serde_json::to_string_pretty(&results)?,
+        };
+        Ok(PromptResult {
+            description: format!("关于「{}」的讨论", keyword),
+            messages: vec![
+                PromptMessage::text(format!(
+                    "下面是在聊天记录中搜索「{}」的结果（JSON，包含匹配的联系人、群聊和消息）。\
+                     请按会话归纳各处讨论的内容和结论，并注明时间。",
+                    keyword
+                )),
+                PromptMessage::resource(resource),
+            ],
+        })
+    }
+
+    /// 联系人的显示名称，找不到时使用 wxid
+    async fn name_of(&self, wxid: &str) -> String {
+        self.resources
+            .contacts()
+            .await
+            .unwrap_or_default()
+            .iter()
+            .find(|c| c.username == wxid)
+            .map(display_name)
+            .unwrap_or_else(|| wxid.to_string())
+    }
+}
+
+fn required<'a>(arguments: &'a HashMap<String, String>, name: &str) -> Result<&'a str> {
+    arguments
+        .get(name)
+        .map(String::as_str)
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| McpError::ProtocolError(format!("缺少参数: {}", name)).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Contact;
+    use crate::wechat::db::messages::tests::create_message_dbs;
+
+    #[tokio::test]
+    async fn test_prompts_embed_data() {
+        let dir = tempfile::tempdir().unwrap();
+        create_message_dbs(dir.path()).await;
+        let mut friend = Contact::new("wxid_friend".to_string());
+        friend.remark = Some("老友".to_string());
+        let library = PromptLibrary::new(ResourceProvider::new(dir.path()).with_contacts(vec![friend]));
+        let args = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+        };
+
+        let result = library
+            .get("summarize_month", &args(&[("wxid", "wxid_friend"), ("month", "2023-11")]))
+            .await
+            .unwrap();
+        assert!(result.description.contains("老友"));
+        let PromptContent::Resource { resource } = &result.messages[1].content else {
+            panic!("应嵌入消息资源");
+        };
+        assert_eq!(resource.uri, "wechat://messages/wxid_friend/2023-11");
+        assert!(resource.text.contains("在吗"));
+
+        let result = library.get("find_files", &args(&[("wxid", "wxid_friend")])).await.unwrap();
+        assert_eq!(result.messages.len(), 2);
+
+        let result = library.get("search_topic", &args(&[("keyword", "你好")])).await.unwrap();
+        let PromptContent::Resource { resource } = &result.messages[1].content else {
+            panic!("应嵌入搜索结果");
+        };
+        assert!(resource.text.contains("你好"));
+
+        assert!(library.get("summarize_month", &args(&[])).await.is_err());
+        assert!(library.get("unknown", &args(&[])).await.is_err());
+        let names: Vec<String> = PromptLibrary::prompts().into_iter().map(|p| p.name).collect();
+        assert_eq!(names, ["summarize_month", "find_files", "search_topic"]);
+    }
+}
@@ -0,0 +1,375 @@
+//! MCP 资源
+//!
+//! - `wechat://contacts`：联系人列表
+//! - `wechat://contacts/{wxid}`：联系人信息及有消息的月份
+//! - `wechat://messages/{wxid}/{yyyy-mm}`：会话某个月的消息
+//!
+//! 资源列表按联系人分页，客户端先浏览联系人和月份，再读取具体消息或调用查询工具。
+//! [`McpServer`](super::McpServer) 把 `resources/list`、`resources/templates/list` 和
+//! `resources/read` 请求分派到 [`ResourceProvider`]。
+
+use chrono::{Datelike, FixedOffset, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::errors::{McpError, Result};
+use crate::models::{Contact, Cursor, Message, Page};
+use crate::scopes::{Scope, Scopes};
+use crate::wechat::db::contacts::{self, CONTACT_DB_PATH};
+use crate::wechat::db::messages::MessageRepository;
+use crate::wechat::db::SqliteDataSource;
+
+/// 资源 URI 的协议
+pub const URI_SCHEME: &str = "wechat://";
+
+/// 资源列表每页条数
+pub const RESOURCE_PAGE_SIZE: usize = 100;
+
+/// 读取一个月的消息时最多返回的条数，超出部分需通过查询工具分页读取
+pub const MAX_RESOURCE_MESSAGES: usize = 1000;
+
+const JSON_MIME: &str = "application/json";
+
+/// 资源 URI 模板
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceTemplate {
+    /// RFC 6570 URI 模板
+    pub uri_template: String,
+    /// 名称
+    pub name: String,
+    /// 说明
+    pub description: String,
+    /// 内容类型
+    pub mime_type: String,
+}
+
+/// 可直接读取的资源
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Resource {
+    /// 资源 URI
+    pub uri: String,
+    /// 名称
+    pub name: String,
+    /// 说明
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// 内容类型
+    pub mime_type: String,
+}
+
+/// 读取到的资源内容
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceContents {
+    /// 资源 URI
+    pub uri: String,
+    /// 内容类型
+    pub mime_type: String,
+    /// 文本内容
+    pub text: String,
+}
+
+/// 解析后的资源 URI
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResourceUri {
+    /// 联系人列表
+    Contacts,
+    /// 单个联系人
+    Contact { wxid: String },
+    /// 会话某个月的消息，`month` 为该月第一天
+    Messages { wxid: String, month: NaiveDate },
+}
+
+impl ResourceUri {
+    /// 解析资源 URI
+    pub fn parse(uri: &str) -> Result<Self> {
+        let invalid = || McpError::ResourceAccessFailed { resource: uri.to_string() };
+        let path = uri.strip_prefix(URI_SCHEME).ok_or_else(invalid)?;
+        let parts: Vec<&str> = path.split('/').collect();
+        let parsed = match parts.as_slice() {
+            ["contacts"] => Some(ResourceUri::Contacts),
+            ["contacts", wxid] if !wxid.is_empty() => Some(ResourceUri::Contact { wxid: wxid.to_string() }),
+            ["messages", wxid, month] if !wxid.is_empty() => {
+                NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d")
+                    .ok()
+                    .map(|month| ResourceUri::Messages { wxid: wxid.to_string(), month })
+            }
+            _ => None,
+        };
+        parsed.ok_or_else(|| invalid().into())
+    }
+
+    /// 读取该资源需要的权限
+    pub fn required_scopes(&self) -> &'static [Scope] {
+        match self {
+            ResourceUri::Contacts => &[Scope::ReadContacts],
+            // 联系人资源附带每月消息数
+            ResourceUri::Contact { .. } => &[Scope::ReadContacts, Scope::ReadMessages],
+            ResourceUri::Messages { .. } => &[Scope::ReadMessages],
+        }
+    }
+}
+
+impl fmt::Display for ResourceUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResourceUri::Contacts => write!(f, "{}contacts", URI_SCHEME),
+            ResourceUri::Contact { wxid } => write!(f, "{}contacts/{}", URI_SCHEME, wxid),
+            ResourceUri::Messages { wxid, month } => {
+                write!(f, "{}messages/{}/{}", URI_SCHEME, wxid, month.format("%Y-%m"))
+            }
+        }
+    }
+}
+
+/// 基于解密工作目录提供资源
+#[derive(Clone)]
+pub struct ResourceProvider {
+    work_dir: PathBuf,
+    offset: FixedOffset,
+    contacts: Option<Vec<Contact>>,
+    scopes: Scopes,
+}
+
+impl ResourceProvider {
+    /// `work_dir` 为解密输出目录
+    pub fn new(work_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            work_dir: work_dir.into(),
+            offset: FixedOffset::east_opt(0).expect("UTC 偏移有效"),
+            contacts: None,
+            scopes: Scopes::all(),
+        }
+    }
+
+    /// 限制可读取的资源，默认拥有全部权限
+    pub fn with_scopes(mut self, scopes: Scopes) -> Self {
+        self.scopes = scopes;
+        self
+    }
+
+    /// 划分月份使用的时区，默认 UTC
+    pub fn with_utc_offset(mut self, offset: FixedOffset) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// 使用给定的联系人，不再读取联系人数据库
+    pub fn with_contacts(mut self, contacts: Vec<Contact>) -> Self {
+        self.contacts = Some(contacts);
+        self
+    }
+
+    /// 资源 URI 模板
+    pub fn templates() -> Vec<ResourceTemplate> {
+        let template = |uri_template: &str, name: &str, description: &str| ResourceTemplate {
+            uri_template: uri_template.to_string(),
+            name: name.to_string(),
+            description: description.to_string(),
+            mime_type: JSON_MIME.to_string(),
+        };
+        vec![
+            template(
+                "wechat://contacts/{wxid}",
+                "联系人",
+                "联系人信息（昵称、备注、标签）及有消息的月份和每月消息数",
+            ),
+            template(
+                "wechat://messages/{wxid}/{yyyy-mm}",
+                "月度消息",
+                "会话在某个月的消息，按时间顺序排列",
+            ),
+        ]
+    }
+
+    /// 列出资源：联系人列表及每个联系人，按联系人分页
+    pub async fn list(&self, cursor: Option<&str>) -> Result<Page<Resource>> {
+        self.require(&[Scope::ReadContacts])?;
+        let cursor = Cursor::parse(cursor)?;
+        let contacts = self.contacts().await?;
+        let mut page = Page::from_offset(contacts, cursor, RESOURCE_PAGE_SIZE)?.map(|contact| Resource {
+            uri: ResourceUri::Contact { wxid: contact.username.clone() }.to_string(),
+            description: (display_name(&contact) != contact.username).then(|| contact.username.clone()),
+            name: display_name(&contact),
+            mime_type: JSON_MIME.to_string(),
+        });
+        // 联系人列表本身只在第一页列出
+        if cursor.is_none() {
+            page.items.insert(
+                0,
+                Resource {
+                    uri: ResourceUri::Contacts.to_string(),
+                    name: "联系人列表".to_string(),
+                    description: Some("所有联系人及群聊".to_string()),
+                    mime_type: JSON_MIME.to_string(),
+                },
+            );
+        }
+        Ok(page)
+    }
+
+    /// 读取资源
+    pub async fn read(&self, uri: &str) -> Result<ResourceContents> {
+        let parsed = ResourceUri::parse(uri)?;
+        self.require(parsed.required_scopes())?;
+        let value = match parsed {
+            ResourceUri::Contacts => serde_json::to_value(self.contacts().await?)?,
+            ResourceUri::Contact { wxid } => {
+                let contact = self
+                    .contacts()
+                    .await?
+                    .into_iter()
+                    .find(|c| c.username == wxid)
+                    .ok_or_else(|| McpError::ResourceAccessFailed { resource: uri.to_string() })?;
+                let months = self.months(&wxid).await?;
+                json!({ "contact": contact, "months": months })
+            }
+            ResourceUri::Messages { wxid, month } => {
+                let (messages, truncated) = self.month_messages(&wxid, month).await?;
+                json!({
+                    "wxid": wxid,
+                    "month": month.format("%Y-%m").to_string(),
+                    "messages": messages,
+                    "truncated": truncated,
+                })
+            }
+        };
+        Ok(ResourceContents {
+            uri: uri.to_string(),
+            mime_type: JSON_MIME.to_string(),
+            text: serde_json::to_string_pretty(&value)?,
+        })
+    }
+
+    /// 检查是否拥有所需权限
+    pub(crate) fn require(&self, required: &[Scope]) -> Result<()> {
+        match self.scopes.missing(required) {
+            Some(scope) => Err(McpError::ScopeDenied { scope: scope.to_string() }.into()),
+            None => Ok(()),
+        }
+    }
+
+    /// 解密输出目录
+    pub(crate) fn work_dir(&self) -> &std::path::Path {
+        &self.work_dir
+    }
+
+    /// 划分日期使用的时区
+    pub(crate) fn offset(&self) -> FixedOffset {
+        self.offset
+    }
+
+    pub(crate) async fn contacts(&self) -> Result<Vec<Contact>> {
+        if let Some(contacts) = &self.contacts {
+            return Ok(contacts.clone());
+        }
+        let source = SqliteDataSource::open(&self.work_dir.join(CONTACT_DB_PATH)).await?;
+        contacts::load_contacts(&source).await
+    }
+
+    /// 有消息的月份及每月消息数，附带对应的消息资源 URI
+    async fn months(&self, wxid: &str) -> Result<Vec<serde_json::Value>> {
+        let repository = MessageRepository::open(&self.work_dir).await?;
+        let mut months: BTreeMap<NaiveDate, u64> = BTreeMap::new();
+        for day in repository.density(wxid, self.offset).await? {
+            *months.entry(first_of_month(day.date)).or_default() += day.count;
+        }
+        Ok(months
+            .into_iter()
+            .map(|(month, count)| {
+                json!({
+                    "month": month.format("%Y-%m").to_string(),
+                    "count": count,
+                    "uri": ResourceUri::Messages { wxid: wxid.to_string(), month }.to_string(),
+                })
+            })
+            .collect())
+    }
+
+    /// 读取某个月的消息，超过 [`MAX_RESOURCE_MESSAGES`] 时截断
+    async fn month_messages(&self, wxid: &str, month: NaiveDate) -> Result<(Vec<Message>, bool)> {
+        let repository = MessageRepository::open(&self.work_dir).await?;
+        let next_month = first_of_month(month + chrono::Days::new(31));
+        let Some(start) = repository.seq_at_date(wxid, month, self.offset).await? else {
+            return Ok((Vec::new(), false));
+        };
+
+        let mut messages = Vec::new();
+        let mut cursor = Some(start - 1);
+        loop {
+            let page = repository.page(wxid, cursor, 200).await?;
+            let Some(last) = page.last() else {
+                return Ok((messages, false));
+            };
+            cursor = Some(last.seq);
+            for message in page {
+                if message.time.with_timezone(&self.offset).date_naive() >= next_month {
+                    return Ok((messages, false));
+                }
+                if messages.len() == MAX_RESOURCE_MESSAGES {
+                    return Ok((messages, true));
+                }
+                messages.push(message);
+            }
+        }
+    }
+}
+
+fn first_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+/// 备注优先的显示名称
+pub(crate) fn display_name(contact: &Contact) -> String {
+    contact.display_name().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wechat::db::messages::tests::create_message_dbs;
+
+    #[tokio::test]
+    async fn test_list_and_read_resources() {
+        let dir = tempfile::tempdir().unwrap();
+        create_message_dbs(dir.path()).await;
+        let mut friend = Contact::new("wxid_friend".to_string());
+        friend.remark = Some("老友".to_string());
+        let provider = ResourceProvider::new(dir.path()).with_contacts(vec![friend, Contact::new("123@chatroom".to_string())]);
+
+        let page = provider.list(None).await.unwrap();
+        let uris: Vec<&str> = page.items.iter().map(|r| r.uri.as_str()).collect();
+        assert_eq!(uris, ["wechat://contacts", "wechat://contacts/wxid_friend", "wechat://contacts/123@chatroom"]);
+        assert_eq!(page.items[1].name, "老友");
+        assert!(page.next_cursor.is_none());
+
+        let contact: serde_json::Value =
+            serde_json::from_str(&provider.read("wechat://contacts/wxid_friend").await.unwrap().text).unwrap();
+        assert_eq!(contact["months"][0]["month"], "2023-11");
+        assert_eq!(contact["months"][0]["count"], 3);
+        let uri = contact["months"][0]["uri"].as_str().unwrap();
+        assert_eq!(uri, "wechat://messages/wxid_friend/2023-11");
+
+        let messages: serde_json::Value = serde_json::from_str(&provider.read(uri).await.unwrap().text).unwrap();
+        assert_eq!(messages["messages"].as_array().unwrap().len(), 3);
+        assert_eq!(messages["truncated"], false);
+        let empty = provider.read("wechat://messages/wxid_friend/2023-12").await.unwrap();
+        assert!(empty.text.contains("\"messages\": []"));
+
+        for uri in ["wechat://contacts/nobody", "wechat://messages/wxid_friend/2023-13", "file:///etc/passwd"] {
+            assert!(provider.read(uri).await.is_err(), "{}", uri);
+        }
+        assert_eq!(ResourceUri::parse("wechat://messages/a/2024-05").unwrap().to_string(), "wechat://messages/a/2024-05");
+
+        let contacts_only = ResourceProvider::new(dir.path())
+            .with_contacts(Vec::new())
+            .with_scopes(Scopes::new([Scope::ReadContacts]));
+        assert!(contacts_only.read("wechat://contacts").await.is_ok());
+        assert!(contacts_only.read("wechat://messages/wxid_friend/2023-11").await.is_err());
+    }
+}
@@ -0,0 +1,445 @@
+//! MCP 服务
+//!
+//! 处理 JSON-RPC 2.0 消息，把 `tools/*`、`resources/*` 和 `prompts/*` 请求分派给
+//! [`ToolBox`]、[`ResourceProvider`] 和 [`PromptLibrary`]。传输层只负责收发消息：
+//! 标准输入输出传输由 [`serve_stdio`] 提供，每行一条消息；SSE 传输由 HTTP 服务提供。
+//!
+//! 设置事件总线后，数据刷新、任务结束和错误等事件经 [`notification`] 转换为 MCP 通知推送给客户端。
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio_util::sync::CancellationToken;
+
+use super::prompts::PromptLibrary;
+use super::resources::ResourceProvider;
+use super::tools::{ToolBox, ToolResult};
+use crate::errors::{McpError, MwxDumpError, Result};
+use crate::events::{Event, EventBus, EventReceiver};
+
+/// 支持的协议版本，最新的在前
+pub const PROTOCOL_VERSIONS: [&str; 3] = ["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// JSON-RPC 错误码
+pub mod error_code {
+    /// 消息不是有效的 JSON
+    pub const PARSE_ERROR: i64 = -32700;
+    /// 消息不是有效的请求
+    pub const INVALID_REQUEST: i64 = -32600;
+    /// 方法不存在
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    /// 参数无效
+    pub const INVALID_PARAMS: i64 = -32602;
+    /// 服务端处理失败
+    pub const INTERNAL_ERROR: i64 = -32603;
+    /// 资源不存在（MCP 约定）
+    pub const RESOURCE_NOT_FOUND: i64 = -32002;
+}
+
+/// JSON-RPC 请求或通知，通知没有 `id`
+#[derive(Debug, Deserialize)]
+struct Request {
+    jsonrpc: String,
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// JSON-RPC 错误
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
+impl From<anyhow::Error> for RpcError {
+    fn from(error: anyhow::Error) -> Self {
+        let code = match mcp_error(&error) {
+            Some(McpError::ProtocolError(_) | McpError::ScopeDenied { .. }) => error_code::INVALID_PARAMS,
+            Some(McpError::ResourceAccessFailed { .. }) => error_code::RESOURCE_NOT_FOUND,
+            _ => error_code::INTERNAL_ERROR,
+        };
+        Self::new(code, error.to_string())
+    }
+}
+
+/// 错误链中的 MCP 错误
+fn mcp_error(error: &anyhow::Error) -> Option<&McpError> {
+    error.chain().find_map(|cause| match cause.downcast_ref::<MwxDumpError>() {
+        Some(MwxDumpError::Mcp(e)) => Some(e),
+        _ => cause.downcast_ref::<McpError>(),
+    })
+}
+
+impl From<serde_json::Error> for RpcError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::new(error_code::INVALID_PARAMS, error.to_string())
+    }
+}
+
+/// MCP 服务，与传输方式无关
+pub struct McpServer {
+    resources: ResourceProvider,
+    prompts: PromptLibrary,
+    tools: ToolBox,
+    events: Option<EventBus>,
+}
+
+impl McpServer {
+    /// 使用资源提供者创建服务，权限、时区等设置同时作用于工具和提示词
+    pub fn new(resources: ResourceProvider) -> Self {
+        Self {
+            prompts: PromptLibrary::new(resources.clone()),
+            tools: ToolBox::new(resources.clone()),
+            resources,
+            events: None,
+        }
+    }
+
+    /// 订阅事件总线，向客户端推送通知
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// 订阅通知来源，未设置事件总线时返回 `None`
+    pub fn subscribe(&self) -> Option<EventReceiver> {
+        self.events.as_ref().map(EventBus::subscribe)
+    }
+
+    /// 处理一行文本消息，返回需要发送的响应；通知没有响应
+    pub async fn handle_text(&self, text: &str) -> Option<String> {
+        let response = match serde_json::from_str::<Value>(text) {
+            Ok(message) => self.handle(message).await?,
+            Err(e) => error_response(Value::Null, RpcError::new(error_code::PARSE_ERROR, e.to_string())),
+        };
+        Some(response.to_string())
+    }
+
+    /// 处理一条 JSON-RPC 消息，返回需要发送的响应；通知和客户端发来的响应没有响应
+    pub async fn handle(&self, message: Value) -> Option<Value> {
+        // 客户端对服务端请求的响应，目前不向客户端发请求，直接忽略
+        if message.get("method").is_none() && (message.get("result").is_some() || message.get("error").is_some()) {
+            return None;
+        }
+        let id = message.get("id").cloned().unwrap_or(Value::Null);
+        let request = match serde_json::from_value::<Request>(message) {
+            Ok(request) if request.jsonrpc == "2.0" => request,
+            _ => return Some(error_response(id, RpcError::new(error_code::INVALID_REQUEST, "无效的 JSON-RPC 请求"))),
+        };
+        let Some(id) = request.id else {
+            tracing::debug!("收到 MCP 通知: {}", request.method);
+            return None;
+        };
+        Some(match self.dispatch(&request.method, request.params).await {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(e) => {
+                tracing::debug!("MCP 请求 {} 失败: {}", request.method, e.message);
+                error_response(id, e)
+            }
+        })
+    }
+
+    async fn dispatch(&self, method: &str, params: Value) -> std::result::Result<Value, RpcError> {
+        let text = |key: &str| params.get(key).and_then(Value::as_str);
+        match method {
+            "initialize" => Ok(initialize_result(text("protocolVersion"))),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": ToolBox::tools() })),
+            "tools/call" => {
+                let name = text("name").ok_or_else(|| RpcError::new(error_code::INVALID_PARAMS, "缺少工具名称"))?;
+                let arguments = params.get("arguments").cloned().unwrap_or(Value::Null);
+                let result = match self.tools.call(name, arguments).await {
+                    Ok(result) => result,
+                    Err(e) => match mcp_error(&e) {
+                        // 执行失败作为结果返回，客户端可以把原因交给模型
+                        Some(McpError::ToolExecutionFailed { error, .. }) => ToolResult::error(error.clone()),
+                        _ => return Err(e.into()),
+                    },
+                };
+                Ok(serde_json::to_value(result)?)
+            }
+            "resources/list" => {
+                let page = self.resources.list(text("cursor")).await?;
+                Ok(json!({ "resources": page.items, "nextCursor": page.next_cursor }))
+            }
+            "resources/templates/list" => Ok(json!({ "resourceTemplates": ResourceProvider::templates() })),
+            "resources/read" => {
+                let uri = text("uri").ok_or_else(|| RpcError::new(error_code::INVALID_PARAMS, "缺少资源 URI"))?;
+                Ok(json!({ "contents": [self.resources.read(uri).await?] }))
+            }
+            "prompts/list" => Ok(json!({ "prompts": PromptLibrary::prompts() })),
+            "prompts/get" => {
+                let name = text("name").ok_or_else(|| RpcError::new(error_code::INVALID_PARAMS, "缺少提示词名称"))?;
+                let arguments: HashMap<String, String> = match params.get("arguments") {
+                    Some(arguments) if !arguments.is_null() => serde_json::from_value(arguments.clone())?,
+                    _ => HashMap::new(),
+                };
+                Ok(serde_json::to_value(self.prompts.get(name, &arguments).await?)?)
+            }
+            _ => Err(RpcError::new(error_code::METHOD_NOT_FOUND, format!("未知的方法: {}", method))),
+        }
+    }
+}
+
+/// `initialize` 的结果，客户端请求的版本受支持时沿用，否则返回最新版本
+fn initialize_result(requested: Option<&str>) -> Value {
+    let version = requested
+        .filter(|v| PROTOCOL_VERSIONS.contains(v))
+        .unwrap_or(PROTOCOL_VERSIONS[0]);
+    json!({
+        "protocolVersion": version,
+        "capabilities": { "tools": {}, "resources": { "listChanged": true }, "prompts": {}, "logging": {} },
+        "serverInfo": { "name": "mwxdump", "version": env!("CARGO_PKG_VERSION") },
+        "instructions": "先用 search_contacts 或 get_sessions 找到联系人或群的 wxid，再用 query_messages 读取消息。",
+    })
+}
+
+/// 事件对应的 MCP 通知
+///
+/// 数据刷新后资源列表可能变化，发送 `notifications/resources/list_changed`；进程检测、密钥提取、
+/// 任务结束、导出完成和错误作为日志消息发送。解密、导出进度等高频事件不推送。
+pub fn notification(event: &Event) -> Option<Value> {
+    let level = match event {
+        Event::DataUpdated { .. } => {
+            return Some(json!({ "jsonrpc": "2.0", "method": "notifications/resources/list_changed" }));
+        }
+        Event::ProcessDetected { .. }
+        | Event::KeyExtracted { .. }
+        | Event::JobFinished { .. }
+        | Event::ExportFinished { .. } => "info",
+        Event::Error { .. } => "error",
+        Event::DecryptProgress { .. } | Event::ExportProgress { .. } => return None,
+    };
+    Some(json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/message",
+        "params": { "level": level, "logger": "mwxdump", "data": event },
+    }))
+}
+
+/// 等待下一个事件，未订阅时一直等待
+async fn next_event(events: &mut Option<EventReceiver>) -> Option<Event> {
+    match events {
+        Some(events) => events.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message },
+    })
+}
+
+/// 通过标准输入输出提供服务，直到输入结束或取消令牌触发
+///
+/// 标准输出只写协议消息，日志需输出到标准错误。
+pub async fn serve_stdio(server: &McpServer, shutdown: CancellationToken) -> Result<()> {
+    serve_lines(server, tokio::io::stdin(), tokio::io::stdout(), shutdown).await
+}
+
+/// 按行读取消息并逐条写回响应
+async fn serve_lines(
+    server: &McpServer,
+    input: impl AsyncRead + Unpin,
+    mut output: impl AsyncWrite + Unpin,
+    shutdown: CancellationToken,
+) -> Result<()> {
+    let mut lines = BufReader::new(input).lines();
+    let mut events = server.subscribe();
+    loop {
+        let line = tokio::select! {
+            _ = shutdown.cancelled() => return Ok(()),
+            Some(event) = next_event(&mut events) => {
+                if let Some(message) = notification(&event) {
+                    write_line(&mut output, &message.to_string()).await?;
+                }
+                continue;
+            }
+            line = lines.next_line() => line?,
+        };
+        let Some(line) = line else {
+            return Ok(());
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = server.handle_text(&line).await {
+            write_line(&mut output, &response).await?;
+        }
+    }
+}
+
+async fn write_line(output: &mut (impl AsyncWrite + Unpin), line: &str) -> Result<()> {
+    output.write_all(line.as_bytes()).await?;
+    output.write_all(b"\n").await?;
+    output.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Contact;
+    use crate::scopes::{Scope, Scopes};
+    use crate::wechat::db::messages::tests::create_message_dbs;
+
+    #[tokio::test]
+    async fn test_stdio_session() {
+        let dir = tempfile::tempdir().unwrap();
+        create_message_dbs(dir.path()).await;
+        let server = McpServer::new(
+            ResourceProvider::new(dir.path()).with_contacts(vec![Contact::new("wxid_friend".to_string())]),
+        );
+        let input = [
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2024-11-05","capabilities":{}}}"#,
+            r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"query_messages","arguments":{"wxid":"wxid_friend"}}}"#,
+            r#"{"jsonrpc":"2.0","id":3,"method":"resources/read","params":{"uri":"wechat://contacts/nobody"}}"#,
+            r#"{"jsonrpc":"2.0","id":4,"method":"tools/call","params":{"name":"get_sessions"}}"#,
+            "not json",
+            r#"{"jsonrpc":"2.0","id":5,"method":"sampling/createMessage"}"#,
+        ]
+        .join("\n");
+        let mut output = Vec::new();
+        serve_lines(&server, input.as_bytes(), &mut output, CancellationToken::new()).await.unwrap();
+
+        let responses: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(responses.len(), 6);
+        assert_eq!(responses[0]["result"]["protocolVersion"], "2024-11-05");
+        assert_eq!(responses[1]["id"], 2);
+        assert!(responses[1]["result"]["content"][0]["text"].as_str().unwrap().contains("在吗"));
+        assert_eq!(responses[2]["error"]["code"], error_code::RESOURCE_NOT_FOUND);
+        assert_eq!(responses[3]["result"]["isError"], true);
+        assert_eq!(responses[4]["error"]["code"], error_code::PARSE_ERROR);
+        assert_eq!(responses[5]["error"]["code"], error_code::METHOD_NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_events_become_notifications() {
+        let dir = tempfile::tempdir().unwrap();
+        let events = EventBus::new();
+        let server = McpServer::new(ResourceProvider::new(dir.path())).with_events(events.clone());
+        let (client, transport) = tokio::io::duplex(4096);
+        let (input, output) = tokio::io::split(transport);
+        let serving = tokio::spawn(async move { serve_lines(&server, input, output, CancellationToken::new()).await });
+        let mut lines = BufReader::new(client).lines();
+
+        // 等待服务端订阅后再发布
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        events.publish(Event::DecryptProgress {
+            file: "message_0.db".into(),
+            processed: 1,
+            total: 2,
+        });
+        events.publish(Event::DataUpdated {
+            files: vec!["db_storage/message/message_0.db".into()],
+            at: chrono::Utc::now(),
+        });
+        let line = lines.next_line().await.unwrap().unwrap();
+        let message: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(message["method"], "notifications/resources/list_changed");
+
+        events.publish(Event::Error {
+            source: "refresh".to_string(),
+            message: "boom".to_string(),
+        });
+        let message: Value = serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(message["params"]["level"], "error");
+        assert_eq!(message["params"]["data"]["message"], "boom");
+
+        drop(lines);
+        serving.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_resources_are_served() {
+        let dir = tempfile::tempdir().unwrap();
+        create_message_dbs(dir.path()).await;
+        let server = McpServer::new(
+            ResourceProvider::new(dir.path()).with_contacts(vec![Contact::new("wxid_friend".to_string())]),
+        );
+        let call = |method: &str, params: Value| json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+
+        let response = server.handle(call("resources/templates/list", Value::Null)).await.unwrap();
+        let templates: Vec<&str> = response["result"]["resourceTemplates"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|template| template["uriTemplate"].as_str().unwrap())
+            .collect();
+        assert!(templates.contains(&"wechat://messages/{wxid}/{yyyy-mm}"));
+
+        let response = server
+            .handle(call("resources/read", json!({ "uri": "wechat://messages/wxid_friend/2023-11" })))
+            .await
+            .unwrap();
+        let contents = &response["result"]["contents"][0];
+        assert_eq!(contents["uri"], "wechat://messages/wxid_friend/2023-11");
+        assert!(contents["text"].as_str().unwrap().contains("在吗"));
+        let response = server.handle(call("resources/read", Value::Null)).await.unwrap();
+        assert_eq!(response["error"]["code"], error_code::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_prompts_are_served() {
+        let dir = tempfile::tempdir().unwrap();
+        create_message_dbs(dir.path()).await;
+        let server = McpServer::new(
+            ResourceProvider::new(dir.path()).with_contacts(vec![Contact::new("wxid_friend".to_string())]),
+        );
+        let call = |method: &str, params: Value| json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+
+        let response = server.handle(call("prompts/list", Value::Null)).await.unwrap();
+        assert_eq!(response["result"]["prompts"][0]["name"], "summarize_month");
+
+        let params = json!({ "name": "summarize_month", "arguments": { "wxid": "wxid_friend", "month": "2023-11" } });
+        let response = server.handle(call("prompts/get", params)).await.unwrap();
+        let resource = &response["result"]["messages"][1]["content"];
+        assert_eq!(resource["type"], "resource");
+        assert_eq!(resource["resource"]["uri"], "wechat://messages/wxid_friend/2023-11");
+        let response = server.handle(call("prompts/get", json!({ "arguments": {} }))).await.unwrap();
+        assert_eq!(response["error"]["code"], error_code::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_scopes_and_listing() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = McpServer::new(
+            ResourceProvider::new(dir.path())
+                .with_contacts(Vec::new())
+                .with_scopes(Scopes::new([Scope::ReadContacts])),
+        );
+        let call = |method: &str, params: Value| json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+
+        let response = server.handle(call("initialize", json!({ "protocolVersion": "1999-01-01" }))).await.unwrap();
+        assert_eq!(response["result"]["protocolVersion"], PROTOCOL_VERSIONS[0]);
+        let response = server.handle(call("tools/list", Value::Null)).await.unwrap();
+        assert_eq!(response["result"]["tools"].as_array().unwrap().len(), 3);
+        let response = server.handle(call("resources/list", Value::Null)).await.unwrap();
+        assert_eq!(response["result"]["resources"][0]["uri"], "wechat://contacts");
+
+        let response = server
+            .handle(call("tools/call", json!({ "name": "query_messages", "arguments": { "wxid": "a" } })))
+            .await
+            .unwrap();
+        assert_eq!(response["error"]["code"], error_code::INVALID_PARAMS);
+        assert!(response["error"]["message"].as_str().unwrap().contains("read:messages"));
+        assert!(server.handle(json!({ "jsonrpc": "2.0", "id": 7, "result": {} })).await.is_none());
+    }
+}
@@ -0,0 +1,316 @@
+//! MCP 工具
+//!
+//! - `query_messages`：按时间顺序分页读取会话消息，或在会话内按关键词查找
+//! - `search_contacts`：按用户名、昵称或备注查找联系人和群聊
+//! - `get_sessions`：最近会话列表，附带未读数和最后一条消息的预览
+//!
+//! 参数由 JSON Schema 描述，客户端据此构造 `tools/call` 的 `arguments`。
+
+use schemars::{schema_for, JsonSchema};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::resources::ResourceProvider;
+use crate::errors::{McpError, Result};
+use crate::models::{Cursor, Page};
+use crate::scopes::Scope;
+use crate::wechat::db::contacts::ContactRepository;
+use crate::wechat::db::messages::MessageRepository;
+use crate::wechat::db::sessions::SessionRepository;
+
+/// 消息默认每次返回的条数
+pub const DEFAULT_TOOL_MESSAGES: u32 = 100;
+
+/// 消息每次最多返回的条数
+pub const MAX_TOOL_MESSAGES: u32 = 500;
+
+/// 联系人和会话默认每次返回的条数
+pub const DEFAULT_TOOL_LIST: usize = 50;
+
+/// 联系人和会话每次最多返回的条数
+pub const MAX_TOOL_LIST: usize = 500;
+
+/// 工具
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tool {
+    /// 名称
+    pub name: String,
+    /// 说明
+    pub description: String,
+    /// 参数的 JSON Schema
+    pub input_schema: Value,
+}
+
+/// 工具调用结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolResult {
+    /// 结果内容
+    pub content: Vec<ToolContent>,
+    /// 调用是否失败，失败原因在 `content` 中
+    #[serde(default)]
+    pub is_error: bool,
+}
+
+/// 结果内容
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ToolContent {
+    /// 文本
+    Text { text: String },
+}
+
+impl ToolResult {
+    /// 以格式化的 JSON 文本返回数据
+    pub fn json(value: &impl Serialize) -> Result<Self> {
+        Ok(Self {
+            content: vec![ToolContent::Text { text: serde_json::to_string_pretty(value)? }],
+            is_error: false,
+        })
+    }
+
+    /// 调用失败，把原因返回给客户端
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            content: vec![ToolContent::Text { text: message.into() }],
+            is_error: true,
+        }
+    }
+}
+
+/// `query_messages` 的参数
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct QueryMessagesArgs {
+    /// 联系人的 wxid 或群ID
+    pub wxid: String,
+    /// 只返回包含该关键词的文本消息（按时间倒序，不分页）
+    #[serde(default)]
+    pub keyword: Option<String>,
+    /// 上一次结果中的 next_cursor，从其后继续读取
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// 返回条数，默认 100，最多 500
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+/// `search_contacts` 的参数
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SearchContactsArgs {
+    /// 匹配用户名、昵称或备注的关键词
+    pub keyword: String,
+    /// 返回条数，默认 50，最多 500
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// `get_sessions` 的参数
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct GetSessionsArgs {
+    /// 只返回有未读消息的会话
+    #[serde(default)]
+    pub unread_only: bool,
+    /// 上一次结果中的 next_cursor
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// 返回条数，默认 50，最多 500
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// 查询聊天数据的工具集
+pub struct ToolBox {
+    resources: ResourceProvider,
+}
+
+impl ToolBox {
+    /// 使用资源提供者读取数据，权限和时区与资源一致
+    pub fn new(resources: ResourceProvider) -> Self {
+        Self { resources }
+    }
+
+    /// 所有工具
+    pub fn tools() -> Vec<Tool> {
+        let tool = |name: &str, description: &str, schema: schemars::Schema| Tool {
+            name: name.to_string(),
+            description: description.to_string(),
+            input_schema: schema.to_value(),
+        };
+        vec![
+            tool(
+                "query_messages",
+                "按时间顺序分页读取与某个联系人或群的聊天消息；指定 keyword 时只返回包含关键词的文本消息",
+                schema_for!(QueryMessagesArgs),
+            ),
+            tool(
+                "search_contacts",
+                "按用户名、昵称或备注查找联系人和群聊，返回 wxid 供其他工具使用",
+                schema_for!(SearchContactsArgs),
+            ),
+            tool(
+                "get_sessions",
+                "最近会话列表，最近的在前，附带未读数和最后一条消息的预览",
+                schema_for!(GetSessionsArgs),
+            ),
+        ]
+    }
+
+    /// 调用工具需要的权限，未知工具返回 `None`
+    pub fn required_scopes(name: &str) -> Option<&'static [Scope]> {
+        match name {
+            "query_messages" | "get_sessions" => Some(&[Scope::ReadMessages]),
+            "search_contacts" => Some(&[Scope::ReadContacts]),
+            _ => None,
+        }
+    }
+
+    /// 按名称和参数调用工具
+    ///
+    /// 未知工具和无效参数返回 [`McpError::ProtocolError`]，读取数据失败返回 [`McpError::ToolExecutionFailed`]。
+    pub async fn call(&self, name: &str, arguments: Value) -> Result<ToolResult> {
+        let required = Self::required_scopes(name)
+            .ok_or_else(|| McpError::ProtocolError(format!("未知的工具: {}", name)))?;
+        self.resources.require(required)?;
+        let result = match name {
+            "query_messages" => self.query_messages(parse_arguments(name, arguments)?).await,
+            "search_contacts" => self.search_contacts(parse_arguments(name, arguments)?).await,
+            _ => self.get_sessions(parse_arguments(name, arguments)?).await,
+        };
+        result.map_err(|e| {
+            McpError::ToolExecutionFailed {
+                tool: name.to_string(),
+                error: e.to_string(),
+            }
+            .into()
+        })
+    }
+
+    async fn query_messages(&self, args: QueryMessagesArgs) -> Result<ToolResult> {
+        let limit = args.limit.unwrap_or(DEFAULT_TOOL_MESSAGES).clamp(1, MAX_TOOL_MESSAGES);
+        let repository = MessageRepository::open(self.resources.work_dir()).await?;
+        let contacts = self.contact_repository().await;
+
+        let mut page = match args.keyword.as_deref().filter(|k| !k.trim().is_empty()) {
+            Some(keyword) => {
+                // 搜索覆盖所有会话，多取一些再按会话筛选
+                let talkers = [args.wxid.clone()];
+                let messages = repository
+                    .search(keyword, &talkers, MAX_TOOL_MESSAGES as usize)
+                    .await?
+                    .into_iter()
+                    .filter(|m| m.talker == args.wxid)
+                    .take(limit as usize)
+                    .collect();
+                Page::new(messages, None)
+            }
+            None => {
+                let after = Cursor::parse(args.cursor.as_deref())?.map(Cursor::seq).transpose()?;
+                let messages = repository.page(&args.wxid, after, limit).await?;
+                let total = repository.count(&args.wxid).await?;
+                Page::from_seq(messages, limit as usize, |m| m.seq).with_total(total)
+            }
+        };
+        for message in &mut page.items {
+            contacts.fill_names(message);
+        }
+        ToolResult::json(&page)
+    }
+
+    async fn search_contacts(&self, args: SearchContactsArgs) -> Result<ToolResult> {
+        let limit = args.limit.unwrap_or(DEFAULT_TOOL_LIST).clamp(1, MAX_TOOL_LIST);
+        let contacts = ContactRepository::from_contacts(self.resources.contacts().await?);
+        let matched: Vec<_> = contacts.search(&args.keyword, limit).into_iter().cloned().collect();
+        ToolResult::json(&matched)
+    }
+
+    async fn get_sessions(&self, args: GetSessionsArgs) -> Result<ToolResult> {
+        let limit = args.limit.unwrap_or(DEFAULT_TOOL_LIST).clamp(1, MAX_TOOL_LIST);
+        let mut sessions = SessionRepository::open(self.resources.work_dir()).await?;
+        sessions.fill_names(&self.contact_repository().await);
+        let sessions: Vec<_> = sessions
+            .into_sessions()
+            .into_iter()
+            .filter(|s| !args.unread_only || s.unread_count > 0)
+            .collect();
+        let page = Page::from_offset(sessions, Cursor::parse(args.cursor.as_deref())?, limit)?;
+        ToolResult::json(&page)
+    }
+
+    /// 用于补全名称的联系人，联系人数据库不可用时为空
+    async fn contact_repository(&self) -> ContactRepository {
+        ContactRepository::from_contacts(self.resources.contacts().await.unwrap_or_default())
+    }
+}
+
+/// 解析工具参数，`null` 视为空对象
+fn parse_arguments<T: DeserializeOwned>(tool: &str, arguments: Value) -> Result<T> {
+    let arguments = if arguments.is_null() { Value::Object(Default::default()) } else { arguments };
+    serde_json::from_value(arguments)
+        .map_err(|e| McpError::ProtocolError(format!("工具 {} 的参数无效: {}", tool, e)).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Contact;
+    use crate::scopes::Scopes;
+    use crate::wechat::db::messages::tests::create_message_dbs;
+    use serde_json::json;
+
+    fn text(result: &ToolResult) -> Value {
+        let ToolContent::Text { text } = &result.content[0];
+        serde_json::from_str(text).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_call_tools() {
+        let dir = tempfile::tempdir().unwrap();
+        create_message_dbs(dir.path()).await;
+        let mut friend = Contact::new("wxid_friend".to_string());
+        friend.remark = Some("老友".to_string());
+        let tools = ToolBox::new(ResourceProvider::new(dir.path()).with_contacts(vec![friend]));
+
+        let page = text(&tools.call("query_messages", json!({ "wxid": "wxid_friend", "limit": 2 })).await.unwrap());
+        assert_eq!(page["items"].as_array().unwrap().len(), 2);
+        assert_eq!(page["items"][0]["talker_name"], "老友");
+        assert_eq!(page["total"], 3);
+        let cursor = page["next_cursor"].as_str().unwrap();
+        let rest = text(
+            &tools
+                .call("query_messages", json!({ "wxid": "wxid_friend", "cursor": cursor }))
+                .await
+                .unwrap(),
+        );
+        assert_eq!(rest["items"][0]["content"], "压缩的消息");
+
+        let hits = text(&tools.call("query_messages", json!({ "wxid": "wxid_friend", "keyword": "在吗" })).await.unwrap());
+        assert_eq!(hits["items"].as_array().unwrap().len(), 1);
+
+        let contacts = text(&tools.call("search_contacts", json!({ "keyword": "老" })).await.unwrap());
+        assert_eq!(contacts[0]["username"], "wxid_friend");
+
+        assert!(tools.call("query_messages", json!({})).await.is_err());
+        assert!(tools.call("query_messages", json!({ "wxid": "a", "bogus": 1 })).await.is_err());
+        assert!(tools.call("no_such_tool", Value::Null).await.is_err());
+        // 未解密会话数据库时返回执行失败
+        let err = tools.call("get_sessions", Value::Null).await.unwrap_err();
+        assert!(err.to_string().contains("get_sessions"));
+
+        let contacts_only = ToolBox::new(
+            ResourceProvider::new(dir.path())
+                .with_contacts(Vec::new())
+                .with_scopes(Scopes::new([Scope::ReadContacts])),
+        );
+        assert!(contacts_only.call("search_contacts", json!({ "keyword": "a" })).await.is_ok());
+        assert!(contacts_only.call("query_messages", json!({ "wxid": "wxid_friend" })).await.is_err());
+
+        let names: Vec<String> = ToolBox::tools().into_iter().map(|t| t.name).collect();
+        assert_eq!(names, ["query_messages", "search_contacts", "get_sessions"]);
+        assert_eq!(ToolBox::tools()[0].input_schema["required"], json!(["wxid"]));
+    }
+}
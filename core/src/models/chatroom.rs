@@ -1,13 +1,31 @@
 //! 群聊数据模型
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// 群成员
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+#[ts(export)]
+pub struct ChatRoomMember {
+    pub username: String,
+    /// 群昵称
+    pub display_name: Option<String>,
+}
 
 /// 群聊结构
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, TS)]
+#[ts(export)]
 pub struct ChatRoom {
     pub chatroom_name: String,
     pub display_name: Option<String>,
     pub member_count: i32,
+    /// 群主
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// 群成员
+    #[serde(default)]
+    pub members: Vec<ChatRoomMember>,
 }
 
 impl ChatRoom {
@@ -16,6 +34,8 @@ impl ChatRoom {
             chatroom_name,
             display_name: None,
             member_count: 0,
+            owner: None,
+            members: Vec::new(),
         }
     }
 }
\ No newline at end of file
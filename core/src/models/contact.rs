@@ -1,14 +1,20 @@
 //! 联系人数据模型
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use ts_rs::TS;
 
 /// 联系人结构
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, TS)]
+#[ts(export)]
 pub struct Contact {
     pub username: String,
     pub nickname: Option<String>,
     pub remark: Option<String>,
     pub avatar: Option<String>,
+    /// 标签名称
+    #[serde(default)]
+    pub labels: Vec<String>,
 }
 
 impl Contact {
@@ -18,6 +24,17 @@ impl Contact {
             nickname: None,
             remark: None,
             avatar: None,
+            labels: Vec::new(),
         }
     }
+
+    /// 显示名称：备注优先，其次昵称，都为空时为用户名
+    pub fn display_name(&self) -> &str {
+        [&self.remark, &self.nickname]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .find(|name| !name.is_empty())
+            .unwrap_or(&self.username)
+    }
 }
\ No newline at end of file
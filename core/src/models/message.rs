@@ -1,11 +1,15 @@
 //! 消息数据模型
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use ts_rs::TS;
 use chrono::{DateTime, Utc};
 
 /// 消息结构
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, TS)]
+#[ts(export)]
 pub struct Message {
+    #[ts(type = "number")]
     pub seq: i64,
     pub time: DateTime<Utc>,
     pub talker: String,
@@ -14,9 +18,19 @@ pub struct Message {
     pub sender: String,
     pub sender_name: Option<String>,
     pub is_self: bool,
+    #[ts(type = "number")]
     pub msg_type: i64,
+    #[ts(type = "number")]
     pub sub_type: i64,
     pub content: String,
+    /// 插件附加的标签
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[ts(optional, as = "Option<Vec<String>>")]
+    pub tags: Vec<String>,
+    /// 语音转写文本
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub transcript: Option<String>,
 }
 
 impl Message {
@@ -33,6 +47,8 @@ impl Message {
             msg_type: 1,
             sub_type: 0,
             content: String::new(),
+            tags: Vec::new(),
+            transcript: None,
         }
     }
 }
\ No newline at end of file
@@ -4,8 +4,11 @@ pub mod message;
 pub mod contact;
 pub mod chatroom;
 pub mod session;
+pub mod page;
+pub mod schema;
 
 pub use message::Message;
 pub use contact::Contact;
-pub use chatroom::ChatRoom;
-pub use session::Session;
\ No newline at end of file
+pub use chatroom::{ChatRoom, ChatRoomMember};
+pub use session::Session;
+pub use page::{Cursor, Page};
\ No newline at end of file
@@ -0,0 +1,151 @@
+//! 分页数据模型
+//!
+//! 所有列表接口统一返回 [`Page`]，客户端把 `next_cursor` 原样作为下一次请求的 `cursor`，
+//! 为空时表示没有更多数据。游标对客户端不透明，内部记录按序号或偏移继续读取的位置。
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::errors::{DatabaseError, Result};
+
+/// 一页数据
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, TS)]
+#[ts(export)]
+pub struct Page<T> {
+    /// 本页数据
+    pub items: Vec<T>,
+    /// 下一页的游标，没有更多数据时为空
+    pub next_cursor: Option<String>,
+    /// 总条数，统计代价较高的列表不提供
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional, type = "number")]
+    pub total: Option<u64>,
+}
+
+impl<T> Page<T> {
+    /// 创建一页数据
+    pub fn new(items: Vec<T>, next_cursor: Option<Cursor>) -> Self {
+        Self {
+            items,
+            next_cursor: next_cursor.map(|cursor| cursor.encode()),
+            total: None,
+        }
+    }
+
+    /// 设置总条数
+    pub fn with_total(mut self, total: u64) -> Self {
+        self.total = Some(total);
+        self
+    }
+
+    /// 对已全部读入内存的列表按偏移分页，附带总条数
+    pub fn from_offset(all: Vec<T>, cursor: Option<Cursor>, limit: usize) -> Result<Self> {
+        let offset = match cursor {
+            None => 0,
+            Some(Cursor::Offset(offset)) => offset as usize,
+            Some(other) => return Err(DatabaseError::InvalidCursor(other.encode()).into()),
+        };
+        let total = all.len();
+        let items: Vec<T> = all.into_iter().skip(offset).take(limit).collect();
+        let end = offset.saturating_add(items.len());
+        let next = (end < total).then_some(Cursor::Offset(end as u64));
+        Ok(Self::new(items, next).with_total(total as u64))
+    }
+
+    /// 对按序号排序读取的列表分页：读满一页时以最后一条的序号作为下一页的游标
+    pub fn from_seq(items: Vec<T>, limit: usize, seq: impl Fn(&T) -> i64) -> Self {
+        let next = if items.len() >= limit {
+            items.last().map(|item| Cursor::Seq(seq(item)))
+        } else {
+            None
+        };
+        Self::new(items, next)
+    }
+
+    /// 转换每一条数据
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            next_cursor: self.next_cursor,
+            total: self.total,
+        }
+    }
+}
+
+/// 分页游标
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cursor {
+    /// 从该序号之后（按列表方向）继续读取，用于消息等按 `sort_seq` 排序的列表
+    Seq(i64),
+    /// 跳过前若干条，用于联系人等整体读入后分页的列表
+    Offset(u64),
+}
+
+impl Cursor {
+    /// 编码为 URL 安全的不透明字符串
+    pub fn encode(&self) -> String {
+        let raw = match self {
+            Cursor::Seq(seq) => format!("s:{}", seq),
+            Cursor::Offset(offset) => format!("o:{}", offset),
+        };
+        URL_SAFE_NO_PAD.encode(raw)
+    }
+
+    /// 解码 [`Cursor::encode`] 生成的字符串
+    pub fn decode(cursor: &str) -> Result<Self> {
+        let invalid = || DatabaseError::InvalidCursor(cursor.to_string());
+        let raw = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| invalid())?;
+        let raw = String::from_utf8(raw).map_err(|_| invalid())?;
+        let parsed = match raw.split_once(':') {
+            Some(("s", seq)) => seq.parse().ok().map(Cursor::Seq),
+            Some(("o", offset)) => offset.parse().ok().map(Cursor::Offset),
+            _ => None,
+        };
+        parsed.ok_or_else(|| invalid().into())
+    }
+
+    /// 解码可选的游标参数
+    pub fn parse(cursor: Option<&str>) -> Result<Option<Self>> {
+        cursor.map(Self::decode).transpose()
+    }
+
+    /// 序号游标的值，其他游标返回错误
+    pub fn seq(self) -> Result<i64> {
+        match self {
+            Cursor::Seq(seq) => Ok(seq),
+            other => Err(DatabaseError::InvalidCursor(other.encode()).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip_and_pages() {
+        for cursor in [Cursor::Seq(-5), Cursor::Seq(1_700_000_000_000), Cursor::Offset(40)] {
+            assert_eq!(Cursor::decode(&cursor.encode()).unwrap(), cursor);
+        }
+        assert!(Cursor::decode("not a cursor").is_err());
+        assert!(Cursor::decode(&URL_SAFE_NO_PAD.encode("x:1")).is_err());
+
+        let first = Page::from_offset((0..5).collect(), None, 2).unwrap();
+        assert_eq!((first.items.clone(), first.total), (vec![0, 1], Some(5)));
+        let last = Page::from_offset((0..5).collect(), Some(Cursor::Offset(4)), 2).unwrap();
+        assert_eq!(last.items, vec![4]);
+        assert_eq!(
+            Cursor::parse(first.next_cursor.as_deref()).unwrap(),
+            Some(Cursor::Offset(2))
+        );
+        assert!(last.next_cursor.is_none());
+        assert!(Page::from_offset(vec![1], Some(Cursor::Seq(1)), 2).is_err());
+
+        let page = Page::from_seq(vec![30, 20], 2, |n| *n);
+        assert_eq!(Cursor::parse(page.next_cursor.as_deref()).unwrap(), Some(Cursor::Seq(20)));
+        assert!(Page::from_seq(vec![10], 2, |n| *n).next_cursor.is_none());
+    }
+}
@@ -0,0 +1,40 @@
+//! 数据模型的 JSON Schema
+//!
+//! 前端、TypeScript 客户端和导出结果的使用方可以据此生成类型定义或校验数据。
+
+use schemars::{schema_for, Schema};
+
+use super::{ChatRoom, Contact, Message, Page, Session};
+
+/// 所有对外模型的 JSON Schema，依次为模型名称和 Schema
+pub fn model_schemas() -> Vec<(&'static str, Schema)> {
+    vec![
+        ("Contact", schema_for!(Contact)),
+        ("ChatRoom", schema_for!(ChatRoom)),
+        ("Session", schema_for!(Session)),
+        ("Message", schema_for!(Message)),
+        ("MessagePage", schema_for!(Page<Message>)),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_schemas() {
+        let schemas = model_schemas();
+        let names: Vec<_> = schemas.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, ["Contact", "ChatRoom", "Session", "Message", "MessagePage"]);
+
+        let message = serde_json::to_value(&schemas[3].1).unwrap();
+        assert_eq!(message["properties"]["seq"]["type"], "integer");
+        assert_eq!(message["properties"]["time"]["format"], "date-time");
+        let required = message["required"].as_array().unwrap();
+        assert!(required.contains(&"content".into()));
+        assert!(!required.contains(&"tags".into()));
+
+        let chatroom = serde_json::to_value(&schemas[1].1).unwrap();
+        assert!(chatroom["$defs"]["ChatRoomMember"].is_object());
+    }
+}
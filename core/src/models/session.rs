@@ -1,14 +1,26 @@
 //! 会话数据模型
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use ts_rs::TS;
 use chrono::{DateTime, Utc};
 
 /// 会话结构
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, TS)]
+#[ts(export)]
 pub struct Session {
     pub username: String,
     pub last_message_time: DateTime<Utc>,
     pub unread_count: i32,
+    /// 联系人备注或昵称
+    #[serde(default)]
+    pub display_name: Option<String>,
+    /// 最后一条消息的预览
+    #[serde(default)]
+    pub summary: Option<String>,
+    /// 群聊中最后一条消息的发送者名称
+    #[serde(default)]
+    pub last_sender: Option<String>,
 }
 
 impl Session {
@@ -17,6 +29,9 @@ impl Session {
             username,
             last_message_time: Utc::now(),
             unread_count: 0,
+            display_name: None,
+            summary: None,
+            last_sender: None,
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,113 @@
+//! 消息处理插件模块
+//!
+//! 导出时对每条消息依次调用插件，插件可以过滤、改写消息或为消息打标签，
+//! 用户无需修改本库即可定制导出结果。插件在配置文件中声明。
+
+pub mod rhai_plugin;
+
+use crate::errors::Result;
+use crate::models::Message;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+pub use rhai_plugin::RhaiPlugin;
+
+/// 插件对单条消息的处理结果
+#[derive(Debug, Clone)]
+pub enum HookAction {
+    /// 保留消息（可能已被改写），装箱以免放大 `Drop` 的大小
+    Keep(Box<Message>),
+    /// 丢弃消息
+    Drop,
+}
+
+/// 消息处理钩子接口
+pub trait MessageHook: Send + Sync {
+    /// 插件名称
+    fn name(&self) -> &str;
+
+    /// 处理一条消息
+    fn on_message(&self, message: Message) -> Result<HookAction>;
+}
+
+/// 插件声明（来自配置文件）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginConfig {
+    /// 插件名称
+    pub name: String,
+    /// 脚本文件路径
+    pub script: PathBuf,
+    /// 是否启用
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// 插件链
+///
+/// 按声明顺序执行所有插件，任何一个插件丢弃消息后不再调用后续插件。
+#[derive(Default)]
+pub struct PluginChain {
+    hooks: Vec<Box<dyn MessageHook>>,
+}
+
+impl PluginChain {
+    /// 创建空的插件链
+    pub fn new() -> Self {
+        Self { hooks: Vec::new() }
+    }
+
+    /// 根据配置加载所有启用的插件
+    pub fn from_configs(configs: &[PluginConfig]) -> Result<Self> {
+        let mut chain = Self::new();
+        for config in configs.iter().filter(|c| c.enabled) {
+            let plugin = RhaiPlugin::from_file(&config.name, &config.script)?;
+            tracing::info!("已加载消息插件: {} ({:?})", config.name, config.script);
+            chain.push(Box::new(plugin));
+        }
+        Ok(chain)
+    }
+
+    /// 追加一个插件
+    pub fn push(&mut self, hook: Box<dyn MessageHook>) {
+        self.hooks.push(hook);
+    }
+
+    /// 插件数量
+    pub fn len(&self) -> usize {
+        self.hooks.len()
+    }
+
+    /// 是否没有任何插件
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// 处理单条消息，返回 `None` 表示消息被丢弃
+    ///
+    /// 插件执行出错时记录警告并保留该插件处理前的消息，避免单条消息中断整个导出。
+    pub fn process(&self, message: Message) -> Option<Message> {
+        let mut current = message;
+        for hook in &self.hooks {
+            match hook.on_message(current.clone()) {
+                Ok(HookAction::Keep(next)) => current = *next,
+                Ok(HookAction::Drop) => return None,
+                Err(e) => {
+                    tracing::warn!("插件 {} 处理消息 {} 失败: {}", hook.name(), current.seq, e);
+                }
+            }
+        }
+        Some(current)
+    }
+
+    /// 批量处理消息
+    pub fn process_all(&self, messages: Vec<Message>) -> Vec<Message> {
+        if self.is_empty() {
+            return messages;
+        }
+        messages.into_iter().filter_map(|m| self.process(m)).collect()
+    }
+}
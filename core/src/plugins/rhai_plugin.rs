@@ -0,0 +1,164 @@
+//! 基于 Rhai 脚本的消息插件
+//!
+//! 脚本需要定义 `on_message(msg)` 函数，`msg` 是消息的对象映射：
+//! - 返回 `false` 丢弃消息
+//! - 返回修改后的 `msg` 替换原消息
+//! - 返回 `true` 或不返回值则保留原消息
+//!
+//! ```rhai
+//! fn on_message(msg) {
+//!     if msg.msg_type == 47 { return false; }          // 丢弃表情
+//!     msg.content.replace("密码", "**");                // 关键字脱敏（原地替换）
+//!     msg.tags.push("reviewed");
+//!     msg
+//! }
+//! ```
+
+use super::{HookAction, MessageHook};
+use crate::errors::{PluginError, Result};
+use crate::models::Message;
+use rhai::{Dynamic, Engine, Scope, AST};
+use std::path::Path;
+
+/// 脚本入口函数名
+const ENTRY_FN: &str = "on_message";
+/// 单次调用允许的最大操作数，防止脚本死循环
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// Rhai 脚本插件
+pub struct RhaiPlugin {
+    name: String,
+    engine: Engine,
+    ast: AST,
+}
+
+impl RhaiPlugin {
+    /// 从脚本源码创建插件
+    pub fn from_source(name: &str, source: &str) -> Result<Self> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+
+        let ast = engine.compile(source).map_err(|e| PluginError::LoadFailed {
+            name: name.to_string(),
+            reason: e.to_string(),
+        })?;
+
+        if !ast.iter_functions().any(|f| f.name == ENTRY_FN) {
+            return Err(PluginError::LoadFailed {
+                name: name.to_string(),
+                reason: format!("脚本缺少 {} 函数", ENTRY_FN),
+            }
+            .into());
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            engine,
+            ast,
+        })
+    }
+
+    /// 从脚本文件创建插件
+    pub fn from_file(name: &str, path: &Path) -> Result<Self> {
+        let source = std::fs::read_to_string(path).map_err(|e| PluginError::LoadFailed {
+            name: name.to_string(),
+            reason: format!("读取脚本 {:?} 失败: {}", path, e),
+        })?;
+        Self::from_source(name, &source)
+    }
+
+    fn execution_error(&self, reason: impl ToString) -> PluginError {
+        PluginError::ExecutionFailed {
+            name: self.name.clone(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl MessageHook for RhaiPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn on_message(&self, message: Message) -> Result<HookAction> {
+        let mut input = rhai::serde::to_dynamic(&message).map_err(|e| self.execution_error(e))?;
+        // 空标签在序列化时会被省略，这里补上以便脚本直接 push
+        if let Some(mut map) = input.write_lock::<rhai::Map>() {
+            map.entry("tags".into()).or_insert_with(|| Dynamic::from_array(Vec::new()));
+        }
+
+        let mut scope = Scope::new();
+        let output: Dynamic = self
+            .engine
+            .call_fn(&mut scope, &self.ast, ENTRY_FN, (input,))
+            .map_err(|e| self.execution_error(e))?;
+
+        if output.is_unit() {
+            return Ok(HookAction::Keep(Box::new(message)));
+        }
+
+        if let Some(keep) = output.clone().try_cast::<bool>() {
+            return Ok(if keep { HookAction::Keep(Box::new(message)) } else { HookAction::Drop });
+        }
+
+        if output.is_map() {
+            let updated: Message =
+                rhai::serde::from_dynamic(&output).map_err(|e| self.execution_error(e))?;
+            return Ok(HookAction::Keep(Box::new(updated)));
+        }
+
+        Err(self
+            .execution_error(format!("{} 返回了不支持的类型: {}", ENTRY_FN, output.type_name()))
+            .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_message(msg_type: i64, content: &str) -> Message {
+        let mut message = Message::new();
+        message.msg_type = msg_type;
+        message.content = content.to_string();
+        message
+    }
+
+    #[test]
+    fn test_drop_message() {
+        let plugin = RhaiPlugin::from_source(
+            "drop-stickers",
+            "fn on_message(msg) { if msg.msg_type == 47 { return false; } true }",
+        )
+        .unwrap();
+
+        assert!(matches!(plugin.on_message(sample_message(47, "")).unwrap(), HookAction::Drop));
+        assert!(matches!(plugin.on_message(sample_message(1, "hi")).unwrap(), HookAction::Keep(_)));
+    }
+
+    #[test]
+    fn test_transform_and_tag() {
+        let plugin = RhaiPlugin::from_source(
+            "redact",
+            r#"fn on_message(msg) {
+                msg.content.replace("secret", "***");
+                msg.tags.push("redacted");
+                msg
+            }"#,
+        )
+        .unwrap();
+
+        match plugin.on_message(sample_message(1, "my secret")).unwrap() {
+            HookAction::Keep(m) => {
+                assert_eq!(m.content, "my ***");
+                assert_eq!(m.tags, vec!["redacted".to_string()]);
+            }
+            HookAction::Drop => panic!("消息不应被丢弃"),
+        }
+    }
+
+    #[test]
+    fn test_missing_entry_fn() {
+        assert!(RhaiPlugin::from_source("empty", "let x = 1;").is_err());
+    }
+}
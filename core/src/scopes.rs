@@ -0,0 +1,109 @@
+//! 权限范围
+//!
+//! HTTP 令牌和 MCP 会话按范围授权，例如只给大模型 `read:messages`，
+//! 它就能读取聊天记录，却无法触发密钥提取或解密。
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::errors::{ConfigError, MwxDumpError};
+
+/// 权限范围
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Scope {
+    /// 读取消息（含搜索和导出）
+    #[serde(rename = "read:messages")]
+    ReadMessages,
+    /// 读取联系人
+    #[serde(rename = "read:contacts")]
+    ReadContacts,
+    /// 读取图片、视频和文件
+    #[serde(rename = "read:media")]
+    ReadMedia,
+    /// 提取密钥、解密和备份
+    #[serde(rename = "run:decrypt")]
+    RunDecrypt,
+}
+
+impl Scope {
+    /// 所有权限范围
+    pub const ALL: [Scope; 4] = [Scope::ReadMessages, Scope::ReadContacts, Scope::ReadMedia, Scope::RunDecrypt];
+
+    /// 范围名称，如 `read:messages`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::ReadMessages => "read:messages",
+            Scope::ReadContacts => "read:contacts",
+            Scope::ReadMedia => "read:media",
+            Scope::RunDecrypt => "run:decrypt",
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Scope {
+    type Err = MwxDumpError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Scope::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == s)
+            .ok_or_else(|| {
+                ConfigError::InvalidValue {
+                    key: "scope".to_string(),
+                    value: s.to_string(),
+                }
+                .into()
+            })
+    }
+}
+
+/// 授予的权限范围集合
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Scopes(BTreeSet<Scope>);
+
+impl Scopes {
+    /// 由范围列表创建
+    pub fn new(scopes: impl IntoIterator<Item = Scope>) -> Self {
+        Self(scopes.into_iter().collect())
+    }
+
+    /// 拥有全部权限
+    pub fn all() -> Self {
+        Self::new(Scope::ALL)
+    }
+
+    /// 是否拥有该权限
+    pub fn contains(&self, scope: Scope) -> bool {
+        self.0.contains(&scope)
+    }
+
+    /// `required` 中第一个未授予的权限，全部授予时返回 `None`
+    pub fn missing(&self, required: &[Scope]) -> Option<Scope> {
+        required.iter().copied().find(|scope| !self.contains(*scope))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scopes() {
+        assert_eq!("run:decrypt".parse::<Scope>().unwrap(), Scope::RunDecrypt);
+        assert!("write:everything".parse::<Scope>().is_err());
+        assert_eq!(serde_json::to_string(&Scope::ReadMedia).unwrap(), "\"read:media\"");
+
+        let reader = Scopes::new([Scope::ReadMessages, Scope::ReadContacts]);
+        assert_eq!(reader.missing(&[Scope::ReadMessages, Scope::ReadContacts]), None);
+        assert_eq!(reader.missing(&[Scope::ReadMessages, Scope::RunDecrypt]), Some(Scope::RunDecrypt));
+        assert_eq!(Scopes::all().missing(&Scope::ALL), None);
+    }
+}
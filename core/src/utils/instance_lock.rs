@@ -0,0 +1,107 @@
+//! 单实例锁
+//!
+//! 为输出目录加独占文件锁，防止多个进程同时写入同一目录。锁文件放在系统临时目录的
+//! `mwxdump-locks` 下，按输出目录的绝对路径哈希命名，不会混入输出目录（快照、清单和导出结果）。
+//! 锁随 `InstanceLock` 释放而解除（进程异常退出时由操作系统自动释放）。
+//!
+//! Windows 的文件锁是强制锁，持有期间其他进程无法读取被锁的文件，因此持有者 PID 写在
+//! 不加锁的 `.pid` 文件中，获取失败时从中读取并报告。
+
+use sha2::{Digest, Sha256};
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+
+use crate::errors::{Result, SystemError};
+
+/// 锁文件所在的目录名，位于系统临时目录下
+pub const LOCK_DIR_NAME: &str = "mwxdump-locks";
+
+/// 目录级单实例锁
+#[derive(Debug)]
+pub struct InstanceLock {
+    file: File,
+    path: PathBuf,
+    pid_path: PathBuf,
+}
+
+impl InstanceLock {
+    /// 尝试获取目录锁，目录已被其他进程锁定时立即返回错误
+    pub fn acquire(dir: &Path) -> Result<Self> {
+        Self::acquire_in(dir, &std::env::temp_dir().join(LOCK_DIR_NAME))
+    }
+
+    /// 在 `lock_root` 下为 `dir` 创建锁文件并加锁
+    fn acquire_in(dir: &Path, lock_root: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        std::fs::create_dir_all(lock_root)?;
+        let name = lock_name(&std::fs::canonicalize(dir)?);
+        let path = lock_root.join(format!("{}.lock", name));
+        let pid_path = lock_root.join(format!("{}.pid", name));
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+
+        if file.try_lock().is_err() {
+            let holder = std::fs::read_to_string(&pid_path).unwrap_or_default();
+            return Err(SystemError::InstanceLocked {
+                path: dir.display().to_string(),
+                holder: holder.trim().to_string(),
+            }
+            .into());
+        }
+
+        // 记录持有者 PID，便于排查
+        std::fs::write(&pid_path, std::process::id().to_string())?;
+
+        Ok(Self { file, path, pid_path })
+    }
+
+    /// 锁文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// 按目录绝对路径计算锁文件名
+fn lock_name(dir: &Path) -> String {
+    let digest = Sha256::digest(dir.as_os_str().as_encoded_bytes());
+    hex::encode(&digest[..16])
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        // 锁文件本身保留，只解除锁，避免删除与重新加锁之间的竞争；PID 文件在解锁前删除
+        std::fs::remove_file(&self.pid_path).ok();
+        self.file.unlock().ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_second_lock_fails_until_released() {
+        let dir = tempfile::tempdir().unwrap();
+        let locks = tempfile::tempdir().unwrap();
+
+        let first = InstanceLock::acquire_in(dir.path(), locks.path()).unwrap();
+        let err = InstanceLock::acquire_in(dir.path(), locks.path()).unwrap_err();
+        assert!(err.to_string().contains(&std::process::id().to_string()));
+        // 输出目录中不留锁文件
+        assert!(!first.path().starts_with(dir.path()));
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+        drop(first);
+        assert!(InstanceLock::acquire_in(dir.path(), locks.path()).is_ok());
+        // 同一目录的不同写法使用同一把锁
+        let nested = dir.path().join("sub").join("..");
+        let _held = InstanceLock::acquire_in(dir.path(), locks.path()).unwrap();
+        std::fs::create_dir_all(dir.path().join("sub")).unwrap();
+        assert!(InstanceLock::acquire_in(&nested, locks.path()).is_err());
+    }
+}
@@ -0,0 +1,299 @@
+//! 进程内存快照
+//!
+//! 把微信进程的可读内存区域写入一个带索引的快照文件，之后可以在微信已退出、
+//! 甚至在其他平台上反复分析（如离线搜索密钥），不必每次都保持微信运行。
+//!
+//! 文件格式（整数均为小端序）：
+//!
+//! ```text
+//! [魔数 8 字节][格式版本 u32][保留 u32]
+//! [区域数据 ...]                   各区域按顺序紧密排列
+//! [索引 JSON]                      SnapshotIndex：进程信息和每个区域的地址、大小、文件偏移
+//! [索引偏移 u64][魔数 8 字节]
+//! ```
+//!
+//! 索引写在末尾，采集时边读边写，不需要预先知道区域数量。
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::errors::{Result, WeChatError};
+
+/// 快照文件魔数
+pub const SNAPSHOT_MAGIC: &[u8; 8] = b"MWXSNAP\0";
+/// 快照格式版本
+pub const SNAPSHOT_VERSION: u32 = 1;
+/// 快照文件的默认扩展名
+pub const SNAPSHOT_EXTENSION: &str = "mwxsnap";
+
+/// 文件头长度：魔数 + 版本 + 保留
+const HEADER_LEN: u64 = 16;
+/// 文件尾长度：索引偏移 + 魔数
+const FOOTER_LEN: u64 = 16;
+
+/// 要采集的内存区域
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotRegions {
+    /// 私有可写区域（堆），密钥等运行时数据所在，体积较小
+    #[default]
+    Private,
+    /// 所有已提交的可读区域，包括模块映像，体积较大
+    Readable,
+}
+
+/// 被采集进程的信息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotProcess {
+    /// 进程ID
+    pub pid: u32,
+    /// 进程名称
+    pub name: String,
+    /// 微信版本
+    pub version: String,
+    /// 进程架构，如 `x64`、`ARM64`
+    pub architecture: String,
+    /// 指针大小（字节），离线分析时按此解析指针
+    pub pointer_size: usize,
+}
+
+/// 快照中的一个内存区域
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotRegion {
+    /// 区域在进程中的起始地址
+    pub address: usize,
+    /// 已保存的字节数
+    pub size: usize,
+    /// 数据在快照文件中的偏移
+    pub offset: u64,
+}
+
+impl SnapshotRegion {
+    /// 地址是否落在区域内
+    pub fn contains(&self, address: usize) -> bool {
+        address >= self.address && address - self.address < self.size
+    }
+}
+
+/// 快照索引
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotIndex {
+    /// 被采集的进程
+    pub process: SnapshotProcess,
+    /// 采集的区域类型
+    pub kind: SnapshotRegions,
+    /// 采集时间
+    pub captured_at: DateTime<Utc>,
+    /// 区域列表，按地址升序排列
+    pub regions: Vec<SnapshotRegion>,
+}
+
+impl SnapshotIndex {
+    /// 保存的内存总字节数
+    pub fn total_bytes(&self) -> u64 {
+        self.regions.iter().map(|r| r.size as u64).sum()
+    }
+}
+
+/// 快照写入器
+pub struct SnapshotWriter {
+    file: BufWriter<File>,
+    index: SnapshotIndex,
+    offset: u64,
+}
+
+impl SnapshotWriter {
+    /// 创建快照文件并写入文件头
+    pub fn create(path: &Path, process: SnapshotProcess, kind: SnapshotRegions) -> Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(SNAPSHOT_MAGIC)?;
+        file.write_all(&SNAPSHOT_VERSION.to_le_bytes())?;
+        file.write_all(&0u32.to_le_bytes())?;
+        Ok(Self {
+            file,
+            index: SnapshotIndex {
+                process,
+                kind,
+                captured_at: Utc::now(),
+                regions: Vec::new(),
+            },
+            offset: HEADER_LEN,
+        })
+    }
+
+    /// 追加一个区域的数据，空数据忽略
+    pub fn add_region(&mut self, address: usize, data: &[u8]) -> Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        self.file.write_all(data)?;
+        self.index.regions.push(SnapshotRegion {
+            address,
+            size: data.len(),
+            offset: self.offset,
+        });
+        self.offset += data.len() as u64;
+        Ok(())
+    }
+
+    /// 写入索引和文件尾，返回索引
+    pub fn finish(mut self) -> Result<SnapshotIndex> {
+        self.index.regions.sort_by_key(|r| r.address);
+        self.file.write_all(&serde_json::to_vec(&self.index)?)?;
+        self.file.write_all(&self.offset.to_le_bytes())?;
+        self.file.write_all(SNAPSHOT_MAGIC)?;
+        self.file.flush()?;
+        Ok(self.index)
+    }
+}
+
+/// 打开的快照
+pub struct MemorySnapshot {
+    path: PathBuf,
+    index: SnapshotIndex,
+}
+
+impl MemorySnapshot {
+    /// 打开快照并读取索引
+    pub fn open(path: &Path) -> Result<Self> {
+        let invalid = |reason: &str| WeChatError::CorruptedFile {
+            path: format!("{}（{}）", path.display(), reason),
+        };
+        let mut file = File::open(path)?;
+        let len = file.metadata()?.len();
+        if len < HEADER_LEN + FOOTER_LEN {
+            return Err(invalid("文件过短").into());
+        }
+
+        let mut header = [0u8; HEADER_LEN as usize];
+        file.read_exact(&mut header)?;
+        if &header[..8] != SNAPSHOT_MAGIC {
+            return Err(invalid("不是内存快照文件").into());
+        }
+        let version = u32::from_le_bytes(header[8..12].try_into().unwrap());
+        if version != SNAPSHOT_VERSION {
+            return Err(invalid(&format!("不支持的快照版本 {}", version)).into());
+        }
+
+        let mut footer = [0u8; FOOTER_LEN as usize];
+        file.seek(SeekFrom::End(-(FOOTER_LEN as i64)))?;
+        file.read_exact(&mut footer)?;
+        if &footer[8..] != SNAPSHOT_MAGIC {
+            return Err(invalid("文件不完整，采集可能被中断").into());
+        }
+        let index_offset = u64::from_le_bytes(footer[..8].try_into().unwrap());
+        if index_offset < HEADER_LEN || index_offset > len - FOOTER_LEN {
+            return Err(invalid("索引位置无效").into());
+        }
+
+        let mut index = vec![0u8; (len - FOOTER_LEN - index_offset) as usize];
+        file.seek(SeekFrom::Start(index_offset))?;
+        file.read_exact(&mut index)?;
+        let index: SnapshotIndex = serde_json::from_slice(&index).map_err(|e| invalid(&e.to_string()))?;
+        if index.regions.iter().any(|r| r.offset + r.size as u64 > index_offset) {
+            return Err(invalid("区域超出数据范围").into());
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            index,
+        })
+    }
+
+    /// 快照索引
+    pub fn index(&self) -> &SnapshotIndex {
+        &self.index
+    }
+
+    /// 读取一个区域的全部数据
+    pub fn read_region(&self, region: &SnapshotRegion) -> Result<Vec<u8>> {
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(region.offset))?;
+        let mut data = vec![0u8; region.size];
+        file.read_exact(&mut data)?;
+        Ok(data)
+    }
+
+    /// 按进程地址读取数据，地址范围不完全落在某个区域内时返回 `None`
+    pub fn read(&self, address: usize, len: usize) -> Option<Vec<u8>> {
+        let position = self.index.regions.partition_point(|r| r.address <= address);
+        let region = self.index.regions[..position].last().filter(|r| r.contains(address))?;
+        if address - region.address + len > region.size {
+            return None;
+        }
+        let mut file = File::open(&self.path).ok()?;
+        file.seek(SeekFrom::Start(region.offset + (address - region.address) as u64)).ok()?;
+        let mut data = vec![0u8; len];
+        file.read_exact(&mut data).ok()?;
+        Some(data)
+    }
+}
+
+/// 采集进程内存到快照文件（阻塞），在异步上下文中应放入 `spawn_blocking`
+#[cfg(target_os = "windows")]
+pub fn capture(path: &Path, pid: u32, name: &str, version: &str, kind: SnapshotRegions) -> Result<SnapshotIndex> {
+    super::windows::mem_snapshot::capture(path, pid, name, version, kind)
+}
+
+/// 采集进程内存到快照文件（当前平台不支持）
+#[cfg(not(target_os = "windows"))]
+pub fn capture(_path: &Path, _pid: u32, _name: &str, _version: &str, _kind: SnapshotRegions) -> Result<SnapshotIndex> {
+    Err(WeChatError::KeyExtractionFailed("当前平台不支持读取进程内存".to_string()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process() -> SnapshotProcess {
+        SnapshotProcess {
+            pid: 4242,
+            name: "Weixin.exe".to_string(),
+            version: "4.0.3.22".to_string(),
+            architecture: "x64".to_string(),
+            pointer_size: 8,
+        }
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(format!("wechat.{}", SNAPSHOT_EXTENSION));
+
+        let mut writer = SnapshotWriter::create(&path, process(), SnapshotRegions::Private).unwrap();
+        writer.add_region(0x20000, &[0xAA; 64]).unwrap();
+        writer.add_region(0x10000, &(0u8..=255).collect::<Vec<_>>()).unwrap();
+        writer.add_region(0x30000, &[]).unwrap();
+        let written = writer.finish().unwrap();
+        assert_eq!(written.regions.len(), 2);
+        assert_eq!(written.total_bytes(), 320);
+
+        let snapshot = MemorySnapshot::open(&path).unwrap();
+        assert_eq!(snapshot.index(), &written);
+        assert_eq!(snapshot.index().regions[0].address, 0x10000);
+        assert_eq!(snapshot.read(0x10010, 4), Some(vec![0x10, 0x11, 0x12, 0x13]));
+        assert_eq!(snapshot.read(0x20000 + 60, 4), Some(vec![0xAA; 4]));
+        assert_eq!(snapshot.read(0x20000 + 62, 4), None);
+        assert_eq!(snapshot.read(0x18000, 1), None);
+        assert_eq!(snapshot.read_region(&snapshot.index().regions[1]).unwrap(), vec![0xAA; 64]);
+    }
+
+    #[test]
+    fn test_rejects_truncated_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("broken.mwxsnap");
+        let mut writer = SnapshotWriter::create(&path, process(), SnapshotRegions::Readable).unwrap();
+        writer.add_region(0x10000, &[1; 128]).unwrap();
+        writer.finish().unwrap();
+
+        let content = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &content[..content.len() - 4]).unwrap();
+        let err = MemorySnapshot::open(&path).err().unwrap();
+        assert!(matches!(err.downcast_ref::<WeChatError>(), Some(WeChatError::CorruptedFile { .. })));
+
+        std::fs::write(&path, b"not a snapshot at all, just text").unwrap();
+        assert!(MemorySnapshot::open(&path).is_err());
+    }
+}
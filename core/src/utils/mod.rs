@@ -1,8 +1,14 @@
 //! 辅助类
 //!
 
+pub mod instance_lock;
+pub mod memory_snapshot;
+pub mod task;
+#[cfg(target_os = "windows")]
 pub mod windows;
 
+pub use instance_lock::InstanceLock;
+
 #[derive(Debug, Clone)]
 pub struct ProcessInfo {
     pub parent_pid: u32, // 父进程的 PID
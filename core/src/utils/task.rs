@@ -0,0 +1,68 @@
+//! 任务与线程的 panic 隔离
+//!
+//! 工作线程或阻塞任务 panic 时，把 panic 信息转为调用方指定的错误返回，
+//! 不让一个工作线程的 panic 结束整个进程，也不丢失 panic 的原因。
+
+use std::any::Any;
+use tokio::task::JoinError;
+
+use crate::errors::{MwxDumpError, Result, WeChatError};
+
+/// panic 载荷中的信息，载荷不是字符串时返回占位说明
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "非字符串的 panic 信息".to_string())
+}
+
+/// 把任务的 [`JoinError`] 转为错误：panic 时由 `wrap` 包装 panic 信息，任务被取消时返回
+/// `MwxDumpError::Cancelled`
+pub fn join_error(error: JoinError, wrap: impl FnOnce(String) -> WeChatError) -> anyhow::Error {
+    match error.try_into_panic() {
+        Ok(payload) => wrap(format!("任务 panic: {}", panic_message(payload.as_ref()))).into(),
+        Err(_) => MwxDumpError::Cancelled.into(),
+    }
+}
+
+/// 把线程 `join` 的结果转为错误，线程 panic 时由 `wrap` 包装 panic 信息
+pub fn thread_result<T>(result: std::thread::Result<T>, wrap: impl FnOnce(String) -> WeChatError) -> Result<T> {
+    result.map_err(|payload| wrap(format!("线程 panic: {}", panic_message(payload.as_ref()))).into())
+}
+
+/// 在阻塞线程池中执行 `f`，panic 时由 `wrap` 转为错误
+pub async fn spawn_blocking<T, F>(f: F, wrap: impl FnOnce(String) -> WeChatError) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f).await.map_err(|e| join_error(e, wrap))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_panics_become_errors() {
+        let error = spawn_blocking(|| -> Result<()> { panic!("读取内存越界") }, WeChatError::KeyExtractionFailed)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<WeChatError>(),
+            Some(WeChatError::KeyExtractionFailed(message)) if message.contains("读取内存越界")
+        ));
+        assert_eq!(spawn_blocking(|| Ok(7), WeChatError::KeyExtractionFailed).await.unwrap(), 7);
+
+        let code = 3;
+        let joined = std::thread::spawn(move || -> () { panic!("错误码 {}", code) }).join();
+        let error = thread_result(joined, WeChatError::DecryptionFailed).unwrap_err();
+        assert!(error.to_string().contains("错误码 3"));
+
+        let handle = tokio::spawn(std::future::pending::<()>());
+        handle.abort();
+        let error = join_error(handle.await.unwrap_err(), WeChatError::DecryptionFailed);
+        assert!(matches!(error.downcast_ref::<MwxDumpError>(), Some(MwxDumpError::Cancelled)));
+    }
+}
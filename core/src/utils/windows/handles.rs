@@ -0,0 +1,142 @@
+//! # 进程打开的文件句柄
+//!
+//! 通过 `NtQuerySystemInformation(SystemExtendedHandleInformation)` 列举系统中所有句柄，
+//! 把目标进程的句柄复制到当前进程后查询对应的文件路径。只查询磁盘文件，
+//! 管道等句柄查询路径可能一直阻塞，直接跳过。
+
+use crate::errors::Result;
+use anyhow::bail;
+use std::ffi::c_void;
+use std::path::PathBuf;
+use windows::Win32::{
+    Foundation::{DuplicateHandle, DUPLICATE_SAME_ACCESS, HANDLE, STATUS_INFO_LENGTH_MISMATCH},
+    Storage::FileSystem::{GetFileType, GetFinalPathNameByHandleW, FILE_NAME_NORMALIZED, FILE_TYPE_DISK},
+    System::{
+        Threading::{GetCurrentProcess, OpenProcess, PROCESS_DUP_HANDLE},
+        WindowsProgramming::{NtQuerySystemInformation, SYSTEM_INFORMATION_CLASS},
+    },
+};
+
+use super::handle::Handle;
+
+/// `SystemExtendedHandleInformation`，返回带64位进程ID和句柄值的句柄表
+const SYSTEM_EXTENDED_HANDLE_INFORMATION: SYSTEM_INFORMATION_CLASS = SYSTEM_INFORMATION_CLASS(64);
+
+/// 句柄表缓冲区的初始大小，不够时按系统返回的长度扩大
+const INITIAL_BUFFER_SIZE: usize = 4 * 1024 * 1024;
+
+/// 句柄表缓冲区的上限，防止句柄数异常时无限扩大
+const MAX_BUFFER_SIZE: usize = 512 * 1024 * 1024;
+
+/// `SYSTEM_HANDLE_TABLE_ENTRY_INFO_EX`
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct SystemHandleTableEntryInfoEx {
+    object: *mut c_void,
+    unique_process_id: usize,
+    handle_value: usize,
+    granted_access: u32,
+    creator_back_trace_index: u16,
+    object_type_index: u16,
+    handle_attributes: u32,
+    reserved: u32,
+}
+
+/// `SYSTEM_HANDLE_INFORMATION_EX` 的头部，后面紧跟 `number_of_handles` 个句柄项
+#[repr(C)]
+struct SystemHandleInformationEx {
+    number_of_handles: usize,
+    reserved: usize,
+    handles: [SystemHandleTableEntryInfoEx; 1],
+}
+
+/// 列举进程打开的磁盘文件路径
+///
+/// 需要能以 `PROCESS_DUP_HANDLE` 打开目标进程；单个句柄复制或查询失败时跳过。
+pub fn list_open_file_paths(pid: u32) -> Result<Vec<PathBuf>> {
+    let entries = query_process_handles(pid)?;
+    let process = Handle::new(unsafe { OpenProcess(PROCESS_DUP_HANDLE, false, pid)? })?;
+    let current = unsafe { GetCurrentProcess() };
+
+    let mut paths = Vec::new();
+    for entry in entries {
+        let mut duplicated = HANDLE::default();
+        let source = HANDLE(entry.handle_value as *mut c_void);
+        let copied = unsafe {
+            DuplicateHandle(*process, source, current, &mut duplicated, 0, false, DUPLICATE_SAME_ACCESS)
+        };
+        if copied.is_err() {
+            continue;
+        }
+        let Ok(handle) = Handle::new(duplicated) else {
+            continue;
+        };
+        if unsafe { GetFileType(*handle) } != FILE_TYPE_DISK {
+            continue;
+        }
+        if let Some(path) = final_path(&handle) {
+            paths.push(path);
+        }
+    }
+    tracing::debug!("PID {}: 共找到 {} 个打开的文件", pid, paths.len());
+    Ok(paths)
+}
+
+/// 读取系统句柄表，返回属于 `pid` 的句柄项
+fn query_process_handles(pid: u32) -> Result<Vec<SystemHandleTableEntryInfoEx>> {
+    let mut size = INITIAL_BUFFER_SIZE;
+    loop {
+        // 按 usize 分配，保证句柄表的对齐
+        let mut buffer = vec![0usize; size / std::mem::size_of::<usize>()];
+        let mut needed = 0u32;
+        let status = unsafe {
+            NtQuerySystemInformation(
+                SYSTEM_EXTENDED_HANDLE_INFORMATION,
+                buffer.as_mut_ptr() as *mut c_void,
+                size as u32,
+                &mut needed,
+            )
+        };
+        if status == STATUS_INFO_LENGTH_MISMATCH {
+            // 两次调用之间句柄数可能继续增长，多留一些余量
+            size = (needed as usize).max(size) * 2;
+            if size > MAX_BUFFER_SIZE {
+                bail!("系统句柄表超过 {} MB，放弃列举", MAX_BUFFER_SIZE / 1024 / 1024);
+            }
+            continue;
+        }
+        status.ok()?;
+
+        let info = buffer.as_ptr() as *const SystemHandleInformationEx;
+        let count = unsafe { (*info).number_of_handles };
+        let capacity = (size - std::mem::offset_of!(SystemHandleInformationEx, handles))
+            / std::mem::size_of::<SystemHandleTableEntryInfoEx>();
+        let entries = unsafe { std::slice::from_raw_parts((*info).handles.as_ptr(), count.min(capacity)) };
+        return Ok(entries
+            .iter()
+            .filter(|entry| entry.unique_process_id == pid as usize)
+            .copied()
+            .collect());
+    }
+}
+
+/// 句柄对应的文件路径，去掉 `\\?\` 前缀
+fn final_path(handle: &Handle) -> Option<PathBuf> {
+    let mut buffer = vec![0u16; 1024];
+    loop {
+        let len = unsafe { GetFinalPathNameByHandleW(**handle, &mut buffer, FILE_NAME_NORMALIZED) } as usize;
+        if len == 0 {
+            return None;
+        }
+        if len > buffer.len() {
+            buffer.resize(len, 0);
+            continue;
+        }
+        let path = String::from_utf16_lossy(&buffer[..len]);
+        let path = match path.strip_prefix(r"\\?\UNC\") {
+            Some(unc) => format!(r"\\{}", unc),
+            None => path.strip_prefix(r"\\?\").map(str::to_string).unwrap_or(path),
+        };
+        return Some(PathBuf::from(path));
+    }
+}
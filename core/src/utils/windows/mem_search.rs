@@ -0,0 +1,440 @@
+//! # 进程内存搜索引擎
+//!
+//! 生产者线程遍历目标进程的内存区域，按块读取后交给工作线程；工作线程查找特征，
+//! 并把每个命中交给可替换的 [`CandidateValidator`] 验证。验证通过的命中数达到上限后，
+//! 停止信号会结束所有线程。密钥提取（特征前的指针指向密钥）和数据目录验证
+//! （路径字符串本身即为命中）共用此引擎。
+
+use std::ffi::c_void;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use windows::Win32::{
+    Foundation::HANDLE,
+    System::{
+        Diagnostics::Debug::ReadProcessMemory,
+        Memory::{
+            VirtualQueryEx, MEMORY_BASIC_INFORMATION, MEM_COMMIT, MEM_PRIVATE, PAGE_EXECUTE_READ,
+            PAGE_EXECUTE_READWRITE, PAGE_READONLY, PAGE_READWRITE,
+        },
+        Threading::{OpenProcess, PROCESS_QUERY_INFORMATION, PROCESS_VM_READ},
+    },
+};
+
+use super::handle::Handle;
+use crate::errors::{Result, WeChatError};
+use crate::utils::task::thread_result;
+
+/// 用户空间起始地址
+const MIN_ADDRESS: usize = 0x10000;
+
+/// 单次读取的最大块大小，超过的区域分块读取
+const MAX_CHUNK_SIZE: usize = 64 * 1024 * 1024;
+
+/// 指针大小
+const POINTER_SIZE: usize = std::mem::size_of::<usize>();
+
+/// 用户空间最大地址
+pub const fn max_user_address(is_64bit: bool) -> usize {
+    if is_64bit {
+        0x7FFFFFFFFFFF
+    } else {
+        0x7FFFFFFF
+    }
+}
+
+/// 要扫描的内存区域
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionKind {
+    /// 私有可写区域（堆），密钥等运行时数据所在
+    PrivateWritable,
+    /// 所有已提交的可读区域
+    Readable,
+}
+
+/// 搜索配置
+#[derive(Debug, Clone)]
+pub struct SearchConfig {
+    /// 工作线程数
+    pub workers: usize,
+    /// 扫描的区域类型
+    pub regions: RegionKind,
+    /// 最小区域大小（字节），更小的区域跳过
+    pub min_region_size: usize,
+    /// 扫描的最大地址
+    pub max_address: usize,
+    /// 验证通过的命中数上限，达到后停止搜索
+    pub max_hits: usize,
+    /// 在每块内从高地址向低地址查找
+    pub reverse: bool,
+}
+
+impl SearchConfig {
+    /// 堆扫描：大于 1MB 的私有可写区域，块内反向查找，找到一个即停止
+    pub fn heap() -> Self {
+        Self {
+            workers: num_cpus::get().clamp(2, 16),
+            regions: RegionKind::PrivateWritable,
+            min_region_size: 1024 * 1024,
+            max_address: max_user_address(cfg!(target_pointer_width = "64")),
+            max_hits: 1,
+            reverse: true,
+        }
+    }
+
+    /// 全量扫描：所有已提交的可读区域，正向查找，找到一个即停止
+    pub fn readable() -> Self {
+        Self {
+            regions: RegionKind::Readable,
+            min_region_size: 0,
+            reverse: false,
+            ..Self::heap()
+        }
+    }
+
+    /// 设置扫描的最大地址
+    pub fn with_max_address(mut self, max_address: usize) -> Self {
+        self.max_address = max_address;
+        self
+    }
+
+    /// 设置命中数上限
+    pub fn with_max_hits(mut self, max_hits: usize) -> Self {
+        self.max_hits = max_hits.max(1);
+        self
+    }
+
+    pub(super) fn accepts(&self, info: &MEMORY_BASIC_INFORMATION) -> bool {
+        if info.State != MEM_COMMIT || info.RegionSize < self.min_region_size {
+            return false;
+        }
+        match self.regions {
+            RegionKind::PrivateWritable => {
+                info.Type == MEM_PRIVATE && (info.Protect.0 & PAGE_READWRITE.0) != 0
+            }
+            RegionKind::Readable => {
+                let readable = PAGE_READONLY | PAGE_READWRITE | PAGE_EXECUTE_READ | PAGE_EXECUTE_READWRITE;
+                (info.Protect.0 & readable.0) != 0
+            }
+        }
+    }
+}
+
+/// 特征命中位置
+pub struct Candidate<'a> {
+    handle: HANDLE,
+    base: usize,
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl Candidate<'_> {
+    /// 命中位置的地址
+    pub fn address(&self) -> usize {
+        self.base + self.offset
+    }
+
+    /// 命中位置之后（含特征）已读取的数据
+    pub fn bytes(&self) -> &[u8] {
+        &self.data[self.offset..]
+    }
+
+    /// 紧挨特征之前的指针值
+    pub fn pointer_before(&self) -> Option<usize> {
+        let start = self.offset.checked_sub(POINTER_SIZE)?;
+        let bytes = self.data[start..self.offset].try_into().ok()?;
+        Some(usize::from_le_bytes(bytes))
+    }
+
+    /// 从目标进程读取任意地址的数据，读取不完整时返回 `None`
+    pub fn read(&self, address: usize, len: usize) -> Option<Vec<u8>> {
+        let mut buffer = vec![0u8; len];
+        let mut bytes_read = 0;
+        unsafe {
+            ReadProcessMemory(
+                self.handle,
+                address as *const c_void,
+                buffer.as_mut_ptr() as *mut c_void,
+                len,
+                Some(&mut bytes_read),
+            )
+        }
+        .ok()?;
+        (bytes_read == len).then_some(buffer)
+    }
+}
+
+/// 命中验证器
+pub trait CandidateValidator: Send + Sync {
+    /// 验证命中位置，通过时返回要保存的数据（如密钥）
+    fn validate(&self, candidate: &Candidate<'_>) -> Option<Vec<u8>>;
+}
+
+/// 特征本身即为结果，不做额外验证
+pub struct MatchOnly;
+
+impl CandidateValidator for MatchOnly {
+    fn validate(&self, _candidate: &Candidate<'_>) -> Option<Vec<u8>> {
+        Some(Vec::new())
+    }
+}
+
+/// 验证通过的命中
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    /// 特征所在地址
+    pub address: usize,
+    /// 验证器返回的数据
+    pub data: Vec<u8>,
+    /// 验证通过的先后顺序
+    pub order: usize,
+}
+
+/// 搜索结果
+#[derive(Debug, Clone, Default)]
+pub struct SearchReport {
+    /// 验证通过的命中，按先后顺序排列
+    pub hits: Vec<SearchHit>,
+    /// 读取的区域数
+    pub regions: usize,
+    /// 特征命中总数
+    pub candidates: usize,
+    /// 验证未通过的命中数
+    pub rejected: usize,
+}
+
+/// 读取的内存块；只在前 `scan_len` 字节内查找特征起点，其后为与下一块重叠的部分
+struct Chunk {
+    base: usize,
+    data: Vec<u8>,
+    scan_len: usize,
+}
+
+/// 线程间共享的计数和停止信号
+#[derive(Default)]
+struct SearchState {
+    stop: AtomicBool,
+    regions: AtomicUsize,
+    candidates: AtomicUsize,
+    accepted: AtomicUsize,
+    rejected: AtomicUsize,
+}
+
+/// 进程内存搜索引擎
+pub struct MemorySearchEngine<V: CandidateValidator> {
+    pattern: Vec<u8>,
+    validator: Arc<V>,
+    config: SearchConfig,
+}
+
+impl<V: CandidateValidator + 'static> MemorySearchEngine<V> {
+    /// 使用堆扫描配置创建搜索引擎
+    pub fn new(pattern: Vec<u8>, validator: V) -> Self {
+        Self {
+            pattern,
+            validator: Arc::new(validator),
+            config: SearchConfig::heap(),
+        }
+    }
+
+    /// 设置搜索配置
+    pub fn with_config(mut self, config: SearchConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// 在指定进程中搜索（阻塞），在异步上下文中应放入 `spawn_blocking`
+    pub fn search(&self, pid: u32) -> Result<SearchReport> {
+        if self.pattern.is_empty() {
+            return Ok(SearchReport::default());
+        }
+        // 提前打开一次，进程不存在或无权限时直接返回错误
+        Handle::new(unsafe { OpenProcess(PROCESS_VM_READ | PROCESS_QUERY_INFORMATION, false, pid)? })?;
+
+        let state = Arc::new(SearchState::default());
+        let (chunk_sender, chunk_receiver) = crossbeam_channel::bounded::<Chunk>(self.config.workers * 2);
+        let (hit_sender, hit_receiver) = crossbeam_channel::unbounded::<SearchHit>();
+
+        tracing::debug!("启动 {} 个内存搜索线程", self.config.workers);
+        let mut workers = Vec::with_capacity(self.config.workers);
+        for i in 0..self.config.workers {
+            let receiver = chunk_receiver.clone();
+            let sender = hit_sender.clone();
+            let state = Arc::clone(&state);
+            let validator = Arc::clone(&self.validator);
+            let pattern = self.pattern.clone();
+            let config = self.config.clone();
+            workers.push(
+                thread::Builder::new()
+                    .name(format!("mem-search-{}", i))
+                    .spawn(move || {
+                        if let Err(e) = worker(pid, receiver, sender, &state, validator.as_ref(), &pattern, &config) {
+                            tracing::debug!("内存搜索线程退出: {}", e);
+                        }
+                    })
+                    .map_err(|e| WeChatError::KeyExtractionFailed(format!("启动搜索线程失败: {}", e)))?,
+            );
+        }
+        drop(hit_sender);
+        drop(chunk_receiver);
+
+        producer(pid, chunk_sender, &state, self.pattern.len(), &self.config);
+        // 先等待全部线程退出再报告 panic，避免留下仍在读取进程内存的线程
+        let joined: Vec<_> = workers.into_iter().map(|handle| handle.join()).collect();
+        for result in joined {
+            thread_result(result, WeChatError::KeyExtractionFailed)?;
+        }
+
+        let mut hits: Vec<SearchHit> = hit_receiver.try_iter().collect();
+        hits.sort_by_key(|hit| hit.order);
+        hits.truncate(self.config.max_hits);
+        let report = SearchReport {
+            hits,
+            regions: state.regions.load(Ordering::Relaxed),
+            candidates: state.candidates.load(Ordering::Relaxed),
+            rejected: state.rejected.load(Ordering::Relaxed),
+        };
+        tracing::debug!(
+            "内存搜索结束: {} 个区域，{} 个特征命中，{} 个通过验证，{} 个未通过",
+            report.regions,
+            report.candidates,
+            report.hits.len(),
+            report.rejected
+        );
+        Ok(report)
+    }
+}
+
+/// 生产者：遍历内存区域并按块读取
+fn producer(
+    pid: u32,
+    sender: crossbeam_channel::Sender<Chunk>,
+    state: &SearchState,
+    pattern_len: usize,
+    config: &SearchConfig,
+) {
+    let handle = match unsafe { OpenProcess(PROCESS_VM_READ | PROCESS_QUERY_INFORMATION, false, pid) }
+        .map_err(Into::into)
+        .and_then(Handle::new)
+    {
+        Ok(handle) => handle,
+        Err(e) => {
+            tracing::debug!("打开进程 {} 失败: {}", pid, e);
+            return;
+        }
+    };
+
+    let mut current = MIN_ADDRESS;
+    while current < config.max_address && !state.stop.load(Ordering::SeqCst) {
+        let mut info = MEMORY_BASIC_INFORMATION::default();
+        let size = std::mem::size_of::<MEMORY_BASIC_INFORMATION>();
+        if unsafe { VirtualQueryEx(*handle, Some(current as *const c_void), &mut info, size) } == 0 {
+            break;
+        }
+        let region_base = info.BaseAddress as usize;
+        let region_end = region_base.saturating_add(info.RegionSize);
+
+        if config.accepts(&info) {
+            state.regions.fetch_add(1, Ordering::Relaxed);
+            let mut chunk_base = region_base;
+            while chunk_base < region_end && !state.stop.load(Ordering::SeqCst) {
+                let scan_len = MAX_CHUNK_SIZE.min(region_end - chunk_base);
+                // 多读特征长度减一的字节，跨块的特征也能被找到
+                let read_len = (scan_len + pattern_len - 1).min(region_end - chunk_base);
+                let mut data = vec![0u8; read_len];
+                let mut bytes_read = 0;
+                let read = unsafe {
+                    ReadProcessMemory(
+                        *handle,
+                        chunk_base as *const c_void,
+                        data.as_mut_ptr() as *mut c_void,
+                        read_len,
+                        Some(&mut bytes_read),
+                    )
+                };
+                if read.is_err() || bytes_read == 0 {
+                    break;
+                }
+                data.truncate(bytes_read);
+                let chunk = Chunk {
+                    base: chunk_base,
+                    scan_len: scan_len.min(bytes_read),
+                    data,
+                };
+                if sender.send(chunk).is_err() {
+                    // 工作线程已全部退出
+                    return;
+                }
+                chunk_base += scan_len;
+            }
+        }
+
+        if region_end <= current {
+            tracing::debug!("内存区域地址未前进: {:#X} -> {:#X}", current, region_end);
+            break;
+        }
+        current = region_end;
+    }
+    tracing::debug!("内存区域遍历结束");
+}
+
+/// 工作线程：查找特征并验证
+fn worker(
+    pid: u32,
+    receiver: crossbeam_channel::Receiver<Chunk>,
+    sender: crossbeam_channel::Sender<SearchHit>,
+    state: &SearchState,
+    validator: &dyn CandidateValidator,
+    pattern: &[u8],
+    config: &SearchConfig,
+) -> Result<()> {
+    let handle = Handle::new(unsafe { OpenProcess(PROCESS_VM_READ, false, pid)? })?;
+
+    while let Ok(chunk) = receiver.recv() {
+        if state.stop.load(Ordering::SeqCst) {
+            break;
+        }
+        let starts = chunk.scan_len.min(chunk.data.len().saturating_sub(pattern.len() - 1));
+        let offsets: Box<dyn Iterator<Item = usize>> = if config.reverse {
+            Box::new((0..starts).rev())
+        } else {
+            Box::new(0..starts)
+        };
+        for (checked, offset) in offsets.enumerate() {
+            // 定期检查停止信号
+            if checked % 4096 == 0 && state.stop.load(Ordering::SeqCst) {
+                return Ok(());
+            }
+            if chunk.data[offset..offset + pattern.len()] != *pattern {
+                continue;
+            }
+            state.candidates.fetch_add(1, Ordering::Relaxed);
+            let candidate = Candidate {
+                handle: *handle,
+                base: chunk.base,
+                data: &chunk.data,
+                offset,
+            };
+            let Some(data) = validator.validate(&candidate) else {
+                state.rejected.fetch_add(1, Ordering::Relaxed);
+                continue;
+            };
+            let order = state.accepted.fetch_add(1, Ordering::SeqCst);
+            if order >= config.max_hits {
+                return Ok(());
+            }
+            tracing::debug!("第 {} 个命中通过验证，地址: {:#X}", order + 1, candidate.address());
+            let _ = sender.send(SearchHit {
+                address: candidate.address(),
+                data,
+                order,
+            });
+            if order + 1 >= config.max_hits {
+                state.stop.store(true, Ordering::SeqCst);
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,6 +1,9 @@
 pub mod handle;
+pub mod handles;
 pub mod memory;
 pub mod process;
 pub mod registry;
 pub mod file;
-mod module_info;
\ No newline at end of file
+pub mod module_info;
+pub mod mem_search;
+pub mod mem_snapshot;
@@ -6,11 +6,11 @@ use crate::utils::ProcessInfo;
 use anyhow::bail;
 use std::ffi::c_void;
 use std::mem;
-use windows_result::BOOL;
 use windows::{
     core::PCWSTR,
     Win32::{
-        Foundation::STILL_ACTIVE,
+        Foundation::{HANDLE, STILL_ACTIVE},
+        Security::{GetTokenInformation, TokenElevation, TOKEN_ELEVATION, TOKEN_QUERY},
         Storage::FileSystem::{GetFileVersionInfoSizeW, GetFileVersionInfoW, VerQueryValueW, VS_FIXEDFILEINFO},
         System::{
             Diagnostics::ToolHelp::{
@@ -19,18 +19,21 @@ use windows::{
             },
             ProcessStatus::GetModuleFileNameExW,
             SystemInformation::{
-                GetNativeSystemInfo, SYSTEM_INFO, PROCESSOR_ARCHITECTURE_AMD64,
-                PROCESSOR_ARCHITECTURE_ARM64, PROCESSOR_ARCHITECTURE_IA64,
+                IMAGE_FILE_MACHINE, IMAGE_FILE_MACHINE_AMD64, IMAGE_FILE_MACHINE_ARM64,
+                IMAGE_FILE_MACHINE_ARMNT, IMAGE_FILE_MACHINE_I386, IMAGE_FILE_MACHINE_UNKNOWN,
             },
             Threading::{
-                GetExitCodeProcess, IsWow64Process, OpenProcess, PROCESS_QUERY_INFORMATION,
-                PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_VM_READ,
+                GetCurrentProcess, GetExitCodeProcess, GetProcessInformation, IsWow64Process2, OpenProcess,
+                OpenProcessToken, ProcessMachineTypeInfo, PROCESS_MACHINE_INFORMATION,
+                PROCESS_QUERY_INFORMATION, PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_VM_READ,
             },
         },
     },
 };
 use std::collections::HashSet;
 use super::handle::Handle;
+use super::mem_search::max_user_address;
+use crate::errors::WeChatError;
 
 /// 列举系统中的所有进程，并根据过滤器和选项返回匹配的进程信息。
 ///
@@ -62,7 +65,7 @@ pub fn list_processes(filter: &[&str], main_process_only: bool) -> Result<Vec<Pr
             
             // 使用最少的权限打开进程，满足所有后续调用的需求
             // GetModuleFileNameExW: PROCESS_QUERY_LIMITED_INFORMATION | PROCESS_VM_READ
-            // IsWow64Process: PROCESS_QUERY_LIMITED_INFORMATION
+            // IsWow64Process2、GetProcessInformation: PROCESS_QUERY_LIMITED_INFORMATION
             let Ok(process_handle) = Handle::new(unsafe {
                 OpenProcess(
                     PROCESS_QUERY_LIMITED_INFORMATION | PROCESS_VM_READ,
@@ -150,23 +153,38 @@ pub fn get_process_exe_path(pid: u32) -> Result<String> {
 }
 
 
-/// 根据已打开的进程句柄判断一个进程的体系结构（32位或64位）。
+/// 根据已打开的进程句柄判断一个进程的体系结构（32位、x64 或 ARM64）。
+///
+/// `IsWow64Process2` 只能识别 WOW64 下的 32 位进程，ARM64 系统上模拟运行的 x64 进程
+/// 会被报告为原生 ARM64；Windows 11 起再用 `GetProcessInformation` 查询进程实际的指令集。
 pub fn get_process_architecture_by_handle(handle: &Handle) -> Result<ProcessArchitecture> {
-    let mut is_wow64 = BOOL(0);
-    unsafe { IsWow64Process(**handle, &mut is_wow64)? };
-
-    if is_wow64.as_bool() {
-        return Ok(ProcessArchitecture::Bit32);
+    let mut process_machine = IMAGE_FILE_MACHINE_UNKNOWN;
+    let mut native_machine = IMAGE_FILE_MACHINE_UNKNOWN;
+    unsafe { IsWow64Process2(**handle, &mut process_machine, Some(&mut native_machine as *mut _))? };
+
+    let mut machine_info = PROCESS_MACHINE_INFORMATION::default();
+    let queried = unsafe {
+        GetProcessInformation(
+            **handle,
+            ProcessMachineTypeInfo,
+            &mut machine_info as *mut _ as *mut c_void,
+            mem::size_of::<PROCESS_MACHINE_INFORMATION>() as u32,
+        )
+    };
+    if queried.is_ok() && machine_info.ProcessMachine != IMAGE_FILE_MACHINE_UNKNOWN {
+        process_machine = machine_info.ProcessMachine;
     }
+    architecture_from_machines(process_machine, native_machine)
+}
 
-    let mut system_info = SYSTEM_INFO::default();
-    unsafe { GetNativeSystemInfo(&mut system_info) };
-
-    match unsafe { system_info.Anonymous.Anonymous.wProcessorArchitecture } {
-        PROCESSOR_ARCHITECTURE_AMD64 | PROCESSOR_ARCHITECTURE_IA64 | PROCESSOR_ARCHITECTURE_ARM64 => {
-            Ok(ProcessArchitecture::Bit64)
-        }
-        _ => Ok(ProcessArchitecture::Bit32),
+/// 由进程和系统的机器类型确定架构，`process` 为 `IMAGE_FILE_MACHINE_UNKNOWN` 表示进程与系统架构相同
+fn architecture_from_machines(process: IMAGE_FILE_MACHINE, native: IMAGE_FILE_MACHINE) -> Result<ProcessArchitecture> {
+    let machine = if process == IMAGE_FILE_MACHINE_UNKNOWN { native } else { process };
+    match machine {
+        IMAGE_FILE_MACHINE_AMD64 => Ok(ProcessArchitecture::Bit64),
+        IMAGE_FILE_MACHINE_ARM64 => Ok(ProcessArchitecture::Arm64),
+        IMAGE_FILE_MACHINE_I386 | IMAGE_FILE_MACHINE_ARMNT => Ok(ProcessArchitecture::Bit32),
+        other => bail!("不支持的进程架构: 机器类型 {:#06X}", other.0),
     }
 }
 
@@ -269,16 +287,86 @@ pub fn is_process_running(pid: u32) -> bool {
     }
 }
 
+/// 检查当前进程是否以管理员权限（已提升）运行。
+///
+/// 读取进程令牌的 `TokenElevation` 信息，任何一步失败都视为未提升。
+pub fn is_elevated() -> bool {
+    let mut raw_token = HANDLE::default();
+    if unsafe { OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut raw_token) }.is_err() {
+        return false;
+    }
+    let token = match Handle::new(raw_token) {
+        Ok(t) => t,
+        Err(_) => return false,
+    };
+
+    let mut elevation = TOKEN_ELEVATION::default();
+    let mut returned = 0u32;
+    let ok = unsafe {
+        GetTokenInformation(
+            *token,
+            TokenElevation,
+            Some(&mut elevation as *mut _ as *mut c_void),
+            mem::size_of::<TOKEN_ELEVATION>() as u32,
+            &mut returned,
+        )
+    }
+    .is_ok();
+
+    ok && elevation.TokenIsElevated != 0
+}
+
 /// 定义一个枚举来清晰地表示进程架构
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ProcessArchitecture {
+    /// 32 位（x86 或 ARM32，运行在 WOW64 下）
     Bit32,
+    /// x64，包括 ARM64 系统上模拟运行的 x64 进程
     Bit64,
+    /// 原生 ARM64
+    Arm64,
 }
 
 impl ProcessArchitecture {
     pub fn is_64_bit(&self) -> bool {
-        *self == ProcessArchitecture::Bit64
+        matches!(self, ProcessArchitecture::Bit64 | ProcessArchitecture::Arm64)
+    }
+
+    /// 架构名称，用于日志和错误信息
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProcessArchitecture::Bit32 => "32位",
+            ProcessArchitecture::Bit64 => "x64",
+            ProcessArchitecture::Arm64 => "ARM64",
+        }
+    }
+
+    /// 指针是否落在该架构的用户空间地址范围内
+    ///
+    /// ARM64 与 x64 的 Windows 用户空间同为 128TB（47 位地址），指针不带标签位。
+    pub fn is_user_pointer(&self, ptr: usize) -> bool {
+        ptr > 0x10000 && ptr < max_user_address(self.is_64_bit())
+    }
+
+    /// 检查能否从当前构建的 mwxdump 搜索该架构进程中的密钥，不支持时说明原因
+    ///
+    /// 密钥结构在 x64 和 ARM64 上布局相同；32 位微信没有 4.x 版本，32 位的 mwxdump
+    /// 也无法读取 64 位进程的完整地址空间。
+    pub fn check_key_search_supported(&self) -> Result<()> {
+        if *self == ProcessArchitecture::Bit32 {
+            return Err(WeChatError::KeyExtractionFailed(
+                "不支持 32 位微信进程，密钥搜索只支持 x64 和 ARM64 版本的微信 4.x".to_string(),
+            )
+            .into());
+        }
+        if cfg!(target_pointer_width = "32") {
+            return Err(WeChatError::KeyExtractionFailed(format!(
+                "32 位的 mwxdump 无法读取 {} 微信进程的内存，请使用 64 位（x64 或 ARM64）版本",
+                self.name()
+            ))
+            .into());
+        }
+        Ok(())
     }
 }
 
@@ -323,6 +411,26 @@ mod tests {
         
         assert!(!arch_32.is_64_bit());
         assert!(arch_64.is_64_bit());
+        assert!(ProcessArchitecture::Arm64.is_64_bit());
+    }
+
+    #[test]
+    fn test_architecture_from_machines() {
+        let arch = |process, native| architecture_from_machines(process, native).unwrap();
+        // 原生进程
+        assert_eq!(arch(IMAGE_FILE_MACHINE_UNKNOWN, IMAGE_FILE_MACHINE_AMD64), ProcessArchitecture::Bit64);
+        assert_eq!(arch(IMAGE_FILE_MACHINE_UNKNOWN, IMAGE_FILE_MACHINE_ARM64), ProcessArchitecture::Arm64);
+        // WOW64 下的 32 位进程，以及 ARM64 系统上模拟运行的 x64 进程
+        assert_eq!(arch(IMAGE_FILE_MACHINE_I386, IMAGE_FILE_MACHINE_AMD64), ProcessArchitecture::Bit32);
+        assert_eq!(arch(IMAGE_FILE_MACHINE_I386, IMAGE_FILE_MACHINE_ARM64), ProcessArchitecture::Bit32);
+        assert_eq!(arch(IMAGE_FILE_MACHINE_AMD64, IMAGE_FILE_MACHINE_ARM64), ProcessArchitecture::Bit64);
+        assert!(architecture_from_machines(IMAGE_FILE_MACHINE_UNKNOWN, IMAGE_FILE_MACHINE_UNKNOWN).is_err());
+
+        assert!(ProcessArchitecture::Arm64.check_key_search_supported().is_ok());
+        assert!(ProcessArchitecture::Bit32.check_key_search_supported().is_err());
+        assert!(ProcessArchitecture::Arm64.is_user_pointer(0x7FF6_1234_5678));
+        assert!(!ProcessArchitecture::Arm64.is_user_pointer(0xFFFF_8000_0000_0000));
+        assert!(!ProcessArchitecture::Bit32.is_user_pointer(0x7FF6_1234_5678));
     }
 
 }
\ No newline at end of file
@@ -0,0 +1,321 @@
+//! 新消息 Webhook
+//!
+//! 自动刷新发现新消息后，按 `[[webhook.hooks]]` 中的会话和关键词筛选，把匹配的消息逐条推送到
+//! 配置的 URL，便于接入 Home Assistant、ntfy 等服务而无需开放完整的 HTTP 接口。
+//! 请求由外部命令发送（默认 `curl`，Windows 10 起自带），请求体从标准输入传入，支持 HTTPS 和代理。
+//!
+//! 请求体默认为消息的 JSON；设置 `template` 后按模板生成，可用的占位符为 `{talker}`、`{talker_name}`、
+//! `{sender}`、`{sender_name}`、`{content}`、`{time}`、`{type}`、`{seq}`，以及整条消息的 JSON `{json}`。
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, warn};
+
+use crate::errors::Result;
+use crate::models::Message;
+
+/// Webhook 设置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookSettings {
+    /// 发送请求的命令，参数与 curl 兼容
+    pub command: String,
+    /// 单个请求的超时时间（秒）
+    pub timeout_secs: u64,
+    /// Webhook 列表
+    pub hooks: Vec<WebhookConfig>,
+}
+
+impl Default for WebhookSettings {
+    fn default() -> Self {
+        Self {
+            command: "curl".to_string(),
+            timeout_secs: 10,
+            hooks: Vec::new(),
+        }
+    }
+}
+
+/// 单个 Webhook
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    /// 名称，用于日志
+    pub name: String,
+    /// 请求地址
+    pub url: String,
+    /// 请求方法
+    #[serde(default = "default_method")]
+    pub method: String,
+    /// 附加的请求头
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// 请求体模板，未设置时发送消息的 JSON
+    #[serde(default)]
+    pub template: Option<String>,
+    /// 只推送这些会话（wxid 或群ID）的消息，为空时不限
+    #[serde(default)]
+    pub talkers: Vec<String>,
+    /// 只推送包含任一关键词的消息（不区分大小写），为空时不限
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// 是否推送自己发送的消息
+    #[serde(default)]
+    pub include_self: bool,
+    /// 是否启用
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_method() -> String {
+    "POST".to_string()
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl WebhookConfig {
+    /// 消息是否满足会话和关键词条件
+    pub fn matches(&self, message: &Message) -> bool {
+        if message.is_self && !self.include_self {
+            return false;
+        }
+        if !self.talkers.is_empty() && !self.talkers.contains(&message.talker) {
+            return false;
+        }
+        if self.keywords.is_empty() {
+            return true;
+        }
+        let content = message.content.to_lowercase();
+        self.keywords.iter().any(|keyword| content.contains(&keyword.to_lowercase()))
+    }
+
+    /// 生成请求体
+    pub fn render(&self, message: &Message) -> String {
+        let json = || serde_json::to_string(&WebhookPayload::new(&self.name, message)).unwrap_or_default();
+        let Some(template) = &self.template else {
+            return json();
+        };
+        let name = |name: &Option<String>, fallback: &str| name.clone().unwrap_or_else(|| fallback.to_string());
+        let mut body = template.clone();
+        for (placeholder, value) in [
+            ("{talker_name}", name(&message.talker_name, &message.talker)),
+            ("{talker}", message.talker.clone()),
+            ("{sender_name}", name(&message.sender_name, &message.sender)),
+            ("{sender}", message.sender.clone()),
+            ("{time}", message.time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string()),
+            ("{type}", message.msg_type.to_string()),
+            ("{seq}", message.seq.to_string()),
+        ] {
+            body = body.replace(placeholder, &value);
+        }
+        if body.contains("{json}") {
+            body = body.replace("{json}", &json());
+        }
+        // 内容最后替换，避免消息中的花括号被当作占位符
+        body.replace("{content}", &message.content)
+    }
+}
+
+/// 默认的请求体
+#[derive(Debug, Clone, Serialize)]
+struct WebhookPayload<'a> {
+    hook: &'a str,
+    talker: &'a str,
+    talker_name: Option<&'a str>,
+    sender: &'a str,
+    sender_name: Option<&'a str>,
+    is_self: bool,
+    #[serde(rename = "type")]
+    msg_type: i64,
+    time: String,
+    content: &'a str,
+}
+
+impl<'a> WebhookPayload<'a> {
+    fn new(hook: &'a str, message: &'a Message) -> Self {
+        Self {
+            hook,
+            talker: &message.talker,
+            talker_name: message.talker_name.as_deref(),
+            sender: &message.sender,
+            sender_name: message.sender_name.as_deref(),
+            is_self: message.is_self,
+            msg_type: message.msg_type,
+            time: message.time.to_rfc3339(),
+            content: &message.content,
+        }
+    }
+}
+
+/// 待发送的请求
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookRequest {
+    /// Webhook 名称
+    pub hook: String,
+    pub url: String,
+    pub method: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+/// Webhook 分发器
+#[derive(Debug, Clone, Default)]
+pub struct WebhookDispatcher {
+    settings: Arc<WebhookSettings>,
+}
+
+impl WebhookDispatcher {
+    /// 按设置创建分发器
+    pub fn new(settings: WebhookSettings) -> Self {
+        Self {
+            settings: Arc::new(settings),
+        }
+    }
+
+    /// 是否没有启用的 Webhook
+    pub fn is_empty(&self) -> bool {
+        !self.settings.hooks.iter().any(|hook| hook.enabled)
+    }
+
+    /// 为匹配的消息生成请求，按 Webhook 和消息的顺序排列
+    pub fn requests(&self, messages: &[Message]) -> Vec<WebhookRequest> {
+        let mut requests = Vec::new();
+        for hook in self.settings.hooks.iter().filter(|hook| hook.enabled) {
+            for message in messages.iter().filter(|message| hook.matches(message)) {
+                let mut headers: Vec<(String, String)> =
+                    hook.headers.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+                let has_content_type = headers.iter().any(|(k, _)| k.eq_ignore_ascii_case("content-type"));
+                if hook.template.is_none() && !has_content_type {
+                    headers.push(("Content-Type".to_string(), "application/json".to_string()));
+                }
+                requests.push(WebhookRequest {
+                    hook: hook.name.clone(),
+                    url: hook.url.clone(),
+                    method: hook.method.clone(),
+                    headers,
+                    body: hook.render(message),
+                });
+            }
+        }
+        requests
+    }
+
+    /// 推送匹配的消息，返回发送成功的请求数；失败只记录日志
+    pub async fn dispatch(&self, messages: &[Message]) -> usize {
+        let mut sent = 0;
+        for request in self.requests(messages) {
+            match self.send(&request).await {
+                Ok(()) => {
+                    debug!("Webhook {} 已推送", request.hook);
+                    sent += 1;
+                }
+                Err(e) => warn!("⚠️  Webhook {} 推送失败: {}", request.hook, e),
+            }
+        }
+        sent
+    }
+
+    /// 调用外部命令发送请求，请求体从标准输入传入
+    async fn send(&self, request: &WebhookRequest) -> Result<()> {
+        let timeout = self.settings.timeout_secs.max(1);
+        let mut command = tokio::process::Command::new(&self.settings.command);
+        command.args(["-sS", "--fail", "--max-time", &timeout.to_string(), "-X", &request.method]);
+        for (name, value) in &request.headers {
+            command.arg("-H").arg(format!("{}: {}", name, value));
+        }
+        command
+            .args(["--data-binary", "@-", &request.url])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        let mut child = command
+            .spawn()
+            .map_err(|e| anyhow::anyhow!("无法启动 {}: {}", self.settings.command, e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(request.body.as_bytes()).await?;
+        }
+        // 命令自身有超时，这里再留出余量，防止命令卡住
+        let output = tokio::time::timeout(Duration::from_secs(timeout + 5), child.wait_with_output())
+            .await
+            .map_err(|_| anyhow::anyhow!("请求超时"))??;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "{} 退出码 {:?}: {}",
+                self.settings.command,
+                output.status.code(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(talker: &str, content: &str, is_self: bool) -> Message {
+        let mut message = Message::new();
+        message.talker = talker.to_string();
+        message.sender = if is_self { "wxid_me" } else { talker }.to_string();
+        message.sender_name = Some("老王".to_string());
+        message.is_self = is_self;
+        message.msg_type = 1;
+        message.content = content.to_string();
+        message
+    }
+
+    fn hook(toml_text: &str) -> WebhookConfig {
+        toml::from_str(toml_text).unwrap()
+    }
+
+    #[test]
+    fn test_webhook_filters_and_templates() {
+        let ntfy = hook(
+            r#"
+            name = "ntfy"
+            url = "https://ntfy.sh/topic"
+            template = "{sender_name}: {content}"
+            keywords = ["Urgent"]
+            "#,
+        );
+        let home = hook(
+            r#"
+            name = "home"
+            url = "http://ha.local:8123/api/webhook/wechat"
+            talkers = ["wxid_family"]
+            include_self = true
+            "#,
+        );
+        assert!(ntfy.matches(&message("wxid_a", "this is URGENT", false)));
+        assert!(!ntfy.matches(&message("wxid_a", "hello", false)));
+        assert!(!ntfy.matches(&message("wxid_a", "urgent", true)));
+        assert!(home.matches(&message("wxid_family", "hi", true)));
+        assert!(!home.matches(&message("wxid_a", "hi", false)));
+        assert_eq!(ntfy.render(&message("wxid_a", "urgent {sender}", false)), "老王: urgent {sender}");
+
+        let dispatcher = WebhookDispatcher::new(WebhookSettings {
+            hooks: vec![ntfy, home],
+            ..Default::default()
+        });
+        assert!(!dispatcher.is_empty());
+        let requests = dispatcher.requests(&[
+            message("wxid_family", "urgent", false),
+            message("wxid_a", "hello", false),
+        ]);
+        assert_eq!(requests.iter().map(|r| r.hook.as_str()).collect::<Vec<_>>(), ["ntfy", "home"]);
+        assert_eq!(requests[0].method, "POST");
+        assert!(requests[0].headers.is_empty());
+        assert_eq!(requests[1].headers, [("Content-Type".to_string(), "application/json".to_string())]);
+        let payload: serde_json::Value = serde_json::from_str(&requests[1].body).unwrap();
+        assert_eq!((payload["talker"].as_str(), payload["type"].as_i64()), (Some("wxid_family"), Some(1)));
+        assert!(WebhookDispatcher::default().is_empty());
+    }
+}
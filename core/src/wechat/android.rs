@@ -0,0 +1,490 @@
+//! 安卓微信的 `EnMicroMsg.db`
+//!
+//! 安卓微信的数据库位于 `/data/data/com.tencent.mm/MicroMsg/<md5("mm" + uin)>/EnMicroMsg.db`，
+//! 以 SQLCipher 加密，口令为 `md5(IMEI + uin)` 的前7位小写十六进制字符。
+//! 读取不到 IMEI 的设备上微信使用固定的 [`DEFAULT_IMEI`]；uin 见
+//! `shared_prefs/system_config_prefs.xml` 中的 `default_uin`（可能为负数）。
+//!
+//! 支持两种 SQLCipher 参数：
+//!
+//! - [`SqlCipherProfile::V1`]：旧版 SQLCipher 的兼容参数（安卓微信默认），PBKDF2-HMAC-SHA1 4000 次迭代，
+//!   1024 字节页面，页面末尾只保留 IV，没有 HMAC
+//! - [`SqlCipherProfile::V4`]：SQLCipher 4 的默认参数，与 4.x 电脑版相同
+//!
+//! 导入时先解密到临时目录，再把 `message`、`rcontact` 表按 4.x 解密输出的目录结构写入工作目录，
+//! 可以与电脑版的数据一起导出和搜索。
+
+use aes::cipher::{block_padding::NoPadding, BlockDecryptMut, KeyIvInit};
+use md5::{Digest, Md5};
+use pbkdf2::pbkdf2_hmac;
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::Sha512;
+use std::collections::HashSet;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+use crate::errors::{MwxDumpError, Result, WeChatError};
+use crate::wechat::datadir::{open_read_only, SourceGuard};
+use crate::wechat::db::writer::{ImportedContact, ImportedMessage, WorkDirWriter};
+use crate::wechat::db::{DataSource, FilterOp, Query, SqlValue, SqliteDataSource};
+use crate::wechat::decrypt::decrypt_common::{
+    decrypt_page, is_database_encrypted, DerivedKeys, IV_SIZE, KEY_SIZE, SALT_SIZE, SQLITE_HEADER,
+};
+use crate::wechat::decrypt::DecryptConfig;
+
+/// 安卓微信消息数据库的文件名
+pub const ANDROID_DB: &str = "EnMicroMsg.db";
+
+/// 读取不到 IMEI 时微信使用的默认值
+pub const DEFAULT_IMEI: &str = "1234567890ABCDEF";
+
+/// 口令长度
+const PASSWORD_LEN: usize = 7;
+
+/// 每次从消息表读取的行数
+const MESSAGE_BATCH: u32 = 5000;
+
+/// `userinfo` 表中保存当前账号 wxid 的行
+const USERINFO_WXID: i64 = 2;
+
+/// 由 uin 和 IMEI 计算数据库口令，未提供 IMEI 时使用 [`DEFAULT_IMEI`]
+pub fn derive_password(uin: &str, imei: Option<&str>) -> String {
+    let digest = Md5::digest(format!("{}{}", imei.unwrap_or(DEFAULT_IMEI), uin.trim()).as_bytes());
+    hex::encode(digest)[..PASSWORD_LEN].to_string()
+}
+
+/// 文件是否为安卓微信的消息数据库（按文件名判断）
+pub fn is_android_db(path: &Path) -> bool {
+    path.is_file() && path.file_name().is_some_and(|name| name == ANDROID_DB)
+}
+
+/// SQLCipher 加密参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SqlCipherProfile {
+    /// 旧版 SQLCipher 兼容参数
+    V1,
+    /// SQLCipher 4 默认参数
+    V4,
+}
+
+impl SqlCipherProfile {
+    /// 检测时依次尝试的参数
+    pub const ALL: [SqlCipherProfile; 2] = [SqlCipherProfile::V1, SqlCipherProfile::V4];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SqlCipherProfile::V1 => "v1",
+            SqlCipherProfile::V4 => "v4",
+        }
+    }
+
+    /// 页面大小
+    pub fn page_size(&self) -> usize {
+        match self {
+            SqlCipherProfile::V1 => 1024,
+            SqlCipherProfile::V4 => DecryptConfig::v4().page_size,
+        }
+    }
+
+    /// 由口令和 salt 派生密钥，V1 没有 HMAC 密钥
+    fn derive_keys(&self, password: &[u8], salt: &[u8]) -> DerivedKeys {
+        let mut enc_key = vec![0u8; KEY_SIZE];
+        match self {
+            SqlCipherProfile::V1 => {
+                pbkdf2_hmac::<Sha1>(password, salt, 4000, &mut enc_key);
+                DerivedKeys { enc_key, mac_key: Vec::new() }
+            }
+            SqlCipherProfile::V4 => {
+                let config = DecryptConfig::v4();
+                pbkdf2_hmac::<Sha512>(password, salt, config.iter_count, &mut enc_key);
+                let mac_salt: Vec<u8> = salt.iter().map(|&b| b ^ 0x3a).collect();
+                let mut mac_key = vec![0u8; KEY_SIZE];
+                pbkdf2_hmac::<Sha512>(&enc_key, &mac_salt, 2, &mut mac_key);
+                DerivedKeys { enc_key, mac_key }
+            }
+        }
+    }
+
+    /// 解密一个页面，第一页的 salt 替换为 SQLite 文件头
+    fn decrypt_page(&self, page: &[u8], keys: &DerivedKeys, page_num: u64) -> Result<Vec<u8>> {
+        let decrypted = match self {
+            SqlCipherProfile::V1 => {
+                let iv_start = page.len() - IV_SIZE;
+                let offset = if page_num == 0 { SALT_SIZE } else { 0 };
+                let mut data = page[offset..iv_start].to_vec();
+                cbc::Decryptor::<aes::Aes256>::new(keys.enc_key.as_slice().into(), page[iv_start..].into())
+                    .decrypt_padded_mut::<NoPadding>(&mut data)
+                    .map_err(|e| WeChatError::DecryptionFailed(format!("页面 {} AES解密失败: {}", page_num, e)))?;
+                data.extend_from_slice(&page[iv_start..]);
+                data
+            }
+            SqlCipherProfile::V4 => decrypt_page(page, &keys.enc_key, &keys.mac_key, page_num, &DecryptConfig::v4())?,
+        };
+        if page_num == 0 {
+            return Ok([SQLITE_HEADER, decrypted.as_slice()].concat());
+        }
+        Ok(decrypted)
+    }
+
+    /// 解密第一页并检查文件头中的页面大小和固定字段
+    fn accepts(&self, password: &[u8], first_page: &[u8]) -> bool {
+        let page_size = self.page_size();
+        if first_page.len() < page_size {
+            return false;
+        }
+        let keys = self.derive_keys(password, &first_page[..SALT_SIZE]);
+        match self.decrypt_page(&first_page[..page_size], &keys, 0) {
+            Ok(page) => {
+                page[16..18] == (page_size as u16).to_be_bytes() && page[21..24] == [64, 32, 32]
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+/// 导入统计
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportReport {
+    /// 数据库的加密参数
+    pub profile: Option<SqlCipherProfile>,
+    /// `userinfo` 中的当前账号 wxid
+    pub self_wxid: Option<String>,
+    /// 联系人数
+    pub contacts: usize,
+    /// 有消息的会话数
+    pub conversations: usize,
+    /// 写入的消息数
+    pub messages: u64,
+    /// 耗时（毫秒）
+    pub elapsed_ms: u64,
+}
+
+/// 安卓微信的加密数据库
+#[derive(Clone)]
+pub struct AndroidDatabase {
+    path: PathBuf,
+    password: String,
+    cancel_token: CancellationToken,
+}
+
+impl AndroidDatabase {
+    /// 以口令打开数据库，口令可由 [`derive_password`] 计算
+    pub fn new(path: impl Into<PathBuf>, password: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            password: password.into(),
+            cancel_token: CancellationToken::new(),
+        }
+    }
+
+    /// 设置取消令牌，取消后停止解密和导入
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel_token = token;
+        self
+    }
+
+    /// 数据库路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 检测口令能解密的加密参数，口令错误时返回 `None`
+    pub fn detect_profile(&self) -> Result<Option<SqlCipherProfile>> {
+        let mut first_page = Vec::new();
+        open_read_only(&self.path)?
+            .take(SqlCipherProfile::V4.page_size() as u64)
+            .read_to_end(&mut first_page)?;
+        if !is_database_encrypted(&first_page) {
+            return Err(WeChatError::DecryptionFailed(format!("{:?} 未加密", self.path)).into());
+        }
+        Ok(SqlCipherProfile::ALL
+            .into_iter()
+            .find(|profile| profile.accepts(self.password.as_bytes(), &first_page)))
+    }
+
+    /// 按 `profile` 逐页解密到 `output`，返回解密的页数
+    pub fn decrypt_to(&self, profile: SqlCipherProfile, output: &Path) -> Result<u64> {
+        let page_size = profile.page_size();
+        let mut input = open_read_only(&self.path)?;
+        let mut writer = BufWriter::new(std::fs::File::create(output)?);
+        let mut page = vec![0u8; page_size];
+        let mut keys = None;
+        let mut page_num = 0;
+        loop {
+            let read = read_page(&mut input, &mut page)?;
+            if read == 0 {
+                break;
+            }
+            if read < page_size {
+                return Err(WeChatError::DecryptionFailed(format!(
+                    "{:?} 的页面 {} 不完整: {} 字节",
+                    self.path, page_num, read
+                ))
+                .into());
+            }
+            if self.cancel_token.is_cancelled() {
+                return Err(MwxDumpError::Cancelled.into());
+            }
+            let keys = keys.get_or_insert_with(|| profile.derive_keys(self.password.as_bytes(), &page[..SALT_SIZE]));
+            writer.write_all(&profile.decrypt_page(&page, keys, page_num)?)?;
+            page_num += 1;
+        }
+        writer.flush()?;
+        Ok(page_num)
+    }
+
+    /// 解密并导入聊天记录到 `output`，生成与解密输出相同的工作目录结构
+    pub async fn import(&self, output: &Path) -> Result<ImportReport> {
+        let started = Instant::now();
+        SourceGuard::new()
+            .with_root(self.path.parent().unwrap_or(Path::new(".")))
+            .check_output(output)?;
+        info!("🤖 导入安卓微信数据库 {:?} -> {:?}", self.path, output);
+
+        let temp = tempfile::tempdir()?;
+        let plain = temp.path().join(ANDROID_DB);
+        // 密钥派生和逐页解密较慢，放到阻塞线程中执行
+        let database = self.clone();
+        let target = plain.clone();
+        let profile = tokio::task::spawn_blocking(move || -> Result<SqlCipherProfile> {
+            let profile = database.detect_profile()?.ok_or_else(|| {
+                WeChatError::DecryptionFailed(format!("口令无法解密 {:?}，请检查 uin 和 IMEI", database.path))
+            })?;
+            info!("🔑 数据库加密参数: SQLCipher {}", profile.as_str());
+            let pages = database.decrypt_to(profile, &target)?;
+            info!("✅ 已解密 {} 个页面", pages);
+            Ok(profile)
+        })
+        .await??;
+
+        let source = SqliteDataSource::open(&plain).await?;
+        let contacts = load_contacts(&source).await?;
+        let self_wxid = load_self_wxid(&source).await?;
+        let mut report = ImportReport {
+            profile: Some(profile),
+            self_wxid: self_wxid.clone(),
+            contacts: contacts.len(),
+            ..Default::default()
+        };
+
+        let mut writer = WorkDirWriter::create(output).await?;
+        let mut talkers = HashSet::new();
+        let mut last_id = 0;
+        loop {
+            if self.cancel_token.is_cancelled() {
+                return Err(MwxDumpError::Cancelled.into());
+            }
+            let rows = source
+                .fetch(
+                    &Query::select("message")
+                        .columns(["msgId", "type", "isSend", "createTime", "talker", "content"])
+                        .filter("msgId", FilterOp::Gt, SqlValue::Integer(last_id))
+                        .order_by("msgId", false)
+                        .limit(MESSAGE_BATCH),
+                )
+                .await?;
+            for row in &rows {
+                last_id = row["msgId"].as_i64().unwrap_or(last_id);
+                let Some(talker) = row["talker"].as_str().filter(|talker| !talker.is_empty()) else {
+                    continue;
+                };
+                let message = convert_message(row, talker, self_wxid.as_deref());
+                writer.insert_message(talker, &message).await?;
+                talkers.insert(talker.to_string());
+            }
+            if rows.len() < MESSAGE_BATCH as usize {
+                break;
+            }
+        }
+        report.conversations = talkers.len();
+        report.messages = writer.finish(&contacts).await?;
+        report.elapsed_ms = started.elapsed().as_millis() as u64;
+        info!(
+            "✅ 安卓微信数据库导入完成: {} 个会话，{} 条消息，{} 个联系人",
+            report.conversations, report.messages, report.contacts
+        );
+        Ok(report)
+    }
+}
+
+/// 读取一个页面，文件结束时返回已读取的字节数
+fn read_page(input: &mut impl Read, page: &mut [u8]) -> Result<usize> {
+    let mut read = 0;
+    while read < page.len() {
+        match input.read(&mut page[read..])? {
+            0 => break,
+            n => read += n,
+        }
+    }
+    Ok(read)
+}
+
+/// 把 `message` 表的一行转换为 4.x 的消息
+///
+/// 安卓的 `type` 在低16位之上带有标志位（如转账 419430449、引用 822083633 都是 49 类消息），
+/// 只保留低16位；`createTime` 为毫秒，作为会话内的排序序号。
+fn convert_message(row: &serde_json::Value, talker: &str, self_wxid: Option<&str>) -> ImportedMessage {
+    let content = row["content"].as_str().unwrap_or_default().to_string();
+    let is_sender = row["isSend"].as_i64() == Some(1);
+    let sender = if is_sender {
+        self_wxid.unwrap_or_default().to_string()
+    } else if talker.ends_with("@chatroom") {
+        content.split_once(":\n").map(|(sender, _)| sender.to_string()).unwrap_or_default()
+    } else {
+        talker.to_string()
+    };
+    let create_time_ms = row["createTime"].as_i64().unwrap_or_default();
+    ImportedMessage {
+        seq: create_time_ms,
+        create_time: create_time_ms / 1000,
+        msg_type: row["type"].as_i64().unwrap_or(1) & 0xFFFF,
+        sender,
+        is_sender,
+        content,
+        ..Default::default()
+    }
+}
+
+/// 当前账号的 wxid
+async fn load_self_wxid(source: &SqliteDataSource) -> Result<Option<String>> {
+    let rows = source
+        .fetch(
+            &Query::select("userinfo")
+                .columns(["value"])
+                .filter("id", FilterOp::Eq, SqlValue::Integer(USERINFO_WXID)),
+        )
+        .await?;
+    Ok(rows
+        .first()
+        .and_then(|row| row["value"].as_str())
+        .filter(|wxid| !wxid.is_empty())
+        .map(str::to_string))
+}
+
+/// 读取 `rcontact` 中的联系人
+async fn load_contacts(source: &SqliteDataSource) -> Result<Vec<ImportedContact>> {
+    let rows = source
+        .fetch(&Query::select("rcontact").columns(["username", "nickname", "conRemark"]))
+        .await?;
+    let text = |value: &serde_json::Value| value.as_str().filter(|text| !text.is_empty()).map(str::to_string);
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            Some(ImportedContact {
+                username: text(&row["username"])?,
+                nickname: text(&row["nickname"]),
+                remark: text(&row["conRemark"]),
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wechat::db::contacts::ContactRepository;
+    use crate::wechat::db::messages::MessageRepository;
+    use aes::cipher::BlockEncryptMut;
+    use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode};
+    use sqlx::{ConnectOptions, Connection};
+
+    /// 按 SQLCipher V1 的页面布局创建并加密数据库
+    async fn create_android_db(path: &Path, password: &str) {
+        let plain = path.with_extension("plain");
+        let options = SqliteConnectOptions::new()
+            .filename(&plain)
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Delete)
+            .page_size(1024);
+        let mut conn = options.connect().await.unwrap();
+        sqlx::query("PRAGMA user_version = 1").execute(&mut conn).await.unwrap();
+        conn.close().await.unwrap();
+        // 每页末尾为 IV 保留 16 字节，写入数据前修改文件头和空表页的内容起点
+        let mut header = std::fs::read(&plain).unwrap();
+        header[20] = IV_SIZE as u8;
+        header[105..107].copy_from_slice(&(1024 - IV_SIZE as u16).to_be_bytes());
+        std::fs::write(&plain, header).unwrap();
+
+        let mut conn = options.connect().await.unwrap();
+        for sql in [
+            "CREATE TABLE userinfo (id INTEGER PRIMARY KEY, type INT, value TEXT)",
+            "CREATE TABLE rcontact (username TEXT, alias TEXT, conRemark TEXT, nickname TEXT)",
+            "CREATE TABLE message (msgId INTEGER PRIMARY KEY, msgSvrId INTEGER, type INT, status INT, \
+             isSend INT, createTime INTEGER, talker TEXT, content TEXT)",
+            "INSERT INTO userinfo VALUES (2, 3, 'wxid_me')",
+            "INSERT INTO rcontact VALUES ('wxid_friend', '', '老王', '王五'), ('123@chatroom', '', '', '家庭群'), ('', '', '', '')",
+            "INSERT INTO message (type, isSend, createTime, talker, content) VALUES \
+             (1, 0, 1700000000000, 'wxid_friend', '你好'), \
+             (1, 1, 1700000001000, 'wxid_friend', '在吗'), \
+             (1, 0, 1700000002000, '123@chatroom', 'wxid_friend:\n开饭了'), \
+             (419430449, 1, 1700000003000, '123@chatroom', '<msg/>'), \
+             (1, 0, 1700000004000, '', '孤立消息')",
+        ] {
+            sqlx::query(sql).execute(&mut conn).await.unwrap();
+        }
+        conn.close().await.unwrap();
+
+        let plain = std::fs::read(&plain).unwrap();
+        let salt = [0x42u8; SALT_SIZE];
+        let keys = SqlCipherProfile::V1.derive_keys(password.as_bytes(), &salt);
+        let mut encrypted = Vec::with_capacity(plain.len());
+        for (page_num, page) in plain.chunks(1024).enumerate() {
+            let offset = if page_num == 0 { SALT_SIZE } else { 0 };
+            let iv = [page_num as u8 + 1; IV_SIZE];
+            let mut data = page[offset..1024 - IV_SIZE].to_vec();
+            let len = data.len();
+            cbc::Encryptor::<aes::Aes256>::new(keys.enc_key.as_slice().into(), &iv.into())
+                .encrypt_padded_mut::<NoPadding>(&mut data, len)
+                .unwrap();
+            if page_num == 0 {
+                encrypted.extend_from_slice(&salt);
+            }
+            encrypted.extend_from_slice(&data);
+            encrypted.extend_from_slice(&iv);
+        }
+        std::fs::write(path, encrypted).unwrap();
+    }
+
+    #[test]
+    fn test_derive_password() {
+        assert_eq!(derive_password("123456789", None), "3a57b96");
+        assert_eq!(derive_password("-1234567", Some("865000000000000")), "d920597");
+    }
+
+    #[tokio::test]
+    async fn test_import_android_db() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("data").join(ANDROID_DB);
+        std::fs::create_dir_all(input.parent().unwrap()).unwrap();
+        let password = derive_password("123456789", None);
+        create_android_db(&input, &password).await;
+        assert!(is_android_db(&input));
+
+        assert_eq!(AndroidDatabase::new(&input, "0000000").detect_profile().unwrap(), None);
+        let database = AndroidDatabase::new(&input, password);
+        assert_eq!(database.detect_profile().unwrap(), Some(SqlCipherProfile::V1));
+
+        let output = dir.path().join("out");
+        let report = database.import(&output).await.unwrap();
+        assert_eq!(report.self_wxid.as_deref(), Some("wxid_me"));
+        assert_eq!(report.contacts, 2);
+        assert_eq!(report.conversations, 2);
+        assert_eq!(report.messages, 4);
+
+        let contacts = ContactRepository::open(&output).await.unwrap();
+        assert_eq!(contacts.display_name("wxid_friend"), Some("老王"));
+
+        let repo = MessageRepository::open(&output).await.unwrap().with_self_wxid("wxid_me");
+        let messages = repo.page("wxid_friend", None, 10).await.unwrap();
+        assert_eq!(messages.iter().map(|m| m.content.as_str()).collect::<Vec<_>>(), ["你好", "在吗"]);
+        assert!(!messages[0].is_self && messages[1].is_self);
+        let room = repo.page("123@chatroom", None, 10).await.unwrap();
+        assert_eq!((room[0].sender.as_str(), room[0].content.as_str()), ("wxid_friend", "开饭了"));
+        assert!(room[1].is_self && room[1].msg_type == 49);
+    }
+}
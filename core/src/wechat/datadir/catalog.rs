@@ -0,0 +1,308 @@
+//! 数据库目录清单
+//!
+//! 扫描 db_storage 下的所有数据库，按逻辑类别（消息、联系人、会话等）归类并记录分片序号和大小，
+//! 解密和导出可以按类别展示和筛选，而不必依赖具体文件名。
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use ts_rs::TS;
+
+use crate::errors::{ConfigError, Result};
+
+/// 账号目录下存放数据库的目录名
+const DB_STORAGE_DIR: &str = "db_storage";
+
+/// 数据库类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum DbCategory {
+    /// 聊天消息（含公众号消息）
+    Message,
+    /// 联系人与群聊
+    Contact,
+    /// 会话列表
+    Session,
+    /// 表情
+    Emoticon,
+    /// 文件、图片、视频的硬链接索引
+    Hardlink,
+    /// 语音等媒体数据
+    Media,
+    /// 收藏
+    Favorites,
+    /// 朋友圈
+    Sns,
+    /// 全文搜索索引（`fts` 目录或 `*_fts.db`），体积大且可由其他数据库重建
+    Fts,
+    /// 其他数据库
+    Other,
+}
+
+impl DbCategory {
+    /// 所有类别
+    pub const ALL: [DbCategory; 10] = [
+        DbCategory::Message,
+        DbCategory::Contact,
+        DbCategory::Session,
+        DbCategory::Emoticon,
+        DbCategory::Hardlink,
+        DbCategory::Media,
+        DbCategory::Favorites,
+        DbCategory::Sns,
+        DbCategory::Fts,
+        DbCategory::Other,
+    ];
+
+    /// 获取类别标识
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DbCategory::Message => "message",
+            DbCategory::Contact => "contact",
+            DbCategory::Session => "session",
+            DbCategory::Emoticon => "emoticon",
+            DbCategory::Hardlink => "hardlink",
+            DbCategory::Media => "media",
+            DbCategory::Favorites => "favorites",
+            DbCategory::Sns => "sns",
+            DbCategory::Fts => "fts",
+            DbCategory::Other => "other",
+        }
+    }
+
+    /// 根据数据库文件相对 `root` 的位置判断类别
+    ///
+    /// 以 `root` 下的一级目录为准（`root` 为账号目录时跳过 `db_storage`），子目录名不影响归类；
+    /// 清单扫描、目录解密筛选和增量刷新都用它归类，保证同一文件的类别一致。
+    pub fn of_path(root: &Path, path: &Path) -> Self {
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+        let dir_name = path
+            .parent()
+            .and_then(|dir| dir.strip_prefix(root).ok())
+            .and_then(|relative| {
+                let mut components = relative.components().map(|c| c.as_os_str());
+                let first = components.next()?;
+                if first.eq_ignore_ascii_case(DB_STORAGE_DIR) {
+                    components.next()
+                } else {
+                    Some(first)
+                }
+            })
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+        Self::classify(dir_name, stem)
+    }
+
+    /// 根据一级目录名和文件名判断类别
+    ///
+    /// 全文索引（如 `message/message_fts.db`）和 `media_N.db`（位于 message 目录下但保存的是
+    /// 语音等媒体数据）先于目录名判断，单独归类，`--only message` 不会带上这些大文件。
+    fn classify(dir_name: &str, stem: &str) -> Self {
+        if dir_name.eq_ignore_ascii_case("fts") || stem.ends_with("_fts") {
+            return DbCategory::Fts;
+        }
+        if stem.starts_with("media_") {
+            return DbCategory::Media;
+        }
+        let by_name = |name: &str| match name {
+            n if n.starts_with("message") || n.starts_with("biz_message") => Some(DbCategory::Message),
+            n if n.starts_with("contact") => Some(DbCategory::Contact),
+            n if n.starts_with("session") => Some(DbCategory::Session),
+            n if n.starts_with("emoticon") => Some(DbCategory::Emoticon),
+            n if n.starts_with("hardlink") => Some(DbCategory::Hardlink),
+            n if n.starts_with("favorite") => Some(DbCategory::Favorites),
+            n if n.starts_with("sns") => Some(DbCategory::Sns),
+            _ => None,
+        };
+        by_name(dir_name).or_else(|| by_name(stem)).unwrap_or(DbCategory::Other)
+    }
+}
+
+impl fmt::Display for DbCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DbCategory::Message => "消息",
+            DbCategory::Contact => "联系人",
+            DbCategory::Session => "会话",
+            DbCategory::Emoticon => "表情",
+            DbCategory::Hardlink => "文件索引",
+            DbCategory::Media => "媒体",
+            DbCategory::Favorites => "收藏",
+            DbCategory::Sns => "朋友圈",
+            DbCategory::Fts => "全文索引",
+            DbCategory::Other => "其他",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for DbCategory {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        DbCategory::ALL
+            .into_iter()
+            .find(|c| c.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| ConfigError::InvalidValue {
+                key: "category".to_string(),
+                value: s.to_string(),
+            })
+    }
+}
+
+/// 清单中的单个数据库
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DbEntry {
+    /// 数据库文件路径
+    pub path: PathBuf,
+    /// 相对 db_storage 的路径
+    pub relative_path: PathBuf,
+    /// 类别
+    pub category: DbCategory,
+    /// 分片序号，如 `message_3.db` 为 3
+    pub shard: Option<u32>,
+    /// 文件大小（字节）
+    pub size: u64,
+}
+
+/// 数据库清单
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DbCatalog {
+    /// 数据库所在目录
+    pub root: PathBuf,
+    /// 所有数据库，按类别、分片序号排序
+    pub entries: Vec<DbEntry>,
+}
+
+impl DbCatalog {
+    /// 扫描 db_storage 目录
+    pub fn scan(db_storage: &Path) -> Result<Self> {
+        let mut entries = Vec::new();
+        collect_entries(db_storage, db_storage, &mut entries)?;
+        entries.sort_by(|a, b| {
+            (a.category, a.shard, &a.relative_path).cmp(&(b.category, b.shard, &b.relative_path))
+        });
+        tracing::debug!("在 {:?} 中发现 {} 个数据库", db_storage, entries.len());
+        Ok(Self {
+            root: db_storage.to_path_buf(),
+            entries,
+        })
+    }
+
+    /// 指定类别的数据库
+    pub fn by_category(&self, category: DbCategory) -> impl Iterator<Item = &DbEntry> {
+        self.entries.iter().filter(move |e| e.category == category)
+    }
+
+    /// 按类别汇总数量和大小
+    pub fn summary(&self) -> BTreeMap<DbCategory, (usize, u64)> {
+        let mut summary = BTreeMap::new();
+        for entry in &self.entries {
+            let item = summary.entry(entry.category).or_insert((0, 0));
+            item.0 += 1;
+            item.1 += entry.size;
+        }
+        summary
+    }
+
+    /// 所有数据库的总大小
+    pub fn total_size(&self) -> u64 {
+        self.entries.iter().map(|e| e.size).sum()
+    }
+}
+
+/// 递归收集 `.db` 文件
+fn collect_entries(root: &Path, dir: &Path, entries: &mut Vec<DbEntry>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            collect_entries(root, &path, entries)?;
+            continue;
+        }
+        if path.extension().is_none_or(|ext| ext != "db") {
+            continue;
+        }
+
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+        entries.push(DbEntry {
+            relative_path: path.strip_prefix(root).unwrap_or(&path).to_path_buf(),
+            category: DbCategory::of_path(root, &path),
+            shard: shard_index(stem),
+            size: metadata.len(),
+            path,
+        });
+    }
+    Ok(())
+}
+
+/// 解析文件名末尾的分片序号
+fn shard_index(stem: &str) -> Option<u32> {
+    stem.rsplit_once('_').and_then(|(_, index)| index.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_catalog() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_storage = dir.path();
+        for (sub, name, size) in [
+            ("message", "message_0.db", 10),
+            ("message", "message_1.db", 20),
+            ("message", "message_fts.db", 5),
+            ("message", "media_0.db", 7),
+            ("contact", "contact.db", 3),
+            ("session", "session.db", 2),
+            ("hardlink", "hardlink.db", 1),
+            ("general", "general.db", 4),
+        ] {
+            std::fs::create_dir_all(db_storage.join(sub)).unwrap();
+            std::fs::write(db_storage.join(sub).join(name), vec![0u8; size]).unwrap();
+        }
+        std::fs::write(db_storage.join("message").join("message_0.db-wal"), b"").unwrap();
+
+        let catalog = DbCatalog::scan(db_storage).unwrap();
+        assert_eq!(catalog.entries.len(), 8);
+        assert_eq!(catalog.total_size(), 52);
+
+        let shards: Vec<Option<u32>> = catalog.by_category(DbCategory::Message).map(|e| e.shard).collect();
+        assert_eq!(shards, [Some(0), Some(1)]);
+        // 全文索引不随 message 目录归类，按类别筛选 message 时被跳过
+        let fts: Vec<&Path> = catalog.by_category(DbCategory::Fts).map(|e| e.relative_path.as_path()).collect();
+        assert_eq!(fts, [Path::new("message").join("message_fts.db")]);
+        assert_eq!(catalog.by_category(DbCategory::Media).count(), 1);
+        assert_eq!(catalog.summary()[&DbCategory::Other], (1, 4));
+        assert_eq!("Favorites".parse::<DbCategory>().unwrap(), DbCategory::Favorites);
+        assert!("chat".parse::<DbCategory>().is_err());
+    }
+
+    #[test]
+    fn test_category_of_path_is_root_relative() {
+        let root = Path::new("/data/wxid_abc");
+        for (path, category) in [
+            // 以一级目录为准，子目录名不影响归类
+            ("/data/wxid_abc/db_storage/message/sub/store.db", DbCategory::Message),
+            ("/data/wxid_abc/db_storage/message/message_fts.db", DbCategory::Fts),
+            ("/data/wxid_abc/db_storage/message/media_0.db", DbCategory::Media),
+            ("/data/wxid_abc/db_storage/general/contact/store.db", DbCategory::Other),
+            ("/data/wxid_abc/db_storage/fts/fts_index.db", DbCategory::Fts),
+            ("/data/wxid_abc/db_storage/general/general_fts.db", DbCategory::Fts),
+            ("/data/wxid_abc/db_storage/session.db", DbCategory::Session),
+        ] {
+            assert_eq!(DbCategory::of_path(root, Path::new(path)), category, "{}", path);
+        }
+        // root 直接指向 db_storage 时结果相同
+        let db_storage = root.join("db_storage");
+        assert_eq!(
+            DbCategory::of_path(&db_storage, &db_storage.join("message").join("sub").join("store.db")),
+            DbCategory::Message
+        );
+    }
+}
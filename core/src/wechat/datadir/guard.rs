@@ -0,0 +1,155 @@
+//! 原始数据目录保护
+//!
+//! 微信运行时会持续写入数据目录，误把 `--output` 指向其中会覆盖或污染原始数据库。
+//! [`SourceGuard`] 记录识别出的账号数据目录，拒绝解析后落在其中的输出路径；
+//! [`open_read_only`] 以只读方式打开源文件，Windows 上同时允许微信继续读写和删除。
+
+use std::path::{Component, Path, PathBuf};
+
+use super::scan;
+use crate::errors::{Result, WeChatError};
+
+/// Windows 上打开源文件时的共享模式：FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE
+#[cfg(windows)]
+const SHARE_ALL: u32 = 0x1 | 0x2 | 0x4;
+
+/// 受保护的微信数据目录
+#[derive(Debug, Clone, Default)]
+pub struct SourceGuard {
+    roots: Vec<PathBuf>,
+}
+
+impl SourceGuard {
+    /// 创建不保护任何目录的实例
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 以输入路径所属的微信账号数据目录创建；输入不是微信数据目录时不保护任何目录
+    pub fn for_input(input: &Path) -> Self {
+        Self::new().with_data_dir(input)
+    }
+
+    /// 添加输入路径所属的微信账号数据目录
+    pub fn with_data_dir(self, path: &Path) -> Self {
+        match scan(path) {
+            Ok(info) if info.wxid.is_some() || info.db_storage_path.is_some() => self.with_root(&info.root),
+            _ => self,
+        }
+    }
+
+    /// 直接添加受保护的目录
+    pub fn with_root(mut self, root: &Path) -> Self {
+        let root = resolve(root);
+        if !self.roots.contains(&root) {
+            self.roots.push(root);
+        }
+        self
+    }
+
+    /// 受保护的目录
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    /// 路径解析后是否位于受保护的目录中
+    pub fn contains(&self, path: &Path) -> bool {
+        let path = resolve(path);
+        self.roots.iter().any(|root| path.starts_with(root))
+    }
+
+    /// 检查输出路径，位于受保护的目录中时返回错误
+    pub fn check_output(&self, output: &Path) -> Result<()> {
+        let resolved = resolve(output);
+        match self.roots.iter().find(|root| resolved.starts_with(root)) {
+            Some(root) => Err(WeChatError::OutputInsideDataDir {
+                output: output.display().to_string(),
+                data_dir: root.display().to_string(),
+            }
+            .into()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// 解析路径：转为绝对路径，存在的部分解析符号链接，不存在的部分按字面拼接
+///
+/// 输出路径通常还不存在，只能解析到最近一个已存在的上级目录。
+pub fn resolve(path: &Path) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut existing = absolute.as_path();
+    let mut rest = Vec::new();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            let mut resolved = canonical;
+            for component in rest.iter().rev() {
+                match component {
+                    Component::ParentDir => {
+                        resolved.pop();
+                    }
+                    Component::CurDir => {}
+                    other => resolved.push(other),
+                }
+            }
+            return resolved;
+        }
+        match (existing.parent(), existing.components().next_back()) {
+            (Some(parent), Some(last)) => {
+                rest.push(last);
+                existing = parent;
+            }
+            _ => return absolute,
+        }
+    }
+}
+
+/// 以只读方式打开源文件
+pub fn open_read_only(path: &Path) -> std::io::Result<std::fs::File> {
+    let mut options = std::fs::OpenOptions::new();
+    options.read(true);
+    #[cfg(windows)]
+    std::os::windows::fs::OpenOptionsExt::share_mode(&mut options, SHARE_ALL);
+    options.open(path)
+}
+
+/// 以只读方式异步打开源文件
+pub async fn open_read_only_async(path: &Path) -> std::io::Result<tokio::fs::File> {
+    let mut options = tokio::fs::OpenOptions::new();
+    options.read(true);
+    #[cfg(windows)]
+    options.share_mode(SHARE_ALL);
+    options.open(path).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_refuses_output_inside_data_dir() {
+        let temp = tempfile::tempdir().unwrap();
+        let account = temp.path().join("xwechat_files").join("wxid_abc123_1a2b");
+        let storage = account.join("db_storage");
+        std::fs::create_dir_all(storage.join("message")).unwrap();
+
+        let guard = SourceGuard::for_input(&storage);
+        assert_eq!(guard.roots().len(), 1);
+        assert!(guard.check_output(&account.join("decrypted")).is_err());
+        assert!(guard.check_output(&storage.join("message").join("out.db")).is_err());
+        // 经 `..` 绕回数据目录的路径同样拒绝
+        let sneaky = temp.path().join("elsewhere").join("..").join("xwechat_files").join("wxid_abc123_1a2b");
+        assert!(guard.check_output(&sneaky).is_err());
+        assert!(guard.check_output(&temp.path().join("decrypted")).is_ok());
+        assert!(guard.check_output(&temp.path().join("xwechat_files")).is_ok());
+
+        // 普通目录不是微信数据目录，不做限制
+        let plain = temp.path().join("plain");
+        std::fs::create_dir_all(&plain).unwrap();
+        assert!(SourceGuard::for_input(&plain).roots().is_empty());
+
+        std::fs::write(storage.join("message").join("message_0.db"), b"data").unwrap();
+        let mut file = open_read_only(&storage.join("message").join("message_0.db")).unwrap();
+        assert!(file.write_all(b"x").is_err());
+    }
+}
@@ -0,0 +1,155 @@
+//! 微信数据目录识别
+//!
+//! 从任意指向账号目录（或其中子目录、文件）的路径出发，识别账号根目录、wxid、
+//! 数据库目录和目录布局对应的大版本，供 CLI、解密自动检测和 UI 层共用。
+
+pub mod catalog;
+pub mod guard;
+pub mod wal;
+pub mod watch;
+
+pub use catalog::{DbCatalog, DbCategory, DbEntry};
+pub use guard::{open_read_only, open_read_only_async, SourceGuard};
+pub use watch::DbStorageWatcher;
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use ts_rs::TS;
+
+use crate::errors::{Result, WeChatError};
+use crate::wechat::process::accounts::wxid_from_dir_name;
+use crate::wechat::signatures::{DataDirLayout, SignatureDb};
+use crate::wechat::WeChatVersion;
+
+/// 3.x 版本账号目录下的消息数据库目录
+const V3_MSG_DIR: &str = "Msg";
+
+/// 数据目录信息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct DataDirInfo {
+    /// 账号数据目录
+    pub root: PathBuf,
+    /// 微信ID（不含目录后缀），目录名不符合账号前缀时为 `None`
+    pub wxid: Option<String>,
+    /// 按目录布局推断的大版本（如 `4.x`），无法判断时为 `Unknown`
+    #[ts(type = "string")]
+    pub version: WeChatVersion,
+    /// 数据库目录
+    pub db_storage_path: Option<PathBuf>,
+    /// 数据库目录的总大小（字节）
+    #[ts(type = "number")]
+    pub size: u64,
+}
+
+impl DataDirInfo {
+    /// 扫描数据库目录清单，没有数据库目录时返回 `None`
+    pub fn catalog(&self) -> Result<Option<DbCatalog>> {
+        self.db_storage_path.as_deref().map(DbCatalog::scan).transpose()
+    }
+}
+
+/// 识别路径所属的微信账号数据目录
+///
+/// `path` 可以是账号目录本身，也可以是其中的子目录或文件；
+/// 向上查找到第一个符合账号前缀的目录作为根目录，找不到时以 `path` 本身为根目录。
+pub fn scan(path: &Path) -> Result<DataDirInfo> {
+    if !path.exists() {
+        return Err(WeChatError::DataDirNotFound {
+            path: path.display().to_string(),
+        }
+        .into());
+    }
+
+    let layouts = SignatureDb::global().data_dir_layouts();
+    scan_with_layouts(path, &layouts)
+}
+
+/// 使用指定的目录布局识别数据目录
+pub fn scan_with_layouts(path: &Path, layouts: &[DataDirLayout]) -> Result<DataDirInfo> {
+    let start = if path.is_file() { path.parent().unwrap_or(path) } else { path };
+
+    let found = start.ancestors().find_map(|dir| {
+        let name = dir.file_name()?.to_str()?;
+        layouts.iter().find_map(|layout| {
+            wxid_from_dir_name(name, &layout.account_prefix).map(|wxid| (dir, wxid, layout))
+        })
+    });
+
+    let (root, wxid, layout) = match found {
+        Some((dir, wxid, layout)) => (dir.to_path_buf(), Some(wxid), Some(layout)),
+        None if start.is_dir() => (start.to_path_buf(), None, None),
+        None => {
+            return Err(WeChatError::DataDirNotFound {
+                path: path.display().to_string(),
+            }
+            .into())
+        }
+    };
+
+    let db_storage_path = layout
+        .into_iter()
+        .chain(layouts.iter())
+        .map(|l| root.join(&l.db_dir))
+        .find(|dir| dir.is_dir());
+
+    let version = if db_storage_path.is_some() {
+        WeChatVersion::V4x { exact: "4.x".to_string() }
+    } else if root.join(V3_MSG_DIR).is_dir() {
+        WeChatVersion::V3x { exact: "3.x".to_string() }
+    } else {
+        WeChatVersion::Unknown
+    };
+
+    let size = db_storage_path.as_deref().map(dir_size).unwrap_or(0);
+
+    Ok(DataDirInfo {
+        root,
+        wxid,
+        version,
+        db_storage_path,
+        size,
+    })
+}
+
+/// 递归统计目录下所有文件的大小
+fn dir_size(dir: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(meta) if meta.is_dir() => dir_size(&entry.path()),
+            Ok(meta) => meta.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_from_nested_path() {
+        let root = tempfile::tempdir().unwrap();
+        let account = root.path().join("xwechat_files").join("wxid_abc123_1a2b");
+        let message_dir = account.join("db_storage").join("message");
+        std::fs::create_dir_all(&message_dir).unwrap();
+        std::fs::write(message_dir.join("message_0.db"), vec![0u8; 4096]).unwrap();
+
+        let info = scan(&message_dir.join("message_0.db")).unwrap();
+        assert_eq!(info.root, account);
+        assert_eq!(info.wxid.as_deref(), Some("wxid_abc123"));
+        assert!(info.version.is_v4x());
+        assert_eq!(info.db_storage_path, Some(account.join("db_storage")));
+        assert_eq!(info.size, 4096);
+
+        let plain = scan(root.path()).unwrap();
+        assert_eq!(plain.wxid, None);
+        assert_eq!(plain.version, WeChatVersion::Unknown);
+
+        assert!(scan(&root.path().join("missing")).is_err());
+    }
+}
@@ -0,0 +1,166 @@
+//! 预写日志（WAL）合并
+//!
+//! 微信以 WAL 模式写入数据库，最新的消息可能只在 `-wal` 文件中，尚未检查点回主库。
+//! WAL 帧中的页面与主库页面的加密方式相同，因此无需密钥：把最后一次提交之前的有效帧
+//! 按页号写回快照中的主库副本，再照常解密即可读到最新数据。
+
+use std::fs::OpenOptions;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::errors::Result;
+
+/// WAL 文件头长度
+const WAL_HEADER_SIZE: usize = 32;
+
+/// WAL 帧头长度
+const WAL_FRAME_HEADER_SIZE: usize = 24;
+
+/// 校验和按小端计算的魔数，最低位为 1 时按大端计算
+const WAL_MAGIC: u32 = 0x377f_0682;
+
+/// 数据库对应的 WAL 文件路径
+pub fn wal_path(db: &Path) -> std::path::PathBuf {
+    let mut name = db.as_os_str().to_owned();
+    name.push("-wal");
+    name.into()
+}
+
+/// 将 `wal` 中已提交的帧写回数据库 `db`，返回写入的帧数
+///
+/// 只应用校验和连续有效、且以提交帧结尾的部分；WAL 不完整或格式不符时返回 0，主库保持不变。
+pub fn checkpoint(db: &Path, wal: &[u8]) -> Result<usize> {
+    let Some(frames) = committed_frames(wal) else {
+        return Ok(0);
+    };
+    if frames.pages.is_empty() {
+        return Ok(0);
+    }
+    let mut file = OpenOptions::new().write(true).open(db)?;
+    for (page_number, data) in &frames.pages {
+        file.seek(SeekFrom::Start((*page_number as u64 - 1) * frames.page_size as u64))?;
+        file.write_all(data)?;
+    }
+    file.set_len(frames.db_pages as u64 * frames.page_size as u64)?;
+    file.sync_all()?;
+    Ok(frames.pages.len())
+}
+
+/// 已提交的帧
+struct CommittedFrames<'a> {
+    page_size: usize,
+    /// 最后一次提交后的数据库页数
+    db_pages: u32,
+    /// 页号与页面内容，按写入顺序排列
+    pages: Vec<(u32, &'a [u8])>,
+}
+
+fn committed_frames(wal: &[u8]) -> Option<CommittedFrames<'_>> {
+    let header = wal.get(..WAL_HEADER_SIZE)?;
+    let magic = read_u32(header, 0);
+    if magic & !1 != WAL_MAGIC {
+        return None;
+    }
+    let big_endian = magic & 1 == 1;
+    let page_size = match read_u32(header, 8) {
+        1 => 65536,
+        size if size.is_power_of_two() && (512..=32768).contains(&size) => size as usize,
+        _ => return None,
+    };
+    let salt = &header[16..24];
+    let mut checksum = wal_checksum(&header[..24], (0, 0), big_endian);
+    if checksum != (read_u32(header, 24), read_u32(header, 28)) {
+        return None;
+    }
+
+    let mut pages = Vec::new();
+    let mut committed = CommittedFrames {
+        page_size,
+        db_pages: 0,
+        pages: Vec::new(),
+    };
+    let frame_size = WAL_FRAME_HEADER_SIZE + page_size;
+    for frame in wal[WAL_HEADER_SIZE..].chunks_exact(frame_size) {
+        let (frame_header, data) = frame.split_at(WAL_FRAME_HEADER_SIZE);
+        // 盐值不同的帧属于上一轮 WAL，之后的内容都已失效
+        if &frame_header[8..16] != salt {
+            break;
+        }
+        checksum = wal_checksum(&frame_header[..8], checksum, big_endian);
+        checksum = wal_checksum(data, checksum, big_endian);
+        if checksum != (read_u32(frame_header, 16), read_u32(frame_header, 20)) {
+            break;
+        }
+        let page_number = read_u32(frame_header, 0);
+        if page_number == 0 {
+            break;
+        }
+        pages.push((page_number, data));
+        let db_pages = read_u32(frame_header, 4);
+        if db_pages > 0 {
+            committed.db_pages = db_pages;
+            committed.pages.append(&mut pages);
+        }
+    }
+    Some(committed)
+}
+
+/// SQLite WAL 校验和，按 32 位字两两累加
+fn wal_checksum(data: &[u8], (mut s0, mut s1): (u32, u32), big_endian: bool) -> (u32, u32) {
+    let word = |bytes: &[u8]| {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    };
+    for pair in data.chunks_exact(8) {
+        s0 = s0.wrapping_add(word(&pair[..4])).wrapping_add(s1);
+        s1 = s1.wrapping_add(word(&pair[4..])).wrapping_add(s0);
+    }
+    (s0, s1)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode};
+    use sqlx::{ConnectOptions, Connection, Row};
+
+    #[tokio::test]
+    async fn test_checkpoint_applies_committed_frames() {
+        let dir = tempfile::tempdir().unwrap();
+        let live = dir.path().join("live.db");
+        let mut conn = SqliteConnectOptions::new()
+            .filename(&live)
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .pragma("wal_autocheckpoint", "0")
+            .connect()
+            .await
+            .unwrap();
+        sqlx::query("CREATE TABLE t (v TEXT)").execute(&mut conn).await.unwrap();
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)").execute(&mut conn).await.unwrap();
+        sqlx::query("INSERT INTO t VALUES ('new')").execute(&mut conn).await.unwrap();
+
+        // 连接仍打开时复制，模拟微信运行中的快照：主库中还没有新写入的行
+        let snapshot = dir.path().join("snapshot.db");
+        std::fs::copy(&live, &snapshot).unwrap();
+        let wal = std::fs::read(wal_path(&live)).unwrap();
+        conn.close().await.unwrap();
+
+        assert!(checkpoint(&snapshot, &wal).unwrap() > 0);
+        let mut copy = SqliteConnectOptions::new().filename(&snapshot).connect().await.unwrap();
+        let row = sqlx::query("SELECT v FROM t").fetch_one(&mut copy).await.unwrap();
+        assert_eq!(row.get::<String, _>(0), "new");
+
+        // 格式不符或被截断的 WAL 不会改动主库
+        assert_eq!(checkpoint(&snapshot, b"not a wal").unwrap(), 0);
+        assert_eq!(checkpoint(&snapshot, &wal[..WAL_HEADER_SIZE + 10]).unwrap(), 0);
+    }
+}
@@ -0,0 +1,243 @@
+//! 数据库目录监控
+//!
+//! 监听 db_storage 下的文件变化，将 `-wal`/`-shm` 等附属文件归并到对应的数据库，
+//! 去抖后按批次输出发生变化的数据库；持续写入时最迟在最大等待时间后输出一批。
+//! 刷新时先快照变化的分片并合并其 WAL（见 [`super::wal`]）再解密，避免读取到微信正在写入的文件，
+//! 也不会漏掉尚未检查点回主库的消息。
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::time::Instant;
+
+use notify::{RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use super::{wal, DbCategory};
+use crate::errors::Result;
+use crate::wechat::decrypt::DecryptionProcessor;
+
+/// 默认去抖时间，微信写入一条消息通常会连续触发多次文件事件
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// 默认最大等待时间，持续有文件事件时也至少按此间隔输出一批
+pub const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(10);
+
+/// 数据库目录监控器
+pub struct DbStorageWatcher {
+    root: PathBuf,
+    debounce: Duration,
+    max_delay: Duration,
+}
+
+impl DbStorageWatcher {
+    /// 监控指定目录（递归）
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            debounce: DEFAULT_DEBOUNCE,
+            max_delay: DEFAULT_MAX_DELAY,
+        }
+    }
+
+    /// 设置去抖时间
+    pub fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// 设置一批变化从第一个事件起的最大等待时间
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// 开始监控，返回变化批次的接收端
+    ///
+    /// 每个批次是去重排序后的数据库路径；取消令牌触发后停止监控并关闭通道。
+    pub fn start(self, cancel_token: CancellationToken) -> Result<mpsc::Receiver<Vec<PathBuf>>> {
+        let (raw_tx, mut raw_rx) = mpsc::unbounded_channel::<PathBuf>();
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+            Ok(event) => {
+                for path in event.paths {
+                    let _ = raw_tx.send(path);
+                }
+            }
+            Err(e) => warn!("⚠️  文件监控错误: {}", e),
+        })?;
+        watcher.watch(&self.root, RecursiveMode::Recursive)?;
+        info!("👀 开始监控数据库目录: {:?}", self.root);
+
+        let (batch_tx, batch_rx) = mpsc::channel(16);
+        let debounce = self.debounce;
+        let max_delay = self.max_delay;
+        tokio::spawn(async move {
+            // 监控器随任务存活
+            let _watcher = watcher;
+            loop {
+                let first = tokio::select! {
+                    _ = cancel_token.cancelled() => break,
+                    path = raw_rx.recv() => match path {
+                        Some(path) => path,
+                        None => break,
+                    },
+                };
+
+                let mut pending = BTreeSet::new();
+                pending.extend(database_of(&first));
+                let deadline = Instant::now() + max_delay;
+                loop {
+                    let wait = debounce.min(deadline.saturating_duration_since(Instant::now()));
+                    if wait.is_zero() {
+                        break;
+                    }
+                    tokio::select! {
+                        _ = cancel_token.cancelled() => return,
+                        path = tokio::time::timeout(wait, raw_rx.recv()) => match path {
+                            Ok(Some(path)) => pending.extend(database_of(&path)),
+                            _ => break,
+                        },
+                    }
+                }
+
+                if pending.is_empty() {
+                    continue;
+                }
+                debug!("检测到 {} 个数据库变化", pending.len());
+                if batch_tx.send(pending.into_iter().collect()).await.is_err() {
+                    break;
+                }
+            }
+            info!("停止监控数据库目录");
+        });
+
+        Ok(batch_rx)
+    }
+}
+
+/// 将变化的文件映射到所属数据库，非数据库文件返回 `None`
+pub fn database_of(path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?.to_str()?;
+    let db_name = name
+        .strip_suffix("-wal")
+        .or_else(|| name.strip_suffix("-shm"))
+        .or_else(|| name.strip_suffix("-journal"))
+        .unwrap_or(name);
+    db_name.ends_with(".db").then(|| path.with_file_name(db_name))
+}
+
+/// 快照并重新解密变化的数据库
+///
+/// `changed` 中的路径需位于 `data_root` 下，解密结果按相对路径写入 `output`，与完整备份的目录结构一致。
+/// `categories` 不为空时只刷新这些类别。返回实际刷新的相对路径，已被删除的数据库会被跳过。
+pub async fn refresh_changed(
+    data_root: &Path,
+    changed: &[PathBuf],
+    output: &Path,
+    key: &[u8],
+    categories: &[DbCategory],
+    threads: Option<usize>,
+) -> Result<Vec<PathBuf>> {
+    let snapshot = tempfile::Builder::new().prefix("mwxdump-snapshot-").tempdir()?;
+    let mut refreshed = Vec::new();
+    for path in changed {
+        let Ok(relative) = path.strip_prefix(data_root) else {
+            warn!("⚠️  忽略数据目录之外的文件: {:?}", path);
+            continue;
+        };
+        if !path.is_file() || !(categories.is_empty() || categories.contains(&DbCategory::of_path(data_root, path))) {
+            continue;
+        }
+        let target = snapshot.path().join(relative);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(path, &target)?;
+        // 主库之后读取 WAL，保证其中的帧不早于主库副本
+        match std::fs::read(wal::wal_path(path)) {
+            Ok(log) => {
+                let frames = wal::checkpoint(&target, &log)?;
+                debug!("合并 {:?} 的 {} 个 WAL 帧", relative, frames);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        refreshed.push(relative.to_path_buf());
+    }
+
+    if refreshed.is_empty() {
+        return Ok(refreshed);
+    }
+    info!("🔄 重新解密 {} 个变化的数据库", refreshed.len());
+    DecryptionProcessor::new(snapshot.path().to_path_buf(), output.to_path_buf(), key.to_vec(), threads, false)
+        .execute()
+        .await?;
+    Ok(refreshed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_watch_groups_changes_by_database() {
+        assert_eq!(
+            database_of(Path::new("/x/message/message_0.db-wal")),
+            Some(PathBuf::from("/x/message/message_0.db"))
+        );
+        assert_eq!(database_of(Path::new("/x/message/tmp.log")), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join("message")).unwrap();
+
+        let token = CancellationToken::new();
+        let mut batches = DbStorageWatcher::new(&root)
+            .with_debounce(Duration::from_millis(200))
+            .start(token.clone())
+            .unwrap();
+
+        std::fs::write(root.join("message").join("message_0.db"), b"a").unwrap();
+        std::fs::write(root.join("message").join("message_0.db-wal"), b"b").unwrap();
+        std::fs::write(root.join("message").join("notes.txt"), b"c").unwrap();
+
+        let batch = tokio::time::timeout(Duration::from_secs(5), batches.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(batch, [root.join("message").join("message_0.db")]);
+
+        token.cancel();
+        assert!(tokio::time::timeout(Duration::from_secs(5), batches.recv()).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_continuous_writes_flush_after_max_delay() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let token = CancellationToken::new();
+        let mut batches = DbStorageWatcher::new(&root)
+            .with_debounce(Duration::from_millis(300))
+            .with_max_delay(Duration::from_millis(600))
+            .start(token.clone())
+            .unwrap();
+
+        // 写入间隔短于去抖时间，只靠去抖永远不会输出
+        let db = root.join("session.db");
+        let writer = tokio::spawn(async move {
+            for i in 0..40u8 {
+                std::fs::write(&db, [i]).unwrap();
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        });
+        let batch = tokio::time::timeout(Duration::from_secs(3), batches.recv())
+            .await
+            .expect("持续写入时应在最大等待时间后输出")
+            .unwrap();
+        assert_eq!(batch, [root.join("session.db")]);
+        writer.abort();
+        token.cancel();
+    }
+}
@@ -0,0 +1,238 @@
+//! 查询结果缓存
+//!
+//! 对会话列表、联系人最近消息等高频查询做 LRU 缓存，条目超过有效期后失效；
+//! 数据库重新解密后由自动刷新流程整体或按会话失效，避免前端频繁轮询时反复查询数据库。
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::errors::Result;
+
+/// 缓存配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QueryCacheConfig {
+    /// 最多缓存的查询数，为 0 时禁用缓存
+    pub capacity: usize,
+    /// 条目有效期（秒）
+    pub ttl_secs: u64,
+}
+
+impl Default for QueryCacheConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 256,
+            ttl_secs: 30,
+        }
+    }
+}
+
+/// 可缓存的查询
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum QueryKey {
+    /// 会话列表
+    SessionList,
+    /// 指定会话的最近消息
+    RecentMessages { talker: String, limit: usize },
+}
+
+impl QueryKey {
+    /// 查询是否涉及指定会话
+    pub fn involves(&self, talker: &str) -> bool {
+        match self {
+            QueryKey::SessionList => true,
+            QueryKey::RecentMessages { talker: t, .. } => t == talker,
+        }
+    }
+}
+
+struct CacheEntry<V> {
+    value: V,
+    inserted_at: Instant,
+    last_used: u64,
+}
+
+struct CacheInner<K, V> {
+    entries: HashMap<K, CacheEntry<V>>,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+}
+
+/// LRU 查询缓存
+///
+/// 克隆后共享同一份缓存。
+pub struct QueryCache<K, V> {
+    inner: Arc<Mutex<CacheInner<K, V>>>,
+    capacity: usize,
+    ttl: Duration,
+}
+
+impl<K, V> Clone for QueryCache<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            capacity: self.capacity,
+            ttl: self.ttl,
+        }
+    }
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> QueryCache<K, V> {
+    /// 按配置创建缓存
+    pub fn new(config: &QueryCacheConfig) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(CacheInner {
+                entries: HashMap::new(),
+                tick: 0,
+                hits: 0,
+                misses: 0,
+            })),
+            capacity: config.capacity,
+            ttl: Duration::from_secs(config.ttl_secs),
+        }
+    }
+
+    /// 读取未过期的缓存值
+    pub fn get(&self, key: &K) -> Option<V> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.tick += 1;
+        let tick = inner.tick;
+        let ttl = self.ttl;
+        let value = match inner.entries.get_mut(key) {
+            Some(entry) if entry.inserted_at.elapsed() < ttl => {
+                entry.last_used = tick;
+                Some(entry.value.clone())
+            }
+            Some(_) => {
+                inner.entries.remove(key);
+                None
+            }
+            None => None,
+        };
+        if value.is_some() {
+            inner.hits += 1;
+        } else {
+            inner.misses += 1;
+        }
+        value
+    }
+
+    /// 写入缓存，超出容量时淘汰最久未使用的条目
+    pub fn insert(&self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut inner = self.inner.lock().unwrap();
+        inner.tick += 1;
+        let tick = inner.tick;
+        if !inner.entries.contains_key(&key) && inner.entries.len() >= self.capacity {
+            let oldest = inner
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(k, _)| k.clone());
+            if let Some(oldest) = oldest {
+                inner.entries.remove(&oldest);
+            }
+        }
+        inner.entries.insert(
+            key,
+            CacheEntry {
+                value,
+                inserted_at: Instant::now(),
+                last_used: tick,
+            },
+        );
+    }
+
+    /// 命中时返回缓存值，否则执行查询并缓存结果；查询失败不会写入缓存
+    pub async fn get_or_try_insert_with<F, Fut>(&self, key: K, query: F) -> Result<V>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<V>>,
+    {
+        if let Some(value) = self.get(&key) {
+            return Ok(value);
+        }
+        let value = query().await?;
+        self.insert(key, value.clone());
+        Ok(value)
+    }
+
+    /// 失效满足条件的条目
+    pub fn invalidate_matching(&self, predicate: impl Fn(&K) -> bool) {
+        self.inner.lock().unwrap().entries.retain(|key, _| !predicate(key));
+    }
+
+    /// 清空缓存
+    pub fn invalidate_all(&self) {
+        self.inner.lock().unwrap().entries.clear();
+    }
+
+    /// 当前条目数
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    /// 是否为空
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 命中与未命中次数
+    pub fn stats(&self) -> (u64, u64) {
+        let inner = self.inner.lock().unwrap();
+        (inner.hits, inner.misses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_lru_eviction_ttl_and_invalidation() {
+        let cache: QueryCache<QueryKey, Vec<String>> = QueryCache::new(&QueryCacheConfig {
+            capacity: 2,
+            ttl_secs: 60,
+        });
+        let recent = |talker: &str| QueryKey::RecentMessages {
+            talker: talker.to_string(),
+            limit: 50,
+        };
+
+        let sessions = cache
+            .get_or_try_insert_with(QueryKey::SessionList, || async { Ok(vec!["wxid_a".to_string()]) })
+            .await
+            .unwrap();
+        assert_eq!(sessions, ["wxid_a"]);
+        // 命中缓存时不会执行查询
+        let sessions = cache
+            .get_or_try_insert_with(QueryKey::SessionList, || async { panic!("should be cached") })
+            .await
+            .unwrap();
+        assert_eq!(sessions, ["wxid_a"]);
+
+        cache.insert(recent("wxid_a"), vec!["hi".to_string()]);
+        cache.get(&QueryKey::SessionList);
+        cache.insert(recent("wxid_b"), vec!["yo".to_string()]);
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&recent("wxid_a")).is_none());
+
+        cache.invalidate_matching(|key| key.involves("wxid_b"));
+        assert!(cache.is_empty());
+
+        let expired: QueryCache<QueryKey, u32> = QueryCache::new(&QueryCacheConfig {
+            capacity: 4,
+            ttl_secs: 0,
+        });
+        expired.insert(QueryKey::SessionList, 1);
+        assert!(expired.get(&QueryKey::SessionList).is_none());
+        assert_eq!(expired.stats(), (0, 1));
+    }
+}
@@ -0,0 +1,241 @@
+//! 群聊与成员读取
+//!
+//! 群成员、群昵称保存在群聊表的 protobuf 字段中：4.x 为 `chat_room.ext_buffer`，
+//! 3.x 为 `ChatRoom.RoomData`，两者结构相同。3.x 缺少 `RoomData` 时退回到
+//! `^G` 分隔的 `UserNameList` / `DisplayNameList`。
+
+use prost::Message as _;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::contacts::CONTACT_DB_PATH;
+use super::{row_to_json, SqliteDataSource};
+use crate::errors::Result;
+use crate::models::{ChatRoom, ChatRoomMember};
+
+/// 3.x 成员列表的分隔符
+const MEMBER_SEPARATOR: &str = "^G";
+
+/// 群聊查询，依次尝试 4.x 和 3.x 的表结构；群名称优先取备注
+const CHATROOM_QUERIES: [&str; 2] = [
+    "SELECT r.username AS name, r.owner AS owner, r.ext_buffer AS data, \
+     COALESCE(NULLIF(c.remark, ''), c.nick_name) AS display_name, \
+     (SELECT COUNT(*) FROM chatroom_member m WHERE m.room_id = r.id) AS member_count \
+     FROM chat_room r LEFT JOIN contact c ON c.username = r.username ORDER BY r.username",
+    "SELECT r.ChatRoomName AS name, r.Reserved2 AS owner, r.RoomData AS data, \
+     r.UserNameList AS member_list, r.DisplayNameList AS member_names, \
+     COALESCE(NULLIF(c.Remark, ''), c.NickName) AS display_name \
+     FROM ChatRoom r LEFT JOIN Contact c ON c.UserName = r.ChatRoomName ORDER BY r.ChatRoomName",
+];
+
+/// 群资料（`ChatRoomData`）
+#[derive(Clone, PartialEq, prost::Message)]
+struct RoomData {
+    #[prost(message, repeated, tag = "1")]
+    members: Vec<RoomMember>,
+}
+
+/// 群成员（`ChatRoomMember`）
+#[derive(Clone, PartialEq, prost::Message)]
+struct RoomMember {
+    #[prost(string, tag = "1")]
+    username: String,
+    /// 群昵称
+    #[prost(string, optional, tag = "2")]
+    display_name: Option<String>,
+}
+
+/// 群聊仓库
+#[derive(Debug, Clone, Default)]
+pub struct ChatRoomRepository {
+    chatrooms: Vec<ChatRoom>,
+    /// 群ID到 `chatrooms` 下标
+    index: HashMap<String, usize>,
+}
+
+impl ChatRoomRepository {
+    /// 打开工作目录下的联系人数据库（4.x 群聊所在）并加载所有群聊
+    pub async fn open(work_dir: &Path) -> Result<Self> {
+        let source = SqliteDataSource::open(&work_dir.join(CONTACT_DB_PATH)).await?;
+        Self::load(&source).await
+    }
+
+    /// 从数据源加载所有群聊及成员
+    pub async fn load(source: &SqliteDataSource) -> Result<Self> {
+        let mut last_error = None;
+        for sql in CHATROOM_QUERIES {
+            match source.fetch_rows(sql, &[]).await {
+                Ok(rows) => {
+                    let chatrooms = rows.iter().map(row_to_json).filter_map(|row| chatroom_from_row(&row)).collect();
+                    return Ok(Self::from_chatrooms(chatrooms));
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error.expect("群聊查询列表不为空"))
+    }
+
+    /// 由已读取的群聊创建
+    pub fn from_chatrooms(chatrooms: Vec<ChatRoom>) -> Self {
+        let index = chatrooms
+            .iter()
+            .enumerate()
+            .map(|(position, chatroom)| (chatroom.chatroom_name.clone(), position))
+            .collect();
+        Self { chatrooms, index }
+    }
+
+    /// 所有群聊，按群ID排序
+    pub fn all(&self) -> &[ChatRoom] {
+        &self.chatrooms
+    }
+
+    /// 转换为群聊列表
+    pub fn into_chatrooms(self) -> Vec<ChatRoom> {
+        self.chatrooms
+    }
+
+    /// 按群ID查找群聊
+    pub fn get(&self, chatroom_name: &str) -> Option<&ChatRoom> {
+        self.index.get(chatroom_name).map(|&position| &self.chatrooms[position])
+    }
+
+    /// 群成员的群昵称，只包含设置了群昵称的成员
+    pub fn member_names(&self, chatroom_name: &str) -> HashMap<String, String> {
+        self.get(chatroom_name)
+            .map(|chatroom| {
+                chatroom
+                    .members
+                    .iter()
+                    .filter_map(|m| Some((m.username.clone(), m.display_name.clone()?)))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+fn chatroom_from_row(row: &Value) -> Option<ChatRoom> {
+    let text = |key: &str| row[key].as_str().filter(|s| !s.is_empty()).map(str::to_string);
+    let mut chatroom = ChatRoom::new(text("name")?);
+    chatroom.display_name = text("display_name");
+    chatroom.owner = text("owner");
+    chatroom.members = row["data"]
+        .as_str()
+        .and_then(|data| decode_members(&chatroom.chatroom_name, data))
+        .unwrap_or_else(|| split_members(text("member_list"), text("member_names")));
+    chatroom.member_count = (chatroom.members.len() as i32).max(row["member_count"].as_i64().unwrap_or_default() as i32);
+    Some(chatroom)
+}
+
+/// 解码十六进制形式的群资料，为空或无法解析时返回 `None`
+fn decode_members(chatroom_name: &str, data: &str) -> Option<Vec<ChatRoomMember>> {
+    let bytes = hex::decode(data).ok().filter(|bytes| !bytes.is_empty())?;
+    match RoomData::decode(bytes.as_slice()) {
+        Ok(room) => Some(
+            room.members
+                .into_iter()
+                .filter(|m| !m.username.is_empty())
+                .map(|m| ChatRoomMember {
+                    username: m.username,
+                    display_name: m.display_name.filter(|name| !name.is_empty()),
+                })
+                .collect(),
+        ),
+        Err(e) => {
+            tracing::warn!("无法解析群 {} 的成员数据: {}", chatroom_name, e);
+            None
+        }
+    }
+}
+
+/// 按 `^G` 拆分 3.x 的成员列表和群昵称列表
+fn split_members(usernames: Option<String>, display_names: Option<String>) -> Vec<ChatRoomMember> {
+    let display_names = display_names.unwrap_or_default();
+    let mut display_names = display_names.split(MEMBER_SEPARATOR);
+    usernames
+        .unwrap_or_default()
+        .split(MEMBER_SEPARATOR)
+        .map(|username| (username, display_names.next()))
+        .filter(|(username, _)| !username.is_empty())
+        .map(|(username, display_name)| ChatRoomMember {
+            username: username.to_string(),
+            display_name: display_name.filter(|name| !name.is_empty()).map(str::to_string),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqliteConnectOptions;
+    use sqlx::ConnectOptions;
+
+    /// 编码群资料
+    fn encode_room_data(members: &[(&str, Option<&str>)]) -> Vec<u8> {
+        RoomData {
+            members: members
+                .iter()
+                .map(|(username, display_name)| RoomMember {
+                    username: username.to_string(),
+                    display_name: display_name.map(str::to_string),
+                })
+                .collect(),
+        }
+        .encode_to_vec()
+    }
+
+    #[tokio::test]
+    async fn test_load_chatrooms_with_members() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONTACT_DB_PATH);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let mut conn = SqliteConnectOptions::new()
+            .filename(&path)
+            .create_if_missing(true)
+            .connect()
+            .await
+            .unwrap();
+        for sql in [
+            "CREATE TABLE contact (username TEXT, nick_name TEXT, remark TEXT)",
+            "INSERT INTO contact VALUES ('1@chatroom', '同学群', ''), ('2@chatroom', '旧群', '')",
+            "CREATE TABLE chat_room (id INTEGER PRIMARY KEY, username TEXT, owner TEXT, ext_buffer BLOB)",
+            "CREATE TABLE chatroom_member (room_id INTEGER, member_id INTEGER)",
+            "INSERT INTO chatroom_member VALUES (2, 1), (2, 2), (2, 3)",
+        ] {
+            sqlx::query(sql).execute(&mut conn).await.unwrap();
+        }
+        let data = encode_room_data(&[("wxid_a", Some("班长")), ("wxid_b", None), ("", Some("无效"))]);
+        sqlx::query("INSERT INTO chat_room VALUES (1, '1@chatroom', 'wxid_a', ?), (2, '2@chatroom', NULL, x'')")
+            .bind(data)
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        drop(conn);
+
+        let repository = ChatRoomRepository::open(dir.path()).await.unwrap();
+        assert_eq!(repository.all().len(), 2);
+        let room = repository.get("1@chatroom").unwrap();
+        assert_eq!(room.display_name.as_deref(), Some("同学群"));
+        assert_eq!(room.owner.as_deref(), Some("wxid_a"));
+        assert_eq!(room.member_count, 2);
+        assert_eq!(room.members[0].display_name.as_deref(), Some("班长"));
+        assert_eq!(room.members[1].display_name, None);
+        assert_eq!(repository.member_names("1@chatroom"), HashMap::from([("wxid_a".to_string(), "班长".to_string())]));
+
+        let old = repository.get("2@chatroom").unwrap();
+        assert!(old.members.is_empty());
+        assert_eq!(old.member_count, 3);
+    }
+
+    #[test]
+    fn test_split_v3_members() {
+        let members = split_members(
+            Some("wxid_a^Gwxid_b^Gwxid_c".to_string()),
+            Some("班长^G^G".to_string()),
+        );
+        let names: Vec<_> = members.iter().map(|m| (m.username.as_str(), m.display_name.as_deref())).collect();
+        assert_eq!(names, [("wxid_a", Some("班长")), ("wxid_b", None), ("wxid_c", None)]);
+        assert!(split_members(None, None).is_empty());
+    }
+}
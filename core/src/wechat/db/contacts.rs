@@ -0,0 +1,274 @@
+//! 联系人与标签读取
+//!
+//! 从解密后的联系人数据库读取联系人，并将标签ID列表解析为标签名。
+//! 同时兼容 4.x（`contact` / `contact_label`）和 3.x（`Contact` / `ContactLabel`）的表结构。
+//! [`ContactRepository`] 将联系人加载到内存，按 wxid 查找、按昵称或备注模糊搜索，
+//! 并为导出批量解析显示名称。
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::{DataSource, Query, SqliteDataSource};
+use crate::errors::Result;
+use crate::models::{Contact, Message};
+
+/// 解密输出中联系人数据库的相对路径
+pub const CONTACT_DB_PATH: &str = "db_storage/contact/decrypted_contact.db";
+
+/// 标签表：表名、ID 列、名称列
+const LABEL_TABLES: [(&str, &str, &str); 2] = [
+    ("contact_label", "label_id_", "label_name_"),
+    ("ContactLabel", "LabelID", "LabelName"),
+];
+
+/// 联系人表：表名、用户名列
+const CONTACT_TABLES: [(&str, &str); 2] = [("contact", "username"), ("Contact", "UserName")];
+
+/// 读取标签ID到名称的映射，没有标签表时返回空
+pub async fn load_labels(source: &dyn DataSource) -> Result<HashMap<i64, String>> {
+    for (table, id_column, name_column) in LABEL_TABLES {
+        let Ok(rows) = source.fetch(&Query::select(table).columns([id_column, name_column])).await else {
+            continue;
+        };
+        return Ok(rows
+            .iter()
+            .filter_map(|row| Some((row[id_column].as_i64()?, row[name_column].as_str()?.to_string())))
+            .collect());
+    }
+    Ok(HashMap::new())
+}
+
+/// 读取所有联系人及其标签
+pub async fn load_contacts(source: &dyn DataSource) -> Result<Vec<Contact>> {
+    let labels = load_labels(source).await?;
+    let mut last_error = None;
+    for (table, username_column) in CONTACT_TABLES {
+        match source.fetch(&Query::select(table).order_by(username_column, false)).await {
+            Ok(rows) => return Ok(rows.iter().filter_map(|row| contact_from_row(row, &labels)).collect()),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.expect("联系人表列表不为空"))
+}
+
+/// 只保留带有指定标签的联系人
+pub fn filter_by_label(contacts: Vec<Contact>, label: &str) -> Vec<Contact> {
+    contacts
+        .into_iter()
+        .filter(|contact| contact.labels.iter().any(|l| l == label))
+        .collect()
+}
+
+/// 联系人仓库
+#[derive(Debug, Clone, Default)]
+pub struct ContactRepository {
+    contacts: Vec<Contact>,
+    /// 用户名到 `contacts` 下标
+    index: HashMap<String, usize>,
+}
+
+impl ContactRepository {
+    /// 打开工作目录下的联系人数据库并加载所有联系人
+    pub async fn open(work_dir: &Path) -> Result<Self> {
+        let source = SqliteDataSource::open(&work_dir.join(CONTACT_DB_PATH)).await?;
+        Self::load(&source).await
+    }
+
+    /// 从数据源加载所有联系人
+    pub async fn load(source: &dyn DataSource) -> Result<Self> {
+        Ok(Self::from_contacts(load_contacts(source).await?))
+    }
+
+    /// 由已读取的联系人创建，用户名重复时保留第一个
+    pub fn from_contacts(contacts: Vec<Contact>) -> Self {
+        let mut index = HashMap::with_capacity(contacts.len());
+        for (position, contact) in contacts.iter().enumerate() {
+            index.entry(contact.username.clone()).or_insert(position);
+        }
+        Self { contacts, index }
+    }
+
+    /// 所有联系人，按用户名排序
+    pub fn all(&self) -> &[Contact] {
+        &self.contacts
+    }
+
+    /// 联系人数量
+    pub fn len(&self) -> usize {
+        self.contacts.len()
+    }
+
+    /// 是否没有联系人
+    pub fn is_empty(&self) -> bool {
+        self.contacts.is_empty()
+    }
+
+    /// 按 wxid 查找联系人
+    pub fn get(&self, wxid: &str) -> Option<&Contact> {
+        self.index.get(wxid).map(|&position| &self.contacts[position])
+    }
+
+    /// 显示名称（备注优先），联系人不存在时为 `None`
+    pub fn display_name(&self, wxid: &str) -> Option<&str> {
+        self.get(wxid).map(Contact::display_name)
+    }
+
+    /// 按用户名、昵称或备注模糊搜索，忽略大小写
+    ///
+    /// 完全相同的排在最前，其次是前缀匹配、包含，最后是按顺序包含所有字符的子序列匹配。
+    pub fn search(&self, text: &str, limit: usize) -> Vec<&Contact> {
+        let needle = text.trim().to_lowercase();
+        if needle.is_empty() {
+            return Vec::new();
+        }
+        let mut matched: Vec<(u8, &Contact)> = self
+            .contacts
+            .iter()
+            .filter_map(|contact| {
+                let score = [Some(&contact.username), contact.remark.as_ref(), contact.nickname.as_ref()]
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|value| match_score(&value.to_lowercase(), &needle))
+                    .min()?;
+                Some((score, contact))
+            })
+            .collect();
+        matched.sort_by_key(|(score, _)| *score);
+        matched.into_iter().take(limit).map(|(_, contact)| contact).collect()
+    }
+
+    /// 批量解析显示名称，只包含找到的联系人
+    pub fn resolve_names<'a>(&self, wxids: impl IntoIterator<Item = &'a str>) -> HashMap<String, String> {
+        wxids
+            .into_iter()
+            .filter_map(|wxid| Some((wxid.to_string(), self.display_name(wxid)?.to_string())))
+            .collect()
+    }
+
+    /// 填充消息中会话和发送者的显示名称，已有名称时保留
+    pub fn fill_names(&self, message: &mut Message) {
+        if message.talker_name.is_none() {
+            message.talker_name = self.display_name(&message.talker).map(str::to_string);
+        }
+        if message.sender_name.is_none() {
+            message.sender_name = self.display_name(&message.sender).map(str::to_string);
+        }
+    }
+}
+
+/// 模糊匹配的得分，越小越靠前；不匹配时为 `None`
+fn match_score(value: &str, needle: &str) -> Option<u8> {
+    if value == needle {
+        Some(0)
+    } else if value.starts_with(needle) {
+        Some(1)
+    } else if value.contains(needle) {
+        Some(2)
+    } else {
+        let mut chars = value.chars();
+        needle.chars().all(|c| chars.any(|v| v == c)).then_some(3)
+    }
+}
+
+/// 读取第一个存在的字符串列
+fn text_field(row: &Value, columns: &[&str]) -> Option<String> {
+    columns
+        .iter()
+        .find_map(|c| row.get(c).and_then(Value::as_str))
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+fn contact_from_row(row: &Value, labels: &HashMap<i64, String>) -> Option<Contact> {
+    let username = text_field(row, &["username", "UserName"])?;
+    let label_ids = text_field(row, &["label_id_list", "LabelIDList"]).unwrap_or_default();
+    Some(Contact {
+        username,
+        nickname: text_field(row, &["nick_name", "NickName"]),
+        remark: text_field(row, &["remark", "Remark"]),
+        avatar: text_field(row, &["small_head_url", "SmallHeadImgUrl"]),
+        labels: label_ids
+            .split(',')
+            .filter_map(|id| id.trim().parse::<i64>().ok())
+            .filter_map(|id| labels.get(&id).cloned())
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wechat::db::SqliteDataSource;
+    use sqlx::sqlite::SqliteConnectOptions;
+    use sqlx::ConnectOptions;
+
+    #[tokio::test]
+    async fn test_load_contacts_with_labels() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("contact.db");
+        let mut conn = SqliteConnectOptions::new()
+            .filename(&path)
+            .create_if_missing(true)
+            .connect()
+            .await
+            .unwrap();
+        for sql in [
+            "CREATE TABLE contact (username TEXT, nick_name TEXT, remark TEXT, small_head_url TEXT, label_id_list TEXT)",
+            "CREATE TABLE contact_label (label_id_ INTEGER, label_name_ TEXT, sort_order_ INTEGER)",
+            "INSERT INTO contact_label VALUES (1, '同事', 0), (2, '家人', 1)",
+            "INSERT INTO contact VALUES ('wxid_b', '小B', '', NULL, '2'), ('wxid_a', '小A', 'A哥', NULL, '1,2'), ('wxid_c', '小C', NULL, NULL, NULL)",
+        ] {
+            sqlx::query(sql).execute(&mut conn).await.unwrap();
+        }
+        drop(conn);
+
+        let source = SqliteDataSource::open(&path).await.unwrap();
+        let contacts = load_contacts(&source).await.unwrap();
+        assert_eq!(contacts.len(), 3);
+        assert_eq!(contacts[0].username, "wxid_a");
+        assert_eq!(contacts[0].remark.as_deref(), Some("A哥"));
+        assert_eq!(contacts[0].labels, ["同事", "家人"]);
+        assert_eq!(contacts[1].remark, None);
+        assert!(contacts[2].labels.is_empty());
+
+        let colleagues = filter_by_label(contacts, "同事");
+        assert_eq!(colleagues.len(), 1);
+        assert_eq!(colleagues[0].username, "wxid_a");
+    }
+
+    #[test]
+    fn test_contact_repository() {
+        let contact = |username: &str, nickname: &str, remark: Option<&str>| Contact {
+            nickname: Some(nickname.to_string()),
+            remark: remark.map(str::to_string),
+            ..Contact::new(username.to_string())
+        };
+        let repository = ContactRepository::from_contacts(vec![
+            contact("wxid_a", "Alice", Some("")),
+            contact("wxid_b", "Bob Lee", Some("老李")),
+            contact("wxid_c", "alibaba", None),
+        ]);
+        assert_eq!(repository.len(), 3);
+        assert_eq!(repository.display_name("wxid_a"), Some("Alice"));
+        assert_eq!(repository.display_name("wxid_b"), Some("老李"));
+        assert_eq!(repository.display_name("wxid_x"), None);
+
+        let names = |found: Vec<&Contact>| found.iter().map(|c| c.username.clone()).collect::<Vec<_>>();
+        assert_eq!(names(repository.search("ALI", 10)), ["wxid_a", "wxid_c"]);
+        assert_eq!(names(repository.search("blee", 10)), ["wxid_b"]);
+        assert_eq!(names(repository.search("老李", 1)), ["wxid_b"]);
+        assert!(repository.search(" ", 10).is_empty());
+
+        let resolved = repository.resolve_names(["wxid_b", "wxid_x"]);
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved["wxid_b"], "老李");
+
+        let mut message = Message::new();
+        message.talker = "wxid_b".to_string();
+        message.sender = "wxid_a".to_string();
+        repository.fill_names(&mut message);
+        assert_eq!(message.talker_name.as_deref(), Some("老李"));
+        assert_eq!(message.sender_name.as_deref(), Some("Alice"));
+    }
+}
@@ -0,0 +1,164 @@
+//! 硬链接数据库解析
+//!
+//! 4.x 的 hardlink.db 按媒体类型记录文件的 MD5、文件名以及两级目录；
+//! 目录以 `dir2id` 表的行号保存，需要再查一次得到实际目录名。
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use ts_rs::TS;
+
+use super::{DataSource, FilterOp, Query, SqlValue};
+use crate::errors::Result;
+
+/// 解密输出中硬链接数据库的相对路径
+pub const HARDLINK_DB_PATH: &str = "db_storage/hardlink/decrypted_hardlink.db";
+
+/// 媒体类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaKind {
+    /// 图片
+    Image,
+    /// 视频
+    Video,
+    /// 文件
+    File,
+}
+
+impl MediaKind {
+    /// 对应的硬链接表
+    fn table(&self) -> &'static str {
+        match self {
+            MediaKind::Image => "image_hardlink_info_v3",
+            MediaKind::Video => "video_hardlink_info_v3",
+            MediaKind::File => "file_hardlink_info_v3",
+        }
+    }
+}
+
+/// 硬链接记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HardlinkEntry {
+    /// 媒体类型
+    pub kind: MediaKind,
+    /// 文件 MD5
+    pub md5: String,
+    /// 文件名
+    pub file_name: String,
+    /// 文件大小（字节）
+    pub file_size: i64,
+    /// 一级目录，图片为会话用户名的 MD5
+    pub dir1: String,
+    /// 二级目录，图片为 `YYYY-MM`
+    pub dir2: String,
+}
+
+impl HardlinkEntry {
+    /// 相对账号数据目录的文件路径
+    ///
+    /// - 图片：`msg/attach/{dir1}/{dir2}/Img/{file_name}`
+    /// - 视频：`msg/video/{dir1}/{file_name}`
+    /// - 文件：`msg/file/{dir1}/{file_name}`
+    pub fn relative_path(&self) -> PathBuf {
+        let mut path = PathBuf::from("msg");
+        match self.kind {
+            MediaKind::Image => path.extend(["attach", &self.dir1, &self.dir2, "Img"]),
+            MediaKind::Video => path.extend(["video", &self.dir1]),
+            MediaKind::File => path.extend(["file", &self.dir1]),
+        }
+        path.push(&self.file_name);
+        path
+    }
+}
+
+/// 按 MD5 查找硬链接记录
+pub async fn lookup(source: &dyn DataSource, kind: MediaKind, md5: &str) -> Result<Option<HardlinkEntry>> {
+    let query = Query::select(kind.table())
+        .columns(["md5", "file_name", "file_size", "dir1", "dir2"])
+        .filter("md5", FilterOp::Eq, SqlValue::Text(md5.to_lowercase()))
+        .order_by("modify_time", true)
+        .limit(1);
+    let Some(row) = source.fetch(&query).await?.into_iter().next() else {
+        return Ok(None);
+    };
+
+    Ok(Some(HardlinkEntry {
+        kind,
+        md5: row["md5"].as_str().unwrap_or(md5).to_string(),
+        file_name: row["file_name"].as_str().unwrap_or_default().to_string(),
+        file_size: row["file_size"].as_i64().unwrap_or_default(),
+        dir1: dir_name(source, &row["dir1"]).await?,
+        dir2: dir_name(source, &row["dir2"]).await?,
+    }))
+}
+
+/// 将 `dir2id` 的行号解析为目录名
+async fn dir_name(source: &dyn DataSource, id: &serde_json::Value) -> Result<String> {
+    let Some(id) = id.as_i64() else {
+        return Ok(String::new());
+    };
+    let query = Query::select("dir2id")
+        .columns(["username"])
+        .filter("rowid", FilterOp::Eq, SqlValue::Integer(id))
+        .limit(1);
+    Ok(source
+        .fetch(&query)
+        .await?
+        .first()
+        .and_then(|row| row["username"].as_str())
+        .unwrap_or_default()
+        .to_string())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::wechat::db::SqliteDataSource;
+    use sqlx::sqlite::SqliteConnectOptions;
+    use sqlx::ConnectOptions;
+    use std::path::Path;
+
+    /// 创建包含一张图片和一个文件记录的硬链接数据库
+    pub(crate) async fn create_hardlink_db(path: &Path) {
+        let mut conn = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true)
+            .connect()
+            .await
+            .unwrap();
+        for sql in [
+            "CREATE TABLE dir2id (username TEXT)",
+            "INSERT INTO dir2id (rowid, username) VALUES (1, '0123abcd'), (2, '2024-05'), (3, '2024-06')",
+            "CREATE TABLE image_hardlink_info_v3 (md5 TEXT, file_name TEXT, file_size INTEGER, modify_time INTEGER, dir1 INTEGER, dir2 INTEGER)",
+            "INSERT INTO image_hardlink_info_v3 VALUES ('d41d8cd98f00b204e9800998ecf8427e', 'a1b2.dat', 1024, 1, 1, 2)",
+            "CREATE TABLE file_hardlink_info_v3 (md5 TEXT, file_name TEXT, file_size INTEGER, modify_time INTEGER, dir1 INTEGER, dir2 INTEGER)",
+            "INSERT INTO file_hardlink_info_v3 VALUES ('9e107d9d372bb6826bd81d3542a419d6', '报告.pdf', 2048, 1, 3, NULL)",
+        ] {
+            sqlx::query(sql).execute(&mut conn).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lookup_resolves_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hardlink.db");
+        create_hardlink_db(&path).await;
+        let source = SqliteDataSource::open(&path).await.unwrap();
+
+        let image = lookup(&source, MediaKind::Image, "D41D8CD98F00B204E9800998ECF8427E")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(image.relative_path(), Path::new("msg/attach/0123abcd/2024-05/Img/a1b2.dat"));
+
+        let file = lookup(&source, MediaKind::File, "9e107d9d372bb6826bd81d3542a419d6")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(file.relative_path(), Path::new("msg/file/2024-06/报告.pdf"));
+
+        assert!(lookup(&source, MediaKind::Video, "00").await.is_err());
+        assert!(lookup(&source, MediaKind::Image, "00").await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,365 @@
+//! 解密数据库管理
+//!
+//! [`DataSourceManager`] 识别解密输出目录的布局，打开其中的数据库并提供返回模型的查询：
+//!
+//! - 4.x：`contact.db` 为主库，`session.db`、`hardlink.db` 以 `session`、`hardlink` 附加；
+//!   消息分片交给 [`MessageRepository`]
+//! - 3.x：`MicroMsg.db` 同时保存联系人、群聊和会话，消息在 `MSG0.db`、`MSG1.db` 等分片的 `MSG` 表中
+
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+use super::chatrooms::ChatRoomRepository;
+use super::contacts::{load_contacts, ContactRepository, CONTACT_DB_PATH};
+use super::hardlink::HARDLINK_DB_PATH;
+use super::messages::{MessageRepository, MESSAGE_DB_DIR};
+use super::sessions::{SessionRepository, SESSION_DB_PATH};
+use super::{row_to_json, SqlValue, SqliteDataSource};
+use crate::errors::{DatabaseError, Result};
+use crate::models::{ChatRoom, Contact, Message, Session};
+
+/// 3.x 主数据库的文件名
+const MICRO_MSG_DB_NAME: &str = "decrypted_MicroMsg.db";
+
+/// 3.x 消息查询
+const MESSAGES_V3_SQL: &str = "SELECT Sequence, Type, SubType, IsSender, CreateTime, StrContent \
+     FROM MSG WHERE StrTalker = ? AND Sequence > ? ORDER BY Sequence LIMIT ?";
+
+/// 解密输出的数据库布局
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DbLayout {
+    /// 微信 4.x：`db_storage` 下按类别分库
+    V4,
+    /// 微信 3.x：`MicroMsg.db` 加 `MSG*.db` 分片
+    V3,
+}
+
+/// 消息存储
+enum MessageStore {
+    V4(MessageRepository),
+    /// 按分片序号排序的 3.x 消息库
+    V3(Vec<SqliteDataSource>),
+}
+
+/// 解密数据库管理器
+pub struct DataSourceManager {
+    layout: DbLayout,
+    catalog: SqliteDataSource,
+    messages: MessageStore,
+    self_wxid: Option<String>,
+}
+
+impl DataSourceManager {
+    /// 打开解密输出目录，自动识别 4.x 或 3.x 布局
+    pub async fn open(work_dir: &Path) -> Result<Self> {
+        let contact_db = work_dir.join(CONTACT_DB_PATH);
+        if contact_db.is_file() {
+            let attachments: Vec<(&str, PathBuf)> = [("session", SESSION_DB_PATH), ("hardlink", HARDLINK_DB_PATH)]
+                .into_iter()
+                .map(|(alias, path)| (alias, work_dir.join(path)))
+                .filter(|(_, path)| path.is_file())
+                .collect();
+            let catalog = SqliteDataSource::open_with_attachments(&contact_db, &attachments).await?;
+            let messages = if work_dir.join(MESSAGE_DB_DIR).is_dir() {
+                MessageRepository::open(work_dir).await?
+            } else {
+                MessageRepository::from_shards(Vec::new())
+            };
+            tracing::info!("打开 4.x 数据库: {} 个消息分片", messages.shard_count());
+            return Ok(Self {
+                layout: DbLayout::V4,
+                catalog,
+                messages: MessageStore::V4(messages),
+                self_wxid: None,
+            });
+        }
+
+        let mut files = Vec::new();
+        collect_files(work_dir, &mut files)?;
+        let Some(micro_msg) = files.iter().find(|path| path.file_name().is_some_and(|n| n == MICRO_MSG_DB_NAME))
+        else {
+            return Err(DatabaseError::FileNotFound {
+                path: contact_db.display().to_string(),
+            }
+            .into());
+        };
+        let catalog = SqliteDataSource::open(micro_msg).await?;
+
+        let mut shard_paths: Vec<(u32, &PathBuf)> = files
+            .iter()
+            .filter_map(|path| {
+                let name = path.file_name()?.to_str()?;
+                let index = name.strip_prefix("decrypted_MSG")?.strip_suffix(".db")?.parse().ok()?;
+                Some((index, path))
+            })
+            .collect();
+        shard_paths.sort();
+        let mut shards = Vec::with_capacity(shard_paths.len());
+        for (_, path) in shard_paths {
+            shards.push(SqliteDataSource::open(path).await?);
+        }
+        tracing::info!("打开 3.x 数据库: {} 个消息分片", shards.len());
+        Ok(Self {
+            layout: DbLayout::V3,
+            catalog,
+            messages: MessageStore::V3(shards),
+            self_wxid: None,
+        })
+    }
+
+    /// 当前账号的 wxid，用于标记自己发送的消息
+    pub fn with_self_wxid(mut self, wxid: impl Into<String>) -> Self {
+        let wxid = wxid.into();
+        self.messages = match self.messages {
+            MessageStore::V4(repository) => MessageStore::V4(repository.with_self_wxid(wxid.clone())),
+            other => other,
+        };
+        self.self_wxid = Some(wxid);
+        self
+    }
+
+    /// 数据库布局
+    pub fn layout(&self) -> DbLayout {
+        self.layout
+    }
+
+    /// 联系人所在的数据源，4.x 上已附加会话库和硬链接库
+    pub fn catalog(&self) -> &SqliteDataSource {
+        &self.catalog
+    }
+
+    /// 4.x 的消息仓库，3.x 布局返回 `None`
+    pub fn message_repository(&self) -> Option<&MessageRepository> {
+        match &self.messages {
+            MessageStore::V4(repository) => Some(repository),
+            MessageStore::V3(_) => None,
+        }
+    }
+
+    /// 所有联系人及其标签
+    pub async fn contacts(&self) -> Result<Vec<Contact>> {
+        load_contacts(&self.catalog).await
+    }
+
+    /// 加载联系人仓库，用于按 wxid 解析显示名称和模糊搜索
+    pub async fn contact_repository(&self) -> Result<ContactRepository> {
+        ContactRepository::load(&self.catalog).await
+    }
+
+    /// 所有群聊，按群ID排序
+    pub async fn chatrooms(&self) -> Result<Vec<ChatRoom>> {
+        Ok(self.chatroom_repository().await?.into_chatrooms())
+    }
+
+    /// 加载群聊仓库，包含成员和群昵称
+    pub async fn chatroom_repository(&self) -> Result<ChatRoomRepository> {
+        ChatRoomRepository::load(&self.catalog).await
+    }
+
+    /// 会话列表，最近的在前，名称取联系人备注或昵称；4.x 没有会话库时返回空
+    pub async fn sessions(&self) -> Result<Vec<Session>> {
+        let mut sessions = self.session_repository().await?;
+        match self.contact_repository().await {
+            Ok(contacts) => sessions.fill_names(&contacts),
+            Err(e) => tracing::debug!("无法读取联系人，会话不显示名称: {}", e),
+        }
+        Ok(sessions.into_sessions())
+    }
+
+    /// 加载会话仓库，包含未读数和最后一条消息的预览
+    pub async fn session_repository(&self) -> Result<SessionRepository> {
+        match self.layout {
+            DbLayout::V4 if !self.catalog.has_attachment("session").await? => Ok(SessionRepository::default()),
+            DbLayout::V4 => SessionRepository::load_from(&self.catalog, "session.").await,
+            DbLayout::V3 => SessionRepository::load(&self.catalog).await,
+        }
+    }
+
+    /// 按时间顺序读取会话中序号大于 `after` 的最多 `limit` 条消息
+    ///
+    /// 3.x 群消息的发送者保存在 `BytesExtra` 中，这里只区分自己和会话本身。
+    pub async fn messages(&self, talker: &str, after: Option<i64>, limit: u32) -> Result<Vec<Message>> {
+        let shards = match &self.messages {
+            MessageStore::V4(repository) => return repository.page(talker, after, limit).await,
+            MessageStore::V3(shards) => shards,
+        };
+        let params = [
+            SqlValue::Text(talker.to_string()),
+            SqlValue::Integer(after.unwrap_or(i64::MIN)),
+            SqlValue::Integer(limit as i64),
+        ];
+        let mut messages = Vec::new();
+        for shard in shards {
+            for row in shard.fetch_rows(MESSAGES_V3_SQL, &params).await?.iter().map(row_to_json) {
+                messages.push(self.message_from_v3_row(talker, &row));
+            }
+        }
+        messages.sort_by_key(|m| m.seq);
+        messages.truncate(limit as usize);
+        Ok(messages)
+    }
+
+    fn message_from_v3_row(&self, talker: &str, row: &Value) -> Message {
+        let mut message = Message::new();
+        message.seq = row["Sequence"].as_i64().unwrap_or_default();
+        message.time =
+            chrono::DateTime::from_timestamp(row["CreateTime"].as_i64().unwrap_or_default(), 0).unwrap_or_default();
+        message.talker = talker.to_string();
+        message.is_chatroom = talker.ends_with("@chatroom");
+        message.msg_type = row["Type"].as_i64().unwrap_or_default();
+        message.sub_type = row["SubType"].as_i64().unwrap_or_default();
+        message.is_self = row["IsSender"].as_i64() == Some(1);
+        message.sender = match (&self.self_wxid, message.is_self) {
+            (Some(wxid), true) => wxid.clone(),
+            _ => talker.to_string(),
+        };
+        message.content = row["StrContent"].as_str().unwrap_or_default().to_string();
+        message
+    }
+}
+
+impl SqliteDataSource {
+    /// 是否附加了指定别名的数据库
+    async fn has_attachment(&self, alias: &str) -> Result<bool> {
+        let rows = self.fetch_rows("SELECT name FROM pragma_database_list", &[]).await?;
+        Ok(rows.iter().map(row_to_json).any(|row| row["name"].as_str() == Some(alias)))
+    }
+}
+
+/// 递归收集目录下的文件
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wechat::db::messages::tests::create_message_dbs;
+    use sqlx::sqlite::SqliteConnectOptions;
+    use sqlx::ConnectOptions;
+
+    /// 创建数据库并执行建表和插入语句
+    async fn create_db(path: &Path, statements: &[&str]) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let mut conn = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true)
+            .connect()
+            .await
+            .unwrap();
+        for sql in statements {
+            sqlx::query(sql).execute(&mut conn).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_v4_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        create_db(
+            &dir.path().join(CONTACT_DB_PATH),
+            &[
+                "CREATE TABLE contact (id INTEGER PRIMARY KEY, username TEXT, nick_name TEXT, remark TEXT)",
+                "INSERT INTO contact VALUES (1, 'wxid_friend', '老王', ''), (2, '123@chatroom', '同学群', '高中同学')",
+                "CREATE TABLE chat_room (id INTEGER PRIMARY KEY, username TEXT, owner TEXT, ext_buffer BLOB)",
+                "INSERT INTO chat_room VALUES (7, '123@chatroom', 'wxid_friend', NULL)",
+                "CREATE TABLE chatroom_member (room_id INTEGER, member_id INTEGER)",
+                "INSERT INTO chatroom_member VALUES (7, 1), (7, 3), (8, 1)",
+            ],
+        )
+        .await;
+        create_db(
+            &dir.path().join(SESSION_DB_PATH),
+            &[
+                "CREATE TABLE SessionTable (username TEXT, unread_count INTEGER, sort_timestamp INTEGER)",
+                "INSERT INTO SessionTable VALUES ('wxid_friend', 2, 1700000003), ('123@chatroom', 0, 1700000009)",
+            ],
+        )
+        .await;
+        create_message_dbs(dir.path()).await;
+
+        let manager = DataSourceManager::open(dir.path()).await.unwrap().with_self_wxid("wxid_self");
+        assert_eq!(manager.layout(), DbLayout::V4);
+        assert_eq!(manager.message_repository().unwrap().shard_count(), 2);
+
+        let contacts = manager.contacts().await.unwrap();
+        assert_eq!(contacts.len(), 2);
+
+        let chatrooms = manager.chatrooms().await.unwrap();
+        assert_eq!(chatrooms.len(), 1);
+        assert_eq!(chatrooms[0].display_name.as_deref(), Some("高中同学"));
+        assert_eq!(chatrooms[0].member_count, 2);
+
+        let sessions = manager.sessions().await.unwrap();
+        let names: Vec<_> = sessions.iter().map(|s| s.username.as_str()).collect();
+        assert_eq!(names, ["123@chatroom", "wxid_friend"]);
+        assert_eq!(sessions[1].unread_count, 2);
+
+        let messages = manager.messages("wxid_friend", None, 10).await.unwrap();
+        assert_eq!(messages.len(), 3);
+        assert!(messages[0].is_self);
+    }
+
+    #[tokio::test]
+    async fn test_v3_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        create_db(
+            &dir.path().join("Msg").join(MICRO_MSG_DB_NAME),
+            &[
+                "CREATE TABLE Contact (UserName TEXT, NickName TEXT, Remark TEXT)",
+                "INSERT INTO Contact VALUES ('wxid_friend', '老王', ''), ('123@chatroom', '同学群', '')",
+                "CREATE TABLE ChatRoom (ChatRoomName TEXT, UserNameList TEXT, DisplayNameList TEXT, \
+                 Reserved2 TEXT, RoomData BLOB)",
+                "INSERT INTO ChatRoom VALUES ('123@chatroom', 'wxid_self^Gwxid_friend^Gwxid_b', '^G王哥^G', NULL, NULL)",
+                "CREATE TABLE Session (strUsrName TEXT, nUnReadCount INTEGER, nTime INTEGER)",
+                "INSERT INTO Session VALUES ('wxid_friend', 1, 1700000005), ('', 0, 1700000010)",
+            ],
+        )
+        .await;
+        for (index, rows) in [(1, "(3, 1, 0, 1, 1700000003, '收到')"), (0, "(1, 1, 0, 0, 1700000001, '你好')")] {
+            create_db(
+                &dir.path().join("Msg").join("Multi").join(format!("decrypted_MSG{}.db", index)),
+                &[
+                    "CREATE TABLE MSG (Sequence INTEGER, Type INTEGER, SubType INTEGER, IsSender INTEGER, \
+                     CreateTime INTEGER, StrContent TEXT, StrTalker TEXT)",
+                    &format!("INSERT INTO MSG SELECT *, 'wxid_friend' FROM (VALUES {})", rows),
+                ],
+            )
+            .await;
+        }
+
+        let manager = DataSourceManager::open(dir.path()).await.unwrap().with_self_wxid("wxid_self");
+        assert_eq!(manager.layout(), DbLayout::V3);
+        assert!(manager.message_repository().is_none());
+        assert_eq!(manager.contacts().await.unwrap().len(), 2);
+
+        let chatrooms = manager.chatrooms().await.unwrap();
+        assert_eq!(chatrooms[0].display_name.as_deref(), Some("同学群"));
+        assert_eq!(chatrooms[0].member_count, 3);
+        assert_eq!(chatrooms[0].members[1].display_name.as_deref(), Some("王哥"));
+
+        let sessions = manager.sessions().await.unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].unread_count, 1);
+
+        let messages = manager.messages("wxid_friend", None, 10).await.unwrap();
+        let contents: Vec<_> = messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, ["你好", "收到"]);
+        assert_eq!(messages[1].sender, "wxid_self");
+        assert_eq!(manager.messages("wxid_friend", Some(1), 10).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_missing_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = DataSourceManager::open(dir.path()).await.err().unwrap();
+        assert!(matches!(err.downcast_ref::<DatabaseError>(), Some(DatabaseError::FileNotFound { .. })));
+    }
+}
@@ -0,0 +1,691 @@
+//! 消息读取
+//!
+//! 4.x 的消息按时间分布在 `message_N.db` 分片中，每个会话一张 `Msg_{md5(用户名)}` 表；
+//! 发送者保存为 `Name2Id` 表的行号，消息内容可能经 zstd 压缩。
+//! 仓库按 `sort_seq` 游标跨分片分页读取，供导出等按会话遍历的场景使用；
+//! [`MessageRepository::window`] 读取锚点前后的消息，供界面虚拟滚动和跳转；
+//! [`MessageRepository::density`] 按天统计消息数，供日历热力图和跳转到日期；
+//! [`MessageRepository::list_media`] 按时间倒序列出会话中的图片、视频或文件，供媒体墙使用；
+//! [`MessageRepository::search`] 跨会话搜索文本消息。
+
+use chrono::{DateTime, FixedOffset, NaiveDate, Utc};
+use md5::{Digest, Md5};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::Row;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use ts_rs::TS;
+
+use super::hardlink::MediaKind;
+use super::{row_to_json, DataSource, FilterOp, Query, SqlValue, SqliteDataSource};
+use crate::errors::Result;
+use crate::models::Message;
+use crate::wechat::media::{media_md5, message_type};
+use crate::wechat::search::escape_like;
+
+/// 解密输出中消息数据库所在的相对目录
+pub const MESSAGE_DB_DIR: &str = "db_storage/message";
+
+/// zstd 帧头（十六进制）
+const ZSTD_MAGIC_HEX: &str = "28b52ffd";
+
+/// 文本消息类型
+const MSG_TYPE_TEXT: i64 = 1;
+
+/// 消息表的列
+const MESSAGE_COLUMNS: [&str; 7] = [
+    "local_id",
+    "sort_seq",
+    "local_type",
+    "real_sender_id",
+    "create_time",
+    "status",
+    "message_content",
+];
+
+/// 锚点附近的消息窗口
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct MessageWindow {
+    /// 按时间顺序排列的消息
+    pub messages: Vec<Message>,
+    /// 锚点消息在 `messages` 中的位置，锚点及之后没有消息时为 `None`
+    pub anchor_index: Option<usize>,
+    /// 窗口之前是否还有消息
+    pub has_more_before: bool,
+    /// 窗口之后是否还有消息
+    pub has_more_after: bool,
+}
+
+/// 某天的消息数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct DayCount {
+    /// 日期
+    pub date: NaiveDate,
+    /// 消息数
+    #[ts(type = "number")]
+    pub count: u64,
+}
+
+/// 会话中的媒体消息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct MediaItem {
+    /// 消息的 `sort_seq`，作为下一页的游标
+    #[ts(type = "number")]
+    pub seq: i64,
+    /// 发送时间
+    pub time: DateTime<Utc>,
+    /// 发送者
+    pub sender: String,
+    /// 媒体类型
+    pub kind: MediaKind,
+    /// 媒体 MD5，可经硬链接数据库定位文件
+    pub md5: String,
+}
+
+/// 会话对应的消息表名
+pub fn message_table(talker: &str) -> String {
+    format!("Msg_{}", hex::encode(Md5::digest(talker.as_bytes())))
+}
+
+/// 消息仓库
+pub struct MessageRepository {
+    shards: Vec<SqliteDataSource>,
+    self_wxid: Option<String>,
+}
+
+impl MessageRepository {
+    /// 打开工作目录下的所有消息分片，按分片序号排序
+    pub async fn open(work_dir: &Path) -> Result<Self> {
+        let dir = work_dir.join(MESSAGE_DB_DIR);
+        let mut paths: Vec<(u32, std::path::PathBuf)> = std::fs::read_dir(&dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter_map(|path| {
+                let name = path.file_name()?.to_str()?;
+                let index = name.strip_prefix("decrypted_message_")?.strip_suffix(".db")?.parse().ok()?;
+                Some((index, path))
+            })
+            .collect();
+        paths.sort();
+
+        let mut shards = Vec::with_capacity(paths.len());
+        for (_, path) in paths {
+            shards.push(SqliteDataSource::open(&path).await?);
+        }
+        Ok(Self::from_shards(shards))
+    }
+
+    /// 由已打开的分片创建
+    pub fn from_shards(shards: Vec<SqliteDataSource>) -> Self {
+        Self {
+            shards,
+            self_wxid: None,
+        }
+    }
+
+    /// 当前账号的 wxid，用于标记自己发送的消息
+    pub fn with_self_wxid(mut self, wxid: impl Into<String>) -> Self {
+        self.self_wxid = Some(wxid.into());
+        self
+    }
+
+    /// 分片数量
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// 会话的消息总数
+    pub async fn count(&self, talker: &str) -> Result<u64> {
+        let table = message_table(talker);
+        let mut total = 0;
+        for shard in &self.shards {
+            if !has_table(shard, &table).await? {
+                continue;
+            }
+            let rows = shard.fetch(&Query::count(table.as_str())).await?;
+            total += rows.first().and_then(|row| row["count"].as_u64()).unwrap_or_default();
+        }
+        Ok(total)
+    }
+
+    /// 所有会话的消息总数
+    pub async fn count_all(&self) -> Result<u64> {
+        let mut total = 0;
+        for shard in &self.shards {
+            for table in message_tables(shard).await? {
+                let rows = shard.fetch(&Query::count(table.as_str())).await?;
+                total += rows.first().and_then(|row| row["count"].as_u64()).unwrap_or_default();
+            }
+        }
+        Ok(total)
+    }
+
+    /// 按时间顺序读取 `sort_seq` 大于 `after` 的最多 `limit` 条消息
+    pub async fn page(&self, talker: &str, after: Option<i64>, limit: u32) -> Result<Vec<Message>> {
+        self.fetch(talker, after.map(|seq| (FilterOp::Gt, seq)), false, limit).await
+    }
+
+    /// 读取锚点前后的消息
+    ///
+    /// 锚点为 `sort_seq`，窗口包含锚点之前的 `before` 条、锚点（或其后第一条）消息及之后的 `after` 条；
+    /// `anchor` 为 `None` 时以最新一条消息为锚点。
+    pub async fn window(&self, talker: &str, anchor: Option<i64>, before: u32, after: u32) -> Result<MessageWindow> {
+        let anchor = match anchor {
+            Some(seq) => seq,
+            None => match self.fetch(talker, None, true, 1).await?.first() {
+                Some(latest) => latest.seq,
+                None => return Ok(MessageWindow::default()),
+            },
+        };
+
+        // 多取一条用于判断是否还有更多
+        let mut older = self.fetch(talker, Some((FilterOp::Lt, anchor)), true, before + 1).await?;
+        let has_more_before = older.len() > before as usize;
+        older.truncate(before as usize);
+        older.reverse();
+
+        let mut newer = self.fetch(talker, Some((FilterOp::Ge, anchor)), false, after + 2).await?;
+        let has_more_after = newer.len() > after as usize + 1;
+        newer.truncate(after as usize + 1);
+
+        Ok(MessageWindow {
+            anchor_index: (!newer.is_empty()).then_some(older.len()),
+            messages: older.into_iter().chain(newer).collect(),
+            has_more_before,
+            has_more_after,
+        })
+    }
+
+    /// 按天统计会话的消息数，日期按 `offset` 时区划分，按日期升序返回
+    pub async fn density(&self, talker: &str, offset: FixedOffset) -> Result<Vec<DayCount>> {
+        let table = message_table(talker);
+        // 表名由 md5 生成，只含十六进制字符
+        let sql = format!(
+            "SELECT date(create_time + ?, 'unixepoch') AS day, COUNT(*) AS count FROM \"{}\" GROUP BY day",
+            table
+        );
+        let params = [SqlValue::Integer(offset.local_minus_utc() as i64)];
+        let mut days: BTreeMap<NaiveDate, u64> = BTreeMap::new();
+        for shard in &self.shards {
+            if !has_table(shard, &table).await? {
+                continue;
+            }
+            for row in shard.fetch_rows(&sql, &params).await? {
+                let day: Option<String> = row.try_get("day").unwrap_or_default();
+                let Some(date) = day.and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok()) else {
+                    continue;
+                };
+                *days.entry(date).or_default() += row.try_get::<i64, _>("count").unwrap_or_default() as u64;
+            }
+        }
+        Ok(days.into_iter().map(|(date, count)| DayCount { date, count }).collect())
+    }
+
+    /// 指定日期（`offset` 时区）当天或之后的第一条消息的 `sort_seq`，用作跳转锚点
+    pub async fn seq_at_date(&self, talker: &str, date: NaiveDate, offset: FixedOffset) -> Result<Option<i64>> {
+        let table = message_table(talker);
+        let start = date.and_time(chrono::NaiveTime::MIN).and_utc().timestamp() - offset.local_minus_utc() as i64;
+        let mut first: Option<(i64, i64)> = None;
+        for shard in &self.shards {
+            if !has_table(shard, &table).await? {
+                continue;
+            }
+            let query = Query::select(table.as_str())
+                .columns(["create_time", "sort_seq"])
+                .filter("create_time", FilterOp::Ge, SqlValue::Integer(start))
+                .order_by("create_time", false)
+                .order_by("sort_seq", false)
+                .limit(1);
+            for row in shard.fetch(&query).await? {
+                let candidate = (
+                    row["create_time"].as_i64().unwrap_or_default(),
+                    row["sort_seq"].as_i64().unwrap_or_default(),
+                );
+                first = Some(first.map_or(candidate, |current| current.min(candidate)));
+            }
+        }
+        Ok(first.map(|(_, seq)| seq))
+    }
+
+    /// 按时间倒序列出 `sort_seq` 小于 `before` 的最多 `limit` 条媒体消息
+    ///
+    /// 文件为 `<type>6</type>` 的应用消息，需要在读取后筛选，因此按批次读取直到凑满一页。
+    pub async fn list_media(
+        &self,
+        talker: &str,
+        kind: MediaKind,
+        before: Option<i64>,
+        limit: u32,
+    ) -> Result<Vec<MediaItem>> {
+        let table = message_table(talker);
+        let mut items = Vec::new();
+        let mut cursor = before;
+        while items.len() < limit as usize {
+            let mut batch = Vec::new();
+            for shard in &self.shards {
+                if !has_table(shard, &table).await? {
+                    continue;
+                }
+                // 表名由 md5 生成，只含十六进制字符；消息类型的高 32 位为子类型
+                let mut sql = format!(
+                    "SELECT {} FROM \"{}\" WHERE (local_type & 4294967295) = ?",
+                    MESSAGE_COLUMNS.join(", "),
+                    table
+                );
+                let mut params = vec![SqlValue::Integer(message_type(kind))];
+                if let Some(seq) = cursor {
+                    sql.push_str(" AND sort_seq < ?");
+                    params.push(SqlValue::Integer(seq));
+                }
+                sql.push_str(" ORDER BY sort_seq DESC LIMIT ?");
+                params.push(SqlValue::Integer(limit as i64));
+                for row in shard.fetch_rows(&sql, &params).await? {
+                    let row = row_to_json(&row);
+                    let sender = sender_name(shard, &row["real_sender_id"]).await?;
+                    batch.push(self.message_from_row(talker, &row, sender));
+                }
+            }
+            batch.sort_by_key(|m| std::cmp::Reverse(m.seq));
+            batch.truncate(limit as usize);
+            let exhausted = batch.len() < limit as usize;
+            cursor = batch.last().map(|m| m.seq);
+
+            items.extend(batch.into_iter().filter_map(|message| match media_md5(&message) {
+                Some((found, md5)) if found == kind => Some(MediaItem {
+                    seq: message.seq,
+                    time: message.time,
+                    sender: message.sender,
+                    kind,
+                    md5,
+                }),
+                _ => None,
+            }));
+            if exhausted {
+                break;
+            }
+        }
+        items.truncate(limit as usize);
+        Ok(items)
+    }
+
+    /// 跨会话搜索文本消息，按时间倒序返回最多 `limit` 条
+    ///
+    /// 先用 `LIKE` 粗筛（zstd 压缩的内容无法在 SQL 中匹配，一并取出），解码后再按不区分大小写的子串确认。
+    /// 消息表名为会话用户名的 md5，`talkers` 用于还原会话，不在其中的会话以表名代替。
+    pub async fn search(&self, text: &str, talkers: &[String], limit: usize) -> Result<Vec<Message>> {
+        let text = text.trim();
+        if text.is_empty() || limit == 0 {
+            return Ok(Vec::new());
+        }
+        let names: HashMap<String, &str> = talkers.iter().map(|t| (message_table(t), t.as_str())).collect();
+        let needle = text.to_lowercase();
+        let params = [
+            SqlValue::Integer(MSG_TYPE_TEXT),
+            SqlValue::Text(format!("%{}%", escape_like(text))),
+        ];
+
+        let mut hits = Vec::new();
+        for shard in &self.shards {
+            for table in message_tables(shard).await? {
+                let talker = names.get(&table).copied().unwrap_or(table.as_str());
+                let sql = format!(
+                    "SELECT {} FROM \"{}\" WHERE (local_type & 4294967295) = ? \
+                     AND (message_content LIKE ? ESCAPE '\\' OR substr(message_content, 1, 4) = X'{}') \
+                     ORDER BY sort_seq DESC",
+                    MESSAGE_COLUMNS.join(", "),
+                    table,
+                    ZSTD_MAGIC_HEX
+                );
+                let mut found = 0;
+                for row in shard.fetch_rows(&sql, &params).await? {
+                    let row = row_to_json(&row);
+                    let sender = sender_name(shard, &row["real_sender_id"]).await?;
+                    let message = self.message_from_row(talker, &row, sender);
+                    if message.content.to_lowercase().contains(&needle) {
+                        hits.push(message);
+                        found += 1;
+                        if found == limit {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        hits.sort_by_key(|m| std::cmp::Reverse((m.time, m.seq)));
+        hits.truncate(limit);
+        Ok(hits)
+    }
+
+    /// 跨分片读取消息，`desc` 时按时间倒序返回
+    async fn fetch(&self, talker: &str, bound: Option<(FilterOp, i64)>, desc: bool, limit: u32) -> Result<Vec<Message>> {
+        let table = message_table(talker);
+        let mut messages = Vec::new();
+        for shard in &self.shards {
+            if !has_table(shard, &table).await? {
+                continue;
+            }
+            let mut query = Query::select(table.as_str())
+                .columns(MESSAGE_COLUMNS)
+                .order_by("sort_seq", desc)
+                .limit(limit);
+            if let Some((op, seq)) = bound {
+                query = query.filter("sort_seq", op, SqlValue::Integer(seq));
+            }
+            for row in shard.fetch(&query).await? {
+                let sender = sender_name(shard, &row["real_sender_id"]).await?;
+                messages.push(self.message_from_row(talker, &row, sender));
+            }
+        }
+        messages.sort_by_key(|m| m.seq);
+        if desc {
+            messages.reverse();
+        }
+        messages.truncate(limit as usize);
+        Ok(messages)
+    }
+
+    fn message_from_row(&self, talker: &str, row: &Value, sender: String) -> Message {
+        let mut message = Message::new();
+        message.seq = row["sort_seq"].as_i64().unwrap_or_default();
+        message.time = chrono::DateTime::from_timestamp(row["create_time"].as_i64().unwrap_or_default(), 0)
+            .unwrap_or_default();
+        message.talker = talker.to_string();
+        message.is_chatroom = talker.ends_with("@chatroom");
+        message.msg_type = row["local_type"].as_i64().unwrap_or_default();
+        // 高 32 位为子类型
+        message.sub_type = message.msg_type >> 32;
+        message.msg_type &= 0xffff_ffff;
+
+        let mut content = decode_content(&row["message_content"]);
+        if message.is_chatroom {
+            if let Some((prefix, rest)) = content.split_once(":\n") {
+                if !prefix.contains(char::is_whitespace) {
+                    content = rest.to_string();
+                }
+            }
+        }
+        message.content = content;
+        message.is_self = self.self_wxid.as_deref() == Some(sender.as_str());
+        message.sender = if sender.is_empty() { talker.to_string() } else { sender };
+        message
+    }
+}
+
+/// 分片中是否存在指定表
+pub(super) async fn has_table(source: &dyn DataSource, table: &str) -> Result<bool> {
+    let query = Query::select("sqlite_master")
+        .columns(["name"])
+        .filter("type", FilterOp::Eq, SqlValue::Text("table".to_string()))
+        .filter("name", FilterOp::Eq, SqlValue::Text(table.to_string()))
+        .limit(1);
+    Ok(!source.fetch(&query).await?.is_empty())
+}
+
+/// 分片中的消息表，只返回 `Msg_` 加 32 位十六进制的表名
+async fn message_tables(source: &dyn DataSource) -> Result<Vec<String>> {
+    let rows = source
+        .fetch(
+            &Query::select("sqlite_master")
+                .columns(["name"])
+                .filter("type", FilterOp::Eq, SqlValue::Text("table".to_string()))
+                .filter("name", FilterOp::Like, SqlValue::Text("Msg_%".to_string())),
+        )
+        .await?;
+    Ok(rows
+        .iter()
+        .filter_map(|row| row["name"].as_str())
+        .filter(|name| {
+            name.strip_prefix("Msg_")
+                .is_some_and(|hash| hash.len() == 32 && hash.bytes().all(|b| b.is_ascii_hexdigit()))
+        })
+        .map(str::to_string)
+        .collect())
+}
+
+/// 将 `Name2Id` 的行号解析为用户名
+async fn sender_name(source: &dyn DataSource, id: &Value) -> Result<String> {
+    let Some(id) = id.as_i64() else {
+        return Ok(String::new());
+    };
+    let query = Query::select("Name2Id")
+        .columns(["user_name"])
+        .filter("rowid", FilterOp::Eq, SqlValue::Integer(id))
+        .limit(1);
+    Ok(source
+        .fetch(&query)
+        .await?
+        .first()
+        .and_then(|row| row["user_name"].as_str())
+        .unwrap_or_default()
+        .to_string())
+}
+
+/// 解码消息内容，BLOB 列为十六进制字符串，zstd 压缩的内容先解压
+fn decode_content(value: &Value) -> String {
+    let Some(text) = value.as_str() else {
+        return String::new();
+    };
+    if text.starts_with(ZSTD_MAGIC_HEX) {
+        if let Some(decoded) = hex::decode(text)
+            .ok()
+            .and_then(|bytes| zstd::decode_all(bytes.as_slice()).ok())
+        {
+            return String::from_utf8_lossy(&decoded).into_owned();
+        }
+    }
+    text.to_string()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use sqlx::sqlite::SqliteConnectOptions;
+    use sqlx::ConnectOptions;
+
+    /// 在 `work_dir` 下创建两个消息分片：`wxid_friend` 的 3 条消息分布在两个分片中，
+    /// 其中一条为 zstd 压缩；`123@chatroom` 有 1 条带发送者前缀的群消息
+    pub(crate) async fn create_message_dbs(work_dir: &Path) {
+        let dir = work_dir.join(MESSAGE_DB_DIR);
+        std::fs::create_dir_all(&dir).unwrap();
+        let friend = message_table("wxid_friend");
+        let room = message_table("123@chatroom");
+
+        for (index, rows) in [
+            (0, [(&friend, 1000, 1, 1, "你好", false), (&room, 1500, 1, 2, "wxid_b:\nhi all", false)]),
+            (1, [(&friend, 2000, 1, 2, "在吗", false), (&friend, 3000, 34_i64 | (1 << 32), 1, "压缩的消息", true)]),
+        ] {
+            let mut conn = SqliteConnectOptions::new()
+                .filename(dir.join(format!("decrypted_message_{}.db", index)))
+                .create_if_missing(true)
+                .connect()
+                .await
+                .unwrap();
+            sqlx::query("CREATE TABLE Name2Id (user_name TEXT)").execute(&mut conn).await.unwrap();
+            sqlx::query("INSERT INTO Name2Id (rowid, user_name) VALUES (1, 'wxid_self'), (2, 'wxid_b')")
+                .execute(&mut conn)
+                .await
+                .unwrap();
+            for (table, seq, local_type, sender, content, compressed) in rows {
+                let create = format!(
+                    "CREATE TABLE IF NOT EXISTS {} (local_id INTEGER PRIMARY KEY, sort_seq INTEGER, local_type INTEGER, \
+                     real_sender_id INTEGER, create_time INTEGER, status INTEGER, message_content)",
+                    table
+                );
+                sqlx::query(&create).execute(&mut conn).await.unwrap();
+                let insert = format!(
+                    "INSERT INTO {} (sort_seq, local_type, real_sender_id, create_time, status, message_content) \
+                     VALUES (?, ?, ?, ?, 2, ?)",
+                    table
+                );
+                let query = sqlx::query(&insert)
+                    .bind(seq)
+                    .bind(local_type)
+                    .bind(sender)
+                    .bind(1_700_000_000 + seq / 1000);
+                let query = if compressed {
+                    query.bind(zstd::encode_all(content.as_bytes(), 3).unwrap())
+                } else {
+                    query.bind(content)
+                };
+                query.execute(&mut conn).await.unwrap();
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_page_across_shards() {
+        let dir = tempfile::tempdir().unwrap();
+        create_message_dbs(dir.path()).await;
+        let repo = MessageRepository::open(dir.path()).await.unwrap().with_self_wxid("wxid_self");
+        assert_eq!(repo.shard_count(), 2);
+        assert_eq!(repo.count("wxid_friend").await.unwrap(), 3);
+        assert_eq!(repo.count("nobody").await.unwrap(), 0);
+        assert_eq!(repo.count_all().await.unwrap(), 4);
+
+        let first = repo.page("wxid_friend", None, 2).await.unwrap();
+        assert_eq!(first.iter().map(|m| m.seq).collect::<Vec<_>>(), [1000, 2000]);
+        assert!(first[0].is_self);
+        assert_eq!(first[1].sender, "wxid_b");
+        assert_eq!(first[1].content, "在吗");
+
+        let rest = repo.page("wxid_friend", Some(2000), 2).await.unwrap();
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].content, "压缩的消息");
+        assert_eq!((rest[0].msg_type, rest[0].sub_type), (34, 1));
+
+        let room = repo.page("123@chatroom", None, 10).await.unwrap();
+        assert_eq!(room[0].content, "hi all");
+        assert!(room[0].is_chatroom);
+    }
+
+    #[tokio::test]
+    async fn test_window_around_anchor() {
+        let dir = tempfile::tempdir().unwrap();
+        create_message_dbs(dir.path()).await;
+        let repo = MessageRepository::open(dir.path()).await.unwrap();
+        let seqs = |window: &MessageWindow| window.messages.iter().map(|m| m.seq).collect::<Vec<_>>();
+
+        let window = repo.window("wxid_friend", Some(2000), 1, 0).await.unwrap();
+        assert_eq!(seqs(&window), [1000, 2000]);
+        assert_eq!(window.anchor_index, Some(1));
+        assert!(!window.has_more_before && window.has_more_after);
+
+        // 锚点不存在时从其后第一条开始
+        let window = repo.window("wxid_friend", Some(1500), 0, 5).await.unwrap();
+        assert_eq!(seqs(&window), [2000, 3000]);
+        assert!(window.has_more_before && !window.has_more_after);
+
+        let latest = repo.window("wxid_friend", None, 1, 1).await.unwrap();
+        assert_eq!(seqs(&latest), [2000, 3000]);
+        assert_eq!(latest.anchor_index, Some(1));
+
+        let past_end = repo.window("wxid_friend", Some(9999), 1, 1).await.unwrap();
+        assert_eq!(seqs(&past_end), [3000]);
+        assert_eq!(past_end.anchor_index, None);
+
+        assert!(repo.window("nobody", None, 10, 10).await.unwrap().messages.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_density_and_jump_to_date() {
+        let dir = tempfile::tempdir().unwrap();
+        create_message_dbs(dir.path()).await;
+        let repo = MessageRepository::open(dir.path()).await.unwrap();
+        // 测试数据的时间为 1_700_000_001..=1_700_000_003，即 2023-11-14 22:13:2x UTC
+        let utc = FixedOffset::east_opt(0).unwrap();
+        let beijing = FixedOffset::east_opt(8 * 3600).unwrap();
+        let day = |d: u32| NaiveDate::from_ymd_opt(2023, 11, d).unwrap();
+
+        assert_eq!(repo.density("wxid_friend", utc).await.unwrap(), [DayCount { date: day(14), count: 3 }]);
+        assert_eq!(repo.density("wxid_friend", beijing).await.unwrap(), [DayCount { date: day(15), count: 3 }]);
+        assert!(repo.density("nobody", utc).await.unwrap().is_empty());
+
+        assert_eq!(repo.seq_at_date("wxid_friend", day(14), utc).await.unwrap(), Some(1000));
+        assert_eq!(repo.seq_at_date("wxid_friend", day(15), utc).await.unwrap(), None);
+        assert_eq!(repo.seq_at_date("wxid_friend", day(15), beijing).await.unwrap(), Some(1000));
+    }
+
+    #[tokio::test]
+    async fn test_list_media() {
+        let dir = tempfile::tempdir().unwrap();
+        create_message_dbs(dir.path()).await;
+        let md5 = |n: u8| format!("{:032x}", n);
+        let mut conn = SqliteConnectOptions::new()
+            .filename(dir.path().join(MESSAGE_DB_DIR).join("decrypted_message_1.db"))
+            .connect()
+            .await
+            .unwrap();
+        let table = message_table("wxid_friend");
+        for (seq, local_type, content) in [
+            (4000, 3_i64, format!(r#"<msg><img md5="{}" /></msg>"#, md5(1))),
+            (5000, 49 | (6 << 32), format!("<msg><appmsg><type>6</type><md5>{}</md5></appmsg></msg>", md5(2))),
+            (6000, 49 | (5 << 32), "<msg><appmsg><type>5</type></appmsg></msg>".to_string()),
+            (7000, 3, format!(r#"<msg><img md5="{}" /></msg>"#, md5(3))),
+            (8000, 43, format!(r#"<msg><videomsg md5="{}" /></msg>"#, md5(4))),
+        ] {
+            let insert = format!(
+                "INSERT INTO {} (sort_seq, local_type, real_sender_id, create_time, status, message_content) \
+                 VALUES (?, ?, 2, ?, 2, ?)",
+                table
+            );
+            let query = sqlx::query(&insert).bind(seq).bind(local_type).bind(1_700_000_000 + seq / 1000);
+            query.bind(content).execute(&mut conn).await.unwrap();
+        }
+        drop(conn);
+        let repo = MessageRepository::open(dir.path()).await.unwrap();
+
+        let images = repo.list_media("wxid_friend", MediaKind::Image, None, 1).await.unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!((images[0].seq, images[0].md5.as_str()), (7000, md5(3).as_str()));
+        assert_eq!(images[0].sender, "wxid_b");
+        let older = repo.list_media("wxid_friend", MediaKind::Image, Some(7000), 10).await.unwrap();
+        assert_eq!(older.iter().map(|m| m.seq).collect::<Vec<_>>(), [4000]);
+
+        // 非文件的应用消息被跳过
+        let files = repo.list_media("wxid_friend", MediaKind::File, None, 1).await.unwrap();
+        assert_eq!(files.iter().map(|m| m.seq).collect::<Vec<_>>(), [5000]);
+        let videos = repo.list_media("wxid_friend", MediaKind::Video, None, 10).await.unwrap();
+        assert_eq!(videos[0].md5, md5(4));
+        assert!(repo.list_media("nobody", MediaKind::Image, None, 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_search_text_messages() {
+        let dir = tempfile::tempdir().unwrap();
+        create_message_dbs(dir.path()).await;
+        let mut conn = SqliteConnectOptions::new()
+            .filename(dir.path().join(MESSAGE_DB_DIR).join("decrypted_message_1.db"))
+            .connect()
+            .await
+            .unwrap();
+        let insert = format!(
+            "INSERT INTO {} (sort_seq, local_type, real_sender_id, create_time, status, message_content) \
+             VALUES (4000, 1, 1, 1700000004, 2, ?)",
+            message_table("wxid_friend")
+        );
+        let compressed = zstd::encode_all("压缩的长文本".as_bytes(), 3).unwrap();
+        sqlx::query(&insert).bind(compressed).execute(&mut conn).await.unwrap();
+        drop(conn);
+        let repo = MessageRepository::open(dir.path()).await.unwrap();
+        let talkers = ["wxid_friend".to_string(), "123@chatroom".to_string()];
+
+        let hits = repo.search("在", &talkers, 10).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!((hits[0].talker.as_str(), hits[0].content.as_str()), ("wxid_friend", "在吗"));
+
+        // 群消息的发送者前缀不参与匹配，大小写不敏感
+        assert_eq!(repo.search("HI", &talkers, 10).await.unwrap()[0].talker, "123@chatroom");
+        assert!(repo.search("wxid_b", &talkers, 10).await.unwrap().is_empty());
+        assert_eq!(repo.search("长文本", &talkers, 10).await.unwrap()[0].seq, 4000);
+        // 语音消息不参与搜索
+        assert!(repo.search("压缩的消息", &talkers, 10).await.unwrap().is_empty());
+
+        // 不在 talkers 中的会话以表名代替
+        let hits = repo.search("你好", &[], 10).await.unwrap();
+        assert_eq!(hits[0].talker, message_table("wxid_friend"));
+        assert!(repo.search(" ", &talkers, 10).await.unwrap().is_empty());
+    }
+}
@@ -1,20 +1,306 @@
 //! 微信数据库数据源模块
+//!
+//! 只读访问解密后的数据库：连接以只读方式打开并启用 `PRAGMA query_only`，
+//! 查询通过 [`Query`] 构建器生成；原始 SQL 需要显式开启，且不能附加或分离数据库。
 
-use crate::errors::Result;
+pub mod cache;
+pub mod chatrooms;
+pub mod contacts;
+pub mod hardlink;
+pub mod manager;
+pub mod messages;
+pub mod query;
+pub mod sessions;
+pub mod tail;
+pub mod voices;
+pub mod writer;
+
+pub use cache::{QueryCache, QueryCacheConfig, QueryKey};
+pub use manager::{DataSourceManager, DbLayout};
+pub use query::{Filter, FilterOp, OrderBy, Query, SqlValue};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::sqlite::{SqliteArguments, SqliteConnectOptions, SqlitePool, SqlitePoolOptions, SqliteRow};
+use sqlx::{Arguments, Column, Row};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::errors::{DatabaseError, Result};
 
 /// 数据源接口
-pub trait DataSource {
-    async fn connect(&self) -> Result<()>;
-    async fn query(&self, sql: &str) -> Result<Vec<serde_json::Value>>;
+#[async_trait]
+pub trait DataSource: Send + Sync {
+    /// 执行构建器生成的查询，每行转换为以列名为键的 JSON 对象
+    async fn fetch(&self, query: &Query) -> Result<Vec<Value>>;
+
+    /// 执行原始 SQL，`?` 占位符按顺序绑定 `params`；未开启时返回 `DatabaseError::RawSqlDisabled`，
+    /// 包含 `ATTACH`/`DETACH` 时返回 `DatabaseError::ForbiddenSql`
+    async fn fetch_raw(&self, sql: &str, params: &[SqlValue]) -> Result<QueryRows>;
+}
+
+/// 保留列顺序的查询结果
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QueryRows {
+    /// 列名
+    pub columns: Vec<String>,
+    /// 各行的值，与列名一一对应
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl QueryRows {
+    /// 转换为以列名为键的 JSON 对象
+    pub fn into_objects(self) -> Vec<Value> {
+        self.rows
+            .into_iter()
+            .map(|row| Value::Object(self.columns.iter().cloned().zip(row).collect()))
+            .collect()
+    }
+}
+
+/// SQLite 只读数据源
+pub struct SqliteDataSource {
+    pool: SqlitePool,
+    allow_raw_sql: bool,
 }
 
-/// 数据源管理器
-pub struct DataSourceManager {
-    // 占位符实现
+impl SqliteDataSource {
+    /// 以只读方式打开数据库
+    pub async fn open(path: &Path) -> Result<Self> {
+        Self::open_with_attachments(path, &[]).await
+    }
+
+    /// 以只读方式打开数据库，并在每个连接上附加 `attachments` 中的数据库
+    ///
+    /// 附加库通过 `别名.表名` 访问，可与主库联表查询；别名必须是合法的标识符。
+    /// `query_only` 作用于整个连接，附加库同样只读。
+    pub async fn open_with_attachments(path: &Path, attachments: &[(&str, PathBuf)]) -> Result<Self> {
+        let mut statements = Vec::with_capacity(attachments.len());
+        for (alias, attached) in std::iter::once(&("main", path.to_path_buf())).chain(attachments) {
+            if !attached.is_file() {
+                return Err(DatabaseError::FileNotFound {
+                    path: attached.display().to_string(),
+                }
+                .into());
+            }
+            if *alias != "main" {
+                let sql = format!("ATTACH DATABASE ? AS {}", query::quote_ident(alias)?);
+                statements.push((sql, attached.display().to_string()));
+            }
+        }
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .read_only(true)
+            .pragma("query_only", "ON");
+        let statements = Arc::new(statements);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(4)
+            .after_connect(move |conn, _| {
+                let statements = statements.clone();
+                Box::pin(async move {
+                    for (sql, attached) in statements.iter() {
+                        sqlx::query(sql).bind(attached).execute(&mut *conn).await?;
+                    }
+                    Ok(())
+                })
+            })
+            .connect_with(options)
+            .await
+            .map_err(|e| DatabaseError::ConnectionFailed(e.to_string()))?;
+        Ok(Self {
+            pool,
+            allow_raw_sql: false,
+        })
+    }
+
+    /// 是否允许执行原始 SQL，连接仍保持只读
+    pub fn with_raw_sql(mut self, allow: bool) -> Self {
+        self.allow_raw_sql = allow;
+        self
+    }
 }
 
-impl DataSourceManager {
-    pub fn new() -> Result<Self> {
-        Ok(Self {})
+#[async_trait]
+impl DataSource for SqliteDataSource {
+    async fn fetch(&self, query: &Query) -> Result<Vec<Value>> {
+        let (sql, params) = query.build()?;
+        let rows = self.fetch_rows(&sql, &params).await?;
+        Ok(rows.iter().map(row_to_json).collect())
+    }
+
+    async fn fetch_raw(&self, sql: &str, params: &[SqlValue]) -> Result<QueryRows> {
+        if !self.allow_raw_sql {
+            return Err(DatabaseError::RawSqlDisabled.into());
+        }
+        if let Some(keyword) = forbidden_keyword(sql) {
+            return Err(DatabaseError::ForbiddenSql(keyword.to_string()).into());
+        }
+        tracing::warn!("执行原始 SQL: {}", sql);
+        let rows = self.fetch_rows(sql, params).await?;
+        let columns = rows
+            .first()
+            .map(|row| row.columns().iter().map(|c| c.name().to_string()).collect())
+            .unwrap_or_default();
+        Ok(QueryRows {
+            columns,
+            rows: rows.iter().map(row_values).collect(),
+        })
     }
-}
\ No newline at end of file
+}
+
+impl SqliteDataSource {
+    /// 绑定参数并执行查询
+    async fn fetch_rows(&self, sql: &str, params: &[SqlValue]) -> Result<Vec<SqliteRow>> {
+        let mut arguments = SqliteArguments::default();
+        for param in params {
+            let added = match param.clone() {
+                SqlValue::Null => arguments.add(Option::<i64>::None),
+                SqlValue::Integer(v) => arguments.add(v),
+                SqlValue::Real(v) => arguments.add(v),
+                SqlValue::Text(v) => arguments.add(v),
+            };
+            added.map_err(|e| DatabaseError::SqlError(sqlx::Error::Encode(e)))?;
+        }
+        let rows = sqlx::query_with(sql, arguments)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(DatabaseError::SqlError)?;
+        Ok(rows)
+    }
+}
+
+/// 原始 SQL 中禁止的关键字
+///
+/// `query_only` 不限制附加数据库，`ATTACH` 可以读取磁盘上的任意 SQLite 文件；
+/// `DETACH` 会移除连接上预先附加的数据库，影响连接池中后续的查询。
+const FORBIDDEN_RAW_KEYWORDS: [&str; 2] = ["ATTACH", "DETACH"];
+
+/// 查找原始 SQL 中禁止的关键字，跳过字符串、带引号的标识符和注释
+fn forbidden_keyword(sql: &str) -> Option<&'static str> {
+    let bytes = sql.as_bytes();
+    let skip_until = |from: usize, end: &[u8]| {
+        bytes[from..]
+            .windows(end.len())
+            .position(|window| window == end)
+            .map_or(bytes.len(), |offset| from + offset + end.len())
+    };
+    let is_word = |b: u8| b.is_ascii_alphanumeric() || b == b'_' || b == b'$' || b >= 0x80;
+
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            // 连续两个引号表示转义，按结束后紧接着新的引号处理，效果相同
+            quote @ (b'\'' | b'"' | b'`') => i = skip_until(i + 1, &[quote]),
+            b'[' => i = skip_until(i + 1, b"]"),
+            b'-' if bytes.get(i + 1) == Some(&b'-') => i = skip_until(i + 2, b"\n"),
+            b'/' if bytes.get(i + 1) == Some(&b'*') => i = skip_until(i + 2, b"*/"),
+            b if is_word(b) => {
+                let start = i;
+                while i < bytes.len() && is_word(bytes[i]) {
+                    i += 1;
+                }
+                let word = &bytes[start..i];
+                if let Some(keyword) = FORBIDDEN_RAW_KEYWORDS
+                    .iter()
+                    .find(|keyword| keyword.as_bytes().eq_ignore_ascii_case(word))
+                {
+                    return Some(keyword);
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// 将一行转换为 JSON 对象
+fn row_to_json(row: &SqliteRow) -> Value {
+    let object: Map<String, Value> = row
+        .columns()
+        .iter()
+        .map(|c| c.name().to_string())
+        .zip(row_values(row))
+        .collect();
+    Value::Object(object)
+}
+
+/// 按列顺序读取一行的值，BLOB 列输出为十六进制字符串
+fn row_values(row: &SqliteRow) -> Vec<Value> {
+    (0..row.columns().len())
+        .map(|index| {
+            if let Ok(v) = row.try_get::<Option<i64>, _>(index) {
+                v.map_or(Value::Null, Value::from)
+            } else if let Ok(Some(v)) = row.try_get::<Option<f64>, _>(index) {
+                Value::from(v)
+            } else if let Ok(Some(v)) = row.try_get::<Option<String>, _>(index) {
+                Value::from(v)
+            } else if let Ok(Some(v)) = row.try_get::<Option<Vec<u8>>, _>(index) {
+                Value::from(hex::encode(v))
+            } else {
+                Value::Null
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::ConnectOptions;
+
+    #[tokio::test]
+    async fn test_read_only_data_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("contact.db");
+        let mut conn = SqliteConnectOptions::new()
+            .filename(&path)
+            .create_if_missing(true)
+            .connect()
+            .await
+            .unwrap();
+        sqlx::query("CREATE TABLE contact (username TEXT, remark TEXT, flag INTEGER)")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO contact VALUES ('wxid_a', '同事', 1), ('wxid_b', NULL, 2)")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        drop(conn);
+
+        let source = SqliteDataSource::open(&path).await.unwrap();
+        let rows = source
+            .fetch(&Query::select("contact").filter("remark", FilterOp::Eq, SqlValue::Text("同事".to_string())))
+            .await
+            .unwrap();
+        assert_eq!(rows, [serde_json::json!({"username": "wxid_a", "remark": "同事", "flag": 1})]);
+
+        let injected = Query::select("contact").filter("username", FilterOp::Eq, SqlValue::Text("x' OR '1'='1".to_string()));
+        assert!(source.fetch(&injected).await.unwrap().is_empty());
+
+        assert!(source.fetch_raw("SELECT 1", &[]).await.is_err());
+        let source = source.with_raw_sql(true);
+        let result = source
+            .fetch_raw("SELECT flag, username FROM contact WHERE flag > ?", &[SqlValue::Integer(1)])
+            .await
+            .unwrap();
+        assert_eq!(result.columns, ["flag", "username"]);
+        assert_eq!(result.rows, [vec![serde_json::json!(2), serde_json::json!("wxid_b")]]);
+        assert!(source.fetch_raw("DELETE FROM contact", &[]).await.is_err());
+
+        // 不能通过附加数据库读取其他文件
+        let other = dir.path().join("other.db");
+        std::fs::copy(&path, &other).unwrap();
+        let attach = format!("/* x */ attach database '{}' AS other", other.display());
+        let err = source.fetch_raw(&attach, &[]).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<DatabaseError>(), Some(DatabaseError::ForbiddenSql(_))));
+        assert!(source.fetch_raw("DETACH main", &[]).await.is_err());
+        // 字符串、带引号的标识符和注释中的同名单词不受影响
+        let quoted = source
+            .fetch_raw("SELECT 'attach' AS \"detach\", [ATTACH] -- attach\nFROM (SELECT 1 AS [ATTACH])", &[])
+            .await
+            .unwrap();
+        assert_eq!(quoted.rows, [vec![serde_json::json!("attach"), serde_json::json!(1)]]);
+    }
+}
@@ -0,0 +1,287 @@
+//! 只读查询构建器
+//!
+//! 查询只能通过构建器生成：表名、列名按标识符规则校验，条件值全部作为参数绑定，
+//! 不会拼接进 SQL 文本。构建器可以直接从 JSON 反序列化，供 HTTP 接口使用。
+
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::str::FromStr;
+use ts_rs::TS;
+
+use crate::errors::{DatabaseError, Result};
+
+/// 查询参数值
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(untagged)]
+pub enum SqlValue {
+    /// 空值
+    Null,
+    /// 整数
+    Integer(#[ts(type = "number")] i64),
+    /// 浮点数
+    Real(f64),
+    /// 文本
+    Text(String),
+}
+
+impl FromStr for SqlValue {
+    type Err = Infallible;
+
+    /// 按字面量解析：`null`、整数、浮点数，其余作为文本
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("null") {
+            Ok(SqlValue::Null)
+        } else if let Ok(v) = s.parse() {
+            Ok(SqlValue::Integer(v))
+        } else if let Ok(v) = s.parse() {
+            Ok(SqlValue::Real(v))
+        } else {
+            Ok(SqlValue::Text(s.to_string()))
+        }
+    }
+}
+
+/// 比较运算符
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Like,
+    IsNull,
+    IsNotNull,
+}
+
+impl FilterOp {
+    fn as_sql(&self) -> &'static str {
+        match self {
+            FilterOp::Eq => "=",
+            FilterOp::Ne => "!=",
+            FilterOp::Lt => "<",
+            FilterOp::Le => "<=",
+            FilterOp::Gt => ">",
+            FilterOp::Ge => ">=",
+            FilterOp::Like => "LIKE",
+            FilterOp::IsNull => "IS NULL",
+            FilterOp::IsNotNull => "IS NOT NULL",
+        }
+    }
+
+    /// 运算符是否需要参数
+    fn takes_value(&self) -> bool {
+        !matches!(self, FilterOp::IsNull | FilterOp::IsNotNull)
+    }
+}
+
+/// 查询条件，多个条件之间为 AND 关系
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct Filter {
+    /// 列名
+    pub column: String,
+    /// 运算符
+    pub op: FilterOp,
+    /// 比较值，`is_null`/`is_not_null` 时忽略
+    #[serde(default = "null_value")]
+    pub value: SqlValue,
+}
+
+fn null_value() -> SqlValue {
+    SqlValue::Null
+}
+
+/// 排序
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct OrderBy {
+    /// 列名
+    pub column: String,
+    /// 是否降序
+    #[serde(default)]
+    pub desc: bool,
+}
+
+/// SELECT 查询
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct Query {
+    /// 表名
+    pub table: String,
+    /// 查询的列，为空时查询全部列
+    pub columns: Vec<String>,
+    /// 查询条件
+    pub filters: Vec<Filter>,
+    /// 排序
+    pub order_by: Vec<OrderBy>,
+    /// 最多返回的行数
+    pub limit: Option<u32>,
+    /// 跳过的行数
+    pub offset: Option<u32>,
+    /// 只返回满足条件的行数（`count` 列），忽略查询的列
+    pub count: bool,
+}
+
+impl Query {
+    /// 查询指定表
+    pub fn select(table: impl Into<String>) -> Self {
+        Self {
+            table: table.into(),
+            ..Default::default()
+        }
+    }
+
+    /// 统计指定表满足条件的行数
+    pub fn count(table: impl Into<String>) -> Self {
+        Self {
+            table: table.into(),
+            count: true,
+            ..Default::default()
+        }
+    }
+
+    /// 设置查询的列
+    pub fn columns<S: Into<String>>(mut self, columns: impl IntoIterator<Item = S>) -> Self {
+        self.columns = columns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// 添加条件
+    pub fn filter(mut self, column: impl Into<String>, op: FilterOp, value: SqlValue) -> Self {
+        self.filters.push(Filter {
+            column: column.into(),
+            op,
+            value,
+        });
+        self
+    }
+
+    /// 添加排序
+    pub fn order_by(mut self, column: impl Into<String>, desc: bool) -> Self {
+        self.order_by.push(OrderBy {
+            column: column.into(),
+            desc,
+        });
+        self
+    }
+
+    /// 设置返回行数上限
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// 设置跳过的行数
+    pub fn offset(mut self, offset: u32) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// 生成 SQL 文本和按顺序绑定的参数
+    pub fn build(&self) -> Result<(String, Vec<SqlValue>)> {
+        let mut sql = String::from("SELECT ");
+        if self.count {
+            sql.push_str("COUNT(*) AS \"count\"");
+        } else if self.columns.is_empty() {
+            sql.push('*');
+        } else {
+            let columns: Vec<String> = self.columns.iter().map(|c| quote_ident(c)).collect::<Result<_>>()?;
+            sql.push_str(&columns.join(", "));
+        }
+        sql.push_str(" FROM ");
+        sql.push_str(&quote_ident(&self.table)?);
+
+        let mut params = Vec::new();
+        if !self.filters.is_empty() {
+            let mut conditions = Vec::with_capacity(self.filters.len());
+            for filter in &self.filters {
+                let column = quote_ident(&filter.column)?;
+                if filter.op.takes_value() {
+                    conditions.push(format!("{} {} ?", column, filter.op.as_sql()));
+                    params.push(filter.value.clone());
+                } else {
+                    conditions.push(format!("{} {}", column, filter.op.as_sql()));
+                }
+            }
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+
+        if !self.order_by.is_empty() {
+            let orders: Vec<String> = self
+                .order_by
+                .iter()
+                .map(|o| Ok(format!("{} {}", quote_ident(&o.column)?, if o.desc { "DESC" } else { "ASC" })))
+                .collect::<Result<_>>()?;
+            sql.push_str(" ORDER BY ");
+            sql.push_str(&orders.join(", "));
+        }
+
+        match (self.limit, self.offset) {
+            (Some(limit), Some(offset)) => sql.push_str(&format!(" LIMIT {} OFFSET {}", limit, offset)),
+            (Some(limit), None) => sql.push_str(&format!(" LIMIT {}", limit)),
+            (None, Some(offset)) => sql.push_str(&format!(" LIMIT -1 OFFSET {}", offset)),
+            (None, None) => {}
+        }
+        Ok((sql, params))
+    }
+}
+
+/// 校验标识符并加引号
+pub(super) fn quote_ident(name: &str) -> Result<String> {
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(DatabaseError::InvalidIdentifier(name.to_string()).into());
+    }
+    Ok(format!("\"{}\"", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_binds_values_and_rejects_identifiers() {
+        let (sql, params) = Query::select("Msg_abc")
+            .columns(["local_id", "message_content"])
+            .filter("create_time", FilterOp::Ge, SqlValue::Integer(1700000000))
+            .filter("message_content", FilterOp::Like, SqlValue::Text("%' OR 1=1 --%".to_string()))
+            .filter("status", FilterOp::IsNotNull, SqlValue::Null)
+            .order_by("create_time", true)
+            .limit(20)
+            .build()
+            .unwrap();
+        assert_eq!(
+            sql,
+            "SELECT \"local_id\", \"message_content\" FROM \"Msg_abc\" \
+             WHERE \"create_time\" >= ? AND \"message_content\" LIKE ? AND \"status\" IS NOT NULL \
+             ORDER BY \"create_time\" DESC LIMIT 20"
+        );
+        assert_eq!(params.len(), 2);
+
+        let (sql, _) = Query::count("Msg_abc").columns(["local_id"]).build().unwrap();
+        assert_eq!(sql, "SELECT COUNT(*) AS \"count\" FROM \"Msg_abc\"");
+
+        assert!(Query::select("Msg; DROP TABLE Msg").build().is_err());
+        assert!(Query::select("Msg").columns(["a\" FROM x --"]).build().is_err());
+
+        let query: Query = serde_json::from_str(
+            r#"{"table":"Contact","filters":[{"column":"remark","op":"eq","value":"同事"}],"limit":5}"#,
+        )
+        .unwrap();
+        assert_eq!(query.build().unwrap().1, [SqlValue::Text("同事".to_string())]);
+
+        assert_eq!("42".parse::<SqlValue>().unwrap(), SqlValue::Integer(42));
+        assert_eq!("1.5".parse::<SqlValue>().unwrap(), SqlValue::Real(1.5));
+        assert_eq!("NULL".parse::<SqlValue>().unwrap(), SqlValue::Null);
+        assert_eq!("wxid_a".parse::<SqlValue>().unwrap(), SqlValue::Text("wxid_a".to_string()));
+    }
+}
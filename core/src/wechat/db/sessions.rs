@@ -0,0 +1,194 @@
+//! 会话列表读取
+//!
+//! 最近会话保存在 4.x 的 `session.db`（`SessionTable`）和 3.x 的 `MicroMsg.db`（`Session`）中，
+//! 包含未读数、排序时间和最后一条消息的预览。旧版本缺少预览列时退回到只读取基本列。
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::contacts::ContactRepository;
+use super::{row_to_json, SqliteDataSource};
+use crate::errors::Result;
+use crate::models::Session;
+
+/// 解密输出中会话数据库的相对路径（4.x）
+pub const SESSION_DB_PATH: &str = "db_storage/session/decrypted_session.db";
+
+/// 会话查询，依次尝试 4.x 和 3.x 的表结构，`{schema}` 替换为附加数据库的前缀
+const SESSION_QUERIES: [&str; 4] = [
+    "SELECT username, unread_count, sort_timestamp AS time, summary, \
+     last_msg_sender AS sender, last_sender_display_name AS sender_name \
+     FROM {schema}SessionTable WHERE username <> '' ORDER BY sort_timestamp DESC",
+    "SELECT username, unread_count, sort_timestamp AS time \
+     FROM {schema}SessionTable WHERE username <> '' ORDER BY sort_timestamp DESC",
+    "SELECT strUsrName AS username, nUnReadCount AS unread_count, nTime AS time, \
+     strContent AS summary, strNickName AS display_name \
+     FROM {schema}Session WHERE strUsrName <> '' ORDER BY nTime DESC",
+    "SELECT strUsrName AS username, nUnReadCount AS unread_count, nTime AS time \
+     FROM {schema}Session WHERE strUsrName <> '' ORDER BY nTime DESC",
+];
+
+/// 会话仓库
+#[derive(Debug, Clone, Default)]
+pub struct SessionRepository {
+    sessions: Vec<Session>,
+    /// 用户名到 `sessions` 下标
+    index: HashMap<String, usize>,
+}
+
+impl SessionRepository {
+    /// 打开工作目录下的会话数据库（4.x）并加载所有会话
+    pub async fn open(work_dir: &Path) -> Result<Self> {
+        let source = SqliteDataSource::open(&work_dir.join(SESSION_DB_PATH)).await?;
+        Self::load(&source).await
+    }
+
+    /// 从数据源加载所有会话，最近的在前
+    pub async fn load(source: &SqliteDataSource) -> Result<Self> {
+        Self::load_from(source, "").await
+    }
+
+    /// 从附加的数据库加载，`schema` 为带点的前缀（如 `session.`）
+    pub(super) async fn load_from(source: &SqliteDataSource, schema: &str) -> Result<Self> {
+        let mut last_error = None;
+        for sql in SESSION_QUERIES {
+            match source.fetch_rows(&sql.replace("{schema}", schema), &[]).await {
+                Ok(rows) => {
+                    let sessions = rows.iter().map(row_to_json).filter_map(|row| session_from_row(&row)).collect();
+                    return Ok(Self::from_sessions(sessions));
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error.expect("会话查询列表不为空"))
+    }
+
+    /// 由已读取的会话创建，按最后消息时间从新到旧排序
+    pub fn from_sessions(mut sessions: Vec<Session>) -> Self {
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.last_message_time));
+        let index = sessions
+            .iter()
+            .enumerate()
+            .map(|(position, session)| (session.username.clone(), position))
+            .collect();
+        Self { sessions, index }
+    }
+
+    /// 所有会话，最近的在前
+    pub fn all(&self) -> &[Session] {
+        &self.sessions
+    }
+
+    /// 转换为会话列表
+    pub fn into_sessions(self) -> Vec<Session> {
+        self.sessions
+    }
+
+    /// 按用户名查找会话
+    pub fn get(&self, username: &str) -> Option<&Session> {
+        self.index.get(username).map(|&position| &self.sessions[position])
+    }
+
+    /// 所有会话的未读数之和
+    pub fn unread_total(&self) -> i64 {
+        self.sessions.iter().map(|s| s.unread_count as i64).sum()
+    }
+
+    /// 用联系人备注或昵称补全会话名称和群聊最后发送者的名称
+    pub fn fill_names(&mut self, contacts: &ContactRepository) {
+        for session in &mut self.sessions {
+            if let Some(name) = contacts.display_name(&session.username) {
+                session.display_name = Some(name.to_string());
+            }
+            if let Some(name) = session.last_sender.as_deref().and_then(|sender| contacts.display_name(sender)) {
+                session.last_sender = Some(name.to_string());
+            }
+        }
+    }
+}
+
+fn session_from_row(row: &Value) -> Option<Session> {
+    let text = |key: &str| row[key].as_str().filter(|s| !s.is_empty()).map(str::to_string);
+    let mut session = Session::new(text("username")?);
+    session.last_message_time =
+        chrono::DateTime::from_timestamp(row["time"].as_i64().unwrap_or_default(), 0).unwrap_or_default();
+    session.unread_count = row["unread_count"].as_i64().unwrap_or_default() as i32;
+    session.display_name = text("display_name");
+    let (sender, summary) = split_sender(&session.username, text("summary"));
+    session.summary = summary;
+    session.last_sender = text("sender_name").or(text("sender")).or(sender);
+    Some(session)
+}
+
+/// 群聊预览以 `发送者:\n` 开头时拆出发送者
+fn split_sender(username: &str, summary: Option<String>) -> (Option<String>, Option<String>) {
+    let Some(summary) = summary else {
+        return (None, None);
+    };
+    if !username.ends_with("@chatroom") {
+        return (None, Some(summary));
+    }
+    match summary.split_once(":\n") {
+        Some((sender, content)) if !sender.is_empty() && !sender.contains(char::is_whitespace) => {
+            (Some(sender.to_string()), Some(content.to_string()))
+        }
+        _ => (None, Some(summary)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Contact;
+    use sqlx::sqlite::SqliteConnectOptions;
+    use sqlx::ConnectOptions;
+
+    #[tokio::test]
+    async fn test_load_sessions_with_preview() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SESSION_DB_PATH);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let mut conn = SqliteConnectOptions::new()
+            .filename(&path)
+            .create_if_missing(true)
+            .connect()
+            .await
+            .unwrap();
+        for sql in [
+            "CREATE TABLE SessionTable (username TEXT, unread_count INTEGER, sort_timestamp INTEGER, \
+             summary TEXT, last_msg_sender TEXT, last_sender_display_name TEXT)",
+            "INSERT INTO SessionTable VALUES \
+             ('wxid_friend', 2, 1700000003, '晚上吃什么', '', ''), \
+             ('123@chatroom', 5, 1700000009, 'wxid_b:\nhi all', '', ''), \
+             ('', 1, 1700000010, '', '', '')",
+        ] {
+            sqlx::query(sql).execute(&mut conn).await.unwrap();
+        }
+        drop(conn);
+
+        let mut repository = SessionRepository::open(dir.path()).await.unwrap();
+        let names: Vec<_> = repository.all().iter().map(|s| s.username.as_str()).collect();
+        assert_eq!(names, ["123@chatroom", "wxid_friend"]);
+        assert_eq!(repository.unread_total(), 7);
+
+        let room = repository.get("123@chatroom").unwrap();
+        assert_eq!(room.summary.as_deref(), Some("hi all"));
+        assert_eq!(room.last_sender.as_deref(), Some("wxid_b"));
+
+        let contacts = ContactRepository::from_contacts(vec![
+            Contact {
+                nickname: Some("老王".to_string()),
+                ..Contact::new("wxid_friend".to_string())
+            },
+            Contact {
+                remark: Some("小李".to_string()),
+                ..Contact::new("wxid_b".to_string())
+            },
+        ]);
+        repository.fill_names(&contacts);
+        assert_eq!(repository.get("wxid_friend").unwrap().display_name.as_deref(), Some("老王"));
+        assert_eq!(repository.get("wxid_friend").unwrap().summary.as_deref(), Some("晚上吃什么"));
+        assert_eq!(repository.get("123@chatroom").unwrap().last_sender.as_deref(), Some("小李"));
+    }
+}
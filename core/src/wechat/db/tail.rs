@@ -0,0 +1,234 @@
+//! 会话消息跟踪
+//!
+//! 自动刷新会替换工作目录中的消息分片，[`ConversationTail`] 每次读取时重新打开分片，
+//! 记住已输出的最后一条消息的 `sort_seq`，只返回之后新增的消息。
+//! [`NewMessageScanner`] 对所有会话做同样的跟踪，按会话列表中的最后消息时间找出有新消息的会话。
+
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::contacts::ContactRepository;
+use super::messages::MessageRepository;
+use super::sessions::SessionRepository;
+use crate::errors::Result;
+use crate::models::Message;
+
+/// 每次读取的消息数
+const PAGE_SIZE: u32 = 500;
+
+/// 跟踪一个会话的新消息
+pub struct ConversationTail {
+    work_dir: PathBuf,
+    talker: String,
+    self_wxid: Option<String>,
+    last_seq: Option<i64>,
+}
+
+impl ConversationTail {
+    /// 跟踪工作目录中会话 `talker` 的消息
+    pub fn new(work_dir: &Path, talker: impl Into<String>) -> Self {
+        Self {
+            work_dir: work_dir.to_path_buf(),
+            talker: talker.into(),
+            self_wxid: None,
+            last_seq: None,
+        }
+    }
+
+    /// 当前账号的 wxid，用于标记自己发送的消息
+    pub fn with_self_wxid(mut self, wxid: impl Into<String>) -> Self {
+        self.self_wxid = Some(wxid.into());
+        self
+    }
+
+    /// 已输出的最后一条消息的 `sort_seq`
+    pub fn last_seq(&self) -> Option<i64> {
+        self.last_seq
+    }
+
+    /// 读取最近的 `lines` 条消息，之后的 [`poll`](Self::poll) 从最新一条消息之后开始
+    pub async fn backlog(&mut self, lines: u32) -> Result<Vec<Message>> {
+        let repository = self.repository().await?;
+        let window = repository.window(&self.talker, None, lines.saturating_sub(1), 0).await?;
+        if let Some(latest) = window.messages.last() {
+            self.last_seq = Some(latest.seq);
+        }
+        let skip = window.messages.len().saturating_sub(lines as usize);
+        Ok(window.messages.into_iter().skip(skip).collect())
+    }
+
+    /// 读取上次之后新增的消息
+    pub async fn poll(&mut self) -> Result<Vec<Message>> {
+        let repository = self.repository().await?;
+        let mut messages = Vec::new();
+        loop {
+            let page = repository.page(&self.talker, self.last_seq, PAGE_SIZE).await?;
+            let full = page.len() == PAGE_SIZE as usize;
+            if let Some(last) = page.last() {
+                self.last_seq = Some(last.seq);
+            }
+            messages.extend(page);
+            if !full {
+                return Ok(messages);
+            }
+        }
+    }
+
+    async fn repository(&self) -> Result<MessageRepository> {
+        open_repository(&self.work_dir, self.self_wxid.as_deref()).await
+    }
+}
+
+/// 跟踪所有会话的新消息
+pub struct NewMessageScanner {
+    work_dir: PathBuf,
+    self_wxid: Option<String>,
+    /// 会话到已读取的最后消息时间和 `sort_seq`
+    seen: HashMap<String, (DateTime<Utc>, Option<i64>)>,
+}
+
+impl NewMessageScanner {
+    /// 跟踪工作目录中的所有会话
+    pub fn new(work_dir: &Path) -> Self {
+        Self {
+            work_dir: work_dir.to_path_buf(),
+            self_wxid: None,
+            seen: HashMap::new(),
+        }
+    }
+
+    /// 当前账号的 wxid，用于标记自己发送的消息
+    pub fn with_self_wxid(mut self, wxid: impl Into<String>) -> Self {
+        self.self_wxid = Some(wxid.into());
+        self
+    }
+
+    /// 跟踪的工作目录
+    pub fn work_dir(&self) -> &Path {
+        &self.work_dir
+    }
+
+    /// 记录各会话当前的最后一条消息，之后的 [`scan`](Self::scan) 只返回此后的消息
+    pub async fn prime(&mut self) -> Result<()> {
+        let sessions = SessionRepository::open(&self.work_dir).await?;
+        let repository = self.repository().await?;
+        self.seen.clear();
+        for session in sessions.all() {
+            let latest = repository.window(&session.username, None, 0, 0).await?;
+            let seq = latest.messages.last().map(|m| m.seq);
+            self.seen.insert(session.username.clone(), (session.last_message_time, seq));
+        }
+        Ok(())
+    }
+
+    /// 读取最后消息时间变化的会话中新增的消息，按时间排序并填写会话和发送者名称
+    pub async fn scan(&mut self) -> Result<Vec<Message>> {
+        let sessions = SessionRepository::open(&self.work_dir).await?;
+        let repository = self.repository().await?;
+        let mut messages = Vec::new();
+        for session in sessions.all() {
+            let previous = self.seen.get(&session.username).copied();
+            if previous.is_some_and(|(time, _)| time >= session.last_message_time) {
+                continue;
+            }
+            let mut last_seq = previous.and_then(|(_, seq)| seq);
+            loop {
+                let page = repository.page(&session.username, last_seq, PAGE_SIZE).await?;
+                let full = page.len() == PAGE_SIZE as usize;
+                if let Some(last) = page.last() {
+                    last_seq = Some(last.seq);
+                }
+                messages.extend(page);
+                if !full {
+                    break;
+                }
+            }
+            self.seen.insert(session.username.clone(), (session.last_message_time, last_seq));
+        }
+        if messages.is_empty() {
+            return Ok(messages);
+        }
+
+        let contacts = ContactRepository::open(&self.work_dir).await.unwrap_or_default();
+        let name = |wxid: &str| contacts.display_name(wxid).map(str::to_string);
+        for message in &mut messages {
+            message.talker_name = name(&message.talker);
+            message.sender_name = name(&message.sender);
+        }
+        messages.sort_by_key(|m| m.time);
+        Ok(messages)
+    }
+
+    async fn repository(&self) -> Result<MessageRepository> {
+        open_repository(&self.work_dir, self.self_wxid.as_deref()).await
+    }
+}
+
+/// 打开工作目录中的消息分片
+async fn open_repository(work_dir: &Path, self_wxid: Option<&str>) -> Result<MessageRepository> {
+    let repository = MessageRepository::open(work_dir).await?;
+    Ok(match self_wxid {
+        Some(wxid) => repository.with_self_wxid(wxid),
+        None => repository,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wechat::db::writer::{ImportedMessage, WorkDirWriter};
+
+    /// 以 `count` 条消息重新生成工作目录，模拟刷新后替换的分片
+    async fn write_messages(dir: &Path, count: i64) {
+        let mut writer = WorkDirWriter::create(dir).await.unwrap();
+        for i in 0..count {
+            let message = ImportedMessage {
+                create_time: 1000 + i,
+                msg_type: 1,
+                sender: "wxid_friend".to_string(),
+                content: format!("消息{}", i),
+                ..Default::default()
+            };
+            writer.insert_message("wxid_friend", &message).await.unwrap();
+        }
+        writer.finish(&[]).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tail_returns_new_messages() {
+        let dir = tempfile::tempdir().unwrap();
+        write_messages(dir.path(), 3).await;
+
+        let mut tail = ConversationTail::new(dir.path(), "wxid_friend");
+        let backlog = tail.backlog(2).await.unwrap();
+        assert_eq!(backlog.iter().map(|m| m.content.as_str()).collect::<Vec<_>>(), ["消息1", "消息2"]);
+        assert_eq!(tail.last_seq(), Some(1_002_000));
+        assert!(tail.poll().await.unwrap().is_empty());
+
+        write_messages(dir.path(), 5).await;
+        let new = tail.poll().await.unwrap();
+        assert_eq!(new.iter().map(|m| m.content.as_str()).collect::<Vec<_>>(), ["消息3", "消息4"]);
+        assert!(tail.poll().await.unwrap().is_empty());
+
+        let mut empty = ConversationTail::new(dir.path(), "wxid_friend");
+        assert!(empty.backlog(0).await.unwrap().is_empty());
+        assert_eq!(empty.last_seq(), Some(1_004_000));
+    }
+
+    #[tokio::test]
+    async fn test_scanner_returns_new_messages() {
+        let dir = tempfile::tempdir().unwrap();
+        write_messages(dir.path(), 3).await;
+
+        let mut scanner = NewMessageScanner::new(dir.path()).with_self_wxid("wxid_me");
+        scanner.prime().await.unwrap();
+        assert!(scanner.scan().await.unwrap().is_empty());
+
+        write_messages(dir.path(), 5).await;
+        let new = scanner.scan().await.unwrap();
+        assert_eq!(new.iter().map(|m| m.content.as_str()).collect::<Vec<_>>(), ["消息3", "消息4"]);
+        assert!(new.iter().all(|m| m.talker == "wxid_friend" && !m.is_self));
+        assert!(scanner.scan().await.unwrap().is_empty());
+    }
+}
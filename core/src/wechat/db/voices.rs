@@ -0,0 +1,184 @@
+//! 语音数据读取
+//!
+//! 4.x 的语音不在文件系统中，而是以 BLOB 保存在 `message/media_N.db` 的 `VoiceInfo` 表：
+//! 会话保存为该分片 `Name2Id` 表的行号，`voice_data` 为 SILK 编码的音频。
+//! 语音消息与 `VoiceInfo` 之间按会话和发送时间对应。
+
+use sqlx::Row;
+use std::path::Path;
+
+use super::messages::MESSAGE_DB_DIR;
+use super::{SqlValue, SqliteDataSource};
+use crate::errors::Result;
+
+/// 解密输出中媒体数据库的文件名前缀
+pub const MEDIA_DB_PREFIX: &str = "decrypted_media_";
+
+/// 关联会话后的语音列
+const VOICE_SELECT: &str = "SELECT n.user_name AS talker, v.create_time, v.local_id \
+                            FROM VoiceInfo v JOIN Name2Id n ON n.rowid = v.chat_name_id";
+
+/// 一条语音
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VoiceEntry {
+    /// 会话用户名
+    pub talker: String,
+    /// 发送时间（秒）
+    pub create_time: i64,
+    /// 在会话消息表中的 `local_id`
+    pub local_id: i64,
+}
+
+/// 语音仓库
+#[derive(Default)]
+pub struct VoiceRepository {
+    shards: Vec<SqliteDataSource>,
+}
+
+impl VoiceRepository {
+    /// 打开工作目录下的所有媒体分片，没有分片时为空仓库
+    pub async fn open(work_dir: &Path) -> Result<Self> {
+        let dir = work_dir.join(MESSAGE_DB_DIR);
+        let mut paths: Vec<(u32, std::path::PathBuf)> = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter_map(|path| {
+                    let name = path.file_name()?.to_str()?;
+                    let index = name.strip_prefix(MEDIA_DB_PREFIX)?.strip_suffix(".db")?.parse().ok()?;
+                    Some((index, path))
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        paths.sort();
+
+        let mut shards = Vec::with_capacity(paths.len());
+        for (_, path) in paths {
+            shards.push(SqliteDataSource::open(&path).await?);
+        }
+        Ok(Self { shards })
+    }
+
+    /// 分片数量
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// 列出语音，`talker` 为 `None` 时列出所有会话，按会话和时间排序
+    pub async fn list(&self, talker: Option<&str>) -> Result<Vec<VoiceEntry>> {
+        let (sql, params) = match talker {
+            Some(talker) => (
+                format!("{} WHERE n.user_name = ?", VOICE_SELECT),
+                vec![SqlValue::Text(talker.to_string())],
+            ),
+            None => (VOICE_SELECT.to_string(), Vec::new()),
+        };
+        let mut entries = Vec::new();
+        for shard in &self.shards {
+            for row in shard.fetch_rows(&sql, &params).await? {
+                entries.push(VoiceEntry {
+                    talker: row.try_get("talker").unwrap_or_default(),
+                    create_time: row.try_get("create_time").unwrap_or_default(),
+                    local_id: row.try_get("local_id").unwrap_or_default(),
+                });
+            }
+        }
+        entries.sort_by(|a, b| (&a.talker, a.create_time, a.local_id).cmp(&(&b.talker, b.create_time, b.local_id)));
+        Ok(entries)
+    }
+
+    /// 读取语音数据
+    pub async fn data(&self, entry: &VoiceEntry) -> Result<Option<Vec<u8>>> {
+        let sql = "SELECT v.voice_data FROM VoiceInfo v JOIN Name2Id n ON n.rowid = v.chat_name_id \
+                   WHERE n.user_name = ? AND v.create_time = ? AND v.local_id = ? LIMIT 1";
+        self.first_blob(
+            sql,
+            &[
+                SqlValue::Text(entry.talker.clone()),
+                SqlValue::Integer(entry.create_time),
+                SqlValue::Integer(entry.local_id),
+            ],
+        )
+        .await
+    }
+
+    /// 按会话和发送时间查找语音消息的数据
+    pub async fn find(&self, talker: &str, create_time: i64) -> Result<Option<Vec<u8>>> {
+        let sql = "SELECT v.voice_data FROM VoiceInfo v JOIN Name2Id n ON n.rowid = v.chat_name_id \
+                   WHERE n.user_name = ? AND v.create_time = ? LIMIT 1";
+        self.first_blob(sql, &[SqlValue::Text(talker.to_string()), SqlValue::Integer(create_time)])
+            .await
+    }
+
+    /// 各分片中第一个非空的 `voice_data`
+    async fn first_blob(&self, sql: &str, params: &[SqlValue]) -> Result<Option<Vec<u8>>> {
+        for shard in &self.shards {
+            for row in shard.fetch_rows(sql, params).await? {
+                if let Ok(Some(data)) = row.try_get::<Option<Vec<u8>>, _>("voice_data") {
+                    if !data.is_empty() {
+                        return Ok(Some(data));
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use sqlx::sqlite::SqliteConnectOptions;
+    use sqlx::ConnectOptions;
+
+    /// 语音测试数据的内容
+    pub(crate) const VOICE_DATA: &[u8] = b"\x02#!SILK_V3voice";
+
+    /// 创建媒体分片，`wxid_friend` 有两条语音，与消息测试数据中的语音消息时间一致
+    pub(crate) async fn create_media_db(work_dir: &Path) {
+        let dir = work_dir.join(MESSAGE_DB_DIR);
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut conn = SqliteConnectOptions::new()
+            .filename(dir.join(format!("{}0.db", MEDIA_DB_PREFIX)))
+            .create_if_missing(true)
+            .connect()
+            .await
+            .unwrap();
+        for sql in [
+            "CREATE TABLE Name2Id (user_name TEXT)",
+            "INSERT INTO Name2Id (rowid, user_name) VALUES (1, 'wxid_friend'), (2, '123@chatroom')",
+            "CREATE TABLE VoiceInfo (chat_name_id INTEGER, create_time INTEGER, local_id INTEGER, \
+             svr_id INTEGER, voice_data BLOB, data_index TEXT)",
+        ] {
+            sqlx::query(sql).execute(&mut conn).await.unwrap();
+        }
+        for (chat, create_time, local_id) in [(1, 1_700_000_003, 4), (1, 1_700_000_100, 9), (2, 1_700_000_200, 1)] {
+            sqlx::query("INSERT INTO VoiceInfo VALUES (?, ?, ?, 0, ?, '0')")
+                .bind(chat)
+                .bind(create_time)
+                .bind(local_id)
+                .bind(VOICE_DATA)
+                .execute(&mut conn)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_and_find_voices() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(VoiceRepository::open(dir.path()).await.unwrap().shard_count(), 0);
+        create_media_db(dir.path()).await;
+        let repo = VoiceRepository::open(dir.path()).await.unwrap();
+
+        let all = repo.list(None).await.unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].talker, "123@chatroom");
+        let friend = repo.list(Some("wxid_friend")).await.unwrap();
+        assert_eq!(friend.iter().map(|v| v.local_id).collect::<Vec<_>>(), [4, 9]);
+        assert_eq!(repo.data(&friend[1]).await.unwrap().as_deref(), Some(VOICE_DATA));
+
+        assert_eq!(repo.find("wxid_friend", 1_700_000_003).await.unwrap().as_deref(), Some(VOICE_DATA));
+        assert_eq!(repo.find("wxid_friend", 1_700_000_004).await.unwrap(), None);
+    }
+}
@@ -0,0 +1,230 @@
+//! 导入数据的写入
+//!
+//! 微信电脑版备份、iOS 备份等来源的数据结构各不相同，导入时统一按 4.x 解密输出的目录结构写入
+//! 消息、联系人和会话数据库，之后的导出、搜索和 HTTP 接口无需区分数据来源。
+
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{ConnectOptions, Connection, SqliteConnection};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::contacts::CONTACT_DB_PATH;
+use super::messages::{message_table, MESSAGE_DB_DIR};
+use super::sessions::SESSION_DB_PATH;
+use crate::errors::{DatabaseError, Result};
+
+/// 导入的消息写入的分片
+const MESSAGE_DB_FILE: &str = "decrypted_message_0.db";
+
+/// 待写入的消息
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportedMessage {
+    /// 会话内的排序序号，为 0 时按发送时间生成
+    pub seq: i64,
+    /// 发送时间（秒）
+    pub create_time: i64,
+    pub msg_type: i64,
+    pub sub_type: i64,
+    /// 发送者 wxid
+    pub sender: String,
+    /// 是否为自己发送
+    pub is_sender: bool,
+    /// 消息内容，群聊中他人发送的消息与 4.x 一致带 `发送者:\n` 前缀
+    pub content: String,
+}
+
+/// 待写入的联系人
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportedContact {
+    pub username: String,
+    pub nickname: Option<String>,
+    pub remark: Option<String>,
+}
+
+/// 会话最后一条消息，用于生成会话列表
+#[derive(Debug, Clone, Default)]
+struct LastMessage {
+    time: i64,
+    sender: String,
+    summary: String,
+}
+
+/// 按 4.x 解密输出的目录结构写入导入的数据
+pub struct WorkDirWriter {
+    output: PathBuf,
+    conn: SqliteConnection,
+    /// 用户名到 `Name2Id` 行号
+    names: HashMap<String, i64>,
+    /// 会话到已创建的消息表
+    tables: HashMap<String, String>,
+    last_messages: HashMap<String, LastMessage>,
+    messages: u64,
+}
+
+impl WorkDirWriter {
+    /// 在 `output` 下创建消息数据库，已有的导入结果被覆盖
+    pub async fn create(output: &Path) -> Result<Self> {
+        let mut conn = create_database(&output.join(MESSAGE_DB_DIR).join(MESSAGE_DB_FILE)).await?;
+        sqlx::query("CREATE TABLE Name2Id (user_name TEXT)")
+            .execute(&mut conn)
+            .await
+            .map_err(DatabaseError::SqlError)?;
+        sqlx::query("BEGIN").execute(&mut conn).await.map_err(DatabaseError::SqlError)?;
+        Ok(Self {
+            output: output.to_path_buf(),
+            conn,
+            names: HashMap::new(),
+            tables: HashMap::new(),
+            last_messages: HashMap::new(),
+            messages: 0,
+        })
+    }
+
+    /// 已写入的消息数
+    pub fn message_count(&self) -> u64 {
+        self.messages
+    }
+
+    /// 写入会话 `talker` 的一条消息
+    pub async fn insert_message(&mut self, talker: &str, message: &ImportedMessage) -> Result<()> {
+        let table = self.table(talker).await?;
+        let sender_id = self.name_id(&message.sender).await?;
+        let seq = match message.seq {
+            0 => message.create_time * 1000,
+            seq => seq,
+        };
+        // 与 4.x 一致，类型的高32位为子类型
+        let local_type = message.msg_type | (message.sub_type << 32);
+        let insert = format!(
+            "INSERT INTO {} (sort_seq, local_type, real_sender_id, create_time, status, message_content) \
+             VALUES (?, ?, ?, ?, ?, ?)",
+            table
+        );
+        sqlx::query(&insert)
+            .bind(seq)
+            .bind(local_type)
+            .bind(sender_id)
+            .bind(message.create_time)
+            .bind(if message.is_sender { 2 } else { 3 })
+            .bind(&message.content)
+            .execute(&mut self.conn)
+            .await
+            .map_err(DatabaseError::SqlError)?;
+
+        let last = self.last_messages.entry(talker.to_string()).or_default();
+        if message.create_time >= last.time {
+            *last = LastMessage {
+                time: message.create_time,
+                sender: message.sender.clone(),
+                summary: message.content.clone(),
+            };
+        }
+        self.messages += 1;
+        Ok(())
+    }
+
+    /// 提交消息，写入联系人和会话列表；会话按最后一条消息排序
+    pub async fn finish(mut self, contacts: &[ImportedContact]) -> Result<u64> {
+        sqlx::query("COMMIT").execute(&mut self.conn).await.map_err(DatabaseError::SqlError)?;
+        self.conn.close().await.map_err(DatabaseError::SqlError)?;
+        write_contacts(&self.output.join(CONTACT_DB_PATH), contacts).await?;
+        write_sessions(&self.output.join(SESSION_DB_PATH), &self.last_messages).await?;
+        Ok(self.messages)
+    }
+
+    /// 用户名在 `Name2Id` 中的行号，不存在时插入
+    async fn name_id(&mut self, name: &str) -> Result<i64> {
+        if let Some(id) = self.names.get(name) {
+            return Ok(*id);
+        }
+        let id = sqlx::query("INSERT INTO Name2Id (user_name) VALUES (?)")
+            .bind(name)
+            .execute(&mut self.conn)
+            .await
+            .map_err(DatabaseError::SqlError)?
+            .last_insert_rowid();
+        self.names.insert(name.to_string(), id);
+        Ok(id)
+    }
+
+    /// 会话的消息表，不存在时创建
+    async fn table(&mut self, talker: &str) -> Result<String> {
+        if let Some(table) = self.tables.get(talker) {
+            return Ok(table.clone());
+        }
+        let table = message_table(talker);
+        let create = format!(
+            "CREATE TABLE {} (local_id INTEGER PRIMARY KEY, sort_seq INTEGER, local_type INTEGER, \
+             real_sender_id INTEGER, create_time INTEGER, status INTEGER, message_content)",
+            table
+        );
+        sqlx::query(&create).execute(&mut self.conn).await.map_err(DatabaseError::SqlError)?;
+        self.tables.insert(talker.to_string(), table.clone());
+        Ok(table)
+    }
+}
+
+/// 创建（覆盖）输出数据库
+async fn create_database(path: &Path) -> Result<SqliteConnection> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true)
+        .connect()
+        .await
+        .map_err(|e| DatabaseError::ConnectionFailed(e.to_string()).into())
+}
+
+/// 按 4.x 联系人数据库的结构写入联系人
+async fn write_contacts(path: &Path, contacts: &[ImportedContact]) -> Result<()> {
+    let mut conn = create_database(path).await?;
+    let mut tx = conn.begin().await.map_err(DatabaseError::SqlError)?;
+    sqlx::query("CREATE TABLE contact (username TEXT, nick_name TEXT, remark TEXT)")
+        .execute(&mut *tx)
+        .await
+        .map_err(DatabaseError::SqlError)?;
+    for contact in contacts {
+        sqlx::query("INSERT INTO contact (username, nick_name, remark) VALUES (?, ?, ?)")
+            .bind(&contact.username)
+            .bind(&contact.nickname)
+            .bind(&contact.remark)
+            .execute(&mut *tx)
+            .await
+            .map_err(DatabaseError::SqlError)?;
+    }
+    tx.commit().await.map_err(DatabaseError::SqlError)?;
+    Ok(())
+}
+
+/// 按 4.x 会话数据库的结构写入会话列表，排序时间取最后一条消息
+async fn write_sessions(path: &Path, last_messages: &HashMap<String, LastMessage>) -> Result<()> {
+    let mut conn = create_database(path).await?;
+    let mut tx = conn.begin().await.map_err(DatabaseError::SqlError)?;
+    sqlx::query(
+        "CREATE TABLE SessionTable (username TEXT, unread_count INTEGER, sort_timestamp INTEGER, \
+         summary TEXT, last_msg_sender TEXT, last_sender_display_name TEXT)",
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(DatabaseError::SqlError)?;
+    for (username, last) in last_messages {
+        sqlx::query(
+            "INSERT INTO SessionTable (username, unread_count, sort_timestamp, summary, last_msg_sender, \
+             last_sender_display_name) VALUES (?, 0, ?, ?, ?, '')",
+        )
+        .bind(username)
+        .bind(last.time)
+        .bind(&last.summary)
+        .bind(&last.sender)
+        .execute(&mut *tx)
+        .await
+        .map_err(DatabaseError::SqlError)?;
+    }
+    tx.commit().await.map_err(DatabaseError::SqlError)?;
+    Ok(())
+}
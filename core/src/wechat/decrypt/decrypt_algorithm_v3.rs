@@ -0,0 +1,326 @@
+//! 微信V3版本解密器实现
+//!
+//! 微信3.x 的数据库使用 SQLCipher 3 的参数：PBKDF2-HMAC-SHA1 迭代 64000 次派生密钥，
+//! 页面大小 1024 字节，每页末尾保留 IV(16) + HMAC-SHA1(20)，按AES块大小对齐到 48 字节。
+
+use async_trait::async_trait;
+use std::path::Path;
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tracing::{debug, info, warn};
+use zeroize::Zeroize;
+
+use crate::errors::{Result, WeChatError};
+use crate::wechat::datadir::open_read_only_async;
+use super::{
+    decrypt_algorithm_v4::{available_memory_mb, choose_mode},
+    decrypt_common::{
+        derive_keys_v3, is_database_encrypted, decrypt_page, verify_page_hmac,
+        SALT_SIZE, SQLITE_HEADER,
+    },
+    parallel_decrypt::{MemoryMonitor, ParallelDecryptor, ParallelDecryptConfig},
+    DecryptConfig, Decryptor, ProgressCallback,
+};
+
+/// V3版本解密器
+pub struct V3Decryptor {
+    config: DecryptConfig,
+    /// 是否启用并行处理，`None` 时按文件大小和系统资源自动选择
+    enable_parallel: Option<bool>,
+    parallel_config: ParallelDecryptConfig,
+    /// 共享的内存监控器，未设置时每次并行解密按 `parallel_config` 新建
+    memory_monitor: Option<MemoryMonitor>,
+}
+
+impl V3Decryptor {
+    /// 创建新的V3解密器（自动选择并行或顺序模式）
+    pub fn new() -> Self {
+        Self {
+            config: DecryptConfig::v3(),
+            enable_parallel: None,
+            parallel_config: ParallelDecryptConfig::auto_configure(),
+            memory_monitor: None,
+        }
+    }
+
+    /// 创建新的V3解密器（禁用并行）
+    pub fn new_sequential() -> Self {
+        Self {
+            enable_parallel: Some(false),
+            ..Self::new()
+        }
+    }
+
+    /// 强制启用或禁用并行处理
+    pub fn set_parallel_enabled(&mut self, enabled: bool) {
+        self.enable_parallel = Some(enabled);
+    }
+
+    /// 设置共享的内存监控器，用于统计多个文件同时解密时的内存峰值
+    pub fn set_memory_monitor(&mut self, monitor: MemoryMonitor) {
+        self.memory_monitor = Some(monitor);
+    }
+
+    /// 设置并行配置
+    pub fn set_parallel_config(&mut self, config: ParallelDecryptConfig) {
+        self.parallel_config = config;
+    }
+
+    /// 获取并行配置
+    pub fn parallel_config(&self) -> &ParallelDecryptConfig {
+        &self.parallel_config
+    }
+
+    /// 读取文件大小和第一页
+    async fn read_db_info(&self, file_path: &Path) -> Result<(u64, Vec<u8>)> {
+        let mut file = open_read_only_async(file_path).await
+            .map_err(|e| WeChatError::DecryptionFailed(format!("打开文件失败: {}", e)))?;
+
+        let file_size = file.metadata().await
+            .map_err(|e| WeChatError::DecryptionFailed(format!("获取文件信息失败: {}", e)))?
+            .len();
+
+        let mut first_page = vec![0u8; self.config.page_size];
+        let bytes_read = file.read(&mut first_page).await
+            .map_err(|e| WeChatError::DecryptionFailed(format!("读取第一页失败: {}", e)))?;
+        first_page.truncate(bytes_read);
+
+        Ok((file_size, first_page))
+    }
+
+    /// 解密数据库的核心实现
+    async fn decrypt_database_impl(
+        &self,
+        input_path: &Path,
+        output_path: &Path,
+        key: &[u8],
+        progress_callback: Option<ProgressCallback>,
+    ) -> Result<()> {
+        let parallel = match self.enable_parallel {
+            Some(enabled) => enabled,
+            None => {
+                let file_size = tokio::fs::metadata(input_path).await.map(|m| m.len()).unwrap_or_default();
+                let choice = choose_mode(file_size, num_cpus::get(), available_memory_mb(), &self.parallel_config);
+                info!(
+                    "⚙️  自动选择{}模式: {}",
+                    if choice.parallel { "并行" } else { "顺序" },
+                    choice.reason
+                );
+                choice.parallel
+            }
+        };
+        if parallel {
+            info!("🚀 使用并行模式解密V3数据库: {:?} -> {:?}", input_path, output_path);
+            let mut parallel_decryptor = ParallelDecryptor::new(self.config.clone(), self.parallel_config.clone());
+            if let Some(monitor) = &self.memory_monitor {
+                parallel_decryptor = parallel_decryptor.with_memory_monitor(monitor.clone());
+            }
+            parallel_decryptor
+                .decrypt_database_parallel(input_path, output_path, key, progress_callback)
+                .await
+        } else {
+            self.decrypt_database_sequential(input_path, output_path, key, progress_callback).await
+        }
+    }
+
+    /// 顺序解密数据库
+    async fn decrypt_database_sequential(
+        &self,
+        input_path: &Path,
+        output_path: &Path,
+        key: &[u8],
+        progress_callback: Option<ProgressCallback>,
+    ) -> Result<()> {
+        info!("📝 使用顺序模式解密V3数据库: {:?} -> {:?}", input_path, output_path);
+
+        let (file_size, first_page) = self.read_db_info(input_path).await?;
+        let total_pages = (file_size as usize).div_ceil(self.config.page_size);
+        debug!("文件大小: {} 字节, 总页数: {}", file_size, total_pages);
+
+        if !is_database_encrypted(&first_page) {
+            return Err(WeChatError::DecryptionFailed("数据库已经解密".to_string()).into());
+        }
+        if first_page.len() < SALT_SIZE {
+            return Err(WeChatError::DecryptionFailed("第一页数据不完整".to_string()).into());
+        }
+
+        let mut derived_keys = derive_keys_v3(key, &first_page[..SALT_SIZE])?;
+        if !verify_page_hmac(&first_page, &derived_keys.mac_key, 0, &self.config)? {
+            derived_keys.zeroize();
+            return Err(WeChatError::DecryptionFailed("密钥验证失败".to_string()).into());
+        }
+        info!("密钥验证成功，开始解密");
+
+        let mut input_file = open_read_only_async(input_path).await
+            .map_err(|e| WeChatError::DecryptionFailed(format!("打开输入文件失败: {}", e)))?;
+        let mut output_file = File::create(output_path).await
+            .map_err(|e| WeChatError::DecryptionFailed(format!("创建输出文件失败: {}", e)))?;
+
+        // 第一页的 Salt 位置写回 SQLite 头
+        output_file.write_all(SQLITE_HEADER).await
+            .map_err(|e| WeChatError::DecryptionFailed(format!("写入SQLite头失败: {}", e)))?;
+
+        let mut processed_pages = 0u64;
+        for page_num in 0..total_pages {
+            let mut page_data = vec![0u8; self.config.page_size];
+            let bytes_read = input_file.read(&mut page_data).await
+                .map_err(|e| WeChatError::DecryptionFailed(format!("读取页面 {} 失败: {}", page_num, e)))?;
+            if bytes_read == 0 {
+                break;
+            }
+            page_data.truncate(bytes_read);
+
+            let page = if page_data.iter().all(|&b| b == 0) {
+                debug!("跳过空页面 {}", page_num);
+                page_data
+            } else {
+                match decrypt_page(
+                    &page_data,
+                    &derived_keys.enc_key,
+                    &derived_keys.mac_key,
+                    page_num as u64,
+                    &self.config,
+                ) {
+                    Ok(decrypted) => decrypted,
+                    Err(e) => {
+                        warn!("页面 {} 解密失败: {}, 跳过", page_num, e);
+                        page_data
+                    }
+                }
+            };
+            output_file.write_all(&page).await
+                .map_err(|e| WeChatError::DecryptionFailed(format!("写入页面 {} 失败: {}", page_num, e)))?;
+
+            processed_pages += 1;
+            if let Some(ref callback) = progress_callback {
+                callback(processed_pages, total_pages as u64);
+            }
+        }
+        output_file.flush().await?;
+
+        derived_keys.zeroize();
+        info!("V3数据库解密完成，处理了 {} 页", processed_pages);
+        Ok(())
+    }
+}
+
+impl Default for V3Decryptor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Decryptor for V3Decryptor {
+    async fn decrypt_database(
+        &self,
+        input_path: &Path,
+        output_path: &Path,
+        key: &[u8],
+    ) -> Result<()> {
+        self.decrypt_database_impl(input_path, output_path, key, None).await
+    }
+
+    async fn decrypt_database_with_progress(
+        &self,
+        input_path: &Path,
+        output_path: &Path,
+        key: &[u8],
+        progress_callback: Option<ProgressCallback>,
+    ) -> Result<()> {
+        self.decrypt_database_impl(input_path, output_path, key, progress_callback).await
+    }
+
+    async fn validate_key(
+        &self,
+        db_path: &Path,
+        key: &[u8],
+    ) -> Result<bool> {
+        debug!("验证V3密钥");
+
+        let (_, first_page) = self.read_db_info(db_path).await?;
+        if !is_database_encrypted(&first_page) || first_page.len() < self.config.page_size {
+            return Ok(false);
+        }
+
+        let mut derived_keys = match derive_keys_v3(key, &first_page[..SALT_SIZE]) {
+            Ok(keys) => keys,
+            Err(_) => return Ok(false),
+        };
+        let result = verify_page_hmac(&first_page, &derived_keys.mac_key, 0, &self.config)
+            .unwrap_or(false);
+        derived_keys.zeroize();
+
+        debug!("V3密钥验证结果: {}", result);
+        Ok(result)
+    }
+
+    fn config(&self) -> &DecryptConfig {
+        &self.config
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::wechat::decrypt::decrypt_common::{AES_BLOCK_SIZE, IV_SIZE};
+    use aes::cipher::{block_padding::NoPadding, BlockEncryptMut, KeyIvInit};
+    use hmac::{Hmac, Mac};
+    use sha1::Sha1;
+
+    /// 按 SQLCipher 3 的格式加密明文页面，第一页的前16字节替换为 Salt
+    pub(crate) fn encrypt_v3(plain: &[u8], key: &[u8], salt: &[u8; SALT_SIZE]) -> Vec<u8> {
+        let config = DecryptConfig::v3();
+        let keys = derive_keys_v3(key, salt).unwrap();
+        let data_end = config.page_size - config.reserve_size;
+        let mut output = Vec::with_capacity(plain.len());
+        for (page_num, page) in plain.chunks(config.page_size).enumerate() {
+            let offset = if page_num == 0 { SALT_SIZE } else { 0 };
+            let iv = [page_num as u8 + 1; IV_SIZE];
+            let mut encrypted = page[offset..data_end].to_vec();
+            cbc::Encryptor::<aes::Aes256>::new(keys.enc_key.as_slice().into(), &iv.into())
+                .encrypt_padded_mut::<NoPadding>(&mut encrypted, data_end - offset)
+                .unwrap();
+
+            let mut mac = Hmac::<Sha1>::new_from_slice(&keys.mac_key).unwrap();
+            mac.update(&encrypted);
+            mac.update(&iv);
+            mac.update(&(page_num as u32 + 1).to_le_bytes());
+
+            let mut encrypted_page = if page_num == 0 { salt.to_vec() } else { Vec::new() };
+            encrypted_page.extend_from_slice(&encrypted);
+            encrypted_page.extend_from_slice(&iv);
+            encrypted_page.extend_from_slice(&mac.finalize().into_bytes());
+            encrypted_page.resize(config.page_size, 0);
+            output.extend_from_slice(&encrypted_page);
+        }
+        assert_eq!((data_end - SALT_SIZE) % AES_BLOCK_SIZE, 0);
+        output
+    }
+
+    #[tokio::test]
+    async fn test_decrypt_v3_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = [0x5au8; 32];
+        let page_size = DecryptConfig::v3().page_size;
+        let mut plain: Vec<u8> = (0..page_size * 3).map(|i| (i * 7 % 251) as u8).collect();
+        plain[..SQLITE_HEADER.len()].copy_from_slice(SQLITE_HEADER);
+
+        let input = dir.path().join("MSG0.db");
+        std::fs::write(&input, encrypt_v3(&plain, &key, &[0x11; SALT_SIZE])).unwrap();
+
+        let decryptor = V3Decryptor::new_sequential();
+        assert!(decryptor.validate_key(&input, &key).await.unwrap());
+        assert!(!decryptor.validate_key(&input, &[0u8; 32]).await.unwrap());
+
+        let output = dir.path().join("MSG0_decrypted.db");
+        decryptor.decrypt_database(&input, &output, &key).await.unwrap();
+        let decrypted = std::fs::read(&output).unwrap();
+        assert_eq!(decrypted.len(), plain.len());
+        let data_end = page_size - DecryptConfig::v3().reserve_size;
+        for page in 0..3 {
+            let start = page * page_size;
+            assert_eq!(decrypted[start..start + data_end], plain[start..start + data_end]);
+        }
+    }
+}
@@ -8,29 +8,71 @@ use tracing::{debug, info, warn};
 use zeroize::Zeroize;
 
 use crate::errors::{Result, WeChatError};
+use crate::wechat::datadir::open_read_only_async;
 use super::{
     decrypt_common::{
         derive_keys_v4, is_database_encrypted, decrypt_page, verify_page_hmac,
         SALT_SIZE, SQLITE_HEADER,
     },
-    parallel_decrypt::{ParallelDecryptor, ParallelDecryptConfig},
+    parallel_decrypt::{MemoryMonitor, ParallelDecryptor, ParallelDecryptConfig},
     DecryptConfig, Decryptor, ProgressCallback,
 };
 
+/// 自动模式下启用页面并行解密的最小文件大小（8MB）
+pub const PARALLEL_MIN_FILE_SIZE: u64 = 8 * 1024 * 1024;
+
+/// 自动模式下选择的解密方式及原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModeChoice {
+    /// 是否使用页面并行解密
+    pub parallel: bool,
+    /// 选择原因，用于日志
+    pub reason: String,
+}
+
+/// 根据文件大小和系统资源选择解密方式
+///
+/// 小文件并行收益有限；单核或可用内存低于并行内存上限时退回顺序解密。
+/// `available_mb` 为 `None` 表示无法获取可用内存，此时不按内存限制。
+pub fn choose_mode(file_size: u64, cpu_count: usize, available_mb: Option<u64>, config: &ParallelDecryptConfig) -> ModeChoice {
+    let (parallel, reason) = if file_size < PARALLEL_MIN_FILE_SIZE {
+        (false, format!("文件较小 ({} 字节 < {} 字节)", file_size, PARALLEL_MIN_FILE_SIZE))
+    } else if cpu_count < 2 {
+        (false, "单核 CPU".to_string())
+    } else if let Some(available) = available_mb.filter(|&mb| mb < config.max_memory_mb as u64) {
+        (false, format!("可用内存不足 ({}MB < {}MB)", available, config.max_memory_mb))
+    } else {
+        (true, format!("文件 {} 字节，{} 个 CPU 核心", file_size, cpu_count))
+    };
+    ModeChoice { parallel, reason }
+}
+
+/// 当前可用内存 (MB)
+pub(super) fn available_memory_mb() -> Option<u64> {
+    let mut system = sysinfo::System::new();
+    system.refresh_memory();
+    let available = system.available_memory();
+    (available > 0).then_some(available / 1024 / 1024)
+}
+
 /// V4版本解密器
 pub struct V4Decryptor {
     config: DecryptConfig,
-    enable_parallel: bool,
+    /// 是否启用并行处理，`None` 时按文件大小和系统资源自动选择
+    enable_parallel: Option<bool>,
     parallel_config: ParallelDecryptConfig,
+    /// 共享的内存监控器，未设置时每次并行解密按 `parallel_config` 新建
+    memory_monitor: Option<MemoryMonitor>,
 }
 
 impl V4Decryptor {
-    /// 创建新的V4解密器
+    /// 创建新的V4解密器（自动选择并行或顺序模式）
     pub fn new() -> Self {
         Self {
             config: DecryptConfig::v4(),
-            enable_parallel: true,
+            enable_parallel: None,
             parallel_config: ParallelDecryptConfig::auto_configure(),
+            memory_monitor: None,
         }
     }
     
@@ -38,8 +80,9 @@ impl V4Decryptor {
     pub fn new_sequential() -> Self {
         Self {
             config: DecryptConfig::v4(),
-            enable_parallel: false,
+            enable_parallel: Some(false),
             parallel_config: ParallelDecryptConfig::auto_configure(),
+            memory_monitor: None,
         }
     }
     
@@ -47,16 +90,22 @@ impl V4Decryptor {
     pub fn new_with_parallel_config(parallel_config: ParallelDecryptConfig) -> Self {
         Self {
             config: DecryptConfig::v4(),
-            enable_parallel: true,
+            enable_parallel: Some(true),
             parallel_config,
+            memory_monitor: None,
         }
     }
     
-    /// 设置是否启用并行处理
+    /// 强制启用或禁用并行处理
     pub fn set_parallel_enabled(&mut self, enabled: bool) {
-        self.enable_parallel = enabled;
+        self.enable_parallel = Some(enabled);
     }
     
+    /// 设置共享的内存监控器，用于统计多个文件同时解密时的内存峰值
+    pub fn set_memory_monitor(&mut self, monitor: MemoryMonitor) {
+        self.memory_monitor = Some(monitor);
+    }
+
     /// 设置并行配置
     pub fn set_parallel_config(&mut self, config: ParallelDecryptConfig) {
         self.parallel_config = config;
@@ -69,7 +118,7 @@ impl V4Decryptor {
     
     /// 读取数据库文件信息
     async fn read_db_info(&self, file_path: &Path) -> Result<(u64, Vec<u8>)> {
-        let mut file = File::open(file_path).await
+        let mut file = open_read_only_async(file_path).await
             .map_err(|e| WeChatError::DecryptionFailed(format!("打开文件失败: {}", e)))?;
         
         // 获取文件大小
@@ -97,8 +146,21 @@ impl V4Decryptor {
         key: &[u8],
         progress_callback: Option<ProgressCallback>,
     ) -> Result<()> {
-        // 根据配置选择解密方式
-        if self.enable_parallel {
+        // 根据配置选择解密方式，未指定时自动选择
+        let parallel = match self.enable_parallel {
+            Some(enabled) => enabled,
+            None => {
+                let file_size = tokio::fs::metadata(input_path).await.map(|m| m.len()).unwrap_or_default();
+                let choice = choose_mode(file_size, num_cpus::get(), available_memory_mb(), &self.parallel_config);
+                info!(
+                    "⚙️  自动选择{}模式: {}",
+                    if choice.parallel { "并行" } else { "顺序" },
+                    choice.reason
+                );
+                choice.parallel
+            }
+        };
+        if parallel {
             self.decrypt_database_parallel(input_path, output_path, key, progress_callback).await
         } else {
             self.decrypt_database_sequential(input_path, output_path, key, progress_callback).await
@@ -115,10 +177,13 @@ impl V4Decryptor {
     ) -> Result<()> {
         info!("🚀 使用并行模式解密V4数据库: {:?} -> {:?}", input_path, output_path);
         
-        let parallel_decryptor = ParallelDecryptor::new(
+        let mut parallel_decryptor = ParallelDecryptor::new(
             self.config.clone(),
             self.parallel_config.clone(),
         );
+        if let Some(monitor) = &self.memory_monitor {
+            parallel_decryptor = parallel_decryptor.with_memory_monitor(monitor.clone());
+        }
         
         parallel_decryptor.decrypt_database_parallel(
             input_path,
@@ -169,7 +234,7 @@ impl V4Decryptor {
         info!("密钥验证成功，开始解密");
         
         // 6. 打开输入输出文件
-        let mut input_file = File::open(input_path).await
+        let mut input_file = open_read_only_async(input_path).await
             .map_err(|e| WeChatError::DecryptionFailed(format!("打开输入文件失败: {}", e)))?;
         
         let mut output_file = File::create(output_path).await
@@ -313,4 +378,23 @@ impl Decryptor for V4Decryptor {
     fn config(&self) -> &DecryptConfig {
         &self.config
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_choose_mode() {
+        let config = ParallelDecryptConfig::auto_configure();
+        let large = PARALLEL_MIN_FILE_SIZE * 4;
+        assert!(choose_mode(large, 8, Some(8192), &config).parallel);
+        assert!(choose_mode(large, 8, None, &config).parallel);
+
+        let small = choose_mode(4096, 8, Some(8192), &config);
+        assert!(!small.parallel);
+        assert!(small.reason.contains("文件较小"));
+        assert!(!choose_mode(large, 1, Some(8192), &config).parallel);
+        assert!(choose_mode(large, 8, Some(64), &config).reason.contains("可用内存不足"));
+    }
+}
@@ -2,19 +2,70 @@
 
 use crate::errors::Result;
 use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::fs;
 use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
 use tracing::{error, info, warn};
 
-use crate::errors::WeChatError;
+use crate::errors::{MwxDumpError, WeChatError};
+use crate::events::{Event, EventBus};
+use crate::utils::InstanceLock;
+use crate::wechat::datadir::{DbCategory, SourceGuard};
 use crate::wechat::decrypt::{
-    create_decryptor,
+    create_decryptor_with_options,
     decrypt_validator::KeyValidator,
+    manifest::{Change, DecryptManifest, FileFingerprint},
+    parallel_decrypt::MemoryMonitor,
     DecryptVersion,
+    ParallelDecryptConfig,
+    ParallelOptions,
 };
 
+/// 排序输出模式下记录失败文件的文件名，写在输出目录下
+pub const FAILURES_FILE: &str = "failures.json";
+
+/// 目录解密中单个文件的结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileOutcome {
+    /// 相对输入目录的路径
+    pub file: PathBuf,
+    /// 失败原因，成功时为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 一次解密的统计
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecryptReport {
+    /// 待解密的文件数
+    pub files: usize,
+    /// 成功的文件数
+    pub succeeded: usize,
+    /// 失败的文件数
+    pub failed: usize,
+    /// 取消后未处理的文件数
+    pub skipped: usize,
+    /// 增量模式下源文件未变化而跳过的文件数
+    #[serde(default)]
+    pub unchanged: usize,
+    /// 耗时（毫秒）
+    pub elapsed_ms: u64,
+    /// 页面并行解密的内存峰值（字节），只统计页面缓冲区，全部顺序解密时为 0
+    pub peak_memory_bytes: u64,
+    /// 页面并行解密的内存上限 (MB)
+    pub max_memory_mb: usize,
+}
+
+impl DecryptReport {
+    /// 内存峰值 (MB)
+    pub fn peak_memory_mb(&self) -> f64 {
+        self.peak_memory_bytes as f64 / (1024.0 * 1024.0)
+    }
+}
+
 /// 解密处理器
 ///
 /// 负责处理微信数据库文件的解密操作，支持单文件和批量目录解密。
@@ -30,6 +81,22 @@ pub struct DecryptionProcessor {
     threads: usize,
     /// 是否仅验证密钥而不执行解密
     validate_only: bool,
+    /// 取消令牌，触发后不再开始新文件，并清理未完成的输出
+    cancel_token: CancellationToken,
+    /// 目录模式下只解密这些类别的数据库，为空时解密全部
+    categories: Vec<DbCategory>,
+    /// 单个文件内的页面并行解密选项
+    parallel: ParallelOptions,
+    /// 每完成一个文件发布解密进度
+    events: EventBus,
+    /// 目录解密结束后按路径排序输出每个文件的结果，并写入 `failures.json`
+    ordered: bool,
+    /// 指定数据库版本，`None` 时逐个文件自动检测
+    version: Option<DecryptVersion>,
+    /// 目录模式下按输出目录中的清单跳过未变化的文件
+    incremental: bool,
+    /// 增量模式下忽略已有清单，全部重新解密
+    force: bool,
 }
 
 impl DecryptionProcessor {
@@ -74,9 +141,81 @@ impl DecryptionProcessor {
             key,
             threads: thread_count,
             validate_only,
+            cancel_token: CancellationToken::new(),
+            categories: Vec::new(),
+            parallel: ParallelOptions::default(),
+            events: EventBus::new(),
+            ordered: false,
+            version: None,
+            incremental: false,
+            force: false,
         }
     }
 
+    /// 设置取消令牌
+    ///
+    /// 令牌被触发后，已在进行中的文件会等待写入完成，尚未开始的文件将被跳过，
+    /// 未完成的 `.partial` 输出文件会被删除，最终返回 `MwxDumpError::Cancelled`。
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel_token = token;
+        self
+    }
+
+    /// 设置目录模式下要解密的数据库类别
+    ///
+    /// 跳过不需要的全文索引、媒体等大文件可以显著缩短备份时间；单文件模式下忽略并给出警告。
+    pub fn with_categories(mut self, categories: Vec<DbCategory>) -> Self {
+        self.categories = categories;
+        self
+    }
+
+    /// 设置单个文件内的页面并行解密选项
+    ///
+    /// `threads` 控制同时解密的文件数，这里的选项控制每个文件内的页面并发。
+    pub fn with_parallel_options(mut self, options: ParallelOptions) -> Self {
+        self.parallel = options;
+        self
+    }
+
+    /// 设置是否按路径排序输出目录解密的结果
+    ///
+    /// 开启后解密过程中不再按完成顺序逐个报告失败，而是在全部完成后按相对路径排序，
+    /// 逐个输出每个文件的结果，并把失败的文件按同样的顺序写入输出目录下的 `failures.json`
+    /// （全部成功时删除旧的 `failures.json`），便于比较多次运行的结果。
+    pub fn with_ordered_results(mut self, ordered: bool) -> Self {
+        self.ordered = ordered;
+        self
+    }
+
+    /// 指定数据库版本（V3 为微信3.x，V4 为微信4.x），不再自动检测
+    ///
+    /// 密钥仍会按指定版本验证，版本不符时与密钥错误一样报告验证失败。
+    pub fn with_version(mut self, version: Option<DecryptVersion>) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// 设置是否增量解密
+    ///
+    /// 开启后目录解密在输出目录下维护 [`MANIFEST_FILE`](super::manifest::MANIFEST_FILE)，
+    /// 跳过自上次解密后大小、修改时间或内容都未变化且输出仍存在的文件；单文件模式不受影响。
+    pub fn with_incremental(mut self, incremental: bool) -> Self {
+        self.incremental = incremental;
+        self
+    }
+
+    /// 增量模式下忽略已有清单，全部重新解密并重写清单
+    pub fn with_force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    /// 每完成一个文件（无论成败）向事件总线发布 [`Event::DecryptProgress`]
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = events;
+        self
+    }
+
     /// 执行解密操作
     ///
     /// 根据输入路径的类型（文件或目录）自动选择相应的处理方式：
@@ -85,12 +224,15 @@ impl DecryptionProcessor {
     ///
     /// # 返回值
     ///
-    /// * `Ok(())` - 解密操作成功完成
+    /// * `Ok(DecryptReport)` - 解密操作成功完成，返回文件数、耗时和内存峰值等统计
     /// * `Err(...)` - 解密过程中发生错误
     ///
+    /// 同时解密的文件共用一个内存监控器，报告中的内存峰值是整次解密的峰值。
+    ///
     /// # 错误
     ///
-    /// 当输入路径既不是文件也不是目录时，返回 `WeChatError::DecryptionFailed`
+    /// 当输入路径既不是文件也不是目录时，返回 `WeChatError::DecryptionFailed`；
+    /// 输出路径位于输入所属的微信数据目录中时，返回 `WeChatError::OutputInsideDataDir`
     ///
     /// # 示例
     ///
@@ -102,18 +244,42 @@ impl DecryptionProcessor {
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn execute(&self) -> Result<()> {
-        if self.input_path.is_file() {
-            self.handle_single_file_decrypt().await
+    pub async fn execute(&self) -> Result<DecryptReport> {
+        // 输入位于微信数据目录时，拒绝写入该目录
+        if !self.validate_only {
+            SourceGuard::for_input(&self.input_path).check_output(&self.output_path)?;
+        }
+
+        let monitor = self.parallel.memory_monitor.clone().unwrap_or_else(|| {
+            let max_memory_mb = self
+                .parallel
+                .max_memory_mb
+                .unwrap_or_else(|| ParallelDecryptConfig::auto_configure().max_memory_mb);
+            MemoryMonitor::new(max_memory_mb)
+        });
+        let parallel = ParallelOptions {
+            memory_monitor: Some(monitor.clone()),
+            ..self.parallel.clone()
+        };
+        let start_time = std::time::Instant::now();
+        let mut report = if self.input_path.is_file() {
+            self.handle_single_file_decrypt(&parallel).await?
         } else if self.input_path.is_dir() {
-            self.handle_directory_decrypt().await
+            self.handle_directory_decrypt(&parallel).await?
         } else {
-            Err(WeChatError::DecryptionFailed(format!(
+            return Err(WeChatError::DecryptionFailed(format!(
                 "输入路径既不是文件也不是目录: {:?}",
                 self.input_path
             ))
-            .into())
+            .into());
+        };
+        report.elapsed_ms = start_time.elapsed().as_millis() as u64;
+        report.peak_memory_bytes = monitor.peak_usage_bytes();
+        report.max_memory_mb = monitor.max_memory_mb();
+        if !self.validate_only {
+            info!("💾 内存使用峰值: {:.1} MB（上限 {} MB）", report.peak_memory_mb(), report.max_memory_mb);
         }
+        Ok(report)
     }
 
     /// 处理单文件解密
@@ -129,7 +295,7 @@ impl DecryptionProcessor {
     ///
     /// # 返回值
     ///
-    /// * `Ok(())` - 单文件处理成功
+    /// * `Ok(DecryptReport)` - 单文件处理成功
     /// * `Err(...)` - 处理过程中发生错误
     ///
     /// # 错误
@@ -138,15 +304,21 @@ impl DecryptionProcessor {
     /// - 版本检测失败
     /// - 文件解密失败
     /// - 输出目录创建失败
-    async fn handle_single_file_decrypt(&self) -> Result<()> {
+    async fn handle_single_file_decrypt(&self, parallel: &ParallelOptions) -> Result<DecryptReport> {
         info!("📁 单文件解密模式: {:?}", self.input_path);
+        if !self.categories.is_empty() {
+            warn!("⚠️  按类别筛选只对目录输入生效，单文件解密忽略 {:?}", self.category_names());
+        }
 
         let validator = KeyValidator::new();
-        let version = determine_version(&validator, &self.input_path, &self.key).await?;
+        let version = determine_version(&validator, &self.input_path, &self.key, self.version).await?;
 
         if self.validate_only {
             info!("✅ 密钥验证成功！版本: {:?}", version);
-            return Ok(());
+            return Ok(DecryptReport {
+                files: 1,
+                ..Default::default()
+            });
         }
 
         if let Some(parent) = self.output_path.parent() {
@@ -154,8 +326,39 @@ impl DecryptionProcessor {
                 fs::create_dir_all(parent).await?;
             }
         }
+        let lock_dir = self
+            .output_path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let _lock = InstanceLock::acquire(lock_dir)?;
 
-        decrypt_single_file(&self.input_path, &self.output_path, &self.key, version).await
+        // 取消时放弃正在进行的解密，只清理未完成的临时文件，不留下半个数据库
+        let partial = partial_path(&self.output_path);
+        let decrypted = tokio::select! {
+            biased;
+            _ = self.cancel_token.cancelled() => Err(MwxDumpError::Cancelled.into()),
+            result = decrypt_single_file(&self.input_path, &partial, &self.key, version, parallel) => result,
+        };
+        match decrypted {
+            Ok(()) => {
+                fs::rename(&partial, &self.output_path).await?;
+                self.events.publish(Event::DecryptProgress {
+                    file: self.input_path.clone(),
+                    processed: 1,
+                    total: 1,
+                });
+                Ok(DecryptReport {
+                    files: 1,
+                    succeeded: 1,
+                    ..Default::default()
+                })
+            }
+            Err(e) => {
+                remove_partial(&partial).await;
+                Err(e)
+            }
+        }
     }
 
     /// 处理目录批量解密
@@ -179,7 +382,7 @@ impl DecryptionProcessor {
     ///
     /// # 返回值
     ///
-    /// * `Ok(())` - 批量处理成功完成
+    /// * `Ok(DecryptReport)` - 批量处理成功完成
     /// * `Err(...)` - 处理过程中发生错误
     ///
     /// # 错误
@@ -187,7 +390,7 @@ impl DecryptionProcessor {
     /// - 输出路径不是目录
     /// - 文件收集失败
     /// - 密钥验证失败（验证模式）
-    async fn handle_directory_decrypt(&self) -> Result<()> {
+    async fn handle_directory_decrypt(&self, parallel: &ParallelOptions) -> Result<DecryptReport> {
         info!("📁 目录批量解密模式: {:?}", self.input_path);
 
         if !self.output_path.exists() {
@@ -204,36 +407,79 @@ impl DecryptionProcessor {
         }
 
         let files = collect_files_recursively(self.input_path.to_path_buf()).await?;
+        let total = files.len();
+        let all_relative: Vec<PathBuf> = files
+            .iter()
+            .filter_map(|file| file.strip_prefix(&self.input_path).ok().map(Path::to_path_buf))
+            .collect();
+        let files = filter_by_category(&self.input_path, files, &self.categories);
+        if files.len() < total {
+            info!("🗂️  按类别 {:?} 筛选，跳过 {} 个文件", self.category_names(), total - files.len());
+        }
         info!("📊 发现 {} 个文件待处理", files.len());
 
         if self.validate_only {
             info!("✅ 仅验证模式，跳过实际解密");
             if let Some(first_file) = files.first() {
                 let validator = KeyValidator::new();
-                let version = determine_version(&validator, first_file, &self.key).await?;
+                let version = determine_version(&validator, first_file, &self.key, self.version).await?;
                 info!("✅ 密钥对第一个文件验证成功！版本: {:?}", version);
             }
-            return Ok(());
+            return Ok(DecryptReport {
+                files: files.len(),
+                ..Default::default()
+            });
         }
 
+        let _lock = InstanceLock::acquire(&self.output_path)?;
         info!("🚀 使用 {} 个并发线程处理文件", self.threads);
 
+        // 增量模式：按上次的清单判断，成功解密的文件写入新清单
+        let manifest = self.incremental.then(|| {
+            let mut previous = DecryptManifest::load(&self.output_path, &self.key);
+            if self.force {
+                info!("🔁 忽略增量清单，全部重新解密");
+                previous.files.clear();
+            }
+            // 源文件已删除的记录不再保留
+            previous.retain(&all_relative);
+            let updated = Arc::new(std::sync::Mutex::new(previous.clone()));
+            (Arc::new(previous), updated)
+        });
+        let unchanged_count = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
         let semaphore = Arc::new(Semaphore::new(self.threads));
         let success_count = Arc::new(std::sync::atomic::AtomicUsize::new(0));
         let failed_count = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let skipped_count = Arc::new(std::sync::atomic::AtomicUsize::new(0));
         let start_time = std::time::Instant::now();
 
-        let tasks = files.iter().map(|file_path| {
+        let file_count = files.len();
+        let outcomes = Arc::new(std::sync::Mutex::new(Vec::with_capacity(file_count)));
+        // 按值移动文件路径，使整个解密 future 可以在后台任务中运行
+        let tasks = files.into_iter().map(|file| {
             let sem = semaphore.clone();
             let suc_count = success_count.clone();
             let fail_count = failed_count.clone();
+            let skip_count = skipped_count.clone();
+            let unchanged_count = unchanged_count.clone();
+            let manifest = manifest.clone();
+            let cancel_token = self.cancel_token.clone();
             let key = self.key.clone();
-            let file = file_path.clone();
             let in_dir = self.input_path.clone();
             let out_dir = self.output_path.clone();
+            let parallel = parallel.clone();
+            let events = self.events.clone();
+            let outcomes = outcomes.clone();
+            let ordered = self.ordered;
+            let version = self.version;
 
             async move {
                 let _permit = sem.acquire().await.unwrap();
+                if cancel_token.is_cancelled() {
+                    skip_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    return;
+                }
                 let relative_path = file.strip_prefix(&in_dir).unwrap();
                 let mut output_file = out_dir.join(relative_path);
 
@@ -242,35 +488,176 @@ impl DecryptionProcessor {
                     output_file.set_file_name(new_name);
                 }
 
+                let mut fingerprint = None;
+                if let Some((previous, updated)) = &manifest {
+                    match previous.check(relative_path, &file) {
+                        Ok(Change::Unchanged(touched)) if output_file.exists() => {
+                            if let Some(touched) = touched {
+                                updated.lock().unwrap().record(relative_path, touched);
+                            }
+                            let unchanged = unchanged_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1;
+                            let processed = suc_count.load(std::sync::atomic::Ordering::Relaxed)
+                                + fail_count.load(std::sync::atomic::Ordering::Relaxed)
+                                + unchanged;
+                            events.publish(Event::DecryptProgress {
+                                file: relative_path.to_path_buf(),
+                                processed: processed as u64,
+                                total: file_count as u64,
+                            });
+                            return;
+                        }
+                        // 输出已被删除时重新解密
+                        Ok(Change::Unchanged(_)) => fingerprint = FileFingerprint::of(&file).ok(),
+                        Ok(Change::Changed(changed)) => fingerprint = Some(changed),
+                        Err(e) => warn!("⚠️  无法计算文件指纹，将重新解密: {:?} - {}", file, e),
+                    }
+                }
+
                 if let Some(parent) = output_file.parent() {
                     if !parent.exists() {
                         fs::create_dir_all(parent).await.ok();
                     }
                 }
 
-                match decrypt_file_with_auto_version(&file, &output_file, &key).await {
+                let partial = partial_path(&output_file);
+                let result = match decrypt_file_with_auto_version(&file, &partial, &key, version, &parallel).await {
+                    Ok(_) => fs::rename(&partial, &output_file).await.map_err(Into::into),
+                    Err(e) => Err(e),
+                };
+                if let Some((_, updated)) = &manifest {
+                    let mut updated = updated.lock().unwrap();
+                    match (&result, fingerprint) {
+                        (Ok(_), Some(fingerprint)) => updated.record(relative_path, fingerprint),
+                        _ => updated.forget(relative_path),
+                    }
+                }
+                let error = match result {
                     Ok(_) => {
                         suc_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                        None
                     }
                     Err(e) => {
+                        remove_partial(&partial).await;
                         fail_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-                        warn!("⚠️  解密失败: {:?} - {}", file, e);
+                        if !ordered {
+                            warn!("⚠️  解密失败: {:?} - {}", file, e);
+                        }
+                        Some(e.to_string())
                     }
-                }
+                };
+                outcomes.lock().unwrap().push(FileOutcome {
+                    file: relative_path.to_path_buf(),
+                    error,
+                });
+                let processed = suc_count.load(std::sync::atomic::Ordering::Relaxed)
+                    + fail_count.load(std::sync::atomic::Ordering::Relaxed)
+                    + unchanged_count.load(std::sync::atomic::Ordering::Relaxed);
+                events.publish(Event::DecryptProgress {
+                    file: relative_path.to_path_buf(),
+                    processed: processed as u64,
+                    total: file_count as u64,
+                });
             }
         });
 
         stream::iter(tasks).buffer_unordered(self.threads).collect::<Vec<_>>().await;
 
+        // 中断时也保存清单，已完成的文件下次不必重新解密
+        let unchanged = unchanged_count.load(std::sync::atomic::Ordering::Relaxed);
+        if let Some((_, updated)) = &manifest {
+            updated.lock().unwrap().save(&self.output_path)?;
+            if unchanged > 0 {
+                info!("⏭️  跳过 {} 个未变化的文件", unchanged);
+            }
+        }
+
+        if self.ordered {
+            let mut outcomes = std::mem::take(&mut *outcomes.lock().unwrap());
+            outcomes.sort_by(|a, b| a.file.cmp(&b.file));
+            self.report_ordered(&outcomes)?;
+        }
+
         let elapsed = start_time.elapsed();
         info!("🎉 并行批量解密完成！");
         info!("🚀 使用线程数: {}", self.threads);
-        info!("📊 总文件数: {}", files.len());
+        info!("📊 总文件数: {}", file_count);
         info!("✅ 成功: {}", success_count.load(std::sync::atomic::Ordering::Relaxed));
         info!("❌ 失败: {}", failed_count.load(std::sync::atomic::Ordering::Relaxed));
         info!("⏱️  总耗时: {:.2} 秒", elapsed.as_secs_f64());
+
+        if self.cancel_token.is_cancelled() {
+            warn!(
+                "⏹️  解密已中断：{} 个文件已完成，{} 个文件未处理，未完成的输出已清理",
+                success_count.load(std::sync::atomic::Ordering::Relaxed),
+                skipped_count.load(std::sync::atomic::Ordering::Relaxed)
+            );
+            return Err(MwxDumpError::Cancelled.into());
+        }
+        Ok(DecryptReport {
+            files: file_count,
+            succeeded: success_count.load(std::sync::atomic::Ordering::Relaxed),
+            failed: failed_count.load(std::sync::atomic::Ordering::Relaxed),
+            skipped: skipped_count.load(std::sync::atomic::Ordering::Relaxed),
+            unchanged,
+            ..Default::default()
+        })
+    }
+
+    /// 按排好的顺序输出每个文件的结果，并写入或清理 `failures.json`
+    fn report_ordered(&self, outcomes: &[FileOutcome]) -> Result<()> {
+        for outcome in outcomes {
+            match &outcome.error {
+                None => info!("✅ {}", outcome.file.display()),
+                Some(error) => warn!("⚠️  解密失败: {} - {}", outcome.file.display(), error),
+            }
+        }
+        let failures: Vec<&FileOutcome> = outcomes.iter().filter(|o| o.error.is_some()).collect();
+        let path = self.output_path.join(FAILURES_FILE);
+        if failures.is_empty() {
+            if path.exists() {
+                std::fs::remove_file(&path)?;
+            }
+        } else {
+            std::fs::write(&path, serde_json::to_string_pretty(&failures)?)?;
+            warn!("📝 {} 个文件解密失败，详见 {:?}", failures.len(), path);
+        }
         Ok(())
     }
+
+    /// 筛选类别的标识，用于日志
+    fn category_names(&self) -> Vec<&'static str> {
+        self.categories.iter().map(|c| c.as_str()).collect()
+    }
+}
+
+/// 按数据库类别筛选 `root` 下的文件，类别列表为空时保留全部
+fn filter_by_category(root: &Path, files: Vec<PathBuf>, categories: &[DbCategory]) -> Vec<PathBuf> {
+    if categories.is_empty() {
+        return files;
+    }
+    files
+        .into_iter()
+        .filter(|file| categories.contains(&DbCategory::of_path(root, file)))
+        .collect()
+}
+
+/// 获取输出文件对应的临时文件路径（在原文件名后追加 `.partial`）
+///
+/// 解密过程中先写入临时文件，完成后再重命名为最终文件名，
+/// 中断或失败时不会留下看似完整的半成品。
+fn partial_path(output_path: &Path) -> PathBuf {
+    let mut name = output_path.as_os_str().to_os_string();
+    name.push(".partial");
+    PathBuf::from(name)
+}
+
+/// 删除未完成的临时输出文件
+async fn remove_partial(partial: &Path) {
+    if let Err(e) = fs::remove_file(partial).await {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            warn!("⚠️  清理未完成的输出文件失败: {:?} - {}", partial, e);
+        }
+    }
 }
 
 /// 自动检测微信数据库文件的解密版本
@@ -283,6 +670,7 @@ impl DecryptionProcessor {
 /// * `validator` - 密钥验证器实例
 /// * `file_path` - 要检测的数据库文件路径
 /// * `key_bytes` - 解密密钥字节数组
+/// * `forced` - 指定的版本，设置时只按该版本验证密钥
 ///
 /// # 返回值
 ///
@@ -300,7 +688,7 @@ impl DecryptionProcessor {
 /// # use anyhow::Result;
 /// # async fn example() -> Result<()> {
 /// let validator = KeyValidator::new();
-/// let version = determine_version(&validator, &file_path, &key_bytes).await?;
+/// let version = determine_version(&validator, &file_path, &key_bytes, None).await?;
 /// println!("检测到版本: {:?}", version);
 /// # Ok(())
 /// # }
@@ -309,7 +697,19 @@ async fn determine_version(
     validator: &KeyValidator,
     file_path: &Path,
     key_bytes: &[u8],
+    forced: Option<DecryptVersion>,
 ) -> Result<DecryptVersion> {
+    if let Some(version) = forced {
+        let valid = match version {
+            DecryptVersion::V3 => validator.validate_v3_key(file_path, key_bytes).await?,
+            DecryptVersion::V4 => validator.validate_v4_key(file_path, key_bytes).await?,
+        };
+        if !valid {
+            error!("❌ 密钥按 {} 版本验证失败", version.as_str());
+            return Err(WeChatError::DecryptionFailed(format!("密钥按 {} 版本验证失败", version.as_str())).into());
+        }
+        return Ok(version);
+    }
     info!("🔍 自动检测 {:?} 的版本...", file_path);
     match validator.validate_key_auto(file_path, key_bytes).await? {
         Some(detected_version) => {
@@ -383,6 +783,7 @@ fn collect_files_recursively(dir: PathBuf) -> std::pin::Pin<Box<dyn std::future:
 /// * `output_path` - 输出的解密数据库文件路径
 /// * `key_bytes` - 解密密钥字节数组
 /// * `version` - 要使用的解密版本
+/// * `parallel` - 页面并行解密选项
 ///
 /// # 返回值
 ///
@@ -410,9 +811,10 @@ async fn decrypt_single_file(
     output_path: &Path,
     key_bytes: &[u8],
     version: DecryptVersion,
+    parallel: &ParallelOptions,
 ) -> Result<()> {
     info!("📁 输出文件: {:?}", output_path);
-    let decryptor = create_decryptor(version);
+    let decryptor = create_decryptor_with_options(version, parallel);
     info!("🔓 开始解密...");
     let start_time = std::time::Instant::now();
 
@@ -436,6 +838,8 @@ async fn decrypt_single_file(
 /// * `input_path` - 输入的加密数据库文件路径
 /// * `output_path` - 输出的解密数据库文件路径
 /// * `key_bytes` - 解密密钥字节数组
+/// * `version` - 指定的解密版本，`None` 时自动检测
+/// * `parallel` - 页面并行解密选项
 ///
 /// # 返回值
 ///
@@ -463,6 +867,8 @@ async fn decrypt_file_with_auto_version(
     input_path: &Path,
     output_path: &Path,
     key_bytes: &[u8],
+    version: Option<DecryptVersion>,
+    parallel: &ParallelOptions,
 ) -> Result<()> {
     let metadata = fs::metadata(input_path).await?;
     if metadata.len() < 1024 {
@@ -475,8 +881,8 @@ async fn decrypt_file_with_auto_version(
     }
 
     let validator = KeyValidator::new();
-    let version = determine_version(&validator, input_path, key_bytes).await?;
-    let decryptor = create_decryptor(version);
+    let version = determine_version(&validator, input_path, key_bytes, version).await?;
+    let decryptor = create_decryptor_with_options(version, parallel);
 
     decryptor
         .decrypt_database_with_progress(input_path, output_path, key_bytes, None)
@@ -535,4 +941,201 @@ async fn verify_output_file(output_path: &Path) -> Result<()> {
         warn!("⚠️ 输出文件可能不是有效的SQLite数据库");
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_path() {
+        let partial = partial_path(Path::new("/out/decrypted_message_0.db"));
+        assert_eq!(partial, PathBuf::from("/out/decrypted_message_0.db.partial"));
+    }
+
+    #[test]
+    fn test_filter_by_category() {
+        let root = Path::new("wxid_abc");
+        let files: Vec<PathBuf> = [
+            "wxid_abc/db_storage/message/message_0.db",
+            "wxid_abc/db_storage/message/message_fts.db",
+            "wxid_abc/db_storage/message/media_0.db",
+            "wxid_abc/db_storage/contact/contact.db",
+            "wxid_abc/db_storage/session/session.db",
+            "wxid_abc/db_storage/fts/fts_index.db",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+
+        assert_eq!(filter_by_category(root, files.clone(), &[]).len(), 6);
+        let kept = filter_by_category(root, files.clone(), &[DbCategory::Message, DbCategory::Session]);
+        assert_eq!(
+            kept,
+            [
+                PathBuf::from("wxid_abc/db_storage/message/message_0.db"),
+                PathBuf::from("wxid_abc/db_storage/session/session.db"),
+            ]
+        );
+        assert_eq!(
+            filter_by_category(root, files, &[DbCategory::Fts]),
+            [
+                PathBuf::from("wxid_abc/db_storage/message/message_fts.db"),
+                PathBuf::from("wxid_abc/db_storage/fts/fts_index.db"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_directory_decrypt_skips_files() {
+        let input = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        std::fs::write(input.path().join("message_0.db"), vec![1u8; 4096]).unwrap();
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let processor = DecryptionProcessor::new(
+            input.path().to_path_buf(),
+            output.path().to_path_buf(),
+            vec![0u8; 32],
+            Some(1),
+            false,
+        )
+        .with_cancellation(token);
+
+        let err = processor.execute().await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<MwxDumpError>(), Some(MwxDumpError::Cancelled)));
+        assert_eq!(std::fs::read_dir(output.path()).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_refuses_output_inside_data_dir() {
+        let temp = tempfile::tempdir().unwrap();
+        let storage = temp.path().join("wxid_abc123_1a2b").join("db_storage");
+        std::fs::create_dir_all(&storage).unwrap();
+
+        let processor = DecryptionProcessor::new(storage.clone(), storage.join("decrypted"), vec![0u8; 32], Some(1), false);
+        let err = processor.execute().await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<WeChatError>(),
+            Some(WeChatError::OutputInsideDataDir { .. })
+        ));
+        assert!(!storage.join("decrypted").exists());
+    }
+
+    #[tokio::test]
+    async fn test_ordered_results_write_sorted_failures() {
+        let input = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        for name in ["c.db", "a.db", "b.db"] {
+            std::fs::write(input.path().join(name), vec![1u8; 4096]).unwrap();
+        }
+
+        let processor = DecryptionProcessor::new(
+            input.path().to_path_buf(),
+            output.path().to_path_buf(),
+            vec![0u8; 32],
+            Some(3),
+            false,
+        )
+        .with_ordered_results(true);
+        let report = processor.execute().await.unwrap();
+        assert_eq!((report.files, report.succeeded, report.failed), (3, 0, 3));
+        assert_eq!(report.max_memory_mb, ParallelDecryptConfig::auto_configure().max_memory_mb);
+
+        let failures: Vec<FileOutcome> =
+            serde_json::from_str(&std::fs::read_to_string(output.path().join(FAILURES_FILE)).unwrap()).unwrap();
+        let files: Vec<PathBuf> = failures.iter().map(|f| f.file.clone()).collect();
+        assert_eq!(files, ["a.db", "b.db", "c.db"].map(PathBuf::from));
+        assert!(failures.iter().all(|f| f.error.is_some()));
+    }
+
+    #[tokio::test]
+    async fn test_decrypts_v3_databases() {
+        use super::super::decrypt_algorithm_v3::tests::encrypt_v3;
+        use super::super::decrypt_common::SQLITE_HEADER;
+
+        let input = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        let key = vec![0x24u8; 32];
+        let mut plain = vec![0x61u8; 1024 * 4];
+        plain[..SQLITE_HEADER.len()].copy_from_slice(SQLITE_HEADER);
+        std::fs::write(input.path().join("MSG0.db"), encrypt_v3(&plain, &key, &[0x09; 16])).unwrap();
+
+        let processor = |version| {
+            DecryptionProcessor::new(input.path().to_path_buf(), output.path().to_path_buf(), key.clone(), Some(1), false)
+                .with_version(version)
+        };
+        let report = processor(None).execute().await.unwrap();
+        assert_eq!((report.succeeded, report.failed), (1, 0));
+        let decrypted = std::fs::read(output.path().join("decrypted_MSG0.db")).unwrap();
+        assert!(decrypted.starts_with(SQLITE_HEADER));
+
+        let report = processor(Some(DecryptVersion::V4)).execute().await.unwrap();
+        assert_eq!((report.succeeded, report.failed), (0, 1));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_single_file_decrypt_leaves_no_output() {
+        use super::super::decrypt_algorithm_v3::tests::encrypt_v3;
+        use super::super::decrypt_common::SQLITE_HEADER;
+
+        let dir = tempfile::tempdir().unwrap();
+        let key = vec![0x24u8; 32];
+        let mut plain = vec![0x61u8; 1024 * 4];
+        plain[..SQLITE_HEADER.len()].copy_from_slice(SQLITE_HEADER);
+        let input = dir.path().join("MSG0.db");
+        std::fs::write(&input, encrypt_v3(&plain, &key, &[0x09; 16])).unwrap();
+        let output = dir.path().join("out").join("MSG0.db");
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let err = DecryptionProcessor::new(input, output.clone(), key, Some(1), false)
+            .with_cancellation(token)
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(matches!(err.downcast_ref::<MwxDumpError>(), Some(MwxDumpError::Cancelled)));
+        assert!(!output.exists());
+        assert!(!partial_path(&output).exists());
+    }
+
+    #[tokio::test]
+    async fn test_incremental_skips_unchanged_files() {
+        use super::super::decrypt_algorithm_v3::tests::encrypt_v3;
+        use super::super::decrypt_common::SQLITE_HEADER;
+        use super::super::manifest::MANIFEST_FILE;
+
+        let input = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        let key = vec![0x24u8; 32];
+        let mut plain = vec![0x61u8; 1024 * 4];
+        plain[..SQLITE_HEADER.len()].copy_from_slice(SQLITE_HEADER);
+        for name in ["MSG0.db", "MSG1.db"] {
+            std::fs::write(input.path().join(name), encrypt_v3(&plain, &key, &[0x09; 16])).unwrap();
+        }
+        std::fs::write(input.path().join("broken.db"), vec![1u8; 4096]).unwrap();
+
+        let processor = |force| {
+            DecryptionProcessor::new(input.path().to_path_buf(), output.path().to_path_buf(), key.clone(), Some(2), false)
+                .with_incremental(true)
+                .with_force(force)
+        };
+        let report = processor(false).execute().await.unwrap();
+        assert_eq!((report.succeeded, report.failed, report.unchanged), (2, 1, 0));
+        assert!(output.path().join(MANIFEST_FILE).is_file());
+
+        // 失败的文件不记入清单，下次仍然重试
+        let report = processor(false).execute().await.unwrap();
+        assert_eq!((report.succeeded, report.failed, report.unchanged), (0, 1, 2));
+
+        plain[100] = 0x62;
+        std::fs::write(input.path().join("MSG1.db"), encrypt_v3(&plain, &key, &[0x09; 16])).unwrap();
+        std::fs::remove_file(output.path().join("decrypted_MSG0.db")).unwrap();
+        let report = processor(false).execute().await.unwrap();
+        assert_eq!((report.succeeded, report.unchanged), (2, 0));
+
+        let report = processor(true).execute().await.unwrap();
+        assert_eq!((report.succeeded, report.unchanged), (2, 0));
+    }
+}
@@ -1,13 +1,19 @@
 //! 密钥验证器
 
-use std::path::Path;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use tracing::{debug, info};
 
-use crate::errors::Result;
-use super::{DecryptVersion, Decryptor, decrypt_algorithm_v4::V4Decryptor};
+use crate::errors::{Result, WeChatError};
+use crate::wechat::datadir::{self, open_read_only, DbCatalog};
+use super::decrypt_common::{derive_keys, is_database_encrypted, verify_page_hmac, SALT_SIZE};
+use super::{DecryptConfig, DecryptVersion, Decryptor, decrypt_algorithm_v3::V3Decryptor, decrypt_algorithm_v4::V4Decryptor};
 
 /// 密钥验证器
 pub struct KeyValidator {
+    v3_decryptor: V3Decryptor,
     v4_decryptor: V4Decryptor,
 }
 
@@ -15,6 +21,7 @@ impl KeyValidator {
     /// 创建新的密钥验证器
     pub fn new() -> Self {
         Self {
+            v3_decryptor: V3Decryptor::new(),
             v4_decryptor: V4Decryptor::new(),
         }
     }
@@ -43,10 +50,22 @@ impl KeyValidator {
             return Ok(Some(DecryptVersion::V4));
         }
         
+        // 尝试V3版本
+        debug!("尝试V3版本验证");
+        if self.v3_decryptor.validate_key(db_path, key).await? {
+            info!("密钥验证成功: V3版本");
+            return Ok(Some(DecryptVersion::V3));
+        }
+        
         info!("密钥验证失败: 所有版本都不匹配");
         Ok(None)
     }
     
+    /// 验证V3版本密钥
+    pub async fn validate_v3_key(&self, db_path: &Path, key: &[u8]) -> Result<bool> {
+        self.v3_decryptor.validate_key(db_path, key).await
+    }
+    
     /// 验证V4版本密钥
     pub async fn validate_v4_key(&self, db_path: &Path, key: &[u8]) -> Result<bool> {
         self.v4_decryptor.validate_key(db_path, key).await
@@ -59,8 +78,90 @@ impl Default for KeyValidator {
     }
 }
 
+/// 缓存数据库第一页的同步验证器
+///
+/// 内存搜索在阻塞线程中逐个验证候选密钥：第一页只读取一次，每个不同的候选只做一次密钥派生和
+/// HMAC 校验，明显不是随机字节的候选直接拒绝。
+#[derive(Debug, Clone)]
+pub struct PageKeyValidator {
+    path: PathBuf,
+    first_page: Vec<u8>,
+    config: DecryptConfig,
+    /// 已验证过的候选及结果，多个特征命中常指向同一密钥
+    checked: Arc<Mutex<HashMap<Vec<u8>, bool>>>,
+}
+
+/// 随机密钥中不同字节值的最少个数，低于此值的候选不做密钥派生
+const MIN_DISTINCT_KEY_BYTES: usize = 16;
+
+impl PageKeyValidator {
+    /// 读取加密数据库的第一页
+    pub fn open(db_path: &Path, config: DecryptConfig) -> Result<Self> {
+        let mut first_page = vec![0u8; config.page_size];
+        open_read_only(db_path)?.read_exact(&mut first_page)?;
+        if !is_database_encrypted(&first_page) {
+            return Err(WeChatError::DecryptionFailed(format!("数据库未加密，不能用于验证密钥: {:?}", db_path)).into());
+        }
+        Ok(Self {
+            path: db_path.to_path_buf(),
+            first_page,
+            config,
+            checked: Arc::default(),
+        })
+    }
+
+    /// 在微信数据目录中选择体积最小的加密数据库
+    pub fn for_data_dir(data_dir: &Path, config: DecryptConfig) -> Result<Self> {
+        let info = datadir::scan(data_dir)?;
+        let db_storage = info.db_storage_path.ok_or_else(|| WeChatError::DataDirNotFound {
+            path: data_dir.display().to_string(),
+        })?;
+        let mut entries = DbCatalog::scan(&db_storage)?.entries;
+        entries.retain(|entry| entry.size >= config.page_size as u64);
+        entries.sort_by_key(|entry| entry.size);
+        for entry in entries {
+            match Self::open(&entry.path, config.clone()) {
+                Ok(validator) => {
+                    debug!("使用 {:?} 验证候选密钥", entry.path);
+                    return Ok(validator);
+                }
+                Err(e) => debug!("跳过 {:?}: {}", entry.path, e),
+            }
+        }
+        Err(WeChatError::DataDirNotFound {
+            path: db_storage.display().to_string(),
+        }
+        .into())
+    }
+
+    /// 用于验证的数据库
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 密钥能否通过第一页的 HMAC 校验
+    pub fn validate(&self, key: &[u8]) -> bool {
+        if key.len() != 32 {
+            return false;
+        }
+        let mut distinct = [false; 256];
+        key.iter().for_each(|&b| distinct[b as usize] = true);
+        if distinct.iter().filter(|&&seen| seen).count() < MIN_DISTINCT_KEY_BYTES {
+            return false;
+        }
+        if let Some(&valid) = self.checked.lock().unwrap().get(key) {
+            return valid;
+        }
+        let valid = derive_keys(key, &self.first_page[..SALT_SIZE], &self.config)
+            .and_then(|derived| verify_page_hmac(&self.first_page, &derived.mac_key, 0, &self.config))
+            .unwrap_or(false);
+        self.checked.lock().unwrap().insert(key.to_vec(), valid);
+        valid
+    }
+}
+
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
     use tempfile::NamedTempFile;
     use std::io::Write;
@@ -89,6 +190,23 @@ mod tests {
         assert!(result.unwrap().is_none());
     }
     
+    #[tokio::test]
+    async fn test_validate_key_auto_detects_v3() {
+        use crate::wechat::decrypt::decrypt_algorithm_v3::tests::encrypt_v3;
+        use crate::wechat::decrypt::decrypt_common::SQLITE_HEADER;
+        
+        let mut plain = vec![0x42u8; 1024 * 2];
+        plain[..SQLITE_HEADER.len()].copy_from_slice(SQLITE_HEADER);
+        let key = [0x33u8; 32];
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(&encrypt_v3(&plain, &key, &[0x07; 16])).unwrap();
+        temp_file.flush().unwrap();
+        
+        let validator = KeyValidator::new();
+        let version = validator.validate_key_auto(temp_file.path(), &key).await.unwrap();
+        assert_eq!(version, Some(DecryptVersion::V3));
+        assert!(validator.validate_key_auto(temp_file.path(), &[0u8; 32]).await.unwrap().is_none());
+    }
    
     #[tokio::test]
     async fn test_validate_v4_key() {
@@ -106,4 +224,52 @@ mod tests {
         assert!(result.is_ok());
         assert!(!result.unwrap());
     }
+
+    /// 生成第一页 HMAC 有效的加密数据库（页面内容不是真正的密文）
+    pub(crate) fn signed_first_page(key: &[u8], salt: &[u8; SALT_SIZE], config: &DecryptConfig) -> Vec<u8> {
+        use hmac::{Hmac, Mac};
+
+        let mut page = vec![0x5cu8; config.page_size];
+        page[..SALT_SIZE].copy_from_slice(salt);
+        let derived = derive_keys(key, salt, config).unwrap();
+        let mac_key = &derived.mac_key;
+        let data_end = config.page_size - config.reserve_size + super::super::decrypt_common::IV_SIZE;
+        let mac = match config.version {
+            DecryptVersion::V3 => {
+                let mut mac = Hmac::<sha1::Sha1>::new_from_slice(mac_key).unwrap();
+                mac.update(&page[SALT_SIZE..data_end]);
+                mac.update(&1u32.to_le_bytes());
+                mac.finalize().into_bytes().to_vec()
+            }
+            DecryptVersion::V4 => {
+                let mut mac = Hmac::<sha2::Sha512>::new_from_slice(mac_key).unwrap();
+                mac.update(&page[SALT_SIZE..data_end]);
+                mac.update(&1u32.to_le_bytes());
+                mac.finalize().into_bytes().to_vec()
+            }
+        };
+        page[data_end..data_end + config.hmac_size].copy_from_slice(&mac[..config.hmac_size]);
+        page
+    }
+
+    #[test]
+    fn test_page_key_validator() {
+        let key: Vec<u8> = (0..32u8).map(|i| i.wrapping_mul(37) ^ 0x5a).collect();
+        let config = DecryptConfig::v4();
+        let dir = tempfile::tempdir().unwrap();
+        let contact = dir.path().join("db_storage").join("contact");
+        std::fs::create_dir_all(&contact).unwrap();
+        std::fs::write(contact.join("contact.db"), signed_first_page(&key, &[0x21; SALT_SIZE], &config)).unwrap();
+        std::fs::write(contact.join("contact_fts.db"), b"SQLite format 3\x00").unwrap();
+
+        let validator = PageKeyValidator::for_data_dir(dir.path(), config).unwrap();
+        assert_eq!(validator.path(), contact.join("contact.db"));
+        assert!(validator.validate(&key));
+        assert!(validator.validate(&key));
+        assert!(!validator.validate(&[0u8; 32]));
+        let mut wrong = key.clone();
+        wrong[0] ^= 1;
+        assert!(!validator.validate(&wrong));
+        assert!(!validator.validate(&key[..16]));
+    }
 }
\ No newline at end of file
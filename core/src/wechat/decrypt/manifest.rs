@@ -0,0 +1,218 @@
+//! 增量解密清单
+//!
+//! 目录解密的输出目录下保存 `decrypt_manifest.json`，记录每个源文件上次解密时的大小、修改时间和
+//! 密文的 BLAKE3 哈希。再次解密时，大小和修改时间都未变的文件直接跳过；修改时间变化但内容相同
+//! （如被复制或 touch 过）的文件比较哈希后同样跳过，只更新清单。
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tracing::warn;
+
+use crate::errors::Result;
+
+/// 清单文件名，写在输出目录下
+pub const MANIFEST_FILE: &str = "decrypt_manifest.json";
+
+/// 清单格式版本
+const MANIFEST_VERSION: u32 = 1;
+
+/// 源文件的指纹
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileFingerprint {
+    /// 文件大小（字节）
+    pub size: u64,
+    /// 修改时间（Unix 毫秒）
+    pub mtime: i64,
+    /// 密文的 BLAKE3 哈希（十六进制）
+    pub blake3: String,
+}
+
+impl FileFingerprint {
+    /// 计算文件的指纹
+    pub fn of(path: &Path) -> Result<Self> {
+        let (size, mtime) = stat(path)?;
+        Ok(Self {
+            size,
+            mtime,
+            blake3: hash_file(path)?,
+        })
+    }
+}
+
+/// 增量解密清单
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecryptManifest {
+    /// 格式版本
+    pub version: u32,
+    /// 解密密钥的 BLAKE3 哈希，密钥变化时清单作废
+    pub key: String,
+    /// 相对输入目录的路径（以 `/` 分隔）到指纹
+    pub files: BTreeMap<String, FileFingerprint>,
+}
+
+impl DecryptManifest {
+    /// 读取输出目录下的清单；不存在、无法解析或与 `key` 不符时返回空清单
+    pub fn load(output_dir: &Path, key: &[u8]) -> Self {
+        let key_hash = blake3::hash(key).to_hex().to_string();
+        let empty = Self {
+            version: MANIFEST_VERSION,
+            key: key_hash.clone(),
+            files: BTreeMap::new(),
+        };
+        let path = output_dir.join(MANIFEST_FILE);
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(_) => return empty,
+        };
+        match serde_json::from_str::<Self>(&text) {
+            Ok(manifest) if manifest.version == MANIFEST_VERSION && manifest.key == key_hash => manifest,
+            Ok(_) => {
+                warn!("⚠️  增量清单与当前密钥或版本不符，全部重新解密: {:?}", path);
+                empty
+            }
+            Err(e) => {
+                warn!("⚠️  增量清单无法解析，全部重新解密: {:?} - {}", path, e);
+                empty
+            }
+        }
+    }
+
+    /// 写入输出目录，先写临时文件再重命名
+    pub fn save(&self, output_dir: &Path) -> Result<()> {
+        let path = output_dir.join(MANIFEST_FILE);
+        let partial = output_dir.join(format!("{}.partial", MANIFEST_FILE));
+        std::fs::write(&partial, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&partial, &path)?;
+        Ok(())
+    }
+
+    /// 检查源文件自上次解密后是否变化
+    ///
+    /// 未变化时返回 `None`；变化时返回新的指纹，解密成功后用 [`record`](Self::record) 写入清单。
+    /// 修改时间变化但哈希相同的文件视为未变化，返回的指纹通过 [`record`](Self::record) 更新修改时间。
+    pub fn check(&self, relative: &Path, source: &Path) -> Result<Change> {
+        let (size, mtime) = stat(source)?;
+        let previous = self.files.get(&manifest_key(relative));
+        if let Some(previous) = previous {
+            if previous.size == size && previous.mtime == mtime {
+                return Ok(Change::Unchanged(None));
+            }
+        }
+        let fingerprint = FileFingerprint {
+            size,
+            mtime,
+            blake3: hash_file(source)?,
+        };
+        match previous {
+            Some(previous) if previous.size == size && previous.blake3 == fingerprint.blake3 => {
+                Ok(Change::Unchanged(Some(fingerprint)))
+            }
+            _ => Ok(Change::Changed(fingerprint)),
+        }
+    }
+
+    /// 记录解密成功的文件
+    pub fn record(&mut self, relative: &Path, fingerprint: FileFingerprint) {
+        self.files.insert(manifest_key(relative), fingerprint);
+    }
+
+    /// 删除文件的记录，解密失败的文件下次重新解密
+    pub fn forget(&mut self, relative: &Path) {
+        self.files.remove(&manifest_key(relative));
+    }
+
+    /// 只保留 `present` 中的文件，删除源文件已不存在的记录
+    pub fn retain(&mut self, present: &[PathBuf]) {
+        let present: std::collections::HashSet<String> = present.iter().map(|p| manifest_key(p)).collect();
+        self.files.retain(|key, _| present.contains(key));
+    }
+}
+
+/// [`DecryptManifest::check`] 的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// 未变化，可以跳过；修改时间变化但内容相同时带有新的指纹
+    Unchanged(Option<FileFingerprint>),
+    /// 新文件或内容已变化，需要重新解密
+    Changed(FileFingerprint),
+}
+
+/// 清单中的路径键，统一使用 `/` 分隔
+fn manifest_key(relative: &Path) -> String {
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// 文件大小和修改时间（Unix 毫秒）
+fn stat(path: &Path) -> Result<(u64, i64)> {
+    let metadata = std::fs::metadata(path)?;
+    let mtime = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default();
+    Ok((metadata.len(), mtime))
+}
+
+/// 流式计算文件的 BLAKE3 哈希
+pub(crate) fn hash_file(path: &Path) -> Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = blake3::Hasher::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_manifest_detects_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("message_0.db");
+        std::fs::write(&source, b"cipher").unwrap();
+        let relative = Path::new("message").join("message_0.db");
+
+        let mut manifest = DecryptManifest::load(dir.path(), b"key");
+        let Change::Changed(fingerprint) = manifest.check(&relative, &source).unwrap() else {
+            panic!("新文件应需要解密");
+        };
+        assert_eq!(fingerprint, FileFingerprint::of(&source).unwrap());
+        manifest.record(&relative, fingerprint);
+        assert_eq!(manifest.check(&relative, &source).unwrap(), Change::Unchanged(None));
+        manifest.save(dir.path()).unwrap();
+
+        // 只有修改时间变化时比较哈希
+        let touched = SystemTime::now() + Duration::from_secs(60);
+        std::fs::File::options().write(true).open(&source).unwrap().set_modified(touched).unwrap();
+        let loaded = DecryptManifest::load(dir.path(), b"key");
+        assert!(loaded.files.contains_key("message/message_0.db"));
+        assert!(matches!(loaded.check(&relative, &source).unwrap(), Change::Unchanged(Some(_))));
+
+        // 同样大小的新内容，修改时间不依赖文件系统的时间精度
+        std::fs::write(&source, b"CIPHER").unwrap();
+        let rewritten = touched + Duration::from_secs(60);
+        std::fs::File::options().write(true).open(&source).unwrap().set_modified(rewritten).unwrap();
+        assert!(matches!(loaded.check(&relative, &source).unwrap(), Change::Changed(_)));
+
+        // 密钥变化时清单作废
+        assert!(DecryptManifest::load(dir.path(), b"other").files.is_empty());
+        let mut retained = loaded.clone();
+        retained.retain(&[]);
+        assert!(retained.files.is_empty());
+    }
+}
@@ -1,6 +1,9 @@
 //! 微信数据库解密模块
 //! 
 //! 支持微信V3和V4版本的SQLite数据库解密
+//!
+//! - V3（微信3.x）：SQLCipher 3 参数，PBKDF2-HMAC-SHA1 64000 次迭代，HMAC-SHA1，1024 字节页面
+//! - V4（微信4.x）：PBKDF2-HMAC-SHA512 256000 次迭代，HMAC-SHA512，4096 字节页面
 
 use async_trait::async_trait;
 use std::path::Path;
@@ -8,19 +11,27 @@ use crate::errors::Result;
 
 pub mod decrypt_files;
 pub mod decrypt_common;
+pub mod decrypt_algorithm_v3;
 pub mod decrypt_algorithm_v4;
 pub mod decrypt_validator;
 pub mod parallel_decrypt;
 pub mod cached_key_validator;
+pub mod session;
+pub mod manifest;
 
 
-pub use decrypt_files::DecryptionProcessor;
-pub use parallel_decrypt::{ParallelDecryptor, ParallelDecryptConfig};
+pub use decrypt_files::{DecryptReport, DecryptionProcessor, FileOutcome, FAILURES_FILE};
+pub use parallel_decrypt::{MemoryMonitor, ParallelDecryptor, ParallelDecryptConfig, ParallelOptions};
+pub use decrypt_validator::PageKeyValidator;
 pub use cached_key_validator::{CachedKeyValidator, CacheConfig, BatchValidationResult, ValidationStats};
+pub use session::DecryptedSession;
+pub use manifest::{DecryptManifest, MANIFEST_FILE};
 
 /// 解密器版本
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DecryptVersion {
+    /// 微信3.x版本
+    V3,
     /// 微信4.0版本
     V4,
 }
@@ -29,6 +40,7 @@ impl DecryptVersion {
     /// 获取版本字符串
     pub fn as_str(&self) -> &'static str {
         match self {
+            DecryptVersion::V3 => "V3",
             DecryptVersion::V4 => "V4",
         }
     }
@@ -50,6 +62,17 @@ pub struct DecryptConfig {
 }
 
 impl DecryptConfig {
+    /// 创建V3配置
+    pub fn v3() -> Self {
+        Self {
+            version: DecryptVersion::V3,
+            page_size: 1024,
+            iter_count: 64000,
+            hmac_size: 20,
+            reserve_size: 48, // IV(16) + HMAC(20)，按AES块大小对齐到48
+        }
+    }
+
     /// 创建V4配置
     pub fn v4() -> Self {
         Self {
@@ -127,8 +150,40 @@ pub trait Decryptor: Send + Sync {
 /// # 返回
 /// 对应版本的解密器实例
 pub fn create_decryptor(version: DecryptVersion) -> Box<dyn Decryptor> {
+    create_decryptor_with_options(version, &ParallelOptions::default())
+}
+
+/// 按页面并行选项创建解密器
+pub fn create_decryptor_with_options(version: DecryptVersion, options: &ParallelOptions) -> Box<dyn Decryptor> {
     match version {
-        DecryptVersion::V4 => Box::new(decrypt_algorithm_v4::V4Decryptor::new()),
+        DecryptVersion::V3 => {
+            let mut decryptor = decrypt_algorithm_v3::V3Decryptor::new();
+            if let Some(enabled) = options.enabled {
+                decryptor.set_parallel_enabled(enabled);
+            }
+            if options.has_overrides() {
+                let config = options.apply(decryptor.parallel_config().clone());
+                decryptor.set_parallel_config(config);
+            }
+            if let Some(monitor) = &options.memory_monitor {
+                decryptor.set_memory_monitor(monitor.clone());
+            }
+            Box::new(decryptor)
+        }
+        DecryptVersion::V4 => {
+            let mut decryptor = decrypt_algorithm_v4::V4Decryptor::new();
+            if let Some(enabled) = options.enabled {
+                decryptor.set_parallel_enabled(enabled);
+            }
+            if options.has_overrides() {
+                let config = options.apply(decryptor.parallel_config().clone());
+                decryptor.set_parallel_config(config);
+            }
+            if let Some(monitor) = &options.memory_monitor {
+                decryptor.set_memory_monitor(monitor.clone());
+            }
+            Box::new(decryptor)
+        }
     }
 }
 
@@ -138,6 +193,7 @@ mod tests {
     
     #[test]
     fn test_decrypt_version() {
+        assert_eq!(DecryptVersion::V3.as_str(), "V3");
         assert_eq!(DecryptVersion::V4.as_str(), "V4");
     }
     
@@ -148,6 +204,12 @@ mod tests {
         assert_eq!(v4_config.version, DecryptVersion::V4);
         assert_eq!(v4_config.iter_count, 256000);
         assert_eq!(v4_config.hmac_size, 64);
+
+        let v3_config = DecryptConfig::v3();
+        assert_eq!(v3_config.version, DecryptVersion::V3);
+        assert_eq!(v3_config.page_size, 1024);
+        assert_eq!(v3_config.iter_count, 64000);
+        assert_eq!(v3_config.reserve_size % 16, 0);
     }
     
     #[test]
@@ -155,5 +217,6 @@ mod tests {
 
         let v4_decryptor = create_decryptor(DecryptVersion::V4);
         assert_eq!(v4_decryptor.version(), DecryptVersion::V4);
+        assert_eq!(create_decryptor(DecryptVersion::V3).version(), DecryptVersion::V3);
     }
 }
\ No newline at end of file
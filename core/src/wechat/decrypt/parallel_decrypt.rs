@@ -1,22 +1,163 @@
 //! 并行页面解密实现
 //! 
 //! 提供高性能的异步并行解密功能，显著提升大文件解密速度
+//!
+//! 读取任务按页号顺序发出页面，多个工作任务从同一通道领取页面解密，写入任务按页号重排后顺序写出。
+//! 工作任务受 [`ReorderWindow`] 限制，某个页面处理较慢时其余任务不会继续领先太多，
+//! 写入端的乱序缓冲区不超过窗口大小。
 
 use std::collections::BTreeMap;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use tokio::fs::File;
-use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt, SeekFrom};
-use tokio::sync::{mpsc, Mutex, Semaphore};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt, BufWriter, SeekFrom};
+use tokio::sync::{mpsc, Mutex, Notify, Semaphore};
+use tokio::task::JoinSet;
 use tracing::{debug, info, warn};
-use futures::future::try_join_all;
 
 use crate::errors::{Result, WeChatError};
+use crate::utils::task::{join_error, panic_message};
+use crate::wechat::datadir::open_read_only_async;
 use super::{
-    decrypt_common::{derive_keys_v4, verify_page_hmac, SQLITE_HEADER},
+    decrypt_common::{derive_keys, verify_page_hmac, SQLITE_HEADER},
     DecryptConfig, ProgressCallback,
 };
 
+/// 流水线阶段，用于汇总各任务的统计
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    Read,
+    Process,
+    Write,
+}
+
+/// 阶段任务的结果：读取和写入为页数，处理为该工作任务处理的页数
+type StageResult = (Stage, Result<usize>);
+
+/// 单个文件在内存监控器中登记的页面缓冲区
+///
+/// 读取页面时登记，写入后释放；任务出错中止时由 [`PageBudget::release_all`] 归还未释放的部分，
+/// 避免共享的监控器残留占用。工作任务通过 `window` 限制领先写入的页数。
+#[derive(Clone)]
+struct PageBudget {
+    monitor: MemoryMonitor,
+    page_size: usize,
+    in_flight: Arc<AtomicUsize>,
+    window: Arc<ReorderWindow>,
+}
+
+impl PageBudget {
+    fn new(monitor: MemoryMonitor, page_size: usize, window: usize) -> Self {
+        Self {
+            monitor,
+            page_size,
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            window: Arc::new(ReorderWindow::new(window)),
+        }
+    }
+
+    fn acquire(&self) {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        self.monitor.allocate(self.page_size);
+    }
+
+    fn release(&self) {
+        self.in_flight.fetch_sub(1, Ordering::Relaxed);
+        self.monitor.deallocate(self.page_size);
+    }
+
+    fn release_all(&self) {
+        let pages = self.in_flight.swap(0, Ordering::Relaxed);
+        self.monitor.deallocate(pages * self.page_size);
+    }
+}
+
+/// 写入端的重排窗口
+///
+/// 工作任务只处理页号小于「下一个待写入页号 + 窗口大小」的页面，超出时等待写入推进。
+/// 下一个待写入的页面总在窗口内，因此不会死锁。
+struct ReorderWindow {
+    size: u64,
+    next: AtomicU64,
+    notify: Notify,
+    stalls: AtomicU64,
+    peak_pending: AtomicUsize,
+}
+
+impl ReorderWindow {
+    fn new(size: usize) -> Self {
+        Self {
+            size: size.max(1) as u64,
+            next: AtomicU64::new(0),
+            notify: Notify::new(),
+            stalls: AtomicU64::new(0),
+            peak_pending: AtomicUsize::new(0),
+        }
+    }
+
+    /// 等待页面进入窗口
+    async fn admit(&self, page_num: u64) {
+        let mut stalled = false;
+        loop {
+            // 先登记等待再检查，避免错过检查与等待之间的推进
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if page_num < self.next.load(Ordering::Acquire) + self.size {
+                return;
+            }
+            if !stalled {
+                stalled = true;
+                self.stalls.fetch_add(1, Ordering::Relaxed);
+            }
+            notified.await;
+        }
+    }
+
+    /// 写入任务推进到下一个待写入页号
+    fn advance(&self, next: u64) {
+        self.next.store(next, Ordering::Release);
+        self.notify.notify_waiters();
+    }
+
+    /// 记录乱序缓冲区的占用
+    fn record_pending(&self, pending: usize) {
+        self.peak_pending.fetch_max(pending, Ordering::Relaxed);
+    }
+
+    fn stats(&self) -> ReorderStats {
+        ReorderStats {
+            window: self.size as usize,
+            peak_pending: self.peak_pending.load(Ordering::Relaxed),
+            stalls: self.stalls.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// 重排缓冲区统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReorderStats {
+    /// 窗口大小（页）
+    pub window: usize,
+    /// 写入端乱序缓冲区的峰值占用（页）
+    pub peak_pending: usize,
+    /// 工作任务因超出窗口而等待的次数
+    pub stalls: u64,
+}
+
+/// 创建输出文件，`write_through` 时绕过系统写缓存
+async fn create_output_file(path: &std::path::Path, write_through: bool) -> Result<File> {
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    if write_through {
+        #[cfg(windows)]
+        options.custom_flags(windows::Win32::Storage::FileSystem::FILE_FLAG_WRITE_THROUGH.0);
+        #[cfg(unix)]
+        options.custom_flags(libc::O_DSYNC);
+    }
+    Ok(options.open(path).await?)
+}
+
 /// 页面处理任务
 #[derive(Debug, Clone)]
 pub struct PageTask {
@@ -70,6 +211,13 @@ pub struct ParallelDecryptConfig {
     pub write_buffer_size: usize,
     /// 内存使用限制 (MB)
     pub max_memory_mb: usize,
+    /// 重排窗口大小（页），限制写入端等待落后页面时缓冲的乱序页面数
+    pub reorder_window: usize,
+    /// 写入前按输入大小预分配输出文件，减少文件系统碎片，结束时截断到实际写入的长度
+    pub preallocate: bool,
+    /// 以直写方式打开输出文件（Windows 为 `FILE_FLAG_WRITE_THROUGH`，Unix 为 `O_DSYNC`），
+    /// 解密数 GB 的文件时避免数据在系统缓存中堆积
+    pub write_through: bool,
 }
 
 impl ParallelDecryptConfig {
@@ -82,6 +230,9 @@ impl ParallelDecryptConfig {
             read_buffer_size: 1024 * 1024, // 1MB
             write_buffer_size: 1024 * 1024, // 1MB
             max_memory_mb: 512, // 512MB
+            reorder_window: 256,
+            preallocate: true,
+            write_through: false,
         }
     }
     
@@ -93,6 +244,9 @@ impl ParallelDecryptConfig {
             read_buffer_size: 256 * 1024, // 256KB
             write_buffer_size: 256 * 1024, // 256KB
             max_memory_mb: 128, // 128MB
+            reorder_window: 64,
+            preallocate: true,
+            write_through: false,
         }
     }
     
@@ -105,27 +259,77 @@ impl ParallelDecryptConfig {
             read_buffer_size: 2 * 1024 * 1024, // 2MB
             write_buffer_size: 2 * 1024 * 1024, // 2MB
             max_memory_mb: 1024, // 1GB
+            reorder_window: 512,
+            preallocate: true,
+            write_through: false,
+        }
+    }
+}
+
+/// 页面并行解密选项，未设置的项使用解密器的默认值
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParallelOptions {
+    /// 强制启用（`true`）或禁用（`false`）页面并行解密
+    pub enabled: Option<bool>,
+    /// 并发页面数量
+    pub concurrent_pages: Option<usize>,
+    /// 每批处理的页面数
+    pub batch_size: Option<usize>,
+    /// 内存使用限制 (MB)
+    pub max_memory_mb: Option<usize>,
+    /// 多个文件共享的内存监控器，用于统计整次解密的内存峰值；未设置时每个文件单独监控
+    pub memory_monitor: Option<MemoryMonitor>,
+}
+
+impl ParallelOptions {
+    /// 是否设置了任何并行参数
+    pub fn has_overrides(&self) -> bool {
+        self.concurrent_pages.is_some() || self.batch_size.is_some() || self.max_memory_mb.is_some()
+    }
+
+    /// 将设置的参数覆盖到 `base` 上
+    pub fn apply(&self, base: ParallelDecryptConfig) -> ParallelDecryptConfig {
+        ParallelDecryptConfig {
+            concurrent_pages: self.concurrent_pages.unwrap_or(base.concurrent_pages),
+            batch_size: self.batch_size.unwrap_or(base.batch_size),
+            max_memory_mb: self.max_memory_mb.unwrap_or(base.max_memory_mb),
+            ..base
         }
     }
 }
 
 /// 内存使用监控器
+///
+/// 统计页面缓冲区占用的内存并记录峰值；克隆后共享同一份计数，可在同时解密的多个文件间共用。
+#[derive(Debug, Clone)]
 pub struct MemoryMonitor {
     max_memory_bytes: usize,
     current_usage: Arc<AtomicUsize>,
+    peak_usage: Arc<AtomicUsize>,
+}
+
+impl PartialEq for MemoryMonitor {
+    /// 共享同一份计数时相等
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.current_usage, &other.current_usage)
+    }
 }
 
+impl Eq for MemoryMonitor {}
+
 impl MemoryMonitor {
     pub fn new(max_memory_mb: usize) -> Self {
         Self {
             max_memory_bytes: max_memory_mb * 1024 * 1024,
             current_usage: Arc::new(AtomicUsize::new(0)),
+            peak_usage: Arc::new(AtomicUsize::new(0)),
         }
     }
     
     pub fn allocate(&self, size: usize) -> bool {
-        let current = self.current_usage.fetch_add(size, Ordering::Relaxed);
-        current + size <= self.max_memory_bytes
+        let current = self.current_usage.fetch_add(size, Ordering::Relaxed) + size;
+        self.peak_usage.fetch_max(current, Ordering::Relaxed);
+        current <= self.max_memory_bytes
     }
     
     pub fn deallocate(&self, size: usize) {
@@ -135,6 +339,21 @@ impl MemoryMonitor {
     pub fn current_usage_mb(&self) -> usize {
         self.current_usage.load(Ordering::Relaxed) / (1024 * 1024)
     }
+
+    /// 内存使用峰值（字节）
+    pub fn peak_usage_bytes(&self) -> u64 {
+        self.peak_usage.load(Ordering::Relaxed) as u64
+    }
+
+    /// 内存使用峰值 (MB)
+    pub fn peak_usage_mb(&self) -> usize {
+        self.peak_usage.load(Ordering::Relaxed) / (1024 * 1024)
+    }
+
+    /// 内存使用限制 (MB)
+    pub fn max_memory_mb(&self) -> usize {
+        self.max_memory_bytes / (1024 * 1024)
+    }
     
     pub fn is_memory_pressure(&self) -> bool {
         let current = self.current_usage.load(Ordering::Relaxed);
@@ -147,6 +366,7 @@ pub struct ParallelDecryptor {
     config: DecryptConfig,
     parallel_config: ParallelDecryptConfig,
     memory_monitor: MemoryMonitor,
+    reorder_stats: std::sync::Mutex<ReorderStats>,
 }
 
 impl ParallelDecryptor {
@@ -157,9 +377,16 @@ impl ParallelDecryptor {
             config,
             parallel_config,
             memory_monitor,
+            reorder_stats: std::sync::Mutex::default(),
         }
     }
     
+    /// 使用共享的内存监控器，替换按 `max_memory_mb` 新建的监控器
+    pub fn with_memory_monitor(mut self, memory_monitor: MemoryMonitor) -> Self {
+        self.memory_monitor = memory_monitor;
+        self
+    }
+
     /// 并行解密数据库
     pub async fn decrypt_database_parallel(
         &self,
@@ -187,55 +414,67 @@ impl ParallelDecryptor {
         let derived_keys = Arc::new(derived_keys);
         
         // 3. 创建文件句柄
-        let input_file = Arc::new(Mutex::new(File::open(input_path).await?));
-        let output_file = Arc::new(Mutex::new(File::create(output_path).await?));
+        let input_file = Arc::new(Mutex::new(open_read_only_async(input_path).await?));
+        let mut output_file = create_output_file(output_path, self.parallel_config.write_through).await?;
+        if self.parallel_config.preallocate {
+            output_file.set_len(file_size).await?;
+        }
         
         // 4. 写入SQLite头
-        output_file.lock().await.write_all(SQLITE_HEADER).await?;
+        output_file.write_all(SQLITE_HEADER).await?;
         
         // 5. 创建通信通道
         let (page_sender, page_receiver) = mpsc::channel(self.parallel_config.batch_size * 2);
         let (result_sender, result_receiver) = mpsc::channel(self.parallel_config.batch_size * 2);
         
-        // 6. 启动任务
-        let read_task = self.spawn_read_task(
-            input_file.clone(),
-            page_sender,
-            total_pages,
-        );
-        
-        let process_tasks = self.spawn_process_tasks(
-            page_receiver,
-            result_sender,
-            derived_keys,
-        ).await?;
+        // 6. 在同一个 JoinSet 中启动读取、处理和写入任务
+        let mut tasks = JoinSet::new();
+        // 窗口至少容纳每个工作任务各一页，否则部分任务会一直空等
+        let window = self.parallel_config.reorder_window.max(self.parallel_config.concurrent_pages);
+        let pages = PageBudget::new(self.memory_monitor.clone(), self.config.page_size, window);
+        self.spawn_read_task(&mut tasks, input_file.clone(), page_sender, total_pages, pages.clone());
+        self.spawn_process_tasks(&mut tasks, page_receiver, result_sender, derived_keys, pages.window.clone());
+        self.spawn_write_task(&mut tasks, output_file, result_receiver, total_pages, progress_callback, pages.clone());
         
-        let write_task = self.spawn_write_task(
-            output_file,
-            result_receiver,
-            total_pages,
-            progress_callback,
-        );
-        
-        // 7. 等待所有任务完成
-        let (read_result, process_results, write_result) = tokio::try_join!(
-            read_task,
-            try_join_all(process_tasks),
-            write_task
-        )?;
+        // 7. 等待所有任务完成；任一任务出错或 panic 时立即返回，取消其余任务并归还登记的内存
+        let joined: Result<(usize, usize, usize)> = async {
+            let (mut pages_read, mut workers, mut pages_written) = (0, 0, 0);
+            while let Some(joined) = tasks.join_next().await {
+                let (stage, result) = joined.map_err(|e| join_error(e, WeChatError::DecryptionFailed))?;
+                let count = result?;
+                match stage {
+                    Stage::Read => pages_read = count,
+                    Stage::Process => workers += 1,
+                    Stage::Write => pages_written = count,
+                }
+            }
+            Ok((pages_read, workers, pages_written))
+        }
+        .await;
+        drop(tasks);
+        pages.release_all();
+        let reorder = pages.window.stats();
+        *self.reorder_stats.lock().unwrap() = reorder;
+        let (pages_read, workers, pages_written) = joined?;
         
         let elapsed = start_time.elapsed();
         info!("🎉 并行解密完成! 耗时: {:.2}秒", elapsed.as_secs_f64());
         info!("📈 性能统计: 读取 {} 页, 处理 {} 个任务, 写入 {} 页", 
-              read_result?, process_results.len(), write_result?);
-        info!("💾 内存使用峰值: {} MB", self.memory_monitor.current_usage_mb());
+              pages_read, workers, pages_written);
+        info!("💾 内存使用峰值: {} MB", self.memory_monitor.peak_usage_mb());
+        info!("🔀 重排缓冲峰值: {}/{} 页, 工作任务等待 {} 次", reorder.peak_pending, reorder.window, reorder.stalls);
         
         Ok(())
     }
+
+    /// 最近一次解密的重排缓冲区统计
+    pub fn reorder_stats(&self) -> ReorderStats {
+        *self.reorder_stats.lock().unwrap()
+    }
     
     /// 读取数据库文件信息
     async fn read_db_info(&self, file_path: &std::path::Path) -> Result<(u64, Vec<u8>)> {
-        let mut file = File::open(file_path).await
+        let mut file = open_read_only_async(file_path).await
             .map_err(|e| WeChatError::DecryptionFailed(format!("打开文件失败: {}", e)))?;
         
         // 获取文件大小
@@ -273,7 +512,7 @@ impl ParallelDecryptor {
         debug!("提取Salt: {} 字节", salt.len());
         
         // 派生密钥
-        let derived_keys = derive_keys_v4(key, salt)?;
+        let derived_keys = derive_keys(key, salt, &self.config)?;
         
         // 验证密钥
         if !verify_page_hmac(first_page, &derived_keys.mac_key, 0, &self.config)? {
@@ -287,138 +526,148 @@ impl ParallelDecryptor {
     /// 启动读取任务
     fn spawn_read_task(
         &self,
+        tasks: &mut JoinSet<StageResult>,
         input_file: Arc<Mutex<File>>,
         sender: mpsc::Sender<PageTask>,
         total_pages: usize,
-    ) -> tokio::task::JoinHandle<Result<usize>> {
+        pages: PageBudget,
+    ) {
         let page_size = self.config.page_size;
         let batch_size = self.parallel_config.batch_size;
-        let memory_monitor = Arc::new(self.memory_monitor.current_usage.clone());
         
-        tokio::spawn(async move {
-            let mut pages_read = 0;
-            let mut current_batch = Vec::with_capacity(batch_size);
+        tasks.spawn(async move {
+            let result: Result<usize> = async move {
+                let mut pages_read = 0;
+                let mut current_batch = Vec::with_capacity(batch_size);
             
-            for page_num in 0..total_pages {
-                let offset = page_num * page_size;
+                for page_num in 0..total_pages {
+                    let offset = page_num * page_size;
                 
-                // 内存压力检查
-                while memory_monitor.load(Ordering::Relaxed) > 800 * 1024 * 1024 { // 800MB
-                    tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
-                }
+                    // 内存压力检查，只在批次之间等待，已读取的页面都已发出，写入后会释放内存
+                    while current_batch.is_empty() && pages.monitor.is_memory_pressure() {
+                        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+                    }
                 
-                // 读取页面数据
-                let mut page_data = vec![0u8; page_size];
-                let bytes_read = {
-                    let mut file = input_file.lock().await;
-                    file.seek(SeekFrom::Start(offset as u64)).await?;
-                    file.read(&mut page_data).await?
-                };
+                    // 读取页面数据
+                    let mut page_data = vec![0u8; page_size];
+                    let bytes_read = {
+                        let mut file = input_file.lock().await;
+                        file.seek(SeekFrom::Start(offset as u64)).await?;
+                        file.read(&mut page_data).await?
+                    };
                 
-                if bytes_read == 0 {
-                    break;
-                }
+                    if bytes_read == 0 {
+                        break;
+                    }
+                    pages.acquire();
                 
-                if bytes_read < page_size {
-                    page_data.truncate(bytes_read);
-                }
+                    if bytes_read < page_size {
+                        page_data.truncate(bytes_read);
+                    }
                 
-                // 检查是否为空页面，如果是则跳过解密处理
-                let _is_empty_page = page_data.iter().all(|&b| b == 0);
+                    // 检查是否为空页面，如果是则跳过解密处理
+                    let _is_empty_page = page_data.iter().all(|&b| b == 0);
                 
-                let task = PageTask {
-                    page_num: page_num as u64,
-                    offset: offset as u64,
-                    size: bytes_read,
-                    data: page_data,
-                };
+                    let task = PageTask {
+                        page_num: page_num as u64,
+                        offset: offset as u64,
+                        size: bytes_read,
+                        data: page_data,
+                    };
                 
-                current_batch.push(task);
+                    current_batch.push(task);
                 
-                // 批量发送
-                if current_batch.len() >= batch_size || page_num == total_pages - 1 {
-                    for task in current_batch.drain(..) {
-                        sender.send(task).await.map_err(|_| {
-                            WeChatError::DecryptionFailed("发送页面任务失败".to_string())
-                        })?;
-                        pages_read += 1;
-                    }
+                    // 批量发送
+                    if current_batch.len() >= batch_size || page_num == total_pages - 1 {
+                        for task in current_batch.drain(..) {
+                            sender.send(task).await.map_err(|_| {
+                                WeChatError::DecryptionFailed("发送页面任务失败".to_string())
+                            })?;
+                            pages_read += 1;
+                        }
                     
-                    // 让出控制权
-                    if pages_read % (batch_size * 4) == 0 {
-                        tokio::task::yield_now().await;
+                        // 让出控制权
+                        if pages_read % (batch_size * 4) == 0 {
+                            tokio::task::yield_now().await;
+                        }
                     }
                 }
-            }
             
-            debug!("读取任务完成: {} 页", pages_read);
-            Ok(pages_read)
-        })
+                debug!("读取任务完成: {} 页", pages_read);
+                Ok(pages_read)
+            }
+            .await;
+            (Stage::Read, result)
+        });
     }
     
     /// 启动处理任务池
-    async fn spawn_process_tasks(
+    fn spawn_process_tasks(
         &self,
+        tasks: &mut JoinSet<StageResult>,
         receiver: mpsc::Receiver<PageTask>,
         sender: mpsc::Sender<ProcessedPage>,
         derived_keys: Arc<super::decrypt_common::DerivedKeys>,
-    ) -> Result<Vec<tokio::task::JoinHandle<Result<usize>>>> {
+        window: Arc<ReorderWindow>,
+    ) {
         let semaphore = Arc::new(Semaphore::new(self.parallel_config.concurrent_pages));
         let receiver = Arc::new(Mutex::new(receiver));
-        let mut tasks = Vec::new();
         
         for worker_id in 0..self.parallel_config.concurrent_pages {
             let receiver = receiver.clone();
             let sender = sender.clone();
             let keys = derived_keys.clone();
             let sem = semaphore.clone();
+            let window = window.clone();
             let decrypt_config = self.config.clone();
             
-            let task = tokio::spawn(async move {
-                let mut processed = 0;
+            tasks.spawn(async move {
+                let result: Result<usize> = async move {
+                    let mut processed = 0;
                 
-                loop {
-                    let page_task = {
-                        let mut rx = receiver.lock().await;
-                        match rx.recv().await {
-                            Some(task) => task,
-                            None => break, // 通道关闭
-                        }
-                    };
+                    loop {
+                        let page_task = {
+                            let mut rx = receiver.lock().await;
+                            match rx.recv().await {
+                                Some(task) => task,
+                                None => break, // 通道关闭
+                            }
+                        };
                     
-                    let _permit = sem.acquire().await.unwrap();
-                    let page_num = page_task.page_num; // 保存页面编号
+                        // 超出重排窗口时等待写入推进，等待期间不占用并发许可
+                        window.admit(page_task.page_num).await;
+                        let _permit = sem.acquire().await.unwrap();
+                        let page_num = page_task.page_num; // 保存页面编号
                     
-                    match Self::process_page_async(page_task, &keys, &decrypt_config).await {
-                        Ok(processed_page) => {
-                            sender.send(processed_page).await.map_err(|_| {
-                                WeChatError::DecryptionFailed("发送处理结果失败".to_string())
-                            })?;
-                            processed += 1;
-                        }
-                        Err(e) => {
-                            warn!("Worker {} 处理页面失败: {}", worker_id, e);
-                            // 发送错误页面，保持顺序
-                            let error_page = ProcessedPage::error(page_num,
-                                WeChatError::DecryptionFailed(format!("页面处理失败: {}", e)));
-                            sender.send(error_page).await.ok();
+                        match Self::process_page_async(page_task, &keys, &decrypt_config).await {
+                            Ok(processed_page) => {
+                                sender.send(processed_page).await.map_err(|_| {
+                                    WeChatError::DecryptionFailed("发送处理结果失败".to_string())
+                                })?;
+                                processed += 1;
+                            }
+                            Err(e) => {
+                                warn!("Worker {} 处理页面失败: {}", worker_id, e);
+                                // 发送错误页面，保持顺序
+                                let error_page = ProcessedPage::error(page_num,
+                                    WeChatError::DecryptionFailed(format!("页面处理失败: {}", e)));
+                                sender.send(error_page).await.ok();
+                            }
                         }
-                    }
                     
-                    // 定期让出控制权
-                    if processed % 10 == 0 {
-                        tokio::task::yield_now().await;
+                        // 定期让出控制权
+                        if processed % 10 == 0 {
+                            tokio::task::yield_now().await;
+                        }
                     }
-                }
                 
-                debug!("Worker {} 完成: 处理 {} 页", worker_id, processed);
-                Ok(processed)
+                    debug!("Worker {} 完成: 处理 {} 页", worker_id, processed);
+                    Ok(processed)
+                }
+                .await;
+                (Stage::Process, result)
             });
-            
-            tasks.push(task);
         }
-        
-        Ok(tasks)
     }
     
     /// 异步处理单个页面
@@ -459,6 +708,11 @@ impl ParallelDecryptor {
                 // 对于解密失败的页面，返回原始数据作为备用
                 Ok(ProcessedPage::success(page_num, page_data_backup))
             }
+            Err(e) if e.is_panic() => {
+                let message = panic_message(e.into_panic().as_ref());
+                warn!("页面 {} 解密时 panic: {}", page_num, message);
+                Err(WeChatError::DecryptionFailed(format!("页面 {} 解密时 panic: {}", page_num, message)).into())
+            }
             Err(e) => {
                 warn!("页面 {} 处理任务失败: {}", page_num, e);
                 Err(WeChatError::DecryptionFailed(format!("页面 {} 处理任务失败: {}", page_num, e)).into())
@@ -469,63 +723,82 @@ impl ParallelDecryptor {
     /// 启动写入任务
     fn spawn_write_task(
         &self,
-        output_file: Arc<Mutex<File>>,
+        tasks: &mut JoinSet<StageResult>,
+        output_file: File,
         mut receiver: mpsc::Receiver<ProcessedPage>,
         total_pages: usize,
         progress_callback: Option<ProgressCallback>,
-    ) -> tokio::task::JoinHandle<Result<usize>> {
-        tokio::spawn(async move {
-            let mut pages_written = 0;
-            let mut pending_pages = BTreeMap::new();
-            let mut next_expected_page = 0u64;
-            let mut last_progress_report = std::time::Instant::now();
+        pages: PageBudget,
+    ) {
+        let write_buffer_size = self.parallel_config.write_buffer_size;
+        let preallocated = self.parallel_config.preallocate;
+        tasks.spawn(async move {
+            let result: Result<usize> = async move {
+                let mut output = BufWriter::with_capacity(write_buffer_size, output_file);
+                let mut bytes_written = SQLITE_HEADER.len() as u64;
+                let mut pages_written = 0;
+                let mut pending_pages = BTreeMap::new();
+                let mut next_expected_page = 0u64;
+                let mut last_progress_report = std::time::Instant::now();
             
-            while let Some(processed_page) = receiver.recv().await {
-                pending_pages.insert(processed_page.page_num, processed_page);
+                while let Some(processed_page) = receiver.recv().await {
+                    pending_pages.insert(processed_page.page_num, processed_page);
+                    pages.window.record_pending(pending_pages.len());
                 
-                // 按顺序写入连续的页面
-                while let Some(page) = pending_pages.remove(&next_expected_page) {
-                    match page.result {
-                        Ok(data) => {
-                            output_file.lock().await.write_all(&data).await?;
-                            pages_written += 1;
+                    // 按顺序写入连续的页面
+                    while let Some(page) = pending_pages.remove(&next_expected_page) {
+                        pages.release();
+                        match page.result {
+                            Ok(data) => {
+                                output.write_all(&data).await?;
+                                bytes_written += data.len() as u64;
+                                pages_written += 1;
                             
-                            // 调用进度回调
-                            if let Some(ref callback) = progress_callback {
-                                callback(pages_written as u64, total_pages as u64);
-                            }
+                                // 调用进度回调
+                                if let Some(ref callback) = progress_callback {
+                                    callback(pages_written as u64, total_pages as u64);
+                                }
                             
-                            // 定期报告进度
-                            if last_progress_report.elapsed().as_secs() >= 2 {
-                                let progress = (pages_written as f64 / total_pages as f64) * 100.0;
-                                info!("📈 解密进度: {:.1}% ({}/{})", progress, pages_written, total_pages);
-                                last_progress_report = std::time::Instant::now();
+                                // 定期报告进度
+                                if last_progress_report.elapsed().as_secs() >= 2 {
+                                    let progress = (pages_written as f64 / total_pages as f64) * 100.0;
+                                    info!("📈 解密进度: {:.1}% ({}/{})", progress, pages_written, total_pages);
+                                    last_progress_report = std::time::Instant::now();
+                                }
+                            }
+                            Err(e) => {
+                                warn!("页面 {} 写入失败: {}", next_expected_page, e);
+                                // 写入占位数据
+                                let placeholder = vec![0u8; pages.page_size];
+                                output.write_all(&placeholder).await?;
+                                bytes_written += placeholder.len() as u64;
+                                pages_written += 1;
                             }
                         }
-                        Err(e) => {
-                            warn!("页面 {} 写入失败: {}", next_expected_page, e);
-                            // 写入占位数据
-                            let placeholder = vec![0u8; 4096];
-                            output_file.lock().await.write_all(&placeholder).await?;
-                            pages_written += 1;
-                        }
-                    }
                     
-                    next_expected_page += 1;
+                        next_expected_page += 1;
+                        pages.window.advance(next_expected_page);
                     
-                    // 定期刷新缓冲区
-                    if pages_written % 100 == 0 {
-                        output_file.lock().await.flush().await?;
-                        tokio::task::yield_now().await;
+                        // 写入由缓冲区合并，这里只定期让出控制权
+                        if pages_written % 100 == 0 {
+                            tokio::task::yield_now().await;
+                        }
                     }
                 }
-            }
             
-            // 最终刷新
-            output_file.lock().await.flush().await?;
-            debug!("写入任务完成: {} 页", pages_written);
-            Ok(pages_written)
-        })
+                // 最终刷新，预分配的文件截断到实际写入的长度
+                output.flush().await?;
+                let mut output_file = output.into_inner();
+                if preallocated {
+                    output_file.set_len(bytes_written).await?;
+                }
+                output_file.flush().await?;
+                debug!("写入任务完成: {} 页", pages_written);
+                Ok(pages_written)
+            }
+            .await;
+            (Stage::Write, result)
+        });
     }
     
     /// 获取内存监控器（用于测试）
@@ -547,6 +820,22 @@ mod tests {
         assert!(config.max_memory_mb > 0);
     }
     
+    #[test]
+    fn test_parallel_options_override() {
+        let base = ParallelDecryptConfig::small_file_config();
+        assert!(!ParallelOptions::default().has_overrides());
+        let options = ParallelOptions {
+            enabled: Some(true),
+            batch_size: Some(8),
+            ..Default::default()
+        };
+        assert!(options.has_overrides());
+        let config = options.apply(base.clone());
+        assert_eq!(config.batch_size, 8);
+        assert_eq!(config.concurrent_pages, base.concurrent_pages);
+        assert_eq!(config.max_memory_mb, base.max_memory_mb);
+    }
+
     #[test]
     fn test_memory_monitor() {
         let monitor = MemoryMonitor::new(100); // 100MB
@@ -554,6 +843,15 @@ mod tests {
         assert!(monitor.current_usage_mb() < 100);
         monitor.deallocate(50 * 1024 * 1024);
         assert_eq!(monitor.current_usage_mb(), 0);
+        assert_eq!(monitor.peak_usage_mb(), 50);
+
+        // 克隆共享计数，峰值取同时占用的总量
+        let shared = monitor.clone();
+        assert_eq!(shared, monitor);
+        assert!(shared.allocate(60 * 1024 * 1024));
+        assert!(!monitor.allocate(60 * 1024 * 1024));
+        assert_eq!(shared.peak_usage_mb(), 120);
+        assert_ne!(MemoryMonitor::new(100), monitor);
     }
     
     #[tokio::test]
@@ -568,4 +866,85 @@ mod tests {
         assert_eq!(task.offset, 4096);
         assert_eq!(task.size, 4096);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_reorder_window_backpressure() {
+        let window = Arc::new(ReorderWindow::new(2));
+        window.admit(1).await;
+
+        let waiting = tokio::spawn({
+            let window = window.clone();
+            async move { window.admit(3).await }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        assert!(!waiting.is_finished());
+
+        window.advance(2);
+        tokio::time::timeout(std::time::Duration::from_secs(1), waiting)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(window.stats().stalls, 1);
+    }
+
+    #[tokio::test]
+    async fn test_parallel_decrypt_bounded_reorder() {
+        use super::super::decrypt_algorithm_v3::tests::encrypt_v3;
+
+        let dir = tempfile::tempdir().unwrap();
+        let key = [0x5au8; 32];
+        let config = DecryptConfig::v3();
+        let mut plain: Vec<u8> = (0..config.page_size * 64).map(|i| (i * 13 % 251) as u8).collect();
+        plain[..SQLITE_HEADER.len()].copy_from_slice(SQLITE_HEADER);
+        let input = dir.path().join("MSG0.db");
+        std::fs::write(&input, encrypt_v3(&plain, &key, &[0x11; 16])).unwrap();
+
+        let parallel_config = ParallelDecryptConfig {
+            concurrent_pages: 4,
+            batch_size: 8,
+            reorder_window: 4,
+            ..ParallelDecryptConfig::small_file_config()
+        };
+        let decryptor = ParallelDecryptor::new(config.clone(), parallel_config);
+        let output = dir.path().join("MSG0_decrypted.db");
+        decryptor.decrypt_database_parallel(&input, &output, &key, None).await.unwrap();
+
+        let decrypted = std::fs::read(&output).unwrap();
+        let data_end = config.page_size - config.reserve_size;
+        assert_eq!(decrypted[SQLITE_HEADER.len()..data_end], plain[SQLITE_HEADER.len()..data_end]);
+        let stats = decryptor.reorder_stats();
+        assert_eq!(stats.window, 4);
+        assert!(stats.peak_pending <= stats.window);
+    }
+
+    #[tokio::test]
+    async fn test_parallel_decrypt_preallocated_write_through() {
+        use super::super::decrypt_algorithm_v3::tests::encrypt_v3;
+
+        let dir = tempfile::tempdir().unwrap();
+        let key = [0x3cu8; 32];
+        let config = DecryptConfig::v3();
+        let mut plain: Vec<u8> = (0..config.page_size * 16).map(|i| (i * 7 % 253) as u8).collect();
+        plain[..SQLITE_HEADER.len()].copy_from_slice(SQLITE_HEADER);
+        let input = dir.path().join("MSG0.db");
+        std::fs::write(&input, encrypt_v3(&plain, &key, &[0x22; 16])).unwrap();
+
+        let mut outputs = Vec::new();
+        for (preallocate, write_through) in [(false, false), (true, true)] {
+            let parallel_config = ParallelDecryptConfig {
+                preallocate,
+                write_through,
+                ..ParallelDecryptConfig::small_file_config()
+            };
+            let output = dir.path().join(format!("MSG0_{}.db", preallocate));
+            ParallelDecryptor::new(config.clone(), parallel_config)
+                .decrypt_database_parallel(&input, &output, &key, None)
+                .await
+                .unwrap();
+            outputs.push(std::fs::read(&output).unwrap());
+        }
+        // 预分配的文件应截断到实际写入的长度，内容与普通写入一致
+        assert_eq!(outputs[0].len(), outputs[1].len());
+        assert_eq!(outputs[0], outputs[1]);
+    }
+}
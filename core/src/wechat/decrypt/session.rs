@@ -0,0 +1,202 @@
+//! 会话级临时解密
+//!
+//! 服务端解锁时把选定的数据库解密到临时目录（Linux 下优先使用内存文件系统 `/dev/shm`），
+//! 会话结束时先用零覆盖再删除，磁盘上不保留持久化的明文工作目录。
+//!
+//! Windows、macOS 没有内存文件系统，临时目录位于系统盘（如 `%TEMP%`）。覆盖写入无法保证
+//! SSD 和日志型文件系统上不残留明文，这类系统上请配合全盘加密（BitLocker、FileVault）使用。
+
+use chrono::{DateTime, Utc};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+use zeroize::Zeroizing;
+
+use super::DecryptionProcessor;
+use crate::errors::Result;
+use crate::wechat::datadir::DbCategory;
+
+/// 覆盖写入时的块大小
+const WIPE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// 临时解密会话
+///
+/// 释放时自动清除解密数据；在异步上下文中优先调用 [`DecryptedSession::lock`]，
+/// 否则清除工作交给阻塞线程池，不阻塞异步运行时。
+pub struct DecryptedSession {
+    dir: Option<TempDir>,
+    key: Zeroizing<Vec<u8>>,
+    unlocked_at: DateTime<Utc>,
+    categories: Vec<DbCategory>,
+}
+
+impl DecryptedSession {
+    /// 验证密钥后将数据目录解密到新的临时目录
+    pub async fn unlock(
+        input: &Path,
+        key: Vec<u8>,
+        categories: Vec<DbCategory>,
+        threads: Option<usize>,
+        cancel_token: CancellationToken,
+    ) -> Result<Self> {
+        let dir = tempfile::Builder::new()
+            .prefix("mwxdump-session-")
+            .tempdir_in(session_base_dir())?;
+        let session = Self {
+            dir: Some(dir),
+            key: Zeroizing::new(key.clone()),
+            unlocked_at: Utc::now(),
+            categories,
+        };
+        info!("🔓 解锁会话，临时目录: {:?}", session.path());
+
+        let output = session.path().to_path_buf();
+        DecryptionProcessor::new(input.to_path_buf(), output.clone(), key.clone(), threads, true)
+            .with_categories(session.categories.clone())
+            .execute()
+            .await?;
+        DecryptionProcessor::new(input.to_path_buf(), output, key, threads, false)
+            .with_cancellation(cancel_token)
+            .with_categories(session.categories.clone())
+            .execute()
+            .await?;
+        Ok(session)
+    }
+
+    /// 解密数据所在的临时目录
+    pub fn path(&self) -> &Path {
+        self.dir.as_ref().expect("会话目录在释放前始终存在").path()
+    }
+
+    /// 锁定会话，等待临时数据清除完成
+    pub async fn lock(mut self) {
+        if let Some(dir) = self.dir.take() {
+            if let Err(e) = tokio::task::spawn_blocking(move || wipe_session(dir)).await {
+                warn!("⚠️  清除会话数据的任务异常退出: {}", e);
+            }
+        }
+    }
+
+    /// 解锁使用的数据密钥，自动刷新时用于重新解密变化的数据库
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+
+    /// 解锁时间
+    pub fn unlocked_at(&self) -> DateTime<Utc> {
+        self.unlocked_at
+    }
+
+    /// 本次会话解密的数据库类别，为空表示全部
+    pub fn categories(&self) -> &[DbCategory] {
+        &self.categories
+    }
+}
+
+impl Drop for DecryptedSession {
+    fn drop(&mut self) {
+        let Some(dir) = self.dir.take() else {
+            return;
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                runtime.spawn_blocking(move || wipe_session(dir));
+            }
+            Err(_) => wipe_session(dir),
+        }
+    }
+}
+
+/// 覆盖并删除会话目录
+fn wipe_session(dir: TempDir) {
+    if let Err(e) = wipe_dir(dir.path()) {
+        warn!("⚠️  清除会话数据失败: {:?} - {}", dir.path(), e);
+    }
+    info!("🔒 会话已锁定，临时数据已清除: {:?}", dir.path());
+}
+
+/// 临时目录的上级目录，优先使用内存文件系统，没有时退回到磁盘上的系统临时目录
+fn session_base_dir() -> PathBuf {
+    let shm = Path::new("/dev/shm");
+    if cfg!(target_os = "linux") && shm.is_dir() {
+        return shm.to_path_buf();
+    }
+    let temp = std::env::temp_dir();
+    warn!("⚠️  没有可用的内存文件系统，会话明文将写入磁盘上的临时目录: {:?}", temp);
+    temp
+}
+
+/// 用零覆盖目录下的所有文件后删除
+fn wipe_dir(dir: &Path) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            wipe_dir(&path)?;
+            std::fs::remove_dir(&path)?;
+        } else {
+            wipe_file(&path)?;
+            std::fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+fn wipe_file(path: &Path) -> std::io::Result<()> {
+    let mut file = std::fs::OpenOptions::new().write(true).open(path)?;
+    let mut remaining = file.metadata()?.len();
+    let zeros = vec![0u8; WIPE_CHUNK_SIZE];
+    while remaining > 0 {
+        let n = remaining.min(WIPE_CHUNK_SIZE as u64) as usize;
+        file.write_all(&zeros[..n])?;
+        remaining -= n as u64;
+    }
+    file.sync_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_session_wiped_on_drop() {
+        let input = tempfile::tempdir().unwrap();
+        let session = DecryptedSession::unlock(
+            input.path(),
+            vec![0u8; 32],
+            vec![DbCategory::Message],
+            Some(1),
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+
+        let path = session.path().to_path_buf();
+        std::fs::create_dir_all(path.join("message")).unwrap();
+        std::fs::write(path.join("message").join("decrypted_message_0.db"), vec![7u8; 100_000]).unwrap();
+        assert_eq!(session.categories(), [DbCategory::Message]);
+
+        session.lock().await;
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_session_wiped_off_runtime_on_drop() {
+        let input = tempfile::tempdir().unwrap();
+        let session = DecryptedSession::unlock(input.path(), vec![0u8; 32], Vec::new(), Some(1), CancellationToken::new())
+            .await
+            .unwrap();
+        let path = session.path().to_path_buf();
+        std::fs::write(path.join("decrypted_session.db"), vec![7u8; 1000]).unwrap();
+
+        drop(session);
+        for _ in 0..100 {
+            if !path.exists() {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        panic!("会话目录未被清除: {:?}", path);
+    }
+}
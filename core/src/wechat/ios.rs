@@ -0,0 +1,492 @@
+//! iTunes / 访达备份中的微信数据
+//!
+//! 未加密的 iOS 备份目录中，`Manifest.db` 的 `Files` 表记录每个文件所属的域和相对路径，
+//! 文件本身保存为 `<fileID 前两位>/<fileID>`。微信的数据位于域 `AppDomain-com.tencent.xin`，
+//! 每个账号一个目录 `Documents/<md5(wxid)>/DB/`：
+//!
+//! - `MM.sqlite`、`message_<n>.sqlite`：消息，每个会话一张 `Chat_<md5(会话)>` 表，
+//!   `Des` 为 0 表示自己发送，群聊中他人发送的消息内容带 `发送者:\n` 前缀
+//! - `WCDB_Contact.sqlite`：联系人，`Friend` 表的 `dbContactRemark` 为 protobuf 编码的昵称和备注
+//!
+//! iOS 上的数据库没有加密。导入时先把账号的数据库提取到临时目录，再按解密输出的目录结构写入工作目录。
+
+use md5::{Digest, Md5};
+use prost::Message as _;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::errors::{MwxDumpError, Result, WeChatError};
+use crate::wechat::datadir::{open_read_only, SourceGuard};
+use crate::wechat::db::writer::{ImportedContact, ImportedMessage, WorkDirWriter};
+use crate::wechat::db::{DataSource, FilterOp, Query, SqlValue, SqliteDataSource};
+
+/// 备份清单数据库的文件名
+pub const MANIFEST_DB: &str = "Manifest.db";
+
+/// 微信在备份中的域
+pub const WECHAT_DOMAIN: &str = "AppDomain-com.tencent.xin";
+
+/// 联系人数据库的文件名
+const CONTACT_DB: &str = "WCDB_Contact.sqlite";
+
+/// 每次从消息表读取的行数
+const MESSAGE_BATCH: u32 = 5000;
+
+/// 清单中表示普通文件的 `flags`
+const FLAG_FILE: i64 = 1;
+
+/// `Friend.dbContactRemark`
+#[derive(Clone, PartialEq, prost::Message)]
+struct ContactRemark {
+    #[prost(string, tag = "1")]
+    nickname: String,
+    #[prost(string, tag = "3")]
+    remark: String,
+}
+
+/// 清单中的文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestFile {
+    /// 文件ID（域和相对路径的 SHA-1）
+    pub file_id: String,
+    /// 在应用目录中的相对路径
+    pub relative_path: String,
+}
+
+impl ManifestFile {
+    /// 文件在备份目录中的位置
+    pub fn source_path(&self, backup: &Path) -> PathBuf {
+        backup.join(self.file_id.get(..2).unwrap_or_default()).join(&self.file_id)
+    }
+}
+
+/// 导入统计
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportReport {
+    /// 账号目录名（wxid 的 MD5）
+    pub account: String,
+    /// 从联系人中识别出的当前账号 wxid
+    pub self_wxid: Option<String>,
+    /// 提取的数据库文件数
+    pub databases: usize,
+    /// 联系人数
+    pub contacts: usize,
+    /// 有消息的会话数
+    pub conversations: usize,
+    /// 写入的消息数
+    pub messages: u64,
+    /// 耗时（毫秒）
+    pub elapsed_ms: u64,
+}
+
+/// 目录是否为 iOS 备份（包含 `Manifest.db`）
+pub fn is_ios_backup(path: &Path) -> bool {
+    path.join(MANIFEST_DB).is_file()
+}
+
+/// 文本的 MD5（小写十六进制），即账号目录名和消息表名中的部分
+fn md5_hex(text: &str) -> String {
+    hex::encode(Md5::digest(text.as_bytes()))
+}
+
+/// iOS 备份
+pub struct IosBackup {
+    dir: PathBuf,
+    files: Vec<ManifestFile>,
+    cancel_token: CancellationToken,
+}
+
+impl IosBackup {
+    /// 读取备份清单中微信的文件
+    pub async fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        let unreadable = |e: anyhow::Error| {
+            WeChatError::DecryptionFailed(format!("无法读取 {:?}，暂不支持加密的 iOS 备份: {}", dir.join(MANIFEST_DB), e))
+        };
+        let manifest = SqliteDataSource::open(&dir.join(MANIFEST_DB)).await.map_err(unreadable)?;
+        let rows = manifest
+            .fetch(
+                &Query::select("Files")
+                    .columns(["fileID", "relativePath"])
+                    .filter("domain", FilterOp::Eq, SqlValue::Text(WECHAT_DOMAIN.to_string()))
+                    .filter("flags", FilterOp::Eq, SqlValue::Integer(FLAG_FILE)),
+            )
+            .await
+            .map_err(unreadable)?;
+        let files = rows
+            .iter()
+            .filter_map(|row| {
+                Some(ManifestFile {
+                    file_id: row["fileID"].as_str()?.to_string(),
+                    relative_path: row["relativePath"].as_str()?.to_string(),
+                })
+            })
+            .collect();
+        Ok(Self {
+            dir,
+            files,
+            cancel_token: CancellationToken::new(),
+        })
+    }
+
+    /// 设置取消令牌，取消后停止导入剩余的消息表
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel_token = token;
+        self
+    }
+
+    /// 备份中微信的文件
+    pub fn files(&self) -> &[ManifestFile] {
+        &self.files
+    }
+
+    /// 备份中的微信账号目录名（wxid 的 MD5），按名称排序
+    pub fn accounts(&self) -> Vec<String> {
+        let mut accounts: Vec<String> = self
+            .files
+            .iter()
+            .filter_map(|file| {
+                let account = file.relative_path.strip_prefix("Documents/")?.strip_suffix("/DB/MM.sqlite")?;
+                (account.len() == 32 && account.bytes().all(|b| b.is_ascii_hexdigit())).then(|| account.to_string())
+            })
+            .collect();
+        accounts.sort();
+        accounts
+    }
+
+    /// 按 wxid 或账号目录名选择账号；未指定时备份中只能有一个账号
+    pub fn resolve_account(&self, account: Option<&str>) -> Result<String> {
+        let accounts = self.accounts();
+        let selected = match account {
+            Some(account) => [account.to_lowercase(), md5_hex(account)]
+                .into_iter()
+                .find(|candidate| accounts.contains(candidate)),
+            None if accounts.len() == 1 => accounts.first().cloned(),
+            None => None,
+        };
+        selected.ok_or_else(|| {
+            WeChatError::DataDirNotFound {
+                path: format!("{:?} 中的微信账号（可选: {}）", self.dir, accounts.join(", ")),
+            }
+            .into()
+        })
+    }
+
+    /// 把账号的数据库提取到 `output`，保持在应用目录中的相对路径，返回提取的文件
+    pub fn extract(&self, account: &str, output: &Path) -> Result<Vec<PathBuf>> {
+        SourceGuard::new().with_root(&self.dir).check_output(output)?;
+        let prefix = format!("Documents/{}/DB/", account);
+        let mut extracted = Vec::new();
+        for file in &self.files {
+            if !file.relative_path.starts_with(&prefix) || !file.relative_path.ends_with(".sqlite") {
+                continue;
+            }
+            // 相对路径来自清单，只接受普通的路径分量
+            let relative = Path::new(&file.relative_path);
+            if !relative.components().all(|c| matches!(c, std::path::Component::Normal(_))) {
+                warn!("跳过异常路径: {}", file.relative_path);
+                continue;
+            }
+            let target = output.join(relative);
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let mut source = open_read_only(&file.source_path(&self.dir))?;
+            std::io::copy(&mut source, &mut std::fs::File::create(&target)?)?;
+            extracted.push(target);
+        }
+        Ok(extracted)
+    }
+
+    /// 导入账号的聊天记录到 `output`，生成与解密输出相同的工作目录结构
+    pub async fn import(&self, account: &str, output: &Path) -> Result<ImportReport> {
+        let started = Instant::now();
+        SourceGuard::new().with_root(&self.dir).check_output(output)?;
+        info!("📱 导入 iOS 备份 {:?} 中的账号 {} -> {:?}", self.dir, account, output);
+
+        let temp = tempfile::tempdir()?;
+        let databases = self.extract(account, temp.path())?;
+        let db_dir = temp.path().join("Documents").join(account).join("DB");
+        let contacts = match db_dir.join(CONTACT_DB).is_file() {
+            true => load_contacts(&SqliteDataSource::open(&db_dir.join(CONTACT_DB)).await?).await?,
+            false => Vec::new(),
+        };
+        let self_wxid = contacts
+            .iter()
+            .find(|contact| md5_hex(&contact.username) == account)
+            .map(|contact| contact.username.clone());
+        let talkers: HashMap<String, String> = contacts
+            .iter()
+            .map(|contact| (md5_hex(&contact.username), contact.username.clone()))
+            .collect();
+
+        let mut report = ImportReport {
+            account: account.to_string(),
+            self_wxid: self_wxid.clone(),
+            databases: databases.len(),
+            contacts: contacts.len(),
+            ..Default::default()
+        };
+        let importer = ChatImporter {
+            self_wxid: self_wxid.unwrap_or_else(|| account.to_string()),
+            talkers,
+        };
+        let mut writer = WorkDirWriter::create(output).await?;
+        for path in &databases {
+            let is_message_db = path.file_name().and_then(|name| name.to_str()).is_some_and(|name| {
+                name == "MM.sqlite" || (name.starts_with("message_") && name.ends_with(".sqlite"))
+            });
+            if !is_message_db {
+                continue;
+            }
+            let source = SqliteDataSource::open(path).await?;
+            for table in chat_tables(&source).await? {
+                if self.cancel_token.is_cancelled() {
+                    return Err(MwxDumpError::Cancelled.into());
+                }
+                if importer.import_table(&source, &table, &mut writer).await? > 0 {
+                    report.conversations += 1;
+                }
+            }
+        }
+        report.messages = writer.finish(&contacts).await?;
+        report.elapsed_ms = started.elapsed().as_millis() as u64;
+        info!(
+            "✅ iOS 备份导入完成: {} 个会话，{} 条消息，{} 个联系人",
+            report.conversations, report.messages, report.contacts
+        );
+        Ok(report)
+    }
+}
+
+/// 把 `Chat_` 表转换为 4.x 的消息
+struct ChatImporter {
+    /// 自己发送的消息的发送者
+    self_wxid: String,
+    /// 会话 MD5 到用户名
+    talkers: HashMap<String, String>,
+}
+
+impl ChatImporter {
+    /// 按 `MesLocalID` 分批导入一张消息表，返回导入的消息数
+    async fn import_table(&self, source: &SqliteDataSource, table: &str, writer: &mut WorkDirWriter) -> Result<u64> {
+        let hash = table.trim_start_matches("Chat_");
+        // 不在联系人中的会话（如已删除的好友）以 MD5 作为会话ID
+        let talker = self.talkers.get(hash).map_or(hash, String::as_str);
+        let is_chatroom = talker.ends_with("@chatroom");
+        let mut last_id = 0;
+        let mut count = 0;
+        loop {
+            let rows = source
+                .fetch(
+                    &Query::select(table)
+                        .columns(["MesLocalID", "CreateTime", "Message", "Type", "Des"])
+                        .filter("MesLocalID", FilterOp::Gt, SqlValue::Integer(last_id))
+                        .order_by("MesLocalID", false)
+                        .limit(MESSAGE_BATCH),
+                )
+                .await?;
+            for row in &rows {
+                last_id = row["MesLocalID"].as_i64().unwrap_or(last_id);
+                let content = row["Message"].as_str().unwrap_or_default().to_string();
+                let is_sender = row["Des"].as_i64() == Some(0);
+                let sender = if is_sender {
+                    self.self_wxid.clone()
+                } else if is_chatroom {
+                    content.split_once(":\n").map(|(sender, _)| sender.to_string()).unwrap_or_default()
+                } else {
+                    talker.to_string()
+                };
+                let message = ImportedMessage {
+                    create_time: row["CreateTime"].as_i64().unwrap_or_default(),
+                    msg_type: row["Type"].as_i64().unwrap_or(1),
+                    sender,
+                    is_sender,
+                    content,
+                    ..Default::default()
+                };
+                writer.insert_message(talker, &message).await?;
+                count += 1;
+            }
+            if rows.len() < MESSAGE_BATCH as usize {
+                return Ok(count);
+            }
+        }
+    }
+}
+
+/// 消息数据库中的 `Chat_<md5>` 表
+async fn chat_tables(source: &SqliteDataSource) -> Result<Vec<String>> {
+    let rows = source
+        .fetch(
+            &Query::select("sqlite_master")
+                .columns(["name"])
+                .filter("type", FilterOp::Eq, SqlValue::Text("table".to_string())),
+        )
+        .await?;
+    Ok(rows
+        .iter()
+        .filter_map(|row| row["name"].as_str())
+        .filter(|name| {
+            name.strip_prefix("Chat_")
+                .is_some_and(|hash| hash.len() == 32 && hash.bytes().all(|b| b.is_ascii_hexdigit()))
+        })
+        .map(str::to_string)
+        .collect())
+}
+
+/// 读取联系人，昵称和备注解析自 `dbContactRemark`
+async fn load_contacts(source: &SqliteDataSource) -> Result<Vec<ImportedContact>> {
+    let rows = source
+        .fetch(&Query::select("Friend").columns(["userName", "dbContactRemark"]))
+        .await?;
+    let non_empty = |text: String| (!text.is_empty()).then_some(text);
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            let username = row["userName"].as_str().filter(|name| !name.is_empty())?.to_string();
+            // BLOB 列读出为十六进制字符串
+            let remark = row["dbContactRemark"]
+                .as_str()
+                .and_then(|blob| hex::decode(blob).ok())
+                .and_then(|blob| ContactRemark::decode(blob.as_slice()).ok())
+                .unwrap_or_default();
+            Some(ImportedContact {
+                username,
+                nickname: non_empty(remark.nickname),
+                remark: non_empty(remark.remark),
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wechat::db::contacts::ContactRepository;
+    use crate::wechat::db::messages::MessageRepository;
+    use crate::wechat::db::sessions::SessionRepository;
+    use sqlx::sqlite::SqliteConnectOptions;
+    use sqlx::ConnectOptions;
+
+    /// 创建数据库并执行语句
+    async fn create_db(path: &Path, statements: &[String]) {
+        let mut conn = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true)
+            .connect()
+            .await
+            .unwrap();
+        for sql in statements {
+            sqlx::query(sql).execute(&mut conn).await.unwrap();
+        }
+    }
+
+    /// 按 iOS 备份的布局写入文件并登记到清单
+    async fn create_backup(dir: &Path, account: &str) {
+        let staging = tempfile::tempdir().unwrap();
+        let friend = format!("Chat_{}", md5_hex("wxid_friend"));
+        let room = format!("Chat_{}", md5_hex("123@chatroom"));
+        let gone = format!("Chat_{}", md5_hex("wxid_gone"));
+        let chat = |table: &str| {
+            format!(
+                "CREATE TABLE {} (MesLocalID INTEGER PRIMARY KEY, MesSvrID INTEGER, CreateTime INTEGER, \
+                 Message TEXT, Status INTEGER, ImgStatus INTEGER, Type INTEGER, Des INTEGER)",
+                table
+            )
+        };
+        create_db(
+            &staging.path().join("MM.sqlite"),
+            &[
+                chat(&friend),
+                chat(&room),
+                chat(&gone),
+                format!("INSERT INTO {} VALUES (1, 0, 1700000000, '你好', 0, 0, 1, 1)", friend),
+                format!("INSERT INTO {} VALUES (2, 0, 1700000060, '在吗', 0, 0, 1, 0)", friend),
+                format!("INSERT INTO {} VALUES (1, 0, 1700000100, 'wxid_b:\nhi all', 0, 0, 1, 1)", room),
+                format!("INSERT INTO {} VALUES (1, 0, 1600000000, '很久以前', 0, 0, 1, 1)", gone),
+                "CREATE TABLE Hello (x INTEGER)".to_string(),
+            ],
+        )
+        .await;
+        let remark = |nickname: &str, remark: &str| {
+            hex::encode(
+                ContactRemark {
+                    nickname: nickname.to_string(),
+                    remark: remark.to_string(),
+                }
+                .encode_to_vec(),
+            )
+        };
+        create_db(
+            &staging.path().join(CONTACT_DB),
+            &[
+                "CREATE TABLE Friend (userName TEXT, dbContactRemark BLOB, type INTEGER)".to_string(),
+                format!("INSERT INTO Friend VALUES ('wxid_self', X'{}', 1)", remark("我", "")),
+                format!("INSERT INTO Friend VALUES ('wxid_friend', X'{}', 1)", remark("老友", "大学同学")),
+                format!("INSERT INTO Friend VALUES ('123@chatroom', X'{}', 2)", remark("周末爬山", "")),
+            ],
+        )
+        .await;
+
+        let files = [
+            ("0a1b2c", format!("Documents/{}/DB/MM.sqlite", account), "MM.sqlite"),
+            ("3d4e5f", format!("Documents/{}/DB/{}", account, CONTACT_DB), CONTACT_DB),
+        ];
+        let mut statements = vec![
+            "CREATE TABLE Files (fileID TEXT PRIMARY KEY, domain TEXT, relativePath TEXT, flags INTEGER, file BLOB)"
+                .to_string(),
+            format!("INSERT INTO Files VALUES ('9f9f9f', '{}', 'Documents/{}', 2, NULL)", WECHAT_DOMAIN, account),
+            "INSERT INTO Files VALUES ('8e8e8e', 'HomeDomain', 'Library/Preferences/a.plist', 1, NULL)".to_string(),
+        ];
+        for (file_id, relative_path, name) in files {
+            std::fs::create_dir_all(dir.join(&file_id[..2])).unwrap();
+            std::fs::copy(staging.path().join(name), dir.join(&file_id[..2]).join(file_id)).unwrap();
+            statements.push(format!(
+                "INSERT INTO Files VALUES ('{}', '{}', '{}', 1, NULL)",
+                file_id, WECHAT_DOMAIN, relative_path
+            ));
+        }
+        create_db(&dir.join(MANIFEST_DB), &statements).await;
+    }
+
+    #[tokio::test]
+    async fn test_import_ios_backup() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("00008030-001A");
+        std::fs::create_dir_all(&dir).unwrap();
+        let account = md5_hex("wxid_self");
+        create_backup(&dir, &account).await;
+        assert!(is_ios_backup(&dir));
+
+        let backup = IosBackup::open(&dir).await.unwrap();
+        assert_eq!(backup.files().len(), 2);
+        assert_eq!(backup.accounts(), std::slice::from_ref(&account));
+        assert_eq!(backup.resolve_account(None).unwrap(), account);
+        assert_eq!(backup.resolve_account(Some("wxid_self")).unwrap(), account);
+        assert!(backup.resolve_account(Some("wxid_other")).is_err());
+        assert!(backup.import(&account, &dir.join("out")).await.is_err());
+
+        let output = root.path().join("out");
+        let report = backup.import(&account, &output).await.unwrap();
+        assert_eq!(report.self_wxid.as_deref(), Some("wxid_self"));
+        assert_eq!((report.databases, report.contacts, report.conversations, report.messages), (2, 3, 3, 4));
+
+        let repo = MessageRepository::open(&output).await.unwrap().with_self_wxid("wxid_self");
+        let messages = repo.page("wxid_friend", None, 10).await.unwrap();
+        assert_eq!(messages.iter().map(|m| m.content.as_str()).collect::<Vec<_>>(), ["你好", "在吗"]);
+        assert!(!messages[0].is_self && messages[1].is_self);
+        let room = repo.page("123@chatroom", None, 10).await.unwrap();
+        assert_eq!((room[0].sender.as_str(), room[0].content.as_str()), ("wxid_b", "hi all"));
+        assert_eq!(repo.page(&md5_hex("wxid_gone"), None, 10).await.unwrap().len(), 1);
+
+        let contacts = ContactRepository::open(&output).await.unwrap();
+        assert_eq!(contacts.display_name("wxid_friend"), Some("大学同学"));
+        let sessions = SessionRepository::open(&output).await.unwrap();
+        assert_eq!(sessions.all()[0].username, "123@chatroom");
+    }
+}
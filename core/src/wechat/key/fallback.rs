@@ -0,0 +1,48 @@
+//! 不受支持平台的密钥提取实现
+//!
+//! 内存读取依赖平台 API，在其他平台上统一返回明确的错误，
+//! 调用方可以改用配置中的预设密钥。
+
+use crate::errors::{Result, WeChatError};
+use crate::wechat::key::{KeyExtractor, KeyOffset, KeyStrategy, KeyVersion, WeChatKey};
+use crate::wechat::process::WechatProcessInfo;
+use async_trait::async_trait;
+
+const UNSUPPORTED_MESSAGE: &str = "当前平台不支持从进程内存提取密钥，请通过配置或参数提供密钥";
+
+/// 不受支持平台上没有可用的内存提取策略
+pub fn platform_strategies(_offsets: Vec<KeyOffset>) -> Result<Vec<Box<dyn KeyStrategy>>> {
+    Ok(Vec::new())
+}
+
+#[derive(Clone)]
+pub struct FallbackKeyExtractor {}
+
+impl FallbackKeyExtractor {
+    pub fn new() -> Result<Self> {
+        Ok(Self {})
+    }
+}
+
+#[async_trait]
+impl KeyExtractor for FallbackKeyExtractor {
+    async fn extract_key(&self, _process: &WechatProcessInfo) -> Result<WeChatKey> {
+        Err(WeChatError::KeyExtractionFailed(UNSUPPORTED_MESSAGE.to_string()).into())
+    }
+
+    async fn search_key_in_memory(
+        &self,
+        _memory: &[u8],
+        _process: &WechatProcessInfo,
+    ) -> Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    async fn validate_key(&self, _key: &[u8]) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn supported_version(&self) -> KeyVersion {
+        KeyVersion::V40
+    }
+}
@@ -5,6 +5,7 @@ use crate::wechat::process::WechatProcessInfo;
 use crate::errors::Result;
 use super::WeChatKey;
 use super::KeyVersion;
+use super::strategy::{KeyOffset, KeyStrategyChain};
 
 /// 平台特定的密钥提取器
 #[cfg(target_os = "windows")]
@@ -13,6 +14,9 @@ pub type PlatformKeyExtractor = super::windows::KeyExtractor;
 #[cfg(target_os = "macos")]
 pub type PlatformKeyExtractor = macos::MacOSKeyExtractor;
 
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub type PlatformKeyExtractor = super::fallback::FallbackKeyExtractor;
+
 /// 密钥提取器接口
 #[async_trait]
 pub trait KeyExtractor: Send + Sync {
@@ -27,10 +31,43 @@ pub trait KeyExtractor: Send + Sync {
 
     /// 获取支持的密钥版本
     fn supported_version(&self) -> KeyVersion;
+
+    /// 依次从每个微信主进程提取密钥（多开时每个账号一个），密钥标记了所属账号
+    ///
+    /// 单个进程失败时记录警告并继续，全部失败时返回最后一个错误。
+    async fn extract_all_keys(&self, processes: &[WechatProcessInfo]) -> Result<Vec<WeChatKey>> {
+        let mut keys = Vec::with_capacity(processes.len());
+        let mut last_error = None;
+        for process in processes {
+            match self.extract_key(process).await {
+                Ok(key) => keys.push(key.with_account(process)),
+                Err(e) => {
+                    tracing::warn!("进程 {} 密钥提取失败: {}", process.pid, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        match last_error {
+            Some(e) if keys.is_empty() => Err(e),
+            _ => Ok(keys),
+        }
+    }
 }
 
-/// 创建平台特定的密钥提取器
-pub fn create_key_extractor() -> Result<PlatformKeyExtractor> {
-    PlatformKeyExtractor::new()
+/// 创建平台特定的密钥提取策略链（不含配置偏移）
+pub fn create_key_extractor() -> Result<KeyStrategyChain> {
+    create_key_extractor_with_offsets(Vec::new())
+}
+
+/// 创建平台特定的密钥提取策略链
+///
+/// 策略顺序：模块相对搜索 → 堆内存特征扫描 → 配置偏移（`offsets` 为空时跳过）。
+pub fn create_key_extractor_with_offsets(offsets: Vec<KeyOffset>) -> Result<KeyStrategyChain> {
+    #[cfg(target_os = "windows")]
+    let strategies = super::windows::platform_strategies(offsets)?;
+    #[cfg(not(target_os = "windows"))]
+    let strategies = super::fallback::platform_strategies(offsets)?;
+
+    Ok(KeyStrategyChain::new(strategies))
 }
 
@@ -4,14 +4,24 @@
 
 pub mod key_extractor;
 pub mod key_version;
+pub mod pattern_finder;
+pub mod ranking;
+pub mod snapshot;
+pub mod strategy;
 pub mod wechatkey;
 
 #[cfg(target_os = "windows")]
 mod windows;
+#[cfg(not(target_os = "windows"))]
+mod fallback;
 // #[cfg(target_os = "macos")]
 // mod macos;
 
 pub use key_extractor::KeyExtractor;
 pub use key_version::KeyVersion;
+pub use pattern_finder::{find_key_pattern, PatternReport, PatternSearchConfig};
+pub use ranking::{rank_keys, validate_key_in_dir, ProbeResult, RankedKey};
+pub use snapshot::SnapshotKeyExtractor;
+pub use strategy::{KeyOffset, KeyStrategy, KeyStrategyChain, KeyStrategyKind, StrategyAttempt};
 pub use wechatkey::WeChatKey;
 pub use wechatkey::KeyValidator;
\ No newline at end of file
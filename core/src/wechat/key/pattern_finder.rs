@@ -0,0 +1,250 @@
+//! 密钥特征辅助定位
+//!
+//! 微信更新后内置特征失效时，用已知正确的密钥在内存快照中反查：
+//! 先找到密钥本身所在的地址，再逐层查找指向它的指针，输出每一处的上下文字节。
+//! 第一层指针之后的字节即为 `signatures.toml` 中 `pattern` 的候选，
+//! 更深的指针链有助于判断密钥结构属于哪个对象。
+
+use serde::Serialize;
+
+use crate::errors::Result;
+use crate::utils::memory_snapshot::MemorySnapshot;
+
+/// 搜索参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PatternSearchConfig {
+    /// 每处地址前后输出的字节数
+    pub context: usize,
+    /// 指针链的最大深度
+    pub max_depth: usize,
+    /// 第二层起，指针可以指向目标之前不超过此距离的位置（结构体起始地址）
+    pub max_field_offset: usize,
+    /// 每层最多保留的结果数
+    pub max_results: usize,
+}
+
+impl Default for PatternSearchConfig {
+    fn default() -> Self {
+        Self {
+            context: 32,
+            max_depth: 2,
+            max_field_offset: 0x100,
+            max_results: 32,
+        }
+    }
+}
+
+/// 一段带地址的内存
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ByteContext {
+    /// 起始地址
+    pub address: usize,
+    /// 字节内容
+    pub bytes: Vec<u8>,
+}
+
+/// 指针链中的一个指针
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PointerLink {
+    /// 层级，1 表示直接指向密钥
+    pub depth: usize,
+    /// 指针所在地址
+    pub location: usize,
+    /// 指针的值
+    pub value: usize,
+    /// 指向的目标：密钥地址或上一层指针的地址
+    pub target: usize,
+    /// 指针所在位置的上下文
+    pub context: ByteContext,
+}
+
+impl PointerLink {
+    /// 目标相对指针值的偏移，即目标在所指结构中的字段偏移
+    pub fn field_offset(&self) -> usize {
+        self.target - self.value
+    }
+}
+
+/// 搜索结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PatternReport {
+    /// 指针大小
+    pub pointer_size: usize,
+    /// 密钥在内存中的位置及上下文
+    pub key_locations: Vec<ByteContext>,
+    /// 按层级排列的指针
+    pub pointers: Vec<PointerLink>,
+}
+
+impl PatternReport {
+    /// 第一层指针之后的字节，可作为 `pattern` 的候选；对应的 `pointer_offset` 为负的指针大小
+    pub fn candidate_pattern(&self, link: &PointerLink, len: usize) -> Option<Vec<u8>> {
+        let start = link.location + self.pointer_size - link.context.address;
+        link.context.bytes.get(start..start + len).map(<[u8]>::to_vec)
+    }
+}
+
+/// 在快照中定位密钥及指向它的指针链
+///
+/// 快照按块保存大区域，恰好跨越块边界的密钥或指针会被漏掉。
+pub fn find_key_pattern(snapshot: &MemorySnapshot, key: &[u8], config: &PatternSearchConfig) -> Result<PatternReport> {
+    let pointer_size = snapshot.index().process.pointer_size;
+    let mut report = PatternReport {
+        pointer_size,
+        ..Default::default()
+    };
+    if key.is_empty() {
+        return Ok(report);
+    }
+
+    let mut addresses = Vec::new();
+    for region in &snapshot.index().regions {
+        let data = snapshot.read_region(region)?;
+        addresses.extend(
+            data.windows(key.len())
+                .enumerate()
+                .filter(|(_, window)| *window == key)
+                .map(|(offset, _)| region.address + offset),
+        );
+        if addresses.len() >= config.max_results {
+            addresses.truncate(config.max_results);
+            break;
+        }
+    }
+    report.key_locations = addresses
+        .iter()
+        .filter_map(|&address| read_context(snapshot, address, key.len(), config.context))
+        .collect();
+    tracing::info!("密钥在快照中出现 {} 次", addresses.len());
+
+    let mut targets = addresses;
+    for depth in 1..=config.max_depth {
+        if targets.is_empty() {
+            break;
+        }
+        // 密钥缓冲区由指针直接指向，更深层的指针通常指向结构体起始地址
+        let max_offset = if depth == 1 { 0 } else { config.max_field_offset };
+        let links = find_pointers(snapshot, &targets, max_offset, config.max_results)?;
+        tracing::info!("第 {} 层找到 {} 个指针", depth, links.len());
+        targets = links.iter().map(|(location, _, _)| *location).collect();
+        targets.sort_unstable();
+        targets.dedup();
+        report.pointers.extend(links.into_iter().filter_map(|(location, value, target)| {
+            Some(PointerLink {
+                depth,
+                location,
+                value,
+                target,
+                context: read_context(snapshot, location, pointer_size, config.context)?,
+            })
+        }));
+    }
+    Ok(report)
+}
+
+/// 查找值落在 `[目标 - max_offset, 目标]` 内的对齐指针，返回（指针地址，指针值，目标）
+fn find_pointers(
+    snapshot: &MemorySnapshot,
+    targets: &[usize],
+    max_offset: usize,
+    max_results: usize,
+) -> Result<Vec<(usize, usize, usize)>> {
+    let pointer_size = snapshot.index().process.pointer_size;
+    let mut targets = targets.to_vec();
+    targets.sort_unstable();
+
+    let mut links = Vec::new();
+    for region in &snapshot.index().regions {
+        let data = snapshot.read_region(region)?;
+        let skip = (pointer_size - region.address % pointer_size) % pointer_size;
+        for (index, chunk) in data.get(skip..).unwrap_or_default().chunks_exact(pointer_size).enumerate() {
+            let value = read_pointer(chunk);
+            if value == 0 {
+                continue;
+            }
+            let position = targets.partition_point(|&t| t < value);
+            let Some(&target) = targets.get(position).filter(|&&t| t - value <= max_offset) else {
+                continue;
+            };
+            links.push((region.address + skip + index * pointer_size, value, target));
+            if links.len() >= max_results {
+                return Ok(links);
+            }
+        }
+    }
+    Ok(links)
+}
+
+/// 按小端序解析指针
+pub(crate) fn read_pointer(bytes: &[u8]) -> usize {
+    let mut buffer = [0u8; 8];
+    buffer[..bytes.len()].copy_from_slice(bytes);
+    u64::from_le_bytes(buffer) as usize
+}
+
+/// 读取 `[address - context, address + len + context)`，裁剪到所在区域内
+fn read_context(snapshot: &MemorySnapshot, address: usize, len: usize, context: usize) -> Option<ByteContext> {
+    let region = snapshot.index().regions.iter().find(|r| r.contains(address))?;
+    let start = address.saturating_sub(context).max(region.address);
+    let end = (address + len + context).min(region.address + region.size);
+    Some(ByteContext {
+        address: start,
+        bytes: snapshot.read(start, end - start)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::memory_snapshot::{SnapshotProcess, SnapshotRegions, SnapshotWriter};
+
+    #[test]
+    fn test_find_key_pattern() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wechat.mwxsnap");
+        let key: Vec<u8> = (1..=32).collect();
+        let process = SnapshotProcess {
+            pid: 1,
+            name: "Weixin.exe".to_string(),
+            version: "4.0.3.22".to_string(),
+            architecture: "x64".to_string(),
+            pointer_size: 8,
+        };
+
+        // 0x10040 处为密钥；0x20010 处的结构体在偏移 8 处保存密钥指针，之后是特征字节；
+        // 0x30000 处保存指向该结构体起始地址的指针
+        let mut heap = vec![0u8; 0x100];
+        heap[0x40..0x60].copy_from_slice(&key);
+        let mut module = vec![0u8; 0x40];
+        module[0x18..0x20].copy_from_slice(&0x10040u64.to_le_bytes());
+        module[0x20..0x28].copy_from_slice(&[0x20, 0, 0, 0, 0, 0, 0, 0]);
+        let mut object = vec![0u8; 0x10];
+        object[..8].copy_from_slice(&0x20010u64.to_le_bytes());
+
+        let mut writer = SnapshotWriter::create(&path, process, SnapshotRegions::Readable).unwrap();
+        writer.add_region(0x10000, &heap).unwrap();
+        writer.add_region(0x20000, &module).unwrap();
+        writer.add_region(0x30000, &object).unwrap();
+        writer.finish().unwrap();
+
+        let snapshot = MemorySnapshot::open(&path).unwrap();
+        let config = PatternSearchConfig {
+            context: 16,
+            ..Default::default()
+        };
+        let report = find_key_pattern(&snapshot, &key, &config).unwrap();
+
+        assert_eq!(report.key_locations.len(), 1);
+        assert_eq!(report.key_locations[0].address, 0x10030);
+        assert_eq!(report.key_locations[0].bytes.len(), 64);
+
+        assert_eq!(report.pointers.len(), 2);
+        let direct = &report.pointers[0];
+        assert_eq!((direct.depth, direct.location, direct.field_offset()), (1, 0x20018, 0));
+        assert_eq!(report.candidate_pattern(direct, 8).unwrap(), [0x20, 0, 0, 0, 0, 0, 0, 0]);
+
+        let parent = &report.pointers[1];
+        assert_eq!((parent.depth, parent.location, parent.target), (2, 0x30000, 0x20018));
+        assert_eq!(parent.field_offset(), 8);
+    }
+}
@@ -0,0 +1,188 @@
+//! 候选密钥排序
+//!
+//! 一个进程树中可能登录了多个账号，内存中会有多个通过验证的32字节候选密钥。
+//! 这里用各账号数据库目录中的代表数据库（每个类别取第一个）逐一试解密，
+//! 按能解密的数据库数量从多到少排序；数量相同时保持提取顺序。
+//! [`validate_key_in_dir`] 用同样的代表数据库检查单个密钥，逐个类别给出结果。
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::WeChatKey;
+use crate::errors::Result;
+use crate::wechat::datadir::{DbCatalog, DbCategory, DbEntry};
+use crate::wechat::decrypt::decrypt_validator::KeyValidator;
+use crate::wechat::decrypt::DecryptVersion;
+
+/// 排序后的候选密钥
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RankedKey {
+    /// 候选密钥
+    pub key: WeChatKey,
+    /// 能解密的数据库
+    pub databases: Vec<PathBuf>,
+}
+
+impl RankedKey {
+    /// 能解密的数据库数量
+    pub fn score(&self) -> usize {
+        self.databases.len()
+    }
+}
+
+/// 去掉重复的候选密钥，保留第一次出现的位置
+pub fn dedup_keys(candidates: Vec<WeChatKey>) -> Vec<WeChatKey> {
+    let mut seen = HashSet::new();
+    candidates
+        .into_iter()
+        .filter(|key| seen.insert(key.key_data.clone()))
+        .collect()
+}
+
+/// 单个数据库的试解密结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProbeResult {
+    /// 数据库类别
+    pub category: DbCategory,
+    /// 数据库路径
+    pub path: PathBuf,
+    /// 能解密时为对应的数据库版本
+    pub version: Option<DecryptVersion>,
+}
+
+/// 各数据库目录中用于试解密的数据库，每个类别取第一个
+pub fn probe_databases(db_storage_roots: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut probes = Vec::new();
+    for root in db_storage_roots {
+        probes.extend(category_probes(root)?.into_iter().map(|entry| entry.path));
+    }
+    Ok(probes)
+}
+
+/// 数据库目录中每个类别的第一个数据库
+fn category_probes(db_storage: &Path) -> Result<Vec<DbEntry>> {
+    let mut categories = HashSet::new();
+    Ok(DbCatalog::scan(db_storage)?
+        .entries
+        .into_iter()
+        .filter(|entry| categories.insert(entry.category))
+        .collect())
+}
+
+/// 用数据库目录中每个类别的代表数据库验证密钥，按类别顺序返回结果
+pub async fn validate_key_in_dir(key: &[u8], db_storage: &Path) -> Result<Vec<ProbeResult>> {
+    let validator = KeyValidator::new();
+    let mut results = Vec::new();
+    for entry in category_probes(db_storage)? {
+        let version = validator.validate_key_auto(&entry.path, key).await.unwrap_or_else(|e| {
+            tracing::debug!("验证 {:?} 失败: {}", entry.path, e);
+            None
+        });
+        results.push(ProbeResult {
+            category: entry.category,
+            path: entry.path,
+            version,
+        });
+    }
+    Ok(results)
+}
+
+/// 按能解密的数据库对候选密钥排序
+pub async fn rank_keys(candidates: Vec<WeChatKey>, db_storage_roots: &[PathBuf]) -> Result<Vec<RankedKey>> {
+    let probes = probe_databases(db_storage_roots)?;
+    let validator = Arc::new(KeyValidator::new());
+    let ranked = rank_keys_with(candidates, &probes, move |path, key| {
+        let validator = Arc::clone(&validator);
+        async move { validator.validate_v4_key(&path, &key).await.unwrap_or(false) }
+    })
+    .await;
+    Ok(ranked)
+}
+
+/// 使用给定的检查函数排序，检查函数返回密钥能否解密该数据库
+async fn rank_keys_with<F, Fut>(candidates: Vec<WeChatKey>, probes: &[PathBuf], check: F) -> Vec<RankedKey>
+where
+    F: Fn(PathBuf, Vec<u8>) -> Fut,
+    Fut: Future<Output = bool>,
+{
+    let candidates = dedup_keys(candidates);
+    let mut ranked = Vec::with_capacity(candidates.len());
+    for key in candidates {
+        let mut databases = Vec::new();
+        for probe in probes {
+            if check(probe.clone(), key.key_data.clone()).await {
+                databases.push(probe.clone());
+            }
+        }
+        tracing::debug!("候选密钥 {}... 可解密 {} 个数据库", &key.to_hex()[..8], databases.len());
+        ranked.push(RankedKey { key, databases });
+    }
+    // 稳定排序，数量相同时保持提取顺序
+    ranked.sort_by_key(|r| std::cmp::Reverse(r.score()));
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wechat::key::KeyVersion;
+
+    fn key(byte: u8) -> WeChatKey {
+        WeChatKey::new(vec![byte; 32], 42, KeyVersion::V40)
+    }
+
+    #[tokio::test]
+    async fn test_rank_keys_by_decrypted_databases() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = dir.path().join("db_storage");
+        for db in ["contact/contact.db", "message/message_0.db", "message/message_1.db", "session/session.db"] {
+            let path = storage.join(db);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, b"encrypted").unwrap();
+        }
+        let probes = probe_databases(std::slice::from_ref(&storage)).unwrap();
+        assert_eq!(probes.len(), 3);
+        assert!(!probes.contains(&storage.join("message/message_1.db")));
+
+        // 密钥 1 只能解密联系人库，密钥 2 能解密全部，密钥 3 一个都不能
+        let ranked = rank_keys_with(vec![key(1), key(2), key(1), key(3)], &probes, |path, key| async move {
+            key[0] == 2 || (key[0] == 1 && path.ends_with("contact.db"))
+        })
+        .await;
+        let order: Vec<u8> = ranked.iter().map(|r| r.key.key_data[0]).collect();
+        assert_eq!(order, [2, 1, 3]);
+        assert_eq!(ranked[0].score(), 3);
+        assert_eq!(ranked[1].databases, [storage.join("contact/contact.db")]);
+        assert_eq!(ranked[2].score(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_validate_key_in_dir() {
+        use crate::wechat::decrypt::decrypt_algorithm_v3::tests::encrypt_v3;
+        use crate::wechat::decrypt::decrypt_common::SQLITE_HEADER;
+
+        let dir = tempfile::tempdir().unwrap();
+        let storage = dir.path().join("db_storage");
+        let key = [0x24u8; 32];
+        let mut plain = vec![0x61u8; 1024 * 4];
+        plain[..SQLITE_HEADER.len()].copy_from_slice(SQLITE_HEADER);
+        for (db, content) in [
+            ("contact/contact.db", encrypt_v3(&plain, &key, &[0x09; 16])),
+            ("message/message_0.db", encrypt_v3(&plain, &[0x42; 32], &[0x09; 16])),
+        ] {
+            let path = storage.join(db);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+
+        let results = validate_key_in_dir(&key, &storage).await.unwrap();
+        let summary: Vec<_> = results.iter().map(|r| (r.category, r.version)).collect();
+        assert_eq!(
+            summary,
+            [(DbCategory::Message, None), (DbCategory::Contact, Some(DecryptVersion::V3))]
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! 从内存快照离线提取密钥
+//!
+//! `dump-memory` 保存的快照可以带到另一台机器上提取密钥：按快照记录的微信版本从特征库取得
+//! 密钥结构特征，在快照的各个内存区域中搜索特征，读取特征前的密钥指针，
+//! 再用账号数据库的第一页校验指针指向的 32 字节。
+
+use async_trait::async_trait;
+use std::path::Path;
+use std::sync::Arc;
+
+use super::pattern_finder::read_pointer;
+use super::{KeyExtractor, KeyVersion, WeChatKey};
+use crate::errors::{Result, WeChatError};
+use crate::utils::memory_snapshot::MemorySnapshot;
+use crate::utils::task;
+use crate::wechat::decrypt::{DecryptConfig, PageKeyValidator};
+use crate::wechat::process::WechatProcessInfo;
+use crate::wechat::signatures::SignatureDb;
+
+/// 密钥长度
+const KEY_SIZE: usize = 32;
+
+/// 基于内存快照的密钥提取器，不需要运行中的微信进程
+#[derive(Clone)]
+pub struct SnapshotKeyExtractor {
+    snapshot: Arc<MemorySnapshot>,
+    validator: PageKeyValidator,
+}
+
+impl SnapshotKeyExtractor {
+    /// 打开快照；`data_dir` 为快照所属账号的数据目录，用其中的加密数据库校验候选密钥
+    pub fn open(snapshot: &Path, data_dir: &Path) -> Result<Self> {
+        Ok(Self {
+            snapshot: Arc::new(MemorySnapshot::open(snapshot)?),
+            validator: PageKeyValidator::for_data_dir(data_dir, DecryptConfig::v4())?,
+        })
+    }
+
+    /// 快照来源进程的 PID
+    pub fn pid(&self) -> u32 {
+        self.snapshot.index().process.pid
+    }
+
+    /// 搜索快照，返回最多 `limit` 个通过校验且不重复的密钥（阻塞）
+    ///
+    /// 快照按块保存大区域，恰好跨越块边界的特征会被漏掉。
+    pub fn extract_candidates(&self, limit: usize) -> Result<Vec<WeChatKey>> {
+        let index = self.snapshot.index();
+        let process = &index.process;
+
+        // 版本未收录时使用特征库的第一个条目（用户特征优先）
+        let signatures = SignatureDb::global();
+        let signature = signatures
+            .lookup(&process.version)
+            .or_else(|| signatures.entries().first())
+            .ok_or_else(|| WeChatError::KeyExtractionFailed("版本特征库为空".to_string()))?;
+        let pattern = signature.key.pattern_bytes()?;
+        let pointer_offset = signature.key.pointer_offset as isize;
+        tracing::info!(
+            "使用特征 {} 在快照中搜索密钥（微信 {}，PID {}）",
+            signature.name,
+            process.version,
+            process.pid
+        );
+
+        let mut keys: Vec<Vec<u8>> = Vec::new();
+        'regions: for region in &index.regions {
+            let data = self.snapshot.read_region(region)?;
+            let hits = data
+                .windows(pattern.len())
+                .enumerate()
+                .filter(|(_, window)| *window == pattern.as_slice())
+                .filter_map(|(offset, _)| (region.address + offset).checked_add_signed(pointer_offset));
+            for location in hits {
+                let Some(key) = self.read_key(location) else {
+                    continue;
+                };
+                if !keys.contains(&key) {
+                    tracing::info!("🎉 快照中的候选密钥通过校验，指针地址: {:#X}", location);
+                    keys.push(key);
+                }
+                if keys.len() >= limit {
+                    break 'regions;
+                }
+            }
+        }
+
+        Ok(keys
+            .into_iter()
+            .map(|key| WeChatKey::new(key, process.pid, KeyVersion::V40))
+            .collect())
+    }
+
+    /// 读取 `location` 处的指针指向的 32 字节并校验
+    fn read_key(&self, location: usize) -> Option<Vec<u8>> {
+        let pointer_size = self.snapshot.index().process.pointer_size;
+        let pointer = read_pointer(&self.snapshot.read(location, pointer_size)?);
+        if pointer == 0 {
+            return None;
+        }
+        let key = self.snapshot.read(pointer, KEY_SIZE)?;
+        self.validator.validate(&key).then_some(key)
+    }
+}
+
+#[async_trait]
+impl KeyExtractor for SnapshotKeyExtractor {
+    /// 从快照提取密钥，快照已记录来源进程，忽略 `process`
+    async fn extract_key(&self, _process: &WechatProcessInfo) -> Result<WeChatKey> {
+        let extractor = self.clone();
+        task::spawn_blocking(
+            move || {
+                extractor
+                    .extract_candidates(1)?
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| WeChatError::KeyExtractionFailed("快照中未找到有效密钥".to_string()).into())
+            },
+            WeChatError::KeyExtractionFailed,
+        )
+        .await
+    }
+
+    /// 快照中的指针需要按进程地址解析，不支持单独的内存块
+    async fn search_key_in_memory(&self, _memory: &[u8], _process: &WechatProcessInfo) -> Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    async fn validate_key(&self, key: &[u8]) -> Result<bool> {
+        Ok(self.validator.validate(key))
+    }
+
+    fn supported_version(&self) -> KeyVersion {
+        KeyVersion::V40
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::memory_snapshot::{SnapshotProcess, SnapshotRegions, SnapshotWriter};
+    use crate::wechat::decrypt::decrypt_validator::tests::signed_first_page;
+
+    #[test]
+    fn test_extract_key_from_snapshot() {
+        let key: Vec<u8> = (0..32u8).map(|i| i.wrapping_mul(53) ^ 0x3c).collect();
+        let decoy: Vec<u8> = (0..32u8).map(|i| i.wrapping_mul(29) ^ 0x71).collect();
+        let dir = tempfile::tempdir().unwrap();
+        let message = dir.path().join("db_storage").join("message");
+        std::fs::create_dir_all(&message).unwrap();
+        std::fs::write(
+            message.join("message_0.db"),
+            signed_first_page(&key, &[0x42; 16], &DecryptConfig::v4()),
+        )
+        .unwrap();
+
+        // 两处 `[密钥指针][特征]`，第一处指向无法通过校验的字节
+        let pattern = SignatureDb::global().lookup("4.0.3.22").unwrap().key.pattern_bytes().unwrap();
+        let mut structs = vec![0u8; 0x80];
+        structs[0x08..0x10].copy_from_slice(&0x10000u64.to_le_bytes());
+        structs[0x10..0x10 + pattern.len()].copy_from_slice(&pattern);
+        structs[0x40..0x48].copy_from_slice(&0x10020u64.to_le_bytes());
+        structs[0x48..0x48 + pattern.len()].copy_from_slice(&pattern);
+        let mut heap = decoy.clone();
+        heap.extend_from_slice(&key);
+
+        let path = dir.path().join("wechat.mwxsnap");
+        let process = SnapshotProcess {
+            pid: 7,
+            name: "Weixin.exe".to_string(),
+            version: "4.0.3.22".to_string(),
+            architecture: "x64".to_string(),
+            pointer_size: 8,
+        };
+        let mut writer = SnapshotWriter::create(&path, process, SnapshotRegions::Private).unwrap();
+        writer.add_region(0x10000, &heap).unwrap();
+        writer.add_region(0x20000, &structs).unwrap();
+        writer.finish().unwrap();
+
+        let extractor = SnapshotKeyExtractor::open(&path, dir.path()).unwrap();
+        let keys = extractor.extract_candidates(4).unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].key_data, key);
+        assert_eq!(keys[0].source_pid, 7);
+    }
+}
@@ -0,0 +1,387 @@
+//! 密钥提取策略链
+//!
+//! 微信每次更新都可能让某种内存定位方式失效，因此密钥提取按顺序尝试多个策略：
+//! 模块相对搜索 → 堆内存特征扫描 → 配置偏移，并记录最终成功的策略，
+//! 便于在微信版本更新后判断哪种特征需要维护。
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tracing::{info, warn};
+
+use super::{KeyExtractor, KeyVersion, WeChatKey};
+use crate::backup::catalog::key_fingerprint;
+use crate::errors::{Result, WeChatError};
+use crate::events::{Event, EventBus};
+use crate::wechat::process::WechatProcessInfo;
+
+/// 密钥提取策略类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyStrategyKind {
+    /// 在微信主模块（Weixin.dll）映像范围内搜索密钥结构特征
+    ModuleRelative,
+    /// 扫描进程私有堆内存中的密钥结构特征
+    HeapPatternScan,
+    /// 按配置中给出的模块偏移直接读取密钥指针
+    ConfigOffsets,
+}
+
+impl KeyStrategyKind {
+    /// 获取策略标识
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KeyStrategyKind::ModuleRelative => "module_relative",
+            KeyStrategyKind::HeapPatternScan => "heap_pattern_scan",
+            KeyStrategyKind::ConfigOffsets => "config_offsets",
+        }
+    }
+}
+
+impl fmt::Display for KeyStrategyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            KeyStrategyKind::ModuleRelative => "模块相对搜索",
+            KeyStrategyKind::HeapPatternScan => "堆内存特征扫描",
+            KeyStrategyKind::ConfigOffsets => "配置偏移",
+        };
+        f.write_str(name)
+    }
+}
+
+/// 配置中的密钥指针偏移
+///
+/// 密钥指针地址 = 模块基址 + `offset`，指针指向32字节的密钥。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyOffset {
+    /// 模块名称，例如 `Weixin.dll`
+    pub module: String,
+    /// 相对模块基址的偏移
+    pub offset: u64,
+}
+
+/// 单个密钥提取策略
+#[async_trait]
+pub trait KeyStrategy: Send + Sync {
+    /// 策略类型
+    fn kind(&self) -> KeyStrategyKind;
+
+    /// 从进程中提取密钥
+    async fn extract(&self, process: &WechatProcessInfo) -> Result<WeChatKey>;
+
+    /// 从进程中提取所有通过验证的候选密钥，默认只返回 `extract` 的结果
+    async fn extract_all(&self, process: &WechatProcessInfo) -> Result<Vec<WeChatKey>> {
+        Ok(vec![self.extract(process).await?])
+    }
+
+    /// 在内存数据中搜索密钥，不支持时返回 `None`
+    async fn search_key_in_memory(
+        &self,
+        _memory: &[u8],
+        _process: &WechatProcessInfo,
+    ) -> Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    /// 验证密钥是否有效，不支持时返回 `false`
+    async fn validate_key(&self, _key: &[u8]) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+/// 单个策略的尝试结果
+#[derive(Debug, Clone)]
+pub struct StrategyAttempt {
+    /// 策略类型
+    pub kind: KeyStrategyKind,
+    /// 失败原因，成功时为 `None`
+    pub error: Option<String>,
+}
+
+/// 按顺序尝试多个策略的密钥提取器
+///
+/// 设置事件总线后，开始处理进程时发布 [`Event::ProcessDetected`]，提取成功时发布 [`Event::KeyExtracted`]。
+pub struct KeyStrategyChain {
+    strategies: Vec<Box<dyn KeyStrategy>>,
+    events: EventBus,
+}
+
+impl KeyStrategyChain {
+    /// 创建策略链，策略按给定顺序尝试
+    pub fn new(strategies: Vec<Box<dyn KeyStrategy>>) -> Self {
+        Self {
+            strategies,
+            events: EventBus::default(),
+        }
+    }
+
+    /// 向共享的事件总线发布进程检测和密钥提取事件，默认使用独立的总线
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = events;
+        self
+    }
+
+    fn publish_detected(&self, process: &WechatProcessInfo) {
+        self.events.publish(Event::ProcessDetected {
+            pid: process.pid,
+            version: process.version.to_string(),
+            wxid: process.get_current_wxid(),
+        });
+    }
+
+    fn publish_extracted(&self, key: &WeChatKey) {
+        self.events.publish(Event::KeyExtracted {
+            pid: key.source_pid,
+            fingerprint: key_fingerprint(&key.key_data),
+        });
+    }
+
+    /// 策略链中的策略类型（按尝试顺序）
+    pub fn kinds(&self) -> Vec<KeyStrategyKind> {
+        self.strategies.iter().map(|s| s.kind()).collect()
+    }
+
+    /// 依次尝试各个策略，返回提取结果及每个策略的尝试记录
+    ///
+    /// 成功时密钥的 `strategy` 字段记录了生效的策略。
+    pub async fn extract_with_report(
+        &self,
+        process: &WechatProcessInfo,
+    ) -> (Result<WeChatKey>, Vec<StrategyAttempt>) {
+        let mut attempts = Vec::with_capacity(self.strategies.len());
+        self.publish_detected(process);
+
+        for strategy in &self.strategies {
+            let kind = strategy.kind();
+            info!("尝试密钥提取策略: {}", kind);
+            match strategy.extract(process).await {
+                Ok(mut key) => {
+                    info!("密钥提取策略 {} 成功", kind);
+                    key.strategy = Some(kind);
+                    attempts.push(StrategyAttempt { kind, error: None });
+                    self.publish_extracted(&key);
+                    return (Ok(key.with_account(process)), attempts);
+                }
+                Err(e) => {
+                    warn!("密钥提取策略 {} 失败: {}", kind, e);
+                    attempts.push(StrategyAttempt {
+                        kind,
+                        error: Some(e.to_string()),
+                    });
+                }
+            }
+        }
+
+        let reason = if attempts.is_empty() {
+            "当前平台没有可用的密钥提取策略，请通过配置或参数提供密钥".to_string()
+        } else {
+            let details = attempts
+                .iter()
+                .map(|a| format!("{}: {}", a.kind, a.error.as_deref().unwrap_or_default()))
+                .collect::<Vec<_>>()
+                .join("; ");
+            format!("所有策略均失败 ({})", details)
+        };
+        (Err(WeChatError::KeyExtractionFailed(reason).into()), attempts)
+    }
+
+    /// 尝试所有策略并汇总候选密钥（去重，按策略顺序），用于同一进程树中有多个账号的情况
+    pub async fn extract_all_with_report(
+        &self,
+        process: &WechatProcessInfo,
+    ) -> (Vec<WeChatKey>, Vec<StrategyAttempt>) {
+        let mut keys = Vec::new();
+        let mut attempts = Vec::with_capacity(self.strategies.len());
+        self.publish_detected(process);
+
+        for strategy in &self.strategies {
+            let kind = strategy.kind();
+            info!("尝试密钥提取策略: {}", kind);
+            match strategy.extract_all(process).await {
+                Ok(found) => {
+                    info!("密钥提取策略 {} 找到 {} 个候选密钥", kind, found.len());
+                    keys.extend(found.into_iter().map(|mut key| {
+                        key.strategy = Some(kind);
+                        key.with_account(process)
+                    }));
+                    attempts.push(StrategyAttempt { kind, error: None });
+                }
+                Err(e) => {
+                    warn!("密钥提取策略 {} 失败: {}", kind, e);
+                    attempts.push(StrategyAttempt {
+                        kind,
+                        error: Some(e.to_string()),
+                    });
+                }
+            }
+        }
+        let keys = super::ranking::dedup_keys(keys);
+        for key in &keys {
+            self.publish_extracted(key);
+        }
+        (keys, attempts)
+    }
+}
+
+#[async_trait]
+impl KeyExtractor for KeyStrategyChain {
+    async fn extract_key(&self, process: &WechatProcessInfo) -> Result<WeChatKey> {
+        self.extract_with_report(process).await.0
+    }
+
+    async fn search_key_in_memory(
+        &self,
+        memory: &[u8],
+        process: &WechatProcessInfo,
+    ) -> Result<Option<Vec<u8>>> {
+        for strategy in &self.strategies {
+            if let Some(key) = strategy.search_key_in_memory(memory, process).await? {
+                return Ok(Some(key));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn validate_key(&self, key: &[u8]) -> Result<bool> {
+        for strategy in &self.strategies {
+            if strategy.validate_key(key).await? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn supported_version(&self) -> KeyVersion {
+        KeyVersion::V40
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::ProcessInfo;
+
+    struct StubStrategy {
+        kind: KeyStrategyKind,
+        succeed: bool,
+    }
+
+    #[async_trait]
+    impl KeyStrategy for StubStrategy {
+        fn kind(&self) -> KeyStrategyKind {
+            self.kind
+        }
+
+        async fn extract(&self, process: &WechatProcessInfo) -> Result<WeChatKey> {
+            if self.succeed {
+                Ok(WeChatKey::new(vec![1u8; 32], process.pid, KeyVersion::V40))
+            } else {
+                Err(WeChatError::KeyExtractionFailed("未命中".to_string()).into())
+            }
+        }
+    }
+
+    fn process() -> WechatProcessInfo {
+        process_with_pid(42)
+    }
+
+    fn process_with_pid(pid: u32) -> WechatProcessInfo {
+        WechatProcessInfo::new(ProcessInfo::new(
+            0,
+            pid,
+            "Weixin.exe".to_string(),
+            Some("Weixin.exe".to_string()),
+            None,
+            true,
+            true,
+        ))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_chain_records_successful_strategy() {
+        let chain = KeyStrategyChain::new(vec![
+            Box::new(StubStrategy { kind: KeyStrategyKind::ModuleRelative, succeed: false }),
+            Box::new(StubStrategy { kind: KeyStrategyKind::HeapPatternScan, succeed: true }),
+            Box::new(StubStrategy { kind: KeyStrategyKind::ConfigOffsets, succeed: true }),
+        ]);
+
+        let (result, attempts) = chain.extract_with_report(&process()).await;
+        let key = result.unwrap();
+        assert_eq!(key.strategy, Some(KeyStrategyKind::HeapPatternScan));
+        assert_eq!(attempts.len(), 2);
+        assert!(attempts[0].error.is_some());
+        assert!(attempts[1].error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_chain_collects_all_candidates() {
+        let chain = KeyStrategyChain::new(vec![
+            Box::new(StubStrategy { kind: KeyStrategyKind::ModuleRelative, succeed: false }),
+            Box::new(StubStrategy { kind: KeyStrategyKind::HeapPatternScan, succeed: true }),
+            Box::new(StubStrategy { kind: KeyStrategyKind::ConfigOffsets, succeed: true }),
+        ]);
+
+        let (keys, attempts) = chain.extract_all_with_report(&process()).await;
+        // 两个策略找到同一个密钥，只保留第一个
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].strategy, Some(KeyStrategyKind::HeapPatternScan));
+        assert_eq!(attempts.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_chain_publishes_events() {
+        let events = EventBus::new();
+        let mut received = events.subscribe();
+        let chain = KeyStrategyChain::new(vec![Box::new(StubStrategy {
+            kind: KeyStrategyKind::HeapPatternScan,
+            succeed: true,
+        })])
+        .with_events(events);
+
+        let key = chain.extract_key(&process()).await.unwrap();
+        assert!(matches!(received.try_recv(), Some(Event::ProcessDetected { pid: 42, .. })));
+        let Some(Event::KeyExtracted { pid, fingerprint }) = received.try_recv() else {
+            panic!("应发布密钥提取事件");
+        };
+        assert_eq!(pid, 42);
+        assert_eq!(fingerprint, key_fingerprint(&key.key_data));
+    }
+
+    #[tokio::test]
+    async fn test_empty_chain_fails() {
+        let chain = KeyStrategyChain::new(Vec::new());
+        assert!(chain.extract_key(&process()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_extract_all_keys_tags_accounts() {
+        struct PidStrategy;
+
+        #[async_trait]
+        impl KeyStrategy for PidStrategy {
+            fn kind(&self) -> KeyStrategyKind {
+                KeyStrategyKind::HeapPatternScan
+            }
+
+            async fn extract(&self, process: &WechatProcessInfo) -> Result<WeChatKey> {
+                if process.pid == 3 {
+                    return Err(WeChatError::KeyExtractionFailed("未命中".to_string()).into());
+                }
+                Ok(WeChatKey::new(vec![process.pid as u8; 32], process.pid, KeyVersion::V40))
+            }
+        }
+
+        let mut processes: Vec<_> = [1, 2, 3].into_iter().map(process_with_pid).collect();
+        processes[0].data_dir = Some(std::path::PathBuf::from("/xwechat_files/wxid_alice_1a2b"));
+        processes[1].data_dir = Some(std::path::PathBuf::from("/xwechat_files/wxid_bob_3c4d"));
+
+        let chain = KeyStrategyChain::new(vec![Box::new(PidStrategy)]);
+        let keys = chain.extract_all_keys(&processes).await.unwrap();
+        let accounts: Vec<_> = keys.iter().map(|k| (k.source_pid, k.wxid.as_deref())).collect();
+        assert_eq!(accounts, [(1, Some("wxid_alice")), (2, Some("wxid_bob"))]);
+        assert_eq!(keys[1].data_dir, processes[1].data_dir);
+
+        assert!(chain.extract_all_keys(&processes[2..]).await.is_err());
+    }
+}
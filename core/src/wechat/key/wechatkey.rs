@@ -1,10 +1,13 @@
 /// 密钥数据结构
 /// 
 use super::KeyVersion;
+use super::strategy::KeyStrategyKind;
 use serde::{Deserialize, Serialize};
 use async_trait::async_trait;
 use std::fmt;
+use std::path::PathBuf;
 use crate::errors::Result;
+use crate::wechat::process::WechatProcessInfo;
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct WeChatKey {
@@ -16,6 +19,15 @@ pub struct WeChatKey {
     pub extracted_at: chrono::DateTime<chrono::Utc>,
     /// 密钥版本信息
     pub version: KeyVersion,
+    /// 成功提取密钥的策略，非策略链提取时为 `None`
+    #[serde(default)]
+    pub strategy: Option<KeyStrategyKind>,
+    /// 来源进程登录的账号
+    #[serde(default)]
+    pub wxid: Option<String>,
+    /// 来源进程的账号数据目录
+    #[serde(default)]
+    pub data_dir: Option<PathBuf>,
 }
 
 
@@ -27,9 +39,19 @@ impl WeChatKey {
             source_pid,
             extracted_at: chrono::Utc::now(),
             version,
+            strategy: None,
+            wxid: None,
+            data_dir: None,
         }
     }
 
+    /// 标记密钥所属的账号（取自来源进程的数据目录）
+    pub fn with_account(mut self, process: &WechatProcessInfo) -> Self {
+        self.wxid = process.get_current_wxid();
+        self.data_dir = process.data_dir.clone();
+        self
+    }
+
     /// 获取密钥的十六进制表示
     pub fn to_hex(&self) -> String {
         hex::encode(&self.key_data)
@@ -78,6 +100,9 @@ impl fmt::Debug for WeChatKey {
             .field("source_pid", &self.source_pid)
             .field("extracted_at", &self.extracted_at)
             .field("version", &self.version)
+            .field("strategy", &self.strategy)
+            .field("wxid", &self.wxid)
+            .field("data_dir", &self.data_dir)
             .finish()
     }
 }
@@ -1,6 +1,8 @@
 
 
 mod win_key_extractor_v4;
+mod win_key_strategies;
 
 pub use win_key_extractor_v4::KeyExtractorV4 as KeyExtractor;
+pub use win_key_strategies::platform_strategies;
 
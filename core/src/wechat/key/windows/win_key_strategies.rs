@@ -0,0 +1,205 @@
+//! Windows 平台的密钥提取策略
+
+use async_trait::async_trait;
+
+use super::win_key_extractor_v4::{page_validator, target_architecture, KeyExtractorV4, KEY_SIZE, V4_KEY_PATTERN};
+use crate::errors::{Result, WeChatError};
+use crate::utils::task;
+use crate::utils::windows::process::ProcessArchitecture;
+use crate::utils::windows::{memory, module_info};
+use crate::wechat::decrypt::PageKeyValidator;
+use crate::wechat::key::{
+    KeyExtractor, KeyOffset, KeyStrategy, KeyStrategyKind, KeyVersion, WeChatKey,
+};
+use crate::wechat::process::WechatProcessInfo;
+use crate::wechat::signatures::SignatureDb;
+
+/// 微信4.0主模块名称
+const WEIXIN_MODULE: &str = "Weixin.dll";
+/// 模块内最多检查的特征命中次数
+const MAX_MODULE_MATCHES: usize = 64;
+/// 提取所有候选密钥时最多保留的数量
+const MAX_KEY_CANDIDATES: usize = 16;
+const POINTER_SIZE: usize = 8;
+
+/// 按默认顺序创建 Windows 平台的策略列表
+pub fn platform_strategies(offsets: Vec<KeyOffset>) -> Result<Vec<Box<dyn KeyStrategy>>> {
+    Ok(vec![
+        Box::new(ModuleRelativeStrategy),
+        Box::new(HeapPatternScanStrategy {
+            inner: KeyExtractorV4::new()?,
+        }),
+        Box::new(ConfigOffsetsStrategy { offsets }),
+    ])
+}
+
+/// 读取指针指向的32字节密钥并验证
+fn read_key_via_pointer(
+    pid: u32,
+    arch: ProcessArchitecture,
+    pointer_addr: usize,
+    validator: &PageKeyValidator,
+) -> Option<Vec<u8>> {
+    let ptr_bytes = memory::read_process_memory(pid, pointer_addr, POINTER_SIZE).ok()?;
+    let ptr_value = usize::from_le_bytes(ptr_bytes.as_slice().try_into().ok()?);
+    if !arch.is_user_pointer(ptr_value) {
+        return None;
+    }
+
+    let key_data = memory::read_process_memory(pid, ptr_value, KEY_SIZE).ok()?;
+    KeyExtractorV4::validate_key_impl(&key_data, validator).then_some(key_data)
+}
+
+/// 模块相对搜索：只在 Weixin.dll 映像范围内查找密钥结构特征
+struct ModuleRelativeStrategy;
+
+#[async_trait]
+impl KeyStrategy for ModuleRelativeStrategy {
+    fn kind(&self) -> KeyStrategyKind {
+        KeyStrategyKind::ModuleRelative
+    }
+
+    async fn extract(&self, process: &WechatProcessInfo) -> Result<WeChatKey> {
+        self.extract_limited(process, 1)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| WeChatError::KeyExtractionFailed("模块中未找到有效密钥".to_string()).into())
+    }
+
+    async fn extract_all(&self, process: &WechatProcessInfo) -> Result<Vec<WeChatKey>> {
+        self.extract_limited(process, MAX_KEY_CANDIDATES).await
+    }
+}
+
+impl ModuleRelativeStrategy {
+    /// 检查模块内的特征命中，返回最多 `limit` 个不重复的有效密钥
+    async fn extract_limited(&self, process: &WechatProcessInfo, limit: usize) -> Result<Vec<WeChatKey>> {
+        let pid = process.pid;
+        let arch = target_architecture(process)?;
+        let validator = page_validator(process)?;
+
+        // 优先使用版本特征库中的模块和特征，未收录的版本使用内置默认值
+        let (module, pattern, pointer_offset) =
+            match SignatureDb::global().lookup_version(&process.version) {
+                Some(sig) => (sig.key.module.clone(), sig.key.pattern_bytes()?, sig.key.pointer_offset),
+                None => (WEIXIN_MODULE.to_string(), V4_KEY_PATTERN.to_vec(), -(POINTER_SIZE as i64)),
+            };
+
+        task::spawn_blocking(
+            move || {
+                let matches =
+                    memory::search_module_for_pattern(pid, &module, &pattern, MAX_MODULE_MATCHES)?;
+
+                let mut keys: Vec<Vec<u8>> = Vec::new();
+                for key in matches
+                    .into_iter()
+                    .filter_map(|addr| addr.checked_add_signed(pointer_offset as isize))
+                    .filter_map(|addr| read_key_via_pointer(pid, arch, addr, &validator))
+                {
+                    if !keys.contains(&key) {
+                        keys.push(key);
+                    }
+                    if keys.len() >= limit {
+                        break;
+                    }
+                }
+                if keys.is_empty() {
+                    return Err(WeChatError::KeyExtractionFailed(format!("{} 中未找到有效密钥", module)).into());
+                }
+                Ok(keys
+                    .into_iter()
+                    .map(|key| WeChatKey::new(key, pid, KeyVersion::V40))
+                    .collect())
+            },
+            WeChatError::KeyExtractionFailed,
+        )
+        .await
+    }
+}
+
+/// 堆内存特征扫描：遍历进程私有可写内存区域
+struct HeapPatternScanStrategy {
+    inner: KeyExtractorV4,
+}
+
+#[async_trait]
+impl KeyStrategy for HeapPatternScanStrategy {
+    fn kind(&self) -> KeyStrategyKind {
+        KeyStrategyKind::HeapPatternScan
+    }
+
+    async fn extract(&self, process: &WechatProcessInfo) -> Result<WeChatKey> {
+        self.inner.extract_key(process).await
+    }
+
+    async fn extract_all(&self, process: &WechatProcessInfo) -> Result<Vec<WeChatKey>> {
+        let inner = self.inner.clone();
+        let process = process.clone();
+        let keys = task::spawn_blocking(
+            move || inner.extract_candidates(&process, MAX_KEY_CANDIDATES),
+            WeChatError::KeyExtractionFailed,
+        )
+        .await?;
+        if keys.is_empty() {
+            return Err(WeChatError::KeyExtractionFailed("V4算法未找到有效密钥".to_string()).into());
+        }
+        Ok(keys)
+    }
+
+    async fn search_key_in_memory(
+        &self,
+        memory: &[u8],
+        process: &WechatProcessInfo,
+    ) -> Result<Option<Vec<u8>>> {
+        self.inner.search_key_in_memory(memory, process).await
+    }
+
+    async fn validate_key(&self, key: &[u8]) -> Result<bool> {
+        self.inner.validate_key(key).await
+    }
+}
+
+/// 配置偏移：按 `模块基址 + 偏移` 读取密钥指针
+struct ConfigOffsetsStrategy {
+    offsets: Vec<KeyOffset>,
+}
+
+#[async_trait]
+impl KeyStrategy for ConfigOffsetsStrategy {
+    fn kind(&self) -> KeyStrategyKind {
+        KeyStrategyKind::ConfigOffsets
+    }
+
+    async fn extract(&self, process: &WechatProcessInfo) -> Result<WeChatKey> {
+        if self.offsets.is_empty() {
+            return Err(WeChatError::KeyExtractionFailed("未配置密钥偏移".to_string()).into());
+        }
+
+        let pid = process.pid;
+        let arch = target_architecture(process)?;
+        let validator = page_validator(process)?;
+        let offsets = self.offsets.clone();
+        task::spawn_blocking(
+            move || {
+                for entry in &offsets {
+                    let module = match module_info::get_module_info(pid, &entry.module) {
+                        Ok(m) => m,
+                        Err(e) => {
+                            tracing::debug!("获取模块 {} 信息失败: {}", entry.module, e);
+                            continue;
+                        }
+                    };
+                    let addr = module.base_address.saturating_add(entry.offset as usize);
+                    if let Some(key) = read_key_via_pointer(pid, arch, addr, &validator) {
+                        tracing::info!("通过配置偏移 {}+{:#X} 找到密钥", entry.module, entry.offset);
+                        return Ok(WeChatKey::new(key, pid, KeyVersion::V40));
+                    }
+                }
+                Err(WeChatError::KeyExtractionFailed("配置的偏移均未找到有效密钥".to_string()).into())
+            },
+            WeChatError::KeyExtractionFailed,
+        )
+        .await
+    }
+}
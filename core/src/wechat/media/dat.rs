@@ -0,0 +1,427 @@
+//! 图片缓存（`.dat`）解码
+//!
+//! 微信把收发的图片保存为混淆过的 `.dat` 文件，常见两种格式：
+//!
+//! - 3.x：整个文件与一个字节异或，用文件头与 JPG/PNG/GIF 等图片魔数比对即可推断出该字节
+//! - 4.x：文件以 `07 08 V1 08 07` 或 `07 08 V2 08 07` 开头，随后是 AES 加密部分和异或部分的长度，
+//!   正文依次为 AES-128-ECB 加密的开头、未加密的中段和逐字节异或的结尾。
+//!   V1 使用固定密钥，V2 的密钥需要从微信进程中获取；异或字节可以由图片结尾的固定字节推断
+//!
+//! [`DatDecoder::decode_tree`] 并行解码整个 `FileStorage` 目录，输出保持原有的相对路径。
+
+use aes::cipher::{generic_array::GenericArray, BlockDecrypt, KeyInit};
+use anyhow::Context;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+use crate::errors::{MwxDumpError, Result, WeChatError};
+use crate::wechat::datadir::{open_read_only, SourceGuard};
+
+/// 4.x V1 格式的文件头
+const V1_SIGNATURE: [u8; 6] = [0x07, 0x08, b'V', b'1', 0x08, 0x07];
+/// 4.x V2 格式的文件头
+const V2_SIGNATURE: [u8; 6] = [0x07, 0x08, b'V', b'2', 0x08, 0x07];
+/// 4.x 文件头长度：签名、AES 部分长度、异或部分长度和1个保留字节
+const V4_HEADER_SIZE: usize = 15;
+/// V1 格式的固定 AES 密钥
+const V1_AES_KEY: &[u8; 16] = b"cfcd208495d565ef";
+const AES_BLOCK_SIZE: usize = 16;
+
+/// 图片格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageFormat {
+    Jpg,
+    Png,
+    Gif,
+    Bmp,
+    Webp,
+    Tiff,
+}
+
+/// 图片魔数，按常见程度排序
+const MAGICS: [(ImageFormat, &[u8]); 6] = [
+    (ImageFormat::Jpg, &[0xFF, 0xD8, 0xFF]),
+    (ImageFormat::Png, &[0x89, 0x50, 0x4E, 0x47]),
+    (ImageFormat::Gif, &[0x47, 0x49, 0x46, 0x38]),
+    (ImageFormat::Webp, &[0x52, 0x49, 0x46, 0x46]),
+    (ImageFormat::Tiff, &[0x49, 0x49, 0x2A, 0x00]),
+    (ImageFormat::Bmp, &[0x42, 0x4D]),
+];
+
+impl ImageFormat {
+    /// 文件扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Jpg => "jpg",
+            ImageFormat::Png => "png",
+            ImageFormat::Gif => "gif",
+            ImageFormat::Bmp => "bmp",
+            ImageFormat::Webp => "webp",
+            ImageFormat::Tiff => "tif",
+        }
+    }
+
+    /// 按文件头识别图片格式
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        MAGICS
+            .iter()
+            .find(|(_, magic)| data.starts_with(magic))
+            .map(|(format, _)| *format)
+    }
+
+    /// 图片结尾的固定字节，用于推断 4.x 格式的异或字节
+    fn trailer(&self) -> Option<&'static [u8]> {
+        match self {
+            ImageFormat::Jpg => Some(&[0xFF, 0xD9]),
+            ImageFormat::Png => Some(&[0xAE, 0x42, 0x60, 0x82]),
+            ImageFormat::Gif => Some(&[0x3B]),
+            _ => None,
+        }
+    }
+}
+
+/// `.dat` 文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatVersion {
+    /// 3.x 整体异或
+    Xor,
+    /// 4.x，固定 AES 密钥
+    V1,
+    /// 4.x，AES 密钥取自微信进程
+    V2,
+}
+
+impl DatVersion {
+    /// 按文件头识别格式
+    pub fn detect(data: &[u8]) -> Self {
+        if data.starts_with(&V1_SIGNATURE) {
+            DatVersion::V1
+        } else if data.starts_with(&V2_SIGNATURE) {
+            DatVersion::V2
+        } else {
+            DatVersion::Xor
+        }
+    }
+}
+
+/// 解码后的图片
+#[derive(Debug, Clone)]
+pub struct DecodedImage {
+    pub format: ImageFormat,
+    pub data: Vec<u8>,
+}
+
+/// 目录解码统计
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecodeReport {
+    /// 找到的 `.dat` 文件数
+    pub files: usize,
+    /// 成功解码的文件数
+    pub decoded: usize,
+    /// 无法识别或解码失败的文件数
+    pub failed: usize,
+    /// 输出已存在而跳过的文件数
+    pub skipped: usize,
+    /// 耗时（毫秒）
+    pub elapsed_ms: u64,
+}
+
+/// 单个文件的解码结果
+enum FileOutcome {
+    Decoded,
+    Failed,
+    Skipped,
+}
+
+/// `.dat` 解码器
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DatDecoder {
+    aes_key: Option<[u8; 16]>,
+    xor_key: Option<u8>,
+}
+
+impl DatDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置 4.x V2 格式的 AES 密钥
+    pub fn with_aes_key(mut self, key: [u8; 16]) -> Self {
+        self.aes_key = Some(key);
+        self
+    }
+
+    /// 设置 4.x 格式的异或字节，不设置时由图片结尾推断
+    pub fn with_xor_key(mut self, key: u8) -> Self {
+        self.xor_key = Some(key);
+        self
+    }
+
+    /// 读取并解码文件
+    pub fn decode_file(&self, path: &Path) -> Result<DecodedImage> {
+        let mut data = Vec::new();
+        open_read_only(path)?.read_to_end(&mut data)?;
+        self.decode(&data).with_context(|| format!("解码图片失败: {}", path.display()))
+    }
+
+    /// 解码 `.dat` 数据
+    pub fn decode(&self, data: &[u8]) -> Result<DecodedImage> {
+        let data = match DatVersion::detect(data) {
+            DatVersion::Xor => {
+                let key = infer_xor_key(data).ok_or_else(corrupted)?;
+                data.iter().map(|byte| byte ^ key).collect()
+            }
+            DatVersion::V1 => self.decode_v4(data, V1_AES_KEY)?,
+            DatVersion::V2 => {
+                let key = self.aes_key.ok_or_else(|| {
+                    WeChatError::DecryptionFailed("4.x V2 格式的图片需要提供图片密钥".to_string())
+                })?;
+                self.decode_v4(data, &key)?
+            }
+        };
+        let format = ImageFormat::detect(&data).ok_or_else(|| {
+            WeChatError::DecryptionFailed("解码结果不是可识别的图片，图片密钥可能不正确".to_string())
+        })?;
+        Ok(DecodedImage { format, data })
+    }
+
+    /// 解码 4.x 格式：AES 加密的开头 + 原样的中段 + 异或的结尾
+    fn decode_v4(&self, data: &[u8], aes_key: &[u8; 16]) -> Result<Vec<u8>> {
+        if data.len() < V4_HEADER_SIZE {
+            return Err(corrupted().into());
+        }
+        let aes_len = u32::from_le_bytes(data[6..10].try_into().unwrap()) as usize;
+        let xor_len = u32::from_le_bytes(data[10..14].try_into().unwrap()) as usize;
+        let body = &data[V4_HEADER_SIZE..];
+        // 加密部分按 PKCS#7 填充，恰好对齐时也补一整块
+        let encrypted_len = aes_len - aes_len % AES_BLOCK_SIZE + AES_BLOCK_SIZE;
+        if body.len() < encrypted_len || body.len() - encrypted_len < xor_len {
+            return Err(corrupted().into());
+        }
+
+        let mut head = body[..encrypted_len].to_vec();
+        let cipher = aes::Aes128::new(GenericArray::from_slice(aes_key));
+        for block in head.chunks_exact_mut(AES_BLOCK_SIZE) {
+            cipher.decrypt_block(GenericArray::from_mut_slice(block));
+        }
+        let padding = *head.last().unwrap() as usize;
+        if padding == 0 || padding > AES_BLOCK_SIZE || head[head.len() - padding..].iter().any(|&b| b as usize != padding) {
+            return Err(WeChatError::DecryptionFailed("图片密钥不正确".to_string()).into());
+        }
+        head.truncate(head.len() - padding);
+
+        let rest = &body[encrypted_len..];
+        let (middle, tail) = rest.split_at(rest.len() - xor_len);
+        let xor_key = match self.xor_key {
+            Some(key) => key,
+            None if tail.is_empty() => 0,
+            None => ImageFormat::detect(&head)
+                .and_then(|format| infer_trailer_xor_key(tail, format))
+                .ok_or_else(|| WeChatError::DecryptionFailed("无法推断图片的异或字节，请手动指定".to_string()))?,
+        };
+
+        let mut image = head;
+        image.extend_from_slice(middle);
+        image.extend(tail.iter().map(|byte| byte ^ xor_key));
+        Ok(image)
+    }
+
+    /// 并行解码 `input` 下所有 `.dat` 文件到 `output`，已解码的文件跳过
+    ///
+    /// 输出文件保持相对路径，扩展名换成识别出的图片格式；`concurrency` 为同时解码的文件数。
+    pub async fn decode_tree(
+        &self,
+        input: &Path,
+        output: &Path,
+        concurrency: usize,
+        cancel_token: &CancellationToken,
+    ) -> Result<DecodeReport> {
+        let started = Instant::now();
+        SourceGuard::new().with_root(input).check_output(output)?;
+        let mut files = Vec::new();
+        collect_dat_files(input, &mut files)?;
+        let mut report = DecodeReport {
+            files: files.len(),
+            ..Default::default()
+        };
+
+        let decoder = *self;
+        let mut outcomes = stream::iter(files)
+            .map(|file| {
+                let relative = file.strip_prefix(input).unwrap_or(&file).to_path_buf();
+                let target = output.join(relative);
+                tokio::task::spawn_blocking(move || decoder.decode_to(&file, &target))
+            })
+            .buffer_unordered(concurrency.max(1));
+        while let Some(outcome) = outcomes.next().await {
+            if cancel_token.is_cancelled() {
+                return Err(MwxDumpError::Cancelled.into());
+            }
+            match outcome? {
+                FileOutcome::Decoded => report.decoded += 1,
+                FileOutcome::Failed => report.failed += 1,
+                FileOutcome::Skipped => report.skipped += 1,
+            }
+        }
+        report.elapsed_ms = started.elapsed().as_millis() as u64;
+        Ok(report)
+    }
+
+    /// 解码单个文件写到 `target`（扩展名按图片格式替换）
+    fn decode_to(&self, file: &Path, target: &Path) -> FileOutcome {
+        if MAGICS.iter().any(|(format, _)| target.with_extension(format.extension()).exists()) {
+            return FileOutcome::Skipped;
+        }
+        let image = match self.decode_file(file) {
+            Ok(image) => image,
+            Err(e) => {
+                debug!("解码图片失败 {:?}: {}", file, e);
+                return FileOutcome::Failed;
+            }
+        };
+        let target = target.with_extension(image.format.extension());
+        let written = target
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(&target, &image.data));
+        match written {
+            Ok(()) => FileOutcome::Decoded,
+            Err(e) => {
+                warn!("写入图片失败 {:?}: {}", target, e);
+                FileOutcome::Failed
+            }
+        }
+    }
+}
+
+fn corrupted() -> WeChatError {
+    WeChatError::DecryptionFailed("不是可识别的图片缓存文件".to_string())
+}
+
+/// 用图片魔数推断 3.x 格式的异或字节
+pub fn infer_xor_key(data: &[u8]) -> Option<u8> {
+    MAGICS.iter().find_map(|(_, magic)| {
+        let key = data.first()? ^ magic[0];
+        (data.len() >= magic.len() && data.iter().zip(magic.iter()).all(|(byte, m)| byte ^ key == *m)).then_some(key)
+    })
+}
+
+/// 用图片结尾的固定字节推断 4.x 格式的异或字节
+fn infer_trailer_xor_key(tail: &[u8], format: ImageFormat) -> Option<u8> {
+    let trailer = format.trailer()?;
+    if tail.len() < trailer.len() {
+        return None;
+    }
+    let end = &tail[tail.len() - trailer.len()..];
+    let key = end[0] ^ trailer[0];
+    end.iter().zip(trailer).all(|(byte, t)| byte ^ key == *t).then_some(key)
+}
+
+/// 递归收集目录下的 `.dat` 文件
+fn collect_dat_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_dat_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("dat")) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes::cipher::BlockEncrypt;
+
+    const JPG: &[u8] = &[0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10, b'J', b'F', b'I', b'F', 0x00, 0x01, 0x02, 0x03, 0xFF, 0xD9];
+
+    /// 按 4.x 格式编码：前 `aes_len` 字节 AES 加密，末尾 `xor_len` 字节异或
+    fn encode_v4(image: &[u8], signature: [u8; 6], key: &[u8; 16], aes_len: usize, xor_len: usize, xor: u8) -> Vec<u8> {
+        let mut head = image[..aes_len].to_vec();
+        let padding = AES_BLOCK_SIZE - aes_len % AES_BLOCK_SIZE;
+        head.extend(std::iter::repeat_n(padding as u8, padding));
+        let cipher = aes::Aes128::new(GenericArray::from_slice(key));
+        for block in head.chunks_exact_mut(AES_BLOCK_SIZE) {
+            cipher.encrypt_block(GenericArray::from_mut_slice(block));
+        }
+        let mut data = signature.to_vec();
+        data.extend_from_slice(&(aes_len as u32).to_le_bytes());
+        data.extend_from_slice(&(xor_len as u32).to_le_bytes());
+        data.push(0x01);
+        data.extend(head);
+        data.extend_from_slice(&image[aes_len..image.len() - xor_len]);
+        data.extend(image[image.len() - xor_len..].iter().map(|byte| byte ^ xor));
+        data
+    }
+
+    #[test]
+    fn test_decode_xor() {
+        let data: Vec<u8> = JPG.iter().map(|byte| byte ^ 0x37).collect();
+        assert_eq!(DatVersion::detect(&data), DatVersion::Xor);
+        assert_eq!(infer_xor_key(&data), Some(0x37));
+        let image = DatDecoder::new().decode(&data).unwrap();
+        assert_eq!(image.format, ImageFormat::Jpg);
+        assert_eq!(image.data, JPG);
+
+        let png: Vec<u8> = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A].iter().map(|byte| byte ^ 0x9A).collect();
+        assert_eq!(DatDecoder::new().decode(&png).unwrap().format, ImageFormat::Png);
+        assert!(DatDecoder::new().decode(&[0x00, 0x11, 0x22, 0x33]).is_err());
+    }
+
+    #[test]
+    fn test_decode_v4() {
+        let data = encode_v4(JPG, V1_SIGNATURE, V1_AES_KEY, 8, 4, 0x5C);
+        assert_eq!(DatVersion::detect(&data), DatVersion::V1);
+        assert_eq!(DatDecoder::new().decode(&data).unwrap().data, JPG);
+
+        // AES 部分恰好对齐时补一整块填充
+        let data = encode_v4(JPG, V1_SIGNATURE, V1_AES_KEY, 16, 0, 0);
+        assert_eq!(DatDecoder::new().decode(&data).unwrap().data, JPG);
+
+        let key = *b"0123456789abcdef";
+        let data = encode_v4(JPG, V2_SIGNATURE, &key, 4, 2, 0xA1);
+        assert!(DatDecoder::new().decode(&data).is_err());
+        assert!(DatDecoder::new().with_aes_key(*b"fedcba9876543210").decode(&data).is_err());
+        let image = DatDecoder::new().with_aes_key(key).decode(&data).unwrap();
+        assert_eq!((image.format, image.data.as_slice()), (ImageFormat::Jpg, JPG));
+        let image = DatDecoder::new().with_aes_key(key).with_xor_key(0xA1).decode(&data).unwrap();
+        assert_eq!(image.data, JPG);
+
+        assert!(DatDecoder::new().decode(&V1_SIGNATURE).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_decode_tree() {
+        let input = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        let month = input.path().join("msg/attach/abc/2024-05/Img");
+        std::fs::create_dir_all(&month).unwrap();
+        std::fs::write(month.join("a.dat"), JPG.iter().map(|byte| byte ^ 0x37).collect::<Vec<_>>()).unwrap();
+        std::fs::write(month.join("b_t.dat"), encode_v4(JPG, V1_SIGNATURE, V1_AES_KEY, 8, 2, 0x11)).unwrap();
+        std::fs::write(month.join("c.dat"), [0x00, 0x11, 0x22]).unwrap();
+        std::fs::write(month.join("d.txt"), "not an image").unwrap();
+
+        let token = CancellationToken::new();
+        let decoder = DatDecoder::new();
+        let report = decoder.decode_tree(input.path(), output.path(), 2, &token).await.unwrap();
+        assert_eq!((report.files, report.decoded, report.failed, report.skipped), (3, 2, 1, 0));
+        let out = output.path().join("msg/attach/abc/2024-05/Img");
+        assert_eq!(std::fs::read(out.join("a.jpg")).unwrap(), JPG);
+        assert_eq!(std::fs::read(out.join("b_t.jpg")).unwrap(), JPG);
+
+        let report = decoder.decode_tree(input.path(), output.path(), 2, &token).await.unwrap();
+        assert_eq!((report.decoded, report.skipped), (0, 2));
+        assert!(decoder
+            .decode_tree(input.path(), &input.path().join("out"), 2, &token)
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,161 @@
+//! 媒体文件定位
+//!
+//! 从消息内容中提取媒体 MD5，经硬链接数据库映射到账号数据目录下的 `.dat` 或原始文件，
+//! 供导出、HTTP 媒体接口和 UI 预览共用。[`dat`] 把 `.dat` 图片缓存还原为图片，
+//! [`voice`] 把数据库中的 SILK 语音转码为 WAV/MP3。
+
+pub mod dat;
+#[cfg(feature = "ocr")]
+pub mod ocr;
+#[cfg(feature = "thumbnails")]
+pub mod thumbnail;
+#[cfg(feature = "transcription")]
+pub mod transcribe;
+pub mod voice;
+
+pub use dat::{DatDecoder, DatVersion, DecodeReport, DecodedImage, ImageFormat};
+#[cfg(feature = "ocr")]
+pub use ocr::{CommandOcr, OcrBackend, OcrConfig, OcrIndexer};
+#[cfg(feature = "thumbnails")]
+pub use thumbnail::{ThumbnailConfig, Thumbnailer};
+#[cfg(feature = "transcription")]
+pub use transcribe::{CommandTranscriber, Transcriber, TranscriptionConfig, VoiceTranscription};
+pub use voice::{AudioFormat, VoiceConfig, VoiceReport, VoiceTranscoder};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+
+use crate::errors::Result;
+use crate::models::Message;
+use crate::wechat::db::hardlink::{self, MediaKind};
+use crate::wechat::db::DataSource;
+
+/// 图片消息类型
+const MSG_TYPE_IMAGE: i64 = 3;
+/// 视频消息类型
+const MSG_TYPE_VIDEO: i64 = 43;
+/// 应用消息类型（文件为其中 `<type>6</type>`）
+const MSG_TYPE_APP: i64 = 49;
+
+static MD5_ATTR: Lazy<Regex> = Lazy::new(|| Regex::new(r#"\bmd5\s*=\s*"([0-9a-fA-F]{32})""#).unwrap());
+static MD5_ELEMENT: Lazy<Regex> = Lazy::new(|| Regex::new(r"<md5>([0-9a-fA-F]{32})</md5>").unwrap());
+static APP_FILE_TYPE: Lazy<Regex> = Lazy::new(|| Regex::new(r"<type>\s*6\s*</type>").unwrap());
+
+/// 外部识别命令参数中的媒体路径占位符
+pub const INPUT_PLACEHOLDER: &str = "{input}";
+
+/// 运行外部识别命令（OCR、语音转写），`{input}` 替换为媒体文件路径，返回标准输出文本
+#[cfg(any(feature = "ocr", feature = "transcription"))]
+pub(crate) async fn run_text_command(command: &str, args: &[String], input: &Path) -> Result<String> {
+    let input = input.to_string_lossy();
+    let output = tokio::process::Command::new(command)
+        .args(args.iter().map(|arg| arg.replace(INPUT_PLACEHOLDER, &input)))
+        .kill_on_drop(true)
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "外部命令执行失败 ({}, {}): {}",
+            command,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// 媒体类型对应的消息类型（低 32 位）
+pub(crate) fn message_type(kind: MediaKind) -> i64 {
+    match kind {
+        MediaKind::Image => MSG_TYPE_IMAGE,
+        MediaKind::Video => MSG_TYPE_VIDEO,
+        MediaKind::File => MSG_TYPE_APP,
+    }
+}
+
+/// 提取消息引用的媒体类型和 MD5，非媒体消息返回 `None`
+pub fn media_md5(message: &Message) -> Option<(MediaKind, String)> {
+    let kind = match message.msg_type {
+        MSG_TYPE_IMAGE => MediaKind::Image,
+        MSG_TYPE_VIDEO => MediaKind::Video,
+        MSG_TYPE_APP if APP_FILE_TYPE.is_match(&message.content) => MediaKind::File,
+        _ => return None,
+    };
+    let md5 = MD5_ATTR
+        .captures(&message.content)
+        .or_else(|| MD5_ELEMENT.captures(&message.content))?[1]
+        .to_lowercase();
+    Some((kind, md5))
+}
+
+/// 媒体文件定位器
+pub struct MediaResolver<S: DataSource> {
+    data_root: PathBuf,
+    hardlink: S,
+}
+
+impl<S: DataSource> MediaResolver<S> {
+    /// `data_root` 为账号数据目录，`hardlink` 为解密后的硬链接数据库
+    pub fn new(data_root: impl Into<PathBuf>, hardlink: S) -> Self {
+        Self {
+            data_root: data_root.into(),
+            hardlink,
+        }
+    }
+
+    /// 账号数据目录
+    pub fn data_root(&self) -> &Path {
+        &self.data_root
+    }
+
+    /// 定位消息对应的媒体文件，非媒体消息或没有硬链接记录时返回 `None`
+    pub async fn resolve(&self, message: &Message) -> Result<Option<PathBuf>> {
+        match media_md5(message) {
+            Some((kind, md5)) => self.resolve_md5(kind, &md5).await,
+            None => Ok(None),
+        }
+    }
+
+    /// 按媒体类型和 MD5 定位文件
+    pub async fn resolve_md5(&self, kind: MediaKind, md5: &str) -> Result<Option<PathBuf>> {
+        let entry = hardlink::lookup(&self.hardlink, kind, md5).await?;
+        Ok(entry.map(|entry| self.data_root.join(entry.relative_path())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wechat::db::hardlink::tests::create_hardlink_db;
+    use crate::wechat::db::SqliteDataSource;
+
+    fn message(msg_type: i64, content: &str) -> Message {
+        let mut message = Message::new();
+        message.msg_type = msg_type;
+        message.content = content.to_string();
+        message
+    }
+
+    #[tokio::test]
+    async fn test_resolve_message_media() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hardlink.db");
+        create_hardlink_db(&path).await;
+        let resolver = MediaResolver::new("/data/wxid_abc_1234", SqliteDataSource::open(&path).await.unwrap());
+
+        let image = message(3, r#"<msg><img length="1024" md5="D41D8CD98F00B204E9800998ECF8427E" /></msg>"#);
+        assert_eq!(
+            resolver.resolve(&image).await.unwrap(),
+            Some(PathBuf::from("/data/wxid_abc_1234/msg/attach/0123abcd/2024-05/Img/a1b2.dat"))
+        );
+
+        let file = message(49, "<msg><appmsg><type>6</type><md5>9e107d9d372bb6826bd81d3542a419d6</md5></appmsg></msg>");
+        assert_eq!(media_md5(&file).unwrap().0, MediaKind::File);
+        assert!(resolver.resolve(&file).await.unwrap().is_some());
+
+        let link = message(49, "<msg><appmsg><type>5</type><md5>9e107d9d372bb6826bd81d3542a419d6</md5></appmsg></msg>");
+        assert!(resolver.resolve(&link).await.unwrap().is_none());
+        assert!(resolver.resolve(&message(1, "hello")).await.unwrap().is_none());
+    }
+}
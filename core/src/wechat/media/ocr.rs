@@ -0,0 +1,183 @@
+//! 图片文字识别
+//!
+//! 对导出的图片运行可替换的 OCR 后端，并把识别结果写入 [`SearchIndex`]，使图片内容可被搜索。
+//! 默认后端调用外部命令（`tesseract`），命令的标准输出即识别文本；也可以实现 [`OcrBackend`]
+//! 接入其他引擎。
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::run_text_command;
+use crate::errors::Result;
+use crate::wechat::search::{IndexedText, SearchIndex, TextKind};
+
+/// 可识别的图片扩展名
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "gif", "bmp", "webp"];
+
+/// OCR 配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OcrConfig {
+    /// 识别命令
+    pub command: String,
+    /// 命令参数，`{input}` 替换为图片路径
+    pub args: Vec<String>,
+}
+
+impl Default for OcrConfig {
+    fn default() -> Self {
+        Self {
+            command: "tesseract".to_string(),
+            args: ["{input}", "stdout", "-l", "chi_sim+eng"].map(String::from).to_vec(),
+        }
+    }
+}
+
+/// OCR 后端
+#[async_trait]
+pub trait OcrBackend: Send + Sync {
+    /// 识别图片中的文字
+    async fn recognize(&self, image: &Path) -> Result<String>;
+}
+
+/// 调用外部命令的 OCR 后端
+#[derive(Debug, Clone, Default)]
+pub struct CommandOcr {
+    config: OcrConfig,
+}
+
+impl CommandOcr {
+    /// 按配置创建
+    pub fn new(config: OcrConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl OcrBackend for CommandOcr {
+    async fn recognize(&self, image: &Path) -> Result<String> {
+        run_text_command(&self.config.command, &self.config.args, image).await
+    }
+}
+
+/// 识别统计
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OcrSummary {
+    /// 识别并写入索引的图片数
+    pub indexed: usize,
+    /// 已索引而跳过的图片数
+    pub skipped: usize,
+    /// 识别失败的图片数
+    pub failed: usize,
+}
+
+/// 识别图片并写入全文索引
+pub struct OcrIndexer<B: OcrBackend> {
+    backend: B,
+    index: SearchIndex,
+}
+
+impl<B: OcrBackend> OcrIndexer<B> {
+    /// 使用指定后端和索引创建
+    pub fn new(backend: B, index: SearchIndex) -> Self {
+        Self { backend, index }
+    }
+
+    /// 识别单张图片，没有识别出文字时不写入索引
+    pub async fn index_image(&self, image: &Path) -> Result<bool> {
+        let content = self.backend.recognize(image).await?;
+        if content.is_empty() {
+            return Ok(false);
+        }
+        self.index
+            .upsert(&IndexedText {
+                kind: TextKind::Image,
+                source: image.to_string_lossy().into_owned(),
+                content,
+            })
+            .await?;
+        Ok(true)
+    }
+
+    /// 识别目录下的所有图片，`force` 为 `false` 时跳过已索引的图片；单张失败只记录日志
+    pub async fn index_dir(&self, dir: &Path, force: bool) -> Result<OcrSummary> {
+        let mut images = Vec::new();
+        collect_images(dir, &mut images)?;
+        images.sort();
+
+        let mut summary = OcrSummary::default();
+        for image in images {
+            let source = image.to_string_lossy();
+            if !force && self.index.contains(TextKind::Image, &source).await? {
+                summary.skipped += 1;
+                continue;
+            }
+            match self.index_image(&image).await {
+                Ok(true) => summary.indexed += 1,
+                Ok(false) => summary.skipped += 1,
+                Err(e) => {
+                    tracing::warn!("图片识别失败: {:?} - {}", image, e);
+                    summary.failed += 1;
+                }
+            }
+        }
+        Ok(summary)
+    }
+}
+
+/// 递归收集图片，跳过缩略图目录
+fn collect_images(dir: &Path, images: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if path.file_name().is_some_and(|name| name != ".thumbs") {
+                collect_images(&path, images)?;
+            }
+            continue;
+        }
+        let is_image = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()));
+        if is_image {
+            images.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wechat::search::SEARCH_INDEX_FILE;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_index_dir_with_command_backend() {
+        let dir = tempfile::tempdir().unwrap();
+        let images = dir.path().join("images");
+        std::fs::create_dir_all(images.join(".thumbs")).unwrap();
+        std::fs::write(images.join("receipt.png"), b"png").unwrap();
+        std::fs::write(images.join(".thumbs/receipt.png.jpg"), b"jpg").unwrap();
+        std::fs::write(images.join("notes.txt"), b"txt").unwrap();
+
+        let index = SearchIndex::open(&dir.path().join(SEARCH_INDEX_FILE)).await.unwrap();
+        let backend = CommandOcr::new(OcrConfig {
+            command: "echo".to_string(),
+            args: vec!["发票金额 {input}".to_string()],
+        });
+        let indexer = OcrIndexer::new(backend, index.clone());
+
+        let summary = indexer.index_dir(&images, false).await.unwrap();
+        assert_eq!(summary, OcrSummary { indexed: 1, skipped: 0, failed: 0 });
+        let hits = index.search("发票金额", 10).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert!(hits[0].source.ends_with("receipt.png"));
+
+        assert_eq!(indexer.index_dir(&images, false).await.unwrap().skipped, 1);
+
+        let failing = OcrIndexer::new(CommandOcr::new(OcrConfig { command: "false".to_string(), args: vec![] }), index);
+        assert_eq!(failing.index_dir(&images, true).await.unwrap().failed, 1);
+    }
+}
@@ -0,0 +1,176 @@
+//! 缩略图生成
+//!
+//! 为导出的图片生成小尺寸 JPEG 预览，视频使用微信保存在同目录下的 `*_thumb.jpg` 封面；
+//! 缩略图默认保存在媒体文件所在目录的 `.thumbs` 子目录中；媒体位于微信数据目录等只读位置时，
+//! 可指定单独的存储目录。已生成且未过期的缩略图直接复用。
+
+use image::codecs::jpeg::JpegEncoder;
+use image::ImageReader;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use crate::errors::Result;
+
+/// 缩略图目录名
+pub const THUMBNAIL_DIR: &str = ".thumbs";
+
+/// 缩略图配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThumbnailConfig {
+    /// 最长边像素
+    pub max_size: u32,
+    /// JPEG 质量（1-100）
+    pub quality: u8,
+}
+
+impl Default for ThumbnailConfig {
+    fn default() -> Self {
+        Self {
+            max_size: 256,
+            quality: 80,
+        }
+    }
+}
+
+/// 缩略图生成器
+#[derive(Debug, Clone, Default)]
+pub struct Thumbnailer {
+    config: ThumbnailConfig,
+    store_dir: Option<PathBuf>,
+}
+
+impl Thumbnailer {
+    /// 按配置创建
+    pub fn new(config: ThumbnailConfig) -> Self {
+        Self {
+            config,
+            store_dir: None,
+        }
+    }
+
+    /// 将缩略图统一保存到 `dir/.thumbs`，而不是媒体文件旁边
+    pub fn with_store_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.store_dir = Some(dir.into());
+        self
+    }
+
+    /// 媒体文件对应的缩略图路径
+    pub fn thumbnail_path(&self, media: &Path) -> PathBuf {
+        let name = media.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+        let base = match &self.store_dir {
+            Some(dir) => dir.as_path(),
+            None => media.parent().unwrap_or(Path::new(".")),
+        };
+        base.join(THUMBNAIL_DIR).join(format!("{}.jpg", name))
+    }
+
+    /// 生成缩略图并返回其路径
+    ///
+    /// 无法识别的格式（如未解码的 `.dat`）以及没有封面的视频返回 `None`。
+    pub fn generate(&self, media: &Path) -> Result<Option<PathBuf>> {
+        let Some(source) = preview_source(media) else {
+            return Ok(None);
+        };
+        let target = self.thumbnail_path(media);
+        if is_fresh(&target, media) {
+            return Ok(Some(target));
+        }
+
+        let reader = ImageReader::open(&source)?.with_guessed_format()?;
+        if reader.format().is_none() {
+            tracing::debug!("无法识别的图片格式，跳过缩略图: {:?}", source);
+            return Ok(None);
+        }
+        let image = match reader.decode() {
+            Ok(image) => image,
+            Err(e) => {
+                tracing::warn!("图片解码失败，跳过缩略图: {:?} - {}", source, e);
+                return Ok(None);
+            }
+        };
+
+        let thumbnail = image.thumbnail(self.config.max_size, self.config.max_size).to_rgb8();
+        if let Some(dir) = target.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let writer = BufWriter::new(File::create(&target)?);
+        JpegEncoder::new_with_quality(writer, self.config.quality.clamp(1, 100))
+            .encode_image(&thumbnail)
+            .map_err(std::io::Error::other)?;
+        Ok(Some(target))
+    }
+
+    /// 为多个媒体文件生成缩略图，单个文件失败只记录日志
+    pub fn generate_all<'a>(&self, media: impl IntoIterator<Item = &'a Path>) -> Vec<PathBuf> {
+        media
+            .into_iter()
+            .filter_map(|path| match self.generate(path) {
+                Ok(thumbnail) => thumbnail,
+                Err(e) => {
+                    tracing::warn!("生成缩略图失败: {:?} - {}", path, e);
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+/// 缩略图的来源图片：视频使用同目录的 `*_thumb.jpg` 封面
+fn preview_source(media: &Path) -> Option<PathBuf> {
+    let extension = media.extension()?.to_str()?.to_ascii_lowercase();
+    if matches!(extension.as_str(), "mp4" | "mov" | "m4v") {
+        let stem = media.file_stem()?.to_string_lossy();
+        let cover = media.with_file_name(format!("{}_thumb.jpg", stem));
+        return cover.is_file().then_some(cover);
+    }
+    Some(media.to_path_buf())
+}
+
+/// 缩略图存在且不早于媒体文件
+fn is_fresh(thumbnail: &Path, media: &Path) -> bool {
+    let modified = |p: &Path| std::fs::metadata(p).and_then(|m| m.modified()).ok();
+    matches!((modified(thumbnail), modified(media)), (Some(t), Some(m)) if t >= m)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, RgbImage};
+
+    #[test]
+    fn test_generate_thumbnails() {
+        let dir = tempfile::tempdir().unwrap();
+        let photo = dir.path().join("photo.png");
+        RgbImage::from_pixel(800, 400, image::Rgb([200, 10, 10]))
+            .save_with_format(&photo, ImageFormat::Png)
+            .unwrap();
+        let video = dir.path().join("clip.mp4");
+        std::fs::write(&video, b"not really a video").unwrap();
+        RgbImage::from_pixel(64, 64, image::Rgb([0, 0, 0]))
+            .save_with_format(dir.path().join("clip_thumb.jpg"), ImageFormat::Jpeg)
+            .unwrap();
+        let dat = dir.path().join("a1b2.dat");
+        std::fs::write(&dat, [0x12u8; 64]).unwrap();
+
+        let thumbnailer = Thumbnailer::default();
+        let thumbnail = thumbnailer.generate(&photo).unwrap().unwrap();
+        assert_eq!(thumbnail, dir.path().join(".thumbs/photo.png.jpg"));
+        let size = image::image_dimensions(&thumbnail).unwrap();
+        assert_eq!(size, (256, 128));
+
+        assert!(thumbnailer.generate(&video).unwrap().is_some());
+        assert!(thumbnailer.generate(&dat).unwrap().is_none());
+        assert!(thumbnailer.generate(&dir.path().join("other.mp4")).unwrap().is_none());
+        assert_eq!(thumbnailer.generate_all([photo.as_path(), dat.as_path()]).len(), 1);
+
+        let store = tempfile::tempdir().unwrap();
+        let thumbnailer = Thumbnailer::default().with_store_dir(store.path());
+        assert_eq!(
+            thumbnailer.generate(&photo).unwrap().unwrap(),
+            store.path().join(".thumbs/photo.png.jpg")
+        );
+    }
+}
@@ -0,0 +1,151 @@
+//! 语音消息转写
+//!
+//! 导出流程中为语音消息调用可替换的转写后端，把文本附加到 [`Message::transcript`]，
+//! 同时写入 [`SearchIndex`] 供搜索；已转写过的语音直接复用索引中的文本。
+//! 默认后端调用 whisper.cpp 的 `whisper-cli`；微信语音为 SILK 格式，需先转码为 WAV，
+//! 可将转码和转写写在一个脚本中作为命令配置。
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::run_text_command;
+use crate::errors::Result;
+use crate::models::Message;
+use crate::wechat::search::{IndexedText, SearchIndex, TextKind};
+
+/// 语音消息类型
+const MSG_TYPE_VOICE: i64 = 34;
+
+/// 语音转写配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TranscriptionConfig {
+    /// 转写命令
+    pub command: String,
+    /// 命令参数，`{input}` 替换为音频文件路径
+    pub args: Vec<String>,
+}
+
+impl Default for TranscriptionConfig {
+    fn default() -> Self {
+        Self {
+            command: "whisper-cli".to_string(),
+            args: ["-m", "models/ggml-base.bin", "-l", "auto", "-nt", "-np", "-f", "{input}"]
+                .map(String::from)
+                .to_vec(),
+        }
+    }
+}
+
+/// 转写后端
+#[async_trait]
+pub trait Transcriber: Send + Sync {
+    /// 将音频转写为文本
+    async fn transcribe(&self, audio: &Path) -> Result<String>;
+}
+
+/// 调用外部命令的转写后端
+#[derive(Debug, Clone, Default)]
+pub struct CommandTranscriber {
+    config: TranscriptionConfig,
+}
+
+impl CommandTranscriber {
+    /// 按配置创建
+    pub fn new(config: TranscriptionConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl Transcriber for CommandTranscriber {
+    async fn transcribe(&self, audio: &Path) -> Result<String> {
+        run_text_command(&self.config.command, &self.config.args, audio).await
+    }
+}
+
+/// 导出流程中的语音转写步骤
+pub struct VoiceTranscription<T: Transcriber> {
+    transcriber: T,
+    index: SearchIndex,
+}
+
+impl<T: Transcriber> VoiceTranscription<T> {
+    /// 使用指定后端和索引创建
+    pub fn new(transcriber: T, index: SearchIndex) -> Self {
+        Self { transcriber, index }
+    }
+
+    /// 为语音消息附加转写文本，`audio` 为导出的语音文件
+    ///
+    /// 非语音消息不做处理并返回 `false`；转写结果为空时不写入索引。
+    pub async fn attach(&self, message: &mut Message, audio: &Path) -> Result<bool> {
+        if message.msg_type != MSG_TYPE_VOICE {
+            return Ok(false);
+        }
+        let source = format!("{}:{}", message.talker, message.seq);
+        if let Some(text) = self.index.get(TextKind::Voice, &source).await? {
+            message.transcript = Some(text);
+            return Ok(true);
+        }
+
+        let text = self.transcriber.transcribe(audio).await?;
+        if text.is_empty() {
+            return Ok(false);
+        }
+        self.index
+            .upsert(&IndexedText {
+                kind: TextKind::Voice,
+                source,
+                content: text.clone(),
+            })
+            .await?;
+        message.transcript = Some(text);
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wechat::search::SEARCH_INDEX_FILE;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_attach_transcript_to_voice_message() {
+        let dir = tempfile::tempdir().unwrap();
+        let index = SearchIndex::open(&dir.path().join(SEARCH_INDEX_FILE)).await.unwrap();
+        let audio = dir.path().join("voice.silk");
+        std::fs::write(&audio, b"#!SILK_V3").unwrap();
+
+        let mut voice = Message::new();
+        voice.msg_type = 34;
+        voice.talker = "wxid_friend".to_string();
+        voice.seq = 42;
+
+        let step = VoiceTranscription::new(
+            CommandTranscriber::new(TranscriptionConfig {
+                command: "echo".to_string(),
+                args: vec!["明天上午十点开会".to_string()],
+            }),
+            index.clone(),
+        );
+        assert!(step.attach(&mut voice, &audio).await.unwrap());
+        assert_eq!(voice.transcript.as_deref(), Some("明天上午十点开会"));
+        assert_eq!(index.search("十点开会", 10).await.unwrap()[0].source, "wxid_friend:42");
+
+        // 已转写的语音复用索引，不再调用后端
+        let cached = VoiceTranscription::new(
+            CommandTranscriber::new(TranscriptionConfig { command: "false".to_string(), args: vec![] }),
+            index,
+        );
+        voice.transcript = None;
+        assert!(cached.attach(&mut voice, &audio).await.unwrap());
+        assert_eq!(voice.transcript.as_deref(), Some("明天上午十点开会"));
+
+        let mut text = Message::new();
+        assert!(!cached.attach(&mut text, &audio).await.unwrap());
+        assert!(text.transcript.is_none());
+    }
+}
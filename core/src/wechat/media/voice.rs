@@ -0,0 +1,345 @@
+//! 语音消息转码
+//!
+//! 微信语音为 SILK v3 编码，数据开头比标准的 `#!SILK_V3` 文件多一个 `0x02` 字节。
+//! 解码调用随程序分发的 `silk_v3_decoder`（优先使用与可执行文件同目录的副本，其次按 PATH 查找），
+//! 得到 24kHz 单声道 16 位 PCM 后补上 WAV 文件头；MP3 再交给 `ffmpeg` 编码。
+//! [`VoiceTranscoder::extract_all`] 从 [`VoiceRepository`] 批量导出语音，导出流程中按消息逐条转码。
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tokio_util::sync::CancellationToken;
+use ts_rs::TS;
+
+use super::INPUT_PLACEHOLDER;
+use crate::errors::{MwxDumpError, Result};
+use crate::export::sanitize_filename;
+use crate::wechat::datadir::SourceGuard;
+use crate::wechat::db::voices::VoiceRepository;
+
+/// 外部命令参数中的输出路径占位符
+pub const OUTPUT_PLACEHOLDER: &str = "{output}";
+
+/// 解码输出的 PCM 采样率
+pub const PCM_SAMPLE_RATE: u32 = 24000;
+
+/// SILK 文件头
+const SILK_HEADER: &[u8] = b"#!SILK_V3";
+
+/// 语音消息类型
+pub(crate) const MSG_TYPE_VOICE: i64 = 34;
+
+/// 语音导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioFormat {
+    /// 原始 SILK 数据，不转码
+    Silk,
+    /// 16 位 PCM 的 WAV
+    Wav,
+    /// MP3（需要 ffmpeg）
+    Mp3,
+}
+
+impl AudioFormat {
+    /// 所有格式
+    pub const ALL: [AudioFormat; 3] = [AudioFormat::Silk, AudioFormat::Wav, AudioFormat::Mp3];
+
+    /// 文件扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AudioFormat::Silk => "silk",
+            AudioFormat::Wav => "wav",
+            AudioFormat::Mp3 => "mp3",
+        }
+    }
+
+    /// MIME 类型
+    pub fn mime(&self) -> &'static str {
+        match self {
+            AudioFormat::Silk => "audio/silk",
+            AudioFormat::Wav => "audio/wav",
+            AudioFormat::Mp3 => "audio/mpeg",
+        }
+    }
+}
+
+impl FromStr for AudioFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|format| format.extension().eq_ignore_ascii_case(value))
+            .ok_or_else(|| format!("不支持的语音格式: {}（可选 silk/wav/mp3）", value))
+    }
+}
+
+/// 语音转码配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VoiceConfig {
+    /// SILK 解码命令，输出 24kHz 单声道 16 位 PCM
+    pub decoder: String,
+    /// 解码命令参数，`{input}` 为 SILK 文件，`{output}` 为 PCM 文件
+    pub decoder_args: Vec<String>,
+    /// MP3 编码命令
+    pub encoder: String,
+    /// 编码命令参数，`{input}` 为 WAV 文件，`{output}` 为 MP3 文件
+    pub encoder_args: Vec<String>,
+}
+
+impl Default for VoiceConfig {
+    fn default() -> Self {
+        Self {
+            decoder: "silk_v3_decoder".to_string(),
+            decoder_args: ["{input}", "{output}", "-Fs_API", "24000"].map(String::from).to_vec(),
+            encoder: "ffmpeg".to_string(),
+            encoder_args: ["-y", "-loglevel", "error", "-i", "{input}", "{output}"]
+                .map(String::from)
+                .to_vec(),
+        }
+    }
+}
+
+/// 批量导出统计
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoiceReport {
+    /// 找到的语音数
+    pub voices: usize,
+    /// 转码成功的语音数
+    pub converted: usize,
+    /// 输出已存在而跳过的语音数
+    pub skipped: usize,
+    /// 没有数据或转码失败的语音数
+    pub failed: usize,
+}
+
+/// 数据是否为 SILK 编码（允许微信的 `0x02` 前缀）
+pub fn is_silk(data: &[u8]) -> bool {
+    data.strip_prefix(&[0x02]).unwrap_or(data).starts_with(SILK_HEADER)
+}
+
+/// 为 24kHz 单声道 16 位 PCM 生成 WAV 文件
+pub fn pcm_to_wav(pcm: &[u8]) -> Vec<u8> {
+    let data_len = pcm.len() as u32;
+    let byte_rate = PCM_SAMPLE_RATE * 2;
+    let mut wav = Vec::with_capacity(44 + pcm.len());
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    // PCM、单声道
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&PCM_SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&byte_rate.to_le_bytes());
+    // 块对齐 2 字节、16 位采样
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    wav.extend_from_slice(pcm);
+    wav
+}
+
+/// 命令为单独的文件名时优先使用与可执行文件同目录的副本
+fn resolve_command(command: &str) -> PathBuf {
+    let path = Path::new(command);
+    if path.components().count() == 1 {
+        let bundled = std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(|dir| dir.join(format!("{}{}", command, std::env::consts::EXE_SUFFIX))));
+        if let Some(bundled) = bundled.filter(|bundled| bundled.is_file()) {
+            return bundled;
+        }
+    }
+    path.to_path_buf()
+}
+
+/// 运行转码命令，替换输入和输出路径占位符
+async fn run_command(command: &str, args: &[String], input: &Path, output: &Path) -> Result<()> {
+    let (input, output) = (input.to_string_lossy(), output.to_string_lossy());
+    let result = tokio::process::Command::new(resolve_command(command))
+        .args(
+            args.iter()
+                .map(|arg| arg.replace(INPUT_PLACEHOLDER, &input).replace(OUTPUT_PLACEHOLDER, &output)),
+        )
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| anyhow::anyhow!("无法运行 {}: {}", command, e))?;
+    if !result.status.success() {
+        return Err(anyhow::anyhow!(
+            "外部命令执行失败 ({}, {}): {}",
+            command,
+            result.status,
+            String::from_utf8_lossy(&result.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// 语音转码器
+#[derive(Debug, Clone, Default)]
+pub struct VoiceTranscoder {
+    config: VoiceConfig,
+}
+
+impl VoiceTranscoder {
+    /// 按配置创建
+    pub fn new(config: VoiceConfig) -> Self {
+        Self { config }
+    }
+
+    /// 把 SILK 数据转为 `format` 写入 `output`
+    pub async fn transcode(&self, silk: &[u8], format: AudioFormat, output: &Path) -> Result<()> {
+        if !is_silk(silk) {
+            return Err(anyhow::anyhow!("不是 SILK 格式的语音数据"));
+        }
+        if let Some(dir) = output.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        match format {
+            AudioFormat::Silk => std::fs::write(output, silk)?,
+            AudioFormat::Wav => std::fs::write(output, pcm_to_wav(&self.decode_pcm(silk).await?))?,
+            AudioFormat::Mp3 => {
+                let temp = tempfile::tempdir()?;
+                let wav = temp.path().join("voice.wav");
+                std::fs::write(&wav, pcm_to_wav(&self.decode_pcm(silk).await?))?;
+                run_command(&self.config.encoder, &self.config.encoder_args, &wav, output).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 解码为 PCM
+    async fn decode_pcm(&self, silk: &[u8]) -> Result<Vec<u8>> {
+        let temp = tempfile::tempdir()?;
+        let (input, output) = (temp.path().join("voice.silk"), temp.path().join("voice.pcm"));
+        std::fs::write(&input, silk)?;
+        run_command(&self.config.decoder, &self.config.decoder_args, &input, &output).await?;
+        Ok(std::fs::read(&output)?)
+    }
+
+    /// 导出语音到 `output/<会话>/<发送时间>_<local_id>.<扩展名>`，`talker` 为 `None` 时导出所有会话
+    ///
+    /// 输出已存在的语音跳过；单条语音失败只计数并记录日志。
+    pub async fn extract_all(
+        &self,
+        repository: &VoiceRepository,
+        talker: Option<&str>,
+        format: AudioFormat,
+        output: &Path,
+        cancel_token: &CancellationToken,
+    ) -> Result<VoiceReport> {
+        SourceGuard::new().check_output(output)?;
+        let entries = repository.list(talker).await?;
+        let mut report = VoiceReport {
+            voices: entries.len(),
+            ..Default::default()
+        };
+        for entry in &entries {
+            if cancel_token.is_cancelled() {
+                return Err(MwxDumpError::Cancelled.into());
+            }
+            let target = output.join(sanitize_filename(&entry.talker)).join(format!(
+                "{}_{}.{}",
+                entry.create_time,
+                entry.local_id,
+                format.extension()
+            ));
+            if target.exists() {
+                report.skipped += 1;
+                continue;
+            }
+            let converted = match repository.data(entry).await? {
+                Some(data) => self.transcode(&data, format, &target).await,
+                None => Err(anyhow::anyhow!("没有语音数据")),
+            };
+            match converted {
+                Ok(()) => report.converted += 1,
+                Err(e) => {
+                    tracing::warn!("导出语音 {} {} 失败: {}", entry.talker, entry.local_id, e);
+                    report.failed += 1;
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wechat::db::voices::tests::{create_media_db, VOICE_DATA};
+
+    /// 把输入原样复制为输出的“解码器”
+    #[cfg(unix)]
+    fn copy_config() -> VoiceConfig {
+        VoiceConfig {
+            decoder: "cp".to_string(),
+            decoder_args: vec!["{input}".to_string(), "{output}".to_string()],
+            encoder: "cp".to_string(),
+            encoder_args: vec!["{input}".to_string(), "{output}".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_formats_and_wav_header() {
+        assert_eq!("MP3".parse::<AudioFormat>(), Ok(AudioFormat::Mp3));
+        assert!("ogg".parse::<AudioFormat>().is_err());
+        assert!(is_silk(VOICE_DATA) && is_silk(b"#!SILK_V3") && !is_silk(b"RIFF"));
+
+        let wav = pcm_to_wav(&[0u8; 480]);
+        assert_eq!(wav.len(), 44 + 480);
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(wav[24..28].try_into().unwrap()), PCM_SAMPLE_RATE);
+        assert_eq!(u32::from_le_bytes(wav[40..44].try_into().unwrap()), 480);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_extract_voices() {
+        let work = tempfile::tempdir().unwrap();
+        create_media_db(work.path()).await;
+        let repo = VoiceRepository::open(work.path()).await.unwrap();
+        let out = tempfile::tempdir().unwrap();
+        let transcoder = VoiceTranscoder::new(copy_config());
+        let token = CancellationToken::new();
+
+        let report = transcoder
+            .extract_all(&repo, Some("wxid_friend"), AudioFormat::Wav, out.path(), &token)
+            .await
+            .unwrap();
+        assert_eq!((report.voices, report.converted, report.failed), (2, 2, 0));
+        let wav = std::fs::read(out.path().join("wxid_friend").join("1700000003_4.wav")).unwrap();
+        assert_eq!(&wav[44..], VOICE_DATA);
+
+        let report = transcoder
+            .extract_all(&repo, None, AudioFormat::Mp3, out.path(), &token)
+            .await
+            .unwrap();
+        assert_eq!((report.voices, report.converted), (3, 3));
+        let mp3 = std::fs::read(out.path().join("123@chatroom").join("1700000200_1.mp3")).unwrap();
+        assert_eq!(&mp3[..4], b"RIFF");
+
+        let report = transcoder
+            .extract_all(&repo, None, AudioFormat::Mp3, out.path(), &token)
+            .await
+            .unwrap();
+        assert_eq!(report.skipped, 3);
+
+        let broken = VoiceTranscoder::new(VoiceConfig {
+            decoder: "false".to_string(),
+            ..copy_config()
+        });
+        let report = broken
+            .extract_all(&repo, None, AudioFormat::Wav, &out.path().join("broken"), &token)
+            .await
+            .unwrap();
+        assert_eq!(report.failed, 3);
+        assert!(broken.transcode(b"RIFF", AudioFormat::Silk, &out.path().join("x.silk")).await.is_err());
+    }
+}
@@ -0,0 +1,395 @@
+//! 合并多个工作目录
+//!
+//! 电脑版解密、电脑版备份、iOS 和安卓导入的输出都使用同一套工作目录结构，
+//! [`ArchiveMerger`] 把它们合并为一个工作目录：联系人取并集，会话按 wxid 对齐，
+//! 同一会话的消息按时间合并去重后重新编号。
+//!
+//! 默认只去掉完全相同的消息（同一目录被重复加入或多次导入）。跨设备模式下，手机和电脑上
+//! 同一条消息的时间可能相差一两秒，内容的 XML 格式也不同：在两个来源时间范围重叠的部分，
+//! 发送者和类型相同、时间相差不超过容差的消息，文本按内容比较，图片、视频和文件按媒体 MD5
+//! 比较，匹配的只保留先加入的来源中的一条。
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::errors::{MwxDumpError, Result};
+use crate::models::Message;
+use crate::wechat::datadir::SourceGuard;
+use crate::wechat::db::contacts::ContactRepository;
+use crate::wechat::db::messages::MessageRepository;
+use crate::wechat::db::sessions::SessionRepository;
+use crate::wechat::db::writer::{ImportedContact, ImportedMessage, WorkDirWriter};
+use crate::wechat::media::media_md5;
+
+/// 跨设备对齐时默认的时间容差（秒）
+pub const DEFAULT_TOLERANCE_SECS: i64 = 2;
+
+/// 每次从来源读取的消息数
+const PAGE_SIZE: u32 = 5000;
+
+/// 文本消息类型
+const MSG_TYPE_TEXT: i64 = 1;
+
+/// 合并统计
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MergeReport {
+    /// 来源数
+    pub sources: usize,
+    /// 合并后的会话数
+    pub conversations: usize,
+    /// 合并后的联系人数
+    pub contacts: usize,
+    /// 写入的消息数
+    pub messages: u64,
+    /// 去掉的重复消息数
+    pub duplicates: u64,
+    /// 耗时（毫秒）
+    pub elapsed_ms: u64,
+}
+
+/// 来源中的消息
+struct SourceMessage {
+    source: usize,
+    message: Message,
+}
+
+impl SourceMessage {
+    /// 跨设备比较用的内容：文本为去掉首尾空白的内容，媒体为 MD5，其他消息为原始内容
+    fn fingerprint(&self) -> String {
+        let message = &self.message;
+        if message.msg_type == MSG_TYPE_TEXT {
+            return message.content.trim().to_string();
+        }
+        match media_md5(message) {
+            Some((_, md5)) => md5,
+            None => message.content.clone(),
+        }
+    }
+}
+
+/// 工作目录合并
+pub struct ArchiveMerger {
+    sources: Vec<PathBuf>,
+    self_wxid: Option<String>,
+    cross_device: bool,
+    tolerance_secs: i64,
+    cancel_token: CancellationToken,
+}
+
+impl ArchiveMerger {
+    /// 按顺序合并 `sources`，重复的消息保留先出现的来源中的一条
+    pub fn new(sources: Vec<PathBuf>) -> Self {
+        Self {
+            sources,
+            self_wxid: None,
+            cross_device: false,
+            tolerance_secs: DEFAULT_TOLERANCE_SECS,
+            cancel_token: CancellationToken::new(),
+        }
+    }
+
+    /// 当前账号的 wxid，用于标记自己发送的消息
+    pub fn with_self_wxid(mut self, wxid: impl Into<String>) -> Self {
+        self.self_wxid = Some(wxid.into());
+        self
+    }
+
+    /// 跨设备对齐：按时间容差和内容识别不同来源中的同一条消息
+    pub fn with_cross_device(mut self, enabled: bool) -> Self {
+        self.cross_device = enabled;
+        self
+    }
+
+    /// 跨设备对齐的时间容差（秒）
+    pub fn with_tolerance(mut self, secs: i64) -> Self {
+        self.tolerance_secs = secs.max(0);
+        self
+    }
+
+    /// 设置取消令牌，取消后在下一个会话开始前返回 `MwxDumpError::Cancelled`
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel_token = token;
+        self
+    }
+
+    /// 合并到 `output`，生成与解密输出相同的工作目录结构
+    pub async fn merge(&self, output: &Path) -> Result<MergeReport> {
+        let started = Instant::now();
+        let mut guard = SourceGuard::new();
+        for source in &self.sources {
+            guard = guard.with_root(source);
+        }
+        guard.check_output(output)?;
+        info!(
+            "🔀 合并 {} 个工作目录 -> {:?}{}",
+            self.sources.len(),
+            output,
+            if self.cross_device { "（跨设备对齐）" } else { "" }
+        );
+
+        let mut repositories = Vec::with_capacity(self.sources.len());
+        let mut contacts: Vec<ImportedContact> = Vec::new();
+        let mut contact_index: HashMap<String, usize> = HashMap::new();
+        let mut talkers = BTreeSet::new();
+        for source in &self.sources {
+            let mut repository = MessageRepository::open(source).await?;
+            if let Some(self_wxid) = &self.self_wxid {
+                repository = repository.with_self_wxid(self_wxid.clone());
+            }
+            repositories.push(repository);
+
+            let source_contacts = ContactRepository::open(source).await.unwrap_or_default();
+            for contact in source_contacts.all() {
+                talkers.insert(contact.username.clone());
+                let non_empty = |text: &Option<String>| text.clone().filter(|text| !text.is_empty());
+                match contact_index.get(&contact.username) {
+                    // 先加入的来源优先，缺少的昵称和备注由后面的来源补全
+                    Some(&index) => {
+                        let merged = &mut contacts[index];
+                        merged.nickname = merged.nickname.take().or_else(|| non_empty(&contact.nickname));
+                        merged.remark = merged.remark.take().or_else(|| non_empty(&contact.remark));
+                    }
+                    None => {
+                        contact_index.insert(contact.username.clone(), contacts.len());
+                        contacts.push(ImportedContact {
+                            username: contact.username.clone(),
+                            nickname: non_empty(&contact.nickname),
+                            remark: non_empty(&contact.remark),
+                        });
+                    }
+                }
+            }
+            match SessionRepository::open(source).await {
+                Ok(sessions) => talkers.extend(sessions.all().iter().map(|session| session.username.clone())),
+                Err(e) => warn!("无法读取 {:?} 的会话列表: {}", source, e),
+            }
+        }
+
+        let mut report = MergeReport {
+            sources: self.sources.len(),
+            contacts: contacts.len(),
+            ..Default::default()
+        };
+        let mut writer = WorkDirWriter::create(output).await?;
+        for talker in &talkers {
+            if self.cancel_token.is_cancelled() {
+                return Err(MwxDumpError::Cancelled.into());
+            }
+            let mut messages = Vec::new();
+            for (source, repository) in repositories.iter().enumerate() {
+                let mut cursor = None;
+                loop {
+                    let page = repository.page(talker, cursor, PAGE_SIZE).await?;
+                    let Some(last) = page.last() else {
+                        break;
+                    };
+                    cursor = Some(last.seq);
+                    let full = page.len() == PAGE_SIZE as usize;
+                    messages.extend(page.into_iter().map(|message| SourceMessage { source, message }));
+                    if !full {
+                        break;
+                    }
+                }
+            }
+            if messages.is_empty() {
+                continue;
+            }
+            let total = messages.len() as u64;
+            let merged = self.deduplicate(messages);
+            report.duplicates += total - merged.len() as u64;
+            report.conversations += 1;
+
+            // 按合并后的顺序重新编号，同一秒内的消息保持先后
+            let mut last_second = i64::MIN;
+            let mut offset = 0;
+            for message in merged {
+                let create_time = message.time.timestamp();
+                offset = if create_time == last_second { (offset + 1).min(999) } else { 0 };
+                last_second = create_time;
+                let content = match message.is_chatroom && !message.is_self && message.sender != *talker {
+                    true => format!("{}:\n{}", message.sender, message.content),
+                    false => message.content,
+                };
+                let imported = ImportedMessage {
+                    seq: create_time * 1000 + offset,
+                    create_time,
+                    msg_type: message.msg_type,
+                    sub_type: message.sub_type,
+                    // 单聊中对方发送的消息在 4.x 中发送者可能为空，读取时已还原为会话
+                    sender: message.sender,
+                    is_sender: message.is_self,
+                    content,
+                };
+                writer.insert_message(talker, &imported).await?;
+            }
+        }
+        report.messages = writer.finish(&contacts).await?;
+        report.elapsed_ms = started.elapsed().as_millis() as u64;
+        info!(
+            "✅ 合并完成: {} 个会话，{} 条消息，去掉 {} 条重复消息",
+            report.conversations, report.messages, report.duplicates
+        );
+        Ok(report)
+    }
+
+    /// 按时间合并一个会话在各来源中的消息并去重
+    fn deduplicate(&self, mut messages: Vec<SourceMessage>) -> Vec<Message> {
+        messages.sort_by_key(|m| (m.message.time, m.source, m.message.seq));
+        let ranges = source_ranges(&messages);
+        let mut kept: Vec<SourceMessage> = Vec::with_capacity(messages.len());
+        for candidate in messages {
+            if self.is_duplicate(&candidate, &kept, &ranges) {
+                continue;
+            }
+            kept.push(candidate);
+        }
+        kept.into_iter().map(|m| m.message).collect()
+    }
+
+    /// 是否与已保留的消息重复，只需向前查看容差范围内的消息
+    fn is_duplicate(&self, candidate: &SourceMessage, kept: &[SourceMessage], ranges: &HashMap<usize, (i64, i64)>) -> bool {
+        let time = candidate.message.time.timestamp();
+        let tolerance = if self.cross_device { self.tolerance_secs } else { 0 };
+        let mut fingerprint = None;
+        for existing in kept.iter().rev() {
+            let existing_time = existing.message.time.timestamp();
+            if time - existing_time > tolerance {
+                return false;
+            }
+            let (a, b) = (&candidate.message, &existing.message);
+            if a.msg_type != b.msg_type || a.sender != b.sender || a.is_self != b.is_self {
+                continue;
+            }
+            if existing.source == candidate.source || !self.cross_device {
+                // 同一来源或非跨设备模式只去掉完全相同的消息
+                if existing_time == time && a.sub_type == b.sub_type && a.content == b.content {
+                    return true;
+                }
+                continue;
+            }
+            // 只有两个来源都覆盖的时间段才可能有重复
+            if !overlaps(ranges, existing.source, candidate.source, time) {
+                continue;
+            }
+            let fingerprint = fingerprint.get_or_insert_with(|| candidate.fingerprint());
+            if *fingerprint == existing.fingerprint() {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// 各来源在会话中的时间范围
+fn source_ranges(messages: &[SourceMessage]) -> HashMap<usize, (i64, i64)> {
+    let mut ranges: HashMap<usize, (i64, i64)> = HashMap::new();
+    for m in messages {
+        let time = m.message.time.timestamp();
+        let range = ranges.entry(m.source).or_insert((time, time));
+        range.0 = range.0.min(time);
+        range.1 = range.1.max(time);
+    }
+    ranges
+}
+
+/// 时间是否位于两个来源的时间范围的重叠部分
+fn overlaps(ranges: &HashMap<usize, (i64, i64)>, a: usize, b: usize, time: i64) -> bool {
+    match (ranges.get(&a), ranges.get(&b)) {
+        (Some(&(a_start, a_end)), Some(&(b_start, b_end))) => {
+            a_start.max(b_start) <= time && time <= a_end.min(b_end)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 写入一个来源的工作目录
+    async fn create_source(dir: &Path, nickname: Option<&str>, messages: &[(&str, i64, i64, &str, bool, &str)]) {
+        let mut writer = WorkDirWriter::create(dir).await.unwrap();
+        for &(talker, create_time, msg_type, sender, is_sender, content) in messages {
+            let message = ImportedMessage {
+                create_time,
+                msg_type,
+                sender: sender.to_string(),
+                is_sender,
+                content: content.to_string(),
+                ..Default::default()
+            };
+            writer.insert_message(talker, &message).await.unwrap();
+        }
+        let contacts = [ImportedContact {
+            username: "wxid_friend".to_string(),
+            nickname: nickname.map(str::to_string),
+            remark: None,
+        }];
+        writer.finish(&contacts).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cross_device_merge() {
+        let root = tempfile::tempdir().unwrap();
+        let (pc, phone) = (root.path().join("pc"), root.path().join("phone"));
+        let image = |md5: &str, extra: &str| format!(r#"<msg><img md5="{}" {}/></msg>"#, md5, extra);
+        create_source(
+            &pc,
+            None,
+            &[
+                ("wxid_friend", 1000, 1, "wxid_friend", false, "你好"),
+                ("wxid_friend", 1010, 1, "wxid_me", true, "在吗"),
+                ("wxid_friend", 1020, 3, "wxid_me", true, &image("0123456789abcdef0123456789abcdef", "length=\"1\"")),
+                ("wxid_friend", 1030, 1, "wxid_friend", false, "好的"),
+                ("123@chatroom", 1100, 1, "wxid_b", false, "wxid_b:\nhi all"),
+            ],
+        )
+        .await;
+        create_source(
+            &phone,
+            Some("老王"),
+            &[
+                ("wxid_friend", 500, 1, "wxid_friend", false, "更早的消息"),
+                ("wxid_friend", 1001, 1, "wxid_friend", false, "你好"),
+                ("wxid_friend", 1010, 1, "wxid_me", true, "在吗 "),
+                ("wxid_friend", 1021, 3, "wxid_me", true, &image("0123456789ABCDEF0123456789ABCDEF", "cdnurl=\"x\"")),
+                ("wxid_friend", 1031, 1, "wxid_friend", false, "好的！"),
+                ("wxid_friend", 2000, 1, "wxid_me", true, "更晚的消息"),
+            ],
+        )
+        .await;
+
+        // 不对齐时只去掉完全相同的消息
+        let plain = ArchiveMerger::new(vec![pc.clone(), phone.clone()]).with_self_wxid("wxid_me");
+        let report = plain.merge(&root.path().join("plain")).await.unwrap();
+        assert_eq!((report.messages, report.duplicates), (11, 0));
+
+        let merger = ArchiveMerger::new(vec![pc.clone(), phone.clone(), pc.clone()])
+            .with_self_wxid("wxid_me")
+            .with_cross_device(true);
+        assert!(merger.merge(&pc.join("merged")).await.is_err());
+        let output = root.path().join("merged");
+        let report = merger.merge(&output).await.unwrap();
+        assert_eq!((report.sources, report.conversations, report.contacts), (3, 2, 1));
+        assert_eq!((report.messages, report.duplicates), (8, 8));
+
+        let repo = MessageRepository::open(&output).await.unwrap().with_self_wxid("wxid_me");
+        let messages = repo.page("wxid_friend", None, 100).await.unwrap();
+        assert_eq!(
+            messages.iter().map(|m| m.content.as_str()).collect::<Vec<_>>()[..3],
+            ["更早的消息", "你好", "在吗"]
+        );
+        assert_eq!(messages.len(), 7);
+        assert!(messages.iter().any(|m| m.content == "好的") && messages.iter().any(|m| m.content == "好的！"));
+        assert!(messages[2].is_self && !messages[1].is_self);
+        let room = repo.page("123@chatroom", None, 10).await.unwrap();
+        assert_eq!((room[0].sender.as_str(), room[0].content.as_str()), ("wxid_b", "hi all"));
+
+        let contacts = ContactRepository::open(&output).await.unwrap();
+        assert_eq!(contacts.display_name("wxid_friend"), Some("老王"));
+    }
+}
@@ -1,8 +1,17 @@
 //! 微信相关功能模块
 
+pub mod android;
+pub mod datadir;
+pub mod db;
 pub mod decrypt;
+pub mod ios;
 pub mod key;
+pub mod media;
+pub mod merge;
+pub mod pcbackup;
 pub mod process;
+pub mod search;
+pub mod signatures;
 pub mod wechat_version;
 
 pub use wechat_version::WeChatVersion;
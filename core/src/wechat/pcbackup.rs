@@ -0,0 +1,456 @@
+//! 微信电脑版「备份与恢复」生成的备份
+//!
+//! 备份保存在 `<WeChat Files>/Backup/<wxid>/<备份ID>/` 下：
+//!
+//! - `Backup.db`：与账号数据库使用同一密钥的加密数据库，`Session` 表记录会话的昵称和备注，
+//!   `MsgSegments` 表记录每段消息所在的数据文件、偏移和长度
+//! - `BAK_<n>_TEXT`：消息数据，每段以 AES-256-CBC 加密（前16字节为 IV，PKCS#7 填充），
+//!   解密后为 protobuf 编码的消息列表
+//! - `BAK_<n>_MEDIA`：图片、视频等媒体数据，暂不解包
+//!
+//! 解包时按 4.x 解密输出的目录结构写入消息、联系人和会话数据库，导出、搜索等功能可以直接使用。
+
+use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, KeyIvInit};
+use prost::Message as _;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::errors::{MwxDumpError, Result, WeChatError};
+use crate::wechat::datadir::{open_read_only, SourceGuard};
+use crate::wechat::db::writer::{ImportedContact, ImportedMessage, WorkDirWriter};
+use crate::wechat::db::{DataSource, Query, SqliteDataSource};
+use crate::wechat::decrypt::{create_decryptor, DecryptReport, DecryptVersion};
+
+/// 备份索引数据库的文件名
+pub const BACKUP_DB: &str = "Backup.db";
+
+/// 消息分段的 IV 长度
+const SEGMENT_IV_SIZE: usize = 16;
+
+/// 单个消息分段的最大长度，超出时视为索引损坏
+const MAX_SEGMENT_SIZE: u64 = 256 * 1024 * 1024;
+
+/// 一段消息（`BackupSegment`）
+#[derive(Clone, PartialEq, prost::Message)]
+struct SegmentData {
+    #[prost(message, repeated, tag = "1")]
+    messages: Vec<BackupMessage>,
+}
+
+/// 备份中的消息（`BackupMessage`）
+#[derive(Clone, PartialEq, prost::Message)]
+struct BackupMessage {
+    /// 会话内的排序序号，旧版本备份为 0
+    #[prost(int64, tag = "1")]
+    seq: i64,
+    /// 发送时间（秒）
+    #[prost(int64, tag = "2")]
+    create_time: i64,
+    #[prost(int32, tag = "3")]
+    msg_type: i32,
+    #[prost(int32, tag = "4")]
+    sub_type: i32,
+    /// 发送者，单聊中对方发送的消息可能为空
+    #[prost(string, tag = "5")]
+    sender: String,
+    /// 是否为自己发送
+    #[prost(bool, tag = "6")]
+    is_sender: bool,
+    #[prost(string, tag = "7")]
+    content: String,
+}
+
+/// 消息分段索引
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Segment {
+    talker: String,
+    file: String,
+    offset: u64,
+    length: u64,
+}
+
+/// 解包统计
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnpackReport {
+    /// 备份的数据库版本
+    #[serde(skip)]
+    pub version: Option<DecryptVersion>,
+    /// 会话数
+    pub sessions: usize,
+    /// 消息分段数
+    pub segments: usize,
+    /// 无法读取或解密的分段数
+    pub failed_segments: usize,
+    /// 写入的消息数
+    pub messages: u64,
+    /// 耗时（毫秒）
+    pub elapsed_ms: u64,
+}
+
+impl From<UnpackReport> for DecryptReport {
+    /// 每个消息分段计为一个文件
+    fn from(report: UnpackReport) -> Self {
+        DecryptReport {
+            files: report.segments,
+            succeeded: report.segments - report.failed_segments,
+            failed: report.failed_segments,
+            elapsed_ms: report.elapsed_ms,
+            ..Default::default()
+        }
+    }
+}
+
+/// 目录是否为微信电脑版的备份（包含 `Backup.db`）
+pub fn is_backup_dir(path: &Path) -> bool {
+    path.join(BACKUP_DB).is_file()
+}
+
+/// 微信电脑版备份
+pub struct PcBackup {
+    dir: PathBuf,
+    key: Vec<u8>,
+    self_wxid: Option<String>,
+    cancel_token: CancellationToken,
+}
+
+impl PcBackup {
+    /// 打开备份目录，当前账号的 wxid 取自上级目录名（`Backup/<wxid>/<备份ID>`）
+    pub fn new(dir: impl Into<PathBuf>, key: Vec<u8>) -> Self {
+        let dir = dir.into();
+        let self_wxid = dir
+            .parent()
+            .and_then(Path::file_name)
+            .and_then(|name| name.to_str())
+            .filter(|name| name.starts_with("wxid_"))
+            .map(str::to_string);
+        Self {
+            dir,
+            key,
+            self_wxid,
+            cancel_token: CancellationToken::new(),
+        }
+    }
+
+    /// 指定当前账号的 wxid，用于标记自己发送的消息
+    pub fn with_self_wxid(mut self, wxid: impl Into<String>) -> Self {
+        self.self_wxid = Some(wxid.into());
+        self
+    }
+
+    /// 设置取消令牌，取消后停止处理剩余分段
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel_token = token;
+        self
+    }
+
+    /// 备份目录
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// 当前账号的 wxid
+    pub fn self_wxid(&self) -> Option<&str> {
+        self.self_wxid.as_deref()
+    }
+
+    /// 按密钥验证结果识别 `Backup.db` 的版本，密钥不正确时返回 `None`
+    pub async fn detect_version(&self) -> Result<Option<DecryptVersion>> {
+        let db = self.dir.join(BACKUP_DB);
+        for version in [DecryptVersion::V4, DecryptVersion::V3] {
+            if create_decryptor(version).validate_key(&db, &self.key).await? {
+                return Ok(Some(version));
+            }
+        }
+        Ok(None)
+    }
+
+    /// 解包到 `output`，生成与解密输出相同的工作目录结构
+    pub async fn unpack(&self, output: &Path) -> Result<UnpackReport> {
+        let started = Instant::now();
+        SourceGuard::new().with_root(&self.dir).check_output(output)?;
+        let version = self.detect_version().await?.ok_or_else(|| {
+            WeChatError::DecryptionFailed(format!("密钥无法解密备份索引 {:?}", self.dir.join(BACKUP_DB)))
+        })?;
+        info!("📦 解包微信备份 {:?}（{}）-> {:?}", self.dir, version.as_str(), output);
+
+        // 索引数据库只在解包期间使用，解密到临时目录
+        let temp = tempfile::tempdir()?;
+        let index_db = temp.path().join(BACKUP_DB);
+        create_decryptor(version)
+            .decrypt_database(&self.dir.join(BACKUP_DB), &index_db, &self.key)
+            .await?;
+        let index = SqliteDataSource::open(&index_db).await?;
+        let sessions = load_sessions(&index).await?;
+        let segments = load_segments(&index).await?;
+
+        let mut report = UnpackReport {
+            version: Some(version),
+            sessions: sessions.len(),
+            segments: segments.len(),
+            ..Default::default()
+        };
+        let mut writer = WorkDirWriter::create(output).await?;
+        for segment in &segments {
+            if self.cancel_token.is_cancelled() {
+                return Err(MwxDumpError::Cancelled.into());
+            }
+            let messages = match self.read_segment(segment) {
+                Ok(messages) => messages,
+                Err(e) => {
+                    warn!("读取消息分段失败 {}@{}: {}", segment.file, segment.offset, e);
+                    report.failed_segments += 1;
+                    continue;
+                }
+            };
+            for message in messages {
+                writer.insert_message(&segment.talker, &self.convert(&segment.talker, message)).await?;
+            }
+        }
+        report.messages = writer.finish(&sessions).await?;
+        report.elapsed_ms = started.elapsed().as_millis() as u64;
+        info!(
+            "✅ 备份解包完成: {} 个会话，{} 条消息，{} 个分段失败",
+            report.sessions, report.messages, report.failed_segments
+        );
+        Ok(report)
+    }
+
+    /// 读取并解密一段消息
+    fn read_segment(&self, segment: &Segment) -> Result<Vec<BackupMessage>> {
+        if Path::new(&segment.file).file_name().and_then(|name| name.to_str()) != Some(segment.file.as_str()) {
+            return Err(WeChatError::CorruptedFile { path: segment.file.clone() }.into());
+        }
+        if segment.length <= SEGMENT_IV_SIZE as u64 || segment.length > MAX_SEGMENT_SIZE {
+            return Err(WeChatError::CorruptedFile {
+                path: format!("{}@{}", segment.file, segment.offset),
+            }
+            .into());
+        }
+        let mut file = open_read_only(&self.dir.join(&segment.file))?;
+        file.seek(SeekFrom::Start(segment.offset))?;
+        let mut data = vec![0u8; segment.length as usize];
+        file.read_exact(&mut data)?;
+        let plain = decrypt_segment(&data, &self.key)?;
+        Ok(SegmentData::decode(plain.as_slice())
+            .map_err(|e| WeChatError::DecryptionFailed(format!("消息分段解码失败: {}", e)))?
+            .messages)
+    }
+
+    /// 转换为 4.x 的消息：自己发送的发送者取当前账号，单聊中对方发送的取会话本身，
+    /// 群聊中他人发送的内容加上 `发送者:\n` 前缀
+    fn convert(&self, talker: &str, message: BackupMessage) -> ImportedMessage {
+        let sender = match (&self.self_wxid, message.is_sender) {
+            (Some(wxid), true) => wxid.clone(),
+            _ if message.sender.is_empty() && !is_chatroom(talker) => talker.to_string(),
+            _ => message.sender,
+        };
+        let content = if is_chatroom(talker) && !message.is_sender && !sender.is_empty() {
+            format!("{}:\n{}", sender, message.content)
+        } else {
+            message.content
+        };
+        ImportedMessage {
+            seq: message.seq,
+            create_time: message.create_time,
+            msg_type: i64::from(message.msg_type),
+            sub_type: i64::from(message.sub_type),
+            sender,
+            is_sender: message.is_sender,
+            content,
+        }
+    }
+}
+
+/// 解密一段消息数据：前16字节为 IV，其余为 PKCS#7 填充的密文
+fn decrypt_segment(data: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+    let (iv, encrypted) = data.split_at(SEGMENT_IV_SIZE);
+    let mut buffer = encrypted.to_vec();
+    let plain = cbc::Decryptor::<aes::Aes256>::new_from_slices(key, iv)
+        .map_err(|e| WeChatError::DecryptionFailed(format!("密钥长度错误: {}", e)))?
+        .decrypt_padded_mut::<Pkcs7>(&mut buffer)
+        .map_err(|_| WeChatError::DecryptionFailed("消息分段填充错误，密钥可能不正确".to_string()))?;
+    Ok(plain.to_vec())
+}
+
+fn is_chatroom(talker: &str) -> bool {
+    talker.ends_with("@chatroom")
+}
+
+/// 读取会话对应的联系人
+async fn load_sessions(index: &SqliteDataSource) -> Result<Vec<ImportedContact>> {
+    let rows = index
+        .fetch(&Query::select("Session").columns(["UsrName", "NickName", "Remark"]))
+        .await?;
+    let text = |value: &serde_json::Value| value.as_str().filter(|s| !s.is_empty()).map(str::to_string);
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            Some(ImportedContact {
+                username: text(&row["UsrName"])?,
+                nickname: text(&row["NickName"]),
+                remark: text(&row["Remark"]),
+            })
+        })
+        .collect())
+}
+
+/// 读取消息分段索引，按开始时间排序
+async fn load_segments(index: &SqliteDataSource) -> Result<Vec<Segment>> {
+    let rows = index
+        .fetch(
+            &Query::select("MsgSegments")
+                .columns(["UsrName", "FileName", "OffSet", "Length"])
+                .order_by("StartTime", false),
+        )
+        .await?;
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            Some(Segment {
+                talker: row["UsrName"].as_str()?.to_string(),
+                file: row["FileName"].as_str()?.to_string(),
+                offset: row["OffSet"].as_u64()?,
+                length: row["Length"].as_u64()?,
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wechat::db::contacts::ContactRepository;
+    use crate::wechat::db::messages::MessageRepository;
+    use crate::wechat::db::sessions::SessionRepository;
+    use crate::wechat::decrypt::decrypt_algorithm_v3::tests::encrypt_v3;
+    use crate::wechat::decrypt::decrypt_common::SALT_SIZE;
+    use aes::cipher::BlockEncryptMut;
+    use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode};
+    use sqlx::{ConnectOptions, Connection};
+
+    /// 按 SQLCipher 3 的页面布局创建并加密索引数据库
+    async fn create_backup_db(path: &Path, key: &[u8], segments: &[(&str, &str, u64, u64, i64)]) {
+        let plain = path.with_extension("plain");
+        let options = SqliteConnectOptions::new()
+            .filename(&plain)
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Delete)
+            .page_size(1024);
+        let mut conn = options.connect().await.unwrap();
+        sqlx::query("PRAGMA user_version = 1").execute(&mut conn).await.unwrap();
+        conn.close().await.unwrap();
+        // 每页末尾为 IV 和 HMAC 保留 48 字节，写入数据前修改文件头和空表页的内容起点
+        let mut header = std::fs::read(&plain).unwrap();
+        header[20] = 48;
+        header[105..107].copy_from_slice(&(1024u16 - 48).to_be_bytes());
+        std::fs::write(&plain, header).unwrap();
+
+        let mut conn = options.connect().await.unwrap();
+        for sql in [
+            "CREATE TABLE Session (UsrName TEXT, NickName TEXT, Remark TEXT)",
+            "INSERT INTO Session VALUES ('wxid_friend', '老友', '大学同学'), ('123@chatroom', '周末爬山', NULL)",
+            "CREATE TABLE MsgSegments (UsrName TEXT, FileName TEXT, OffSet INTEGER, Length INTEGER, StartTime INTEGER)",
+        ] {
+            sqlx::query(sql).execute(&mut conn).await.unwrap();
+        }
+        for (talker, file, offset, length, start) in segments {
+            sqlx::query("INSERT INTO MsgSegments VALUES (?, ?, ?, ?, ?)")
+                .bind(talker)
+                .bind(file)
+                .bind(*offset as i64)
+                .bind(*length as i64)
+                .bind(start)
+                .execute(&mut conn)
+                .await
+                .unwrap();
+        }
+        conn.close().await.unwrap();
+        let plain = std::fs::read(&plain).unwrap();
+        std::fs::write(path, encrypt_v3(&plain, key, &[0x33; SALT_SIZE])).unwrap();
+    }
+
+    fn encrypt_segment(messages: Vec<BackupMessage>, key: &[u8]) -> Vec<u8> {
+        let iv = [0x42u8; SEGMENT_IV_SIZE];
+        let plain = SegmentData { messages }.encode_to_vec();
+        let mut buffer = plain.clone();
+        buffer.resize(plain.len() + 16 - plain.len() % 16, 0);
+        let encrypted = cbc::Encryptor::<aes::Aes256>::new_from_slices(key, &iv)
+            .unwrap()
+            .encrypt_padded_mut::<Pkcs7>(&mut buffer, plain.len())
+            .unwrap();
+        [iv.as_slice(), encrypted].concat()
+    }
+
+    fn message(create_time: i64, sender: &str, is_sender: bool, content: &str) -> BackupMessage {
+        BackupMessage {
+            seq: 0,
+            create_time,
+            msg_type: 1,
+            sub_type: 0,
+            sender: sender.to_string(),
+            is_sender,
+            content: content.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unpack_backup() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("Backup/wxid_self/20240501");
+        std::fs::create_dir_all(&dir).unwrap();
+        let key = [0x5au8; 32];
+
+        let friend = encrypt_segment(
+            vec![
+                message(1_700_000_000, "", false, "你好"),
+                message(1_700_000_060, "", true, "在吗"),
+            ],
+            &key,
+        );
+        let room = encrypt_segment(vec![message(1_700_000_100, "wxid_b", false, "hi all")], &key);
+        let text = [friend.as_slice(), &room, &[0u8; 64]].concat();
+        std::fs::write(dir.join("BAK_0_TEXT"), &text).unwrap();
+        let (friend_len, room_len) = (friend.len() as u64, room.len() as u64);
+        create_backup_db(
+            &dir.join(BACKUP_DB),
+            &key,
+            &[
+                ("wxid_friend", "BAK_0_TEXT", 0, friend_len, 1),
+                ("123@chatroom", "BAK_0_TEXT", friend_len, room_len, 2),
+                ("123@chatroom", "BAK_0_TEXT", friend_len + room_len, 64, 3),
+                ("wxid_friend", "../BAK_0_TEXT", 0, friend_len, 4),
+            ],
+        )
+        .await;
+        assert!(is_backup_dir(&dir));
+
+        let backup = PcBackup::new(&dir, key.to_vec());
+        assert_eq!(backup.self_wxid.as_deref(), Some("wxid_self"));
+        assert_eq!(backup.detect_version().await.unwrap(), Some(DecryptVersion::V3));
+        assert!(PcBackup::new(&dir, vec![0; 32]).unpack(&root.path().join("bad")).await.is_err());
+        assert!(backup.unpack(&dir.join("out")).await.is_err());
+
+        let output = root.path().join("out");
+        let report = backup.unpack(&output).await.unwrap();
+        assert_eq!((report.sessions, report.segments, report.failed_segments), (2, 4, 2));
+        assert_eq!(report.messages, 3);
+        assert_eq!(DecryptReport::from(report).succeeded, 2);
+
+        let repo = MessageRepository::open(&output).await.unwrap().with_self_wxid("wxid_self");
+        let messages = repo.page("wxid_friend", None, 10).await.unwrap();
+        assert_eq!(messages.iter().map(|m| m.content.as_str()).collect::<Vec<_>>(), ["你好", "在吗"]);
+        assert_eq!(messages[0].sender, "wxid_friend");
+        assert!(messages[1].is_self);
+        let room = repo.page("123@chatroom", None, 10).await.unwrap();
+        assert_eq!((room[0].sender.as_str(), room[0].content.as_str()), ("wxid_b", "hi all"));
+
+        let mut sessions = SessionRepository::open(&output).await.unwrap();
+        sessions.fill_names(&ContactRepository::open(&output).await.unwrap());
+        assert_eq!(sessions.all()[0].username, "123@chatroom");
+        assert_eq!(sessions.get("wxid_friend").unwrap().display_name.as_deref(), Some("大学同学"));
+    }
+}
@@ -0,0 +1,201 @@
+//! 本机微信账号枚举
+//!
+//! 一台机器上可能配置了多个存储根目录（不同盘符），每个根目录下又可能有多个账号目录。
+//! 这里把它们全部列出，供多账号配置使用。[`AccountProfile`] 是 CLI 配置文件和 UI 共用的账号配置。
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use ts_rs::TS;
+
+use super::data_dir_validation::inspect_data_dir;
+use crate::wechat::signatures::DataDirLayout;
+
+/// 本机发现的微信账号
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WeChatAccount {
+    /// 微信ID（不含目录后缀）
+    pub wxid: String,
+    /// 账号数据目录
+    pub data_dir: PathBuf,
+    /// 所在的存储根目录
+    pub storage_root: PathBuf,
+    /// 数据库文件的最近修改时间
+    pub last_modified: Option<SystemTime>,
+}
+
+/// 账号配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct AccountProfile {
+    /// 配置名称
+    pub name: String,
+
+    /// 微信ID
+    pub wxid: Option<String>,
+
+    /// 数据目录
+    pub data_dir: PathBuf,
+
+    /// 数据密钥
+    pub data_key: Option<String>,
+
+    /// 输出目录
+    pub output_dir: PathBuf,
+}
+
+impl AccountProfile {
+    /// 为发现的账号生成配置，输出目录为 `work_root/<wxid>`，密钥待提取
+    pub fn detected(account: &WeChatAccount, work_root: &Path) -> Self {
+        Self {
+            name: account.wxid.clone(),
+            wxid: Some(account.wxid.clone()),
+            data_dir: account.data_dir.clone(),
+            data_key: None,
+            output_dir: work_root.join(&account.wxid),
+        }
+    }
+
+    /// 名称或微信ID是否与 `id` 相同
+    pub fn matches(&self, id: &str) -> bool {
+        self.name == id || self.wxid.as_deref() == Some(id)
+    }
+}
+
+/// 合并已有配置和发现的账号
+///
+/// 已有配置在前；发现的账号中数据目录和微信ID都未被配置的，按 [`AccountProfile::detected`] 追加在后。
+pub fn merge_profiles(profiles: &[AccountProfile], detected: &[WeChatAccount], work_root: &Path) -> Vec<AccountProfile> {
+    let mut merged = profiles.to_vec();
+    for account in detected {
+        let configured = profiles
+            .iter()
+            .any(|p| p.data_dir == account.data_dir || p.wxid.as_deref() == Some(account.wxid.as_str()));
+        if !configured {
+            merged.push(AccountProfile::detected(account, work_root));
+        }
+    }
+    merged
+}
+
+/// 从账号目录名中提取 wxid
+///
+/// `wxid_acglnhh5lp3l21_36f6` 返回 `wxid_acglnhh5lp3l21`，没有后缀时返回整个目录名。
+pub fn wxid_from_dir_name(name: &str, account_prefix: &str) -> Option<String> {
+    let rest = name.strip_prefix(account_prefix)?;
+    match rest.find('_') {
+        Some(pos) => Some(name[..account_prefix.len() + pos].to_string()),
+        None => Some(name.to_string()),
+    }
+}
+
+/// 枚举存储根目录下所有账号目录
+///
+/// 按布局依次匹配，只保留包含数据库目录的账号；结果按最近修改时间倒序排列，同一目录只出现一次。
+pub fn enumerate_accounts_in_roots(roots: &[PathBuf], layouts: &[DataDirLayout]) -> Vec<WeChatAccount> {
+    let mut seen = HashSet::new();
+    let mut accounts = Vec::new();
+
+    for root in roots {
+        for layout in layouts {
+            for data_dir in account_dirs(&root.join(&layout.root), &layout.account_prefix) {
+                if !seen.insert(data_dir.clone()) {
+                    continue;
+                }
+                let check = inspect_data_dir(&data_dir, Some(layout));
+                if !check.has_db_dir {
+                    tracing::debug!("跳过没有数据库目录的账号目录: {:?}", data_dir);
+                    continue;
+                }
+                let Some(wxid) = data_dir
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .and_then(|name| wxid_from_dir_name(name, &layout.account_prefix))
+                else {
+                    continue;
+                };
+                accounts.push(WeChatAccount {
+                    wxid,
+                    data_dir,
+                    storage_root: root.clone(),
+                    last_modified: check.last_modified,
+                });
+            }
+        }
+    }
+
+    accounts.sort_by_key(|a| std::cmp::Reverse(a.last_modified));
+    accounts
+}
+
+/// 列出目录下以账号前缀开头的子目录
+fn account_dirs(parent: &Path, account_prefix: &str) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(parent) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(account_prefix))
+        })
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wxid_from_dir_name() {
+        assert_eq!(wxid_from_dir_name("wxid_acglnhh5lp3l21_36f6", "wxid_").as_deref(), Some("wxid_acglnhh5lp3l21"));
+        assert_eq!(wxid_from_dir_name("wxid_acglnhh5lp3l21", "wxid_").as_deref(), Some("wxid_acglnhh5lp3l21"));
+        assert_eq!(wxid_from_dir_name("all_users", "wxid_"), None);
+    }
+
+    #[test]
+    fn test_enumerate_accounts_across_roots() {
+        let drive_c = tempfile::tempdir().unwrap();
+        let drive_d = tempfile::tempdir().unwrap();
+        for (root, account) in [
+            (drive_c.path(), "wxid_alice_1a2b"),
+            (drive_d.path(), "wxid_bob_3c4d"),
+            (drive_d.path(), "wxid_carol"),
+        ] {
+            std::fs::create_dir_all(root.join("xwechat_files").join(account).join("db_storage")).unwrap();
+        }
+        std::fs::create_dir_all(drive_d.path().join("xwechat_files").join("wxid_empty")).unwrap();
+        std::fs::create_dir_all(drive_d.path().join("xwechat_files").join("all_users")).unwrap();
+
+        let roots = vec![drive_c.path().to_path_buf(), drive_d.path().to_path_buf(), drive_c.path().to_path_buf()];
+        let accounts = enumerate_accounts_in_roots(&roots, &[DataDirLayout::default()]);
+
+        let mut wxids: Vec<&str> = accounts.iter().map(|a| a.wxid.as_str()).collect();
+        wxids.sort();
+        assert_eq!(wxids, ["wxid_alice", "wxid_bob", "wxid_carol"]);
+        let bob = accounts.iter().find(|a| a.wxid == "wxid_bob").unwrap();
+        assert_eq!(bob.storage_root, drive_d.path());
+
+        let configured = AccountProfile {
+            name: "home".to_string(),
+            wxid: Some("wxid_bob".to_string()),
+            data_dir: PathBuf::from("elsewhere"),
+            data_key: Some("00".repeat(32)),
+            output_dir: PathBuf::from("backups/home"),
+        };
+        let work_root = Path::new("work");
+        let merged = merge_profiles(std::slice::from_ref(&configured), &accounts, work_root);
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[0], configured);
+        assert!(merged[0].matches("home") && merged[0].matches("wxid_bob"));
+        let carol = merged.iter().find(|p| p.matches("wxid_carol")).unwrap();
+        assert_eq!(carol.output_dir, work_root.join("wxid_carol"));
+        assert_eq!(carol.data_key, None);
+    }
+}
@@ -0,0 +1,206 @@
+//! 微信数据目录验证
+//!
+//! 默认通过文件系统特征（账号目录前缀、数据库目录、数据库文件）判断候选目录是否有效，
+//! 无需读取进程内存；内存验证作为严格模式保留，需要相应权限且耗时较长。
+//! 同一存储根目录下有多个有效账号目录时，按数据库最近修改时间选出当前使用的账号。
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::wechat::signatures::DataDirLayout;
+
+/// 数据目录验证方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataDirValidation {
+    /// 仅检查目录结构
+    #[default]
+    Filesystem,
+    /// 目录结构检查通过后，再在进程内存中搜索路径确认（严格模式）
+    Memory,
+}
+
+/// 数据目录结构检查结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DataDirCheck {
+    /// 目录名是否符合账号目录前缀
+    pub has_account_prefix: bool,
+    /// 是否存在数据库目录
+    pub has_db_dir: bool,
+    /// 数据库目录下的 `.db` 文件数量
+    pub db_file_count: usize,
+    /// 数据库文件的最近修改时间，用于在多个有效账号目录中排序，不影响 [`is_valid`](Self::is_valid)
+    pub last_modified: Option<SystemTime>,
+}
+
+impl DataDirCheck {
+    /// 目录结构是否像一个有效的账号数据目录
+    pub fn is_valid(&self) -> bool {
+        self.has_account_prefix && self.has_db_dir && self.db_file_count > 0
+    }
+}
+
+/// 检查候选目录的文件系统特征
+pub fn inspect_data_dir(dir: &Path, layout: Option<&DataDirLayout>) -> DataDirCheck {
+    let default_layout = DataDirLayout::default();
+    let layout = layout.unwrap_or(&default_layout);
+    let account_prefix = layout.account_prefix.as_str();
+    let db_dir_name = layout.db_dir.as_str();
+
+    let has_account_prefix = dir
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with(account_prefix));
+
+    let db_dir = dir.join(db_dir_name);
+    let mut check = DataDirCheck {
+        has_account_prefix,
+        has_db_dir: db_dir.is_dir(),
+        ..Default::default()
+    };
+    if check.has_db_dir {
+        collect_db_files(&db_dir, &mut check);
+    }
+    check
+}
+
+/// 从存储根目录中找出当前使用的账号目录
+///
+/// `base` 可以是账号目录本身、数据根目录（如 `xwechat_files`），或注册表和 xwechat 配置中记录的
+/// 上一级存储目录。返回结构有效且数据库最近修改的账号目录，没有有效目录时返回 `None`。
+pub fn find_account_dir(base: &Path, layout: Option<&DataDirLayout>) -> Option<PathBuf> {
+    let default_layout = DataDirLayout::default();
+    let layout = layout.unwrap_or(&default_layout);
+
+    let check = inspect_data_dir(base, Some(layout));
+    if check.has_account_prefix {
+        return check.is_valid().then(|| base.to_path_buf());
+    }
+
+    [base.join(&layout.root), base.to_path_buf()]
+        .iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten().map(|entry| entry.path()))
+        .filter(|dir| dir.is_dir())
+        .map(|dir| {
+            let check = inspect_data_dir(&dir, Some(layout));
+            (dir, check)
+        })
+        .filter(|(_, check)| check.is_valid())
+        .max_by_key(|(_, check)| check.last_modified)
+        .map(|(dir, _)| dir)
+}
+
+/// 从进程打开的文件路径推断候选账号目录
+///
+/// 取每个路径中数据库目录（如 `db_storage`）的上一级目录，按出现次数从多到少排列，
+/// 次数相同时保持首次出现的顺序。
+pub fn account_dirs_from_open_files(paths: &[PathBuf], layout: Option<&DataDirLayout>) -> Vec<PathBuf> {
+    let default_layout = DataDirLayout::default();
+    let db_dir_name = layout.unwrap_or(&default_layout).db_dir.as_str();
+
+    let mut candidates: Vec<(PathBuf, usize)> = Vec::new();
+    for path in paths {
+        let Some(db_dir) = path
+            .ancestors()
+            .find(|ancestor| ancestor.file_name().is_some_and(|name| name.eq_ignore_ascii_case(db_dir_name)))
+        else {
+            continue;
+        };
+        let Some(account_dir) = db_dir.parent() else {
+            continue;
+        };
+        match candidates.iter_mut().find(|(dir, _)| dir == account_dir) {
+            Some((_, count)) => *count += 1,
+            None => candidates.push((account_dir.to_path_buf(), 1)),
+        }
+    }
+    // 稳定排序，次数相同时保持首次出现的顺序
+    candidates.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    candidates.into_iter().map(|(dir, _)| dir).collect()
+}
+
+/// 递归统计数据库文件及其最近修改时间
+fn collect_db_files(dir: &Path, check: &mut DataDirCheck) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_db_files(&path, check);
+        } else if path.extension().is_some_and(|ext| ext == "db") {
+            check.db_file_count += 1;
+            if let Ok(modified) = entry.metadata().and_then(|m| m.modified()) {
+                check.last_modified = check.last_modified.max(Some(modified));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inspect_data_dir() {
+        let root = tempfile::tempdir().unwrap();
+        let account = root.path().join("wxid_abc123_1a2b");
+        std::fs::create_dir_all(account.join("db_storage").join("message")).unwrap();
+
+        let check = inspect_data_dir(&account, None);
+        assert!(check.has_account_prefix && check.has_db_dir);
+        assert!(!check.is_valid());
+
+        std::fs::write(account.join("db_storage").join("message").join("message_0.db"), b"").unwrap();
+        let check = inspect_data_dir(&account, None);
+        assert_eq!(check.db_file_count, 1);
+        assert!(check.last_modified.is_some());
+        assert!(check.is_valid());
+
+        let other = root.path().join("other");
+        std::fs::create_dir_all(other.join("db_storage")).unwrap();
+        assert!(!inspect_data_dir(&other, None).is_valid());
+    }
+
+    #[test]
+    fn test_find_account_dir_from_registry_root() {
+        // 注册表 FileSavePath 记录的是 xwechat_files 的上一级目录，本身不带账号前缀
+        let base = tempfile::tempdir().unwrap();
+        let root = base.path().join("xwechat_files");
+        assert!(!inspect_data_dir(base.path(), None).is_valid());
+        assert_eq!(find_account_dir(base.path(), None), None);
+
+        let old = root.join("wxid_old_9f9f");
+        let current = root.join("wxid_abc123_1a2b");
+        for account in [&old, &current] {
+            std::fs::create_dir_all(account.join("db_storage").join("message")).unwrap();
+        }
+        std::fs::create_dir_all(root.join("all_users")).unwrap();
+        std::fs::write(old.join("db_storage").join("message").join("message_0.db"), b"").unwrap();
+        let file = std::fs::File::create(current.join("db_storage").join("message").join("message_0.db")).unwrap();
+        file.set_modified(SystemTime::now() + std::time::Duration::from_secs(60)).unwrap();
+
+        // 最近修改的有效账号目录胜出，根目录和账号目录本身也可直接传入
+        assert_eq!(find_account_dir(base.path(), None), Some(current.clone()));
+        assert_eq!(find_account_dir(&root, None), Some(current.clone()));
+        assert_eq!(find_account_dir(&old, None), Some(old.clone()));
+        assert_eq!(find_account_dir(&root.join("wxid_missing"), None), None);
+    }
+
+    #[test]
+    fn test_account_dirs_from_open_files() {
+        let moved = PathBuf::from("D:/relocated/xwechat_files/wxid_abc123_1a2b");
+        let other = PathBuf::from("C:/Users/me/xwechat_files/wxid_old_9f9f");
+        let paths = vec![
+            other.join("db_storage").join("contact").join("contact.db"),
+            moved.join("db_storage").join("message").join("message_0.db"),
+            PathBuf::from("C:/Windows/System32/kernel32.dll"),
+            moved.join("DB_STORAGE").join("session").join("session.db"),
+            moved.join("db_storage").join("message").join("message_0.db-wal"),
+        ];
+        assert_eq!(account_dirs_from_open_files(&paths, None), vec![moved, other]);
+        assert!(account_dirs_from_open_files(&[], None).is_empty());
+    }
+}
@@ -0,0 +1,35 @@
+//! 不受支持平台的进程检测实现
+//!
+//! 在 Linux/容器等没有微信客户端的环境中，检测器不会报错，而是返回空列表，
+//! 使得基于已解密数据的服务/导出流程仍然可以运行。
+
+use super::{DataDirValidation, ProcessDetector, WechatProcessInfo};
+use crate::errors::Result;
+use async_trait::async_trait;
+
+pub fn is_wxwork(_process: &WechatProcessInfo) -> bool {
+    false
+}
+
+/// 空实现的进程检测器
+#[derive(Clone)]
+pub struct FallbackProcessDetector {}
+
+impl FallbackProcessDetector {
+    pub fn create_wechat_detector() -> Result<Self> {
+        Ok(Self {})
+    }
+
+    /// 当前平台不检测数据目录，验证方式不生效
+    pub fn with_data_dir_validation(self, _validation: DataDirValidation) -> Self {
+        self
+    }
+}
+
+#[async_trait]
+impl ProcessDetector for FallbackProcessDetector {
+    async fn detect_processes(&self) -> Result<Vec<WechatProcessInfo>> {
+        tracing::warn!("当前平台不支持微信进程检测，跳过");
+        Ok(Vec::new())
+    }
+}
@@ -76,7 +76,7 @@ impl MacOSProcessDetector {
                                     debug!("检测到版本信息: {}", version);
                                     
                                     if version.starts_with("4.") {
-                                        return Ok(WeChatVersion::V40 { exact: version });
+                                        return Ok(WeChatVersion::V4x { exact: version });
                                     } else if version.starts_with("3.") {
                                         return Ok(WeChatVersion::V3x { exact: version });
                                     }
@@ -91,7 +91,7 @@ impl MacOSProcessDetector {
         // 如果无法从Info.plist获取版本，尝试从路径判断
         let path_str = app_path.to_string_lossy().to_lowercase();
         if path_str.contains("4.0") {
-            Ok(WeChatVersion::V40 { exact: "4.0.x".to_string() })
+            Ok(WeChatVersion::V4x { exact: "4.0.x".to_string() })
         } else {
             Ok(WeChatVersion::V3x { exact: "3.x.x".to_string() })
         }
@@ -1,10 +1,18 @@
+pub mod accounts;
+pub mod data_dir_validation;
 pub mod process_detector;
 pub mod wechat_process_info;
 #[cfg(target_os = "windows")]
 mod windows;
 #[cfg(target_os = "macos")]
 mod macos;
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod fallback;
 
 pub use process_detector::ProcessDetector;
 pub use wechat_process_info::WechatProcessInfo;
-pub use process_detector::create_process_detector;
\ No newline at end of file
+pub use process_detector::{
+    create_process_detector, create_process_detector_with_validation, detect_primary, select_primary,
+};
+pub use accounts::{merge_profiles, AccountProfile, WeChatAccount};
+pub use data_dir_validation::DataDirValidation;
\ No newline at end of file
@@ -1,7 +1,11 @@
 
 use async_trait::async_trait;
+use super::accounts::WeChatAccount;
+use super::data_dir_validation::DataDirValidation;
 use super::wechat_process_info::WechatProcessInfo;
-use crate::errors::Result;
+use crate::errors::{Result, WeChatError};
+use crate::wechat::datadir::{self, DataDirInfo};
+use crate::wechat::WeChatVersion;
 
 #[cfg(target_os = "windows")]
 use super::windows::WindowsProcessDetector as Detector;
@@ -9,6 +13,9 @@ use super::windows::WindowsProcessDetector as Detector;
 #[cfg(target_os = "macos")]
 use super::macos::MacOSProcessDetector as Detector;
 
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+use super::fallback::FallbackProcessDetector as Detector;
+
 
 /// 进程检测器接口
 #[async_trait]
@@ -16,6 +23,16 @@ pub trait ProcessDetector: Send + Sync {
     /// 检测所有微信进程
     async fn detect_processes(&self) -> Result<Vec<WechatProcessInfo>>;
 
+    /// 枚举本机所有存储根目录下的微信账号，不要求微信正在运行
+    async fn enumerate_accounts(&self) -> Result<Vec<WeChatAccount>> {
+        Ok(Vec::new())
+    }
+
+    /// 检测主账号：选出版本有效、数据目录可识别的微信主进程
+    async fn detect_primary(&self) -> Result<(WechatProcessInfo, DataDirInfo)> {
+        select_primary(self.detect_processes().await?)
+    }
+
     // /// 获取指定PID的进程信息
     // async fn get_process_info(&self, pid: u32) -> Result<Option<WechatProcessInfo>>;
 
@@ -31,3 +48,111 @@ pub trait ProcessDetector: Send + Sync {
 pub fn create_process_detector() -> Result<Detector> {
     Detector::create_wechat_detector()
 }
+
+/// 创建平台特定的进程检测器，并指定数据目录验证方式
+pub fn create_process_detector_with_validation(validation: DataDirValidation) -> Result<Detector> {
+    Ok(Detector::create_wechat_detector()?.with_data_dir_validation(validation))
+}
+
+/// 使用平台检测器检测主账号，见 [`ProcessDetector::detect_primary`]
+///
+/// 选中进程的版本不在 `supported_versions`（版本模式见 [`WeChatVersion::matches_pattern`]）中时
+/// 返回 `WeChatError::UnsupportedVersion`，列表为空时不限制。
+pub async fn detect_primary(
+    validation: DataDirValidation,
+    supported_versions: &[String],
+) -> Result<(WechatProcessInfo, DataDirInfo)> {
+    let (process, info) = create_process_detector_with_validation(validation)?.detect_primary().await?;
+    process.version.check_supported(supported_versions)?;
+    Ok((process, info))
+}
+
+/// 从检测到的进程中选出主账号
+///
+/// 只考虑主进程；有数据库目录的数据目录优先于只识别出账号的，
+/// 同等条件下版本已知的进程优先，再按检测顺序。
+pub fn select_primary(processes: Vec<WechatProcessInfo>) -> Result<(WechatProcessInfo, DataDirInfo)> {
+    let mains: Vec<WechatProcessInfo> = processes.into_iter().filter(|p| p.is_main_process).collect();
+    if mains.is_empty() {
+        return Err(WeChatError::ProcessNotFound.into());
+    }
+
+    let mut candidates: Vec<(WechatProcessInfo, DataDirInfo)> = Vec::new();
+    for process in &mains {
+        let Some(dir) = &process.data_dir else {
+            tracing::debug!("进程 {} 未检测到数据目录", process.pid);
+            continue;
+        };
+        match datadir::scan(dir) {
+            Ok(info) if info.db_storage_path.is_some() || info.wxid.is_some() => {
+                candidates.push((process.clone(), info))
+            }
+            Ok(_) => tracing::debug!("进程 {} 的数据目录 {:?} 不是微信账号目录", process.pid, dir),
+            Err(e) => tracing::debug!("识别进程 {} 的数据目录失败: {}", process.pid, e),
+        }
+    }
+    // 稳定排序，保持检测顺序
+    candidates.sort_by_key(|(process, info)| {
+        (info.db_storage_path.is_none(), process.version == WeChatVersion::Unknown)
+    });
+
+    match candidates.into_iter().next() {
+        Some((process, info)) => {
+            tracing::info!("选定微信主进程 {} (PID: {})，数据目录: {:?}", process.name, process.pid, info.root);
+            Ok((process, info))
+        }
+        None => Err(WeChatError::DataDirNotFound {
+            path: mains
+                .iter()
+                .filter_map(|p| p.data_dir.as_ref().map(|d| d.display().to_string()))
+                .next()
+                .unwrap_or_else(|| format!("PID {}", mains[0].pid)),
+        }
+        .into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::ProcessInfo;
+
+    fn process(pid: u32, is_main: bool, data_dir: Option<std::path::PathBuf>) -> WechatProcessInfo {
+        let mut process = WechatProcessInfo::new(ProcessInfo::new(
+            0,
+            pid,
+            "Weixin.exe".to_string(),
+            Some("Weixin.exe".to_string()),
+            None,
+            true,
+            is_main,
+        ))
+        .unwrap();
+        process.data_dir = data_dir;
+        process
+    }
+
+    #[test]
+    fn test_select_primary() {
+        let root = tempfile::tempdir().unwrap();
+        let without_db = root.path().join("wxid_empty_1a2b");
+        let account = root.path().join("wxid_abc123_3c4d");
+        std::fs::create_dir_all(&without_db).unwrap();
+        std::fs::create_dir_all(account.join("db_storage")).unwrap();
+
+        assert!(select_primary(Vec::new()).is_err());
+        assert!(select_primary(vec![process(1, false, Some(account.clone()))]).is_err());
+        let err = select_primary(vec![process(2, true, None)]).unwrap_err();
+        assert!(matches!(err.downcast_ref::<WeChatError>(), Some(WeChatError::DataDirNotFound { .. })));
+
+        let (process, info) = select_primary(vec![
+            process(3, false, Some(account.clone())),
+            process(4, true, Some(without_db)),
+            process(5, true, Some(account.clone())),
+        ])
+        .unwrap();
+        assert_eq!(process.pid, 5);
+        assert_eq!(info.wxid.as_deref(), Some("wxid_abc123"));
+        assert_eq!(info.db_storage_path, Some(account.join("db_storage")));
+    }
+}
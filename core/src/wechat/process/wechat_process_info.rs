@@ -3,7 +3,9 @@ use crate::errors::WeChatError;
 use crate::errors::SystemError;
 use crate::errors::Result;
 use crate::utils::ProcessInfo;
+use crate::wechat::wechat_version::MIN_SUPPORTED_VERSION;
 use crate::wechat::WeChatVersion;
+use super::accounts::wxid_from_dir_name;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
@@ -15,6 +17,9 @@ use super::windows as platform_impl;
 #[cfg(target_os = "macos")]
 use self::macos as platform_impl;
 
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+use super::fallback as platform_impl;
+
 /// 进程信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WechatProcessInfo {
@@ -78,17 +83,15 @@ impl WechatProcessInfo {
                 // 首先，尝试解析版本字符串
                 let parsed_version = v_str.parse::<WeChatVersion>()?;
 
-                // 接着，检查解析后的版本是否是 V3x
-                match parsed_version {
-                    // 如果是 V3x，则返回不支持的版本错误
-                    WeChatVersion::V3x { exact } => {
-                        return Err(WeChatError::UnsupportedVersion { version: exact }.into());
+                // 接着，检查解析后的版本是否达到最低支持版本，未知版本放行
+                if parsed_version != WeChatVersion::Unknown && !parsed_version.is_supported() {
+                    return Err(WeChatError::UnsupportedVersion {
+                        version: parsed_version.to_string(),
+                        supported: format!("{}+", MIN_SUPPORTED_VERSION),
                     }
-                    // 如果是 V4x 或其他可接受的版本，则继续
-                    v @ WeChatVersion::V4x { .. } => v,
-                    // Unknown 理论上不会从 parse 产生，但为了代码健壮性，我们处理它
-                    WeChatVersion::Unknown => WeChatVersion::Unknown,
+                    .into());
                 }
+                parsed_version
             }
             // 如果版本字符串不存在，则默认为 Unknown
             None => WeChatVersion::Unknown,
@@ -118,28 +121,10 @@ impl WechatProcessInfo {
     pub fn get_current_wxid(&self) -> Option<String> {
         self.data_dir.as_ref().and_then(|data_dir| {
             // 获取路径的最后一个组件（目录名）
-            data_dir.file_name()
+            data_dir
+                .file_name()
                 .and_then(|name| name.to_str())
-                .and_then(|name_str| {
-                    // 检查是否以 wxid_ 开头
-                    if name_str.starts_with("wxid_") {
-                        // 查找第一个下划线后的第二个下划线位置
-                        // wxid_acglnhh5lp3l21_36f6
-                        //     ^              ^
-                        //     5              20
-                        let first_underscore = 4; // "wxid" 的长度
-                        if let Some(second_underscore_pos) = name_str[first_underscore + 1..].find('_') {
-                            // 提取 wxid 部分（不包含后缀）
-                            let wxid_end = first_underscore + 1 + second_underscore_pos;
-                            Some(name_str[..wxid_end].to_string())
-                        } else {
-                            // 如果没有第二个下划线，返回整个目录名
-                            Some(name_str.to_string())
-                        }
-                    } else {
-                        None
-                    }
-                })
+                .and_then(|name_str| wxid_from_dir_name(name_str, "wxid_"))
         })
     }
 }
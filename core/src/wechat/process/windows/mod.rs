@@ -1,5 +1,5 @@
 use super::super::WeChatVersion;
-use super::{ProcessDetector, WechatProcessInfo};
+use super::{DataDirValidation, ProcessDetector, WechatProcessInfo};
 
 use once_cell::sync::Lazy;
 use windows::Win32::System::Registry::HKEY_CURRENT_USER;
@@ -30,6 +30,8 @@ pub fn is_wxwork(process: &WechatProcessInfo) -> bool {
 pub struct WindowsProcessDetector {
     /// 微信进程名称列表
     wechat_process_names: Vec<&'static str>,
+    /// 数据目录验证方式
+    data_dir_validation: DataDirValidation,
 }
 
 pub mod win_process_detector;
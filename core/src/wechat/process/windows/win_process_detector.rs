@@ -1,9 +1,15 @@
 //! Windows平台的微信进程检测实现
 
-use super::{ProcessDetector, WeChatVersion, WechatProcessInfo};
+use super::{DataDirValidation, ProcessDetector, WechatProcessInfo};
+use crate::wechat::process::accounts::{enumerate_accounts_in_roots, WeChatAccount};
+use crate::wechat::process::data_dir_validation::{
+    account_dirs_from_open_files, find_account_dir, inspect_data_dir,
+};
 // use crate::errors::{Result, WeChatError};
 use crate::errors::Result;
 use crate::utils::windows as utils_windows;
+use crate::utils::windows::mem_search::{max_user_address, MatchOnly, MemorySearchEngine, SearchConfig};
+use crate::wechat::signatures::{DataDirLayout, SignatureDb};
 use async_trait::async_trait;
 use chrono::Utc;
 use core::time;
@@ -13,14 +19,15 @@ use tracing::{debug, info, warn};
 use windows::Win32::System::Registry::HKEY_CURRENT_USER;
 
 impl super::WindowsProcessDetector {
-    const MIN_ADDRESS: usize = 0x10000; // Process space usually starts from 0x10000
-    const MAX_ADDRESS_32: usize = 0x7FFFFFFF; // 32-bit process space limit
-    const MAX_ADDRESS_64: usize = 0x00007FFFFFFFFFFF;
+    /// 特征库中没有匹配版本时使用的数据目录布局
+    const DEFAULT_DATA_ROOT: &'static str = "xwechat_files";
+    const DEFAULT_ACCOUNT_PREFIX: &'static str = "wxid_";
 
     pub fn create_wxwork_detector() -> Result<Self> {
         Ok(Self {
             // 直接克隆 Lazy<Vec> 里的 Vec。这非常高效。
             wechat_process_names: super::WXWORK_PROCESS_NAMES.clone(),
+            data_dir_validation: DataDirValidation::default(),
         })
     }
 
@@ -28,44 +35,52 @@ impl super::WindowsProcessDetector {
         Ok(Self {
             // .clone() 会隐式地解引用 Lazy，然后调用 Vec::clone()
             wechat_process_names: super::WECHAT_PROCESS_NAMES.clone(),
+            data_dir_validation: DataDirValidation::default(),
         })
     }
 
-    /// 验证进程版本是否有效（包含数字和点号，非Unknown）
+    /// 设置数据目录验证方式
+    pub fn with_data_dir_validation(mut self, validation: DataDirValidation) -> Self {
+        self.data_dir_validation = validation;
+        self
+    }
+
+    /// 验证进程版本是否有效（可解析为数字版本号，非Unknown）
     fn validate_process_version(&self, process: &WechatProcessInfo) -> bool {
-        match &process.version {
-            WeChatVersion::V3x { exact } | WeChatVersion::V4x { exact } => {
-                exact.chars().any(|c| c.is_ascii_digit()) && exact.contains('.')
-            }
-            WeChatVersion::Unknown => false,
-        }
+        process.version.components().is_some()
     }
 
     // 这是一个私有的、同步的、阻塞的辅助方法。
     // 必须保证只在 spawn_blocking 中调用它。
     fn find_wechat_data_directory(&self, process: &WechatProcessInfo) -> Result<Option<PathBuf>> {
+        // 目录布局来自版本特征库
+        let layout = SignatureDb::global()
+            .lookup_version(&process.version)
+            .map(|s| s.data_dir.clone());
+
         // 策略1: 尝试从注册表获取并验证
         if let Ok(reg_path_str) = utils_windows::registry::get_string_from_registry(
             HKEY_CURRENT_USER,
             super::WECHAT_REG_KEY_PATH,
             super::WECHAT_FILES_VALUE_NAME,
         ) {
-            let candidate_dir = PathBuf::from(reg_path_str);
-            // 检查目录是否存在，并且在内存中验证通过
-            if candidate_dir.is_dir() && self.is_datadir_valid_in_memory(process, &candidate_dir)? {
-                tracing::info!(
-                    "PID {}: 通过注册表找到并验证了数据目录: {:?}",
-                    process.pid,
-                    candidate_dir
-                );
-                return Ok(Some(candidate_dir)); // 验证成功，立即返回
+            // 注册表记录的是存储根目录，需要先从中找出账号目录再验证
+            if let Some(candidate_dir) = find_account_dir(Path::new(&reg_path_str), layout.as_ref()) {
+                if self.is_datadir_valid(process, &candidate_dir, layout.as_ref())? {
+                    tracing::info!(
+                        "PID {}: 通过注册表找到并验证了数据目录: {:?}",
+                        process.pid,
+                        candidate_dir
+                    );
+                    return Ok(Some(candidate_dir)); // 验证成功，立即返回
+                }
             }
         }
 
         // 策略2: 尝试从 xwechat 配置文件获取并验证
-        if let Ok(Some(candidate_dir)) = self.find_from_xwechat_config() {
-            // 同样，检查目录是否存在并进行内存验证
-            if candidate_dir.is_dir() && self.is_datadir_valid_in_memory(process, &candidate_dir)? {
+        if let Ok(Some(candidate_dir)) = self.find_from_xwechat_config(layout.as_ref()) {
+            // 同样，检查目录是否存在并进行验证
+            if candidate_dir.is_dir() && self.is_datadir_valid(process, &candidate_dir, layout.as_ref())? {
                 tracing::info!(
                     "通过PID {}: 验证了数据目录: {:?} 有效",
                     process.pid,
@@ -75,14 +90,49 @@ impl super::WindowsProcessDetector {
             }
         }
 
-        // 策略3: (TBD) 最后尝试内存路径搜索方法
-        // ...
+        // 策略3: 从进程打开的文件句柄中寻找数据库目录，用户迁移过数据目录时更可靠
+        match utils_windows::handles::list_open_file_paths(process.pid) {
+            Ok(paths) => {
+                for candidate_dir in account_dirs_from_open_files(&paths, layout.as_ref()) {
+                    if candidate_dir.is_dir() && self.is_datadir_valid(process, &candidate_dir, layout.as_ref())? {
+                        tracing::info!(
+                            "PID {}: 通过打开的文件句柄找到并验证了数据目录: {:?}",
+                            process.pid,
+                            candidate_dir
+                        );
+                        return Ok(Some(candidate_dir));
+                    }
+                }
+            }
+            Err(e) => debug!("PID {}: 无法列举打开的文件句柄: {}", process.pid, e),
+        }
 
         // 所有策略都失败后
         tracing::warn!("PID {}: 未能找到微信数据目录", process.pid);
         Ok(None)
     }
 
+    /// 按配置的验证方式检查候选数据目录
+    ///
+    /// 始终先检查目录结构；严格模式下再到进程内存中确认路径。
+    fn is_datadir_valid(
+        &self,
+        process: &WechatProcessInfo,
+        data_dir: &PathBuf,
+        layout: Option<&DataDirLayout>,
+    ) -> Result<bool> {
+        let check = inspect_data_dir(data_dir, layout);
+        if !check.is_valid() {
+            tracing::debug!("PID {}: 数据目录 {:?} 结构检查未通过: {:?}", process.pid, data_dir, check);
+            return Ok(false);
+        }
+
+        match self.data_dir_validation {
+            DataDirValidation::Filesystem => Ok(true),
+            DataDirValidation::Memory => self.is_datadir_valid_in_memory(process, data_dir),
+        }
+    }
+
     /// 辅助函数：检查候选的数据目录是否真实有效（通过在进程内存中搜索路径字符串）。
     /// 这个函数封装了所有验证逻辑。
     fn is_datadir_valid_in_memory(
@@ -103,51 +153,53 @@ impl super::WindowsProcessDetector {
             }
         };
 
-        let end_address = if process.is_64_bit{
-            Self::MAX_ADDRESS_64
-        } else {
-            Self::MAX_ADDRESS_32
-        };
-
-        match utils_windows::memory::search_memory_for_pattern(
-            process.pid,
-            dir_str.as_bytes(),
-            Self::MIN_ADDRESS,
-            end_address,
-            1,
-        ) {
-            Ok(location) => {
-                if !location.is_empty() {
-                    // 修复日志信息，并正确处理找到的情况
-                    tracing::debug!(
-                        "PID {}: 在内存中成功验证数据目录 '{}'，所在位置为: {:?}",
-                        process.pid,
-                        dir_str,
-                        location
-                    );
-                    Ok(true) // 找到了，验证成功
-                } else {
-                    // 修复未找到的逻辑
-                    tracing::debug!(
-                        "PID {}: 数据目录 '{}' 未在进程内存中找到，判定为无效",
-                        process.pid,
-                        dir_str
-                    );
-                    Ok(false) // 没找到，验证失败
-                }
+        let config = SearchConfig::readable().with_max_address(max_user_address(process.is_64_bit));
+        // 改进错误处理：将搜索本身的错误传递出去，调用者可以决定如何处理
+        let report = MemorySearchEngine::new(dir_str.as_bytes().to_vec(), MatchOnly)
+            .with_config(config)
+            .search(process.pid)
+            .inspect_err(|e| {
+                tracing::error!("PID {}: 在内存中验证数据目录时发生错误: {}", process.pid, e)
+            })?;
+
+        match report.hits.first() {
+            Some(hit) => {
+                tracing::debug!(
+                    "PID {}: 在内存中成功验证数据目录 '{}'，所在位置为: {:#X}",
+                    process.pid,
+                    dir_str,
+                    hit.address
+                );
+                Ok(true) // 找到了，验证成功
             }
-            Err(e) => {
-                // 改进错误处理：将搜索本身的错误传递出去
-                tracing::error!("PID {}: 在内存中验证数据目录时发生错误: {}", process.pid, e);
-                // 这里返回 Err，而不是 Ok(false)，因为这意味着验证操作本身失败了，
-                // 而不是“验证了但结果是假的”。调用者可以决定如何处理这个错误。
-                Err(e.into())
+            None => {
+                tracing::debug!(
+                    "PID {}: 数据目录 '{}' 未在进程内存中找到，判定为无效",
+                    process.pid,
+                    dir_str
+                );
+                Ok(false) // 没找到，验证失败
             }
         }
     }
 
     /// 从 xwechat 配置文件中查找数据目录
-    fn find_from_xwechat_config(&self) -> Result<Option<PathBuf>> {
+    fn find_from_xwechat_config(&self, layout: Option<&DataDirLayout>) -> Result<Option<PathBuf>> {
+        // 依次验证每个存储根目录（最近使用的在前）
+        for base_dir in self.xwechat_storage_roots()? {
+            if let Some(wxid_dir) = self.validate_wechat_data_directory(&base_dir, layout)? {
+                return Ok(Some(wxid_dir));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// 读取 xwechat 配置目录中的所有 ini 文件，返回去重后的存储根目录
+    ///
+    /// 每个 ini 文件记录一个存储根目录，用户可能在不同盘符上配置过多个。
+    /// 结果按 ini 文件修改时间排序，最新的在前。
+    fn xwechat_storage_roots(&self) -> Result<Vec<PathBuf>> {
         // 1. 获取用户主目录
         let user_dir = utils_windows::file::get_user_profile_dir()?;
 
@@ -156,7 +208,7 @@ impl super::WindowsProcessDetector {
 
         if !utils_windows::file::check_directory_exists(&config_dir) {
             tracing::debug!("xwechat 配置目录不存在: {:?}", config_dir);
-            return Ok(None);
+            return Ok(Vec::new());
         }
 
         // 3. 获取所有 ini 文件
@@ -164,7 +216,7 @@ impl super::WindowsProcessDetector {
 
         if ini_files.is_empty() {
             tracing::debug!("xwechat 配置目录中没有找到 ini 文件");
-            return Ok(None);
+            return Ok(Vec::new());
         }
 
         // 4. 读取并解析 ini 文件，收集有效的数据目录
@@ -199,26 +251,30 @@ impl super::WindowsProcessDetector {
 
         if potential_dirs.is_empty() {
             tracing::debug!("没有找到有效的数据目录配置");
-            return Ok(None);
         }
 
-        // 5. 按修改时间排序（最新的在前）
+        // 5. 按修改时间排序（最新的在前），并去掉重复的根目录
         potential_dirs.sort_by(|a, b| b.1.cmp(&a.1));
-
-        // 6. 验证每个潜在目录
-        for (base_dir, _) in potential_dirs {
-            if let Some(wxid_dir) = self.validate_wechat_data_directory(&base_dir)? {
-                return Ok(Some(wxid_dir));
+        let mut roots: Vec<PathBuf> = Vec::new();
+        for (dir, _) in potential_dirs {
+            if !roots.contains(&dir) {
+                roots.push(dir);
             }
         }
 
-        Ok(None)
+        Ok(roots)
     }
 
     /// 验证微信数据目录
     /// 检查 base_dir\xwechat_files\wxid_* 格式的目录是否存在
-    fn validate_wechat_data_directory(&self, base_dir: &Path) -> Result<Option<PathBuf>> {
-        let xwechat_files_dir = base_dir.join("xwechat_files");
+    fn validate_wechat_data_directory(
+        &self,
+        base_dir: &Path,
+        layout: Option<&DataDirLayout>,
+    ) -> Result<Option<PathBuf>> {
+        let root = layout.map_or(Self::DEFAULT_DATA_ROOT, |l| l.root.as_str());
+        let account_prefix = layout.map_or(Self::DEFAULT_ACCOUNT_PREFIX, |l| l.account_prefix.as_str());
+        let xwechat_files_dir = base_dir.join(root);
 
         if !utils_windows::file::check_directory_exists(&xwechat_files_dir) {
             tracing::debug!("xwechat_files 目录不存在: {:?}", xwechat_files_dir);
@@ -227,12 +283,12 @@ impl super::WindowsProcessDetector {
 
         // 查找以 wxid_ 开头的目录
         let wxid_dirs =
-            utils_windows::file::find_directories_with_prefix(&xwechat_files_dir, "wxid_")?;
+            utils_windows::file::find_directories_with_prefix(&xwechat_files_dir, account_prefix)?;
 
-        // 如果找到了 wxid_ 目录，返回第一个
-        if let Some(wxid_dir) = wxid_dirs.first() {
+        // 优先返回结构有效且数据库最近修改的账号目录，否则返回第一个
+        if let Some(wxid_dir) = find_account_dir(&xwechat_files_dir, layout).or_else(|| wxid_dirs.first().cloned()) {
             tracing::info!("通过xwechat_files，找到有效的微信数据目录: {:?}", wxid_dir);
-            return Ok(Some(wxid_dir.clone()));
+            return Ok(Some(wxid_dir));
         }
 
         tracing::debug!("在 {:?} 中未找到 wxid_ 开头的目录", xwechat_files_dir);
@@ -291,6 +347,18 @@ impl ProcessDetector for super::WindowsProcessDetector {
         Ok(detected_processes)
     }
 
+    async fn enumerate_accounts(&self) -> Result<Vec<WeChatAccount>> {
+        let detector = self.clone();
+        tokio::task::spawn_blocking(move || -> Result<Vec<WeChatAccount>> {
+            let roots = detector.xwechat_storage_roots()?;
+            let layouts = SignatureDb::global().data_dir_layouts();
+            let accounts = enumerate_accounts_in_roots(&roots, &layouts);
+            tracing::info!("在 {} 个存储根目录中找到 {} 个微信账号", roots.len(), accounts.len());
+            Ok(accounts)
+        })
+        .await?
+    }
+
     // async fn get_process_info(&self, pid: u32) -> Result<Option<WechatProcessInfo>> {
     //     let processes = self.detect_processes().await?;
     //     Ok(processes.into_iter().find(|p| p.pid == pid))
@@ -0,0 +1,226 @@
+//! 综合搜索
+//!
+//! 一个搜索框同时查找联系人、群聊、文本消息和媒体识别文本，结果按类别分组返回；
+//! 消息结果附带截取的片段和命中位置，供界面高亮。
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use ts_rs::TS;
+
+use super::{IndexedText, SearchIndex, SEARCH_INDEX_FILE};
+use crate::errors::Result;
+use crate::models::{Contact, Message};
+use crate::wechat::db::contacts::{self, CONTACT_DB_PATH};
+use crate::wechat::db::messages::{MessageRepository, MESSAGE_DB_DIR};
+use crate::wechat::db::SqliteDataSource;
+
+/// 消息片段的最大字符数
+const SNIPPET_CHARS: usize = 80;
+
+/// 片段中命中位置之前保留的字符数
+const SNIPPET_CONTEXT: usize = 20;
+
+/// 命中的消息
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct MessageHit {
+    /// 消息
+    pub message: Message,
+    /// 命中位置附近的内容片段，截断处以 `…` 表示
+    pub snippet: String,
+    /// 片段中命中的字符区间 `[start, end)`，按 Unicode 字符计
+    pub highlights: Vec<(usize, usize)>,
+}
+
+/// 分组的搜索结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SearchResults {
+    /// 用户名、昵称或备注匹配的联系人
+    pub contacts: Vec<Contact>,
+    /// 群名或群 ID 匹配的群聊
+    pub chatrooms: Vec<Contact>,
+    /// 内容匹配的文本消息，按时间倒序
+    pub messages: Vec<MessageHit>,
+    /// 媒体识别文本（OCR、语音转写）
+    pub media: Vec<IndexedText>,
+}
+
+/// 综合搜索，缺少的数据源对应的结果为空
+#[derive(Default)]
+pub struct CombinedSearch {
+    contacts: Vec<Contact>,
+    messages: Option<MessageRepository>,
+    index: Option<SearchIndex>,
+}
+
+impl CombinedSearch {
+    /// 打开工作目录下已解密的联系人、消息数据库和已存在的全文索引
+    pub async fn open(work_dir: &Path) -> Result<Self> {
+        let mut search = Self::default();
+        let contact_db = work_dir.join(CONTACT_DB_PATH);
+        if contact_db.is_file() {
+            search.contacts = contacts::load_contacts(&SqliteDataSource::open(&contact_db).await?).await?;
+        }
+        if work_dir.join(MESSAGE_DB_DIR).is_dir() {
+            search.messages = Some(MessageRepository::open(work_dir).await?);
+        }
+        // 只读取已有的索引，不在搜索时创建
+        let index = work_dir.join(SEARCH_INDEX_FILE);
+        if index.is_file() {
+            search.index = Some(SearchIndex::open(&index).await?);
+        }
+        Ok(search)
+    }
+
+    /// 使用已加载的联系人
+    pub fn with_contacts(mut self, contacts: Vec<Contact>) -> Self {
+        self.contacts = contacts;
+        self
+    }
+
+    /// 使用已打开的消息仓库
+    pub fn with_messages(mut self, messages: MessageRepository) -> Self {
+        self.messages = Some(messages);
+        self
+    }
+
+    /// 使用已打开的全文索引
+    pub fn with_index(mut self, index: SearchIndex) -> Self {
+        self.index = Some(index);
+        self
+    }
+
+    /// 搜索，每个类别最多返回 `limit` 条
+    pub async fn search(&self, text: &str, limit: usize) -> Result<SearchResults> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Ok(SearchResults::default());
+        }
+        let needle = text.to_lowercase();
+        let matches = |value: &Option<String>| value.as_deref().is_some_and(|v| v.to_lowercase().contains(&needle));
+
+        let mut results = SearchResults::default();
+        for contact in &self.contacts {
+            if !(contact.username.to_lowercase().contains(&needle)
+                || matches(&contact.nickname)
+                || matches(&contact.remark))
+            {
+                continue;
+            }
+            let group = match contact.username.ends_with("@chatroom") {
+                true => &mut results.chatrooms,
+                false => &mut results.contacts,
+            };
+            if group.len() < limit {
+                group.push(contact.clone());
+            }
+        }
+
+        if let Some(repo) = &self.messages {
+            let talkers: Vec<String> = self.contacts.iter().map(|c| c.username.clone()).collect();
+            results.messages = repo
+                .search(text, &talkers, limit)
+                .await?
+                .into_iter()
+                .map(|message| {
+                    let (snippet, highlights) = highlight(&message.content, text);
+                    MessageHit {
+                        message,
+                        snippet,
+                        highlights,
+                    }
+                })
+                .collect();
+        }
+
+        if let Some(index) = &self.index {
+            results.media = index.search(text, limit).await?;
+        }
+        Ok(results)
+    }
+}
+
+/// 截取首个命中位置附近的片段，并返回片段中所有命中的字符区间（不区分大小写）
+pub fn highlight(content: &str, query: &str) -> (String, Vec<(usize, usize)>) {
+    let chars: Vec<char> = content.chars().collect();
+    let lower: Vec<char> = chars.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect();
+    let needle: Vec<char> = query.chars().map(|c| c.to_lowercase().next().unwrap_or(c)).collect();
+
+    let mut hits = Vec::new();
+    if !needle.is_empty() {
+        let mut start = 0;
+        while start + needle.len() <= lower.len() {
+            if lower[start..start + needle.len()] == needle[..] {
+                hits.push((start, start + needle.len()));
+                start += needle.len();
+            } else {
+                start += 1;
+            }
+        }
+    }
+
+    let from = match hits.first() {
+        Some(&(first, _)) if chars.len() > SNIPPET_CHARS => {
+            first.saturating_sub(SNIPPET_CONTEXT).min(chars.len() - SNIPPET_CHARS)
+        }
+        _ => 0,
+    };
+    let to = (from + SNIPPET_CHARS).min(chars.len());
+    let prefix = usize::from(from > 0);
+    let mut snippet: String = chars[from..to].iter().collect();
+    if from > 0 {
+        snippet.insert(0, '…');
+    }
+    if to < chars.len() {
+        snippet.push('…');
+    }
+    let highlights = hits
+        .into_iter()
+        .filter(|&(start, end)| start >= from && end <= to)
+        .map(|(start, end)| (start - from + prefix, end - from + prefix))
+        .collect();
+    (snippet, highlights)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wechat::db::messages::tests::create_message_dbs;
+
+    #[test]
+    fn test_highlight() {
+        assert_eq!(highlight("Hello hello", "HELLO"), ("Hello hello".to_string(), vec![(0, 5), (6, 11)]));
+
+        let long = format!("{}周五开会{}", "甲".repeat(50), "乙".repeat(100));
+        let (snippet, highlights) = highlight(&long, "周五");
+        assert!(snippet.starts_with('…') && snippet.ends_with('…'));
+        assert_eq!(snippet.chars().count(), SNIPPET_CHARS + 2);
+        let (start, end) = highlights[0];
+        assert_eq!(snippet.chars().skip(start).take(end - start).collect::<String>(), "周五");
+    }
+
+    #[tokio::test]
+    async fn test_grouped_results() {
+        let dir = tempfile::tempdir().unwrap();
+        create_message_dbs(dir.path()).await;
+        let mut friend = Contact::new("wxid_friend".to_string());
+        friend.remark = Some("Hi 老友".to_string());
+        let mut room = Contact::new("123@chatroom".to_string());
+        room.nickname = Some("hi 群".to_string());
+
+        let search = CombinedSearch::open(dir.path())
+            .await
+            .unwrap()
+            .with_contacts(vec![friend, room, Contact::new("wxid_other".to_string())]);
+        let results = search.search("hi", 10).await.unwrap();
+        assert_eq!(results.contacts.len(), 1);
+        assert_eq!(results.chatrooms[0].username, "123@chatroom");
+        assert_eq!(results.messages.len(), 1);
+        assert_eq!(results.messages[0].message.talker, "123@chatroom");
+        assert_eq!(results.messages[0].highlights, [(0, 2)]);
+        assert!(results.media.is_empty());
+
+        assert!(search.search("  ", 10).await.unwrap().contacts.is_empty());
+    }
+}
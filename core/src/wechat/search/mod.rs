@@ -0,0 +1,202 @@
+//! 媒体文本全文索引
+//!
+//! 图片识别（OCR）、语音转写等从媒体中提取的文本保存在工作目录下单独的 SQLite 数据库中，
+//! 使用 FTS5 `trigram` 分词以支持中文子串搜索；该库由本程序维护，与只读的微信数据库分开。
+//! [`combined`] 在此基础上同时搜索联系人、群聊和消息。
+
+pub mod combined;
+
+pub use combined::{CombinedSearch, MessageHit, SearchResults};
+
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use sqlx::Row;
+use std::path::Path;
+use ts_rs::TS;
+
+use crate::errors::{DatabaseError, Result};
+
+/// 工作目录中全文索引数据库的文件名
+pub const SEARCH_INDEX_FILE: &str = "mwxdump_search.db";
+
+/// `trigram` 分词要求查询至少包含的字符数，更短的查询退化为 `LIKE`
+const TRIGRAM_MIN_CHARS: usize = 3;
+
+/// 文本来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum TextKind {
+    /// 图片识别文本
+    Image,
+    /// 语音转写文本
+    Voice,
+}
+
+impl TextKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TextKind::Image => "image",
+            TextKind::Voice => "voice",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "image" => Some(TextKind::Image),
+            "voice" => Some(TextKind::Voice),
+            _ => None,
+        }
+    }
+}
+
+/// 已索引的文本
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct IndexedText {
+    /// 文本来源
+    pub kind: TextKind,
+    /// 来源标识，图片为文件路径，语音为 `会话:消息序号`
+    pub source: String,
+    /// 提取的文本
+    pub content: String,
+}
+
+/// 全文索引
+#[derive(Clone)]
+pub struct SearchIndex {
+    pool: SqlitePool,
+}
+
+impl SearchIndex {
+    /// 打开索引数据库，不存在时创建
+    pub async fn open(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let options = SqliteConnectOptions::new().filename(path).create_if_missing(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await
+            .map_err(|e| DatabaseError::ConnectionFailed(e.to_string()))?;
+        sqlx::query(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS media_text \
+             USING fts5(kind UNINDEXED, source UNINDEXED, content, tokenize = 'trigram')",
+        )
+        .execute(&pool)
+        .await
+        .map_err(DatabaseError::SqlError)?;
+        Ok(Self { pool })
+    }
+
+    /// 写入文本，覆盖同一来源已有的记录
+    pub async fn upsert(&self, text: &IndexedText) -> Result<()> {
+        let mut tx = self.pool.begin().await.map_err(DatabaseError::SqlError)?;
+        sqlx::query("DELETE FROM media_text WHERE kind = ? AND source = ?")
+            .bind(text.kind.as_str())
+            .bind(&text.source)
+            .execute(&mut *tx)
+            .await
+            .map_err(DatabaseError::SqlError)?;
+        sqlx::query("INSERT INTO media_text (kind, source, content) VALUES (?, ?, ?)")
+            .bind(text.kind.as_str())
+            .bind(&text.source)
+            .bind(&text.content)
+            .execute(&mut *tx)
+            .await
+            .map_err(DatabaseError::SqlError)?;
+        tx.commit().await.map_err(DatabaseError::SqlError)?;
+        Ok(())
+    }
+
+    /// 来源是否已经索引过
+    pub async fn contains(&self, kind: TextKind, source: &str) -> Result<bool> {
+        let row = sqlx::query("SELECT 1 FROM media_text WHERE kind = ? AND source = ? LIMIT 1")
+            .bind(kind.as_str())
+            .bind(source)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(DatabaseError::SqlError)?;
+        Ok(row.is_some())
+    }
+
+    /// 读取来源已索引的文本
+    pub async fn get(&self, kind: TextKind, source: &str) -> Result<Option<String>> {
+        let row = sqlx::query("SELECT content FROM media_text WHERE kind = ? AND source = ? LIMIT 1")
+            .bind(kind.as_str())
+            .bind(source)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(DatabaseError::SqlError)?;
+        Ok(row.map(|row| row.get("content")))
+    }
+
+    /// 按子串搜索，结果按相关度排序
+    pub async fn search(&self, text: &str, limit: usize) -> Result<Vec<IndexedText>> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Ok(Vec::new());
+        }
+        let query = if text.chars().count() >= TRIGRAM_MIN_CHARS {
+            sqlx::query("SELECT kind, source, content FROM media_text WHERE media_text MATCH ? ORDER BY rank LIMIT ?")
+                .bind(format!("\"{}\"", text.replace('"', "\"\"")))
+        } else {
+            sqlx::query("SELECT kind, source, content FROM media_text WHERE content LIKE ? ESCAPE '\\' LIMIT ?")
+                .bind(format!("%{}%", escape_like(text)))
+        };
+        let rows = query
+            .bind(limit as i64)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(DatabaseError::SqlError)?;
+
+        Ok(rows
+            .iter()
+            .filter_map(|row| {
+                Some(IndexedText {
+                    kind: TextKind::parse(row.get::<&str, _>("kind"))?,
+                    source: row.get("source"),
+                    content: row.get("content"),
+                })
+            })
+            .collect())
+    }
+}
+
+/// 转义 `LIKE` 通配符，配合 `ESCAPE '\'` 使用
+pub(crate) fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_index_and_search() {
+        let dir = tempfile::tempdir().unwrap();
+        let index = SearchIndex::open(&dir.path().join(SEARCH_INDEX_FILE)).await.unwrap();
+        let image = |source: &str, content: &str| IndexedText {
+            kind: TextKind::Image,
+            source: source.to_string(),
+            content: content.to_string(),
+        };
+
+        index.upsert(&image("a.jpg", "会议纪要：周五下午三点")).await.unwrap();
+        index.upsert(&image("b.jpg", "Invoice 100% paid")).await.unwrap();
+        assert!(index.contains(TextKind::Image, "a.jpg").await.unwrap());
+        assert!(!index.contains(TextKind::Image, "c.jpg").await.unwrap());
+
+        let hits = index.search("周五下午", 10).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].source, "a.jpg");
+        assert_eq!(index.search("会议", 10).await.unwrap().len(), 1);
+        assert_eq!(index.search("0%", 10).await.unwrap()[0].source, "b.jpg");
+        assert!(index.search("\"paid", 10).await.unwrap().is_empty());
+
+        index.upsert(&image("a.jpg", "已更新")).await.unwrap();
+        assert!(index.search("会议", 10).await.unwrap().is_empty());
+        assert!(index.search("  ", 10).await.unwrap().is_empty());
+    }
+}
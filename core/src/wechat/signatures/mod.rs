@@ -0,0 +1,253 @@
+//! 微信版本特征库
+//!
+//! 将微信各版本的密钥特征和数据目录布局以数据的形式维护，由进程检测器和密钥提取器查询。
+//! 内置特征随程序发布，用户可以通过 TOML 文件追加或覆盖。
+//!
+//! 数据库的页面大小、KDF 迭代次数等参数由解密器按数据库格式（V3/V4）确定，不随客户端版本配置；
+//! 旧特征文件中的 `[signatures.db]` 段会被忽略。
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::path::Path;
+
+use crate::errors::{ConfigError, Result};
+use crate::wechat::wechat_version::{compare_build, parse_build};
+use crate::wechat::WeChatVersion;
+
+/// 内置特征文件
+const EMBEDDED_SIGNATURES: &str = include_str!("signatures.toml");
+
+static GLOBAL: OnceCell<SignatureDb> = OnceCell::new();
+
+/// 密钥特征
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeySignature {
+    /// 密钥结构所在模块
+    pub module: String,
+    /// 密钥指针之后的结构特征（十六进制）
+    pub pattern: String,
+    /// 密钥指针相对特征起始位置的偏移
+    pub pointer_offset: i64,
+}
+
+impl KeySignature {
+    /// 解码后的特征字节
+    pub fn pattern_bytes(&self) -> Result<Vec<u8>> {
+        hex::decode(&self.pattern).map_err(|e| {
+            ConfigError::InvalidValue {
+                key: "key.pattern".to_string(),
+                value: format!("{} ({})", self.pattern, e),
+            }
+            .into()
+        })
+    }
+}
+
+/// 数据目录布局
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataDirLayout {
+    /// 数据根目录名
+    pub root: String,
+    /// 账号目录前缀
+    pub account_prefix: String,
+    /// 数据库所在子目录
+    pub db_dir: String,
+}
+
+impl Default for DataDirLayout {
+    /// 特征库中没有匹配版本时使用的 4.x 布局
+    fn default() -> Self {
+        Self {
+            root: "xwechat_files".to_string(),
+            account_prefix: "wxid_".to_string(),
+            db_dir: "db_storage".to_string(),
+        }
+    }
+}
+
+/// 单个版本区间的特征
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VersionSignature {
+    /// 特征名称
+    pub name: String,
+    /// 最低版本（含）
+    pub min_version: String,
+    /// 最高版本（含），省略表示不设上限
+    #[serde(default)]
+    pub max_version: Option<String>,
+    /// 密钥特征
+    pub key: KeySignature,
+    /// 数据目录布局
+    pub data_dir: DataDirLayout,
+}
+
+impl VersionSignature {
+    /// 检查版本号是否落在本条目的区间内
+    pub fn matches(&self, version: &str) -> bool {
+        let Some(version) = parse_build(version) else {
+            return false;
+        };
+        let above_min = parse_build(&self.min_version)
+            .map(|min| compare_build(&version, &min) != Ordering::Less)
+            .unwrap_or(false);
+        let below_max = match &self.max_version {
+            Some(max) => parse_build(max)
+                .map(|max| compare_build(&version, &max) != Ordering::Greater)
+                .unwrap_or(false),
+            None => true,
+        };
+        above_min && below_max
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct SignatureFile {
+    #[serde(default)]
+    signatures: Vec<VersionSignature>,
+}
+
+/// 版本特征库
+#[derive(Debug, Clone, Default)]
+pub struct SignatureDb {
+    entries: Vec<VersionSignature>,
+}
+
+impl SignatureDb {
+    /// 加载内置特征
+    pub fn embedded() -> Result<Self> {
+        Self::from_toml(EMBEDDED_SIGNATURES)
+    }
+
+    /// 从 TOML 文本解析特征
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let file: SignatureFile =
+            toml::from_str(content).map_err(|e| ConfigError::ParseError(e.to_string()))?;
+        for entry in &file.signatures {
+            entry.key.pattern_bytes()?;
+        }
+        Ok(Self { entries: file.signatures })
+    }
+
+    /// 加载内置特征，并叠加用户特征文件（用户条目优先）
+    pub fn load_with_overrides(user_file: Option<&Path>) -> Result<Self> {
+        let mut db = Self::embedded()?;
+        if let Some(path) = user_file {
+            let content = std::fs::read_to_string(path).map_err(|_| ConfigError::FileNotFound {
+                path: path.display().to_string(),
+            })?;
+            let user = Self::from_toml(&content)?;
+            tracing::info!("已加载 {} 条用户版本特征: {:?}", user.entries.len(), path);
+            db.entries.splice(0..0, user.entries);
+        }
+        Ok(db)
+    }
+
+    /// 所有条目（按匹配优先级排序）
+    pub fn entries(&self) -> &[VersionSignature] {
+        &self.entries
+    }
+
+    /// 所有条目中不同的数据目录布局，没有条目时返回默认布局
+    pub fn data_dir_layouts(&self) -> Vec<DataDirLayout> {
+        let mut layouts: Vec<DataDirLayout> = Vec::new();
+        for entry in &self.entries {
+            if !layouts.contains(&entry.data_dir) {
+                layouts.push(entry.data_dir.clone());
+            }
+        }
+        if layouts.is_empty() {
+            layouts.push(DataDirLayout::default());
+        }
+        layouts
+    }
+
+    /// 查找版本号对应的特征
+    pub fn lookup(&self, version: &str) -> Option<&VersionSignature> {
+        self.entries.iter().find(|entry| entry.matches(version))
+    }
+
+    /// 查找微信版本对应的特征，版本未知时返回 `None`
+    pub fn lookup_version(&self, version: &WeChatVersion) -> Option<&VersionSignature> {
+        match version {
+            WeChatVersion::Unknown => None,
+            v => self.lookup(v.version_string()),
+        }
+    }
+
+    /// 设置全局特征库，只能在首次使用前设置一次
+    pub fn install_global(db: SignatureDb) -> Result<()> {
+        GLOBAL
+            .set(db)
+            .map_err(|_| ConfigError::ParseError("版本特征库已初始化".to_string()).into())
+    }
+
+    /// 获取全局特征库，未设置时使用内置特征
+    pub fn global() -> &'static SignatureDb {
+        GLOBAL.get_or_init(|| {
+            Self::embedded().unwrap_or_else(|e| {
+                tracing::error!("内置版本特征解析失败: {}", e);
+                Self::default()
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedded_signatures_parse() {
+        let db = SignatureDb::embedded().unwrap();
+        let sig = db.lookup("4.0.3.22").unwrap();
+        assert_eq!(sig.key.module, "Weixin.dll");
+        assert_eq!(sig.key.pattern_bytes().unwrap().len(), 24);
+        assert_eq!(sig.data_dir.root, "xwechat_files");
+        assert!(db.lookup("3.9.12.51").is_none());
+    }
+
+    #[test]
+    fn test_user_signatures_take_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("signatures.toml");
+        std::fs::write(
+            &path,
+            r#"
+[[signatures]]
+name = "weixin-4.1-test"
+min_version = "4.1"
+max_version = "4.1.99"
+
+[signatures.key]
+module = "Weixin.dll"
+pattern = "2f00"
+pointer_offset = -8
+
+[signatures.data_dir]
+root = "xwechat_files"
+account_prefix = "wxid_"
+db_dir = "db_storage"
+
+[signatures.db]
+page_size = 4096
+kdf_iterations = 256000
+hmac_size = 64
+reserve_size = 80
+"#,
+        )
+        .unwrap();
+
+        // 旧特征文件中的 [signatures.db] 段被忽略
+        let db = SignatureDb::load_with_overrides(Some(&path)).unwrap();
+        assert_eq!(db.lookup("4.1.0.10").unwrap().name, "weixin-4.1-test");
+        assert_eq!(db.lookup("4.0.5").unwrap().name, "weixin-4.0");
+    }
+
+    #[test]
+    fn test_compare_build() {
+        assert_eq!(compare_build(&[4, 0], &[4, 0, 0, 0]), Ordering::Equal);
+        assert_eq!(compare_build(&[4, 0, 10], &[4, 0, 9, 99]), Ordering::Greater);
+        assert!(parse_build("4.0.beta").is_none());
+    }
+}
@@ -1,14 +1,23 @@
-use crate::errors::{Result, MwxDumpError};
+use crate::errors::{MwxDumpError, Result, WeChatError};
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::fmt;
 use std::str::FromStr;
 
+/// 支持解密的最低微信版本
+pub const MIN_SUPPORTED_VERSION: &str = "4.0";
+
 /// 微信版本信息
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+///
+/// 序列化为版本号字符串（如 `"4.0.3.22"`），未知版本为 `"unknown"`，
+/// 与 `Display`/`FromStr` 保持一致。相等和大小比较都按数字版本号进行，缺失的段视为0，
+/// 因此 `"4.0"` 与 `"4.0.0"` 相等。
+#[derive(Debug, Clone)]
 pub enum WeChatVersion {
     /// 3.x版本
     V3x { exact: String },
-    /// 4.0版本
+    /// 4.x版本（Windows 与 macOS 共用）
     V4x { exact: String },
     /// 未知版本
     Unknown,
@@ -24,33 +33,273 @@ impl WeChatVersion {
             WeChatVersion::Unknown => "unknown",
         }
     }
-   
+
     /// 是否为3.x版本
     pub fn is_v3x(&self) -> bool {
         matches!(self, WeChatVersion::V3x { .. })
     }
-    
+
     /// 是否为4.x版本
     pub fn is_v4x(&self) -> bool {
         matches!(self, WeChatVersion::V4x { .. })
     }
+
+    /// 数字形式的版本号，未知或包含非数字段时返回 `None`
+    pub fn components(&self) -> Option<Vec<u32>> {
+        match self {
+            WeChatVersion::Unknown => None,
+            v => parse_build(v.version_string()),
+        }
+    }
+
+    /// 与版本号字符串比较，任一方无法解析时返回 `None`
+    pub fn compare_to(&self, other: &str) -> Option<Ordering> {
+        let own = self.components()?;
+        let other = parse_build(other)?;
+        Some(compare_build(&own, &other))
+    }
+
+    /// 是否不低于指定版本，用于按版本开启功能，如 `at_least("4.0.3")`
+    ///
+    /// 版本未知或无法解析时返回 `false`。
+    pub fn at_least(&self, min: &str) -> bool {
+        matches!(self.compare_to(min), Some(Ordering::Greater | Ordering::Equal))
+    }
+
+    /// 是否在支持的版本范围内
+    pub fn is_supported(&self) -> bool {
+        self.at_least(MIN_SUPPORTED_VERSION)
+    }
+
+    /// 是否匹配版本模式：`4.0` 匹配 `4.0.*`，`3.x` 或 `3.*` 匹配所有 3.x 版本
+    ///
+    /// `4.0.x` 这样的版本系列按 `x` 之前的数字部分比较，只有该部分已确定模式前缀时才算匹配，
+    /// 如 `4.0.x` 匹配 `4.x` 和 `4.0`，但不匹配 `4.0.3`。
+    pub fn matches_pattern(&self, pattern: &str) -> bool {
+        let Some(own) = self.components().or_else(|| self.family_prefix()) else {
+            return false;
+        };
+        let pattern = pattern.trim();
+        let prefix = pattern
+            .strip_suffix(".x")
+            .or_else(|| pattern.strip_suffix(".*"))
+            .unwrap_or(pattern);
+        match parse_build(prefix) {
+            Some(prefix) => own.len() >= prefix.len() && own[..prefix.len()] == prefix[..],
+            None => false,
+        }
+    }
+
+    /// 版本系列（如 `4.0.x`）中 `x` 之前的数字部分，非版本系列返回 `None`
+    fn family_prefix(&self) -> Option<Vec<u32>> {
+        let (prefix, rest) = self.version_string().split_once(".x")?;
+        if !rest.split('.').all(|part| part.is_empty() || part == "x") {
+            return None;
+        }
+        parse_build(prefix)
+    }
+
+    /// 检查是否在配置的支持版本列表（`wechat.supported_versions`）中
+    ///
+    /// 列表为空时不限制；版本未知时无法判断，只记录警告。
+    pub fn check_supported(&self, supported: &[String]) -> Result<()> {
+        if supported.is_empty() || supported.iter().any(|pattern| self.matches_pattern(pattern)) {
+            return Ok(());
+        }
+        if *self == WeChatVersion::Unknown {
+            tracing::warn!("无法识别微信版本，跳过支持版本检查");
+            return Ok(());
+        }
+        Err(WeChatError::UnsupportedVersion {
+            version: self.to_string(),
+            supported: supported.join(", "),
+        }
+        .into())
+    }
+}
+
+impl fmt::Display for WeChatVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.version_string())
+    }
 }
 
-// 现在，我们来实现解析逻辑
 impl FromStr for WeChatVersion {
-    // 这里的 Err 类型可以使用我们自定义的错误类型
     type Err = MwxDumpError;
 
+    /// 按主版本号区分 3.x / 4.x，`"unknown"` 解析为 `Unknown`
+    ///
+    /// 检测不到具体版本时进程信息使用 `4.0.x` 这样末尾为 `x` 的版本系列，同样可以解析，
+    /// 但没有数字版本号，只与原文相同的版本相等；其他非数字段解析失败。
     fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
-        // 在这里你应该放入你真实的解析逻辑
-        // 下面是一个简化的例子
-        if s.starts_with("3.") {
-            Ok(WeChatVersion::V3x { exact: s.to_string() })
-        } else if s.starts_with("4.") {
-            Ok(WeChatVersion::V4x { exact: s.to_string() })
-        } else {
-            // 如果解析失败，返回我们的自定义错误
-            Err(MwxDumpError::InvalidVersion(s.to_string()))
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("unknown") {
+            return Ok(WeChatVersion::Unknown);
+        }
+
+        let invalid = || MwxDumpError::InvalidVersion(s.to_string());
+        let parts: Vec<&str> = s.split('.').collect();
+        let exact = parts.iter().take_while(|part| *part != &"x").count();
+        if parts[exact..].iter().any(|part| *part != "x") {
+            return Err(invalid());
+        }
+        let components = parse_build(&parts[..exact].join(".")).ok_or_else(invalid)?;
+        // 至少要有两段，如 `4.0` 或 `4.x`
+        if parts.len() < 2 {
+            return Err(invalid());
+        }
+        match components[0] {
+            3 => Ok(WeChatVersion::V3x { exact: s.to_string() }),
+            4 => Ok(WeChatVersion::V4x { exact: s.to_string() }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl PartialEq for WeChatVersion {
+    /// 按数字版本号比较；`4.0.x` 这样的版本系列只与原文相同的版本相等
+    fn eq(&self, other: &Self) -> bool {
+        match (self.components(), other.components()) {
+            (Some(own), Some(other)) => compare_build(&own, &other) == Ordering::Equal,
+            (None, None) => self.version_string() == other.version_string(),
+            _ => false,
         }
     }
 }
+
+impl Eq for WeChatVersion {}
+
+impl PartialOrd for WeChatVersion {
+    /// 按数字版本号比较，与 [`PartialEq`] 一致；未知或无法解析的版本只与自身可比较
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self.components(), other.components()) {
+            (Some(own), Some(other)) => Some(compare_build(&own, &other)),
+            _ => (self == other).then_some(Ordering::Equal),
+        }
+    }
+}
+
+impl Serialize for WeChatVersion {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.version_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for WeChatVersion {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// 解析形如 `4.0.3.22` 的版本号
+pub(crate) fn parse_build(version: &str) -> Option<Vec<u32>> {
+    version
+        .trim()
+        .split('.')
+        .map(|part| part.parse::<u32>().ok())
+        .collect()
+}
+
+/// 比较版本号，缺失的段视为0
+pub(crate) fn compare_build(a: &[u32], b: &[u32]) -> Ordering {
+    let len = a.len().max(b.len());
+    (0..len)
+        .map(|i| a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0)))
+        .find(|o| *o != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_display_roundtrip() {
+        let v: WeChatVersion = "4.0.3.22".parse().unwrap();
+        assert_eq!(v, WeChatVersion::V4x { exact: "4.0.3.22".to_string() });
+        assert_eq!(v.to_string(), "4.0.3.22");
+        assert!("3.9.12.51".parse::<WeChatVersion>().unwrap().is_v3x());
+        assert_eq!("unknown".parse::<WeChatVersion>().unwrap(), WeChatVersion::Unknown);
+        assert!("5".parse::<WeChatVersion>().is_err());
+        assert!("40.1".parse::<WeChatVersion>().is_err());
+
+        let json = serde_json::to_string(&v).unwrap();
+        assert_eq!(json, "\"4.0.3.22\"");
+        assert_eq!(serde_json::from_str::<WeChatVersion>(&json).unwrap(), v);
+    }
+
+    #[test]
+    fn test_version_ordering() {
+        let v: WeChatVersion = "4.0.3.22".parse().unwrap();
+        assert!(v.at_least("4.0.3"));
+        assert!(v.at_least("4.0.3.22"));
+        assert!(!v.at_least("4.0.10"));
+        assert!(v.is_supported());
+        assert!(!"3.9.12.51".parse::<WeChatVersion>().unwrap().is_supported());
+        assert!(!WeChatVersion::Unknown.at_least("3.0"));
+        assert!(v > "3.9.12.51".parse().unwrap());
+        assert_eq!(WeChatVersion::Unknown.partial_cmp(&v), None);
+        assert!(parse_build("4.0.beta").is_none());
+    }
+
+    #[test]
+    fn test_equality_matches_ordering() {
+        let short: WeChatVersion = "4.0".parse().unwrap();
+        let long: WeChatVersion = "4.0.0".parse().unwrap();
+        assert_eq!(short, long);
+        assert_eq!(short.partial_cmp(&long), Some(Ordering::Equal));
+        assert_ne!(short, "4.0.1".parse().unwrap());
+        assert_eq!(WeChatVersion::Unknown, WeChatVersion::Unknown);
+        assert_eq!(WeChatVersion::Unknown.partial_cmp(&WeChatVersion::Unknown), Some(Ordering::Equal));
+        assert_ne!(WeChatVersion::Unknown, short);
+
+        // 检测不到具体版本时的版本系列可以解析和往返，但不与具体版本比较
+        let family: WeChatVersion = "4.0.x".parse().unwrap();
+        assert!(family.is_v4x() && family.components().is_none());
+        assert!("3.x".parse::<WeChatVersion>().unwrap().is_v3x());
+        assert_eq!(family, "4.0.x".parse().unwrap());
+        assert_ne!(family, long);
+        assert_eq!(family.partial_cmp(&long), None);
+        let json = serde_json::to_string(&family).unwrap();
+        assert_eq!(serde_json::from_str::<WeChatVersion>(&json).unwrap(), family);
+
+        // 其他非数字段明确拒绝
+        for invalid in ["4.x.0", "3.*", "4.0.beta", "4.", "x", "5.x"] {
+            assert!(matches!(
+                invalid.parse::<WeChatVersion>(),
+                Err(MwxDumpError::InvalidVersion(v)) if v == invalid
+            ));
+        }
+    }
+
+    #[test]
+    fn test_supported_version_patterns() {
+        let v: WeChatVersion = "4.0.3.22".parse().unwrap();
+        assert!(v.matches_pattern("4.0"));
+        assert!(v.matches_pattern("4.x"));
+        assert!(v.matches_pattern("4.0.3.22"));
+        assert!(!v.matches_pattern("4.1"));
+        assert!(!v.matches_pattern("3.x"));
+        assert!(!v.matches_pattern("4.0.beta"));
+
+        // 读不到 Info.plist 时 macOS 检测器只给出版本系列，仍按数字部分匹配默认配置
+        let family: WeChatVersion = "4.0.x".parse().unwrap();
+        assert!(family.check_supported(&["4.x".into()]).is_ok());
+        assert!(family.matches_pattern("4.0") && !family.matches_pattern("4.0.3") && !family.matches_pattern("3.x"));
+        let family: WeChatVersion = "3.x.x".parse().unwrap();
+        assert!(family.check_supported(&["3.x".into(), "4.x".into()]).is_ok());
+        assert!(family.check_supported(&["4.x".into()]).is_err());
+
+        let supported = vec!["3.x".to_string(), "4.1".to_string()];
+        assert!(v.check_supported(&[]).is_ok());
+        assert!("4.1.0.10".parse::<WeChatVersion>().unwrap().check_supported(&supported).is_ok());
+        assert!(WeChatVersion::Unknown.check_supported(&supported).is_ok());
+        let err = v.check_supported(&supported).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<WeChatError>(),
+            Some(WeChatError::UnsupportedVersion { .. })
+        ));
+        assert!(err.to_string().contains("3.x, 4.1"));
+    }
+}
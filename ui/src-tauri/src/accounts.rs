@@ -0,0 +1,152 @@
+//! 账号切换
+//!
+//! 账号列表由保存的账号配置和本机发现的微信账号合并而成，配置和当前选择保存在应用配置目录的
+//! `accounts.json` 中。选中账号后，未显式传入目录的数据查询都使用该账号的数据目录和输出目录。
+//! 开启 `auto_decrypt` 时，启动后把当前账号解密到其输出目录。
+
+use mwxdump_core::backup::BackupTask;
+use mwxdump_core::wechat::process::{create_process_detector, merge_profiles, AccountProfile, ProcessDetector};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+use ts_rs::TS;
+
+use crate::AppState;
+
+/// 应用配置目录中的账号文件名
+const ACCOUNTS_FILE: &str = "accounts.json";
+
+/// 保存的账号配置
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AccountStore {
+    /// 账号配置
+    #[serde(default)]
+    pub profiles: Vec<AccountProfile>,
+    /// 当前选择的配置名称
+    pub selected: Option<String>,
+    /// 启动时自动解密当前账号
+    #[serde(default)]
+    pub auto_decrypt: bool,
+}
+
+impl AccountStore {
+    fn path(app: &AppHandle) -> Result<PathBuf, String> {
+        Ok(app.path().app_config_dir().map_err(|e| e.to_string())?.join(ACCOUNTS_FILE))
+    }
+
+    /// 读取账号文件，不存在或无法解析时返回空配置
+    pub fn load(app: &AppHandle) -> Self {
+        let Ok(path) = Self::path(app) else {
+            return Self::default();
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                tracing::warn!("账号文件 {:?} 无法解析: {}", path, e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// 写入账号文件
+    pub fn save(&self, app: &AppHandle) -> Result<(), String> {
+        let path = Self::path(app)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let content = serde_json::to_vec_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, content).map_err(|e| e.to_string())
+    }
+
+    /// 当前选择的账号配置
+    pub fn selected_profile(&self) -> Option<&AccountProfile> {
+        let selected = self.selected.as_deref()?;
+        self.profiles.iter().find(|p| p.name == selected)
+    }
+}
+
+/// 账号列表
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct AccountList {
+    /// 保存的和本机发现的账号
+    pub accounts: Vec<AccountProfile>,
+    /// 当前选择的配置名称
+    pub selected: Option<String>,
+}
+
+/// 启动时恢复上次选择的账号，开启自动解密时在后台解密该账号
+pub fn restore(app: &AppHandle) {
+    let store = AccountStore::load(app);
+    let Some(profile) = store.selected_profile() else {
+        return;
+    };
+    tracing::info!("当前账号: {}", profile.name);
+    let state = app.state::<AppState>();
+    *state.account.lock().unwrap() = Some(profile.clone());
+    if store.auto_decrypt {
+        auto_decrypt(&state, profile);
+    }
+}
+
+/// 把账号解密到其输出目录，缺少密钥时跳过
+fn auto_decrypt(state: &AppState, profile: &AccountProfile) {
+    let Some(key) = profile.data_key.as_deref().and_then(|k| hex::decode(k).ok()).filter(|k| k.len() == 32) else {
+        tracing::warn!("账号 {} 没有有效的数据密钥，跳过自动解密", profile.name);
+        return;
+    };
+    let task = BackupTask {
+        input: profile.data_dir.clone(),
+        output: profile.output_dir.clone(),
+        key,
+        categories: Vec::new(),
+        threads: None,
+        snapshot: false,
+        retention: Default::default(),
+    };
+    let events = state.events.clone();
+    let id = state
+        .jobs
+        .spawn_with_output("auto_decrypt", move |token| async move { task.run(token, events).await });
+    tracing::info!("已启动自动解密: 账号 {}，任务 {}", profile.name, id);
+}
+
+/// 列出保存的和本机发现的微信账号
+#[tauri::command]
+pub async fn list_accounts(app: AppHandle) -> Result<AccountList, String> {
+    let store = AccountStore::load(&app);
+    let detected = match create_process_detector() {
+        Ok(detector) => detector.enumerate_accounts().await.unwrap_or_default(),
+        Err(e) => {
+            tracing::warn!("枚举微信账号失败: {}", e);
+            Vec::new()
+        }
+    };
+    let work_root = app.path().app_data_dir().map_err(|e| e.to_string())?.join("accounts");
+    Ok(AccountList {
+        accounts: merge_profiles(&store.profiles, &detected, &work_root),
+        selected: store.selected,
+    })
+}
+
+/// 切换账号，`id` 为配置名称或微信ID
+#[tauri::command]
+pub async fn select_account(app: AppHandle, state: State<'_, AppState>, id: String) -> Result<AccountProfile, String> {
+    let profile = list_accounts(app.clone())
+        .await?
+        .accounts
+        .into_iter()
+        .find(|p| p.matches(&id))
+        .ok_or_else(|| format!("账号不存在: {}", id))?;
+
+    let mut store = AccountStore::load(&app);
+    if !store.profiles.iter().any(|p| p.name == profile.name) {
+        store.profiles.push(profile.clone());
+    }
+    store.selected = Some(profile.name.clone());
+    store.save(&app)?;
+
+    tracing::info!("切换到账号: {}", profile.name);
+    *state.account.lock().unwrap() = Some(profile.clone());
+    Ok(profile)
+}
@@ -0,0 +1,49 @@
+//! 协议处理
+//!
+//! 处理 `mwxdump://conversation/<wxid>` 链接：显示并聚焦主窗口，通过 `open-conversation`
+//! 事件通知前端打开对应会话。应用未运行时链接随启动参数传入，已运行时由单实例插件转发。
+
+use mwxdump_core::export::parse_conversation_link;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_deep_link::DeepLinkExt;
+
+/// 注册协议并处理启动时和运行中收到的链接
+pub fn setup(app: &AppHandle) -> tauri::Result<()> {
+    // 安装包会注册协议，开发构建在 Windows/Linux 上需要运行时注册
+    #[cfg(any(windows, target_os = "linux"))]
+    if let Err(e) = app.deep_link().register_all() {
+        tracing::warn!("注册 mwxdump:// 协议失败: {}", e);
+    }
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            open(&handle, url.as_str());
+        }
+    });
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        for url in urls {
+            open(app, url.as_str());
+        }
+    }
+    Ok(())
+}
+
+/// 显示并聚焦主窗口
+pub fn focus_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+fn open(app: &AppHandle, link: &str) {
+    let Some(wxid) = parse_conversation_link(link) else {
+        tracing::warn!("无法识别的链接: {}", link);
+        return;
+    };
+    tracing::info!("通过链接打开会话: {}", wxid);
+    focus_main_window(app);
+    let _ = app.emit("open-conversation", wxid);
+}
@@ -1,22 +1,125 @@
 //! MWXDump UI Tauri Library
 //! 
 //! 这是 MWXDump UI 应用程序的 Tauri 后端库，提供与前端交互的命令。
+//! 关闭主窗口后应用驻留在系统托盘，按配置的间隔在后台执行定时备份，
+//! 备份和导出结束时发送系统通知；`mwxdump://conversation/<wxid>` 链接打开对应会话。
+
+mod accounts;
+mod deep_link;
+mod notify;
+mod tray;
 
 use mwxdump_core::{
     ProcessDetector, WechatProcessInfo,
     models::{Contact, Message, ChatRoom, Session},
+    backup::{BackupTask, RetentionPolicy},
+    jobs::{JobId, JobInfo, JobManager, JobStatus, Scheduler},
+    events::EventBus,
+    export::{self, ExportFormat, ExportFormatInfo, ExportOptions, ExportService, ExportSummary},
     logs::{init_tracing_with_config, LogConfig},
+    wechat::datadir::{self, DataDirInfo},
+    wechat::db::{contacts::ContactRepository, hardlink::{MediaKind, HARDLINK_DB_PATH}, DataSourceManager, SqliteDataSource},
+    wechat::db::messages::{DayCount, MediaItem, MessageRepository, MessageWindow},
+    wechat::process::AccountProfile,
+    wechat::media::MediaResolver,
+    wechat::search::{CombinedSearch, SearchResults},
     Result,
 };
 use serde::{Deserialize, Serialize};
-use tauri::State;
-use std::sync::Mutex;
+use tauri::{AppHandle, State};
+use std::sync::{Arc, Mutex};
 use std::path::PathBuf;
+use std::time::Duration;
+
+/// 消息窗口单侧最多读取的消息数
+const MAX_WINDOW_SIDE: usize = 500;
+
+/// 媒体列表每页最多条数
+const MAX_MEDIA_PAGE: u32 = 500;
+
+/// 搜索结果每类最多条数
+const MAX_SEARCH_LIMIT: usize = 200;
 
 /// 应用程序状态
-#[derive(Default)]
 pub struct AppState {
     pub current_process: Mutex<Option<WechatProcessInfo>>,
+    /// 事件总线，由 [`notify::spawn_listener`] 转发给前端
+    pub events: EventBus,
+    /// 后台任务，与 HTTP 服务使用同一套任务管理
+    pub jobs: JobManager,
+    /// 定时备份，未配置时为 `None`
+    pub backup: Mutex<Option<Scheduler>>,
+    /// 最近一次导出所在目录
+    pub last_export: Mutex<Option<PathBuf>>,
+    /// 当前选择的账号
+    pub account: Mutex<Option<AccountProfile>>,
+    /// 最近打开的消息仓库及其工作目录，滚动时复用连接
+    pub messages: tokio::sync::Mutex<Option<(PathBuf, Arc<MessageRepository>)>>,
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        let events = EventBus::new();
+        Self {
+            current_process: Mutex::default(),
+            jobs: JobManager::new().with_events(events.clone()),
+            events,
+            backup: Mutex::default(),
+            last_export: Mutex::default(),
+            account: Mutex::default(),
+            messages: tokio::sync::Mutex::default(),
+        }
+    }
+}
+
+impl AppState {
+    /// 工作目录，未传入时使用当前账号的输出目录
+    pub fn work_dir(&self, explicit: Option<String>) -> std::result::Result<PathBuf, String> {
+        explicit
+            .map(PathBuf::from)
+            .or_else(|| self.account.lock().unwrap().as_ref().map(|p| p.output_dir.clone()))
+            .ok_or_else(|| "未选择账号，请传入工作目录".to_string())
+    }
+
+    /// 工作目录的消息仓库，工作目录未变时复用
+    pub async fn message_repository(&self, work_dir: PathBuf) -> std::result::Result<Arc<MessageRepository>, String> {
+        let mut cached = self.messages.lock().await;
+        if let Some((dir, repo)) = cached.as_ref() {
+            if *dir == work_dir {
+                return Ok(repo.clone());
+            }
+        }
+        let mut repo = MessageRepository::open(&work_dir).await.map_err(|e| e.to_string())?;
+        if let Some(wxid) = self.account.lock().unwrap().as_ref().and_then(|p| p.wxid.clone()) {
+            repo = repo.with_self_wxid(wxid);
+        }
+        let repo = Arc::new(repo);
+        *cached = Some((work_dir, repo.clone()));
+        Ok(repo)
+    }
+
+    /// 微信数据目录，未传入时使用当前账号的数据目录
+    pub fn data_dir(&self, explicit: Option<String>) -> Option<PathBuf> {
+        explicit
+            .map(PathBuf::from)
+            .or_else(|| self.account.lock().unwrap().as_ref().map(|p| p.data_dir.clone()))
+    }
+
+    /// 立即执行一次备份，未配置或上一次备份仍在运行时返回 `None`
+    pub fn run_backup_now(&self) -> Option<JobId> {
+        self.backup.lock().unwrap().as_ref().and_then(|scheduler| scheduler.trigger())
+    }
+
+    /// 暂停或恢复定时备份
+    pub fn set_backup_paused(&self, paused: bool) {
+        if let Some(scheduler) = self.backup.lock().unwrap().as_ref() {
+            if paused {
+                scheduler.pause();
+            } else {
+                scheduler.resume();
+            }
+        }
+    }
 }
 
 /// 进程信息响应
@@ -33,12 +136,264 @@ fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
+/// 识别微信数据目录
+#[tauri::command]
+fn scan_data_dir(path: String) -> std::result::Result<DataDirInfo, String> {
+    datadir::scan(&PathBuf::from(path)).map_err(|e| e.to_string())
+}
+
+/// 定位媒体文件供预览，`hardlink_db` 为解密后的硬链接数据库，未传入时使用当前账号的目录
+#[tauri::command]
+async fn resolve_media(
+    state: State<'_, AppState>,
+    data_dir: Option<String>,
+    hardlink_db: Option<String>,
+    kind: MediaKind,
+    md5: String,
+) -> std::result::Result<Option<String>, String> {
+    let data_dir = state.data_dir(data_dir).ok_or_else(|| "未选择账号，请传入数据目录".to_string())?;
+    let hardlink_db = match hardlink_db {
+        Some(path) => PathBuf::from(path),
+        None => state.work_dir(None)?.join(HARDLINK_DB_PATH),
+    };
+    let hardlink = SqliteDataSource::open(&hardlink_db)
+        .await
+        .map_err(|e| e.to_string())?;
+    let path = MediaResolver::new(data_dir, hardlink)
+        .resolve_md5(kind, &md5)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(path.map(|p| p.to_string_lossy().to_string()))
+}
+
+/// 联系人列表，传入 `query` 时按用户名、昵称或备注模糊搜索，最多返回 200 条
+#[tauri::command]
+async fn get_contacts(
+    state: State<'_, AppState>,
+    query: Option<String>,
+    limit: Option<usize>,
+    work_dir: Option<String>,
+) -> std::result::Result<Vec<Contact>, String> {
+    let work_dir = state.work_dir(work_dir)?;
+    let contacts = ContactRepository::open(&work_dir).await.map_err(|e| e.to_string())?;
+    Ok(match query.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        Some(query) => {
+            let limit = limit.unwrap_or(MAX_SEARCH_LIMIT).min(MAX_SEARCH_LIMIT);
+            contacts.search(query, limit).into_iter().cloned().collect()
+        }
+        None => contacts.all().to_vec(),
+    })
+}
+
+/// 最近会话列表，最近的在前，包含未读数和最后一条消息的预览；`limit` 为空时返回全部
+#[tauri::command]
+async fn get_sessions(
+    state: State<'_, AppState>,
+    limit: Option<usize>,
+    work_dir: Option<String>,
+) -> std::result::Result<Vec<Session>, String> {
+    let work_dir = state.work_dir(work_dir)?;
+    let manager = DataSourceManager::open(&work_dir).await.map_err(|e| e.to_string())?;
+    let mut sessions = manager.sessions().await.map_err(|e| e.to_string())?;
+    if let Some(limit) = limit {
+        sessions.truncate(limit);
+    }
+    Ok(sessions)
+}
+
+/// 读取锚点消息前后的消息，供虚拟滚动和跳转使用
+///
+/// `anchor_id` 为消息的 `seq`，为空时以最新消息为锚点；`before`/`after` 单侧最多 500 条。
+#[tauri::command]
+async fn get_messages_window(
+    state: State<'_, AppState>,
+    contact_id: String,
+    anchor_id: Option<i64>,
+    before: usize,
+    after: usize,
+    work_dir: Option<String>,
+) -> std::result::Result<MessageWindow, String> {
+    let repo = state.message_repository(state.work_dir(work_dir)?).await?;
+    let side = |n: usize| n.min(MAX_WINDOW_SIDE) as u32;
+    repo.window(&contact_id, anchor_id, side(before), side(after))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 按时间倒序列出会话中的图片、视频或文件，供媒体墙使用
+///
+/// 以上一页最后一条的 `seq` 作为 `before` 翻页，每页最多 500 条；文件经 `resolve_media` 定位。
+#[tauri::command]
+async fn list_media(
+    state: State<'_, AppState>,
+    contact_id: String,
+    kind: MediaKind,
+    before: Option<i64>,
+    limit: u32,
+    work_dir: Option<String>,
+) -> std::result::Result<Vec<MediaItem>, String> {
+    let repo = state.message_repository(state.work_dir(work_dir)?).await?;
+    repo.list_media(&contact_id, kind, before, limit.clamp(1, MAX_MEDIA_PAGE))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 同时搜索联系人、群聊、文本消息和媒体识别文本，供统一搜索框使用，每类最多 `limit` 条
+#[tauri::command]
+async fn search(
+    state: State<'_, AppState>,
+    query: String,
+    limit: usize,
+    work_dir: Option<String>,
+) -> std::result::Result<SearchResults, String> {
+    let work_dir = state.work_dir(work_dir)?;
+    CombinedSearch::open(&work_dir)
+        .await
+        .map_err(|e| e.to_string())?
+        .search(&query, limit.clamp(1, MAX_SEARCH_LIMIT))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 按天统计会话的消息数，供日历热力图使用；`utc_offset_minutes` 决定日期划分，默认 UTC
+#[tauri::command]
+async fn get_message_density(
+    state: State<'_, AppState>,
+    contact_id: String,
+    utc_offset_minutes: Option<i32>,
+    work_dir: Option<String>,
+) -> std::result::Result<Vec<DayCount>, String> {
+    let offset = utc_offset(utc_offset_minutes)?;
+    let repo = state.message_repository(state.work_dir(work_dir)?).await?;
+    repo.density(&contact_id, offset).await.map_err(|e| e.to_string())
+}
+
+/// 指定日期当天或之后第一条消息的 `seq`，作为 `get_messages_window` 的锚点
+#[tauri::command]
+async fn find_message_by_date(
+    state: State<'_, AppState>,
+    contact_id: String,
+    date: chrono::NaiveDate,
+    utc_offset_minutes: Option<i32>,
+    work_dir: Option<String>,
+) -> std::result::Result<Option<i64>, String> {
+    let offset = utc_offset(utc_offset_minutes)?;
+    let repo = state.message_repository(state.work_dir(work_dir)?).await?;
+    repo.seq_at_date(&contact_id, date, offset).await.map_err(|e| e.to_string())
+}
+
+fn utc_offset(minutes: Option<i32>) -> std::result::Result<chrono::FixedOffset, String> {
+    let minutes = minutes.unwrap_or_default();
+    minutes
+        .checked_mul(60)
+        .and_then(chrono::FixedOffset::east_opt)
+        .ok_or_else(|| format!("无效的时区偏移: {}", minutes))
+}
+
+/// 可用的导出格式及其选项的 JSON Schema，界面据此生成导出对话框
+#[tauri::command]
+fn export_formats() -> Vec<ExportFormatInfo> {
+    export::available_formats()
+}
+
+/// 导出单个会话，进度通过 `export_progress` 事件推送；以后台任务运行，结束时发送通知
+#[tauri::command]
+async fn export_conversation(
+    state: State<'_, AppState>,
+    work_dir: Option<String>,
+    data_dir: Option<String>,
+    wxid: String,
+    format: ExportFormat,
+    options: ExportOptions,
+) -> std::result::Result<ExportSummary, String> {
+    let mut service = ExportService::new(state.work_dir(work_dir)?).with_events(state.events.clone());
+    if let Some(data_dir) = state.data_dir(data_dir) {
+        service = service.with_data_root(data_dir);
+    }
+    let id = state.jobs.spawn_with_output("export", move |token| async move {
+        service
+            .with_cancellation(token)
+            .export_conversation(&wxid, format, &options)
+            .await
+    });
+    let job = state.jobs.wait(&id).await.ok_or_else(|| format!("任务 {} 不存在", id))?;
+    if job.status != JobStatus::Succeeded {
+        return Err(job.error.unwrap_or_else(|| format!("{:?}", job.status)));
+    }
+    let summary: ExportSummary = serde_json::from_value(job.output.unwrap_or_default()).map_err(|e| e.to_string())?;
+    *state.last_export.lock().unwrap() = summary.file.parent().map(PathBuf::from);
+    Ok(summary)
+}
+
+/// 配置定时备份，替换之前的配置；`interval_hours` 为 0 时只允许手动触发。
+/// 未传入的目录和密钥取当前账号的配置
+#[tauri::command]
+async fn configure_backup(
+    state: State<'_, AppState>,
+    input: Option<String>,
+    output: Option<String>,
+    key: Option<String>,
+    interval_hours: u64,
+    snapshot: bool,
+    retention: Option<RetentionPolicy>,
+) -> std::result::Result<(), String> {
+    let input = state.data_dir(input).ok_or_else(|| "缺少微信数据目录".to_string())?;
+    let output = state.work_dir(output)?;
+    let key = key
+        .or_else(|| state.account.lock().unwrap().as_ref().and_then(|p| p.data_key.clone()))
+        .ok_or_else(|| "缺少数据密钥".to_string())?;
+    let key = hex::decode(&key)
+        .ok()
+        .filter(|k| k.len() == 32)
+        .ok_or_else(|| "密钥必须为64个十六进制字符".to_string())?;
+    let task = Arc::new(BackupTask {
+        input,
+        output,
+        key,
+        categories: Vec::new(),
+        threads: None,
+        snapshot,
+        retention: retention.unwrap_or_default(),
+    });
+    let events = state.events.clone();
+    let scheduler = Scheduler::new(state.jobs.clone(), "backup", move |token| {
+        let task = task.clone();
+        let events = events.clone();
+        async move { task.run(token, events).await }
+    });
+    if interval_hours > 0 {
+        scheduler.start(Duration::from_secs(interval_hours * 3600));
+    }
+    if let Some(previous) = state.backup.lock().unwrap().replace(scheduler) {
+        previous.stop();
+    }
+    Ok(())
+}
+
+/// 立即执行一次备份
+#[tauri::command]
+fn run_backup_now(state: State<'_, AppState>) -> Option<JobId> {
+    state.run_backup_now()
+}
+
+/// 暂停或恢复定时备份，同步托盘菜单的勾选状态
+#[tauri::command]
+fn set_backup_paused(app: AppHandle, paused: bool) {
+    tray::set_backup_paused(&app, paused);
+}
+
+/// 所有后台任务
+#[tauri::command]
+fn list_jobs(state: State<'_, AppState>) -> Vec<JobInfo> {
+    state.jobs.list()
+}
+
 impl From<WechatProcessInfo> for ProcessInfoResponse {
     fn from(info: WechatProcessInfo) -> Self {
         Self {
             pid: info.pid,
             name: info.name,
-            version: format!("{:?}", info.version), // 使用 Debug 格式
+            version: info.version.to_string(),
             path: info.path.to_string_lossy().to_string(), // 转换 PathBuf 为 String
         }
     }
@@ -60,11 +415,51 @@ pub fn run() -> Result<()> {
         std::process::exit(1);
     }
 
-    tauri::Builder::default()
+    let builder = tauri::Builder::default();
+    // 单实例插件需最先注册：再次启动时把链接转发给已运行的实例
+    #[cfg(any(windows, target_os = "linux"))]
+    let builder = builder.plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
+        deep_link::focus_main_window(app);
+    }));
+
+    builder
         .manage(AppState::default())
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_deep_link::init())
+        .setup(|app| {
+            tray::create(app.handle())?;
+            notify::spawn_listener(app.handle());
+            accounts::restore(app.handle());
+            deep_link::setup(app.handle())?;
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            // 关闭主窗口时隐藏到托盘，后台备份继续运行
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                let _ = window.hide();
+                api.prevent_close();
+            }
+        })
         .invoke_handler(tauri::generate_handler![
-            greet
+            greet,
+            scan_data_dir,
+            resolve_media,
+            get_contacts,
+            get_sessions,
+            get_messages_window,
+            get_message_density,
+            list_media,
+            search,
+            find_message_by_date,
+            export_formats,
+            export_conversation,
+            configure_backup,
+            run_backup_now,
+            set_backup_paused,
+            list_jobs,
+            accounts::list_accounts,
+            accounts::select_account
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");    
@@ -0,0 +1,75 @@
+//! 事件转发和任务通知
+//!
+//! 订阅事件总线，把每个事件以其名称（如 `export_progress`）转发给前端；
+//! 备份和导出完成或失败时发送系统通知，附带数量和耗时。
+
+use mwxdump_core::backup::BackupOutcome;
+use mwxdump_core::events::Event;
+use mwxdump_core::export::ExportSummary;
+use mwxdump_core::jobs::{JobInfo, JobStatus};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::AppState;
+
+/// 在后台转发事件和任务结束通知
+pub fn spawn_listener(app: &AppHandle) {
+    let app = app.clone();
+    let mut events = app.state::<AppState>().events.subscribe();
+    tauri::async_runtime::spawn(async move {
+        while let Some(event) = events.recv().await {
+            if let Err(e) = app.emit(event.name(), &event) {
+                tracing::warn!("转发事件失败: {}", e);
+            }
+            let Event::JobFinished { job } = &event else {
+                continue;
+            };
+            if let Some((title, body)) = describe(job) {
+                if let Err(e) = app.notification().builder().title(title).body(body).show() {
+                    tracing::warn!("发送系统通知失败: {}", e);
+                }
+            }
+        }
+    });
+}
+
+/// 通知标题和正文，取消的任务和其他类型的任务不通知
+fn describe(job: &JobInfo) -> Option<(String, String)> {
+    let duration = format_duration(job);
+    let output = job.output.clone().unwrap_or_default();
+    match (job.kind.as_str(), job.status) {
+        ("backup", JobStatus::Succeeded) => {
+            let record = serde_json::from_value::<BackupOutcome>(output).ok().and_then(|o| o.record);
+            Some((
+                "备份完成".to_string(),
+                format!(
+                    "{} 个数据库，{} 条消息，用时 {}",
+                    record.as_ref().map_or(0, |r| r.databases),
+                    record.as_ref().and_then(|r| r.messages).unwrap_or(0),
+                    duration
+                ),
+            ))
+        }
+        ("export", JobStatus::Succeeded) => {
+            let summary: ExportSummary = serde_json::from_value(output).ok()?;
+            Some((
+                "导出完成".to_string(),
+                format!("{} 条消息，{} 个媒体文件，用时 {}", summary.messages, summary.media, duration),
+            ))
+        }
+        (kind @ ("backup" | "export"), JobStatus::Failed) => Some((
+            if kind == "backup" { "备份失败" } else { "导出失败" }.to_string(),
+            format!("{}（用时 {}）", job.error.as_deref().unwrap_or_default(), duration),
+        )),
+        _ => None,
+    }
+}
+
+fn format_duration(job: &JobInfo) -> String {
+    let secs = job.duration().map_or(0, |d| d.num_seconds().max(0));
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m {:02}s", secs / 60, secs % 60),
+        _ => format!("{}h {:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
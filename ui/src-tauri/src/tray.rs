@@ -0,0 +1,92 @@
+//! 系统托盘
+//!
+//! 菜单提供立即备份、打开最近导出、暂停定时备份、显示主窗口和退出。
+
+use tauri::menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Manager, Wry};
+use tauri_plugin_opener::OpenerExt;
+
+use crate::AppState;
+
+const BACKUP_NOW: &str = "backup_now";
+const OPEN_EXPORT: &str = "open_export";
+const PAUSE_SCHEDULE: &str = "pause_schedule";
+const SHOW_WINDOW: &str = "show_window";
+const QUIT: &str = "quit";
+
+/// 托盘菜单中需要同步状态的项
+struct TrayMenu {
+    pause: CheckMenuItem<Wry>,
+}
+
+/// 创建托盘图标和菜单
+pub fn create(app: &AppHandle) -> tauri::Result<()> {
+    let pause = CheckMenuItem::with_id(app, PAUSE_SCHEDULE, "暂停定时备份", true, false, None::<&str>)?;
+    let menu = Menu::with_items(
+        app,
+        &[
+            &MenuItem::with_id(app, BACKUP_NOW, "立即备份", true, None::<&str>)?,
+            &MenuItem::with_id(app, OPEN_EXPORT, "打开最近导出", true, None::<&str>)?,
+            &pause,
+            &PredefinedMenuItem::separator(app)?,
+            &MenuItem::with_id(app, SHOW_WINDOW, "显示主窗口", true, None::<&str>)?,
+            &MenuItem::with_id(app, QUIT, "退出", true, None::<&str>)?,
+        ],
+    )?;
+
+    let mut builder = TrayIconBuilder::with_id("main")
+        .tooltip("mwxdump")
+        .menu(&menu)
+        .on_menu_event(|app, event| handle_menu_event(app, event.id().as_ref()));
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+    app.manage(TrayMenu { pause });
+    Ok(())
+}
+
+/// 暂停或恢复定时备份并同步菜单勾选状态
+pub fn set_backup_paused(app: &AppHandle, paused: bool) {
+    app.state::<AppState>().set_backup_paused(paused);
+    if let Some(menu) = app.try_state::<TrayMenu>() {
+        let _ = menu.pause.set_checked(paused);
+    }
+}
+
+fn handle_menu_event(app: &AppHandle, id: &str) {
+    match id {
+        BACKUP_NOW => {
+            // 菜单事件在主线程回调，任务需在异步运行时中启动
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if app.state::<AppState>().run_backup_now().is_none() {
+                    tracing::info!("未配置备份或上一次备份仍在运行");
+                }
+            });
+        }
+        OPEN_EXPORT => {
+            let last_export = app.state::<AppState>().last_export.lock().unwrap().clone();
+            match last_export {
+                Some(dir) => {
+                    if let Err(e) = app.opener().open_path(dir.to_string_lossy(), None::<&str>) {
+                        tracing::warn!("打开导出目录失败: {}", e);
+                    }
+                }
+                None => tracing::info!("尚未导出过会话"),
+            }
+        }
+        PAUSE_SCHEDULE => {
+            // 勾选项在点击时已切换状态，以菜单当前状态为准
+            let paused = app
+                .try_state::<TrayMenu>()
+                .and_then(|menu| menu.pause.is_checked().ok())
+                .unwrap_or(false);
+            set_backup_paused(app, paused);
+        }
+        SHOW_WINDOW => crate::deep_link::focus_main_window(app),
+        QUIT => app.exit(0),
+        _ => {}
+    }
+}